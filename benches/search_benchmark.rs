@@ -1,10 +1,15 @@
 //! Benchmarks for Daedra operations
 
-use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use daedra::{
     cache::SearchCache,
-    types::{ContentType, ResultMetadata, SearchOptions, SearchResponse, SearchResult},
+    server::{JsonFormat, JsonRpcResponse},
+    tools::fetch::FetchClient,
+    types::{
+        ContentType, ResultMetadata, SearchOptions, SearchResponse, SearchResult, VisitPageArgs,
+    },
 };
+use serde_json::json;
 
 fn create_test_response(result_count: usize) -> SearchResponse {
     let results: Vec<SearchResult> = (0..result_count)
@@ -17,9 +22,10 @@ fn create_test_response(result_count: usize) -> SearchResponse {
             ),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: format!("example{}.com", i),
+                source: format!("example{}.com", i).into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         })
         .collect();
@@ -103,6 +109,54 @@ fn bench_serialization(c: &mut Criterion) {
     group.finish();
 }
 
+/// The text a `web_search` tool call returns, in each layout, and the JSON-RPC
+/// message carrying it; throughput is the size of what each one produces.
+fn bench_tool_output(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tool_output");
+
+    for size in [10, 50].iter() {
+        let response = create_test_response(*size);
+
+        let bytes = serde_json::to_string_pretty(&response).unwrap().len();
+        group.throughput(Throughput::Bytes(bytes as u64));
+        group.bench_with_input(
+            BenchmarkId::new("to_string_pretty", size),
+            &response,
+            |b, response| {
+                b.iter(|| serde_json::to_string_pretty(black_box(response)).unwrap());
+            },
+        );
+
+        for (name, format) in [("pretty", JsonFormat::Pretty), ("compact", JsonFormat::Compact)] {
+            let text = format.to_string(&response).unwrap();
+            group.throughput(Throughput::Bytes(text.len() as u64));
+            group.bench_with_input(BenchmarkId::new(name, size), &response, |b, response| {
+                b.iter(|| format.to_string(black_box(response)).unwrap());
+            });
+
+            let message = JsonRpcResponse::success(
+                Some(json!(1)),
+                json!({"content": [{"type": "text", "text": text}], "isError": false}),
+            );
+            let bytes = serde_json::to_vec(&message).unwrap().len();
+            group.throughput(Throughput::Bytes(bytes as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name}_message"), size),
+                &message,
+                |b, message| {
+                    b.iter(|| {
+                        let mut buffer = Vec::with_capacity(bytes);
+                        serde_json::to_writer(&mut buffer, black_box(message)).unwrap();
+                        buffer
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 fn bench_response_creation(c: &mut Criterion) {
     let mut group = c.benchmark_group("response_creation");
 
@@ -114,9 +168,10 @@ fn bench_response_creation(c: &mut Criterion) {
                 description: format!("Description {}", i),
                 metadata: ResultMetadata {
                     content_type: ContentType::Article,
-                    source: format!("example{}.com", i),
+                    source: format!("example{}.com", i).into(),
                     favicon: None,
                     published_date: None,
+                    ..Default::default()
                 },
             })
             .collect();
@@ -141,10 +196,77 @@ fn bench_response_creation(c: &mut Criterion) {
     group.finish();
 }
 
+/// A page that is one big `<pre>`, as changelogs and logs served as HTML are
+fn preformatted_page(title: &str, lines: impl Iterator<Item = String>) -> String {
+    let text: String = lines.collect();
+    format!("<html><head><title>{title}</title></head><body><pre>{text}</pre></body></html>")
+}
+
+/// `page` with its body wrapped in a `<div>`, which no longer reads as plain
+/// text and so goes through readability and the Markdown converter as every
+/// page did before the fast path
+fn wrapped_in_div(page: &str) -> String {
+    page.replace("<body>", "<body><div>").replace("</body>", "</div></body>")
+}
+
+/// `visit_page` extraction of large plain text pages, which skip the content
+/// cascade, next to the same pages through readability and the Markdown
+/// converter (the path they took before), and through the converter alone by
+/// selecting their `<body>`
+fn bench_plain_text_extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("plain_text_extraction");
+    let client = FetchClient::new().unwrap();
+    let args = VisitPageArgs {
+        url: "https://example.com/CHANGELOG".to_string(),
+        ..Default::default()
+    };
+    let converted = VisitPageArgs {
+        selector: Some("body".to_string()),
+        ..args.clone()
+    };
+
+    let changelog = preformatted_page(
+        "CHANGELOG",
+        (0..2_000).map(|i| {
+            format!(
+                "## 0.{i}.0 - 2024-01-01\n\n- Fix &lt;select&gt; parsing (#{i})\n\
+                 - Speed up `search` by {i}%\n\n"
+            )
+        }),
+    );
+    let log = preformatted_page(
+        "server.log",
+        (0..10_000).map(|i| {
+            format!(
+                "2024-01-01T00:00:{:02}Z INFO request id={i} path=/api/items/{i} status=200\n",
+                i % 60
+            )
+        }),
+    );
+
+    for (name, html) in [("changelog", &changelog), ("log", &log)] {
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        group.bench_with_input(BenchmarkId::new("fast_path", name), html, |b, html| {
+            b.iter(|| client.extract_html(black_box(html), &args).unwrap());
+        });
+        let cascade = wrapped_in_div(html);
+        group.bench_with_input(BenchmarkId::new("readability", name), &cascade, |b, html| {
+            b.iter(|| client.extract_html(black_box(html), &args).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("full_conversion", name), html, |b, html| {
+            b.iter(|| client.extract_html(black_box(html), &converted).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_cache_operations,
     bench_serialization,
-    bench_response_creation
+    bench_tool_output,
+    bench_response_creation,
+    bench_plain_text_extraction
 );
 criterion_main!(benches);
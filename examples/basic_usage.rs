@@ -3,7 +3,7 @@
 //! Run with: cargo run --example basic_usage
 
 use daedra::tools::{fetch, search};
-use daedra::types::{SafeSearchLevel, SearchArgs, SearchOptions, VisitPageArgs};
+use daedra::types::{SafeSearchLevel, SearchArgs, SearchOptions, TimeRange, VisitPageArgs};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -24,6 +24,7 @@ async fn main() -> anyhow::Result<()> {
             region: "wt-wt".to_string(),
             safe_search: SafeSearchLevel::Moderate,
             time_range: None,
+            ..Default::default()
         }),
     };
 
@@ -56,7 +57,8 @@ async fn main() -> anyhow::Result<()> {
             num_results: 3,
             region: "us-en".to_string(),
             safe_search: SafeSearchLevel::Moderate,
-            time_range: Some("m".to_string()), // Last month
+            time_range: Some(TimeRange::Month),
+            ..Default::default()
         }),
     };
 
@@ -79,6 +81,7 @@ async fn main() -> anyhow::Result<()> {
         url: "https://www.rust-lang.org".to_string(),
         selector: None,
         include_images: false,
+        ..Default::default()
     };
 
     match fetch::fetch_page(&fetch_args).await {
@@ -106,6 +109,7 @@ async fn main() -> anyhow::Result<()> {
         url: "https://example.com".to_string(),
         selector: Some("p".to_string()),
         include_images: false,
+        ..Default::default()
     };
 
     match fetch::fetch_page(&selective_fetch).await {
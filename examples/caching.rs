@@ -16,6 +16,7 @@ async fn main() -> anyhow::Result<()> {
         ttl: Duration::from_secs(60), // 1 minute TTL
         max_entries: 100,
         enabled: true,
+        ..Default::default()
     });
 
     let search_args = SearchArgs {
@@ -11,7 +11,7 @@
 //! to prevent corruption of the JSON-RPC stream on stdout.
 
 use daedra::cache::CacheConfig;
-use daedra::server::{DaedraServer, ServerConfig, TransportType};
+use daedra::server::{DaedraServer, HttpTransportConfig, ServerConfig, TransportType};
 use std::time::Duration;
 
 #[tokio::main]
@@ -42,9 +42,11 @@ async fn main() -> anyhow::Result<()> {
             ttl: Duration::from_secs(600), // 10 minute cache
             max_entries: 500,
             enabled: true,
+            ..Default::default()
         },
         verbose: true,
         max_concurrent_tools: 5,
+        ..Default::default()
     };
 
     // Create the server
@@ -52,10 +54,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Choose transport
     let transport = if use_sse {
-        TransportType::Sse {
-            port: 3000,
-            host: [127, 0, 0, 1],
-        }
+        TransportType::Http(HttpTransportConfig::new("127.0.0.1", 3000))
     } else {
         TransportType::Stdio
     };
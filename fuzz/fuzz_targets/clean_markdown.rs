@@ -0,0 +1,16 @@
+//! Arbitrary bytes through HTML → Markdown conversion and the Markdown cleaner.
+//! Cleaning must not panic and must be idempotent.
+
+#![no_main]
+
+use daedra::tools::fetch::{clean_markdown, html_to_markdown};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+
+    let cleaned = clean_markdown(&input);
+    assert_eq!(clean_markdown(&cleaned), cleaned, "clean_markdown is not idempotent");
+
+    let _ = clean_markdown(&html_to_markdown(&input));
+});
@@ -0,0 +1,50 @@
+//! Arbitrary bytes as a JSON-RPC message through `DaedraHandler::handle_message`,
+//! the same path the STDIO transport uses for every line.
+//!
+//! The handler runs in dry-run mode, so no tool call goes out to the network:
+//! well-formed `tools/call` requests, including ones only the tolerant parser
+//! accepts (byte order mark, trailing commas, trailing data), are planned
+//! rather than run, and malformed ones exercise argument validation.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use daedra::server::{DaedraHandler, ServerConfig};
+use libfuzzer_sys::fuzz_target;
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("tokio runtime")
+    })
+}
+
+fn handler() -> &'static DaedraHandler {
+    static HANDLER: OnceLock<DaedraHandler> = OnceLock::new();
+    HANDLER.get_or_init(|| {
+        // Every tool answers with the requests it would make instead of making them
+        let config = ServerConfig {
+            dry_run: true,
+            ..Default::default()
+        };
+        DaedraHandler::new(config).expect("handler")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(message) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Some(response) = runtime().block_on(handler().handle_message(message)) {
+        assert_eq!(response.jsonrpc, "2.0");
+        assert!(
+            response.result.is_some() != response.error.is_some(),
+            "response must carry exactly one of result/error"
+        );
+        serde_json::to_string(&response).expect("response serializes");
+    }
+});
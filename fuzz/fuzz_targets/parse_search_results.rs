@@ -0,0 +1,15 @@
+//! Arbitrary bytes as a DuckDuckGo (HTML or Lite) SERP: parsing must never panic, and every
+//! result it does produce must carry an absolute http(s) URL.
+
+#![no_main]
+
+use daedra::tools::search::{parse_lite_results, parse_search_results};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let html = String::from_utf8_lossy(data);
+    let lite = parse_lite_results(&html, 50);
+    for result in parse_search_results(&html, 50).into_iter().chain(lite) {
+        assert!(result.url.starts_with("http"), "non-http URL: {}", result.url);
+    }
+});
@@ -0,0 +1,223 @@
+//! Blocking (synchronous) API.
+//!
+//! Enabled with the `blocking` feature. For build scripts, simple CLIs and
+//! other code without an async runtime, this module mirrors the async entry
+//! points and drives them on a private Tokio runtime, in the spirit of
+//! `reqwest::blocking`:
+//!
+//! - [`perform_search`], [`fetch_page`] and [`crawl_site`] run one call each
+//! - [`DaedraClient`] keeps a runtime alive across calls, so its cache and
+//!   backend health state carry over like the async [`crate::DaedraClient`]
+//!
+//! ```rust,no_run
+//! use daedra::{SearchArgs, blocking};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let results = blocking::perform_search(&SearchArgs {
+//!         query: "rust async runtime".to_string(),
+//!         options: None,
+//!     })?;
+//!     println!("{} results", results.data.len());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! These functions must not be called from inside an async runtime; they
+//! return [`DaedraError::ServerError`] instead of blocking its worker thread.
+
+use crate::cache::CacheStats;
+use crate::server::{McpTool, ServerConfig};
+use crate::tools;
+use crate::types::{
+    CheckUrlArgs, CrawlArgs, CrawlResult, DaedraError, DaedraResult, DeepResearchArgs,
+    DeepResearchReport, HackerNewsArgs, MultiSearchArgs, MultiSearchItem, PageContent,
+    ResearchDigest, SearchAndReadArgs, SearchArgs, SearchResponse, StackOverflowArgs, Transcript,
+    TranscriptArgs, UrlCheck, VisitPageArgs,
+};
+use std::future::Future;
+use tokio::runtime::Runtime;
+
+/// Build the current-thread runtime the blocking calls run on.
+fn runtime() -> DaedraResult<Runtime> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err(DaedraError::ServerError(
+            "daedra::blocking called from within an async runtime; use the async API instead"
+                .to_string(),
+        ));
+    }
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?)
+}
+
+fn block_on<F: Future<Output = DaedraResult<T>>, T>(future: F) -> DaedraResult<T> {
+    runtime()?.block_on(future)
+}
+
+/// Blocking version of [`tools::search::perform_search`]
+pub fn perform_search(args: &SearchArgs) -> DaedraResult<SearchResponse> {
+    block_on(tools::search::perform_search(args))
+}
+
+/// Blocking version of [`tools::fetch::fetch_page`]
+pub fn fetch_page(args: &VisitPageArgs) -> DaedraResult<PageContent> {
+    block_on(tools::fetch::fetch_page(args))
+}
+
+/// Blocking version of [`tools::crawl_site`]
+pub fn crawl_site(args: CrawlArgs) -> DaedraResult<CrawlResult> {
+    block_on(tools::crawl_site(args))
+}
+
+/// Blocking version of [`crate::DaedraClient`].
+///
+/// Owns a runtime for its lifetime; create one and reuse it rather than one per call.
+pub struct DaedraClient {
+    inner: crate::DaedraClient,
+    runtime: Runtime,
+}
+
+impl DaedraClient {
+    /// Create a client with the given configuration
+    pub fn new(config: ServerConfig) -> DaedraResult<Self> {
+        Ok(Self {
+            runtime: runtime()?,
+            inner: crate::DaedraClient::new(config)?,
+        })
+    }
+
+    /// Create a client with default configuration
+    pub fn with_defaults() -> DaedraResult<Self> {
+        Self::new(ServerConfig::default())
+    }
+
+    /// Search the web (the `web_search` tool)
+    pub fn search(&self, args: SearchArgs) -> DaedraResult<SearchResponse> {
+        self.runtime.block_on(self.inner.search(args))
+    }
+
+    /// Run several searches concurrently, one item per query in order (the
+    /// `multi_search` tool)
+    pub fn multi_search(&self, args: MultiSearchArgs) -> DaedraResult<Vec<MultiSearchItem>> {
+        self.runtime.block_on(self.inner.multi_search(args))
+    }
+
+    /// Search, then read the top results into one digest (the `search_and_read` tool)
+    pub fn search_and_read(&self, args: SearchAndReadArgs) -> DaedraResult<ResearchDigest> {
+        self.runtime.block_on(self.inner.search_and_read(args))
+    }
+
+    /// Research a question over several search rounds and read the best
+    /// sources into one report (the `deep_research` tool)
+    pub fn deep_research(&self, args: DeepResearchArgs) -> DaedraResult<DeepResearchReport> {
+        self.runtime.block_on(self.inner.deep_research(args))
+    }
+
+    /// Search Hacker News through the Algolia HN API (the `search_hackernews` tool)
+    pub fn search_hackernews(&self, args: HackerNewsArgs) -> DaedraResult<SearchResponse> {
+        self.runtime.block_on(self.inner.search_hackernews(args))
+    }
+
+    /// Search Stack Overflow questions through the Stack Exchange API (the
+    /// `search_stackoverflow` tool)
+    pub fn search_stackoverflow(&self, args: StackOverflowArgs) -> DaedraResult<SearchResponse> {
+        self.runtime.block_on(self.inner.search_stackoverflow(args))
+    }
+
+    /// Get a YouTube video's captions (the `get_transcript` tool)
+    pub fn get_transcript(&self, args: TranscriptArgs) -> DaedraResult<Transcript> {
+        self.runtime.block_on(self.inner.get_transcript(args))
+    }
+
+    /// Fetch a page and extract it as Markdown (the `visit_page` tool)
+    pub fn visit_page(&self, args: VisitPageArgs) -> DaedraResult<PageContent> {
+        self.runtime.block_on(self.inner.visit_page(args))
+    }
+
+    /// Check a URL's status and headers without downloading it (the `check_url` tool)
+    pub fn check_url(&self, args: CheckUrlArgs) -> DaedraResult<UrlCheck> {
+        self.runtime.block_on(self.inner.check_url(args))
+    }
+
+    /// Crawl a site from a root URL (the `crawl_site` tool)
+    pub fn crawl_site(&self, args: CrawlArgs) -> DaedraResult<CrawlResult> {
+        self.runtime.block_on(self.inner.crawl_site(args))
+    }
+
+    /// Tools this client exposes, as advertised over MCP
+    pub fn list_tools(&self) -> Vec<McpTool> {
+        self.inner.list_tools()
+    }
+
+    /// Get the cache statistics
+    pub fn cache_stats(&self) -> CacheStats {
+        self.inner.cache_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Mock server on its own multi-threaded runtime, so it keeps answering
+    /// while the test thread blocks.
+    fn page_server(expected_calls: u64) -> (Runtime, MockServer) {
+        let rt = Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/page"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    "<html><head><title>Hello</title></head><body><p>Hello from the page.</p></body></html>",
+                    "text/html",
+                ))
+                .expect(expected_calls)
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, server)
+    }
+
+    fn page_args(server: &MockServer) -> VisitPageArgs {
+        VisitPageArgs {
+            url: format!("{}/page", server.uri()),
+            selector: None,
+            include_images: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blocking_fetch_page() {
+        let (_rt, server) = page_server(1);
+        let page = fetch_page(&page_args(&server)).unwrap();
+        assert!(page.content.contains("Hello from the page"), "{}", page.content);
+    }
+
+    #[test]
+    fn test_blocking_client_reuses_cache() {
+        let (_rt, server) = page_server(1);
+        let client = DaedraClient::with_defaults().unwrap();
+        assert!(client.list_tools().iter().any(|t| t.name == "visit_page"));
+
+        let first = client.visit_page(page_args(&server)).unwrap();
+        let second = client.visit_page(page_args(&server)).unwrap();
+        assert_eq!(first.content, second.content);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_inside_runtime_is_an_error() {
+        let args = VisitPageArgs {
+            url: "https://example.com".to_string(),
+            selector: None,
+            include_images: false,
+            ..Default::default()
+        };
+        let err = fetch_page(&args).unwrap_err();
+        assert!(matches!(&err, DaedraError::ServerError(msg) if msg.contains("async runtime")));
+        assert!(DaedraClient::with_defaults().is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Dropping results from low-quality and SEO-spam domains.
+//!
+//! Content farms and Stack Overflow scrapers rank well and rarely add anything
+//! the original source doesn't have, so an agent that fetches them wastes a
+//! call. A [`DomainBlocklist`] removes their results before anything else
+//! looks at them. It starts from an optional curated list and takes extra
+//! lists in the usual community formats:
+//!
+//! - hosts files: `0.0.0.0 spam.example`
+//! - adblock network rules: `||spam.example^` (exceptions and cosmetic rules
+//!   are ignored)
+//! - one domain per line
+//!
+//! `#` and `!` start comments. A listed domain also blocks its subdomains.
+
+use crate::types::{DaedraError, DaedraResult, SearchResult};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Scraper clones and content farms that mirror other sites' answers
+pub const CURATED_SPAM_DOMAINS: &[&str] = &[
+    "answerbun.com",
+    "askdev.io",
+    "bleepcoder.com",
+    "codeleading.com",
+    "coder.work",
+    "coderedirect.com",
+    "developpaper.com",
+    "exchangetuts.com",
+    "fixes.pub",
+    "i-harness.com",
+    "itectec.com",
+    "ittone.ma",
+    "newbedev.com",
+    "programmerall.com",
+    "qa.wujigu.com",
+    "quabr.com",
+    "stackoom.com",
+    "stackovernet.com",
+    "stackoverrun.com",
+    "thecodeteacher.com",
+    "tutorialmore.com",
+];
+
+/// Which result domains to drop as spam
+#[derive(Debug, Clone, Default)]
+pub struct BlocklistConfig {
+    /// Include [`CURATED_SPAM_DOMAINS`]
+    pub curated: bool,
+
+    /// Lists to load, in hosts, adblock or one-domain-per-line format
+    pub files: Vec<PathBuf>,
+
+    /// Extra domains blocked by this deployment
+    pub domains: Vec<String>,
+}
+
+/// A set of blocked domains, matched against result hosts and their parents.
+#[derive(Debug, Clone, Default)]
+pub struct DomainBlocklist {
+    domains: HashSet<String>,
+}
+
+impl DomainBlocklist {
+    /// An empty blocklist
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A blocklist holding [`CURATED_SPAM_DOMAINS`]
+    pub fn curated() -> Self {
+        let mut list = Self::new();
+        list.extend(CURATED_SPAM_DOMAINS.iter().copied());
+        list
+    }
+
+    /// Build the blocklist `config` describes, reading its list files.
+    pub fn from_config(config: &BlocklistConfig) -> DaedraResult<Self> {
+        let mut list = if config.curated { Self::curated() } else { Self::new() };
+        for path in &config.files {
+            list.load_file(path)?;
+        }
+        list.extend(config.domains.iter().map(String::as_str));
+        Ok(list)
+    }
+
+    /// Add every domain in `text`, returning how many entries it named.
+    pub fn load_str(&mut self, text: &str) -> usize {
+        let domains: Vec<String> =
+            text.lines().flat_map(parse_line).filter_map(normalize_domain).collect();
+        let count = domains.len();
+        self.domains.extend(domains);
+        count
+    }
+
+    /// Add every domain in the list at `path`.
+    pub fn load_file(&mut self, path: &Path) -> DaedraResult<usize> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            DaedraError::InvalidArguments(format!(
+                "Cannot read blocklist {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let count = self.load_str(&text);
+        debug!(path = %path.display(), count, "Loaded blocklist");
+        Ok(count)
+    }
+
+    /// Add domains; entries that aren't valid domain names are skipped.
+    pub fn extend<'a>(&mut self, domains: impl IntoIterator<Item = &'a str>) {
+        self.domains.extend(domains.into_iter().filter_map(normalize_domain));
+    }
+
+    /// Number of blocked domains
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Whether nothing is blocked
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Whether `url`'s host, or a domain it is under, is blocked
+    pub fn is_blocked(&self, url: &str) -> bool {
+        if self.domains.is_empty() {
+            return false;
+        }
+        let Some(host) = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_lowercase))
+        else {
+            return false;
+        };
+        let mut rest = host.trim_end_matches('.');
+        loop {
+            if self.domains.contains(rest) {
+                return true;
+            }
+            match rest.split_once('.') {
+                Some((_, parent)) => rest = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Remove results from blocked domains, returning how many were removed.
+    pub fn filter(&self, results: &mut Vec<SearchResult>) -> usize {
+        let before = results.len();
+        results.retain(|r| !self.is_blocked(&r.url));
+        before - results.len()
+    }
+}
+
+/// The domains a list line names, in any of the supported formats.
+fn parse_line(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    // Adblock headers, comments, exceptions and cosmetic rules name nothing to block
+    if line.is_empty()
+        || line.starts_with(['!', '#', '['])
+        || line.starts_with("@@")
+        || line.contains("##")
+        || line.contains("#@#")
+    {
+        return Vec::new();
+    }
+    if let Some(rule) = line.strip_prefix("||") {
+        let end = rule.find(['^', '/', '$', '*']).unwrap_or(rule.len());
+        return vec![&rule[..end]];
+    }
+
+    let line = line.split('#').next().unwrap_or_default();
+    let mut fields = line.split_whitespace();
+    match fields.next() {
+        Some(first) if first.parse::<IpAddr>().is_ok() => fields.collect(),
+        Some(domain) => vec![domain],
+        None => Vec::new(),
+    }
+}
+
+/// Lowercased `domain` without a leading `*.` or `.`, if it is a dotted host name
+fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_start_matches("*.").trim_matches('.').to_lowercase();
+    let valid = domain.contains('.')
+        && domain.parse::<IpAddr>().is_err()
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    valid.then_some(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ResultMetadata;
+
+    fn result(url: &str) -> SearchResult {
+        SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            metadata: ResultMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_curated_list_is_sorted_and_valid() {
+        assert!(CURATED_SPAM_DOMAINS.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(DomainBlocklist::curated().len(), CURATED_SPAM_DOMAINS.len());
+    }
+
+    #[test]
+    fn test_blocks_domain_and_subdomains_only() {
+        let list = DomainBlocklist::curated();
+        assert!(list.is_blocked("https://newbedev.com/some-question"));
+        assert!(list.is_blocked("https://www.NewBedev.com/x"));
+        assert!(!list.is_blocked("https://notnewbedev.com/x"));
+        assert!(!list.is_blocked("https://stackoverflow.com/questions/1"));
+        assert!(!list.is_blocked("not a url"));
+    }
+
+    #[test]
+    fn test_load_hosts_adblock_and_plain_lists() {
+        let mut list = DomainBlocklist::new();
+        let named = list.load_str(
+            "[Adblock Plus 2.0]\n\
+             ! Title: spam\n\
+             # hosts section\n\
+             127.0.0.1 localhost\n\
+             0.0.0.0 farm-one.example farm-two.example # trailing comment\n\
+             ||farm-three.example^$third-party\n\
+             @@||allowed.example^\n\
+             example.org##.advert\n\
+             *.farm-four.example\n\
+             \n",
+        );
+        assert_eq!(named, 4);
+        assert_eq!(list.len(), 4);
+        for host in ["farm-one", "farm-two", "farm-three", "sub.farm-four"] {
+            assert!(list.is_blocked(&format!("https://{host}.example/")), "{host}");
+        }
+        assert!(!list.is_blocked("https://allowed.example/"));
+        assert!(!list.is_blocked("https://example.org/"));
+        assert!(!list.is_blocked("http://localhost/"));
+    }
+
+    #[test]
+    fn test_from_config_combines_sources() {
+        let path =
+            std::env::temp_dir().join(format!("daedra-blocklist-{}.txt", std::process::id()));
+        std::fs::write(&path, "||listed.example^\n").unwrap();
+        let list = DomainBlocklist::from_config(&BlocklistConfig {
+            curated: true,
+            files: vec![path.clone()],
+            domains: vec!["Extra.Example".to_string()],
+        })
+        .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(list.len(), CURATED_SPAM_DOMAINS.len() + 2);
+        assert!(list.is_blocked("https://extra.example/"));
+
+        let missing = BlocklistConfig {
+            files: vec![PathBuf::from("/nonexistent/daedra-blocklist.txt")],
+            ..Default::default()
+        };
+        let err = DomainBlocklist::from_config(&missing).unwrap_err().to_string();
+        assert!(err.contains("Cannot read blocklist"), "{err}");
+    }
+
+    #[test]
+    fn test_filter_removes_blocked_results() {
+        let list = DomainBlocklist::curated();
+        let mut results = vec![
+            result("https://docs.rs/tokio"),
+            result("https://programmerall.com/article/1"),
+            result("https://stackoverflow.com/q/2"),
+        ];
+        assert_eq!(list.filter(&mut results), 1);
+        assert_eq!(results.len(), 2);
+        assert_eq!(DomainBlocklist::new().filter(&mut results), 0);
+    }
+}
@@ -0,0 +1,309 @@
+//! Fitting tool results into a caller's token budget.
+//!
+//! Agents pass `max_tokens_hint` to say how much context a result may take.
+//! Tokens are estimated at four characters each rather than counted with a
+//! real tokenizer, so the budget is approximate. A result that does not fit is
+//! cut down and flagged `truncated`; when something was left out, `next_cursor`
+//! is the value to pass back as `cursor` to get the rest.
+//!
+//! Cursors index into the full result, which the cache keeps, so continuing
+//! does not refetch while the entry is live.
+//!
+//! Pages can also be read in fixed slices, as with the reference MCP `fetch`
+//! server: [`slice_page`] takes a `start_index` and `max_length` in
+//! characters, and a cut page carries `next_index` to start the next slice at.
+
+use crate::types::{DaedraError, DaedraResult, PageContent, SearchResponse};
+
+/// Characters per token assumed by [`estimate_tokens`]
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Snippets are shortened to this many characters before results are dropped
+const MIN_SNIPPET_CHARS: usize = 80;
+
+/// Page content returned per call never drops below this, however small the budget
+const MIN_PAGE_CHARS: usize = 200;
+
+/// Tokens set aside for the `visit_page` header (title, URL, timestamp, counts)
+const PAGE_HEADER_TOKENS: usize = 40;
+
+/// Rough token count of `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+fn parse_cursor(cursor: Option<&str>) -> DaedraResult<usize> {
+    cursor.map_or(Ok(0), |c| {
+        c.trim()
+            .parse()
+            .map_err(|_| DaedraError::InvalidArguments(format!("Invalid cursor: {}", c)))
+    })
+}
+
+fn response_tokens(response: &SearchResponse) -> usize {
+    serde_json::to_string_pretty(response).map_or(0, |json| estimate_tokens(&json))
+}
+
+/// Cut `text` to at most `max_chars` characters, at a word boundary, marking the cut.
+fn shorten(text: &mut String, max_chars: usize) -> bool {
+    let Some((end, _)) = text.char_indices().nth(max_chars) else {
+        return false;
+    };
+    let end = text[..end].rfind(char::is_whitespace).unwrap_or(end);
+    text.truncate(end);
+    text.push('…');
+    true
+}
+
+/// Skip to `cursor`, then shorten snippets and drop trailing results until
+/// `response` fits in `max_tokens`. At least one result is always kept.
+pub fn fit_search(
+    response: &mut SearchResponse,
+    max_tokens: Option<usize>,
+    cursor: Option<&str>,
+) -> DaedraResult<()> {
+    let offset = parse_cursor(cursor)?;
+    if offset > response.data.len() {
+        return Err(DaedraError::InvalidArguments(format!(
+            "Invalid cursor: {} is past the last of {} results",
+            offset,
+            response.data.len()
+        )));
+    }
+    if offset > 0 {
+        // Only the first page carries the panels
+        response.data.drain(..offset);
+        response.knowledge_graph = None;
+        response.people_also_ask.clear();
+        response.verticals = Default::default();
+    }
+
+    if let Some(max_tokens) = max_tokens
+        && response_tokens(response) > max_tokens
+    {
+        for result in &mut response.data {
+            response.truncated |= shorten(&mut result.description, MIN_SNIPPET_CHARS);
+        }
+        let total = response.data.len();
+        while response.data.len() > 1 && response_tokens(response) > max_tokens {
+            response.data.pop();
+        }
+        if response.data.len() < total {
+            response.truncated = true;
+            response.next_cursor = Some((offset + response.data.len()).to_string());
+        }
+    }
+
+    response.metadata.result_count = response.data.len();
+    Ok(())
+}
+
+/// Skip to `cursor`, then cut the page content so the page fits in
+/// `max_tokens`, preferring a paragraph or word boundary.
+pub fn fit_page(
+    page: &mut PageContent,
+    max_tokens: Option<usize>,
+    cursor: Option<&str>,
+) -> DaedraResult<()> {
+    let offset = parse_cursor(cursor)?;
+    if offset > page.content.len() || !page.content.is_char_boundary(offset) {
+        return Err(DaedraError::InvalidArguments(format!(
+            "Invalid cursor: {} is not a position in the page content",
+            offset
+        )));
+    }
+    let skipped = page.content[..offset].chars().count();
+    page.content.drain(..offset);
+
+    let Some(max_tokens) = max_tokens else {
+        return Ok(());
+    };
+    let max_chars = (max_tokens.saturating_sub(PAGE_HEADER_TOKENS) * CHARS_PER_TOKEN)
+        .max(MIN_PAGE_CHARS);
+    let Some((hard_end, _)) = page.content.char_indices().nth(max_chars) else {
+        return Ok(());
+    };
+    let window = &page.content[..hard_end];
+    let end = window
+        .rfind("\n\n")
+        .filter(|&i| i >= hard_end / 2)
+        .or_else(|| window.rfind(char::is_whitespace).filter(|&i| i > 0))
+        .unwrap_or(hard_end);
+
+    page.content.truncate(end);
+    page.truncated = true;
+    page.next_cursor = Some((offset + end).to_string());
+    page.next_index = Some(skipped + page.content.chars().count());
+    Ok(())
+}
+
+/// Skip the first `start_index` characters of the page content, fit the rest
+/// in `max_tokens` as [`fit_page`] does, then cut it to at most `max_length`
+/// characters.
+pub fn slice_page(
+    page: &mut PageContent,
+    start_index: usize,
+    max_length: Option<usize>,
+    max_tokens: Option<usize>,
+) -> DaedraResult<()> {
+    if max_length == Some(0) {
+        return Err(DaedraError::InvalidArguments(
+            "max_length must be at least 1".to_string(),
+        ));
+    }
+    let start = match page.content.char_indices().nth(start_index) {
+        Some((start, _)) => start,
+        None if start_index == page.content.chars().count() => page.content.len(),
+        None => {
+            return Err(DaedraError::InvalidArguments(format!(
+                "Invalid start_index: {} is past the end of the content ({} characters)",
+                start_index,
+                page.content.chars().count()
+            )));
+        },
+    };
+    fit_page(page, max_tokens, Some(&start.to_string()))?;
+
+    if let Some(max_length) = max_length
+        && let Some((end, _)) = page.content.char_indices().nth(max_length)
+    {
+        page.content.truncate(end);
+        page.truncated = true;
+        page.next_cursor = Some((start + end).to_string());
+        page.next_index = Some(start_index + max_length);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ResultMetadata, SearchOptions, SearchResult};
+
+    fn response(count: usize) -> SearchResponse {
+        let results = (0..count)
+            .map(|i| SearchResult {
+                title: format!("Result {i}"),
+                url: format!("https://example.com/{i}"),
+                description: "lorem ipsum dolor sit amet ".repeat(20),
+                metadata: ResultMetadata::default(),
+            })
+            .collect();
+        SearchResponse::new("q".to_string(), results, &SearchOptions::default())
+    }
+
+    fn page(content: &str) -> PageContent {
+        PageContent {
+            url: "https://example.com".to_string(),
+            title: "Example".to_string(),
+            content: content.to_string(),
+            timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
+            word_count: content.split_whitespace().count(),
+            links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_fit_search_within_budget_is_untouched() {
+        let mut r = response(3);
+        fit_search(&mut r, Some(100_000), None).unwrap();
+        assert_eq!(r.data.len(), 3);
+        assert!(!r.truncated);
+        assert!(r.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_fit_search_trims_snippets_then_drops_results() {
+        let mut r = response(10);
+        fit_search(&mut r, Some(400), None).unwrap();
+        assert!(r.truncated);
+        assert!(r.data.len() < 10);
+        assert!(r.data.iter().all(|d| d.description.chars().count() <= MIN_SNIPPET_CHARS + 1));
+        assert!(response_tokens(&r) <= 400);
+        assert_eq!(r.metadata.result_count, r.data.len());
+
+        let cursor = r.next_cursor.clone().unwrap();
+        assert_eq!(cursor, r.data.len().to_string());
+        let mut rest = response(10);
+        fit_search(&mut rest, None, Some(&cursor)).unwrap();
+        assert_eq!(rest.data[0].url, format!("https://example.com/{cursor}"));
+    }
+
+    #[test]
+    fn test_fit_search_rejects_bad_cursor() {
+        assert!(fit_search(&mut response(2), None, Some("3")).is_err());
+        assert!(fit_search(&mut response(2), None, Some("next")).is_err());
+        assert!(fit_search(&mut response(2), None, Some("2")).is_ok());
+    }
+
+    #[test]
+    fn test_fit_page_pages_through_content() {
+        let text = "First paragraph. ".repeat(30) + "\n\n" + &"Second paragraph. ".repeat(30);
+        let mut first = page(&text);
+        fit_page(&mut first, Some(PAGE_HEADER_TOKENS + 130), None).unwrap();
+        assert!(first.truncated);
+        assert!(first.content.ends_with("First paragraph. "));
+
+        let cursor = first.next_cursor.clone().unwrap();
+        let mut second = page(&text);
+        fit_page(&mut second, None, Some(&cursor)).unwrap();
+        assert!(!second.truncated);
+        assert_eq!(first.content + &second.content, text);
+    }
+
+    #[test]
+    fn test_slice_page_reads_in_character_slices() {
+        let text = "héllo wörld, ".repeat(10);
+        let mut read = String::new();
+        let mut start = 0;
+        loop {
+            let mut slice = page(&text);
+            slice_page(&mut slice, start, Some(50), None).unwrap();
+            read += &slice.content;
+            let Some(next) = slice.next_index else {
+                assert!(!slice.truncated);
+                break;
+            };
+            assert!(slice.truncated);
+            assert_eq!(slice.content.chars().count(), 50);
+            assert_eq!(next, start + 50);
+
+            // The byte cursor points at the same place
+            let mut resumed = page(&text);
+            fit_page(&mut resumed, None, slice.next_cursor.as_deref()).unwrap();
+            assert_eq!(resumed.content, text.chars().skip(next).collect::<String>());
+            start = next;
+        }
+        assert_eq!(read, text);
+
+        let mut budgeted = page(&text.repeat(5));
+        slice_page(&mut budgeted, 10, Some(1000), Some(PAGE_HEADER_TOKENS + 10)).unwrap();
+        assert!(budgeted.truncated);
+        assert_eq!(budgeted.next_index, Some(10 + budgeted.content.chars().count()));
+
+        assert!(slice_page(&mut page(&text), 130, None, None).is_ok());
+        assert!(slice_page(&mut page(&text), 131, None, None).is_err());
+        assert!(slice_page(&mut page(&text), 0, Some(0), None).is_err());
+    }
+
+    #[test]
+    fn test_fit_page_rejects_mid_character_cursor() {
+        assert!(fit_page(&mut page("héllo"), None, Some("2")).is_err());
+        assert!(fit_page(&mut page("héllo"), None, Some("99")).is_err());
+    }
+}
@@ -2,12 +2,58 @@
 //!
 //! This module provides caching capabilities to improve performance
 //! and reduce redundant network requests.
+//!
+//! Entries are held behind an [`Arc`], so a cache hit hands out a shared
+//! reference to the cached response or page instead of copying it.
+//!
+//! With [`CacheConfig::persistence`] set, entries are also written behind to a
+//! file (see [`cache_file`](crate::cache_file)) and read back when the cache is
+//! created, keeping the time they had left to live.
+//!
+//! Pages whose response carried an `ETag` or `Last-Modified` are kept, with
+//! those validators, for [`CacheConfig::revalidation_window`] after they expire
+//! ([`SearchCache::get_stale_page`]), so the next fetch can ask the site
+//! whether they changed instead of downloading them again. These stay in
+//! memory only.
+//!
+//! Each cache holds up to [`CacheConfig::max_entries`] entries, or, with
+//! [`CacheConfig::max_memory_bytes`] set, entries adding up to that many bytes
+//! as estimated by the length of their JSON, so a few huge pages can't crowd
+//! out memory. [`CacheStats::bytes`] reports the estimate either way.
+//!
+//! Fetches that failed in a way retrying won't fix — see
+//! [`is_lasting_failure`](crate::tools::fetch::is_lasting_failure) — are
+//! remembered for [`CacheConfig::failure_ttl`], so a caller retrying a dead
+//! URL gets the earlier error back at once ([`SearchCache::get_failure`]).
+//!
+//! [`SearchCache::invalidate_matching`] drops the pages of one site, or the
+//! searches for one query, and leaves the rest.
+//!
+//! [`SearchCache::export`] writes the cached searches and pages to a snapshot,
+//! with the time each has left to live, and [`SearchCache::import`] loads one,
+//! to checkpoint a research session or start a CI sandbox warm.
 
-use crate::types::{PageContent, SearchResponse};
-use moka::future::Cache;
-use std::sync::Arc;
-use std::time::Duration;
-use tracing::{debug, instrument};
+use crate::cache_file::{self, CacheFile, CacheFileConfig, CachedValue, LoadedEntry};
+use crate::memory::MIN_SHRUNK_ENTRIES;
+use crate::privacy;
+use crate::sandbox::glob_matches;
+use crate::telemetry::{self, Phase};
+use crate::text;
+use crate::timestamp::Timestamp;
+use crate::tools::fetch::{MAX_PAGINATION_PAGES, Validators, is_lasting_failure};
+use crate::types::{
+    DaedraError, DaedraResult, ExtractMode, PageContent, PageFormat, SearchResponse,
+    VisitPageArgs,
+};
+use moka::Expiry;
+use moka::future::{Cache, CacheBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, instrument};
 
 /// Default cache TTL in seconds
 pub const DEFAULT_CACHE_TTL_SECS: u64 = 300; // 5 minutes
@@ -15,6 +61,12 @@ pub const DEFAULT_CACHE_TTL_SECS: u64 = 300; // 5 minutes
 /// Default maximum cache entries
 pub const DEFAULT_MAX_ENTRIES: u64 = 1000;
 
+/// Default for [`CacheConfig::revalidation_window`] (one day)
+pub const DEFAULT_REVALIDATION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default for [`CacheConfig::failure_ttl`]
+pub const DEFAULT_FAILURE_TTL: Duration = Duration::from_secs(60);
+
 /// Configuration for the cache
 #[derive(Debug, Clone)]
 pub struct CacheConfig {
@@ -24,8 +76,26 @@ pub struct CacheConfig {
     /// Maximum number of entries in the cache
     pub max_entries: u64,
 
+    /// Bound each cache by the estimated bytes of its entries instead of
+    /// [`max_entries`](Self::max_entries)
+    pub max_memory_bytes: Option<u64>,
+
     /// Whether caching is enabled
     pub enabled: bool,
+
+    /// Key searches by [`privacy::query_hash`] instead of query text
+    pub hash_keys: bool,
+
+    /// Persist entries to a file; `None` keeps them in memory only
+    pub persistence: Option<CacheFileConfig>,
+
+    /// How long after expiring a page with validators can still be
+    /// revalidated instead of fetched again; zero turns revalidation off
+    pub revalidation_window: Duration,
+
+    /// How long a lasting fetch failure is returned without fetching again;
+    /// zero turns failure caching off
+    pub failure_ttl: Duration,
 }
 
 impl Default for CacheConfig {
@@ -33,42 +103,206 @@ impl Default for CacheConfig {
         Self {
             ttl: Duration::from_secs(DEFAULT_CACHE_TTL_SECS),
             max_entries: DEFAULT_MAX_ENTRIES,
+            max_memory_bytes: None,
             enabled: true,
+            hash_keys: false,
+            persistence: None,
+            revalidation_window: DEFAULT_REVALIDATION_WINDOW,
+            failure_ttl: DEFAULT_FAILURE_TTL,
         }
     }
 }
 
+/// Gives entries read back from the cache file or a snapshot the time they had
+/// left to live; every other entry gets the cache's time-to-live.
+#[derive(Debug, Clone, Default)]
+struct ReadBackExpiry(Arc<Mutex<HashMap<String, Duration>>>);
+
+impl<V> Expiry<String, V> for ReadBackExpiry {
+    fn expire_after_create(&self, key: &String, _value: &V, _at: Instant) -> Option<Duration> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).remove(key)
+    }
+}
+
 /// Cache for search results
 #[derive(Clone)]
 pub struct SearchCache {
     /// Internal cache for search responses
-    search_cache: Arc<Cache<String, SearchResponse>>,
+    search_cache: Arc<Cache<String, Arc<SearchResponse>>>,
 
     /// Internal cache for page content
-    page_cache: Arc<Cache<String, PageContent>>,
+    page_cache: Arc<Cache<String, Arc<PageContent>>>,
+
+    /// Pages with their validators, kept past their time-to-live for the
+    /// revalidation window; `None` when revalidation is off
+    stale_pages: Option<Arc<Cache<String, StalePage>>>,
+
+    /// Recent lasting fetch failures; `None` when failure caching is off
+    failures: Option<Arc<Cache<String, FailedFetch>>>,
 
     /// Whether caching is enabled
     enabled: bool,
+
+    /// Whether search keys hold query hashes
+    hash_keys: bool,
+
+    /// Where entries are written behind, when persisted
+    file: Option<CacheFile>,
+
+    /// Configured entries per cache
+    max_entries: u64,
+
+    /// Entries per cache new ones are added up to, lowered by [`Self::shrink`]
+    capacity: Arc<AtomicU64>,
+
+    /// Estimated bytes held by the search and page caches
+    bytes: Arc<AtomicU64>,
+
+    /// How long fetch failures are kept
+    failure_ttl: Duration,
+
+    /// Time-to-live of searches and pages
+    ttl: Duration,
+
+    /// Time left to live of entries being loaded
+    expiry: ReadBackExpiry,
 }
 
 impl SearchCache {
     /// Create a new search cache with the given configuration
+    ///
+    /// With [`CacheConfig::persistence`] set, the file's entries are loaded
+    /// first; a file that can't be opened is logged and the cache stays in
+    /// memory only.
     pub fn new(config: CacheConfig) -> Self {
-        let search_cache = Cache::builder()
-            .max_capacity(config.max_entries)
-            .time_to_live(config.ttl)
-            .build();
+        let opened = match config.persistence.as_ref().filter(|_| config.enabled) {
+            Some(file) => match CacheFile::open(file, config.ttl) {
+                Ok(opened) => Some(opened),
+                Err(e) => {
+                    error!(path = %file.path.display(), error = %e, "Cannot open cache file");
+                    None
+                },
+            },
+            None => None,
+        };
+        let expiry = ReadBackExpiry::default();
+        let bytes = Arc::new(AtomicU64::new(0));
 
-        let page_cache = Cache::builder()
-            .max_capacity(config.max_entries)
-            .time_to_live(config.ttl)
-            .build();
+        let search_cache =
+            sized_builder(&config, config.ttl, Some(&bytes), |r: &Arc<SearchResponse>| {
+                json_len(&**r)
+            })
+            .expire_after(expiry.clone());
+        let page_cache =
+            sized_builder(&config, config.ttl, Some(&bytes), |p: &Arc<PageContent>| {
+                json_len(&**p)
+            })
+            .expire_after(expiry.clone());
 
-        Self {
-            search_cache: Arc::new(search_cache),
-            page_cache: Arc::new(page_cache),
+        let stale_pages = (config.enabled && !config.revalidation_window.is_zero()).then(|| {
+            let ttl = config.ttl.saturating_add(config.revalidation_window);
+            let stale_pages = sized_builder(&config, ttl, None, |s: &StalePage| json_len(&*s.page));
+            Arc::new(stale_pages.build())
+        });
+
+        let failures = (config.enabled && !config.failure_ttl.is_zero()).then(|| {
+            let failures = Cache::builder()
+                .max_capacity(config.max_entries)
+                .time_to_live(config.failure_ttl);
+            Arc::new(failures.build())
+        });
+
+        let cache = Self {
+            search_cache: Arc::new(search_cache.build()),
+            page_cache: Arc::new(page_cache.build()),
+            stale_pages,
+            failures,
             enabled: config.enabled,
+            hash_keys: config.hash_keys,
+            file: None,
+            max_entries: config.max_entries,
+            capacity: Arc::new(AtomicU64::new(config.max_entries)),
+            bytes,
+            failure_ttl: config.failure_ttl,
+            ttl: config.ttl,
+            expiry,
+        };
+        let Some((file, entries)) = opened else {
+            return cache;
+        };
+
+        futures::executor::block_on(cache.load(entries));
+        Self {
+            file: Some(file),
+            ..cache
+        }
+    }
+
+    /// Insert entries read back from a file, each living the time it has left
+    async fn load(&self, entries: Vec<LoadedEntry>) {
+        for entry in entries {
+            self.expiry
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(entry.key.clone(), entry.remaining);
+            match entry.value {
+                CachedValue::Search(response) => {
+                    self.search_cache.insert(entry.key, response).await
+                },
+                CachedValue::Page(page) => self.page_cache.insert(entry.key, page).await,
+            }
+        }
+    }
+
+    /// Write the cached searches and pages to a snapshot at `path`, replacing
+    /// it, each with the time it has left to live counted from when the
+    /// search ran or the page was fetched; returns how many were written
+    pub fn export(&self, path: impl AsRef<Path>) -> DaedraResult<usize> {
+        let path = path.as_ref();
+        let now = Timestamp::now();
+        let remaining = |at: Timestamp| self.ttl.saturating_sub(cache_file::age(at, now));
+        let searches = self.search_cache.iter().map(|(key, response)| {
+            let remaining = remaining(response.metadata.timestamp);
+            (key, remaining, CachedValue::Search(response))
+        });
+        let pages = self.page_cache.iter().map(|(key, page)| {
+            let remaining = remaining(page.timestamp);
+            (key, remaining, CachedValue::Page(page))
+        });
+        let entries: Vec<_> =
+            searches.chain(pages).filter(|(_, left, _)| !left.is_zero()).collect();
+        let written = cache_file::write_snapshot(
+            path,
+            entries.iter().map(|(key, left, value)| (key.as_str(), *left, value)),
+        )
+        .map_err(|e| snapshot_error(path, e))?;
+        debug!(path = %path.display(), entries = written, "Cache exported");
+        Ok(written)
+    }
+
+    /// Load the entries of a snapshot [exported](Self::export) to `path`,
+    /// each living the time it had left from now; returns how many were
+    /// loaded. Entries are written to the cache file too, when there is one.
+    pub async fn import(&self, path: impl AsRef<Path>) -> DaedraResult<usize> {
+        let path = path.as_ref();
+        if !self.enabled {
+            return Ok(0);
+        }
+        let entries = cache_file::read_snapshot(path).map_err(|e| snapshot_error(path, e))?;
+        if let Some(file) = &self.file {
+            // Backdated so a restart reads them back with the time they have left
+            let now = Timestamp::now();
+            for entry in &entries {
+                let elapsed = self.ttl.saturating_sub(entry.remaining).as_secs();
+                let at = Timestamp::from_unix(now.unix() - elapsed as i64).unwrap_or(now);
+                file.write_at(at, entry.key.clone(), entry.value.clone());
+            }
         }
+        let loaded = entries.len();
+        self.load(entries).await;
+        debug!(path = %path.display(), entries = loaded, "Cache imported");
+        Ok(loaded)
     }
 
     /// Create a cache with default configuration
@@ -85,104 +319,460 @@ impl SearchCache {
     }
 
     /// Generate a cache key for search queries
-    fn search_key(query: &str, region: &str, safe_search: &str) -> String {
-        format!("search:{}:{}:{}", query.to_lowercase(), region, safe_search)
+    fn search_key(&self, query: &str, region: &str, safe_search: &str) -> String {
+        if self.hash_keys {
+            format!("search:{}:{}:{}", privacy::query_hash(query), region, safe_search)
+        } else {
+            format!("search:{}:{}:{}", text::key(query), region, safe_search)
+        }
     }
 
-    /// Generate a cache key for page content
-    fn page_key(url: &str, selector: Option<&str>) -> String {
-        match selector {
-            Some(sel) => format!("page:{}:{}", url, sel),
-            None => format!("page:{}", url),
+    /// Generate a cache key for page content from the arguments that change
+    /// it; [`PageFormat::Json`] pages hold the same Markdown content as
+    /// [`PageFormat::Markdown`] ones. Pages that may come from the archive are
+    /// kept apart, so a later call that didn't allow it never gets one.
+    fn page_key(args: &VisitPageArgs) -> String {
+        let images = if args.include_images { ":images" } else { "" };
+        let format = match args.format {
+            PageFormat::Markdown | PageFormat::Json => "",
+            PageFormat::Text => ":text",
+            PageFormat::Html => ":html",
+        };
+        let extract = match (args.extract, args.follow_pagination) {
+            (ExtractMode::Links, _) => ":links".to_string(),
+            (ExtractMode::Content, Some(pages)) if pages > 1 => {
+                format!(":pages{}", pages.min(MAX_PAGINATION_PAGES))
+            },
+            (ExtractMode::Content, _) => String::new(),
+        };
+        let archive = if args.allow_archive { ":archive" } else { "" };
+        match &args.selector {
+            Some(sel) => {
+                format!("page{images}{format}{extract}{archive}:{}:{sel}", args.url)
+            },
+            None => format!("page{images}{format}{extract}{archive}:{}", args.url),
         }
     }
 
     /// Get a cached search response
-    #[instrument(skip(self))]
+    #[instrument(skip(self, query), fields(query = %privacy::loggable(query)))]
     pub async fn get_search(
         &self,
         query: &str,
         region: &str,
         safe_search: &str,
-    ) -> Option<SearchResponse> {
+    ) -> Option<Arc<SearchResponse>> {
         if !self.enabled {
             return None;
         }
 
-        let key = Self::search_key(query, region, safe_search);
-        let result = self.search_cache.get(&key).await;
+        let key = self.search_key(query, region, safe_search);
+        let result = telemetry::timed(Phase::Cache, self.search_cache.get(&key)).await;
 
         if result.is_some() {
-            debug!(query = %query, "Cache hit for search query");
+            debug!(query = %privacy::loggable(query), "Cache hit for search query");
+            telemetry::record_cache_hit();
         }
 
         result
     }
 
     /// Cache a search response
-    #[instrument(skip(self, response))]
+    #[instrument(skip(self, query, response), fields(query = %privacy::loggable(query)))]
     pub async fn set_search(
         &self,
         query: &str,
         region: &str,
         safe_search: &str,
-        response: SearchResponse,
+        response: impl Into<Arc<SearchResponse>>,
     ) {
-        if !self.enabled {
+        if !self.enabled || !self.has_room(self.search_cache.entry_count()) {
             return;
         }
 
-        let key = Self::search_key(query, region, safe_search);
-        self.search_cache.insert(key, response).await;
-        debug!(query = %query, "Cached search response");
+        let key = self.search_key(query, region, safe_search);
+        let response = response.into();
+        if let Some(file) = &self.file {
+            file.write(key.clone(), CachedValue::Search(Arc::clone(&response)));
+        }
+        telemetry::timed(Phase::Cache, self.search_cache.insert(key, response)).await;
+        debug!(query = %privacy::loggable(query), "Cached search response");
+    }
+
+    /// Whether a search response is cached, without counting as a hit
+    pub fn contains_search(&self, query: &str, region: &str, safe_search: &str) -> bool {
+        self.enabled
+            && self
+                .search_cache
+                .contains_key(&self.search_key(query, region, safe_search))
     }
 
-    /// Get cached page content
-    #[instrument(skip(self))]
-    pub async fn get_page(&self, url: &str, selector: Option<&str>) -> Option<PageContent> {
+    /// Get cached page content for a `visit_page` call
+    #[instrument(skip(self, args), fields(url = %args.url))]
+    pub async fn get_page(&self, args: &VisitPageArgs) -> Option<Arc<PageContent>> {
         if !self.enabled {
             return None;
         }
 
-        let key = Self::page_key(url, selector);
-        let result = self.page_cache.get(&key).await;
+        let key = Self::page_key(args);
+        let result = telemetry::timed(Phase::Cache, self.page_cache.get(&key)).await;
 
         if result.is_some() {
-            debug!(url = %url, "Cache hit for page content");
+            debug!(url = %args.url, "Cache hit for page content");
+            telemetry::record_cache_hit();
         }
 
         result
     }
 
-    /// Cache page content
-    #[instrument(skip(self, content))]
-    pub async fn set_page(&self, url: &str, selector: Option<&str>, content: PageContent) {
-        if !self.enabled {
+    /// Cache page content fetched for a `visit_page` call
+    #[instrument(skip(self, args, content), fields(url = %args.url))]
+    pub async fn set_page(&self, args: &VisitPageArgs, content: impl Into<Arc<PageContent>>) {
+        if !self.enabled || !self.has_room(self.page_cache.entry_count()) {
+            return;
+        }
+
+        let key = Self::page_key(args);
+        let content = content.into();
+        if let Some(file) = &self.file {
+            file.write(key.clone(), CachedValue::Page(Arc::clone(&content)));
+        }
+        telemetry::timed(Phase::Cache, self.page_cache.insert(key, content)).await;
+        debug!(url = %args.url, "Cached page content");
+    }
+
+    /// Cache page content fetched for a `visit_page` call along with the
+    /// validators its response carried, to [revalidate](Self::get_stale_page)
+    /// it once it expires
+    pub async fn set_page_validated(
+        &self,
+        args: &VisitPageArgs,
+        content: impl Into<Arc<PageContent>>,
+        validators: Validators,
+    ) {
+        let content = content.into();
+        self.set_page(args, Arc::clone(&content)).await;
+        if let Some(stale_pages) = &self.stale_pages
+            && !validators.is_empty()
+            && self.has_room(stale_pages.entry_count())
+        {
+            let page = StalePage {
+                page: content,
+                validators,
+            };
+            stale_pages.insert(Self::page_key(args), page).await;
+        }
+    }
+
+    /// A page for `args` that may have expired, with the validators to ask
+    /// the site whether it changed since; once the site says it didn't,
+    /// [`set_page_validated`](Self::set_page_validated) it again
+    pub async fn get_stale_page(
+        &self,
+        args: &VisitPageArgs,
+    ) -> Option<(Arc<PageContent>, Validators)> {
+        let stale_pages = self.stale_pages.as_ref()?;
+        let stale = telemetry::timed(Phase::Cache, stale_pages.get(&Self::page_key(args))).await?;
+        debug!(url = %args.url, "Found a page to revalidate");
+        Some((stale.page, stale.validators))
+    }
+
+    /// Remember that fetching for `args` failed with `error`, when it is
+    /// [lasting](crate::tools::fetch::is_lasting_failure)
+    pub async fn set_failure(&self, args: &VisitPageArgs, error: &DaedraError) {
+        let Some(failures) = &self.failures else {
+            return;
+        };
+        if !is_lasting_failure(error) || !self.has_room(failures.entry_count()) {
             return;
         }
+        let failure = FailedFetch {
+            reason: error.to_string(),
+            expires: Instant::now() + self.failure_ttl,
+        };
+        failures.insert(Self::failure_key(args), failure).await;
+        debug!(url = %args.url, "Cached fetch failure");
+    }
 
-        let key = Self::page_key(url, selector);
-        self.page_cache.insert(key, content).await;
-        debug!(url = %url, "Cached page content");
+    /// The error of a recent failed fetch for `args`, as a
+    /// [`DaedraError::RecentlyFailed`]
+    pub async fn get_failure(&self, args: &VisitPageArgs) -> Option<DaedraError> {
+        let failures = self.failures.as_ref()?;
+        let key = Self::failure_key(args);
+        let failure = telemetry::timed(Phase::Cache, failures.get(&key)).await?;
+        debug!(url = %args.url, "Cache hit for fetch failure");
+        telemetry::record_cache_hit();
+        Some(DaedraError::RecentlyFailed {
+            reason: failure.reason,
+            retry_in: failure.expires.saturating_duration_since(Instant::now()),
+        })
+    }
+
+    /// Whether a failure is cached for `args`, without counting as a hit
+    pub fn contains_failure(&self, args: &VisitPageArgs) -> bool {
+        self.failures.as_ref().is_some_and(|f| f.contains_key(&Self::failure_key(args)))
+    }
+
+    /// Key of fetch failures: a dead link may still be found in the archive
+    fn failure_key(args: &VisitPageArgs) -> String {
+        let archive = if args.allow_archive { ":archive" } else { "" };
+        format!("failure{archive}:{}", args.url)
+    }
+
+    /// Whether page content is cached, without counting as a hit
+    pub fn contains_page(&self, args: &VisitPageArgs) -> bool {
+        self.enabled && self.page_cache.contains_key(&Self::page_key(args))
+    }
+
+    /// Whether a page for `args` could be revalidated instead of fetched again
+    pub fn contains_stale_page(&self, args: &VisitPageArgs) -> bool {
+        self.stale_pages.as_ref().is_some_and(|s| s.contains_key(&Self::page_key(args)))
     }
 
     /// Clear all cached entries
     pub async fn clear(&self) {
         self.search_cache.invalidate_all();
         self.page_cache.invalidate_all();
+        if let Some(stale_pages) = &self.stale_pages {
+            stale_pages.invalidate_all();
+        }
+        if let Some(failures) = &self.failures {
+            failures.invalidate_all();
+        }
+        if let Some(file) = &self.file {
+            file.clear();
+        }
         debug!("Cache cleared");
     }
 
+    /// Drop the pages whose URL and the searches whose query start with
+    /// `pattern`, where `*` in it stands for any run of characters: both
+    /// `https://docs.example.com/` and `https://*.example.com/*` drop a site's
+    /// pages. Queries match case-insensitively, unless cache keys hold
+    /// [hashes](CacheConfig::hash_keys). Returns how many entries were dropped.
+    pub async fn invalidate_matching(&self, pattern: &str) -> InvalidatedEntries {
+        let page_glob = format!("{}*", pattern.trim());
+        let search_glob = format!("{}*", text::key(pattern));
+        let page_matches =
+            |key: &str| key_url(key).is_some_and(|url| glob_matches(&page_glob, url));
+        let search_matches = |key: &str| {
+            key.strip_prefix("search:").is_some_and(|query| glob_matches(&search_glob, query))
+        };
+
+        let invalidated = InvalidatedEntries {
+            search_entries: self.invalidate_keys(&self.search_cache, search_matches).await,
+            page_entries: self.invalidate_keys(&self.page_cache, page_matches).await,
+        };
+        if let Some(stale_pages) = &self.stale_pages {
+            invalidate_where(stale_pages, page_matches).await;
+        }
+        if let Some(failures) = &self.failures {
+            invalidate_where(failures, page_matches).await;
+        }
+        debug!(
+            search_entries = invalidated.search_entries,
+            page_entries = invalidated.page_entries,
+            "Cache entries invalidated"
+        );
+        invalidated
+    }
+
+    /// Invalidate the entries of `cache` whose key `matches`, in the cache
+    /// file too; returns how many
+    async fn invalidate_keys<V>(
+        &self,
+        cache: &Cache<String, V>,
+        matches: impl Fn(&str) -> bool,
+    ) -> u64
+    where
+        V: Clone + Send + Sync + 'static,
+    {
+        let keys = invalidate_where(cache, matches).await;
+        if let Some(file) = &self.file {
+            for key in &keys {
+                file.remove(key.to_string());
+            }
+        }
+        keys.len() as u64
+    }
+
+    /// Drop half the entries of the fuller cache, down to
+    /// [`MIN_SHRUNK_ENTRIES`], from each cache, and add no new ones past that
+    /// until [`restore_capacity`](Self::restore_capacity); returns the new
+    /// capacity. For easing memory pressure; which entries go is arbitrary.
+    pub async fn shrink(&self) -> u64 {
+        self.search_cache.run_pending_tasks().await;
+        self.page_cache.run_pending_tasks().await;
+        let held = self.search_cache.entry_count().max(self.page_cache.entry_count());
+        let current = self.capacity.load(Ordering::Relaxed);
+        let capacity = (current.min(held) / 2).max(MIN_SHRUNK_ENTRIES).min(current);
+        self.capacity.store(capacity, Ordering::Relaxed);
+        evict_to(&self.search_cache, capacity).await;
+        evict_to(&self.page_cache, capacity).await;
+        if let Some(stale_pages) = &self.stale_pages {
+            evict_to(stale_pages, capacity).await;
+        }
+        if let Some(failures) = &self.failures {
+            evict_to(failures, capacity).await;
+        }
+        debug!(capacity, "Cache shrunk");
+        capacity
+    }
+
+    /// Undo [`shrink`](Self::shrink), letting the caches fill to their
+    /// configured size again
+    pub fn restore_capacity(&self) {
+        self.capacity.store(self.max_entries, Ordering::Relaxed);
+    }
+
+    /// Whether a cache holding `held` entries may take another
+    fn has_room(&self, held: u64) -> bool {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        capacity >= self.max_entries || held < capacity
+    }
+
+    /// Wait until every entry cached so far is written to the cache file, when
+    /// there is one
+    pub async fn flush(&self) {
+        if let Some(file) = &self.file {
+            file.flush().await;
+        }
+    }
+
     /// Get statistics about the cache
     pub fn stats(&self) -> CacheStats {
         CacheStats {
             search_entries: self.search_cache.entry_count(),
             page_entries: self.page_cache.entry_count(),
+            bytes: self.bytes.load(Ordering::Relaxed),
             enabled: self.enabled,
         }
     }
 }
 
+/// A cache builder for `config`'s capacity and `ttl`, weighing entries by
+/// `size` when it has a byte budget, and keeping `bytes` at the estimated
+/// size of the entries held, when given
+fn sized_builder<V>(
+    config: &CacheConfig,
+    ttl: Duration,
+    bytes: Option<&Arc<AtomicU64>>,
+    size: fn(&V) -> u64,
+) -> CacheBuilder<String, V, Cache<String, V>>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    let budget = config.max_memory_bytes;
+    let added = bytes.cloned();
+    let removed = bytes.cloned();
+    Cache::builder()
+        .max_capacity(budget.unwrap_or(config.max_entries))
+        .time_to_live(ttl)
+        .weigher(move |_key, value| {
+            let bytes = size(value);
+            if let Some(added) = &added {
+                added.fetch_add(bytes, Ordering::Relaxed);
+            }
+            match budget {
+                Some(_) => u32::try_from(bytes).unwrap_or(u32::MAX),
+                None => 1,
+            }
+        })
+        .eviction_listener(move |_key, value, _cause| {
+            if let Some(removed) = &removed {
+                let bytes = size(&value);
+                let _ = removed.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |held| {
+                    Some(held.saturating_sub(bytes))
+                });
+            }
+        })
+}
+
+/// Estimated bytes `value` takes up: the length of its JSON
+fn json_len(value: &impl Serialize) -> u64 {
+    struct Counter(u64);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// Entries dropped by [`SearchCache::invalidate_matching`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidatedEntries {
+    /// Search responses dropped
+    pub search_entries: u64,
+
+    /// Pages dropped
+    pub page_entries: u64,
+}
+
+/// An error reading or writing the snapshot at `path`
+fn snapshot_error(path: &Path, e: std::io::Error) -> DaedraError {
+    let message = format!("Cannot use cache snapshot {}: {}", path.display(), e);
+    DaedraError::IoError(std::io::Error::new(e.kind(), message))
+}
+
+/// The URL a page or failure key is for, with the selector after it if any
+fn key_url(key: &str) -> Option<&str> {
+    let at = key.find(":http")?;
+    Some(&key[at + 1..])
+}
+
+/// Invalidate the entries of `cache` whose key `matches`, returning their keys
+async fn invalidate_where<V>(
+    cache: &Cache<String, V>,
+    matches: impl Fn(&str) -> bool,
+) -> Vec<Arc<String>>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    let keys: Vec<Arc<String>> =
+        cache.iter().map(|(key, _)| key).filter(|key| matches(key)).collect();
+    for key in &keys {
+        cache.invalidate(key.as_str()).await;
+    }
+    keys
+}
+
+/// A fetch failure kept for [`CacheConfig::failure_ttl`]
+#[derive(Debug, Clone)]
+struct FailedFetch {
+    reason: String,
+    expires: Instant,
+}
+
+/// A page kept for revalidation
+#[derive(Debug, Clone)]
+struct StalePage {
+    page: Arc<PageContent>,
+    validators: Validators,
+}
+
+/// Invalidate arbitrary entries of `cache` until it holds at most `capacity`
+async fn evict_to<V>(cache: &Cache<String, V>, capacity: u64)
+where
+    V: Clone + Send + Sync + 'static,
+{
+    let excess = usize::try_from(cache.entry_count().saturating_sub(capacity)).unwrap_or(0);
+    let keys: Vec<Arc<String>> = cache.iter().map(|(key, _)| key).take(excess).collect();
+    for key in keys {
+        cache.invalidate(key.as_str()).await;
+    }
+    cache.run_pending_tasks().await;
+}
+
 impl Default for SearchCache {
     fn default() -> Self {
         Self::with_defaults()
@@ -198,6 +788,9 @@ pub struct CacheStats {
     /// Number of cached page contents
     pub page_entries: u64,
 
+    /// Estimated bytes of the cached search responses and pages
+    pub bytes: u64,
+
     /// Whether caching is enabled
     pub enabled: bool,
 }
@@ -206,8 +799,8 @@ impl std::fmt::Display for CacheStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Cache Stats: {} search entries, {} page entries (enabled: {})",
-            self.search_entries, self.page_entries, self.enabled
+            "Cache Stats: {} search entries, {} page entries, {} bytes (enabled: {})",
+            self.search_entries, self.page_entries, self.bytes, self.enabled
         )
     }
 }
@@ -217,6 +810,25 @@ mod tests {
     use super::*;
     use crate::types::{ContentType, ResultMetadata, SearchOptions, SearchResult};
 
+    fn page(url: &str, timestamp: Timestamp) -> PageContent {
+        PageContent {
+            url: url.to_string(),
+            title: "Page".to_string(),
+            content: "Body text".to_string(),
+            timestamp,
+            word_count: 2,
+            links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        }
+    }
+
     #[tokio::test]
     async fn test_cache_search() {
         let cache = SearchCache::with_defaults();
@@ -227,9 +839,10 @@ mod tests {
             description: "Test description".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: "example.com".to_string(),
+                source: "example.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }];
 
@@ -248,11 +861,30 @@ mod tests {
         cache
             .set_search("test", "wt-wt", "MODERATE", response.clone())
             .await;
+        assert!(cache.contains_search("TEST", "wt-wt", "MODERATE"));
+        assert!(!cache.contains_search("test", "us-en", "MODERATE"));
+        assert!(cache.contains_search("  Test ", "wt-wt", "MODERATE"));
         let cached = cache.get_search("test", "wt-wt", "MODERATE").await;
         assert!(cached.is_some());
         assert_eq!(cached.unwrap().data.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_hashed_keys_hold_no_query_text() {
+        let cache = SearchCache::new(CacheConfig {
+            hash_keys: true,
+            ..Default::default()
+        });
+        let key = cache.search_key("Secret Query", "wt-wt", "MODERATE");
+        assert!(!key.to_lowercase().contains("secret"), "{key}");
+        assert_eq!(key, cache.search_key("secret query", "wt-wt", "MODERATE"));
+
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        cache.set_search("Secret Query", "wt-wt", "MODERATE", response).await;
+        assert!(cache.get_search("secret query", "wt-wt", "MODERATE").await.is_some());
+        assert!(!cache.contains_search("other query", "wt-wt", "MODERATE"));
+    }
+
     #[tokio::test]
     async fn test_cache_page() {
         let cache = SearchCache::with_defaults();
@@ -261,21 +893,276 @@ mod tests {
             url: "https://example.com".to_string(),
             title: "Test Page".to_string(),
             content: "# Hello World".to_string(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: Timestamp::now(),
             word_count: 2,
             links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        };
+
+        let args = VisitPageArgs {
+            url: "https://example.com".to_string(),
+            ..Default::default()
+        };
+        let with = |change: fn(&mut VisitPageArgs)| {
+            let mut args = args.clone();
+            change(&mut args);
+            args
         };
 
         // Initially empty
-        assert!(cache.get_page("https://example.com", None).await.is_none());
+        assert!(cache.get_page(&args).await.is_none());
 
         // Set and get
-        cache
-            .set_page("https://example.com", None, content.clone())
-            .await;
-        let cached = cache.get_page("https://example.com", None).await;
+        cache.set_page(&args, content.clone()).await;
+        assert!(cache.contains_page(&args));
+        assert!(!cache.contains_page(&with(|a| a.selector = Some("main".to_string()))));
+        assert!(!cache.contains_page(&with(|a| a.include_images = true)));
+        assert!(!cache.contains_page(&with(|a| a.format = PageFormat::Html)));
+        assert!(!cache.contains_page(&with(|a| a.extract = ExtractMode::Links)));
+        assert!(!cache.contains_page(&with(|a| a.follow_pagination = Some(3))));
+        assert!(cache.contains_page(&with(|a| a.follow_pagination = Some(1))));
+        assert!(!cache.contains_page(&with(|a| a.allow_archive = true)));
+        assert!(cache.contains_page(&with(|a| a.format = PageFormat::Json)));
+        assert!(cache.contains_page(&with(|a| a.max_tokens_hint = Some(100))));
+        let cached = cache.get_page(&args).await;
         assert!(cached.is_some());
         assert_eq!(cached.unwrap().title, "Test Page");
+
+        // Hits share the cached page rather than copying it
+        let shared = Arc::new(content);
+        let images = with(|a| a.include_images = true);
+        cache.set_page(&images, shared.clone()).await;
+        let hit = cache.get_page(&images).await.unwrap();
+        assert!(Arc::ptr_eq(&hit, &shared));
+    }
+
+    #[tokio::test]
+    async fn test_expired_pages_with_validators_can_be_revalidated() {
+        let config = CacheConfig {
+            ttl: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let cache = SearchCache::new(config.clone());
+        let args = |url: &str| VisitPageArgs {
+            url: url.to_string(),
+            ..Default::default()
+        };
+        let validators = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        let tagged = args("https://example.com/tagged");
+        let untagged = args("https://example.com/untagged");
+        let now = Timestamp::now();
+        cache.set_page_validated(&tagged, page(&tagged.url, now), validators.clone()).await;
+        cache.set_page_validated(&untagged, page(&untagged.url, now), Validators::default()).await;
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(cache.get_page(&tagged).await.is_none());
+        let (stale, kept) = cache.get_stale_page(&tagged).await.unwrap();
+        assert_eq!(stale.url, tagged.url);
+        assert_eq!(kept, validators);
+        assert!(!cache.contains_stale_page(&untagged));
+
+        cache.clear().await;
+        assert!(!cache.contains_stale_page(&tagged));
+
+        let off = SearchCache::new(CacheConfig {
+            revalidation_window: Duration::ZERO,
+            ..config
+        });
+        off.set_page_validated(&tagged, page(&tagged.url, now), validators).await;
+        assert!(off.contains_page(&tagged));
+        assert!(!off.contains_stale_page(&tagged));
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_matching_drops_only_matching_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.jsonl");
+        let config = CacheConfig {
+            persistence: Some(CacheFileConfig::new(&path)),
+            ..Default::default()
+        };
+        let cache = SearchCache::new(config.clone());
+        let args = |url: &str| VisitPageArgs {
+            url: url.to_string(),
+            ..Default::default()
+        };
+        let urls = [
+            "https://docs.example.com/a",
+            "https://blog.example.com/b",
+            "https://example.org/c",
+        ];
+        for url in urls {
+            cache.set_page(&args(url), page(url, Timestamp::now())).await;
+        }
+        let images = VisitPageArgs {
+            include_images: true,
+            ..args(urls[0])
+        };
+        cache.set_page(&images, page(urls[0], Timestamp::now())).await;
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        for query in ["Rust async", "rust traits", "python"] {
+            cache.set_search(query, "wt-wt", "MODERATE", response.clone()).await;
+        }
+
+        let dropped = cache.invalidate_matching("https://*.example.com/").await;
+        assert_eq!(dropped.page_entries, 3);
+        assert_eq!(dropped.search_entries, 0);
+        assert!(!cache.contains_page(&images));
+        assert!(cache.contains_page(&args(urls[2])));
+
+        let dropped = cache.invalidate_matching("RUST").await;
+        assert_eq!(dropped.search_entries, 2);
+        assert!(cache.contains_search("python", "wt-wt", "MODERATE"));
+
+        // Dropped entries stay gone after a restart
+        cache.flush().await;
+        let restarted = SearchCache::new(config);
+        assert!(!restarted.contains_page(&args(urls[0])));
+        assert!(restarted.contains_page(&args(urls[2])));
+        assert!(!restarted.contains_search("rust async", "wt-wt", "MODERATE"));
+        assert!(restarted.contains_search("python", "wt-wt", "MODERATE"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip_keeps_remaining_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.jsonl");
+        let cache = SearchCache::with_defaults();
+        let args = |url: &str| VisitPageArgs {
+            url: url.to_string(),
+            ..Default::default()
+        };
+        let fresh = args("https://example.com/fresh");
+        let old = args("https://example.com/old");
+        let older = Timestamp::from_unix(Timestamp::now().unix() - 600).unwrap();
+        cache.set_page(&fresh, page(&fresh.url, Timestamp::now())).await;
+        cache.set_page(&old, page(&old.url, older)).await;
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        cache.set_search("q", "wt-wt", "MODERATE", response).await;
+
+        // The page fetched longer ago than the TTL has no time left to carry over
+        assert_eq!(cache.export(&path).unwrap(), 2);
+        let imported = SearchCache::with_defaults();
+        assert_eq!(imported.import(&path).await.unwrap(), 2);
+        assert!(imported.contains_page(&fresh));
+        assert!(!imported.contains_page(&old));
+        assert!(imported.contains_search("q", "wt-wt", "MODERATE"));
+
+        let short = SearchCache::new(CacheConfig {
+            ttl: Duration::from_secs(1),
+            ..Default::default()
+        });
+        short.import(&path).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert!(!short.contains_page(&fresh));
+
+        let err = imported.import(dir.path().join("missing.jsonl")).await.unwrap_err();
+        assert!(err.to_string().contains("Cannot use cache snapshot"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_imported_entries_persist_with_their_remaining_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("snapshot.jsonl");
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        let line = serde_json::json!({
+            "key": "search:q:wt-wt:MODERATE",
+            "ttl_secs": 2,
+            "kind": "search",
+            "value": &response,
+        });
+        std::fs::write(&snapshot, line.to_string()).unwrap();
+        let config = CacheConfig {
+            persistence: Some(CacheFileConfig::new(dir.path().join("cache.jsonl"))),
+            ..Default::default()
+        };
+
+        let cache = SearchCache::new(config.clone());
+        assert_eq!(cache.import(&snapshot).await.unwrap(), 1);
+        cache.flush().await;
+        assert!(SearchCache::new(config.clone()).contains_search("q", "wt-wt", "MODERATE"));
+
+        // A restart after the snapshot's time ran out doesn't bring it back
+        tokio::time::sleep(Duration::from_millis(2100)).await;
+        assert!(!SearchCache::new(config).contains_search("q", "wt-wt", "MODERATE"));
+    }
+
+    #[tokio::test]
+    async fn test_lasting_fetch_failures_are_cached() {
+        let cache = SearchCache::with_defaults();
+        let args = VisitPageArgs {
+            url: "https://example.com/gone".to_string(),
+            ..Default::default()
+        };
+        let archived = VisitPageArgs {
+            allow_archive: true,
+            ..args.clone()
+        };
+
+        cache.set_failure(&args, &DaedraError::Timeout).await;
+        assert!(cache.get_failure(&args).await.is_none());
+
+        let gone = DaedraError::FetchError("HTTP 404 Not Found".to_string());
+        cache.set_failure(&args, &gone).await;
+        assert!(!cache.contains_failure(&archived));
+        let err = cache.get_failure(&args).await.unwrap();
+        assert!(err.to_string().starts_with("Failed to fetch page: HTTP 404"), "{err}");
+        assert!(err.to_string().contains("cached from an earlier attempt"), "{err}");
+        assert!(err.retry_after().unwrap() <= DEFAULT_FAILURE_TTL);
+
+        cache.clear().await;
+        assert!(!cache.contains_failure(&args));
+
+        let off = SearchCache::new(CacheConfig {
+            failure_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        off.set_failure(&args, &DaedraError::BotProtectionDetected).await;
+        assert!(!off.contains_failure(&args));
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget_bounds_the_page_cache() {
+        let page = |n: usize| PageContent {
+            content: "x".repeat(10_000),
+            word_count: 1,
+            ..page(&format!("https://example.com/{n}"), Timestamp::now())
+        };
+        let args = |n: usize| VisitPageArgs {
+            url: format!("https://example.com/{n}"),
+            ..Default::default()
+        };
+
+        let counted = SearchCache::with_defaults();
+        counted.set_page(&args(0), page(0)).await;
+        counted.page_cache.run_pending_tasks().await;
+        let bytes = counted.stats().bytes;
+        assert!((10_000..11_000).contains(&bytes), "{bytes}");
+        counted.clear().await;
+        counted.page_cache.run_pending_tasks().await;
+        assert_eq!(counted.stats().bytes, 0);
+
+        let budgeted = SearchCache::new(CacheConfig {
+            max_memory_bytes: Some(35_000),
+            ..Default::default()
+        });
+        for n in 0..10 {
+            budgeted.set_page(&args(n), page(n)).await;
+            budgeted.page_cache.run_pending_tasks().await;
+        }
+        let stats = budgeted.stats();
+        assert!(stats.page_entries <= 3, "{stats}");
+        assert!(stats.bytes <= 35_000, "{stats}");
     }
 
     #[tokio::test]
@@ -295,6 +1182,80 @@ mod tests {
                 .await
                 .is_none()
         );
+        assert!(!cache.contains_search("test", "wt-wt", "MODERATE"));
+    }
+
+    #[tokio::test]
+    async fn test_persisted_entries_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.jsonl");
+        let config = CacheConfig {
+            persistence: Some(CacheFileConfig::new(&path)),
+            ..Default::default()
+        };
+        let args = VisitPageArgs {
+            url: "https://example.com/a".to_string(),
+            ..Default::default()
+        };
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        let page = |title: &str| PageContent {
+            title: title.to_string(),
+            ..page(&args.url, Timestamp::now())
+        };
+
+        let cache = SearchCache::new(config.clone());
+        cache.set_search("q", "wt-wt", "MODERATE", response).await;
+        cache.set_page(&args, page("First")).await;
+        cache.set_page(&args, page("Second")).await;
+        cache.flush().await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+        let restarted = SearchCache::new(config.clone());
+        assert!(restarted.contains_search("q", "wt-wt", "MODERATE"));
+        assert_eq!(restarted.get_page(&args).await.unwrap().title, "Second");
+        // Reading the file back compacts it to the newest entry per key
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        restarted.clear().await;
+        restarted.flush().await;
+        assert!(!SearchCache::new(config).contains_page(&args));
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_unreadable_lines_are_not_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.jsonl");
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        let now = Timestamp::now().unix();
+        let line = |at: serde_json::Value, key: &str| {
+            serde_json::json!({"at": at, "key": key, "kind": "search", "value": &response})
+                .to_string()
+        };
+        // Older versions wrote Unix seconds; they still load next to timestamps
+        let fresh = Timestamp::from_unix(now - 10).unwrap().to_string();
+        let lines = [
+            line((now - 600).into(), "search:old:wt-wt:MODERATE"),
+            "{ not json".to_string(),
+            line(fresh.into(), "search:fresh:wt-wt:MODERATE"),
+            line((now - 299).into(), "search:stale:wt-wt:MODERATE"),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let cache = SearchCache::new(CacheConfig {
+            persistence: Some(CacheFileConfig {
+                fsync: crate::cache_file::FsyncPolicy::Always,
+                ..CacheFileConfig::new(&path)
+            }),
+            ..Default::default()
+        });
+        assert!(cache.contains_search("fresh", "wt-wt", "MODERATE"));
+        assert!(!cache.contains_search("old", "wt-wt", "MODERATE"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        // Loaded entries keep the time they had left, not a fresh TTL
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!cache.contains_search("stale", "wt-wt", "MODERATE"));
+        assert!(cache.contains_search("fresh", "wt-wt", "MODERATE"));
     }
 
     #[tokio::test]
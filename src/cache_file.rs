@@ -0,0 +1,467 @@
+//! Write-behind persistence for the cache.
+//!
+//! With [`CacheConfig::persistence`](crate::cache::CacheConfig::persistence)
+//! set, every response and page the cache stores is also appended to a JSON
+//! Lines file, one record per entry, so a restarted server starts warm. The
+//! cache never waits on the disk: entries are handed to a writer thread, which
+//! writes them in batches of up to [`CacheFileConfig::batch_size`] or every
+//! [`CacheFileConfig::flush_interval`], whichever comes first, and syncs them
+//! as [`CacheFileConfig::fsync`] says. Entries still queued when the process
+//! dies are lost, which a cache can afford.
+//!
+//! Opening the file reads it back: the newest record for each key is kept,
+//! records older than the cache's time-to-live are dropped, and the survivors
+//! are written out again so the file doesn't grow across restarts. Lines that
+//! don't parse are skipped. Records are stamped with the [`Timestamp`] they
+//! were written at; files from versions that wrote Unix seconds still load.
+//! Entries invalidated one by one are recorded as `removed`, so they stay gone
+//! across a restart.
+//!
+//! Snapshots ([`SearchCache::export`](crate::SearchCache::export)) use the
+//! same lines, stamped with the time each entry had left to live instead of
+//! when it was written, so an imported entry lives that long from the import.
+
+use crate::timestamp::Timestamp;
+use crate::types::{PageContent, SearchResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+/// Default most entries written between syncs
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Default longest time an entry waits to be written
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the cache file is synced to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// Leave syncing to the operating system
+    Never,
+    /// Once per batch of writes
+    #[default]
+    Batch,
+    /// After every entry
+    Always,
+}
+
+/// Where and how the cache is persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheFileConfig {
+    /// The JSON Lines file holding cached entries
+    pub path: PathBuf,
+    /// When writes are synced to disk
+    pub fsync: FsyncPolicy,
+    /// Most entries written in one batch
+    pub batch_size: usize,
+    /// Longest time an entry waits for its batch to fill
+    pub flush_interval: Duration,
+}
+
+impl CacheFileConfig {
+    /// Persist to `path` with the default batching and fsync policy
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            fsync: FsyncPolicy::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+        }
+    }
+}
+
+/// A cached search response or page.
+#[derive(Debug, Clone)]
+pub(crate) enum CachedValue {
+    Search(Arc<SearchResponse>),
+    Page(Arc<PageContent>),
+}
+
+/// A cache entry read back from the file, with the time it has left to live
+#[derive(Debug)]
+pub(crate) struct LoadedEntry {
+    pub key: String,
+    pub value: CachedValue,
+    pub remaining: Duration,
+}
+
+/// One line of the file, as written
+#[derive(Serialize)]
+struct RecordRef<'a> {
+    at: Timestamp,
+    key: &'a str,
+    #[serde(flatten)]
+    value: ValueRef<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+enum ValueRef<'a> {
+    Search(&'a SearchResponse),
+    Page(&'a PageContent),
+    Removed,
+}
+
+/// One line of a snapshot, as written
+#[derive(Serialize)]
+struct SnapshotRecordRef<'a> {
+    key: &'a str,
+    ttl_secs: u64,
+    #[serde(flatten)]
+    value: ValueRef<'a>,
+}
+
+/// One line of a snapshot, as read back
+#[derive(Deserialize)]
+struct SnapshotRecord {
+    key: String,
+    ttl_secs: u64,
+    #[serde(flatten)]
+    value: StoredValue,
+}
+
+/// One line of the file, as read back
+#[derive(Deserialize)]
+struct Record {
+    at: Timestamp,
+    key: String,
+    #[serde(flatten)]
+    value: StoredValue,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+enum StoredValue {
+    Search(SearchResponse),
+    Page(PageContent),
+    Removed,
+}
+
+impl Record {
+    /// The entry the record holds; `None` for a removal
+    fn into_entry(self, remaining: Duration) -> Option<LoadedEntry> {
+        let value = match self.value {
+            StoredValue::Search(response) => CachedValue::Search(Arc::new(response)),
+            StoredValue::Page(page) => CachedValue::Page(Arc::new(page)),
+            StoredValue::Removed => return None,
+        };
+        Some(LoadedEntry {
+            key: self.key,
+            value,
+            remaining,
+        })
+    }
+}
+
+/// A request to the writer thread
+enum Command {
+    Write { at: Timestamp, key: String, value: CachedValue },
+    Remove { at: Timestamp, key: String },
+    Clear,
+    Flush(oneshot::Sender<()>),
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Write { key, .. } => write!(f, "Write({key})"),
+            Self::Remove { key, .. } => write!(f, "Remove({key})"),
+            Self::Clear => f.write_str("Clear"),
+            Self::Flush(_) => f.write_str("Flush"),
+        }
+    }
+}
+
+/// Handle to the writer thread; the thread finishes its last batch and exits
+/// once every handle is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct CacheFile {
+    commands: Sender<Command>,
+}
+
+impl CacheFile {
+    /// Open (or create) the file, compact it, and start the writer thread;
+    /// returns the entries younger than `ttl`, oldest first
+    pub fn open(config: &CacheFileConfig, ttl: Duration) -> io::Result<(Self, Vec<LoadedEntry>)> {
+        let now = Timestamp::now();
+        let entries = compact(&config.path, ttl, now)?;
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        info!(path = %config.path.display(), entries = entries.len(), "Cache file opened");
+
+        let (commands, receiver) = mpsc::channel();
+        let writer = Writer {
+            out: BufWriter::new(file),
+            config: config.clone(),
+        };
+        std::thread::Builder::new()
+            .name("daedra-cache-writer".to_string())
+            .spawn(move || writer.run(receiver))?;
+
+        let loaded = entries
+            .into_iter()
+            .filter_map(|record| {
+                let remaining = ttl.saturating_sub(age(record.at, now));
+                record.into_entry(remaining)
+            })
+            .collect();
+        Ok((Self { commands }, loaded))
+    }
+
+    /// Queue `value` to be written under `key`
+    pub fn write(&self, key: String, value: CachedValue) {
+        self.write_at(Timestamp::now(), key, value);
+    }
+
+    /// Queue `value` to be written under `key` as if it had been `at`, so it
+    /// is read back with the time it had left then
+    pub fn write_at(&self, at: Timestamp, key: String, value: CachedValue) {
+        self.send(Command::Write { at, key, value });
+    }
+
+    /// Record that the entry under `key` is gone
+    pub fn remove(&self, key: String) {
+        self.send(Command::Remove {
+            at: Timestamp::now(),
+            key,
+        });
+    }
+
+    /// Empty the file, after writing anything already queued
+    pub fn clear(&self) {
+        self.send(Command::Clear);
+    }
+
+    /// Wait until everything queued so far has been written and synced as the
+    /// fsync policy says
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        self.send(Command::Flush(done));
+        let _ = written.await;
+    }
+
+    fn send(&self, command: Command) {
+        if self.commands.send(command).is_err() {
+            warn!("Cache file writer has stopped; entry not persisted");
+        }
+    }
+}
+
+/// Write `entries` to a snapshot at `path`, replacing it whole; returns how
+/// many were written
+pub(crate) fn write_snapshot<'a>(
+    path: &Path,
+    entries: impl IntoIterator<Item = (&'a str, Duration, &'a CachedValue)>,
+) -> io::Result<usize> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    let mut written = 0usize;
+    for (key, remaining, value) in entries {
+        let record = SnapshotRecordRef {
+            key,
+            ttl_secs: remaining.as_secs(),
+            value: value.as_ref(),
+        };
+        serde_json::to_writer(&mut out, &record)?;
+        out.write_all(b"\n")?;
+        written += 1;
+    }
+    out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(written)
+}
+
+/// The entries of the snapshot at `path` with time left to live; lines that
+/// don't parse are skipped
+pub(crate) fn read_snapshot(path: &Path) -> io::Result<Vec<LoadedEntry>> {
+    let mut entries = Vec::new();
+    let mut skipped = 0usize;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(record) = serde_json::from_str::<SnapshotRecord>(&line) else {
+            skipped += 1;
+            continue;
+        };
+        let record = Record {
+            at: Timestamp::now(),
+            key: record.key,
+            value: record.value,
+        }
+        .into_entry(Duration::from_secs(record.ttl_secs));
+        entries.extend(record.filter(|entry| !entry.remaining.is_zero()));
+    }
+    if skipped > 0 {
+        warn!(path = %path.display(), lines = skipped, "Skipped unreadable snapshot lines");
+    }
+    Ok(entries)
+}
+
+/// Rewrite the file at `path` with only the newest record per key younger
+/// than `ttl`, returning those records oldest first
+fn compact(path: &Path, ttl: Duration, now: Timestamp) -> io::Result<Vec<Record>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut newest: HashMap<String, Record> = HashMap::new();
+    let mut skipped = 0usize;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Record>(&line) {
+            Ok(record) => {
+                if newest.get(&record.key).is_none_or(|seen| seen.at <= record.at) {
+                    newest.insert(record.key.clone(), record);
+                }
+            },
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        warn!(path = %path.display(), lines = skipped, "Skipped unreadable cache file lines");
+    }
+
+    let mut records: Vec<Record> = newest
+        .into_values()
+        .filter(|record| age(record.at, now) < ttl)
+        .filter(|record| !matches!(record.value, StoredValue::Removed))
+        .collect();
+    records.sort_by_key(|record| record.at);
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        for record in &records {
+            write_record(&mut out, record.at, &record.key, record.value.as_ref())?;
+        }
+        out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(records)
+}
+
+impl CachedValue {
+    fn as_ref(&self) -> ValueRef<'_> {
+        match self {
+            Self::Search(response) => ValueRef::Search(response),
+            Self::Page(page) => ValueRef::Page(page),
+        }
+    }
+}
+
+impl StoredValue {
+    fn as_ref(&self) -> ValueRef<'_> {
+        match self {
+            Self::Search(response) => ValueRef::Search(response),
+            Self::Page(page) => ValueRef::Page(page),
+            Self::Removed => ValueRef::Removed,
+        }
+    }
+}
+
+fn write_record(
+    out: &mut impl Write,
+    at: Timestamp,
+    key: &str,
+    value: ValueRef<'_>,
+) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, &RecordRef { at, key, value })?;
+    out.write_all(b"\n")
+}
+
+/// How long before `now` a record written `at` was; zero for one from the future
+pub(crate) fn age(at: Timestamp, now: Timestamp) -> Duration {
+    Duration::from_secs(u64::try_from(now.unix() - at.unix()).unwrap_or(0))
+}
+
+struct Writer {
+    out: BufWriter<File>,
+    config: CacheFileConfig,
+}
+
+impl Writer {
+    /// Write batches until every [`CacheFile`] handle is gone
+    fn run(mut self, commands: Receiver<Command>) {
+        while let Ok(first) = commands.recv() {
+            let deadline = Instant::now() + self.config.flush_interval;
+            let mut waiting = Vec::new();
+            let mut written = 0usize;
+            let mut next = Some(first);
+            while let Some(command) = next.take() {
+                match command {
+                    Command::Write { at, key, value } => {
+                        self.append(at, &key, value.as_ref());
+                        written += 1;
+                    },
+                    Command::Remove { at, key } => {
+                        self.append(at, &key, ValueRef::Removed);
+                        written += 1;
+                    },
+                    Command::Clear => {
+                        let cleared = self.out.flush().and_then(|()| self.out.get_ref().set_len(0));
+                        self.report(cleared);
+                    },
+                    Command::Flush(done) => {
+                        waiting.push(done);
+                        break;
+                    },
+                }
+                if written >= self.config.batch_size.max(1) {
+                    break;
+                }
+                next = match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) => commands.recv_timeout(left).ok(),
+                    None => commands.try_recv().ok(),
+                };
+            }
+
+            let finished = match self.config.fsync {
+                FsyncPolicy::Batch if written > 0 => self.sync(),
+                _ => self.out.flush(),
+            };
+            self.report(finished);
+            debug!(entries = written, "Cache file batch written");
+            for done in waiting {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    /// Write one record, syncing it when the policy says to after each
+    fn append(&mut self, at: Timestamp, key: &str, value: ValueRef<'_>) {
+        let result = write_record(&mut self.out, at, key, value);
+        self.report(result);
+        if self.config.fsync == FsyncPolicy::Always {
+            let result = self.sync();
+            self.report(result);
+        }
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.out.get_ref().sync_data()
+    }
+
+    fn report(&self, result: io::Result<()>) {
+        if let Err(e) = result {
+            warn!(path = %self.config.path.display(), error = %e, "Cache file write failed");
+        }
+    }
+}
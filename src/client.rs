@@ -0,0 +1,296 @@
+//! In-process client for Daedra's tools.
+//!
+//! [`DaedraClient`] calls the same handler the MCP transports use — search
+//! backends with fallback, circuit breakers, retry/backoff and the response
+//! cache — but with typed arguments and results instead of JSON-RPC.
+//!
+//! ```rust,no_run
+//! use daedra::{DaedraClient, SearchArgs};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = DaedraClient::with_defaults()?;
+//!     let results = client
+//!         .search(SearchArgs {
+//!             query: "rust async runtime".to_string(),
+//!             options: None,
+//!         })
+//!         .await?;
+//!     for r in &results.data {
+//!         println!("{} — {}", r.title, r.url);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::cache::{CacheStats, InvalidatedEntries};
+use crate::server::{DaedraHandler, FetchPlan, McpTool, ResearchPlan, SearchPlan, ServerConfig};
+use crate::tools::{self, fetch};
+use crate::types::{
+    CheckUrlArgs, CrawlArgs, CrawlPlan, CrawlResult, DaedraError, DaedraResult, DeepResearchArgs,
+    DeepResearchReport, HackerNewsArgs, MultiSearchArgs, MultiSearchItem, PageContent,
+    PlannedRequest, ResearchDigest, SearchAndReadArgs, SearchArgs, SearchResponse,
+    StackOverflowArgs, Transcript, TranscriptArgs, UrlCheck, VisitPageArgs,
+};
+
+/// Typed, transport-free access to Daedra's tools.
+///
+/// Cloning is cheap; clones share the cache and backend health state.
+#[derive(Clone)]
+pub struct DaedraClient {
+    handler: DaedraHandler,
+}
+
+impl DaedraClient {
+    /// Create a client with the given configuration
+    pub fn new(config: ServerConfig) -> DaedraResult<Self> {
+        Ok(Self {
+            handler: DaedraHandler::new(config)?,
+        })
+    }
+
+    /// Create a client with default configuration
+    pub fn with_defaults() -> DaedraResult<Self> {
+        Self::new(ServerConfig::default())
+    }
+
+    /// Search the web (the `web_search` tool)
+    pub async fn search(&self, args: SearchArgs) -> DaedraResult<SearchResponse> {
+        self.handler.execute_search(args).await
+    }
+
+    /// Run several searches concurrently, one item per query in order, each
+    /// holding its response or its own error (the `multi_search` tool)
+    pub async fn multi_search(&self, args: MultiSearchArgs) -> DaedraResult<Vec<MultiSearchItem>> {
+        self.handler.execute_multi_search(args).await
+    }
+
+    /// Search, then read the top results into one digest (the `search_and_read` tool)
+    pub async fn search_and_read(&self, args: SearchAndReadArgs) -> DaedraResult<ResearchDigest> {
+        self.handler.execute_search_and_read(args).await
+    }
+
+    /// Research a question over several search rounds and read the best
+    /// sources into one report (the `deep_research` tool)
+    pub async fn deep_research(&self, args: DeepResearchArgs) -> DaedraResult<DeepResearchReport> {
+        self.handler.execute_deep_research(args).await
+    }
+
+    /// Search Hacker News through the Algolia HN API (the `search_hackernews` tool)
+    pub async fn search_hackernews(&self, args: HackerNewsArgs) -> DaedraResult<SearchResponse> {
+        self.handler.execute_search_hackernews(args).await
+    }
+
+    /// Search Stack Overflow questions through the Stack Exchange API (the
+    /// `search_stackoverflow` tool)
+    pub async fn search_stackoverflow(
+        &self,
+        args: StackOverflowArgs,
+    ) -> DaedraResult<SearchResponse> {
+        self.handler.execute_search_stackoverflow(args).await
+    }
+
+    /// Get a YouTube video's captions (the `get_transcript` tool);
+    /// [`Transcript::to_markdown`] renders them as the tool does
+    pub async fn get_transcript(&self, args: TranscriptArgs) -> DaedraResult<Transcript> {
+        self.handler.execute_get_transcript(args).await
+    }
+
+    /// Fetch a page and extract it as Markdown (the `visit_page` tool)
+    pub async fn visit_page(&self, args: VisitPageArgs) -> DaedraResult<PageContent> {
+        if !fetch::is_valid_url(&args.url) {
+            return Err(DaedraError::InvalidArguments(
+                "Invalid URL: must be HTTP or HTTPS".to_string(),
+            ));
+        }
+        self.handler.execute_fetch(args).await
+    }
+
+    /// Status, final URL, content type, size and headers of a URL, without
+    /// downloading it (the `check_url` tool)
+    pub async fn check_url(&self, args: CheckUrlArgs) -> DaedraResult<UrlCheck> {
+        self.handler.execute_check_url(args).await
+    }
+
+    /// Crawl a site from a root URL (the `crawl_site` tool)
+    pub async fn crawl_site(&self, args: CrawlArgs) -> DaedraResult<CrawlResult> {
+        tools::crawl_site(args).await
+    }
+
+    /// What [`search`](Self::search) would do — backends, cache state, rate
+    /// limits and requests — without sending anything
+    pub fn plan_search(&self, args: &SearchArgs) -> SearchPlan {
+        self.handler.plan_search(args)
+    }
+
+    /// What [`multi_search`](Self::multi_search) would do for each query,
+    /// without sending anything
+    pub fn plan_multi_search(&self, args: &MultiSearchArgs) -> DaedraResult<Vec<SearchPlan>> {
+        args.validate()?;
+        Ok(self.handler.plan_multi_search(args))
+    }
+
+    /// What [`search_and_read`](Self::search_and_read) would do, without sending anything
+    pub fn plan_search_and_read(&self, args: &SearchAndReadArgs) -> SearchPlan {
+        self.handler.plan_search_and_read(args)
+    }
+
+    /// What [`deep_research`](Self::deep_research) would do in its first round,
+    /// without sending anything
+    pub fn plan_deep_research(&self, args: &DeepResearchArgs) -> ResearchPlan {
+        self.handler.plan_deep_research(args)
+    }
+
+    /// The request [`search_hackernews`](Self::search_hackernews) would send
+    pub fn plan_search_hackernews(&self, args: &HackerNewsArgs) -> DaedraResult<PlannedRequest> {
+        args.validate()?;
+        Ok(self.handler.plan_search_hackernews(args))
+    }
+
+    /// The request [`search_stackoverflow`](Self::search_stackoverflow) would send
+    pub fn plan_search_stackoverflow(
+        &self,
+        args: &StackOverflowArgs,
+    ) -> DaedraResult<PlannedRequest> {
+        args.validate()?;
+        Ok(self.handler.plan_search_stackoverflow(args))
+    }
+
+    /// The first request [`get_transcript`](Self::get_transcript) would send
+    pub fn plan_get_transcript(&self, args: &TranscriptArgs) -> DaedraResult<PlannedRequest> {
+        self.handler.plan_get_transcript(args)
+    }
+
+    /// The request [`check_url`](Self::check_url) would send first
+    pub fn plan_check_url(&self, args: &CheckUrlArgs) -> DaedraResult<PlannedRequest> {
+        self.handler.plan_check_url(args)
+    }
+
+    /// What [`visit_page`](Self::visit_page) would do, without fetching
+    pub fn plan_visit_page(&self, args: &VisitPageArgs) -> DaedraResult<FetchPlan> {
+        if !fetch::is_valid_url(&args.url) {
+            return Err(DaedraError::InvalidArguments(
+                "Invalid URL: must be HTTP or HTTPS".to_string(),
+            ));
+        }
+        Ok(self.handler.plan_fetch(args))
+    }
+
+    /// What [`crawl_site`](Self::crawl_site) would do, without crawling
+    pub fn plan_crawl(&self, args: &CrawlArgs) -> DaedraResult<CrawlPlan> {
+        tools::crawl::plan_crawl(args)
+    }
+
+    /// Tools this client exposes, as advertised over MCP
+    pub fn list_tools(&self) -> Vec<McpTool> {
+        self.handler.list_tools()
+    }
+
+    /// Circuit breaker status of every search backend
+    pub fn backend_status(&self) -> Vec<tools::BackendStatus> {
+        self.handler.backend_status()
+    }
+
+    /// Get the cache statistics
+    pub fn cache_stats(&self) -> CacheStats {
+        self.handler.cache().stats()
+    }
+
+    /// Clear the cache
+    pub async fn clear_cache(&self) {
+        self.handler.cache().clear().await;
+    }
+
+    /// Drop the cached pages and searches `pattern` matches (see
+    /// [`SearchCache::invalidate_matching`](crate::SearchCache::invalidate_matching))
+    pub async fn invalidate_cache(&self, pattern: &str) -> InvalidatedEntries {
+        self.handler.cache().invalidate_matching(pattern).await
+    }
+
+    /// The underlying handler, for raw JSON-RPC access
+    pub fn handler(&self) -> &DaedraHandler {
+        &self.handler
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_list_tools() {
+        let client = DaedraClient::with_defaults().unwrap();
+        let names: Vec<String> = client.list_tools().into_iter().map(|t| t.name).collect();
+        assert!(names.contains(&"web_search".to_string()));
+        assert!(names.contains(&"visit_page".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_search_uses_shared_cache() {
+        let client = DaedraClient::with_defaults().unwrap();
+        let args = SearchArgs {
+            query: "client-cache-test-query".to_string(),
+            options: None,
+        };
+        let options = args.options.clone().unwrap_or_default();
+        let cached = SearchResponse::new(args.query.clone(), vec![], &options);
+        client
+            .handler()
+            .cache()
+            .set_search(&args.query, &options.region, &options.safe_search.to_string(), cached)
+            .await;
+
+        let plan = client.plan_search(&args);
+        assert!(plan.cached && plan.backends.is_empty());
+
+        let response = client.clone().search(args).await.unwrap();
+        assert_eq!(response.metadata.query, "client-cache-test-query");
+    }
+
+    #[tokio::test]
+    async fn test_visit_page_rejects_non_http_url() {
+        let client = DaedraClient::with_defaults().unwrap();
+        let err = client
+            .visit_page(VisitPageArgs {
+                url: "file:///etc/passwd".to_string(),
+                selector: None,
+                include_images: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DaedraError::InvalidArguments(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_visit_page_is_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Hello</title></head><body><p>Hello from the page.</p></body></html>",
+                "text/html",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DaedraClient::with_defaults().unwrap();
+        let args = VisitPageArgs {
+            url: format!("{}/page", server.uri()),
+            selector: None,
+            include_images: false,
+            ..Default::default()
+        };
+        let plan = client.plan_visit_page(&args).unwrap();
+        assert!(!plan.cached);
+        assert_eq!(plan.request.unwrap().url, args.url);
+
+        let first = client.visit_page(args.clone()).await.unwrap();
+        assert!(client.plan_visit_page(&args).unwrap().cached);
+        let second = client.visit_page(args).await.unwrap();
+        assert_eq!(first.content, second.content);
+    }
+}
@@ -0,0 +1,316 @@
+//! Registrable domains, for grouping results by site.
+//!
+//! A host alone is a poor key for "the same site": `blog.example.co.uk` and
+//! `www.example.co.uk` belong together, while `alice.github.io` and
+//! `bob.github.io` do not. The [Public Suffix List] says where the part a
+//! registrant controls begins, so [`registered_domain`] maps both of the
+//! first pair to `example.co.uk` and keeps the second pair apart.
+//!
+//! Search responses expose it as `metadata.registered_domain`, and
+//! [`SearchOptions::max_per_domain`](crate::types::SearchOptions::max_per_domain)
+//! caps results per registrable domain.
+//!
+//! The list is compiled in from `data/public_suffix_list.dat` (MPL-2.0).
+//!
+//! Internationalized hostnames travel as punycode (`xn--bcher-kva.de`), which
+//! is what URLs keep; [`display_host`] turns them back into Unicode
+//! (`bücher.de`) for people to read, unless [`is_homograph_risk`] finds the
+//! name imitating another, as `pаypal.com` with a Cyrillic `а` does.
+//!
+//! ```
+//! use daedra::domains;
+//!
+//! assert_eq!(domains::registered_domain("blog.example.co.uk").as_deref(), Some("example.co.uk"));
+//! assert_eq!(domains::site_key("https://www.example.co.uk/about"), "example.co.uk");
+//! assert_eq!(domains::site_key("http://127.0.0.1:8080/"), "127.0.0.1");
+//! assert_eq!(domains::display_host("xn--bcher-kva.de"), "bücher.de");
+//! assert!(domains::is_homograph_risk("xn--pypal-4ve.com"));
+//! ```
+//!
+//! [Public Suffix List]: https://publicsuffix.org
+
+use crate::types::SearchResult;
+use lazy_static::lazy_static;
+use publicsuffix::{List, Psl};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Cyrillic letters drawn like Latin ones (`а`, `е`, `о`, `р`, `с`, ...)
+const CYRILLIC_LOOKALIKES: &str = "аеорсухіјѕԁһӏԛԝ";
+
+/// Greek letters drawn like Latin ones
+const GREEK_LOOKALIKES: &str = "οαικνρτυχ";
+
+/// Scripts that share letter shapes with each other, for homograph checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+}
+
+lazy_static! {
+    static ref SUFFIXES: List = include_str!("../data/public_suffix_list.dat")
+        .parse()
+        .expect("bundled public suffix list parses");
+}
+
+/// The registrable domain of `host`: its public suffix plus one label
+/// (`blog.example.co.uk` → `example.co.uk`). `None` for IP addresses,
+/// single-label hosts such as `localhost`, and hosts that are themselves a
+/// public suffix.
+pub fn registered_domain(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    let unbracketed = host.trim_start_matches('[').trim_end_matches(']');
+    if !host.contains('.') || unbracketed.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let domain = SUFFIXES.domain(host.as_bytes())?;
+    std::str::from_utf8(domain.as_bytes()).ok().map(str::to_string)
+}
+
+/// The registrable domain of `url`'s host (see [`registered_domain`])
+pub fn registered_domain_of(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    registered_domain(parsed.host_str()?)
+}
+
+/// Key under which results count as coming from the same site: the
+/// registrable domain of `url`, else its lowercased host (IP addresses,
+/// `localhost`), else `url` itself.
+pub fn site_key(url: &str) -> String {
+    if let Some(domain) = registered_domain_of(url) {
+        return domain;
+    }
+    url::Url::parse(url.trim())
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_lowercase))
+        .unwrap_or_else(|| url.trim().to_lowercase())
+}
+
+/// `host` for display: punycode labels decoded to Unicode
+/// (`xn--bcher-kva.de` → `bücher.de`), unless the name is a homograph risk
+/// (see [`is_homograph_risk`]) or does not decode, in which case it stays in
+/// ASCII.
+pub fn display_host(host: &str) -> String {
+    let host = host.trim().to_lowercase();
+    if !has_punycode(&host) {
+        return host;
+    }
+    match idna::domain_to_unicode(&host) {
+        (unicode, Ok(())) if !homograph_in(&unicode) => unicode,
+        _ => host,
+    }
+}
+
+/// Host of `url` for display (see [`display_host`])
+pub fn display_host_of(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url.trim()).ok()?;
+    Some(display_host(parsed.host_str()?))
+}
+
+/// Whether `host`, in punycode or Unicode, could pass for a different name:
+/// a label mixing Latin, Greek, Cyrillic or Armenian letters (`pаypal` with
+/// a Cyrillic `а`), or a Greek or Cyrillic label made only of Latin
+/// lookalikes under an ASCII top-level domain (`аррӏе.com`).
+pub fn is_homograph_risk(host: &str) -> bool {
+    let host = host.trim().to_lowercase();
+    if has_punycode(&host) {
+        let (unicode, decoded) = idna::domain_to_unicode(&host);
+        // A name that does not decode cleanly is not shown decoded either
+        return decoded.is_err() || homograph_in(&unicode);
+    }
+    homograph_in(&host)
+}
+
+/// Whether `url`'s host is a homograph risk (see [`is_homograph_risk`])
+pub fn is_homograph_risk_url(url: &str) -> bool {
+    url::Url::parse(url.trim())
+        .ok()
+        .and_then(|u| u.host_str().map(is_homograph_risk))
+        .unwrap_or(false)
+}
+
+/// Fill in `metadata.registered_domain` where a result has none, show
+/// punycode sources in Unicode and flag homograph risks.
+pub fn annotate(results: &mut [SearchResult]) {
+    for result in results {
+        if result.metadata.registered_domain.is_none() {
+            result.metadata.registered_domain = registered_domain_of(&result.url).map(Into::into);
+        }
+        if has_punycode(&result.metadata.source) {
+            result.metadata.source = display_host(&result.metadata.source).into();
+        }
+        if is_homograph_risk_url(&result.url) {
+            result.metadata.homograph_risk = true;
+        }
+    }
+}
+
+/// Keep at most `max` results per site (see [`site_key`]), in order,
+/// returning how many were dropped.
+pub fn limit_per_domain(results: &mut Vec<SearchResult>, max: usize) -> usize {
+    let before = results.len();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    results.retain(|result| {
+        let key = match &result.metadata.registered_domain {
+            Some(domain) => domain.to_string(),
+            None => site_key(&result.url),
+        };
+        let count = counts.entry(key).or_default();
+        *count += 1;
+        *count <= max
+    });
+    before - results.len()
+}
+
+fn has_punycode(host: &str) -> bool {
+    host.split('.').any(|label| label.starts_with("xn--"))
+}
+
+/// [`is_homograph_risk`] for a host already in Unicode
+fn homograph_in(host: &str) -> bool {
+    if host.is_ascii() {
+        return false;
+    }
+    let labels: Vec<&str> = host.trim_end_matches('.').split('.').collect();
+    let ascii_tld = labels.last().is_some_and(|tld| tld.is_ascii());
+    labels.iter().any(|label| {
+        let scripts: Vec<Script> = label.chars().filter_map(script).collect();
+        let first = scripts.first().copied();
+        if scripts.iter().any(|s| Some(*s) != first) {
+            return true;
+        }
+        let lookalikes = match first {
+            Some(Script::Cyrillic) => CYRILLIC_LOOKALIKES,
+            Some(Script::Greek) => GREEK_LOOKALIKES,
+            _ => return false,
+        };
+        ascii_tld && label.chars().all(|c| !c.is_alphabetic() || lookalikes.contains(c))
+    })
+}
+
+/// Which of the confusable scripts `c` is a letter of
+fn script(c: char) -> Option<Script> {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}' => {
+            Some(Script::Latin)
+        },
+        '\u{370}'..='\u{3ff}' | '\u{1f00}'..='\u{1fff}' => Some(Script::Greek),
+        '\u{400}'..='\u{52f}' | '\u{1c80}'..='\u{1c8f}' | '\u{2de0}'..='\u{2dff}' => {
+            Some(Script::Cyrillic)
+        },
+        '\u{a640}'..='\u{a69f}' => Some(Script::Cyrillic),
+        '\u{530}'..='\u{58f}' => Some(Script::Armenian),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ResultMetadata;
+
+    fn result(url: &str) -> SearchResult {
+        SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            metadata: ResultMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_registered_domain_uses_public_suffixes() {
+        let of = |host: &str| registered_domain(host);
+        assert_eq!(of("blog.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(of("WWW.Example.co.uk.").as_deref(), Some("example.co.uk"));
+        assert_eq!(of("docs.rust-lang.org").as_deref(), Some("rust-lang.org"));
+        assert_eq!(of("alice.github.io").as_deref(), Some("alice.github.io"));
+        assert_eq!(of("co.uk"), None);
+        assert_eq!(of("localhost"), None);
+        assert_eq!(of("192.168.1.10"), None);
+        assert_eq!(of("[::1]"), None);
+    }
+
+    #[test]
+    fn test_registered_domain_of_punycode_suffixes() {
+        // `公司.hk` is a public suffix, written in Unicode in the list
+        assert_eq!(
+            registered_domain("shop.foo.xn--55qx5d.hk").as_deref(),
+            Some("foo.xn--55qx5d.hk")
+        );
+    }
+
+    #[test]
+    fn test_display_host_decodes_safe_punycode() {
+        assert_eq!(display_host("xn--bcher-kva.de"), "bücher.de");
+        assert_eq!(display_host("XN--E1AFMKFD.xn--p1ai"), "пример.рф");
+        assert_eq!(display_host("docs.rs"), "docs.rs");
+        assert_eq!(display_host("xn--pypal-4ve.com"), "xn--pypal-4ve.com");
+        assert_eq!(display_host("xn--80ak6aa92e.com"), "xn--80ak6aa92e.com");
+        assert_eq!(display_host_of("https://xn--bcher-kva.de/a").as_deref(), Some("bücher.de"));
+    }
+
+    #[test]
+    fn test_homograph_risk() {
+        assert!(is_homograph_risk("pаypal.com"));
+        assert!(is_homograph_risk("xn--pypal-4ve.com"));
+        assert!(is_homograph_risk("аррӏе.com"));
+        assert!(!is_homograph_risk("пример.рф"));
+        assert!(!is_homograph_risk("сор.рф"));
+        assert!(!is_homograph_risk("bücher.de"));
+        assert!(!is_homograph_risk("例子.com"));
+        assert!(!is_homograph_risk("paypal.com"));
+        assert!(is_homograph_risk_url("https://xn--pypal-4ve.com/login"));
+        assert!(!is_homograph_risk_url("not a url"));
+    }
+
+    #[test]
+    fn test_annotate_shows_unicode_sources_and_flags_homographs() {
+        let mut results = vec![
+            result("https://xn--bcher-kva.de/"),
+            result("https://xn--pypal-4ve.com/login"),
+        ];
+        results[0].metadata.source = "xn--bcher-kva.de".into();
+        results[1].metadata.source = "xn--pypal-4ve.com".into();
+        annotate(&mut results);
+        assert_eq!(results[0].metadata.source, "bücher.de");
+        assert!(!results[0].metadata.homograph_risk);
+        assert_eq!(results[1].metadata.source, "xn--pypal-4ve.com");
+        assert!(results[1].metadata.homograph_risk);
+    }
+
+    #[test]
+    fn test_site_key_falls_back_to_host() {
+        assert_eq!(site_key("https://blog.example.co.uk/post"), "example.co.uk");
+        assert_eq!(site_key("http://LOCALHOST:3000/x"), "localhost");
+        assert_eq!(site_key("not a url"), "not a url");
+        assert_eq!(registered_domain_of("not a url"), None);
+    }
+
+    #[test]
+    fn test_limit_per_domain_groups_subdomains() {
+        let mut results = vec![
+            result("https://www.example.co.uk/a"),
+            result("https://blog.example.co.uk/b"),
+            result("https://alice.github.io/"),
+            result("https://example.co.uk/c"),
+            result("https://bob.github.io/"),
+        ];
+        annotate(&mut results);
+        assert_eq!(results[1].metadata.registered_domain.as_deref(), Some("example.co.uk"));
+        assert_eq!(limit_per_domain(&mut results, 2), 1);
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.example.co.uk/a",
+                "https://blog.example.co.uk/b",
+                "https://alice.github.io/",
+                "https://bob.github.io/"
+            ]
+        );
+    }
+}
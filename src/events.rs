@@ -0,0 +1,242 @@
+//! Notifications the server pushes to connected clients.
+//!
+//! Parts of the server publish [`ServerNotification`]s on an [`EventBus`]:
+//! cache invalidations, changes to the tool list, finished tool calls, health
+//! changes and log messages. The SSE transport forwards each one as a JSON-RPC notification in
+//! a `message` event, to every open session or, when it was published while a
+//! session's request ran (see [`scope`]), to that session alone. STDIO clients
+//! only get responses, so nothing subscribes there and publishing is a no-op.
+//!
+//! Embedders publish their own notifications through
+//! [`DaedraServer::events`](crate::DaedraServer::events), e.g.
+//! [`ServerNotification::ToolsListChanged`] after changing what is on offer.
+
+use crate::watchdog::HealthState;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::future::Future;
+use tokio::sync::broadcast;
+
+/// Notifications a slow subscriber may fall behind by before it misses some
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Severity of a [`ServerNotification::Log`] message, as MCP names them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Detail for debugging
+    Debug,
+    /// Normal operation
+    Info,
+    /// Something went wrong but the server carried on
+    Warning,
+    /// A request failed
+    Error,
+}
+
+/// Something the server tells clients without being asked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerNotification {
+    /// Cached searches and pages were dropped; counts are from before
+    CacheInvalidated {
+        /// Search responses dropped
+        search_entries: u64,
+        /// Pages dropped
+        page_entries: u64,
+    },
+
+    /// The tools on offer changed; clients should call `tools/list` again
+    ToolsListChanged,
+
+    /// A tool call finished
+    JobCompleted {
+        /// JSON-RPC id of the `tools/call` request
+        request_id: Option<Value>,
+        /// Tool that ran
+        tool: String,
+        /// Whether it returned a result rather than an error
+        success: bool,
+        /// Wall-clock time of the call
+        duration_ms: u64,
+    },
+
+    /// A log message for the client
+    Log {
+        /// Severity
+        level: LogLevel,
+        /// Part of the server that logged it
+        logger: String,
+        /// The message: a string or any JSON value
+        data: Value,
+    },
+
+    /// The [watchdog](crate::watchdog) judged the server's health changed
+    HealthChanged {
+        /// New state
+        state: HealthState,
+        /// What made the server unhealthy; `None` when healthy
+        reason: Option<String>,
+    },
+}
+
+impl ServerNotification {
+    /// JSON-RPC method of the notification
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::CacheInvalidated { .. } => "notifications/daedra/cache_invalidated",
+            Self::ToolsListChanged => "notifications/tools/list_changed",
+            Self::JobCompleted { .. } => "notifications/daedra/job_completed",
+            Self::Log { .. } => "notifications/message",
+            Self::HealthChanged { .. } => "notifications/daedra/health_changed",
+        }
+    }
+
+    /// JSON-RPC params of the notification
+    pub fn params(&self) -> Option<Value> {
+        match self {
+            Self::CacheInvalidated {
+                search_entries,
+                page_entries,
+            } => Some(json!({ "search_entries": search_entries, "page_entries": page_entries })),
+            Self::ToolsListChanged => None,
+            Self::JobCompleted {
+                request_id,
+                tool,
+                success,
+                duration_ms,
+            } => Some(json!({
+                "request_id": request_id,
+                "tool": tool,
+                "success": success,
+                "duration_ms": duration_ms,
+            })),
+            Self::Log {
+                level,
+                logger,
+                data,
+            } => Some(json!({ "level": level, "logger": logger, "data": data })),
+            Self::HealthChanged { state, reason } => {
+                Some(json!({ "state": state, "reason": reason }))
+            },
+        }
+    }
+
+    /// The notification as a JSON-RPC message
+    pub fn to_json_rpc(&self) -> Value {
+        let mut message = json!({ "jsonrpc": "2.0", "method": self.method() });
+        if let Some(params) = self.params() {
+            message["params"] = params;
+        }
+        message
+    }
+}
+
+/// A published notification and who it is for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerEvent {
+    /// SSE session the notification is for; `None` for every session
+    pub session: Option<String>,
+
+    /// The notification
+    pub notification: ServerNotification,
+}
+
+/// Fan-out of [`ServerNotification`]s to the transports; cheap to clone.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ServerEvent>,
+}
+
+impl EventBus {
+    /// A bus without subscribers
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_BUS_CAPACITY).0,
+        }
+    }
+
+    /// Publish `notification` to the session of the current [`scope`], or to
+    /// every session outside of one.
+    pub fn publish(&self, notification: ServerNotification) {
+        self.send(current_session(), notification);
+    }
+
+    /// Publish `notification` to every session, even inside a [`scope`].
+    pub fn broadcast(&self, notification: ServerNotification) {
+        self.send(None, notification);
+    }
+
+    /// Receive everything published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+
+    fn send(&self, session: Option<String>, notification: ServerNotification) {
+        // No subscribers (STDIO, or no SSE server yet) is not an error
+        let _ = self.sender.send(ServerEvent {
+            session,
+            notification,
+        });
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+tokio::task_local! {
+    static SESSION: String;
+}
+
+/// Run `future` on behalf of SSE `session`: what it [publishes](EventBus::publish)
+/// goes to that session only.
+pub async fn scope<F: Future>(session: String, future: F) -> F::Output {
+    SESSION.scope(session, future).await
+}
+
+/// Session of the current [`scope`], if any
+pub fn current_session() -> Option<String> {
+    SESSION.try_with(Clone::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifications_as_json_rpc() {
+        assert_eq!(
+            ServerNotification::ToolsListChanged.to_json_rpc(),
+            json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"})
+        );
+        let log = ServerNotification::Log {
+            level: LogLevel::Warning,
+            logger: "daedra".to_string(),
+            data: json!("all backends failed"),
+        };
+        assert_eq!(log.to_json_rpc()["params"]["level"], "warning");
+        assert_eq!(log.method(), "notifications/message");
+    }
+
+    #[tokio::test]
+    async fn test_publish_is_scoped_to_the_current_session() {
+        let bus = EventBus::new();
+        bus.publish(ServerNotification::ToolsListChanged);
+
+        let mut events = bus.subscribe();
+        scope("abc".to_string(), async {
+            bus.publish(ServerNotification::ToolsListChanged);
+            bus.broadcast(ServerNotification::ToolsListChanged);
+        })
+        .await;
+        bus.publish(ServerNotification::ToolsListChanged);
+
+        let sessions: Vec<Option<String>> =
+            (0..3).map(|_| events.try_recv().unwrap().session).collect();
+        assert_eq!(sessions, [Some("abc".to_string()), None, None]);
+        assert!(events.try_recv().is_err());
+        assert_eq!(current_session(), None);
+    }
+}
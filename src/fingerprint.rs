@@ -0,0 +1,298 @@
+//! Browser profiles scraped pages and search engines see.
+//!
+//! Requests to sites that expect a browser — DuckDuckGo and Bing result pages,
+//! fetched pages — send the `User-Agent`, `Accept`, `Accept-Language` and
+//! client-hint headers of a [`BrowserProfile`]. Each request takes the next
+//! profile of a pool, the built-in one ([`builtin_profiles`]) unless
+//! [`FingerprintConfig::profiles`] replaces it, so no single fingerprint
+//! carries all of a deployment's traffic. [`FingerprintConfig::pin`] sends one
+//! profile only.
+//!
+//! An `Accept-Language` asked for by a search region wins over the profile's.
+//! Under [`ClientProfile::LowFootprint`](crate::privacy::ClientProfile) no
+//! profile is sent: every request keeps that profile's one `User-Agent`.
+//!
+//! Each client rotates through the [`Rotation`] it was built with (see
+//! [`FetchClientBuilder::browser_profiles`]), so handlers in one process can
+//! send different pools; clients sharing one `Rotation` take turns in it.
+//!
+//! [`FetchClientBuilder::browser_profiles`]: crate::tools::fetch::FetchClientBuilder::browser_profiles
+
+use crate::privacy::ClientProfile;
+use crate::types::{DaedraError, DaedraResult};
+use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE, HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `Accept` of Chromium-based browsers navigating to a page
+const CHROMIUM_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+
+/// `Accept` of Firefox and Safari navigating to a page
+const GECKO_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// The headers one browser sends when navigating to a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrowserProfile {
+    /// Name to pin the profile by, such as `chrome-windows`
+    pub name: String,
+
+    /// `User-Agent` header
+    pub user_agent: String,
+
+    /// `Accept` header
+    pub accept: String,
+
+    /// `Accept-Language` header, unless the search region asks for another
+    pub accept_language: String,
+
+    /// Any other headers, such as `Sec-CH-UA` client hints
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl BrowserProfile {
+    /// The profile's headers, with `accept_language` in place of its own when given
+    pub fn headers(&self, accept_language: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let mut insert = |name: HeaderName, value: &str| {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name, value);
+            }
+        };
+        insert(USER_AGENT, &self.user_agent);
+        insert(ACCEPT, &self.accept);
+        insert(ACCEPT_LANGUAGE, accept_language.unwrap_or(&self.accept_language));
+        for (name, value) in &self.headers {
+            if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+                insert(name, value);
+            }
+        }
+        headers
+    }
+}
+
+/// Which browser profiles requests are sent with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FingerprintConfig {
+    /// Profiles to rotate through; empty keeps [`builtin_profiles`]
+    pub profiles: Vec<BrowserProfile>,
+
+    /// Send only the profile with this name
+    pub pin: Option<String>,
+}
+
+/// A pool of profiles handed out in turn
+#[derive(Debug)]
+pub struct Rotation {
+    profiles: Vec<BrowserProfile>,
+    next: AtomicUsize,
+}
+
+impl Rotation {
+    /// The pool `config` describes; an error when its pin names no profile in it
+    pub fn new(config: &FingerprintConfig) -> DaedraResult<Self> {
+        let mut profiles =
+            if config.profiles.is_empty() { builtin_profiles() } else { config.profiles.clone() };
+        if let Some(pin) = &config.pin {
+            let names: Vec<String> = profiles.iter().map(|p| p.name.clone()).collect();
+            profiles.retain(|p| p.name.eq_ignore_ascii_case(pin));
+            if profiles.is_empty() {
+                return Err(DaedraError::InvalidArguments(format!(
+                    "Unknown browser profile {pin}; expected one of {}",
+                    names.join(", ")
+                )));
+            }
+            profiles.truncate(1);
+        }
+        Ok(Self {
+            profiles,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// The profile for the next request
+    pub fn next(&self) -> &BrowserProfile {
+        let i = self.next.fetch_add(1, Ordering::Relaxed);
+        &self.profiles[i % self.profiles.len()]
+    }
+
+    /// The first profile, sent by requests that don't rotate
+    pub fn first(&self) -> &BrowserProfile {
+        &self.profiles[0]
+    }
+
+    /// `User-Agent` for clients to send by default, on requests that don't
+    /// take [`next_headers`](Self::next_headers)
+    pub fn user_agent(&self) -> &str {
+        &self.first().user_agent
+    }
+
+    /// Headers for the next browser-like request: the next profile's, with
+    /// `accept_language` in place of its own when given. Under the
+    /// low-footprint client profile, only that `Accept-Language`.
+    pub fn next_headers(
+        &self,
+        client_profile: ClientProfile,
+        accept_language: Option<&str>,
+    ) -> HeaderMap {
+        if client_profile == ClientProfile::LowFootprint {
+            let mut headers = HeaderMap::new();
+            if let Some(value) = accept_language.and_then(|l| HeaderValue::from_str(l).ok()) {
+                headers.insert(ACCEPT_LANGUAGE, value);
+            }
+            return headers;
+        }
+        self.next().headers(accept_language)
+    }
+}
+
+/// The built-in pool
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            profiles: builtin_profiles(),
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Load profiles from a JSON file holding an array of [`BrowserProfile`]s
+pub fn load_profiles(path: &Path) -> DaedraResult<Vec<BrowserProfile>> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        DaedraError::InvalidArguments(format!(
+            "Cannot read browser profiles {}: {}",
+            path.display(),
+            e
+        ))
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+    serde_json::from_str(&text).map_err(|e| invalid(&e))
+}
+
+/// Recent desktop Chrome, Edge, Firefox and Safari profiles
+pub fn builtin_profiles() -> Vec<BrowserProfile> {
+    let chromium = |name: &str, user_agent: &str, brand: &str, platform: &str| BrowserProfile {
+        name: name.to_string(),
+        user_agent: user_agent.to_string(),
+        accept: CHROMIUM_ACCEPT.to_string(),
+        accept_language: "en-US,en;q=0.9".to_string(),
+        headers: [
+            ("sec-ch-ua", format!("\"{brand}\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\"")),
+            ("sec-ch-ua-mobile", "?0".to_string()),
+            ("sec-ch-ua-platform", format!("\"{platform}\"")),
+            ("sec-fetch-dest", "document".to_string()),
+            ("sec-fetch-mode", "navigate".to_string()),
+            ("sec-fetch-site", "none".to_string()),
+            ("sec-fetch-user", "?1".to_string()),
+            ("upgrade-insecure-requests", "1".to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect(),
+    };
+    let gecko = |name: &str, user_agent: &str, accept_language: &str| BrowserProfile {
+        name: name.to_string(),
+        user_agent: user_agent.to_string(),
+        accept: GECKO_ACCEPT.to_string(),
+        accept_language: accept_language.to_string(),
+        headers: [("upgrade-insecure-requests", "1")]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    };
+    vec![
+        chromium(
+            "chrome-windows",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+            "Google Chrome",
+            "Windows",
+        ),
+        chromium(
+            "chrome-macos",
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+            "Google Chrome",
+            "macOS",
+        ),
+        chromium(
+            "edge-windows",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0",
+            "Microsoft Edge",
+            "Windows",
+        ),
+        gecko(
+            "firefox-windows",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:133.0) Gecko/20100101 Firefox/133.0",
+            "en-US,en;q=0.5",
+        ),
+        gecko(
+            "firefox-linux",
+            "Mozilla/5.0 (X11; Linux x86_64; rv:133.0) Gecko/20100101 Firefox/133.0",
+            "en-US,en;q=0.5",
+        ),
+        gecko(
+            "safari-macos",
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.1 Safari/605.1.15",
+            "en-US,en;q=0.9",
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_cycles_through_the_pool() {
+        let rotation = Rotation::new(&FingerprintConfig::default()).unwrap();
+        let count = builtin_profiles().len();
+        let names: Vec<String> = (0..count + 1).map(|_| rotation.next().name.clone()).collect();
+        assert_eq!(names[0], "chrome-windows");
+        assert_eq!(names[count], names[0]);
+        let mut distinct = names[..count].to_vec();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), count);
+    }
+
+    #[test]
+    fn test_pinned_profile_is_always_sent() {
+        let config = FingerprintConfig {
+            pin: Some("Firefox-Linux".to_string()),
+            ..Default::default()
+        };
+        let rotation = Rotation::new(&config).unwrap();
+        for _ in 0..3 {
+            assert_eq!(rotation.next().name, "firefox-linux");
+        }
+
+        let config = FingerprintConfig {
+            pin: Some("netscape".to_string()),
+            ..Default::default()
+        };
+        let err = Rotation::new(&config).unwrap_err();
+        assert!(err.to_string().contains("chrome-windows"), "{err}");
+    }
+
+    #[test]
+    fn test_profile_headers() {
+        let chrome = &builtin_profiles()[0];
+        let headers = chrome.headers(None);
+        assert_eq!(headers[USER_AGENT], chrome.user_agent.as_str());
+        assert_eq!(headers[ACCEPT_LANGUAGE], "en-US,en;q=0.9");
+        assert_eq!(headers["sec-ch-ua-platform"], "\"Windows\"");
+        let headers = chrome.headers(Some("fr-CH,fr;q=0.9"));
+        assert_eq!(headers[ACCEPT_LANGUAGE], "fr-CH,fr;q=0.9");
+
+        let custom: BrowserProfile = serde_json::from_value(serde_json::json!({
+            "name": "bot",
+            "user_agent": "bot/1.0",
+            "accept": "*/*",
+            "accept_language": "en",
+        }))
+        .unwrap();
+        assert!(custom.headers.is_empty());
+        assert_eq!(custom.headers(None).len(), 3);
+    }
+}
@@ -0,0 +1,171 @@
+//! Shared copies of strings that repeat across results.
+//!
+//! Every result from a site carries the same `source` and `registered_domain`,
+//! and every result from a backend the same backend name, so a long research
+//! session holding thousands of results would otherwise hold thousands of
+//! copies of a few hundred strings. An [`Interned`] string is reference-counted
+//! and drawn from one process-wide pool: equal strings share one allocation,
+//! and cloning one is a counter increment. Deserializing interns too, so
+//! results read back from JSON share their strings the same way.
+//!
+//! The pool holds at most [`MAX_POOL_SIZE`] strings. When it is full, strings
+//! no result holds any more are dropped from it; if it is still full, new
+//! strings are handed out without being pooled.
+//!
+//! ```
+//! use daedra::intern::Interned;
+//!
+//! let a = Interned::from("example.com");
+//! let b = Interned::from(String::from("example.com"));
+//! assert!(Interned::ptr_eq(&a, &b));
+//! assert_eq!(a, "example.com");
+//! ```
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Most distinct strings the pool keeps
+pub const MAX_POOL_SIZE: usize = 16 * 1024;
+
+lazy_static! {
+    static ref POOL: Mutex<HashSet<Arc<str>>> = Mutex::new(HashSet::new());
+}
+
+/// An immutable string shared with every equal [`Interned`] string.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    /// The pooled copy of `text`, added to the pool if it isn't there yet
+    pub fn new(text: &str) -> Self {
+        let mut pool = POOL.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(shared) = pool.get(text) {
+            return Self(shared.clone());
+        }
+        if pool.len() >= MAX_POOL_SIZE {
+            pool.retain(|shared| Arc::strong_count(shared) > 1);
+        }
+        let shared: Arc<str> = Arc::from(text);
+        if pool.len() < MAX_POOL_SIZE {
+            pool.insert(shared.clone());
+        }
+        Self(shared)
+    }
+
+    /// The string itself
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `a` and `b` share one allocation
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl Default for Interned {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Interned {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Interned {
+    fn from(text: String) -> Self {
+        Self::new(&text)
+    }
+}
+
+impl From<Interned> for String {
+    fn from(text: Interned) -> Self {
+        text.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Interned {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Interned {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_strings_share_one_copy() {
+        let a = Interned::new("intern-test.example");
+        let b: Interned = serde_json::from_str("\"intern-test.example\"").unwrap();
+        assert!(Interned::ptr_eq(&a, &b));
+        assert!(!Interned::ptr_eq(&a, &Interned::new("other.example")));
+        assert_eq!(serde_json::to_string(&b).unwrap(), "\"intern-test.example\"");
+        assert_eq!(format!("{a} {a:?}"), "intern-test.example \"intern-test.example\"");
+        assert_eq!(a.len(), 19);
+    }
+}
@@ -28,6 +28,30 @@
 //! }
 //! ```
 //!
+//! ### In-Process Client
+//!
+//! [`DaedraClient`] runs the tools with the server's caching and backend fallback,
+//! without a transport:
+//!
+//! ```rust,no_run
+//! use daedra::{DaedraClient, VisitPageArgs};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let client = DaedraClient::with_defaults()?;
+//!     let page = client
+//!         .visit_page(VisitPageArgs {
+//!             url: "https://www.rust-lang.org".to_string(),
+//!             selector: None,
+//!             include_images: false,
+//!             ..Default::default()
+//!         })
+//!         .await?;
+//!     println!("{}", page.content);
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ### Direct Tool Usage
 //!
 //! ```rust,no_run
@@ -50,27 +74,86 @@
 //! The crate is organized into several modules:
 //!
 //! - [`server`]: MCP server implementation with transport handling
+//! - [`events`]: Notifications pushed to connected SSE clients
+//! - [`client`]: In-process, typed access to the tools without a transport
+//! - `mcp_client`: Minimal MCP client for a running server (`mcp-client` feature)
 //! - [`tools`]: Individual tool implementations (search, fetch, etc.)
 //! - [`types`]: Common types and schemas
 //! - [`cache`]: Caching infrastructure for performance optimization
+//! - [`cache_file`]: Write-behind persistence of the cache to a file
+//! - [`budget`]: Token-budget estimates, truncation and continuation cursors
+//! - [`blocklist`]: Dropping results from spam and content-farm domains
+//! - [`fingerprint`]: Rotating browser profiles (User-Agent, Accept-Language, client hints)
+//! - [`domains`]: Registrable domains from the Public Suffix List, for grouping results by site
+//! - [`memory`]: Memory cap that shrinks the cache and refuses large pages under pressure
+//! - [`privacy`]: Query hashing for logs and cache keys
+//! - [`query`]: Composing queries from operators in each engine's syntax
+//! - [`rewrite`]: Rewriting queries (question stripping, stopwords, synonyms) before search
+//! - [`text`]: Unicode normalization, case folding and stemming for comparing text
+//! - [`regions`]: Supported search region codes and their validation
+//! - [`sandbox`]: Allowed and denied domain globs limiting what tools may search and read
+//! - [`schema`]: Versions of the JSON shape of tool results, and the v1 compatibility shape
+//! - [`telemetry`]: Per-call request, byte, cache-hit, retry and timing counts
+//! - [`timestamp`]: UTC timestamps in one RFC 3339 form, with local-time rendering
+//! - `blocking`: Synchronous wrappers for non-async callers (`blocking` feature)
+//!
+//! ## TLS
+//!
+//! HTTPS goes through rustls with bundled roots (the default `rustls`
+//! feature), so builds need no OpenSSL. The `native-tls` feature uses the
+//! platform's TLS library and trust store instead.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!("daedra needs a TLS backend: enable the `rustls` or `native-tls` feature");
+
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
+pub mod blocklist;
+pub mod budget;
 pub mod cache;
+pub mod cache_file;
+pub mod client;
+pub mod domains;
+pub mod events;
+pub mod fingerprint;
+pub mod intern;
+#[cfg(feature = "mcp-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mcp-client")))]
+pub mod mcp_client;
+pub mod memory;
+pub mod metadata;
+pub mod privacy;
+pub mod query;
+pub mod regions;
+pub mod rewrite;
+pub mod sandbox;
+pub mod schema;
 pub mod server;
+pub mod tables;
+pub mod telemetry;
+pub mod text;
+pub mod timestamp;
 pub mod tools;
 pub mod types;
 /// URL classification rules for categorizing search results by domain pattern.
 pub mod url_classification;
+pub mod watchdog;
 
 // Re-export commonly used items at crate root
 pub use cache::SearchCache;
-pub use server::{DaedraServer, ServerConfig, TransportType};
+pub use client::DaedraClient;
+pub use server::{
+    BoundServer, DaedraServer, HttpTransportConfig, ServerConfig, ServerHandle, TransportType,
+};
+pub use timestamp::Timestamp;
 pub use types::{
     ContentType, DaedraError, DaedraResult, SafeSearchLevel, SearchArgs, SearchOptions,
-    SearchResponse, SearchResult, VisitPageArgs,
+    SearchResponse, SearchResult, TimeRange, VisitPageArgs,
 };
 
 /// Crate version
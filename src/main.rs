@@ -2,18 +2,29 @@
 //!
 //! A command-line interface for the Daedra MCP server.
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use daedra::{
     DaedraResult, SERVER_NAME, VERSION,
+    blocklist::BlocklistConfig,
     cache::CacheConfig,
-    server::{DaedraServer, ServerConfig, TransportType},
-    tools::{crawl_site, fetch, search},
+    cache_file::{CacheFileConfig, FsyncPolicy},
+    fingerprint::{self, FingerprintConfig},
+    memory::MemoryConfig,
+    privacy::{ClientProfile, PrivacyConfig},
+    regions::REGIONS,
+    rewrite::{QueryRewriter, QuestionToKeywords, StopwordPruner, SynonymExpander},
+    schema::SchemaVersion,
+    server::{DaedraServer, HttpTransportConfig, JsonFormat, ServerConfig, TransportType},
+    telemetry::SlowRequestConfig,
+    tools::{CircuitBreakerConfig, cookies::CookieJar, crawl_site, fetch, search},
     types::{
-        CrawlArgs, CrawlResult, DaedraError, PageContent, SafeSearchLevel, SearchArgs,
-        SearchOptions, SearchResult, VisitPageArgs,
+        CrawlArgs, CrawlResult, DaedraError, PageContent, PageFormat, SafeSearchLevel, SearchArgs,
+        SearchOptions, SearchResult, SearchStrategy, TimeRange, VisitPageArgs,
     },
+    watchdog::WatchdogConfig,
 };
+use std::sync::Arc;
 use std::time::Duration;
 use tracing_subscriber::{EnvFilter, fmt};
 
@@ -47,6 +58,10 @@ struct Cli {
     #[arg(long, global = true)]
     no_color: bool,
 
+    /// Show timestamps in the local time zone instead of UTC (pretty output only)
+    #[arg(long, global = true, env = "DAEDRA_LOCAL_TIME")]
+    local_time: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -63,31 +78,418 @@ enum OutputFormat {
     JsonCompact,
 }
 
-/// Available commands
-#[derive(Subcommand, Debug)]
-enum Commands {
-    /// Start the MCP server
-    Serve {
-        /// Transport type to use
-        #[arg(short, long, default_value = "stdio")]
-        transport: TransportOption,
+/// Options for `daedra serve`
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Transport type to use
+    #[arg(short, long, default_value = "stdio")]
+    transport: TransportOption,
 
-        /// Port for SSE transport (only used with --transport sse)
-        #[arg(short, long, default_value = "3000")]
-        port: u16,
+    /// Port for SSE transport (only used with --transport sse; 0 picks a free port)
+    #[arg(short, long, default_value = "3000")]
+    port: u16,
 
-        /// Host to bind to for SSE transport
-        #[arg(long, default_value = "127.0.0.1")]
-        host: String,
+    /// Host to bind to for SSE transport: an IPv4/IPv6 address (`::` for dual-stack)
+    /// or a hostname such as `localhost`, which listens on every address it resolves to
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
 
-        /// Disable result caching
-        #[arg(long)]
-        no_cache: bool,
+    /// Disable result caching
+    #[arg(long)]
+    no_cache: bool,
 
-        /// Cache TTL in seconds
-        #[arg(long, default_value = "300")]
-        cache_ttl: u64,
-    },
+    /// Cache TTL in seconds
+    #[arg(long, default_value = "300")]
+    cache_ttl: u64,
+
+    /// Seconds a dead link or bot-protected page is answered with its
+    /// earlier error instead of fetched again (0 disables)
+    #[arg(long, env = "DAEDRA_CACHE_FAILURE_SECS", default_value = "60")]
+    cache_failure_secs: u64,
+
+    /// Bound each cache by the estimated bytes of its entries instead of
+    /// their number
+    #[arg(long, env = "DAEDRA_CACHE_MAX_BYTES")]
+    cache_max_bytes: Option<u64>,
+
+    /// Write cached results and pages behind to this file and load them on startup
+    #[arg(long, env = "DAEDRA_CACHE_FILE")]
+    cache_file: Option<std::path::PathBuf>,
+
+    /// When to sync the cache file to disk
+    #[arg(long, env = "DAEDRA_CACHE_FSYNC", default_value = "batch")]
+    cache_fsync: FsyncOption,
+
+    /// Seconds after expiring that a cached page is revalidated with its
+    /// ETag/Last-Modified instead of fetched again (0 disables)
+    #[arg(long, env = "DAEDRA_CACHE_REVALIDATE_SECS", default_value = "86400")]
+    cache_revalidate_secs: u64,
+
+    /// Try these backends one at a time instead of merging all of them
+    /// (comma-separated, e.g. serper,bing,duckduckgo)
+    #[arg(long, env = "DAEDRA_SEARCH_CHAIN", value_delimiter = ',')]
+    search_chain: Vec<String>,
+
+    /// How to combine backends for requests that don't choose
+    /// (default: merge, or fallback when --search-chain is set)
+    #[arg(long, env = "DAEDRA_SEARCH_STRATEGY")]
+    search_strategy: Option<StrategyOption>,
+
+    /// SearXNG instance to query ahead of the built-in backends
+    #[arg(long, env = "SEARXNG_URL")]
+    searxng_url: Option<String>,
+
+    /// Read the Serper.dev API key from this file (overrides SERPER_API_KEY)
+    #[arg(long, env = "SERPER_API_KEY_FILE")]
+    serper_key_file: Option<std::path::PathBuf>,
+
+    /// Consecutive failures before a backend's circuit opens
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    circuit_threshold: u32,
+
+    /// Seconds an open circuit skips its backend before probing again
+    #[arg(long, default_value = "30")]
+    circuit_cooldown: u64,
+
+    /// Watch for runtime stalls and failure storms, logging health changes and
+    /// reporting them on /health and to SSE clients
+    #[arg(long, env = "DAEDRA_WATCHDOG")]
+    watchdog: bool,
+
+    /// Failed tool calls within a minute that the watchdog counts as a failure storm
+    #[arg(long, default_value = "20", value_parser = clap::value_parser!(u64).range(1..))]
+    watchdog_failures: u64,
+
+    /// Restart the SSE listener after this many seconds unhealthy (implies --watchdog)
+    #[arg(
+        long,
+        env = "DAEDRA_WATCHDOG_RESTART_AFTER",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    watchdog_restart_after: Option<u64>,
+
+    /// Largest accepted request in bytes (HTTP body for SSE, line length for STDIO)
+    #[arg(long, default_value = "1048576", value_parser = clap::value_parser!(u64).range(1..))]
+    max_request_bytes: u64,
+
+    /// Largest page body fetched, in bytes; downloads stop as soon as they pass it
+    #[arg(
+        long,
+        env = "DAEDRA_MAX_PAGE_BYTES",
+        default_value = "10485760",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_page_bytes: u64,
+
+    /// Reject requests that aren't exactly one JSON value, instead of
+    /// tolerating a byte order mark, trailing commas and trailing data
+    #[arg(long, env = "DAEDRA_STRICT_JSON")]
+    strict_json: bool,
+
+    /// Most pages parsed and converted to Markdown at once, on blocking
+    /// threads (default: one per CPU core)
+    #[arg(
+        long,
+        env = "DAEDRA_EXTRACTION_THREADS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    extraction_threads: Option<u64>,
+
+    /// Memory cap in MiB: past it the cache shrinks and pages over 1 MiB are refused
+    #[arg(
+        long,
+        env = "DAEDRA_MEMORY_CAP_MB",
+        value_name = "MIB",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    memory_cap: Option<u64>,
+
+    /// Log tool calls slower than this many milliseconds as warnings, with
+    /// their queue, cache, network, parse and serialize times
+    #[arg(
+        long,
+        env = "DAEDRA_SLOW_REQUEST_MS",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    slow_request_ms: Option<u64>,
+
+    /// Slow-call threshold for one tool, as TOOL=MS, overriding
+    /// --slow-request-ms (comma-separated, e.g. deep_research=60000)
+    #[arg(
+        long = "slow-tool",
+        env = "DAEDRA_SLOW_TOOLS",
+        value_delimiter = ',',
+        value_name = "TOOL=MS",
+        value_parser = parse_slow_tool
+    )]
+    slow_tools: Vec<(String, u64)>,
+
+    /// Layout of the JSON tools return: indented, or compact to save tokens
+    #[arg(long, env = "DAEDRA_JSON_FORMAT", default_value = "pretty")]
+    json_format: JsonFormatOption,
+
+    /// Shape of the JSON search responses and pages tools return, for clients
+    /// that don't ask in `initialize`: 1 for the first release's fields only
+    #[arg(long, env = "DAEDRA_SCHEMA_VERSION", default_value_t = SchemaVersion::CURRENT)]
+    schema_version: SchemaVersion,
+
+    /// Seconds before an SSE-transport HTTP request is answered with 408
+    #[arg(long, default_value = "120", value_parser = clap::value_parser!(u64).range(1..))]
+    request_timeout: u64,
+
+    /// Seconds between keep-alive comments on open SSE streams
+    #[arg(long, default_value = "15", value_parser = clap::value_parser!(u64).range(1..))]
+    sse_keep_alive: u64,
+
+    /// Most HTTP connections the SSE transport keeps open at once
+    #[arg(long, default_value = "256", value_parser = clap::value_parser!(u64).range(1..))]
+    max_connections: u64,
+
+    /// Seconds an SSE-transport connection may sit without traffic before it is closed
+    #[arg(long, default_value = "300", value_parser = clap::value_parser!(u64).range(1..))]
+    idle_timeout: u64,
+
+    /// Seconds a write to a client that stopped reading may block before the
+    /// connection is closed
+    #[arg(long, default_value = "30", value_parser = clap::value_parser!(u64).range(1..))]
+    write_timeout: u64,
+
+    /// Extra hostnames accepted in SSE Host/Origin headers, e.g. behind a reverse
+    /// proxy (comma-separated; `*` disables the check)
+    #[arg(long = "allowed-host", env = "DAEDRA_ALLOWED_HOSTS", value_delimiter = ',')]
+    allowed_hosts: Vec<String>,
+
+    /// Answer tool calls with the requests they would make instead of sending them
+    #[arg(long, env = "DAEDRA_DRY_RUN")]
+    dry_run: bool,
+
+    /// Drop results from a curated list of content farms and scraper sites
+    #[arg(long, env = "DAEDRA_SPAM_BLOCKLIST")]
+    spam_blocklist: bool,
+
+    /// Drop results from the domains in these lists, in hosts, adblock or
+    /// one-domain-per-line format (comma-separated)
+    #[arg(long = "blocklist-file", env = "DAEDRA_BLOCKLIST_FILES", value_delimiter = ',')]
+    blocklist_files: Vec<std::path::PathBuf>,
+
+    /// Drop results from these domains and their subdomains (comma-separated)
+    #[arg(long = "block-domain", env = "DAEDRA_BLOCKED_DOMAINS", value_delimiter = ',')]
+    blocked_domains: Vec<String>,
+
+    /// Lowest safe-search level for every search; clients asking for less get this
+    #[arg(long, env = "DAEDRA_MIN_SAFE_SEARCH", default_value = "off")]
+    min_safe_search: SafeSearchOption,
+
+    /// Rewrite every query before searching, in this order (comma-separated)
+    #[arg(long = "rewrite-query", env = "DAEDRA_REWRITE_QUERY", value_delimiter = ',')]
+    query_rewriters: Vec<RewriterOption>,
+
+    /// Expand words into OR groups of their synonyms, one comma-separated group
+    /// per line of this file; runs after the `--rewrite-query` steps
+    #[arg(long, env = "DAEDRA_SYNONYMS_FILE")]
+    synonyms_file: Option<std::path::PathBuf>,
+
+    /// Log a hash of each query instead of its text, and keep raw requests
+    /// out of debug logs
+    #[arg(long, env = "DAEDRA_HASH_LOGGED_QUERIES")]
+    hash_logged_queries: bool,
+
+    /// Key cached searches by query hash instead of query text
+    #[arg(long, env = "DAEDRA_HASH_CACHE_KEYS")]
+    hash_cache_keys: bool,
+
+    /// Secret mixed into query hashes, so they can't be checked against guesses
+    #[arg(long, env = "DAEDRA_QUERY_HASH_SALT", hide_env_values = true)]
+    query_hash_salt: Option<String>,
+
+    /// Send one common browser User-Agent, DNT and GPC headers, and no Referer
+    /// from every HTTP client
+    #[arg(long, env = "DAEDRA_LOW_FOOTPRINT")]
+    low_footprint: bool,
+
+    /// Send only this browser profile (such as `chrome-windows` or
+    /// `firefox-linux`) instead of rotating through them
+    #[arg(long, env = "DAEDRA_BROWSER_PROFILE")]
+    browser_profile: Option<String>,
+
+    /// Rotate through the browser profiles in this JSON file instead of the
+    /// built-in ones
+    #[arg(long, env = "DAEDRA_BROWSER_PROFILES_FILE")]
+    browser_profiles_file: Option<std::path::PathBuf>,
+
+    /// Fetch pages even when the site's robots.txt disallows them
+    #[arg(long, env = "DAEDRA_IGNORE_ROBOTS")]
+    ignore_robots: bool,
+
+    /// Keep the cookies sites set in this file, so sessions survive restarts
+    #[arg(long, env = "DAEDRA_COOKIE_FILE")]
+    cookie_file: Option<std::path::PathBuf>,
+
+    /// Offer the cache_invalidate tool, letting clients drop cached entries
+    #[arg(long, env = "DAEDRA_ADMIN_TOOLS")]
+    admin_tools: bool,
+
+    /// Only search results from and pages on hosts matching these globs, such
+    /// as `*.docs.example.com` (comma-separated)
+    #[arg(long = "allow-domain", env = "DAEDRA_ALLOWED_DOMAINS", value_delimiter = ',')]
+    allowed_domains: Vec<String>,
+
+    /// Never search results from or pages on hosts matching these globs, even
+    /// allowed ones (comma-separated)
+    #[arg(long = "deny-domain", env = "DAEDRA_DENIED_DOMAINS", value_delimiter = ',')]
+    denied_domains: Vec<String>,
+
+    /// Send a header with fetches from hosts matching a glob, as
+    /// `GLOB=Name: value` (repeatable; one per line in the environment)
+    #[arg(
+        long = "domain-header",
+        env = "DAEDRA_DOMAIN_HEADERS",
+        value_delimiter = '\n',
+        hide_env_values = true
+    )]
+    domain_headers: Vec<fetch::DomainHeaders>,
+}
+
+impl ServeArgs {
+    /// Build the server configuration from the CLI options.
+    fn server_config(&self) -> DaedraResult<ServerConfig> {
+        let mut config = ServerConfig {
+            cache: CacheConfig {
+                hash_keys: self.hash_cache_keys,
+                max_memory_bytes: self.cache_max_bytes,
+                failure_ttl: Duration::from_secs(self.cache_failure_secs),
+                revalidation_window: Duration::from_secs(self.cache_revalidate_secs),
+                persistence: self.cache_file.clone().map(|path| CacheFileConfig {
+                    fsync: self.cache_fsync.into(),
+                    ..CacheFileConfig::new(path)
+                }),
+                ..build_cache_config(self.no_cache, self.cache_ttl)
+            },
+            verbose: false,
+            search_chain: self
+                .search_chain
+                .iter()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect(),
+            search_strategy: self.search_strategy.map(Into::into),
+            serper_api_key: self.serper_key_file.as_deref().map(read_key_file).transpose()?,
+            circuit_breaker: CircuitBreakerConfig {
+                failure_threshold: self.circuit_threshold,
+                cooldown: Duration::from_secs(self.circuit_cooldown),
+            },
+            watchdog: WatchdogConfig {
+                enabled: self.watchdog || self.watchdog_restart_after.is_some(),
+                failure_threshold: usize::try_from(self.watchdog_failures).unwrap_or(usize::MAX),
+                restart_after: self.watchdog_restart_after.map(Duration::from_secs),
+                ..Default::default()
+            },
+            max_request_bytes: usize::try_from(self.max_request_bytes).unwrap_or(usize::MAX),
+            max_page_bytes: usize::try_from(self.max_page_bytes).unwrap_or(usize::MAX),
+            strict_json: self.strict_json,
+            json_format: self.json_format.into(),
+            schema_version: self.schema_version,
+            request_timeout: Duration::from_secs(self.request_timeout),
+            sse_keep_alive: Duration::from_secs(self.sse_keep_alive),
+            max_connections: usize::try_from(self.max_connections).unwrap_or(usize::MAX),
+            idle_timeout: Duration::from_secs(self.idle_timeout),
+            write_timeout: Duration::from_secs(self.write_timeout),
+            allowed_hosts: self.allowed_hosts.clone(),
+            blocklist: BlocklistConfig {
+                curated: self.spam_blocklist,
+                files: self.blocklist_files.clone(),
+                domains: self.blocked_domains.clone(),
+            },
+            min_safe_search: self.min_safe_search.into(),
+            query_rewriters: self.query_rewriters()?,
+            privacy: PrivacyConfig {
+                hash_logged_queries: self.hash_logged_queries,
+                salt: self.query_hash_salt.clone().filter(|s| !s.is_empty()),
+                client_profile: if self.low_footprint {
+                    ClientProfile::LowFootprint
+                } else {
+                    ClientProfile::Standard
+                },
+            },
+            fingerprint: FingerprintConfig {
+                profiles: match &self.browser_profiles_file {
+                    Some(path) => fingerprint::load_profiles(path)?,
+                    None => Vec::new(),
+                },
+                pin: self.browser_profile.clone().filter(|p| !p.is_empty()),
+            },
+            memory: MemoryConfig {
+                cap: self.memory_cap.map(|mib| mib.saturating_mul(1024 * 1024)),
+                ..Default::default()
+            },
+            slow_requests: SlowRequestConfig {
+                default_threshold: self.slow_request_ms.map(Duration::from_millis),
+                tools: self
+                    .slow_tools
+                    .iter()
+                    .map(|(tool, ms)| (tool.clone(), Duration::from_millis(*ms)))
+                    .collect(),
+            },
+            dry_run: self.dry_run,
+            ignore_robots: self.ignore_robots,
+            allowed_domains: self.allowed_domains.clone(),
+            blocked_domains: self.denied_domains.clone(),
+            domain_headers: self.domain_headers.clone(),
+            cookie_file: self.cookie_file.clone(),
+            admin_tools: self.admin_tools,
+            ..Default::default()
+        };
+        if let Some(threads) = self.extraction_threads {
+            config.extraction_threads = usize::try_from(threads).unwrap_or(usize::MAX);
+        }
+
+        #[cfg(feature = "searxng")]
+        {
+            config.searxng_url = self.searxng_url.clone().filter(|u| !u.is_empty());
+        }
+        #[cfg(not(feature = "searxng"))]
+        if self.searxng_url.is_some() {
+            tracing::warn!("--searxng-url ignored: built without the `searxng` feature");
+        }
+
+        Ok(config)
+    }
+
+    /// The query rewriter chain the CLI options describe.
+    fn query_rewriters(&self) -> DaedraResult<Vec<Arc<dyn QueryRewriter>>> {
+        let mut rewriters: Vec<Arc<dyn QueryRewriter>> = self
+            .query_rewriters
+            .iter()
+            .map(|option| -> Arc<dyn QueryRewriter> {
+                match option {
+                    RewriterOption::QuestionKeywords => Arc::new(QuestionToKeywords),
+                    RewriterOption::Stopwords => Arc::new(StopwordPruner),
+                }
+            })
+            .collect();
+        if let Some(path) = &self.synonyms_file {
+            rewriters.push(Arc::new(SynonymExpander::load_file(path)?));
+        }
+        Ok(rewriters)
+    }
+
+    /// Resolve the transport to run.
+    fn transport_type(&self) -> DaedraResult<TransportType> {
+        Ok(match self.transport {
+            TransportOption::Stdio => TransportType::Stdio,
+            TransportOption::Sse => {
+                TransportType::Http(HttpTransportConfig::new(self.host.clone(), self.port))
+            },
+        })
+    }
+}
+
+/// Available commands
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Start the MCP server
+    Serve(Box<ServeArgs>),
 
     /// Perform a web search
     Search {
@@ -106,9 +508,9 @@ enum Commands {
         #[arg(short, long, default_value = "moderate")]
         safe_search: SafeSearchOption,
 
-        /// Time range filter (d=day, w=week, m=month, y=year)
+        /// Time range filter (d=day, w=week, m=month, y=year, or YYYY-MM-DD..YYYY-MM-DD)
         #[arg(short = 't', long)]
-        time_range: Option<String>,
+        time_range: Option<TimeRange>,
     },
 
     /// Fetch and extract content from a web page
@@ -123,6 +525,26 @@ enum Commands {
         /// Include images in output
         #[arg(long)]
         include_images: bool,
+
+        /// What to extract the content as (json prints the whole page as JSON)
+        #[arg(long, default_value = "markdown")]
+        content_format: PageFormatOption,
+
+        /// Join up to this many pages of a paginated article (at most 10)
+        #[arg(long, value_name = "PAGES")]
+        follow_pagination: Option<usize>,
+
+        /// Read the latest Wayback Machine snapshot of a page that is gone
+        #[arg(long)]
+        allow_archive: bool,
+
+        /// Fetch the page even when the site's robots.txt disallows it
+        #[arg(long)]
+        ignore_robots: bool,
+
+        /// Send the cookies saved in this file, and save the ones the site sets
+        #[arg(long, env = "DAEDRA_COOKIE_FILE")]
+        cookie_file: Option<std::path::PathBuf>,
     },
 
     /// Crawl a website and extract content from all discovered pages
@@ -139,6 +561,9 @@ enum Commands {
         concurrency: usize,
     },
 
+    /// List the supported search region codes
+    Regions,
+
     /// Show server information
     Info,
 
@@ -168,6 +593,96 @@ enum SafeSearchOption {
     Strict,
 }
 
+/// Built-in query rewriters
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RewriterOption {
+    /// Strip question lead-ins ("how do I") and question marks
+    QuestionKeywords,
+    /// Drop common English words
+    Stopwords,
+}
+
+/// When the cache file is synced to disk
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FsyncOption {
+    /// Leave it to the operating system
+    Never,
+    /// Once per batch of writes
+    Batch,
+    /// After every entry
+    Always,
+}
+
+impl From<FsyncOption> for FsyncPolicy {
+    fn from(opt: FsyncOption) -> Self {
+        match opt {
+            FsyncOption::Never => FsyncPolicy::Never,
+            FsyncOption::Batch => FsyncPolicy::Batch,
+            FsyncOption::Always => FsyncPolicy::Always,
+        }
+    }
+}
+
+/// Layouts for the JSON tools return
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum JsonFormatOption {
+    /// Indented
+    Pretty,
+    /// Without whitespace
+    Compact,
+}
+
+impl From<JsonFormatOption> for JsonFormat {
+    fn from(opt: JsonFormatOption) -> Self {
+        match opt {
+            JsonFormatOption::Pretty => JsonFormat::Pretty,
+            JsonFormatOption::Compact => JsonFormat::Compact,
+        }
+    }
+}
+
+/// Content formats for the fetch command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PageFormatOption {
+    /// Markdown converted from the page
+    Markdown,
+    /// Plain text without markup
+    Text,
+    /// The extracted HTML
+    Html,
+    /// The whole page as JSON, content in Markdown
+    Json,
+}
+
+impl From<PageFormatOption> for PageFormat {
+    fn from(opt: PageFormatOption) -> Self {
+        match opt {
+            PageFormatOption::Markdown => PageFormat::Markdown,
+            PageFormatOption::Text => PageFormat::Text,
+            PageFormatOption::Html => PageFormat::Html,
+            PageFormatOption::Json => PageFormat::Json,
+        }
+    }
+}
+
+/// Backend combination strategies
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum StrategyOption {
+    /// First backend with results wins
+    Fallback,
+    /// Query concurrently, deduplicate and interleave
+    Merge,
+}
+
+impl From<StrategyOption> for SearchStrategy {
+    fn from(opt: StrategyOption) -> Self {
+        match opt {
+            StrategyOption::Fallback => SearchStrategy::Fallback,
+            StrategyOption::Merge => SearchStrategy::Merge,
+        }
+    }
+}
+
 impl From<SafeSearchOption> for SafeSearchLevel {
     fn from(opt: SafeSearchOption) -> Self {
         match opt {
@@ -232,19 +747,14 @@ impl Commands {
         verbose: bool,
         quiet: bool,
         no_color: bool,
+        local_time: bool,
     ) -> DaedraResult<()> {
         match self {
-            Commands::Serve {
-                transport,
-                port,
-                host,
-                no_cache,
-                cache_ttl,
-            } => {
-                if should_print_banner(verbose, quiet, format, transport) {
+            Commands::Serve(args) => {
+                if should_print_banner(verbose, quiet, format, args.transport) {
                     print_banner();
                 }
-                run_serve(transport, port, host, no_cache, cache_ttl).await
+                run_serve(*args).await
             },
 
             Commands::Search {
@@ -270,7 +780,29 @@ impl Commands {
                 url,
                 selector,
                 include_images,
-            } => run_fetch(url, selector, include_images, format, no_color).await,
+                content_format,
+                follow_pagination,
+                allow_archive,
+                ignore_robots,
+                cookie_file,
+            } => {
+                let mut client = fetch::FetchClient::new()?.with_ignore_robots(ignore_robots);
+                if let Some(path) = cookie_file {
+                    client = client.with_cookie_jar(CookieJar::open(path)?)?;
+                }
+                let args = VisitPageArgs {
+                    url,
+                    selector,
+                    include_images,
+                    format: content_format.into(),
+                    follow_pagination,
+                    allow_archive,
+                    ..Default::default()
+                };
+                let fetched = run_fetch(&client, args, format, no_color, local_time).await;
+                client.cookie_jar().flush().await;
+                fetched
+            },
 
             Commands::Crawl {
                 url,
@@ -278,6 +810,8 @@ impl Commands {
                 concurrency,
             } => run_crawl(url, max_pages, concurrency, format, no_color).await,
 
+            Commands::Regions => run_regions(format, no_color),
+
             Commands::Info => {
                 run_info(no_color);
                 Ok(())
@@ -492,6 +1026,33 @@ fn format_info(label: &str, value: &str) -> String {
     format!("  {} {}\n", format!("{}:", label).bright_blue(), value)
 }
 
+/// Read an API key from a file, ignoring surrounding whitespace.
+/// Parse a `--slow-tool` value, `TOOL=MS`
+fn parse_slow_tool(value: &str) -> Result<(String, u64), String> {
+    let (tool, ms) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected TOOL=MS, got '{}'", value))?;
+    let tool = tool.trim();
+    if tool.is_empty() {
+        return Err(format!("missing tool name in '{}'", value));
+    }
+    match ms.trim().parse::<u64>() {
+        Ok(ms) if ms > 0 => Ok((tool.to_string(), ms)),
+        _ => Err(format!("'{}' is not a positive number of milliseconds", ms.trim())),
+    }
+}
+
+fn read_key_file(path: &std::path::Path) -> DaedraResult<String> {
+    let key = std::fs::read_to_string(path)?.trim().to_string();
+    if key.is_empty() {
+        return Err(DaedraError::InvalidArguments(format!(
+            "API key file {} is empty",
+            path.display()
+        )));
+    }
+    Ok(key)
+}
+
 fn build_cache_config(no_cache: bool, cache_ttl: u64) -> CacheConfig {
     if no_cache {
         CacheConfig {
@@ -507,40 +1068,25 @@ fn build_cache_config(no_cache: bool, cache_ttl: u64) -> CacheConfig {
     }
 }
 
-fn parse_host_octets(host: &str) -> DaedraResult<[u8; 4]> {
-    let parts: Vec<u8> = host.split('.').filter_map(|s| s.parse().ok()).collect();
-    if parts.len() != 4 {
-        return Err(DaedraError::InvalidArguments(
-            "Invalid host format".to_string(),
-        ));
-    }
-    Ok([parts[0], parts[1], parts[2], parts[3]])
-}
-
-async fn run_serve(
-    transport: TransportOption,
-    port: u16,
-    host: String,
-    no_cache: bool,
-    cache_ttl: u64,
-) -> DaedraResult<()> {
-    let config = ServerConfig {
-        cache: build_cache_config(no_cache, cache_ttl),
-        verbose: false,
-        ..Default::default()
-    };
-
-    let server = DaedraServer::new(config)?;
-
-    let transport_type = match transport {
-        TransportOption::Stdio => TransportType::Stdio,
-        TransportOption::Sse => TransportType::Sse {
-            port,
-            host: parse_host_octets(&host)?,
+async fn run_serve(args: ServeArgs) -> DaedraResult<()> {
+    let server = DaedraServer::new(args.server_config()?)?;
+    match args.transport_type()?.http_config() {
+        None => {
+            server.run(TransportType::Stdio).await?;
+            // The client is gone. Tokio reads stdin on a blocking thread that cannot be
+            // cancelled, so returning through the runtime could hang until stdin closes.
+            std::process::exit(0);
         },
-    };
-
-    server.run(transport_type).await
+        Some(http) => {
+            let bound = server.bind(&http.host, http.port).await?;
+            // Always on stdout, even with --quiet: with --port 0 this is the only
+            // way for a parent process to learn the port.
+            for addr in bound.local_addrs() {
+                println!("Listening on http://{addr}");
+            }
+            bound.serve().await
+        },
+    }
 }
 
 
@@ -602,12 +1148,17 @@ fn format_search_result_pretty(result: &SearchResult, index: usize, no_color: bo
     }
 }
 
-fn format_page_content_pretty(content: &PageContent, no_color: bool) -> String {
+fn format_page_content_pretty(content: &PageContent, no_color: bool, local_time: bool) -> String {
+    let fetched = if local_time {
+        content.timestamp.to_local_string()
+    } else {
+        content.timestamp.to_string()
+    };
     let mut out = format_page_header(&content.title, no_color);
     if no_color {
         out.push_str(&format!(
             "URL: {}\nFetched: {}\nWords: {}\n\n{}\n",
-            content.url, content.timestamp, content.word_count, content.content
+            content.url, fetched, content.word_count, content.content
         ));
         if let Some(links) = &content.links {
             out.push_str(&format!("\nLinks found ({}):\n", links.len()));
@@ -620,7 +1171,7 @@ fn format_page_content_pretty(content: &PageContent, no_color: bool) -> String {
             "URL",
             &content.url.bright_blue().underline().to_string(),
         ));
-        out.push_str(&format_info("Fetched", &content.timestamp));
+        out.push_str(&format_info("Fetched", &fetched));
         out.push_str(&format_info(
             "Words",
             &content.word_count.to_string().green().to_string(),
@@ -649,8 +1200,8 @@ fn print_search_result_pretty(result: &SearchResult, index: usize, no_color: boo
     print!("{}", format_search_result_pretty(result, index, no_color));
 }
 
-fn print_page_content_pretty(content: &PageContent, no_color: bool) {
-    print!("{}", format_page_content_pretty(content, no_color));
+fn print_page_content_pretty(content: &PageContent, no_color: bool, local_time: bool) {
+    print!("{}", format_page_content_pretty(content, no_color, local_time));
 }
 
 fn print_crawl_result_pretty(result: &CrawlResult, no_color: bool) {
@@ -682,7 +1233,7 @@ async fn run_search(
     num_results: usize,
     region: String,
     safe_search: SafeSearchOption,
-    time_range: Option<String>,
+    time_range: Option<TimeRange>,
     format: OutputFormat,
     no_color: bool,
 ) -> DaedraResult<()> {
@@ -693,6 +1244,7 @@ async fn run_search(
             safe_search: safe_search.into(),
             num_results,
             time_range,
+            ..Default::default()
         }),
     };
 
@@ -720,24 +1272,22 @@ async fn run_search(
 
 
 async fn run_fetch(
-    url: String,
-    selector: Option<String>,
-    include_images: bool,
+    client: &fetch::FetchClient,
+    args: VisitPageArgs,
     format: OutputFormat,
     no_color: bool,
+    local_time: bool,
 ) -> DaedraResult<()> {
-    let args = VisitPageArgs {
-        url: url.clone(),
-        selector,
-        include_images,
-    };
-
-    let content = fetch::fetch_page(&args).await?;
+    let content = client.fetch(&args).await?;
 
+    let format = match format {
+        OutputFormat::Pretty if args.format == PageFormat::Json => OutputFormat::Json,
+        format => format,
+    };
     match format {
         OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&content)?),
         OutputFormat::JsonCompact => println!("{}", serde_json::to_string(&content)?),
-        OutputFormat::Pretty => print_page_content_pretty(&content, no_color),
+        OutputFormat::Pretty => print_page_content_pretty(&content, no_color, local_time),
     }
 
     Ok(())
@@ -768,6 +1318,25 @@ async fn run_crawl(
     Ok(())
 }
 
+fn run_regions(format: OutputFormat, no_color: bool) -> DaedraResult<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(REGIONS)?),
+        OutputFormat::JsonCompact => println!("{}", serde_json::to_string(REGIONS)?),
+        OutputFormat::Pretty => {
+            if !no_color {
+                print_section("Search Regions");
+            }
+            for region in REGIONS {
+                if no_color {
+                    println!("{:<8} {}", region.code, region.name);
+                } else {
+                    println!("  {:<8} {}", region.code.cyan(), region.name.bright_black());
+                }
+            }
+        },
+    }
+    Ok(())
+}
 
 fn run_info(no_color: bool) {
     if no_color {
@@ -843,14 +1412,14 @@ async fn main() {
         colored::control::set_override(false);
     }
 
-    if let Commands::Serve { transport, .. } = &cli.command {
-        let use_stderr = matches!(transport, TransportOption::Stdio);
+    if let Commands::Serve(args) = &cli.command {
+        let use_stderr = matches!(args.transport, TransportOption::Stdio);
         setup_logging(cli.verbose, use_stderr, cli.quiet);
     }
 
     let result = cli
         .command
-        .run(cli.format, cli.verbose, cli.quiet, cli.no_color)
+        .run(cli.format, cli.verbose, cli.quiet, cli.no_color, cli.local_time)
         .await;
 
     if let Err(e) = result {
@@ -956,14 +1525,356 @@ Testing search functionality..."
     }
 
     #[test]
-    fn test_parse_host_octets_valid() {
-        assert_eq!(parse_host_octets("127.0.0.1").unwrap(), [127, 0, 0, 1]);
+    fn test_read_key_file_trims_whitespace() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"  abc123\n").unwrap();
+        assert_eq!(read_key_file(file.path()).unwrap(), "abc123");
+    }
+
+    #[test]
+    fn test_read_key_file_rejects_empty() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(matches!(
+            read_key_file(file.path()),
+            Err(DaedraError::InvalidArguments(_))
+        ));
+    }
+
+    fn serve_args(argv: &[&str]) -> ServeArgs {
+        let cli = Cli::try_parse_from(argv).unwrap();
+        match cli.command {
+            Commands::Serve(args) => *args,
+            other => panic!("expected serve, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_serve_args_search_chain() {
+        let args = serve_args(&["daedra", "serve", "--search-chain", "serper, bing,,duckduckgo"]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.search_chain, vec!["serper", "bing", "duckduckgo"]);
+        assert_eq!(config.search_strategy, None);
+
+        let args = serve_args(&["daedra", "serve", "--search-chain", "bing,wiby", "--search-strategy", "merge"]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.search_strategy, Some(SearchStrategy::Merge));
+    }
+
+    #[test]
+    fn test_serve_args_defaults() {
+        let args = serve_args(&["daedra", "serve"]);
+        let config = args.server_config().unwrap();
+        assert!(config.cache.enabled);
+        assert_eq!(config.circuit_breaker, CircuitBreakerConfig::default());
+        assert_eq!(config.max_request_bytes, daedra::server::DEFAULT_MAX_REQUEST_BYTES);
+        assert_eq!(config.max_page_bytes, daedra::tools::fetch::DEFAULT_MAX_PAGE_BYTES);
+        assert_eq!(config.request_timeout, daedra::server::DEFAULT_REQUEST_TIMEOUT);
+        assert_eq!(config.sse_keep_alive, daedra::server::DEFAULT_SSE_KEEP_ALIVE);
+        assert_eq!(config.max_connections, daedra::server::DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(config.idle_timeout, daedra::server::DEFAULT_IDLE_TIMEOUT);
+        assert_eq!(config.write_timeout, daedra::server::DEFAULT_WRITE_TIMEOUT);
+        assert_eq!(args.transport_type().unwrap(), TransportType::Stdio);
+        assert!(!config.dry_run);
+        assert!(!config.ignore_robots);
+        assert!(!config.strict_json);
+        assert_eq!(config.min_safe_search, SafeSearchLevel::Off);
+
+        let config = serve_args(&["daedra", "serve", "--dry-run"]).server_config().unwrap();
+        assert!(config.dry_run);
+
+        let config = serve_args(&["daedra", "serve", "--ignore-robots"]).server_config().unwrap();
+        assert!(config.ignore_robots);
+        assert!(config.allowed_domains.is_empty() && config.blocked_domains.is_empty());
+        assert_eq!(config.fingerprint, FingerprintConfig::default());
+        assert_eq!(config.cookie_file, None);
+
+        let args = serve_args(&["daedra", "serve", "--browser-profile", "safari-macos"]);
+        assert_eq!(args.server_config().unwrap().fingerprint.pin.as_deref(), Some("safari-macos"));
+        let args = serve_args(&["daedra", "serve", "--browser-profiles-file", "/no/such/file"]);
+        let err = args.server_config().unwrap_err();
+        assert!(err.to_string().contains("Cannot read browser profiles"), "{err}");
+
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--allow-domain",
+            "*.docs.example.com,docs.rs",
+            "--deny-domain",
+            "old.docs.example.com",
+        ]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.allowed_domains, ["*.docs.example.com", "docs.rs"]);
+        assert_eq!(config.blocked_domains, ["old.docs.example.com"]);
+
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--domain-header",
+            "wiki.example.com=Authorization: Bearer a=b",
+            "--domain-header",
+            "*.staging.example.com=Cookie: session=1; theme=dark",
+        ]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.domain_headers.len(), 2);
+        assert_eq!(config.domain_headers[0].glob, "wiki.example.com");
+        let header = ("Authorization".to_string(), "Bearer a=b".to_string());
+        assert_eq!(config.domain_headers[0].headers, [header]);
+        let parsed = Cli::try_parse_from(["daedra", "serve", "--domain-header", "no-header"]);
+        assert!(parsed.is_err());
+
+        let config = serve_args(&["daedra", "serve", "--strict-json"]).server_config().unwrap();
+        assert!(config.strict_json);
+
+        assert_eq!(config.json_format, JsonFormat::Pretty);
+        assert_eq!(config.schema_version, SchemaVersion::CURRENT);
+        let args = serve_args(&["daedra", "serve", "--schema-version", "1"]);
+        assert_eq!(args.server_config().unwrap().schema_version, SchemaVersion::V1);
+        assert!(Cli::try_parse_from(["daedra", "serve", "--schema-version", "3"]).is_err());
+        let args = serve_args(&["daedra", "serve", "--json-format", "compact"]);
+        assert_eq!(args.server_config().unwrap().json_format, JsonFormat::Compact);
+
+        let args = serve_args(&["daedra", "serve", "--min-safe-search", "strict"]);
+        assert_eq!(args.server_config().unwrap().min_safe_search, SafeSearchLevel::Strict);
+
+        assert_eq!(config.extraction_threads, fetch::default_extraction_threads());
+        let args = serve_args(&["daedra", "serve", "--extraction-threads", "2"]);
+        assert_eq!(args.server_config().unwrap().extraction_threads, 2);
+        assert!(Cli::try_parse_from(["daedra", "serve", "--extraction-threads", "0"]).is_err());
+
+        assert_eq!(config.memory.cap, None);
+        let args = serve_args(&["daedra", "serve", "--memory-cap", "256"]);
+        assert_eq!(args.server_config().unwrap().memory.cap, Some(256 * 1024 * 1024));
+
+        assert_eq!(config.cache.persistence, None);
+        let args = serve_args(&["daedra", "serve", "--cache-file", "cache.jsonl"]);
+        let persistence = args.server_config().unwrap().cache.persistence.unwrap();
+        assert_eq!(persistence.path, std::path::Path::new("cache.jsonl"));
+        assert_eq!(persistence.fsync, FsyncPolicy::Batch);
+        let args =
+            serve_args(&["daedra", "serve", "--cache-file", "c.jsonl", "--cache-fsync", "always"]);
+        let persistence = args.server_config().unwrap().cache.persistence.unwrap();
+        assert_eq!(persistence.fsync, FsyncPolicy::Always);
+    }
+
+    #[test]
+    fn test_serve_args_privacy() {
+        let config = serve_args(&["daedra", "serve"]).server_config().unwrap();
+        assert!(!config.privacy.hash_logged_queries);
+        assert!(!config.cache.hash_keys);
+        assert_eq!(config.privacy.client_profile, ClientProfile::Standard);
+
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--hash-logged-queries",
+            "--hash-cache-keys",
+            "--query-hash-salt",
+            "pepper",
+            "--cache-ttl",
+            "60",
+            "--low-footprint",
+        ]);
+        let config = args.server_config().unwrap();
+        assert!(config.privacy.hash_logged_queries);
+        assert_eq!(config.privacy.salt.as_deref(), Some("pepper"));
+        assert!(config.cache.hash_keys);
+        assert_eq!(config.cache.ttl, Duration::from_secs(60));
+        assert_eq!(config.privacy.client_profile, ClientProfile::LowFootprint);
+    }
+
+    #[test]
+    fn test_serve_args_query_rewriters() {
+        let config = serve_args(&["daedra", "serve"]).server_config().unwrap();
+        assert!(config.query_rewriters.is_empty());
+
+        let path = std::env::temp_dir().join(format!("daedra-cli-synonyms-{}", std::process::id()));
+        std::fs::write(&path, "k8s,kubernetes\n").unwrap();
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--rewrite-query",
+            "question-keywords,stopwords",
+            "--synonyms-file",
+            path.to_str().unwrap(),
+        ]);
+        let config = args.server_config().unwrap();
+        std::fs::remove_file(&path).ok();
+        let names: Vec<&str> = config.query_rewriters.iter().map(|r| r.name()).collect();
+        assert_eq!(names, ["question-keywords", "stopwords", "synonyms"]);
+
+        let missing = serve_args(&["daedra", "serve", "--synonyms-file", "/nonexistent/syn.txt"]);
+        assert!(missing.server_config().is_err());
     }
 
     #[test]
-    fn test_parse_host_octets_invalid() {
-        assert!(parse_host_octets("127.0.1").is_err());
-        assert!(parse_host_octets("not-a-host").is_err());
+    fn test_serve_args_allowed_hosts() {
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--allowed-host",
+            "mcp.example.com",
+            "--allowed-host",
+            "daedra.lan,10.0.0.5",
+        ]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.allowed_hosts, vec!["mcp.example.com", "daedra.lan", "10.0.0.5"]);
+    }
+
+    #[test]
+    fn test_serve_args_blocklist() {
+        let config = serve_args(&["daedra", "serve"]).server_config().unwrap();
+        assert!(!config.blocklist.curated);
+        assert!(config.blocklist.files.is_empty());
+
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--spam-blocklist",
+            "--blocklist-file",
+            "/etc/daedra/hosts,/etc/daedra/easylist.txt",
+            "--block-domain",
+            "farm.example",
+        ]);
+        let config = args.server_config().unwrap();
+        assert!(config.blocklist.curated);
+        assert_eq!(config.blocklist.files.len(), 2);
+        assert_eq!(config.blocklist.domains, vec!["farm.example"]);
+    }
+
+    #[test]
+    fn test_serve_args_circuit_breaker() {
+        let args = serve_args(&[
+            "daedra",
+            "serve",
+            "--circuit-threshold",
+            "5",
+            "--circuit-cooldown",
+            "120",
+        ]);
+        let config = args.server_config().unwrap();
+        assert_eq!(config.circuit_breaker.failure_threshold, 5);
+        assert_eq!(config.circuit_breaker.cooldown, Duration::from_secs(120));
+
+        assert!(Cli::try_parse_from(["daedra", "serve", "--circuit-threshold", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_serve_args_watchdog() {
+        let config = serve_args(&["daedra", "serve"]).server_config().unwrap();
+        assert_eq!(config.watchdog, WatchdogConfig::default());
+
+        let args = serve_args(&["daedra", "serve", "--watchdog", "--watchdog-failures", "5"]);
+        let config = args.server_config().unwrap();
+        assert!(config.watchdog.enabled);
+        assert_eq!(config.watchdog.failure_threshold, 5);
+        assert_eq!(config.watchdog.restart_after, None);
+
+        let args = serve_args(&["daedra", "serve", "--watchdog-restart-after", "90"]);
+        let config = args.server_config().unwrap();
+        assert!(config.watchdog.enabled);
+        assert_eq!(config.watchdog.restart_after, Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn test_serve_args_slow_requests() {
+        let config = serve_args(&["daedra", "serve"]).server_config().unwrap();
+        assert_eq!(config.slow_requests, SlowRequestConfig::default());
+
+        let argv = [
+            "daedra",
+            "serve",
+            "--slow-request-ms",
+            "2000",
+            "--slow-tool",
+            "deep_research=60000, visit_page=5000",
+        ];
+        let config = serve_args(&argv).server_config().unwrap();
+        assert_eq!(config.slow_requests.threshold("web_search"), Some(Duration::from_secs(2)));
+        assert_eq!(config.slow_requests.threshold("deep_research"), Some(Duration::from_secs(60)));
+        assert_eq!(config.slow_requests.threshold("visit_page"), Some(Duration::from_secs(5)));
+
+        for bad in ["visit_page", "=100", "visit_page=0", "visit_page=soon"] {
+            assert!(Cli::try_parse_from(["daedra", "serve", "--slow-tool", bad]).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_fetch_content_format() {
+        let cli = Cli::try_parse_from(["daedra", "fetch", "https://example.com"]).unwrap();
+        let Commands::Fetch { content_format, .. } = cli.command else {
+            panic!("expected fetch");
+        };
+        assert_eq!(PageFormat::from(content_format), PageFormat::Markdown);
+
+        let argv = [
+            "daedra",
+            "fetch",
+            "https://example.com",
+            "--content-format",
+            "text",
+            "--follow-pagination",
+            "3",
+            "--allow-archive",
+            "--ignore-robots",
+            "--cookie-file",
+            "cookies.json",
+        ];
+        let Commands::Fetch {
+            content_format,
+            follow_pagination,
+            allow_archive,
+            ignore_robots,
+            cookie_file,
+            ..
+        } = Cli::try_parse_from(argv).unwrap().command
+        else {
+            panic!("expected fetch");
+        };
+        assert_eq!(PageFormat::from(content_format), PageFormat::Text);
+        assert_eq!(follow_pagination, Some(3));
+        assert!(allow_archive);
+        assert!(ignore_robots);
+        assert_eq!(cookie_file, Some("cookies.json".into()));
+        let argv = ["daedra", "fetch", "https://example.com", "--content-format", "pdf"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_search_time_range_parsing() {
+        let cli = Cli::try_parse_from(["daedra", "search", "rust", "-t", "2024-01-01..2024-01-31"])
+            .unwrap();
+        let Commands::Search { time_range, .. } = cli.command else {
+            panic!("expected search command");
+        };
+        assert!(matches!(time_range, Some(TimeRange::Custom { .. })));
+
+        assert!(Cli::try_parse_from(["daedra", "search", "rust", "-t", "fortnight"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commands_regions() {
+        let result = Commands::Regions.run(OutputFormat::Json, false, true, true, false).await;
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_serve_args_sse_transport() {
+        let args = serve_args(&["daedra", "serve", "-t", "sse", "-p", "8080", "--host", "0.0.0.0"]);
+        assert_eq!(
+            args.transport_type().unwrap(),
+            TransportType::Http(HttpTransportConfig::new("0.0.0.0", 8080))
+        );
+    }
+
+    #[test]
+    fn test_serve_args_sse_ipv6_and_hostname() {
+        for host in ["::1", "[::1]", "::", "localhost"] {
+            let args = serve_args(&["daedra", "serve", "-t", "sse", "--host", host]);
+            assert_eq!(
+                args.transport_type().unwrap(),
+                TransportType::Http(HttpTransportConfig::new(host, 3000))
+            );
+        }
     }
 
     fn sample_page_content() -> PageContent {
@@ -971,12 +1882,23 @@ Testing search functionality..."
             url: "https://example.com/page".to_string(),
             title: "Example Page Title".to_string(),
             content: "Page body text.".to_string(),
-            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
             word_count: 3,
             links: Some(vec![PageLink {
                 text: "Other".to_string(),
                 url: "https://example.com/other".to_string(),
+                homograph_risk: false,
+                kind: Default::default(),
+                scope: None,
             }]),
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
         }
     }
 
@@ -987,9 +1909,10 @@ Testing search functionality..."
             description: "A short description.".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: "example.com".to_string(),
+                source: "example.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }
     }
@@ -997,9 +1920,10 @@ Testing search functionality..."
     #[test]
     fn test_print_page_content_pretty_no_color() {
         let content = sample_page_content();
-        let output = format_page_content_pretty(&content, true);
+        let output = format_page_content_pretty(&content, true, false);
         assert!(output.contains("Example Page Title"));
         assert!(output.contains("URL: https://example.com/page"));
+        assert!(output.contains("Fetched: 2024-01-01T00:00:00Z"));
         assert!(output.contains("Page body text."));
         assert!(output.contains("Links found (1):"));
     }
@@ -1007,9 +1931,10 @@ Testing search functionality..."
     #[test]
     fn test_print_page_content_pretty_with_color() {
         let content = sample_page_content();
-        let output = format_page_content_pretty(&content, false);
+        let output = format_page_content_pretty(&content, false, true);
         assert!(output.contains("Example Page Title"));
         assert!(output.contains("https://example.com/page"));
+        assert!(output.contains(&content.timestamp.to_local_string()));
     }
 
     #[test]
@@ -1039,7 +1964,7 @@ Testing search functionality..."
     #[tokio::test]
     async fn test_commands_info() {
         let result = Commands::Info
-            .run(OutputFormat::Pretty, false, true, true)
+            .run(OutputFormat::Pretty, false, true, true, false)
             .await;
         assert!(result.is_ok());
     }
@@ -1054,7 +1979,7 @@ Testing search functionality..."
             safe_search: SafeSearchOption::default(),
             time_range: None,
         }
-        .run(OutputFormat::Pretty, false, true, true)
+        .run(OutputFormat::Pretty, false, true, true, false)
         .await;
         assert!(result.is_ok());
     }
@@ -1063,7 +1988,7 @@ Testing search functionality..."
     #[ignore = "network"]
     async fn test_commands_check() {
         let result = Commands::Check
-            .run(OutputFormat::Pretty, false, true, true)
+            .run(OutputFormat::Pretty, false, true, true, false)
             .await;
         assert!(result.is_ok());
     }
@@ -0,0 +1,282 @@
+//! Minimal MCP client for talking to a running daedra server.
+//!
+//! Enabled with the `mcp-client` feature. It speaks just enough of the
+//! protocol — `initialize`, `tools/list` and `tools/call` — to drive daedra
+//! end to end from Rust tests and tools, over either transport:
+//!
+//! - [`McpClient::spawn`] runs a server process and talks JSON-RPC over its STDIO
+//! - [`McpClient::connect_http`] posts to the SSE transport's `/rpc` endpoint
+//!
+//! ```rust,no_run
+//! use daedra::mcp_client::McpClient;
+//! use daedra::SearchArgs;
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let mut command = tokio::process::Command::new("daedra");
+//!     command.args(["serve", "--transport", "stdio", "--quiet"]);
+//!     let client = McpClient::spawn(command).await?;
+//!     let results = client
+//!         .search(&SearchArgs {
+//!             query: "rust async runtime".to_string(),
+//!             options: None,
+//!         })
+//!         .await?;
+//!     println!("{} results", results.data.len());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::server::{JsonRpcRequest, JsonRpcResponse, MCP_PROTOCOL_VERSION, McpTool};
+use crate::types::{
+    DaedraError, DaedraResult, PageContent, SearchArgs, SearchResponse, VisitPageArgs,
+};
+use crate::{SERVER_NAME, VERSION};
+use serde_json::{Value, json};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// How requests reach the server
+enum Transport {
+    Stdio {
+        /// Held so the server is killed when the client is dropped
+        _child: Child,
+        io: Box<Mutex<(ChildStdin, Lines<BufReader<ChildStdout>>)>>,
+    },
+    Http {
+        client: reqwest::Client,
+        rpc_url: String,
+    },
+}
+
+/// An initialized MCP session with a daedra server.
+pub struct McpClient {
+    transport: Transport,
+    next_id: AtomicU64,
+    server_info: Value,
+}
+
+impl McpClient {
+    /// Spawn `command` as an MCP server on STDIO and initialize the session.
+    ///
+    /// STDIN/STDOUT are piped, and the process is killed when the client drops.
+    pub async fn spawn(mut command: Command) -> DaedraResult<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout")).lines();
+        Self::initialize(Transport::Stdio {
+            _child: child,
+            io: Box::new(Mutex::new((stdin, stdout))),
+        })
+        .await
+    }
+
+    /// Connect to an SSE transport (e.g. `http://127.0.0.1:3000`) and initialize the session.
+    pub async fn connect_http(base_url: &str) -> DaedraResult<Self> {
+        Self::initialize(Transport::Http {
+            client: reqwest::Client::new(),
+            rpc_url: format!("{}/rpc", base_url.trim_end_matches('/')),
+        })
+        .await
+    }
+
+    async fn initialize(transport: Transport) -> DaedraResult<Self> {
+        let mut client = Self {
+            transport,
+            next_id: AtomicU64::new(1),
+            server_info: Value::Null,
+        };
+        client.server_info = client
+            .request(
+                "initialize",
+                Some(json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {"name": format!("{SERVER_NAME}-mcp-client"), "version": VERSION}
+                })),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+        Ok(client)
+    }
+
+    /// The server's `initialize` result (protocol version, capabilities, server info)
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    /// Send a JSON-RPC request and return its `result`.
+    ///
+    /// A JSON-RPC error becomes [`DaedraError::ServerError`].
+    pub async fn request(&self, method: &str, params: Option<Value>) -> DaedraResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let response = self.send(method, Some(json!(id)), params).await?.ok_or_else(|| {
+            DaedraError::ServerError("server closed the connection".to_string())
+        })?;
+        if let Some(error) = response.error {
+            return Err(DaedraError::ServerError(format!(
+                "{method} failed ({}): {}",
+                error.code, error.message
+            )));
+        }
+        Ok(response.result.unwrap_or(Value::Null))
+    }
+
+    async fn notify(&self, method: &str) -> DaedraResult<()> {
+        self.send(method, None, None).await.map(drop)
+    }
+
+    /// Write one message; for requests, read until the response with the same id.
+    async fn send(
+        &self,
+        method: &str,
+        id: Option<Value>,
+        params: Option<Value>,
+    ) -> DaedraResult<Option<JsonRpcResponse>> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: id.clone(),
+            method: method.to_string(),
+            params,
+        };
+        match &self.transport {
+            Transport::Http { client, rpc_url } => {
+                let response = client
+                    .post(rpc_url)
+                    .json(&request)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                // The HTTP endpoint answers notifications too; nobody needs that body
+                if id.is_none() {
+                    return Ok(None);
+                }
+                Ok(Some(response.json().await?))
+            },
+            Transport::Stdio { io, .. } => {
+                let mut io = io.lock().await;
+                let (stdin, stdout) = &mut *io;
+                let mut line = serde_json::to_string(&request)?;
+                line.push('\n');
+                stdin.write_all(line.as_bytes()).await?;
+                stdin.flush().await?;
+                if id.is_none() {
+                    return Ok(None);
+                }
+                while let Some(line) = stdout.next_line().await? {
+                    let response: JsonRpcResponse = serde_json::from_str(&line)?;
+                    if response.id == id {
+                        return Ok(Some(response));
+                    }
+                }
+                Ok(None)
+            },
+        }
+    }
+
+    /// List the server's tools
+    pub async fn list_tools(&self) -> DaedraResult<Vec<McpTool>> {
+        let result = self.request("tools/list", None).await?;
+        Ok(serde_json::from_value(result.get("tools").cloned().unwrap_or_default())?)
+    }
+
+    /// Call a tool and return its raw result (`{"content": [...], "isError": ...}`)
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> DaedraResult<Value> {
+        self.request("tools/call", Some(json!({"name": name, "arguments": arguments})))
+            .await
+    }
+
+    /// Call `web_search` and parse its result
+    pub async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+        let result = self.call_tool("web_search", serde_json::to_value(args)?).await?;
+        SearchResponse::from_tool_content(&result)
+    }
+
+    /// Call `visit_page` and parse its result
+    pub async fn visit_page(&self, args: &VisitPageArgs) -> DaedraResult<PageContent> {
+        let result = self.call_tool("visit_page", serde_json::to_value(args)?).await?;
+        PageContent::from_tool_content(&result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{DaedraServer, ServerHandle};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn connect() -> (ServerHandle, McpClient) {
+        let handle = DaedraServer::with_defaults()
+            .unwrap()
+            .spawn("127.0.0.1", 0)
+            .await
+            .unwrap();
+        let client = McpClient::connect_http(&format!("http://{}", handle.local_addr()))
+            .await
+            .unwrap();
+        (handle, client)
+    }
+
+    #[tokio::test]
+    async fn test_http_initialize_and_list_tools() {
+        let (_server, client) = connect().await;
+        assert_eq!(client.server_info()["serverInfo"]["name"], SERVER_NAME);
+        let tools = client.list_tools().await.unwrap();
+        assert!(tools.iter().any(|t| t.name == "web_search"));
+    }
+
+    #[tokio::test]
+    async fn test_http_visit_page() {
+        let site = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Hello</title></head><body><p>Hello from the page.</p></body></html>",
+                "text/html",
+            ))
+            .mount(&site)
+            .await;
+
+        let (_server, client) = connect().await;
+        let page = client
+            .visit_page(&VisitPageArgs {
+                url: format!("{}/page", site.uri()),
+                selector: None,
+                include_images: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.url, format!("{}/page", site.uri()));
+        assert!(page.content.contains("Hello from the page"), "{}", page.content);
+    }
+
+    #[tokio::test]
+    async fn test_http_tool_error_and_rpc_error() {
+        let (_server, client) = connect().await;
+        let err = client
+            .visit_page(&VisitPageArgs {
+                url: "ftp://example.com".to_string(),
+                selector: None,
+                include_images: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, DaedraError::FetchError(msg) if msg.contains("Invalid URL")),
+            "{err}"
+        );
+
+        let err = client.request("no/such/method", None).await.unwrap_err();
+        assert!(err.to_string().contains("-32601"), "{err}");
+    }
+}
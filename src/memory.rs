@@ -0,0 +1,169 @@
+//! Memory cap for small hosts.
+//!
+//! With [`MemoryConfig::cap`] set, a background task reads the process's
+//! resident set size every [`interval`](MemoryConfig::interval). While it is
+//! over the cap, the server is under [`MemoryPressure`]:
+//!
+//! - the cache gives up half its entries at each check, down to
+//!   [`MIN_SHRUNK_ENTRIES`], and caches nothing new past that shrunken
+//!   capacity (see [`SearchCache::shrink`]);
+//! - pages larger than [`pressure_fetch_limit`](MemoryConfig::pressure_fetch_limit)
+//!   are refused instead of the usual 10 MiB.
+//!
+//! Pressure ends once usage falls below nine tenths of the cap, and the cache
+//! gets its configured capacity back.
+
+use crate::cache::SearchCache;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tracing::{info, warn};
+
+/// Fewest entries each cache is shrunk to under pressure
+pub const MIN_SHRUNK_ENTRIES: u64 = 16;
+
+/// Default time between memory checks
+pub const DEFAULT_MEMORY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default largest page fetched under pressure (1 MiB)
+pub const DEFAULT_PRESSURE_FETCH_LIMIT: usize = 1024 * 1024;
+
+/// How much memory the server may use and how it backs off past that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryConfig {
+    /// Resident set size in bytes above which the server is under pressure;
+    /// `None` never checks
+    pub cap: Option<u64>,
+    /// Time between checks
+    pub interval: Duration,
+    /// Largest page body, in bytes, fetched under pressure
+    pub pressure_fetch_limit: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            cap: None,
+            interval: DEFAULT_MEMORY_INTERVAL,
+            pressure_fetch_limit: DEFAULT_PRESSURE_FETCH_LIMIT,
+        }
+    }
+}
+
+/// Whether the process is over its memory cap, shared by everything that
+/// backs off under pressure.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPressure(Arc<AtomicBool>);
+
+impl MemoryPressure {
+    /// Whether memory is over the cap
+    pub fn is_high(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Record whether memory is over the cap; returns whether that changed
+    pub fn set(&self, high: bool) -> bool {
+        self.0.swap(high, Ordering::Relaxed) != high
+    }
+}
+
+/// Resident set size of this process in bytes, where the platform reports it
+pub fn resident_bytes() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    system.process(pid).map(|process| process.memory())
+}
+
+/// Judge `resident` bytes against `cap`, updating `pressure` and `cache`
+pub(crate) async fn check(
+    resident: u64,
+    cap: u64,
+    pressure: &MemoryPressure,
+    cache: &SearchCache,
+) {
+    const MIB: u64 = 1024 * 1024;
+    if resident > cap {
+        if pressure.set(true) {
+            warn!(resident_mib = resident / MIB, cap_mib = cap / MIB, "Memory over its cap");
+        }
+        let capacity = cache.shrink().await;
+        info!(capacity, "Cache shrunk under memory pressure");
+    } else if resident < cap / 10 * 9 && pressure.set(false) {
+        cache.restore_capacity();
+        info!(resident_mib = resident / MIB, "Memory back under its cap");
+    }
+}
+
+/// Check memory every `config.interval` for as long as the task runs
+pub(crate) async fn run(config: MemoryConfig, pressure: MemoryPressure, cache: SearchCache) {
+    let Some(cap) = config.cap else {
+        return;
+    };
+    info!(cap_mib = cap / (1024 * 1024), "Memory cap enforced");
+    let mut ticks = tokio::time::interval(config.interval.max(Duration::from_millis(10)));
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        match resident_bytes() {
+            Some(resident) => check(resident, cap, &pressure, &cache).await,
+            None => {
+                warn!("Cannot read this process's memory use; memory cap not enforced");
+                return;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheConfig;
+    use crate::types::{SearchOptions, SearchResponse};
+
+    #[test]
+    fn test_resident_bytes() {
+        if cfg!(target_os = "linux") {
+            assert!(resident_bytes().unwrap() > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pressure_shrinks_the_cache_until_memory_recovers() {
+        let cache = SearchCache::new(CacheConfig {
+            max_entries: 100,
+            ..Default::default()
+        });
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        for i in 0..40 {
+            cache.set_search(&format!("q{i}"), "wt-wt", "MODERATE", response.clone()).await;
+        }
+        let pressure = MemoryPressure::default();
+        let mib = 1024 * 1024;
+
+        check(50 * mib, 100 * mib, &pressure, &cache).await;
+        assert!(!pressure.is_high());
+        assert!(cache.contains_search("q39", "wt-wt", "MODERATE"));
+
+        check(120 * mib, 100 * mib, &pressure, &cache).await;
+        assert!(pressure.is_high());
+        assert_eq!(cache.stats().search_entries, 20);
+        check(120 * mib, 100 * mib, &pressure, &cache).await;
+        assert_eq!(cache.stats().search_entries, MIN_SHRUNK_ENTRIES);
+        cache.set_search("new", "wt-wt", "MODERATE", response.clone()).await;
+        assert!(!cache.contains_search("new", "wt-wt", "MODERATE"));
+
+        // Between nine tenths of the cap and the cap, pressure holds
+        check(95 * mib, 100 * mib, &pressure, &cache).await;
+        assert!(pressure.is_high());
+        check(80 * mib, 100 * mib, &pressure, &cache).await;
+        assert!(!pressure.is_high());
+        cache.set_search("new", "wt-wt", "MODERATE", response).await;
+        assert!(cache.contains_search("new", "wt-wt", "MODERATE"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Citation metadata pages declare about themselves.
+//!
+//! Agents quoting a page need who wrote it, when, and where it lives. Pages
+//! say so in several overlapping places: Open Graph and `article:*` meta tags,
+//! plain `<meta name="description">`/`<meta name="author">`, a
+//! `<link rel="canonical">`, and schema.org JSON-LD. [`extract`] gathers them
+//! into one [`PageMeta`], preferring JSON-LD for the article's own dates and
+//! authors (it names the article, where meta tags may describe the site) and
+//! the tags for the rest. JSON-LD `@graph`s are searched as a whole, so an
+//! author given only by `@id` is found on its `Person` node.
+//!
+//! ```
+//! use daedra::metadata;
+//! use scraper::Html;
+//! use url::Url;
+//!
+//! let page = Html::parse_document(
+//!     r#"<head><meta property="og:site_name" content="Example News">
+//!     <link rel="canonical" href="/story">
+//!     <script type="application/ld+json">{"@type": "NewsArticle",
+//!       "datePublished": "2024-05-01", "author": {"@type": "Person", "name": "Ana Lima"}}
+//!     </script></head>"#,
+//! );
+//! let meta = metadata::extract(&page, &Url::parse("https://example.com/story?ref=x").unwrap());
+//! assert_eq!(meta.author.as_deref(), Some("Ana Lima"));
+//! assert_eq!(meta.published_date.as_deref(), Some("2024-05-01"));
+//! assert_eq!(meta.site_name.as_deref(), Some("Example News"));
+//! assert_eq!(meta.canonical_url.as_deref(), Some("https://example.com/story"));
+//! ```
+
+use crate::text;
+use crate::types::PageMeta;
+use lazy_static::lazy_static;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+lazy_static! {
+    static ref JSON_LD_SELECTOR: Selector =
+        Selector::parse("script[type='application/ld+json']").unwrap();
+    static ref CANONICAL_SELECTOR: Selector =
+        Selector::parse("link[rel~='canonical'][href]").unwrap();
+}
+
+/// schema.org types naming the page's own content rather than the site
+const CREATIVE_WORK_HINTS: &[&str] =
+    &["Article", "Posting", "Report", "Review", "Recipe", "CreativeWork", "WebPage", "Book"];
+
+/// The metadata `document` declares; relative URLs are resolved against `base`.
+/// Fields the page doesn't declare are `None`.
+pub fn extract(document: &Html, base: &Url) -> PageMeta {
+    let tag = |selectors: &[&str]| meta_tag(document, selectors);
+    let graph = json_ld_nodes(document);
+    let work = main_node(&graph);
+    let work_field = |key: &str| work.and_then(|node| node.get(key)).and_then(Value::as_str);
+    let work_names = |key: &str| work.and_then(|node| node.get(key)).and_then(|v| names(v, &graph));
+
+    let canonical = document
+        .select(&CANONICAL_SELECTOR)
+        .find_map(|link| link.value().attr("href"))
+        .map(str::to_string)
+        .or_else(|| tag(&["property='og:url'"]));
+    let website_name = graph
+        .iter()
+        .find(|node| has_type(node, |t| t == "WebSite"))
+        .and_then(|node| node.get("name"))
+        .and_then(|name| names(name, &graph));
+
+    PageMeta {
+        title: tag(&["property='og:title'", "name='twitter:title'"])
+            .or_else(|| work_field("headline").and_then(non_empty)),
+        description: tag(&[
+            "property='og:description'",
+            "name='description'",
+            "name='twitter:description'",
+        ])
+        .or_else(|| work_field("description").and_then(non_empty)),
+        canonical_url: canonical.and_then(|href| resolve(base, &href)),
+        author: work_names("author")
+            .or_else(|| work_names("creator"))
+            .or_else(|| tag(&["name='author'", "name='citation_author'"]))
+            .or_else(|| tag(&["property='article:author'"]).filter(|a| !is_url(a))),
+        published_date: work_field("datePublished")
+            .and_then(non_empty)
+            .or_else(|| tag(&["property='article:published_time'", "itemprop='datePublished'"])),
+        modified_date: work_field("dateModified")
+            .and_then(non_empty)
+            .or_else(|| tag(&["property='article:modified_time'", "property='og:updated_time'"])),
+        site_name: tag(&["property='og:site_name'", "name='application-name'"])
+            .or_else(|| work_names("publisher"))
+            .or(website_name),
+        image: tag(&["property='og:image'", "property='og:image:url'", "name='twitter:image'"])
+            .and_then(|src| resolve(base, &src)),
+    }
+}
+
+/// Content of the first non-empty `<meta>` matching one of `attributes`
+/// (`property='og:title'`), in order
+fn meta_tag(document: &Html, attributes: &[&str]) -> Option<String> {
+    attributes.iter().find_map(|attribute| {
+        let selector = Selector::parse(&format!("meta[{attribute}][content]")).ok()?;
+        document
+            .select(&selector)
+            .find_map(|meta| meta.value().attr("content").and_then(non_empty))
+    })
+}
+
+/// Every JSON-LD node on the page, with arrays and `@graph`s flattened;
+/// blocks that aren't valid JSON are skipped
+fn json_ld_nodes(document: &Html) -> Vec<Value> {
+    fn flatten(value: Value, nodes: &mut Vec<Value>) {
+        match value {
+            Value::Array(items) => items.into_iter().for_each(|item| flatten(item, nodes)),
+            Value::Object(mut object) => {
+                if let Some(graph) = object.remove("@graph") {
+                    flatten(graph, nodes);
+                }
+                nodes.push(Value::Object(object));
+            },
+            _ => {},
+        }
+    }
+
+    let mut nodes = Vec::new();
+    for script in document.select(&JSON_LD_SELECTOR) {
+        let json = script.text().collect::<String>();
+        if let Ok(value) = serde_json::from_str(json.trim()) {
+            flatten(value, &mut nodes);
+        }
+    }
+    nodes
+}
+
+/// The node describing the page's content: the first creative work with a
+/// date or author, else the first node with either
+fn main_node(nodes: &[Value]) -> Option<&Value> {
+    let dated = |node: &&Value| {
+        ["datePublished", "author", "headline"].iter().any(|key| node.get(key).is_some())
+    };
+    let creative = |t: &str| CREATIVE_WORK_HINTS.iter().any(|hint| t.contains(hint));
+    nodes
+        .iter()
+        .filter(dated)
+        .find(|node| has_type(node, creative))
+        .or_else(|| nodes.iter().find(dated))
+}
+
+/// Whether `node`'s `@type` (a name or a list of them) satisfies `matches`
+fn has_type(node: &Value, matches: impl Fn(&str) -> bool) -> bool {
+    match node.get("@type") {
+        Some(Value::String(t)) => matches(t),
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).any(matches),
+        _ => false,
+    }
+}
+
+/// Names in a JSON-LD person or organization value, comma-separated: a
+/// plain name, an object with a `name`, an `@id` of a node in `graph`, or a
+/// list of these
+fn names(value: &Value, graph: &[Value]) -> Option<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut add = |name: String| {
+        if !found.contains(&name) {
+            found.push(name);
+        }
+    };
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        _ => std::slice::from_ref(value),
+    };
+    for item in items {
+        let name = match item {
+            Value::String(name) => non_empty(name),
+            Value::Object(object) => object
+                .get("name")
+                .and_then(Value::as_str)
+                .and_then(non_empty)
+                .or_else(|| {
+                    let id = object.get("@id")?.as_str()?;
+                    graph
+                        .iter()
+                        .find(|node| node.get("@id").and_then(Value::as_str) == Some(id))?
+                        .get("name")?
+                        .as_str()
+                        .and_then(non_empty)
+                }),
+            _ => None,
+        };
+        if let Some(name) = name.filter(|name| !is_url(name)) {
+            add(name);
+        }
+    }
+    Some(found.join(", ")).filter(|names| !names.is_empty())
+}
+
+fn resolve(base: &Url, href: &str) -> Option<String> {
+    base.join(href.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(String::from)
+}
+
+fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(text::clean(value)).filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract_from(html: &str) -> PageMeta {
+        extract(&Html::parse_document(html), &Url::parse("https://news.example/a/b").unwrap())
+    }
+
+    #[test]
+    fn test_open_graph_and_meta_tags() {
+        let meta = extract_from(
+            r#"<head>
+            <meta property="og:title" content="Launch day">
+            <meta name="description" content="  What &amp; why  ">
+            <meta property="og:url" content="https://news.example/launch">
+            <meta name="author" content="Kim Park">
+            <meta property="article:published_time" content="2024-03-12T09:00:00Z">
+            <meta property="article:modified_time" content="">
+            <meta property="og:site_name" content="Example News">
+            <meta property="og:image" content="/img/launch.png">
+            </head>"#,
+        );
+        assert_eq!(
+            meta,
+            PageMeta {
+                title: Some("Launch day".to_string()),
+                description: Some("What & why".to_string()),
+                canonical_url: Some("https://news.example/launch".to_string()),
+                author: Some("Kim Park".to_string()),
+                published_date: Some("2024-03-12T09:00:00Z".to_string()),
+                modified_date: None,
+                site_name: Some("Example News".to_string()),
+                image: Some("https://news.example/img/launch.png".to_string()),
+            }
+        );
+        assert!(extract_from("<p>No metadata</p>").is_empty());
+    }
+
+    #[test]
+    fn test_json_ld_graph_with_author_references() {
+        let meta = extract_from(
+            r##"<head>
+            <link rel="canonical" href="../c">
+            <meta property="article:author" content="https://facebook.com/someone">
+            <script type="application/ld+json">{ not json }</script>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@graph": [
+              {"@type": "WebSite", "@id": "#site", "name": "Field Notes"},
+              {"@type": ["BlogPosting"], "headline": "On moss",
+               "datePublished": "2023-05-18", "dateModified": "2023-06-01",
+               "author": [{"@id": "#ana"}, {"@type": "Person", "name": "Bo Chen"}, "Bo Chen"]},
+              {"@type": "Person", "@id": "#ana", "name": "Ana Lima"}
+            ]}</script></head>"##,
+        );
+        assert_eq!(meta.title.as_deref(), Some("On moss"));
+        assert_eq!(meta.author.as_deref(), Some("Ana Lima, Bo Chen"));
+        assert_eq!(meta.published_date.as_deref(), Some("2023-05-18"));
+        assert_eq!(meta.modified_date.as_deref(), Some("2023-06-01"));
+        assert_eq!(meta.site_name.as_deref(), Some("Field Notes"));
+        assert_eq!(meta.canonical_url.as_deref(), Some("https://news.example/c"));
+    }
+}
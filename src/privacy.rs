@@ -0,0 +1,243 @@
+//! Keeping search queries out of logs and cache keys, and outgoing requests
+//! from saying more than they must.
+//!
+//! What users search for is often personal. Deployments that must not keep it
+//! can have logs and traces show a hash of each query instead of its text, and
+//! the cache key searches by hash. The same query always hashes the same way,
+//! so log lines about one search can still be correlated. Without a salt,
+//! anyone can check a guessed query against a logged hash; with a secret salt,
+//! only whoever holds it can.
+//!
+//! The [`ClientProfile::LowFootprint`] profile sends the same few headers from
+//! every HTTP client: one common browser `User-Agent`, the do-not-track and
+//! Global Privacy Control signals, and no `Referer`. No profile adds
+//! cache-busting parameters or `Cache-Control` headers, so upstream and proxy
+//! caches can answer repeat requests.
+//!
+//! Logging is process-wide, and so is query hashing: once any handler turns it
+//! on, it stays on for the process. The client profile is a setting of each
+//! client instead (see [`FetchClientBuilder::client_profile`]), so handlers
+//! and clients in one process can send different headers.
+//!
+//! [`FetchClientBuilder::client_profile`]: crate::tools::fetch::FetchClientBuilder::client_profile
+
+use crate::text;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::{Client, ClientBuilder};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// Hex characters of the digest kept in a [`query_hash`]
+const HASH_HEX_CHARS: usize = 16;
+
+/// The one `User-Agent` every client sends under [`ClientProfile::LowFootprint`]
+pub const LOW_FOOTPRINT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+static HASH_LOGGED_QUERIES: AtomicBool = AtomicBool::new(false);
+
+static SALT: OnceLock<String> = OnceLock::new();
+
+lazy_static! {
+    static ref URL_QUERY_STRING: Regex = Regex::new(r"(https?://[^\s?#]+)\?[^\s#)]*").unwrap();
+}
+
+/// What outgoing HTTP requests reveal about the deployment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientProfile {
+    /// Each client's own `User-Agent` (`daedra/1.0` for APIs, a browser one
+    /// for scraped pages) and reqwest's defaults, `Referer` on redirects included
+    #[default]
+    Standard,
+
+    /// [`LOW_FOOTPRINT_USER_AGENT`] everywhere, `DNT: 1` and `Sec-GPC: 1`, and
+    /// no `Referer`
+    LowFootprint,
+}
+
+/// How queries are kept out of logs, and what outgoing requests send.
+#[derive(Debug, Clone, Default)]
+pub struct PrivacyConfig {
+    /// Log and trace a hash of each query instead of its text, and leave raw
+    /// JSON-RPC messages out of debug logs
+    pub hash_logged_queries: bool,
+
+    /// Secret mixed into every query hash, logged or cached
+    pub salt: Option<String>,
+
+    /// Headers sent by the handler's HTTP clients
+    pub client_profile: ClientProfile,
+}
+
+/// Apply the query hashing of `config` to the process; the client profile is
+/// left to the clients built with it.
+///
+/// Hashing is never turned back off, and the first salt set is kept; a
+/// different one set later is ignored with a warning.
+pub fn configure(config: &PrivacyConfig) {
+    if config.hash_logged_queries {
+        HASH_LOGGED_QUERIES.store(true, Ordering::Relaxed);
+    }
+    if let Some(salt) = &config.salt
+        && SALT.get_or_init(|| salt.clone()) != salt
+    {
+        warn!("A query hash salt was already set for this process; keeping the first one");
+    }
+}
+
+/// Whether log lines carry query hashes instead of query text
+pub fn hashing_logged_queries() -> bool {
+    HASH_LOGGED_QUERIES.load(Ordering::Relaxed)
+}
+
+/// A client builder set up for `profile`; `user_agent` is what the client
+/// sends under the standard profile.
+pub fn client_builder(profile: ClientProfile, user_agent: Option<&str>) -> ClientBuilder {
+    let builder = reqwest::Client::builder();
+    match (profile, user_agent) {
+        (ClientProfile::LowFootprint, _) => {
+            let headers: HeaderMap = [
+                (HeaderName::from_static("dnt"), HeaderValue::from_static("1")),
+                (HeaderName::from_static("sec-gpc"), HeaderValue::from_static("1")),
+            ]
+            .into_iter()
+            .collect();
+            builder
+                .user_agent(LOW_FOOTPRINT_USER_AGENT)
+                .default_headers(headers)
+                .referer(false)
+        },
+        (ClientProfile::Standard, Some(user_agent)) => builder.user_agent(user_agent),
+        (ClientProfile::Standard, None) => builder,
+    }
+}
+
+/// A search backend's or API's HTTP client: [`client_builder`] with a
+/// request `timeout` and every response compression reqwest can decode.
+pub fn backend_client(
+    profile: ClientProfile,
+    user_agent: Option<&str>,
+    timeout: Duration,
+) -> Client {
+    client_builder(profile, user_agent)
+        .timeout(timeout)
+        .gzip(true)
+        .brotli(true)
+        .zstd(true)
+        .deflate(true)
+        .build()
+        .expect("HTTP client")
+}
+
+/// Stable, salted hash of `query`, ignoring case and whitespace differences
+/// (see [`text::key`]).
+pub fn query_hash(query: &str) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(salt) = SALT.get() {
+        hasher.update(salt.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(text::key(query).as_bytes());
+    let hex: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", &hex[..HASH_HEX_CHARS])
+}
+
+/// `query` as it may appear in logs: its text, or its hash when hashing is on.
+///
+/// ```
+/// tracing::info!(query = %daedra::privacy::loggable("rust"), "Searching");
+/// ```
+pub fn loggable(query: &str) -> LoggedQuery<'_> {
+    LoggedQuery(query)
+}
+
+/// `message` with the query strings of any URLs in it cut when hashing is on.
+///
+/// Errors from HTTP clients quote the request URL, and search APIs take the
+/// query in the query string.
+pub fn scrub(message: &str) -> Cow<'_, str> {
+    if hashing_logged_queries() {
+        URL_QUERY_STRING.replace_all(message, "$1?<redacted>")
+    } else {
+        Cow::Borrowed(message)
+    }
+}
+
+/// A query that renders as [`loggable`] describes.
+#[derive(Debug, Clone, Copy)]
+pub struct LoggedQuery<'a>(&'a str);
+
+impl fmt::Display for LoggedQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if hashing_logged_queries() {
+            f.write_str(&query_hash(self.0))
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_hash_is_stable_and_normalized() {
+        let hash = query_hash("Rust Async");
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash.len(), "sha256:".len() + HASH_HEX_CHARS);
+        assert_eq!(hash, query_hash("  rust async "));
+        assert_ne!(hash, query_hash("rust sync"));
+    }
+
+    #[test]
+    fn test_hashing_hides_queries_from_logs() {
+        // Turning hashing on is process-wide, so this is the only test that does
+        configure(&PrivacyConfig {
+            hash_logged_queries: true,
+            ..Default::default()
+        });
+        let query = "my medical condition";
+        assert_eq!(loggable(query).to_string(), query_hash(query));
+
+        let error = "error sending request for url \
+                     (https://search.example/api?q=my+medical+condition&format=json)";
+        assert_eq!(
+            scrub(error),
+            "error sending request for url (https://search.example/api?<redacted>)"
+        );
+        assert_eq!(scrub("HTTP 429 from https://a.example/"), "HTTP 429 from https://a.example/");
+    }
+
+    #[tokio::test]
+    async fn test_low_footprint_clients_send_minimal_headers() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any()).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        let standard = client_builder(ClientProfile::Standard, Some("daedra/1.0")).build().unwrap();
+        standard.get(server.uri()).send().await.unwrap();
+        let low = client_builder(ClientProfile::LowFootprint, Some("daedra/1.0")).build().unwrap();
+        low.get(server.uri()).send().await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let header = |i: usize, name: &str| {
+            requests[i].headers.get(name).map(|v| v.to_str().unwrap().to_string())
+        };
+        assert_eq!(header(0, "user-agent").as_deref(), Some("daedra/1.0"));
+        assert_eq!(header(0, "dnt"), None);
+        assert_eq!(header(1, "user-agent").as_deref(), Some(LOW_FOOTPRINT_USER_AGENT));
+        assert_eq!(header(1, "dnt").as_deref(), Some("1"));
+        assert_eq!(header(1, "sec-gpc").as_deref(), Some("1"));
+        for name in ["referer", "cache-control", "pragma", "cookie"] {
+            assert_eq!(header(1, name), None, "{name}");
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Composing search queries from operators, in each engine's syntax.
+//!
+//! Engines disagree on operators. Google, Bing and DuckDuckGo take quoted
+//! phrases, `-exclusions`, `site:`, `filetype:` and `(a OR b)` groups; Mojeek
+//! has no `OR` or `filetype:`; the Wikipedia, GitHub and Stack Exchange APIs
+//! match keywords only. A [`QueryBuilder`] holds the parts of a query and
+//! writes them out in a [`QueryDialect`].
+//!
+//! `web_search` takes a query either as text or as a [`QueryBuilder`] object.
+//! Either way, backends speaking a simpler dialect get the query parsed back
+//! into parts and rewritten for them, so an operator they don't understand
+//! never reaches them as literal text.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Operator syntax a search engine understands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryDialect {
+    /// Phrases, exclusions, `site:`, `filetype:` and `(a OR b)` groups
+    #[default]
+    Full,
+
+    /// Phrases, exclusions and one `site:`; an OR group keeps only its first
+    /// alternative, and file types are dropped
+    Basic,
+
+    /// Keywords only: phrases lose their quotes, OR groups keep their first
+    /// alternative, and exclusions, sites and file types are dropped
+    Keywords,
+}
+
+/// The parts of a search query, rendered per engine by [`render`](Self::render).
+///
+/// ```
+/// use daedra::query::{QueryBuilder, QueryDialect};
+///
+/// let query = QueryBuilder::new()
+///     .term("tokio")
+///     .phrase("graceful shutdown")
+///     .any_of(["signal", "ctrl-c"])
+///     .site("docs.rs")
+///     .exclude("actix");
+/// assert_eq!(
+///     query.to_string(),
+///     r#""graceful shutdown" tokio (signal OR ctrl-c) site:docs.rs -actix"#
+/// );
+/// assert_eq!(query.render(QueryDialect::Keywords), "graceful shutdown tokio signal");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryBuilder {
+    /// Words that should appear
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub terms: Vec<String>,
+
+    /// Exact phrases that must appear
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phrases: Vec<String>,
+
+    /// Sites to search within; with several, results may come from any
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sites: Vec<String>,
+
+    /// Words or phrases that must not appear
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Groups of alternatives; at least one of each group must appear
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub any_of: Vec<Vec<String>>,
+
+    /// File extension results must have, e.g. `pdf`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filetype: Option<String>,
+}
+
+impl QueryBuilder {
+    /// An empty query
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a word that should appear.
+    pub fn term(mut self, term: impl Into<String>) -> Self {
+        self.terms.push(term.into());
+        self
+    }
+
+    /// Add an exact phrase.
+    pub fn phrase(mut self, phrase: impl Into<String>) -> Self {
+        self.phrases.push(phrase.into());
+        self
+    }
+
+    /// Search within `site` (a domain, or a domain and path).
+    pub fn site(mut self, site: impl Into<String>) -> Self {
+        self.sites.push(site.into());
+        self
+    }
+
+    /// Leave out results containing `term`, a word or phrase.
+    pub fn exclude(mut self, term: impl Into<String>) -> Self {
+        self.exclude.push(term.into());
+        self
+    }
+
+    /// Require at least one of `alternatives`.
+    pub fn any_of<S: Into<String>>(mut self, alternatives: impl IntoIterator<Item = S>) -> Self {
+        self.any_of.push(alternatives.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only return files with this extension.
+    pub fn filetype(mut self, extension: impl Into<String>) -> Self {
+        self.filetype = Some(extension.into());
+        self
+    }
+
+    /// Split a query typed in [`QueryDialect::Full`] syntax into its parts.
+    /// Operators this module doesn't know (`intitle:`) are kept as terms.
+    pub fn parse(query: &str) -> Self {
+        let mut items: Vec<Vec<Atom>> = Vec::new();
+        let mut pending_or = false;
+        let mut group: Option<(Vec<Atom>, bool)> = None;
+        for lexeme in lex(query) {
+            match (lexeme, &mut group) {
+                (Lexeme::Open, None) => group = Some((Vec::new(), false)),
+                (Lexeme::Open, Some(_)) => {},
+                (Lexeme::Close, _) => {
+                    if let Some((atoms, alternatives)) = group.take() {
+                        push_group(&mut items, atoms, alternatives, &mut pending_or);
+                    }
+                },
+                (Lexeme::Or, Some((_, alternatives))) => *alternatives = true,
+                (Lexeme::Or, None) => pending_or = !items.is_empty(),
+                (Lexeme::Atom(atom), Some((atoms, _))) => atoms.push(atom),
+                (Lexeme::Atom(atom), None) => {
+                    push_group(&mut items, vec![atom], true, &mut pending_or)
+                },
+            }
+        }
+        if let Some((atoms, alternatives)) = group {
+            push_group(&mut items, atoms, alternatives, &mut pending_or);
+        }
+
+        let mut builder = Self::new();
+        for mut alternatives in items {
+            if alternatives.len() == 1 {
+                builder.add(alternatives.remove(0));
+            } else if let Some(sites) =
+                alternatives.iter().map(|a| a.operand("site:")).collect::<Option<Vec<_>>>()
+            {
+                builder.sites.extend(sites);
+            } else {
+                builder.any_of.push(alternatives.into_iter().map(|a| a.text).collect());
+            }
+        }
+        builder
+    }
+
+    /// Whether the query has no parts
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.phrases.is_empty()
+            && self.sites.is_empty()
+            && self.exclude.is_empty()
+            && self.any_of.is_empty()
+            && self.filetype.is_none()
+    }
+
+    /// The query in `dialect`'s syntax; parts it cannot express are dropped
+    /// as [`QueryDialect`] describes.
+    pub fn render(&self, dialect: QueryDialect) -> String {
+        let keywords = dialect == QueryDialect::Keywords;
+        let word_or_phrase = |text: &str| {
+            let text = text.replace('"', "");
+            let text = text.trim();
+            if !keywords && text.contains(char::is_whitespace) {
+                format!("\"{}\"", text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut parts: Vec<String> = Vec::new();
+        for phrase in &self.phrases {
+            let phrase = phrase.replace('"', "");
+            let phrase = phrase.trim();
+            parts.push(if keywords { phrase.to_string() } else { format!("\"{}\"", phrase) });
+        }
+        parts.extend(self.terms.iter().map(|t| t.trim().to_string()));
+        for group in &self.any_of {
+            let alternatives: Vec<String> = group.iter().map(|a| word_or_phrase(a)).collect();
+            match (dialect, alternatives.as_slice()) {
+                (_, []) => {},
+                (QueryDialect::Full, [_, _, ..]) => {
+                    parts.push(format!("({})", alternatives.join(" OR ")))
+                },
+                (_, [first, ..]) => parts.push(first.clone()),
+            }
+        }
+        if !keywords {
+            let sites: Vec<String> =
+                self.sites.iter().map(|s| format!("site:{}", s.trim())).collect();
+            match (dialect, sites.as_slice()) {
+                (_, []) => {},
+                (QueryDialect::Full, [_, _, ..]) => parts.push(format!("({})", sites.join(" OR "))),
+                (_, [first, ..]) => parts.push(first.clone()),
+            }
+            if dialect == QueryDialect::Full
+                && let Some(extension) = &self.filetype
+            {
+                parts.push(format!("filetype:{}", extension.trim().trim_start_matches('.')));
+            }
+            parts.extend(self.exclude.iter().map(|e| format!("-{}", word_or_phrase(e))));
+        }
+        parts.retain(|p| !p.is_empty() && p != "\"\"" && p != "-");
+        parts.join(" ")
+    }
+
+    fn add(&mut self, atom: Atom) {
+        if atom.negated {
+            self.exclude.push(atom.text);
+        } else if atom.quoted {
+            self.phrases.push(atom.text);
+        } else if let Some(site) = atom.operand("site:") {
+            self.sites.push(site);
+        } else if let Some(extension) = atom.operand("filetype:").or_else(|| atom.operand("ext:")) {
+            self.filetype = Some(extension);
+        } else {
+            self.terms.push(atom.text);
+        }
+    }
+}
+
+impl fmt::Display for QueryBuilder {
+    /// The query in [`QueryDialect::Full`] syntax
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(QueryDialect::Full))
+    }
+}
+
+/// `query` as an engine speaking `dialect` should get it. Queries without
+/// operators, and every query for [`QueryDialect::Full`], come back unchanged.
+pub fn for_dialect(query: &str, dialect: QueryDialect) -> Cow<'_, str> {
+    if dialect == QueryDialect::Full || !has_operators(query) {
+        return Cow::Borrowed(query);
+    }
+    Cow::Owned(QueryBuilder::parse(query).render(dialect))
+}
+
+/// Deserialize a query given as text or as a [`QueryBuilder`] object into its
+/// text, for `#[serde(deserialize_with)]`.
+pub fn deserialize_query<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum QueryInput {
+        Text(String),
+        Parts(QueryBuilder),
+    }
+    Ok(match QueryInput::deserialize(deserializer)? {
+        QueryInput::Text(text) => text,
+        QueryInput::Parts(parts) => parts.to_string(),
+    })
+}
+
+fn has_operators(query: &str) -> bool {
+    query.contains(['"', '(', ')', '|'])
+        || query.split_whitespace().any(|word| {
+            word == "OR"
+                || (word.len() > 1 && word.starts_with('-'))
+                || ["site:", "filetype:", "ext:"].iter().any(|op| word.starts_with(op))
+        })
+}
+
+/// A word or phrase of a parsed query
+#[derive(Debug)]
+struct Atom {
+    text: String,
+    quoted: bool,
+    negated: bool,
+}
+
+impl Atom {
+    /// What follows `operator` in an unquoted, non-negated `operator:value` word
+    fn operand(&self, operator: &str) -> Option<String> {
+        let value = self.text.strip_prefix(operator).filter(|_| !self.quoted && !self.negated)?;
+        (!value.is_empty()).then(|| value.to_string())
+    }
+}
+
+#[derive(Debug)]
+enum Lexeme {
+    Atom(Atom),
+    Open,
+    Close,
+    Or,
+}
+
+fn lex(query: &str) -> Vec<Lexeme> {
+    let mut lexemes = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            },
+            '(' | ')' | '|' => {
+                chars.next();
+                lexemes.push(match c {
+                    '(' => Lexeme::Open,
+                    ')' => Lexeme::Close,
+                    _ => Lexeme::Or,
+                });
+            },
+            _ => {
+                let negated = c == '-';
+                if negated {
+                    chars.next();
+                }
+                let quoted = chars.peek() == Some(&'"');
+                let text: String = if quoted {
+                    chars.next();
+                    chars.by_ref().take_while(|&c| c != '"').collect()
+                } else {
+                    let mut word = String::new();
+                    while let Some(&c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                        if c == '(' || c == ')' {
+                            break;
+                        }
+                        word.push(c);
+                        chars.next();
+                    }
+                    word
+                };
+                let text = text.trim().to_string();
+                if !quoted && !negated && text == "OR" {
+                    lexemes.push(Lexeme::Or);
+                } else if !text.is_empty() {
+                    lexemes.push(Lexeme::Atom(Atom {
+                        text,
+                        quoted,
+                        negated,
+                    }));
+                }
+            },
+        }
+    }
+    lexemes
+}
+
+/// Add a parenthesized group or a lone atom: joined to the previous item after
+/// an `OR`, otherwise as alternatives of their own (`alternatives`) or one
+/// item per atom.
+fn push_group(
+    items: &mut Vec<Vec<Atom>>,
+    atoms: Vec<Atom>,
+    alternatives: bool,
+    pending_or: &mut bool,
+) {
+    if atoms.is_empty() {
+        return;
+    }
+    if !alternatives {
+        items.extend(atoms.into_iter().map(|a| vec![a]));
+    } else if std::mem::take(pending_or)
+        && let Some(last) = items.last_mut()
+    {
+        last.extend(atoms);
+    } else {
+        items.push(atoms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_query() -> QueryBuilder {
+        QueryBuilder::new()
+            .term("rust")
+            .phrase("borrow checker")
+            .site("doc.rust-lang.org")
+            .site("github.com")
+            .exclude("nightly")
+            .exclude("async trait")
+            .any_of(["error", "lifetime bound"])
+            .filetype(".pdf")
+    }
+
+    #[test]
+    fn test_render_dialects() {
+        let query = full_query();
+        assert_eq!(
+            query.render(QueryDialect::Full),
+            r#""borrow checker" rust (error OR "lifetime bound") (site:doc.rust-lang.org OR site:github.com) filetype:pdf -nightly -"async trait""#
+        );
+        assert_eq!(
+            query.render(QueryDialect::Basic),
+            r#""borrow checker" rust error site:doc.rust-lang.org -nightly -"async trait""#
+        );
+        assert_eq!(query.render(QueryDialect::Keywords), "borrow checker rust error");
+        assert_eq!(QueryBuilder::new().render(QueryDialect::Full), "");
+        assert!(QueryBuilder::new().is_empty() && !query.is_empty());
+    }
+
+    #[test]
+    fn test_parse_round_trips_rendered_queries() {
+        let query = full_query().filetype("pdf");
+        assert_eq!(QueryBuilder::parse(&query.to_string()), query);
+    }
+
+    #[test]
+    fn test_parse_typed_queries() {
+        let typed = r#"tokio OR async-std -"old docs" intitle:runtime ext:md"#;
+        let parsed = QueryBuilder::parse(typed);
+        assert_eq!(parsed.any_of, vec![vec!["tokio", "async-std"]]);
+        assert_eq!(parsed.exclude, vec!["old docs"]);
+        assert_eq!(parsed.terms, vec!["intitle:runtime"]);
+        assert_eq!(parsed.filetype.as_deref(), Some("md"));
+
+        let grouped = QueryBuilder::parse("(a b) | c (unclosed");
+        assert_eq!(grouped.terms, vec!["a", "unclosed"]);
+        assert_eq!(grouped.any_of, vec![vec!["b", "c"]]);
+    }
+
+    #[test]
+    fn test_for_dialect_leaves_plain_queries_alone() {
+        assert!(matches!(for_dialect("rust  async", QueryDialect::Keywords), Cow::Borrowed(_)));
+        assert!(matches!(for_dialect("site:x.org rust", QueryDialect::Full), Cow::Borrowed(_)));
+        assert_eq!(for_dialect("rust site:docs.rs -nightly", QueryDialect::Keywords), "rust");
+        assert_eq!(for_dialect("async-std", QueryDialect::Basic), "async-std");
+    }
+
+    #[test]
+    fn test_deserialize_text_or_parts() {
+        #[derive(Deserialize)]
+        struct Args {
+            #[serde(deserialize_with = "deserialize_query")]
+            query: String,
+        }
+        let text: Args = serde_json::from_value(serde_json::json!({"query": "rust"})).unwrap();
+        assert_eq!(text.query, "rust");
+        let parts: Args = serde_json::from_value(serde_json::json!({
+            "query": {"terms": ["tokio"], "sites": ["docs.rs"], "filetype": "pdf"}
+        }))
+        .unwrap();
+        assert_eq!(parts.query, "tokio site:docs.rs filetype:pdf");
+    }
+}
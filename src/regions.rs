@@ -0,0 +1,280 @@
+//! Search regions understood by DuckDuckGo's `kl` parameter.
+//!
+//! Codes are `country-language` (`us-en`, `ch-fr`), with `wt-wt` meaning no
+//! region. DuckDuckGo silently ignores codes it does not know, so requests are
+//! checked against this list first and rejected with suggestions instead.
+
+use crate::types::{DaedraError, DaedraResult};
+use serde::Serialize;
+
+/// A supported region code and its human-readable name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Region {
+    /// Region code sent to the backends, e.g. `us-en`
+    pub code: &'static str,
+    /// Human-readable name, e.g. `United States`
+    pub name: &'static str,
+}
+
+const fn region(code: &'static str, name: &'static str) -> Region {
+    Region { code, name }
+}
+
+/// Every region DuckDuckGo accepts, sorted by code.
+pub const REGIONS: &[Region] = &[
+    region("ar-es", "Argentina"),
+    region("at-de", "Austria"),
+    region("au-en", "Australia"),
+    region("be-fr", "Belgium (fr)"),
+    region("be-nl", "Belgium (nl)"),
+    region("bg-bg", "Bulgaria"),
+    region("br-pt", "Brazil"),
+    region("ca-en", "Canada"),
+    region("ca-fr", "Canada (fr)"),
+    region("ch-de", "Switzerland (de)"),
+    region("ch-fr", "Switzerland (fr)"),
+    region("ch-it", "Switzerland (it)"),
+    region("cl-es", "Chile"),
+    region("cn-zh", "China"),
+    region("co-es", "Colombia"),
+    region("ct-ca", "Catalonia"),
+    region("cz-cs", "Czech Republic"),
+    region("de-de", "Germany"),
+    region("dk-da", "Denmark"),
+    region("ee-et", "Estonia"),
+    region("es-es", "Spain"),
+    region("fi-fi", "Finland"),
+    region("fr-fr", "France"),
+    region("gr-el", "Greece"),
+    region("hk-tzh", "Hong Kong"),
+    region("hr-hr", "Croatia"),
+    region("hu-hu", "Hungary"),
+    region("id-en", "Indonesia (en)"),
+    region("id-id", "Indonesia"),
+    region("ie-en", "Ireland"),
+    region("il-he", "Israel"),
+    region("in-en", "India"),
+    region("it-it", "Italy"),
+    region("jp-jp", "Japan"),
+    region("kr-kr", "Korea"),
+    region("lt-lt", "Lithuania"),
+    region("lv-lv", "Latvia"),
+    region("mx-es", "Mexico"),
+    region("my-en", "Malaysia (en)"),
+    region("my-ms", "Malaysia"),
+    region("nl-nl", "Netherlands"),
+    region("no-no", "Norway"),
+    region("nz-en", "New Zealand"),
+    region("pe-es", "Peru"),
+    region("ph-en", "Philippines"),
+    region("ph-tl", "Philippines (tl)"),
+    region("pl-pl", "Poland"),
+    region("pt-pt", "Portugal"),
+    region("ro-ro", "Romania"),
+    region("ru-ru", "Russia"),
+    region("se-sv", "Sweden"),
+    region("sg-en", "Singapore"),
+    region("sk-sk", "Slovakia"),
+    region("sl-sl", "Slovenia"),
+    region("th-th", "Thailand"),
+    region("tr-tr", "Turkey"),
+    region("tw-tzh", "Taiwan"),
+    region("ua-uk", "Ukraine"),
+    region("ue-es", "United States (es)"),
+    region("uk-en", "United Kingdom"),
+    region("us-en", "United States"),
+    region("ve-es", "Venezuela"),
+    region("vn-vi", "Vietnam"),
+    region("wt-wt", "No region"),
+    region("xa-ar", "Arabia"),
+    region("xa-en", "Arabia (en)"),
+    region("xl-es", "Latin America"),
+    region("za-en", "South Africa"),
+];
+
+/// Look up a region code (case-insensitive).
+pub fn find_region(code: &str) -> Option<&'static Region> {
+    REGIONS.iter().find(|r| r.code.eq_ignore_ascii_case(code))
+}
+
+/// Known codes close to `code`: the same pair swapped (`en-us`), or sharing its
+/// country or language part. At most five.
+pub fn suggest_regions(code: &str) -> Vec<&'static str> {
+    let code = code.trim().to_lowercase();
+    let (first, second) = code.split_once(['-', '_']).unwrap_or((code.as_str(), ""));
+    let swapped = format!("{second}-{first}");
+
+    let mut suggestions: Vec<&'static str> =
+        REGIONS.iter().filter(|r| r.code == swapped).map(|r| r.code).collect();
+    for r in REGIONS {
+        let (country, lang) = r.code.split_once('-').unwrap_or((r.code, ""));
+        let related = [first, second]
+            .iter()
+            .any(|part| !part.is_empty() && (*part == country || *part == lang));
+        if related && r.code != "wt-wt" && !suggestions.contains(&r.code) {
+            suggestions.push(r.code);
+        }
+    }
+    suggestions.truncate(5);
+    suggestions
+}
+
+/// DuckDuckGo language parts that aren't ISO 639-1 codes
+const LANGUAGE_FIXES: &[(&str, &str)] = &[("jp", "ja"), ("kr", "ko"), ("tzh", "zh")];
+
+/// DuckDuckGo country parts that aren't ISO 3166-1 codes; `None` for regions
+/// spanning several countries
+const COUNTRY_FIXES: &[(&str, Option<&str>)] = &[
+    ("ct", Some("ES")),
+    ("sl", Some("SI")),
+    ("ue", Some("US")),
+    ("uk", Some("GB")),
+    ("xa", None),
+    ("xl", Some("419")),
+];
+
+/// The language and country a region code stands for, in standard codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// ISO 639-1 language, e.g. `en`
+    pub language: String,
+    /// Uppercase ISO 3166-1 country (`GB`) or UN M49 area (`419`); `None` for
+    /// regions spanning several countries
+    pub country: Option<String>,
+}
+
+impl Locale {
+    /// BCP 47 tag, e.g. `en-GB`
+    pub fn tag(&self) -> String {
+        match &self.country {
+            Some(country) => format!("{}-{}", self.language, country),
+            None => self.language.clone(),
+        }
+    }
+
+    /// Lowercase two-letter country code, as country parameters of search
+    /// APIs take it (`gb`); `None` for areas and multi-country regions
+    pub fn country_code(&self) -> Option<String> {
+        self.country
+            .as_deref()
+            .filter(|c| c.len() == 2 && c.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(str::to_lowercase)
+    }
+}
+
+/// Locale of a region code (`uk-en` → `en` in `GB`), or `None` for `wt-wt`
+/// and unknown codes.
+pub fn locale(code: &str) -> Option<Locale> {
+    let region = find_region(code).filter(|r| r.code != "wt-wt")?;
+    let (country, lang) = region.code.split_once('-')?;
+    let lang = LANGUAGE_FIXES.iter().find(|(from, _)| *from == lang).map_or(lang, |(_, to)| *to);
+    let country = match COUNTRY_FIXES.iter().find(|(from, _)| *from == country) {
+        Some((_, fixed)) => fixed.map(str::to_string),
+        None => Some(country.to_uppercase()),
+    };
+    Some(Locale {
+        language: lang.to_string(),
+        country,
+    })
+}
+
+/// BCP 47 language tag for a region code (`uk-en` → `en-GB`, `xa-ar` → `ar`),
+/// or `None` for `wt-wt` and unknown codes.
+pub fn language_tag(code: &str) -> Option<String> {
+    locale(code).map(|l| l.tag())
+}
+
+/// `Accept-Language` value asking for a region's language, then the language
+/// anywhere (`de-de` → `de-DE,de;q=0.9`); `None` for `wt-wt` and unknown codes.
+pub fn accept_language(code: &str) -> Option<String> {
+    let tag = language_tag(code)?;
+    Some(match tag.split_once('-') {
+        Some((lang, _)) => format!("{},{};q=0.9", tag, lang),
+        None => tag,
+    })
+}
+
+/// Check `code` against [`REGIONS`], naming close matches when it is unknown.
+pub fn validate_region(code: &str) -> DaedraResult<()> {
+    if find_region(code).is_some() {
+        return Ok(());
+    }
+    let suggestions = suggest_regions(code);
+    let hint = if suggestions.is_empty() {
+        "run `daedra regions` for the full list".to_string()
+    } else {
+        format!("did you mean {}?", suggestions.join(", "))
+    };
+    Err(DaedraError::InvalidArguments(format!(
+        "Unknown region '{}': {}",
+        code, hint
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions_sorted_and_unique() {
+        assert!(REGIONS.windows(2).all(|w| w[0].code < w[1].code));
+    }
+
+    #[test]
+    fn test_known_regions_validate() {
+        assert!(validate_region("wt-wt").is_ok());
+        assert!(validate_region("us-en").is_ok());
+        assert!(validate_region("DE-DE").is_ok());
+        assert_eq!(find_region("uk-en").unwrap().name, "United Kingdom");
+    }
+
+    #[test]
+    fn test_language_tags() {
+        assert_eq!(language_tag("de-de").as_deref(), Some("de-DE"));
+        assert_eq!(language_tag("UK-EN").as_deref(), Some("en-GB"));
+        assert_eq!(language_tag("jp-jp").as_deref(), Some("ja-JP"));
+        assert_eq!(language_tag("tw-tzh").as_deref(), Some("zh-TW"));
+        assert_eq!(language_tag("xl-es").as_deref(), Some("es-419"));
+        assert_eq!(language_tag("xa-ar").as_deref(), Some("ar"));
+        assert_eq!(language_tag("wt-wt"), None);
+        assert_eq!(language_tag("qq-qq"), None);
+        for region in REGIONS.iter().filter(|r| r.code != "wt-wt") {
+            let tag = language_tag(region.code).unwrap();
+            assert!(tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'), "{tag}");
+        }
+    }
+
+    #[test]
+    fn test_locale_country_codes() {
+        let uk = locale("uk-en").unwrap();
+        assert_eq!((uk.language.as_str(), uk.country_code().as_deref()), ("en", Some("gb")));
+        assert_eq!(locale("xl-es").unwrap().country_code(), None);
+        assert_eq!(locale("xa-ar").unwrap().country_code(), None);
+        assert_eq!(locale("wt-wt"), None);
+    }
+
+    #[test]
+    fn test_accept_language() {
+        assert_eq!(accept_language("ch-fr").as_deref(), Some("fr-CH,fr;q=0.9"));
+        assert_eq!(accept_language("xa-en").as_deref(), Some("en"));
+        assert_eq!(accept_language("wt-wt"), None);
+    }
+
+    #[test]
+    fn test_unknown_region_suggests_swapped_pair_first() {
+        assert_eq!(suggest_regions("en-us").first(), Some(&"us-en"));
+        let err = validate_region("en-us").unwrap_err().to_string();
+        assert!(err.contains("Unknown region 'en-us'"), "{err}");
+        assert!(err.contains("us-en"), "{err}");
+    }
+
+    #[test]
+    fn test_suggestions_share_country_or_language() {
+        let ch = suggest_regions("ch");
+        assert_eq!(ch, vec!["ch-de", "ch-fr", "ch-it"]);
+        assert!(suggest_regions("zh-cn").contains(&"cn-zh"));
+        assert!(suggest_regions("qq-qq").is_empty());
+        let err = validate_region("qq-qq").unwrap_err().to_string();
+        assert!(err.contains("daedra regions"), "{err}");
+    }
+}
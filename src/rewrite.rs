@@ -0,0 +1,316 @@
+//! Rewriting queries before they reach the backends.
+//!
+//! Agents often search with the question they were asked ("How do I read a
+//! file in Rust?"), which keyword engines match poorly. A [`QueryRewriter`]
+//! turns the query into something better suited to search; the rewriters in
+//! [`ServerConfig::query_rewriters`](crate::ServerConfig::query_rewriters) run
+//! in order on every search. Responses keep the query as the caller wrote it
+//! in `metadata.query` and report what was searched in
+//! `metadata.rewritten_query`.
+//!
+//! The built-in rewriters only touch plain words: quoted phrases, `-exclusions`
+//! and operators such as `site:` pass through unchanged.
+
+use crate::types::{DaedraError, DaedraResult};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Leading phrases that make a query a question, longest first
+const QUESTION_LEAD_INS: &[&str] = &[
+    "what is the best way to",
+    "is it possible to",
+    "is there a way to",
+    "where can i find",
+    "when should i",
+    "how would i",
+    "how can i",
+    "how do you",
+    "how do i",
+    "what are the",
+    "what is the",
+    "what is a",
+    "where can i",
+    "how does",
+    "how to",
+    "what are",
+    "what is",
+    "what's",
+    "why does",
+    "why is",
+    "can i",
+    "should i",
+];
+
+/// English words too common to narrow a search
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "do", "does", "for", "from", "i", "in",
+    "into", "is", "it", "me", "my", "of", "on", "or", "that", "the", "this", "to", "was", "with",
+];
+
+/// A step that rewrites a search query.
+pub trait QueryRewriter: Send + Sync + fmt::Debug {
+    /// Name for logs
+    fn name(&self) -> &str;
+
+    /// `query` rewritten; returning it unchanged is fine.
+    fn rewrite(&self, query: &str) -> String;
+}
+
+/// Run `rewriters` over `query` in order, returning the result when it differs
+/// from `query`. A step that would leave nothing to search is skipped.
+pub fn rewrite(rewriters: &[Arc<dyn QueryRewriter>], query: &str) -> Option<String> {
+    let mut current = query.trim().to_string();
+    for rewriter in rewriters {
+        let next = rewriter.rewrite(&current);
+        let next = next.trim();
+        if next.is_empty() {
+            debug!(rewriter = rewriter.name(), "Query rewriter left nothing; skipped");
+        } else if next != current {
+            current = next.to_string();
+        }
+    }
+    (current != query.trim()).then_some(current)
+}
+
+/// Strips question lead-ins and question marks: `How do I read a file in
+/// Rust?` becomes `read a file in Rust`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuestionToKeywords;
+
+impl QueryRewriter for QuestionToKeywords {
+    fn name(&self) -> &str {
+        "question-keywords"
+    }
+
+    fn rewrite(&self, query: &str) -> String {
+        let mut tokens = tokens(query);
+        let leading: Vec<String> = tokens
+            .iter()
+            .take_while(|t| is_plain(t))
+            .map(|t| t.trim_end_matches('?').to_lowercase())
+            .collect();
+        let lead_in = QUESTION_LEAD_INS.iter().find_map(|phrase| {
+            let words: Vec<&str> = phrase.split(' ').collect();
+            let matches = words.len() < tokens.len()
+                && words.iter().zip(&leading).all(|(w, t)| *w == t.as_str())
+                && leading.len() >= words.len();
+            matches.then_some(words.len())
+        });
+        tokens.drain(..lead_in.unwrap_or(0));
+        for token in tokens.iter_mut().filter(|t| is_plain(t)) {
+            let trimmed = token.trim_end_matches('?').len();
+            token.truncate(trimmed);
+        }
+        join(tokens)
+    }
+}
+
+/// Drops common English words ([`STOPWORDS`]) unless the query is nothing but.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StopwordPruner;
+
+impl QueryRewriter for StopwordPruner {
+    fn name(&self) -> &str {
+        "stopwords"
+    }
+
+    fn rewrite(&self, query: &str) -> String {
+        let tokens = tokens(query);
+        let kept: Vec<String> = tokens
+            .iter()
+            .filter(|t| !is_plain(t) || !STOPWORDS.contains(&t.to_lowercase().as_str()))
+            .cloned()
+            .collect();
+        if kept.is_empty() { join(tokens) } else { join(kept) }
+    }
+}
+
+/// Expands words with their synonyms as OR groups: with `k8s` and
+/// `kubernetes` in a group, `k8s ingress` becomes `(k8s OR kubernetes) ingress`.
+///
+/// Backends without `OR` get the word as written (see
+/// [`QueryDialect`](crate::query::QueryDialect)).
+#[derive(Debug, Clone, Default)]
+pub struct SynonymExpander {
+    groups: Vec<Vec<String>>,
+}
+
+impl SynonymExpander {
+    /// An expander for `groups` of interchangeable words
+    pub fn new(groups: Vec<Vec<String>>) -> Self {
+        let groups = groups
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|word| word.trim().to_string())
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| group.len() > 1)
+            .collect();
+        Self { groups }
+    }
+
+    /// Load groups from `path`: one group per line, words separated by commas;
+    /// `#` starts a comment.
+    pub fn load_file(path: &Path) -> DaedraResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            DaedraError::InvalidArguments(format!(
+                "Cannot read synonyms {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let groups = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .map(|line| line.split(',').map(str::to_string).collect())
+            .collect();
+        Ok(Self::new(groups))
+    }
+
+    /// Number of synonym groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether there are no synonym groups
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+impl QueryRewriter for SynonymExpander {
+    fn name(&self) -> &str {
+        "synonyms"
+    }
+
+    fn rewrite(&self, query: &str) -> String {
+        let expanded = tokens(query).into_iter().map(|token| {
+            let group = self
+                .groups
+                .iter()
+                .find(|g| is_plain(&token) && g.iter().any(|w| w.eq_ignore_ascii_case(&token)));
+            match group {
+                Some(group) => {
+                    let others = group.iter().filter(|w| !w.eq_ignore_ascii_case(&token));
+                    let alternatives: Vec<String> = std::iter::once(token.clone())
+                        .chain(others.map(|w| quote_if_spaced(w)))
+                        .collect();
+                    format!("({})", alternatives.join(" OR "))
+                },
+                None => token,
+            }
+        });
+        join(expanded.collect())
+    }
+}
+
+/// Whitespace-separated tokens of `query`, keeping quoted phrases whole
+fn tokens(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Whether `token` is a plain word rather than a phrase, exclusion or operator
+fn is_plain(token: &str) -> bool {
+    token != "OR" && !token.starts_with('-') && !token.contains(['"', ':', '(', ')', '|'])
+}
+
+fn quote_if_spaced(word: &str) -> String {
+    if word.contains(char::is_whitespace) {
+        format!("\"{}\"", word)
+    } else {
+        word.to_string()
+    }
+}
+
+fn join(tokens: Vec<String>) -> String {
+    tokens.into_iter().filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(rewriters: Vec<Arc<dyn QueryRewriter>>, query: &str) -> Option<String> {
+        rewrite(&rewriters, query)
+    }
+
+    #[test]
+    fn test_question_to_keywords() {
+        let q = QuestionToKeywords;
+        assert_eq!(q.rewrite("How do I read a file in Rust?"), "read a file in Rust");
+        assert_eq!(q.rewrite("what is the borrow checker"), "borrow checker");
+        assert_eq!(q.rewrite("how to"), "how to");
+        assert_eq!(q.rewrite("tokio select"), "tokio select");
+        assert_eq!(
+            q.rewrite(r#"how to "exit vim?" site:unix.stackexchange.com"#),
+            r#""exit vim?" site:unix.stackexchange.com"#
+        );
+    }
+
+    #[test]
+    fn test_stopwords_keep_phrases_and_all_stopword_queries() {
+        let s = StopwordPruner;
+        assert_eq!(s.rewrite(r#"read a file in "the book" -the"#), r#"read file "the book" -the"#);
+        assert_eq!(s.rewrite("to be or not to be"), "not");
+        assert_eq!(s.rewrite("the it"), "the it");
+    }
+
+    #[test]
+    fn test_synonyms_become_or_groups() {
+        let expander = SynonymExpander::new(vec![
+            vec!["k8s".into(), "kubernetes".into()],
+            vec!["js".into(), "javascript".into(), "node js".into()],
+            vec!["lonely".into()],
+        ]);
+        assert_eq!(expander.len(), 2);
+        assert_eq!(expander.rewrite("K8s ingress"), "(K8s OR kubernetes) ingress");
+        assert_eq!(expander.rewrite("js -k8s"), r#"(js OR javascript OR "node js") -k8s"#);
+    }
+
+    #[test]
+    fn test_chain_reports_only_changes() {
+        let rewriters: Vec<Arc<dyn QueryRewriter>> =
+            vec![Arc::new(QuestionToKeywords), Arc::new(StopwordPruner)];
+        assert_eq!(
+            chain(rewriters.clone(), "How do I read a file in Rust?").as_deref(),
+            Some("read file Rust")
+        );
+        assert_eq!(chain(rewriters, "tokio runtime"), None);
+        assert_eq!(chain(Vec::new(), "anything"), None);
+    }
+
+    #[test]
+    fn test_load_synonyms_file() {
+        let path =
+            std::env::temp_dir().join(format!("daedra-synonyms-{}.txt", std::process::id()));
+        std::fs::write(&path, "# groups\nk8s, kubernetes\npg,postgres, postgresql # db\n").unwrap();
+        let expander = SynonymExpander::load_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(expander.len(), 2);
+        assert_eq!(expander.rewrite("pg"), "(pg OR postgres OR postgresql)");
+        assert!(SynonymExpander::load_file(Path::new("/nonexistent/synonyms.txt")).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Limiting which domains tools may search and read.
+//!
+//! A [`DomainPolicy`] holds two lists of host globs, where `*` stands for any
+//! run of characters: `docs.example.com`, `*.internal-docs.example.com`,
+//! `*.gov`. With an allowlist, only hosts matching one of its globs may be
+//! read; a host matching the denylist never may, whatever the allowlist says.
+//! A glob matches the whole host, so `example.com` doesn't cover
+//! `www.example.com` — list `*.example.com` as well for that.
+//!
+//! The server drops search results the policy rules out and refuses to fetch
+//! such pages, including when an allowed page redirects to one
+//! ([`DaedraError::DomainNotAllowed`]). Unlike the spam
+//! [`blocklist`](crate::blocklist), it is meant to sandbox an agent to the
+//! sites an operator trusts.
+
+use crate::types::{DaedraError, DaedraResult, SearchResult};
+use url::Url;
+
+/// Allowed and denied host globs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomainPolicy {
+    allowed: Vec<String>,
+    denied: Vec<String>,
+}
+
+impl DomainPolicy {
+    /// A policy allowing hosts that match `allowed` (every host when it is
+    /// empty) unless they match `denied`. Globs are case-insensitive and may
+    /// name internationalized hosts in Unicode.
+    pub fn new(allowed: &[String], denied: &[String]) -> DaedraResult<Self> {
+        let globs = |list: &[String]| -> DaedraResult<Vec<String>> {
+            list.iter().filter(|g| !g.trim().is_empty()).map(|g| normalize_glob(g)).collect()
+        };
+        Ok(Self {
+            allowed: globs(allowed)?,
+            denied: globs(denied)?,
+        })
+    }
+
+    /// Whether the policy lets everything through
+    pub fn is_open(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Whether `host` (in punycode, as [`Url::host_str`] gives it) may be read
+    pub fn permits_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let matches = |glob: &String| glob_matches(glob, &host);
+        !self.denied.iter().any(matches)
+            && (self.allowed.is_empty() || self.allowed.iter().any(matches))
+    }
+
+    /// Whether `url` may be read; a URL without a host may not, unless the
+    /// policy is open
+    pub fn permits(&self, url: &str) -> bool {
+        self.is_open() || Url::parse(url).is_ok_and(|u| self.permits_url(&u))
+    }
+
+    /// [`permits`](Self::permits) for a parsed URL
+    pub fn permits_url(&self, url: &Url) -> bool {
+        self.is_open() || url.host_str().is_some_and(|host| self.permits_host(host))
+    }
+
+    /// An error naming `url`'s host unless the policy permits it
+    pub fn check(&self, url: &str) -> DaedraResult<()> {
+        if self.permits(url) {
+            return Ok(());
+        }
+        let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string));
+        Err(DaedraError::DomainNotAllowed(host.unwrap_or_else(|| url.to_string())))
+    }
+
+    /// Remove results the policy rules out, returning how many were removed.
+    pub fn filter(&self, results: &mut Vec<SearchResult>) -> usize {
+        let before = results.len();
+        results.retain(|r| self.permits(&r.url));
+        before - results.len()
+    }
+}
+
+/// `glob` lowercased, in punycode, without a trailing dot
+pub(crate) fn normalize_glob(glob: &str) -> DaedraResult<String> {
+    let invalid = || DaedraError::InvalidArguments(format!("Invalid domain glob: {glob}"));
+    let glob = glob.trim().trim_end_matches('.');
+    let labels = glob
+        .split('.')
+        .map(|label| {
+            if label.is_ascii() {
+                Ok(label.to_ascii_lowercase())
+            } else if label.contains('*') {
+                Err(invalid())
+            } else {
+                idna::domain_to_ascii(label).map_err(|_| invalid())
+            }
+        })
+        .collect::<DaedraResult<Vec<_>>>()?;
+    let glob = labels.join(".");
+    let valid = !glob.is_empty()
+        && glob.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '*' | '_'));
+    valid.then_some(glob).ok_or_else(invalid)
+}
+
+/// Whether `host` matches `glob` as a whole, `*` standing for any run of characters
+pub(crate) fn glob_matches(glob: &str, host: &str) -> bool {
+    let parts: Vec<&str> = glob.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else {
+        return glob == host;
+    };
+    if host.len() < first.len() + last.len() || !host.starts_with(first) || !host.ends_with(last)
+    {
+        return false;
+    }
+    let mut rest = &host[first.len()..host.len() - last.len()];
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ResultMetadata;
+
+    fn policy(allowed: &[&str], denied: &[&str]) -> DomainPolicy {
+        let list = |l: &[&str]| l.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        DomainPolicy::new(&list(allowed), &list(denied)).unwrap()
+    }
+
+    #[test]
+    fn test_glob_matches_whole_host() {
+        assert!(glob_matches("example.com", "example.com"));
+        assert!(!glob_matches("example.com", "www.example.com"));
+        assert!(glob_matches("*.example.com", "a.b.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(!glob_matches("*.example.com", "badexample.com"));
+        assert!(glob_matches("docs-*.example.*", "docs-v2.example.org"));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(glob_matches("*", "anything.at.all"));
+    }
+
+    #[test]
+    fn test_allowlist_and_denylist() {
+        let sandbox = policy(&["*.internal-docs.example.com", "Docs.RS"], &["secret.*"]);
+        assert!(sandbox.permits("https://api.internal-docs.example.com/v1"));
+        assert!(sandbox.permits("https://docs.rs/tokio"));
+        assert!(sandbox.permits("https://docs.rs./tokio"));
+        assert!(!sandbox.permits("https://secret.internal-docs.example.com/"));
+        assert!(!sandbox.permits("https://example.com/"));
+        assert!(!sandbox.permits("not a url"));
+        let err = sandbox.check("https://evil.example/x").unwrap_err();
+        assert!(matches!(&err, DaedraError::DomainNotAllowed(host) if host == "evil.example"));
+
+        let open = DomainPolicy::default();
+        assert!(open.is_open());
+        assert!(open.permits("not a url"));
+        let denied = policy(&[], &["*.example.com"]);
+        assert!(denied.permits("https://example.org/"));
+        assert!(!denied.permits("https://www.example.com/"));
+    }
+
+    #[test]
+    fn test_globs_are_normalized() {
+        assert!(policy(&["bücher.de"], &[]).permits("https://xn--bcher-kva.de/"));
+        let err = DomainPolicy::new(&["exa mple.com".to_string()], &[]).unwrap_err();
+        assert!(err.to_string().contains("Invalid domain glob"), "{err}");
+        assert!(DomainPolicy::new(&["*.bü*.de".to_string()], &[]).is_err());
+        assert!(DomainPolicy::new(&[" ".to_string()], &[]).unwrap().is_open());
+    }
+
+    #[test]
+    fn test_filter_removes_results_outside_the_policy() {
+        let result = |url: &str| SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            metadata: ResultMetadata::default(),
+        };
+        let mut results = vec![result("https://docs.rs/a"), result("https://blog.example/b")];
+        assert_eq!(policy(&["docs.rs"], &[]).filter(&mut results), 1);
+        assert_eq!(results[0].url, "https://docs.rs/a");
+    }
+}
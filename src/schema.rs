@@ -0,0 +1,283 @@
+//! Versions of the JSON shape tool results take.
+//!
+//! Results gain fields as Daedra grows. Each JSON search response and page a
+//! tool returns carries the `schema_version` it was written in,
+//! [`SchemaVersion::CURRENT`] unless the client asked for an older one. Clients
+//! that break on fields they don't know can ask for [`SchemaVersion::V1`], the
+//! shape of the first release: search responses and pages keep only the fields
+//! they had then, content types added since read `other`, and there is no
+//! `schema_version`.
+//!
+//! A client asks when it initializes, under its experimental capabilities:
+//!
+//! ```json
+//! {"capabilities": {"experimental": {"daedra": {"schemaVersion": 1}}}}
+//! ```
+//!
+//! and the server answers with the version it will write, the newest it
+//! supports that isn't newer than the one asked for. Clients that don't ask get
+//! [`ServerConfig::schema_version`](crate::ServerConfig::schema_version).
+
+use crate::types::{DaedraError, DaedraResult, MultiSearchItem, PageContent, SearchResponse};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Fields of a v1 search response
+const V1_RESPONSE: &[&str] = &["type", "data", "metadata"];
+
+/// Fields of a v1 search result
+const V1_RESULT: &[&str] = &["title", "url", "description", "metadata"];
+
+/// Fields of a v1 result's metadata
+const V1_RESULT_METADATA: &[&str] = &["type", "source", "favicon", "published_date"];
+
+/// Fields of v1 search metadata
+const V1_SEARCH_METADATA: &[&str] =
+    &["query", "timestamp", "result_count", "search_context", "query_analysis"];
+
+/// Fields of a v1 search context
+const V1_SEARCH_CONTEXT: &[&str] = &["region", "safe_search", "num_results"];
+
+/// Fields of a v1 page
+const V1_PAGE: &[&str] = &["url", "title", "content", "timestamp", "word_count", "links"];
+
+/// Fields of a v1 page link
+const V1_LINK: &[&str] = &["text", "url"];
+
+/// Content types v1 knew; the ones added since are written as `other`
+const V1_CONTENT_TYPES: &[&str] =
+    &["documentation", "social", "article", "forum", "video", "shopping", "other"];
+
+/// Version of the JSON shape of tool results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SchemaVersion(u32);
+
+impl SchemaVersion {
+    /// The first release's shape
+    pub const V1: Self = Self(1);
+
+    /// Today's shape, with `schema_version` and every field added since v1
+    pub const V2: Self = Self(2);
+
+    /// The version written unless a client or the configuration asks otherwise
+    pub const CURRENT: Self = Self::V2;
+
+    /// Every version the server can write, oldest first
+    pub const SUPPORTED: [Self; 2] = [Self::V1, Self::V2];
+
+    /// The newest supported version that isn't newer than `requested`; the
+    /// oldest when `requested` predates them all
+    pub fn negotiate(requested: u64) -> Self {
+        Self::SUPPORTED
+            .into_iter()
+            .rfind(|version| u64::from(version.0) <= requested)
+            .unwrap_or(Self::V1)
+    }
+
+    /// The version number
+    pub fn number(self) -> u32 {
+        self.0
+    }
+
+    /// `result` as JSON in this version's shape
+    pub fn to_value<T: Versioned>(self, result: &T) -> DaedraResult<Value> {
+        let mut value = serde_json::to_value(result)?;
+        T::reshape(&mut value, self);
+        Ok(value)
+    }
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = DaedraError;
+
+    /// Parse a supported version number, `1` or `2`
+    fn from_str(s: &str) -> DaedraResult<Self> {
+        let s = s.trim();
+        Self::SUPPORTED
+            .into_iter()
+            .find(|version| s.parse() == Ok(version.0))
+            .ok_or_else(|| {
+                DaedraError::InvalidArguments(format!(
+                    "Unsupported schema version {s}; expected 1 to {}",
+                    Self::CURRENT
+                ))
+            })
+    }
+}
+
+/// A tool result whose JSON shape depends on the [`SchemaVersion`]
+pub trait Versioned: Serialize {
+    /// Rewrite `value`, this result's JSON in the current shape, into `version`'s
+    fn reshape(value: &mut Value, version: SchemaVersion);
+}
+
+impl Versioned for SearchResponse {
+    fn reshape(value: &mut Value, version: SchemaVersion) {
+        let Some(response) = value.as_object_mut() else {
+            return;
+        };
+        if version > SchemaVersion::V1 {
+            response.insert("schema_version".to_string(), version.number().into());
+            return;
+        }
+        retain(response, V1_RESPONSE);
+        if let Some(results) = response.get_mut("data").and_then(Value::as_array_mut) {
+            for result in results.iter_mut().filter_map(Value::as_object_mut) {
+                retain(result, V1_RESULT);
+                if let Some(metadata) = result.get_mut("metadata").and_then(Value::as_object_mut) {
+                    retain(metadata, V1_RESULT_METADATA);
+                    if let Some(kind) = metadata.get_mut("type")
+                        && !kind.as_str().is_some_and(|kind| V1_CONTENT_TYPES.contains(&kind))
+                    {
+                        *kind = "other".into();
+                    }
+                }
+            }
+        }
+        if let Some(metadata) = response.get_mut("metadata").and_then(Value::as_object_mut) {
+            retain(metadata, V1_SEARCH_METADATA);
+            if let Some(context) = metadata.get_mut("search_context").and_then(Value::as_object_mut)
+            {
+                retain(context, V1_SEARCH_CONTEXT);
+            }
+        }
+    }
+}
+
+impl Versioned for PageContent {
+    fn reshape(value: &mut Value, version: SchemaVersion) {
+        let Some(page) = value.as_object_mut() else {
+            return;
+        };
+        if version > SchemaVersion::V1 {
+            page.insert("schema_version".to_string(), version.number().into());
+            return;
+        }
+        retain(page, V1_PAGE);
+        if let Some(links) = page.get_mut("links").and_then(Value::as_array_mut) {
+            for link in links.iter_mut().filter_map(Value::as_object_mut) {
+                retain(link, V1_LINK);
+            }
+        }
+    }
+}
+
+impl Versioned for MultiSearchItem {
+    /// The item is unchanged; its response takes `version`'s shape
+    fn reshape(value: &mut Value, version: SchemaVersion) {
+        if let Some(response) = value.get_mut("response") {
+            SearchResponse::reshape(response, version);
+        }
+    }
+}
+
+impl<T: Versioned> Versioned for Vec<T> {
+    fn reshape(value: &mut Value, version: SchemaVersion) {
+        if let Some(items) = value.as_array_mut() {
+            for item in items {
+                T::reshape(item, version);
+            }
+        }
+    }
+}
+
+/// Drop the fields of `object` not named in `fields`
+fn retain(object: &mut Map<String, Value>, fields: &[&str]) {
+    object.retain(|key, _| fields.contains(&key.as_str()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        ContentType, LinkKind, LinkScope, PageLink, ResultMetadata, SearchOptions, SearchResult,
+        VisitPageArgs,
+    };
+
+    fn response() -> SearchResponse {
+        let result = SearchResult {
+            title: "Rust".to_string(),
+            url: "https://www.rust-lang.org/".to_string(),
+            description: "A language empowering everyone".to_string(),
+            metadata: ResultMetadata {
+                content_type: ContentType::Academic,
+                source: "www.rust-lang.org".into(),
+                registered_domain: Some("rust-lang.org".into()),
+                score: Some(0.9),
+                ..Default::default()
+            },
+        };
+        SearchResponse::new("rust".to_string(), vec![result], &SearchOptions::default())
+    }
+
+    #[test]
+    fn test_current_version_is_stamped() {
+        let value = SchemaVersion::CURRENT.to_value(&response()).unwrap();
+        assert_eq!(value["schema_version"], 2);
+        assert_eq!(value["data"][0]["metadata"]["type"], "academic");
+        assert_eq!(value["data"][0]["metadata"]["registered_domain"], "rust-lang.org");
+        assert_eq!(value["data"][0]["metadata"]["position"], 1);
+    }
+
+    #[test]
+    fn test_v1_keeps_first_release_fields() {
+        let value = SchemaVersion::V1.to_value(&response()).unwrap();
+        assert!(value.get("schema_version").is_none());
+        let metadata = value["data"][0]["metadata"].as_object().unwrap();
+        let keys: Vec<&str> = metadata.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["source", "type"]);
+        assert_eq!(metadata["type"], "other");
+        let context = value["metadata"]["search_context"].as_object().unwrap();
+        let keys: Vec<&str> = context.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["num_results", "region", "safe_search"]);
+
+        let mut page = crate::tools::fetch::FetchClient::new()
+            .unwrap()
+            .extract_html(
+                "<html><head><title>T</title><meta name='author' content='A'></head>\
+                 <body><p>Hello</p></body></html>",
+                &VisitPageArgs {
+                    url: "https://example.com/".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        page.links = Some(vec![PageLink {
+            text: "Next".to_string(),
+            url: "https://example.com/2".to_string(),
+            homograph_risk: false,
+            kind: LinkKind::Download,
+            scope: Some(LinkScope::Internal),
+        }]);
+        let value = SchemaVersion::V1.to_value(&page).unwrap();
+        let keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["content", "links", "timestamp", "title", "url", "word_count"]);
+        let link = serde_json::json!({"text": "Next", "url": "https://example.com/2"});
+        assert_eq!(value["links"][0], link);
+    }
+
+    #[test]
+    fn test_negotiate_and_parse() {
+        assert_eq!(SchemaVersion::negotiate(0), SchemaVersion::V1);
+        assert_eq!(SchemaVersion::negotiate(1), SchemaVersion::V1);
+        assert_eq!(SchemaVersion::negotiate(2), SchemaVersion::V2);
+        assert_eq!(SchemaVersion::negotiate(99), SchemaVersion::CURRENT);
+        assert_eq!("1".parse::<SchemaVersion>().unwrap(), SchemaVersion::V1);
+        assert!("3".parse::<SchemaVersion>().is_err());
+        assert!("v1".parse::<SchemaVersion>().is_err());
+    }
+}
@@ -3,38 +3,209 @@
 //! This module provides the core MCP server implementation that handles
 //! tool requests and manages communication via STDIO or SSE transports.
 
-use crate::cache::{CacheConfig, SearchCache};
-use crate::tools::{self, fetch, crawl_site};
+use crate::blocklist::{BlocklistConfig, DomainBlocklist};
+use crate::budget;
+use crate::cache::{CacheConfig, InvalidatedEntries, SearchCache};
+use crate::domains;
+use crate::events::{self, EventBus, LogLevel, ServerEvent, ServerNotification};
+use crate::fingerprint::{FingerprintConfig, Rotation};
+use crate::memory::{self, MemoryConfig, MemoryPressure};
+use crate::privacy::{self, PrivacyConfig};
+use crate::regions;
+use crate::rewrite::{self, QueryRewriter};
+use crate::sandbox::DomainPolicy;
+use crate::schema::{SchemaVersion, Versioned};
+use crate::telemetry::{self, Phase, SlowRequestConfig};
+use crate::tools::cookies::CookieJar;
+use crate::tools::hackernews::HackerNewsClient;
+use crate::tools::stackexchange::StackExchangeBackend;
+use crate::tools::youtube::YouTubeClient;
+use crate::tools::{self, fetch, crawl_site_with, research};
+use crate::watchdog::{self, Health, WatchdogConfig};
 use crate::types::{
-    CrawlArgs, DaedraError, DaedraResult, PageContent, SearchArgs, SearchResponse, SearchResult,
-    VisitPageArgs, crawl_args_schema, search_args_schema, visit_page_args_schema,
+    CacheInvalidateArgs, CheckUrlArgs, CrawlArgs, DaedraError, DaedraResult, DeepResearchArgs,
+    DeepResearchReport, DigestSource, FetchProbe, HackerNewsArgs, MultiSearchArgs,
+    MultiSearchItem, PageContent, PageFormat, PlannedRequest, ResearchDigest, ResearchStep,
+    SafeSearchLevel, SearchAndReadArgs, SearchArgs, SearchOptions, SearchResponse, SearchResult,
+    SearchStrategy, SkippedSource, StackOverflowArgs, Transcript, TranscriptArgs, UrlCheck,
+    VisitPageArgs, cache_invalidate_args_schema, check_url_args_schema, crawl_args_schema,
+    deep_research_args_schema, hackernews_args_schema, multi_search_args_schema,
+    search_and_read_args_schema, search_args_schema, stackoverflow_args_schema,
+    transcript_args_schema, visit_page_args_schema,
 };
 use crate::{SERVER_NAME, VERSION};
+use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::sync::{RwLock, Semaphore};
-use tracing::{debug, error, info, instrument};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::{RwLock, Semaphore, broadcast, mpsc, watch};
+use tracing::{debug, error, info, instrument, warn};
 
 /// MCP Protocol version
 pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
 
+/// Default for [`ServerConfig::max_request_bytes`] (1 MiB)
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// Default for [`ServerConfig::request_timeout`]
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default for [`ServerConfig::max_connections`]
+pub const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+/// Default for [`ServerConfig::idle_timeout`]
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Default for [`ServerConfig::write_timeout`]
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default for [`ServerConfig::sse_keep_alive`]
+pub const DEFAULT_SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// How long an SSE session outlives its connection, waiting for the client to reconnect
+const SSE_SESSION_TTL: Duration = Duration::from_secs(300);
+
+/// Most recent events an SSE session keeps for replay after a reconnect
+const SSE_REPLAY_EVENTS: usize = 256;
+
+/// How many top results `expand_snippets` fetches
+const EXPANDED_SNIPPETS: usize = 5;
+
+/// Length cap of an expanded snippet, in characters
+const EXPANDED_SNIPPET_CHARS: usize = 600;
+
+/// Most results `enrich_top` fetches
+const MAX_ENRICHED_RESULTS: usize = 10;
+
+/// Time limit for each page fetched to improve a snippet
+const SNIPPET_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most results `search_and_read` reads
+const MAX_READ_RESULTS: usize = 10;
+
+/// Time limit for each page `search_and_read` and `deep_research` read
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Most search rounds `deep_research` runs
+const MAX_RESEARCH_DEPTH: usize = 3;
+
+/// Most pages `deep_research` reads
+const MAX_RESEARCH_PAGES: usize = 20;
+
+/// How many top results `probe_fetchability` HEAD-checks
+const PROBED_RESULTS: usize = 5;
+
 /// Transport type for the MCP server
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum TransportType {
     /// Standard input/output transport
     #[default]
     Stdio,
+    /// HTTP, with Server-Sent Events for responses
+    Http(HttpTransportConfig),
     /// Server-Sent Events over HTTP
+    #[deprecated(
+        since = "0.4.0",
+        note = "use `TransportType::Http(HttpTransportConfig::new(host, port))`"
+    )]
     Sse {
         /// Port to listen on
         port: u16,
-        /// Host to bind to
-        host: [u8; 4],
+        /// Host name or IP literal to bind to
+        host: String,
     },
 }
 
+impl TransportType {
+    /// The HTTP options of an HTTP transport, whichever variant names it;
+    /// `None` for STDIO
+    pub fn http_config(&self) -> Option<HttpTransportConfig> {
+        #[allow(deprecated)]
+        match self {
+            TransportType::Stdio => None,
+            TransportType::Http(config) => Some(config.clone()),
+            TransportType::Sse { port, host } => {
+                Some(HttpTransportConfig::new(host.clone(), *port))
+            },
+        }
+    }
+}
+
+impl From<HttpTransportConfig> for TransportType {
+    fn from(config: HttpTransportConfig) -> Self {
+        TransportType::Http(config)
+    }
+}
+
+/// Where and how the HTTP (SSE) transport listens.
+///
+/// Limits and access checks that apply to every transport (request size and
+/// time, allowed `Host` names) stay on [`ServerConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTransportConfig {
+    /// Host name or IP literal to bind to (`127.0.0.1`, `::1`, `localhost`, `::`),
+    /// resolved when the server starts; every resolved address gets a listener
+    pub host: String,
+
+    /// Port to listen on; 0 picks a free one
+    pub port: u16,
+}
+
+impl HttpTransportConfig {
+    /// Listen on `host` and `port`
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+impl Default for HttpTransportConfig {
+    /// Loopback only, on port 3000
+    fn default() -> Self {
+        Self::new("127.0.0.1", 3000)
+    }
+}
+
+/// Bytes reserved up front for a tool result's JSON, enough for a page of
+/// search results without regrowing the buffer
+const JSON_BUFFER_CAPACITY: usize = 16 * 1024;
+
+/// Layout of the JSON that tools return as their text result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonFormat {
+    /// Indented, for people reading the raw output
+    #[default]
+    Pretty,
+    /// No whitespace: smaller and faster to produce, for programs and tight contexts
+    Compact,
+}
+
+impl JsonFormat {
+    /// `value` as JSON in this layout, written into one preallocated buffer
+    pub fn to_string<T: Serialize + ?Sized>(self, value: &T) -> serde_json::Result<String> {
+        telemetry::timed_sync(Phase::Serialize, || {
+            let mut buffer = Vec::with_capacity(JSON_BUFFER_CAPACITY);
+            match self {
+                JsonFormat::Pretty => serde_json::to_writer_pretty(&mut buffer, value)?,
+                JsonFormat::Compact => serde_json::to_writer(&mut buffer, value)?,
+            }
+            String::from_utf8(buffer).map_err(serde::ser::Error::custom)
+        })
+    }
+}
+
 /// Configuration for the Daedra server
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -46,6 +217,135 @@ pub struct ServerConfig {
 
     /// Maximum concurrent tool executions
     pub max_concurrent_tools: usize,
+
+    /// Most pages parsed and converted at once. Extraction runs on Tokio's
+    /// blocking threads rather than the async workers; defaults to one page
+    /// per CPU core.
+    pub extraction_threads: usize,
+
+    /// Backend names to try one at a time, in order (e.g. `["serper", "bing",
+    /// "duckduckgo"]`); blocked or failing backends fall through to the next.
+    /// Empty queries every backend concurrently and merges the results.
+    pub search_chain: Vec<String>,
+
+    /// Default way to combine backends when a request doesn't set
+    /// `options.strategy`; `None` merges, or falls back when `search_chain` is set.
+    /// With a merge strategy, a non-empty `search_chain` limits which backends run.
+    pub search_strategy: Option<SearchStrategy>,
+
+    /// Serper.dev API key; overrides SERPER_API_KEY when set
+    pub serper_api_key: Option<String>,
+
+    /// When to stop querying a failing backend, and for how long
+    pub circuit_breaker: tools::CircuitBreakerConfig,
+
+    /// Watch for runtime stalls and failure storms (see [`watchdog`]); off by default
+    pub watchdog: WatchdogConfig,
+
+    /// Memory cap past which the cache shrinks and large pages are refused
+    /// (see [`crate::memory`]); none by default
+    pub memory: MemoryConfig,
+
+    /// Largest page body fetched; `visit_page` calls may ask for less with
+    /// `max_bytes`
+    pub max_page_bytes: usize,
+
+    /// Per-tool durations past which a call is logged as a warning with its
+    /// timing breakdown (see [`telemetry`]); none by default
+    pub slow_requests: SlowRequestConfig,
+
+    /// Largest accepted request: HTTP body size for SSE, line length for STDIO
+    pub max_request_bytes: usize,
+
+    /// Parse requests as exactly one JSON value. By default a UTF-8 byte order
+    /// mark, trailing commas and anything after the first value are tolerated,
+    /// as some clients send them.
+    pub strict_json: bool,
+
+    /// Layout of the JSON tools return; compact saves tokens and time on large results
+    pub json_format: JsonFormat,
+
+    /// Shape of the JSON search responses and pages tools return to clients
+    /// that don't ask for one in `initialize` (see [`crate::schema`])
+    pub schema_version: SchemaVersion,
+
+    /// Time limit for an HTTP request before it is answered with 408
+    pub request_timeout: Duration,
+
+    /// Interval between keep-alive comments on open `/sse` streams, so proxies
+    /// and clients don't close idle connections
+    pub sse_keep_alive: Duration,
+
+    /// Most HTTP connections open at once; further clients wait to be accepted
+    pub max_connections: usize,
+
+    /// Time an HTTP connection may go without reading or writing a byte before
+    /// it is closed; keep it above `request_timeout` and `sse_keep_alive`
+    pub idle_timeout: Duration,
+
+    /// Time a write may stay stuck on a client that stopped reading (a hung
+    /// SSE consumer) before its connection is closed
+    pub write_timeout: Duration,
+
+    /// Extra `Host`/`Origin` hostnames the SSE transport accepts besides loopback
+    /// and the bind address (e.g. the public name behind a reverse proxy);
+    /// `"*"` turns the DNS-rebinding check off
+    pub allowed_hosts: Vec<String>,
+
+    /// Domains whose results are dropped before anything else sees them
+    pub blocklist: BlocklistConfig,
+
+    /// Lowest safe-search level any search runs at: laxer requests are raised
+    /// to it, stricter ones kept. `Off` leaves the choice to clients.
+    pub min_safe_search: SafeSearchLevel,
+
+    /// Rewriters run in order on every search query before it reaches the
+    /// backends (see [`rewrite`](crate::rewrite)); empty searches queries as given
+    pub query_rewriters: Vec<Arc<dyn QueryRewriter>>,
+
+    /// Whether logs show queries or their hashes, which applies to the whole
+    /// process (see [`privacy::configure`]), and the headers this handler's
+    /// HTTP clients send. Cache keys are set by [`CacheConfig::hash_keys`].
+    pub privacy: PrivacyConfig,
+
+    /// Browser profiles this handler's clients send to search engines and
+    /// fetched pages (see [`fingerprint`](crate::fingerprint))
+    pub fingerprint: FingerprintConfig,
+
+    /// Answer every tool call with the requests it would make (see [`SearchPlan`])
+    /// instead of making them, as if each call passed `dry_run: true`
+    pub dry_run: bool,
+
+    /// Fetch pages even when the site's robots.txt disallows them. Off by
+    /// default: such pages fail with [`DaedraError::DisallowedByRobots`].
+    /// Turning it on makes you answerable to the sites you fetch from.
+    pub ignore_robots: bool,
+
+    /// Host globs tools may read (see [`sandbox`](crate::sandbox)); empty
+    /// allows every host. Search results elsewhere are dropped and pages
+    /// elsewhere fail with [`DaedraError::DomainNotAllowed`].
+    pub allowed_domains: Vec<String>,
+
+    /// Host globs tools may never read, even when `allowed_domains` matches.
+    /// Unlike [`blocklist`](Self::blocklist), this also refuses page fetches.
+    pub blocked_domains: Vec<String>,
+
+    /// Extra headers, such as credentials, sent with page fetches and URL
+    /// checks to the hosts matching each glob (see [`fetch::DomainHeaders`])
+    pub domain_headers: Vec<fetch::DomainHeaders>,
+
+    /// File the cookies sites set are kept in between runs; without one,
+    /// they are kept in memory for the life of the server
+    pub cookie_file: Option<PathBuf>,
+
+    /// Offer the `cache_invalidate` tool, which drops cached entries by
+    /// pattern; off by default, since any client could then purge the cache
+    pub admin_tools: bool,
+
+    /// Base URL of a SearXNG instance to query ahead of the built-in backends
+    #[cfg(feature = "searxng")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "searxng")))]
+    pub searxng_url: Option<String>,
 }
 
 impl Default for ServerConfig {
@@ -54,6 +354,39 @@ impl Default for ServerConfig {
             cache: CacheConfig::default(),
             verbose: false,
             max_concurrent_tools: 10,
+            extraction_threads: fetch::default_extraction_threads(),
+            search_chain: Vec::new(),
+            search_strategy: None,
+            serper_api_key: None,
+            circuit_breaker: tools::CircuitBreakerConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            memory: MemoryConfig::default(),
+            slow_requests: SlowRequestConfig::default(),
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            max_page_bytes: fetch::DEFAULT_MAX_PAGE_BYTES,
+            strict_json: false,
+            json_format: JsonFormat::Pretty,
+            schema_version: SchemaVersion::CURRENT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            sse_keep_alive: DEFAULT_SSE_KEEP_ALIVE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+            allowed_hosts: Vec::new(),
+            blocklist: BlocklistConfig::default(),
+            min_safe_search: SafeSearchLevel::Off,
+            query_rewriters: Vec::new(),
+            privacy: PrivacyConfig::default(),
+            fingerprint: FingerprintConfig::default(),
+            dry_run: false,
+            ignore_robots: false,
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+            domain_headers: Vec::new(),
+            cookie_file: None,
+            admin_tools: false,
+            #[cfg(feature = "searxng")]
+            searxng_url: None,
         }
     }
 }
@@ -143,6 +476,66 @@ pub struct McpTool {
     pub input_schema: Value,
 }
 
+/// What `web_search` would do, as reported by a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPlan {
+    /// The search query
+    pub query: String,
+
+    /// What the query rewriters would send the backends instead, if anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewritten_query: Option<String>,
+
+    /// How backends would be combined; for fallback, `backends` is the chain order
+    pub strategy: SearchStrategy,
+
+    /// Whether the response would come from the cache, with no requests at all
+    pub cached: bool,
+
+    /// Per-backend decisions and requests (empty when cached)
+    pub backends: Vec<tools::BackendPlan>,
+
+    /// Requests that depend on the results, such as snippet enrichment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// What `deep_research` would do, as reported by a dry run.
+///
+/// Follow-up rounds are chosen from the first round's results, so only the
+/// first round's searches are planned in full.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchPlan {
+    /// The research question
+    pub query: String,
+
+    /// Search rounds, after clamping
+    pub depth: usize,
+
+    /// Most pages read, after clamping
+    pub max_pages: usize,
+
+    /// The first round's searches
+    pub searches: Vec<SearchPlan>,
+
+    /// The follow-up rounds and reads
+    pub notes: Vec<String>,
+}
+
+/// What `visit_page` would do, as reported by a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchPlan {
+    /// The page URL
+    pub url: String,
+
+    /// Whether the page would come from the cache
+    pub cached: bool,
+
+    /// The request it would send (absent when cached)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<PlannedRequest>,
+}
+
 /// Tool handler implementation
 #[derive(Clone)]
 pub struct DaedraHandler {
@@ -155,6 +548,66 @@ pub struct DaedraHandler {
     /// Fetch client
     fetch_client: Arc<fetch::FetchClient>,
 
+    /// Hacker News search client
+    hackernews: Arc<HackerNewsClient>,
+
+    /// Stack Exchange question search client
+    stackoverflow: Arc<StackExchangeBackend>,
+
+    /// YouTube caption client
+    youtube: Arc<YouTubeClient>,
+
+    /// Backend fallback order (empty = every backend)
+    search_chain: Arc<[String]>,
+
+    /// Strategy for requests that don't pick one
+    default_strategy: SearchStrategy,
+
+    /// Domains whose results are dropped
+    blocklist: Arc<DomainBlocklist>,
+
+    /// Page fetches `enrich_top` runs at once
+    max_concurrent_tools: usize,
+
+    /// Safe-search floor applied to every search
+    min_safe_search: SafeSearchLevel,
+
+    /// Rewriters applied to queries before backend dispatch
+    query_rewriters: Arc<[Arc<dyn QueryRewriter>]>,
+
+    /// Plan tool calls instead of executing them
+    dry_run: bool,
+
+    /// Offer the cache administration tool
+    admin_tools: bool,
+
+    /// Reject requests the lenient JSON pre-pass would repair
+    strict_json: bool,
+
+    /// Layout of the JSON tools return
+    json_format: JsonFormat,
+
+    /// Schema version for clients that don't ask for one
+    schema_version: SchemaVersion,
+
+    /// Notifications for connected clients
+    events: EventBus,
+
+    /// When the watchdog runs
+    watchdog: WatchdogConfig,
+
+    /// Health as the watchdog last judged it
+    health: Arc<watch::Sender<Health>>,
+
+    /// Memory cap and how to back off past it
+    memory: MemoryConfig,
+
+    /// Whether memory is over the cap right now
+    memory_pressure: MemoryPressure,
+
+    /// When a tool call counts as slow
+    slow_requests: Arc<SlowRequestConfig>,
+
     /// Initialization state
     initialized: Arc<RwLock<bool>>,
 }
@@ -162,20 +615,115 @@ pub struct DaedraHandler {
 impl DaedraHandler {
     /// Create a new handler
     pub fn new(config: ServerConfig) -> DaedraResult<Self> {
+        privacy::configure(&config.privacy);
+        let profile = config.privacy.client_profile;
+        let rotation = Arc::new(Rotation::new(&config.fingerprint)?);
+        let mut search_provider =
+            tools::SearchProvider::auto_with(profile, Arc::clone(&rotation))
+                .with_circuit_breaker(config.circuit_breaker);
+        if let Some(key) = config.serper_api_key.as_deref().filter(|k| !k.is_empty()) {
+            search_provider = search_provider.with_serper(key);
+        }
+        #[cfg(feature = "searxng")]
+        if let Some(url) = config.searxng_url.as_deref() {
+            search_provider = search_provider.with_searxng(url)?;
+        }
+
+        let memory_pressure = MemoryPressure::default();
+        let fetch_client = fetch::FetchClient::builder()
+            .extraction_threads(config.extraction_threads)
+            .client_profile(profile)
+            .browser_profiles(rotation)
+            .build()?
+            .with_max_page_bytes(config.max_page_bytes)
+            .with_memory_pressure(memory_pressure.clone(), config.memory.pressure_fetch_limit)
+            .with_ignore_robots(config.ignore_robots)
+            .with_domain_policy(DomainPolicy::new(&config.allowed_domains, &config.blocked_domains)?)?
+            .with_domain_headers(&config.domain_headers)?;
+        let fetch_client = match &config.cookie_file {
+            Some(path) => fetch_client.with_cookie_jar(CookieJar::open(path)?)?,
+            None => fetch_client,
+        };
+
         Ok(Self {
             cache: SearchCache::new(config.cache),
-            search_provider: Arc::new(tools::SearchProvider::auto()),
-            fetch_client: Arc::new(fetch::FetchClient::new()?),
+            search_provider: Arc::new(search_provider),
+            fetch_client: Arc::new(fetch_client),
+            hackernews: Arc::new(HackerNewsClient::new().with_client_profile(profile)),
+            stackoverflow: Arc::new(StackExchangeBackend::new().with_client_profile(profile)),
+            youtube: Arc::new(YouTubeClient::new().with_client_profile(profile)),
+            default_strategy: config.search_strategy.unwrap_or(if config.search_chain.is_empty() {
+                SearchStrategy::Merge
+            } else {
+                SearchStrategy::Fallback
+            }),
+            search_chain: config.search_chain.into(),
+            blocklist: Arc::new(DomainBlocklist::from_config(&config.blocklist)?),
+            max_concurrent_tools: config.max_concurrent_tools,
+            min_safe_search: config.min_safe_search,
+            query_rewriters: config.query_rewriters.into(),
+            dry_run: config.dry_run,
+            admin_tools: config.admin_tools,
+            strict_json: config.strict_json,
+            json_format: config.json_format,
+            schema_version: config.schema_version,
+            events: EventBus::new(),
+            watchdog: config.watchdog,
+            health: Arc::new(watch::Sender::new(Health::default())),
+            memory: config.memory,
+            memory_pressure,
+            slow_requests: Arc::new(config.slow_requests),
             initialized: Arc::new(RwLock::new(false)),
         })
     }
 
+    /// Start the watchdog task, when it is enabled
+    fn spawn_watchdog(&self) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.watchdog.enabled {
+            return None;
+        }
+        let provider = Arc::clone(&self.search_provider);
+        let all_circuits_open = move || {
+            let backends = provider.backend_status();
+            !backends.is_empty()
+                && backends.iter().all(|b| b.state == tools::CircuitState::Open)
+        };
+        Some(tokio::spawn(watchdog::run(
+            self.watchdog,
+            self.events.clone(),
+            Arc::clone(&self.health),
+            all_circuits_open,
+        )))
+    }
+
+    /// Start the memory cap task, when a cap is set
+    fn spawn_memory_guard(&self) -> Option<tokio::task::JoinHandle<()>> {
+        self.memory.cap?;
+        Some(tokio::spawn(memory::run(
+            self.memory,
+            self.memory_pressure.clone(),
+            self.cache.clone(),
+        )))
+    }
+
+    /// Circuit breaker status of every search backend
+    pub fn backend_status(&self) -> Vec<tools::BackendStatus> {
+        self.search_provider.backend_status()
+    }
+
     /// Get server information for initialization
     pub fn get_server_info(&self) -> Value {
         json!({
             "protocolVersion": MCP_PROTOCOL_VERSION,
             "capabilities": {
-                "tools": {}
+                "tools": { "listChanged": true },
+                "logging": {},
+                "experimental": {
+                    "daedra": {
+                        "schemaVersion": self.schema_version.number(),
+                        "schemaVersions": SchemaVersion::SUPPORTED.map(SchemaVersion::number)
+                    }
+                }
             },
             "serverInfo": {
                 "name": SERVER_NAME,
@@ -184,9 +732,46 @@ impl DaedraHandler {
         })
     }
 
+    /// Answer `initialize`, settling the schema version of the client's
+    /// connection: the one it asks for under
+    /// `capabilities.experimental.daedra.schemaVersion`, as far as it is
+    /// supported, or the configured one. Outside a connection (`/rpc` without
+    /// a session) there is nothing to remember it on, so the configured one
+    /// applies.
+    async fn initialize(&self, params: Option<&Value>) -> Value {
+        let requested = params
+            .and_then(|p| p.pointer("/capabilities/experimental/daedra/schemaVersion"))
+            .and_then(Value::as_u64)
+            .map(SchemaVersion::negotiate);
+        let stored = CONNECTION_SCHEMA
+            .try_with(|schema| *schema.lock().expect("schema lock") = requested)
+            .is_ok();
+        let version = requested.filter(|_| stored).unwrap_or(self.schema_version);
+        let mut info = self.get_server_info();
+        info["capabilities"]["experimental"]["daedra"]["schemaVersion"] = version.number().into();
+        info
+    }
+
+    /// Schema version of the client being answered
+    fn client_schema(&self) -> SchemaVersion {
+        CONNECTION_SCHEMA
+            .try_with(|schema| *schema.lock().expect("schema lock"))
+            .ok()
+            .flatten()
+            .unwrap_or(self.schema_version)
+    }
+
+    /// `result` as JSON in the client's schema version and the configured layout
+    async fn versioned_json<T: Versioned>(&self, result: &T) -> String {
+        let version = self.client_schema();
+        telemetry::timed_sync(Phase::Serialize, || version.to_value(result))
+            .and_then(|value| Ok(self.json_format.to_string(&value)?))
+            .unwrap_or_default()
+    }
+
     /// List available tools
     pub fn list_tools(&self) -> Vec<McpTool> {
-        vec![
+        let mut tools = vec![
             McpTool {
                 name: "web_search".to_string(),
                 description: Some(
@@ -203,6 +788,54 @@ impl DaedraHandler {
                 ),
                 input_schema: search_args_schema(),
             },
+            McpTool {
+                name: "multi_search".to_string(),
+                description: Some(
+                    "Run several web searches at once (up to 10 queries sharing one set of options) and return a JSON array with one entry per query, in order: its query and either its response, as web_search returns it, or the error that query alone ran into."
+                        .to_string(),
+                ),
+                input_schema: multi_search_args_schema(),
+            },
+            McpTool {
+                name: "search_and_read".to_string(),
+                description: Some(
+                    "Search the web, read the top results' pages, and return one Markdown research digest: a section per source with its URL and content, numbered for citation, followed by the remaining results as links. Saves a web_search plus several visit_page calls."
+                        .to_string(),
+                ),
+                input_schema: search_and_read_args_schema(),
+            },
+            McpTool {
+                name: "deep_research".to_string(),
+                description: Some(
+                    "Research a question over several rounds of searches: the question and each part of a compound one, then follow-ups on related questions and recurring topics. Reads the sources the most searches agreed on, skips duplicate pages, and returns a Markdown report with the searches run, a section per source and a numbered sources list."
+                        .to_string(),
+                ),
+                input_schema: deep_research_args_schema(),
+            },
+            McpTool {
+                name: "search_hackernews".to_string(),
+                description: Some(
+                    "Search Hacker News stories (or Ask HN / Show HN posts) through the Algolia HN API, by relevance, date or points. Each result carries its points and comment count in metadata and links the discussion thread."
+                        .to_string(),
+                ),
+                input_schema: hackernews_args_schema(),
+            },
+            McpTool {
+                name: "search_stackoverflow".to_string(),
+                description: Some(
+                    "Search Stack Overflow (or another Stack Exchange site) questions through the Stack Exchange API, optionally filtered by tags or to questions with an accepted answer. Each result carries the question's votes, answer count, accepted-answer flag and tags in metadata, so answered questions can be preferred."
+                        .to_string(),
+                ),
+                input_schema: stackoverflow_args_schema(),
+            },
+            McpTool {
+                name: "get_transcript".to_string(),
+                description: Some(
+                    "Get the captions of a YouTube video as timestamped Markdown: the video's title, then the transcript in paragraphs of about 30 seconds, each led by a timestamp linking to that point in the video. Manual captions are preferred over automatic ones."
+                        .to_string(),
+                ),
+                input_schema: transcript_args_schema(),
+            },
             McpTool {
                 name: "visit_page".to_string(),
                 description: Some(
@@ -211,6 +844,14 @@ impl DaedraHandler {
                 ),
                 input_schema: visit_page_args_schema(),
             },
+            McpTool {
+                name: "check_url".to_string(),
+                description: Some(
+                    "Check a URL without downloading it: a HEAD request (or a one-byte ranged GET when HEAD is refused) returning the status code, final URL after redirects, content type, content length, response headers and whether visit_page can read it. Useful for validating links before fetching them."
+                        .to_string(),
+                ),
+                input_schema: check_url_args_schema(),
+            },
             McpTool {
                 name: "crawl_site".to_string(),
                 description: Some(
@@ -219,79 +860,290 @@ impl DaedraHandler {
                 ),
                 input_schema: crawl_args_schema(),
             },
-        ]
+        ];
+        if self.admin_tools {
+            tools.push(McpTool {
+                name: "cache_invalidate".to_string(),
+                description: Some(
+                    "Drop cached pages whose URL, and cached searches whose query, start with a pattern ('*' standing for any run of characters), so the next call fetches them afresh. Returns how many search and page entries were dropped."
+                        .to_string(),
+                ),
+                input_schema: cache_invalidate_args_schema(),
+            });
+        }
+        tools
+    }
+
+    /// Cache key variant for a search: merged and fallback responses differ, so
+    /// a per-request strategy override gets its own entry
+    fn search_cache_variant(&self, options: &SearchOptions, strategy: SearchStrategy) -> String {
+        let mut variant = options.safe_search.to_string();
+        if strategy != self.default_strategy {
+            variant = format!("{}:{}", variant, strategy);
+        }
+        if options.expand_snippets {
+            variant.push_str(":expanded");
+        }
+        if options.probe_fetchability {
+            variant.push_str(":probed");
+        }
+        if options.include_sponsored {
+            variant.push_str(":sponsored");
+        }
+        let type_filters = [("prefer", &options.prefer_types), ("exclude", &options.exclude_types)];
+        for (label, types) in type_filters {
+            if !types.is_empty() {
+                let names: Vec<String> = types.iter().map(ToString::to_string).collect();
+                variant = format!("{}:{}={}", variant, label, names.join(","));
+            }
+        }
+        if let Some(max) = options.max_per_domain {
+            variant = format!("{}:perdomain{}", variant, max);
+        }
+        if options.enrich_top > 0 {
+            variant = format!("{}:top{}", variant, options.enrich_top.min(MAX_ENRICHED_RESULTS));
+        }
+        match options.rerank {
+            Some(true) => variant.push_str(":rerank"),
+            Some(false) => variant.push_str(":norerank"),
+            None => {},
+        }
+        variant
+    }
+
+    /// `args` with its safe-search level raised to
+    /// [`ServerConfig::min_safe_search`] where it asks for less
+    fn enforce_safe_search(&self, mut args: SearchArgs) -> SearchArgs {
+        let requested = args.options.as_ref().map(|o| o.safe_search).unwrap_or_default();
+        if requested < self.min_safe_search {
+            debug!(
+                requested = %requested,
+                enforced = %self.min_safe_search,
+                "Raising safe search to the server minimum"
+            );
+            args.options.get_or_insert_with(Default::default).safe_search = self.min_safe_search;
+        }
+        args
+    }
+
+    /// `args` with the query the [`ServerConfig::query_rewriters`] make of it,
+    /// if they change it
+    fn rewritten_args(&self, args: &SearchArgs) -> Option<SearchArgs> {
+        let query = rewrite::rewrite(&self.query_rewriters, &args.query)?;
+        debug!(
+            query = %privacy::loggable(&args.query),
+            rewritten = %privacy::loggable(&query),
+            "Rewrote query"
+        );
+        Some(SearchArgs {
+            query,
+            options: args.options.clone(),
+        })
     }
 
     /// Execute search tool
-    #[instrument(skip(self))]
+    #[instrument(skip(self, args), fields(query = %privacy::loggable(&args.query)))]
     pub async fn execute_search(&self, args: SearchArgs) -> DaedraResult<SearchResponse> {
+        self.search_shared(args).await.map(Arc::unwrap_or_clone)
+    }
+
+    /// [`execute_search`](Self::execute_search), sharing the cached response
+    /// instead of copying it when no token budget or cursor applies
+    async fn search_shared(&self, args: SearchArgs) -> DaedraResult<Arc<SearchResponse>> {
+        let args = self.enforce_safe_search(args);
         let options = args.options.clone().unwrap_or_default();
+        let response = self.search_uncut(&args, &options).await?;
+        if options.max_tokens_hint.is_none() && options.cursor.is_none() {
+            return Ok(response);
+        }
+        let mut response = Arc::unwrap_or_clone(response);
+        budget::fit_search(&mut response, options.max_tokens_hint, options.cursor.as_deref())?;
+        Ok(Arc::new(response))
+    }
+
+    /// The full response for `args`, cached or fresh, before any token budget is applied
+    async fn search_uncut(
+        &self,
+        args: &SearchArgs,
+        options: &SearchOptions,
+    ) -> DaedraResult<Arc<SearchResponse>> {
+        let strategy = options.strategy.unwrap_or(self.default_strategy);
+        let cache_variant = self.search_cache_variant(options, strategy);
 
         // Check cache first
         if let Some(cached) = self
             .cache
-            .get_search(
-                &args.query,
-                &options.region,
-                &options.safe_search.to_string(),
-            )
+            .get_search(&args.query, &options.region, &cache_variant)
             .await
         {
-            info!(query = %args.query, "Returning cached search results");
+            info!(query = %privacy::loggable(&args.query), "Returning cached search results");
             return Ok(cached);
         }
 
-        // Perform search via multi-backend provider (aggregate across backends)
-        let mut response = self.search_provider.search(&args).await?;
+        let rewritten = self.rewritten_args(args);
+        let mut response = self
+            .search_provider
+            .search_with_strategy(rewritten.as_ref().unwrap_or(args), strategy, &self.search_chain)
+            .await?;
+        if let Some(rewritten) = rewritten {
+            response.metadata.query = args.query.clone();
+            response.metadata.rewritten_query = Some(rewritten.query);
+        }
+
+        let blocked = self.blocklist.filter(&mut response.data);
+        if blocked > 0 {
+            debug!(
+                query = %privacy::loggable(&args.query),
+                blocked,
+                "Dropped results from blocklisted domains"
+            );
+            response.metadata.result_count = response.data.len();
+        }
+        self.drop_denied_domains(&mut response);
 
-        self.enrich_sparse_results(&mut response.data, 3).await;
+        if options.expand_snippets {
+            self.expand_snippets(&mut response.data, EXPANDED_SNIPPETS, &options.region).await;
+        }
+        if options.enrich_top > 0 {
+            let count = options.enrich_top.min(MAX_ENRICHED_RESULTS);
+            self.enrich_top_results(&mut response.data, count, &options.region).await;
+        }
+        if !options.expand_snippets && options.enrich_top == 0 {
+            self.enrich_sparse_results(&mut response.data, 3, &options.region).await;
+        }
+        if options.probe_fetchability {
+            self.probe_results(&mut response.data, PROBED_RESULTS).await;
+        }
+        // After the fetches above, which refine content types from page metadata
+        if !options.prefer_types.is_empty() || !options.exclude_types.is_empty() {
+            let excluded = tools::rerank::apply_type_preferences(
+                &mut response.data,
+                &options.prefer_types,
+                &options.exclude_types,
+            );
+            debug!(excluded, "Applied content-type preferences");
+            response.metadata.result_count = response.data.len();
+        }
+        if let Some(max) = options.max_per_domain {
+            let dropped = domains::limit_per_domain(&mut response.data, max);
+            debug!(dropped, max, "Capped results per registered domain");
+            response.metadata.result_count = response.data.len();
+        }
 
         // Cache the results
+        let response = Arc::new(response);
         self.cache
-            .set_search(
-                &args.query,
-                &options.region,
-                &options.safe_search.to_string(),
-                response.clone(),
-            )
+            .set_search(&args.query, &options.region, &cache_variant, response.clone())
             .await;
 
         Ok(response)
     }
 
 
-    /// Fetch page snippets for sparse top results (description < 100 chars).
-    async fn enrich_sparse_results(&self, results: &mut [SearchResult], count: usize) {
+    /// Describe what [`execute_search`](Self::execute_search) would do, without
+    /// touching the network or the rate limiters.
+    pub fn plan_search(&self, args: &SearchArgs) -> SearchPlan {
+        let args = &self.enforce_safe_search(args.clone());
+        let options = args.options.clone().unwrap_or_default();
+        let strategy = options.strategy.unwrap_or(self.default_strategy);
+        let cached = self.cache.contains_search(
+            &args.query,
+            &options.region,
+            &self.search_cache_variant(&options, strategy),
+        );
+        if cached {
+            return SearchPlan {
+                query: args.query.clone(),
+                rewritten_query: None,
+                strategy,
+                cached,
+                backends: Vec::new(),
+                notes: Vec::new(),
+            };
+        }
+        let rewritten = self.rewritten_args(args);
+        SearchPlan {
+            query: args.query.clone(),
+            strategy,
+            cached,
+            backends: self
+                .search_provider
+                .plan(rewritten.as_ref().unwrap_or(args), &self.search_chain),
+            rewritten_query: rewritten.map(|r| r.query),
+            notes: [
+                options.expand_snippets.then(|| {
+                    format!(
+                        "each of the top {} results is fetched (GET, 2 at a time, 5s timeout, \
+                         pages not already cached) to replace its snippet with the page's lead",
+                        EXPANDED_SNIPPETS
+                    )
+                }),
+                (options.enrich_top > 0).then(|| {
+                    format!(
+                        "each of the top {} results is fetched (GET, {} at a time, 5s timeout, \
+                         pages not already cached) to fill in thin snippets and published dates",
+                        options.enrich_top.min(MAX_ENRICHED_RESULTS),
+                        self.max_concurrent_tools
+                    )
+                }),
+                (!options.expand_snippets && options.enrich_top == 0).then(|| {
+                    "each of the top 3 results with a description under 100 characters is \
+                     fetched (GET, 2 at a time, 5s timeout) to fill in its snippet"
+                        .to_string()
+                }),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(options.probe_fetchability.then(|| {
+                format!(
+                    "each of the top {} results is HEAD-checked (4 at a time, 5s timeout; GET \
+                     without reading the body where HEAD is refused)",
+                    PROBED_RESULTS
+                )
+            }))
+            .collect(),
+        }
+    }
+
+    /// Fetch page snippets for sparse top results (description < 100 chars),
+    /// through the page cache.
+    async fn enrich_sparse_results(
+        &self,
+        results: &mut [SearchResult],
+        count: usize,
+        region: &str,
+    ) {
         let enrich_count = count.min(results.len());
         if enrich_count == 0 {
             return;
         }
 
-        let fetch_client = self.fetch_client.clone();
         let enrich_semaphore = Arc::new(Semaphore::new(2));
         let futures: Vec<_> = results[..enrich_count]
             .iter()
             .filter(|r| r.description.len() < 100)
             .map(|r| {
                 let url = r.url.clone();
-                let client = fetch_client.clone();
                 let semaphore = enrich_semaphore.clone();
                 async move {
-                    let _permit = semaphore.acquire_owned().await.unwrap();
+                    let _permit =
+                        telemetry::timed(Phase::Queue, semaphore.acquire_owned()).await.unwrap();
                     let args = VisitPageArgs {
                         url: url.clone(),
                         selector: None,
                         include_images: false,
+                        region: Some(region.to_string()),
+                        ..Default::default()
                     };
                     match tokio::time::timeout(
                         std::time::Duration::from_secs(5),
-                        client.fetch(&args),
+                        self.fetch_uncut(&args),
                     )
                     .await
                     {
                         Ok(Ok(page)) => {
                             let snippet: String = page.content.chars().take(300).collect();
-                            Some((url, snippet))
+                            Some((url, snippet, page.content_type))
                         }
                         _ => None,
                     }
@@ -305,34 +1157,557 @@ impl DaedraHandler {
                 if result.description.len() < 100 {
                     result.description = enrichment.1;
                 }
+                if let Some(content_type) = enrichment.2 {
+                    result.metadata.content_type = content_type;
+                }
             }
         }
     }
 
-    /// Execute fetch/visit page tool
-    #[instrument(skip(self))]
-    pub async fn execute_fetch(&self, args: VisitPageArgs) -> DaedraResult<PageContent> {
-        // Check cache first
-        if let Some(cached) = self
-            .cache
-            .get_page(&args.url, args.selector.as_deref())
-            .await
-        {
-            info!(url = %args.url, "Returning cached page content");
-            return Ok(cached);
+    /// HEAD-check the first `count` results, 4 at a time, recording the outcome
+    /// in their metadata.
+    async fn probe_results(&self, results: &mut [SearchResult], count: usize) {
+        let probe_count = count.min(results.len());
+        let semaphore = Semaphore::new(4);
+        let probes = futures::future::join_all(results[..probe_count].iter().map(|r| {
+            let semaphore = &semaphore;
+            async move {
+                let _permit = telemetry::timed(Phase::Queue, semaphore.acquire()).await;
+                tokio::time::timeout(Duration::from_secs(5), self.fetch_client.probe(&r.url))
+                    .await
+                    .unwrap_or_else(|_| FetchProbe {
+                        reason: Some("timed out".to_string()),
+                        ..Default::default()
+                    })
+            }
+        }))
+        .await;
+
+        for (result, probe) in results.iter_mut().zip(probes) {
+            let mime = probe.content_type.as_deref();
+            if let Some(content_type) = mime.and_then(fetch::content_type_for_mime) {
+                result.metadata.content_type = content_type;
+            }
+            result.metadata.probe = Some(probe);
         }
+    }
 
-        // Fetch page
-        let content = self.fetch_client.fetch(&args).await?;
+    /// Pages of the first `count` results, in `region`'s language,
+    /// `concurrency` at a time with a `timeout` each. The pages go through the
+    /// page cache, so a follow-up `visit_page` is free.
+    async fn fetch_result_pages(
+        &self,
+        results: &[SearchResult],
+        count: usize,
+        region: &str,
+        concurrency: usize,
+        timeout: Duration,
+    ) -> Vec<DaedraResult<Arc<PageContent>>> {
+        let semaphore = Semaphore::new(concurrency.max(1));
+        futures::future::join_all(results.iter().take(count).map(|r| {
+            let semaphore = &semaphore;
+            async move {
+                let _permit = telemetry::timed(Phase::Queue, semaphore.acquire())
+                    .await
+                    .map_err(|e| DaedraError::FetchError(e.to_string()))?;
+                let args = VisitPageArgs {
+                    url: r.url.clone(),
+                    region: Some(region.to_string()),
+                    ..Default::default()
+                };
+                tokio::time::timeout(timeout, self.fetch_uncut(&args))
+                    .await
+                    .map_err(|_| {
+                        DaedraError::FetchError(format!("timed out after {}s", timeout.as_secs()))
+                    })?
+            }
+        }))
+        .await
+    }
 
-        // Cache the results
-        self.cache
-            .set_page(&args.url, args.selector.as_deref(), content.clone())
+    /// Replace the snippets of the first `count` results with the lead
+    /// paragraphs of their pages; results whose page fails keep their snippet.
+    async fn expand_snippets(&self, results: &mut [SearchResult], count: usize, region: &str) {
+        let pages =
+            self.fetch_result_pages(results, count, region, 2, SNIPPET_FETCH_TIMEOUT).await;
+        for (result, page) in results.iter_mut().zip(pages) {
+            let Ok(page) = page else { continue };
+            if let Some(lead) = fetch::lead_paragraphs(&page.content, 2, EXPANDED_SNIPPET_CHARS) {
+                result.description = lead;
+            }
+            if let Some(content_type) = page.content_type {
+                result.metadata.content_type = content_type;
+            }
+        }
+    }
+
+    /// Fetch the first `count` results, up to `max_concurrent_tools` at a time,
+    /// and give each the first paragraph of its page where that says more than
+    /// the snippet, the page's published date where the backend had none, and
+    /// the content type the page declares.
+    async fn enrich_top_results(&self, results: &mut [SearchResult], count: usize, region: &str) {
+        let concurrency = self.max_concurrent_tools;
+        let pages = self
+            .fetch_result_pages(results, count, region, concurrency, SNIPPET_FETCH_TIMEOUT)
+            .await;
+        for (result, page) in results.iter_mut().zip(pages) {
+            let Ok(page) = page else { continue };
+            if let Some(lead) = fetch::lead_paragraphs(&page.content, 1, EXPANDED_SNIPPET_CHARS)
+                && lead.len() > result.description.len()
+            {
+                result.description = lead;
+            }
+            if result.metadata.published_date.is_none() {
+                result.metadata.published_date = page.published_date.clone();
+            }
+            if let Some(content_type) = page.content_type {
+                result.metadata.content_type = content_type;
+            }
+        }
+    }
+
+    /// Search, then read the top results' pages into one digest. Pages that
+    /// fail keep their place, with the error and the search snippet instead.
+    #[instrument(skip(self, args), fields(query = %privacy::loggable(&args.query)))]
+    pub async fn execute_search_and_read(
+        &self,
+        args: SearchAndReadArgs,
+    ) -> DaedraResult<ResearchDigest> {
+        let read_count = args.read_count.clamp(1, MAX_READ_RESULTS);
+        let region = args.options.as_ref().map(|o| o.region.clone()).unwrap_or_default();
+        let response = self
+            .execute_search(SearchArgs {
+                query: args.query.clone(),
+                options: args.options,
+            })
+            .await?;
+        let concurrency = self.max_concurrent_tools;
+        let pages = self
+            .fetch_result_pages(&response.data, read_count, &region, concurrency, READ_TIMEOUT)
             .await;
+        let page_budget = args.max_tokens_hint.map(|tokens| tokens / pages.len().max(1));
+
+        let mut sources: Vec<DigestSource> = response
+            .data
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| DigestSource {
+                index: i + 1,
+                title: result.title,
+                url: result.url,
+                snippet: result.description,
+                published_date: result.metadata.published_date,
+                content: None,
+                truncated: false,
+                error: None,
+            })
+            .collect();
+        let further_reading = sources.split_off(pages.len());
+        for (source, page) in sources.iter_mut().zip(pages) {
+            match page.and_then(|page| {
+                let mut page = Arc::unwrap_or_clone(page);
+                budget::fit_page(&mut page, page_budget, None)?;
+                Ok(page)
+            }) {
+                Ok(page) => {
+                    source.published_date = source.published_date.take().or(page.published_date);
+                    source.truncated = page.truncated;
+                    source.content = Some(page.content);
+                },
+                Err(e) => source.error = Some(e.to_string()),
+            }
+        }
+
+        Ok(ResearchDigest {
+            query: args.query,
+            sources,
+            further_reading,
+        })
+    }
+
+    /// Describe what [`execute_search_and_read`](Self::execute_search_and_read)
+    /// would do: the search plan, plus the page reads that follow it.
+    pub fn plan_search_and_read(&self, args: &SearchAndReadArgs) -> SearchPlan {
+        let mut plan = self.plan_search(&SearchArgs {
+            query: args.query.clone(),
+            options: args.options.clone(),
+        });
+        plan.notes.push(format!(
+            "each of the top {} results is then read (GET, {} at a time, {}s timeout, pages not \
+             already cached) into the digest",
+            args.read_count.clamp(1, MAX_READ_RESULTS),
+            self.max_concurrent_tools,
+            READ_TIMEOUT.as_secs()
+        ));
+        plan
+    }
+
+    /// Research `args.query` over up to `depth` rounds of concurrent searches,
+    /// then read the sources the most searches returned. A page that fails, or
+    /// repeats one already read, is listed as skipped and the next candidate is
+    /// read in its place. Fails only when every search does.
+    #[instrument(skip(self, args), fields(query = %privacy::loggable(&args.query)))]
+    pub async fn execute_deep_research(
+        &self,
+        args: DeepResearchArgs,
+    ) -> DaedraResult<DeepResearchReport> {
+        let depth = args.depth.clamp(1, MAX_RESEARCH_DEPTH);
+        let max_pages = args.max_pages.clamp(1, MAX_RESEARCH_PAGES);
+        let mut steps: Vec<ResearchStep> = Vec::new();
+        let mut responses: Vec<Arc<SearchResponse>> = Vec::new();
+        let mut queries = research::decompose_query(&args.query);
+        for round in 1..=depth {
+            if queries.is_empty() {
+                break;
+            }
+            // The uncut responses: each search's token budget and cursor don't apply here
+            let searches = futures::future::join_all(queries.iter().map(|query| async {
+                let search = self.enforce_safe_search(SearchArgs {
+                    query: query.clone(),
+                    options: args.options.clone(),
+                });
+                let options = search.options.clone().unwrap_or_default();
+                self.search_uncut(&search, &options).await
+            }))
+            .await;
+
+            let mut found = Vec::new();
+            for (query, search) in queries.into_iter().zip(searches) {
+                let (result_count, error) = match search {
+                    Ok(response) => {
+                        let count = response.data.len();
+                        found.push(response);
+                        (count, None)
+                    },
+                    Err(e) => (0, Some(e.to_string())),
+                };
+                steps.push(ResearchStep {
+                    round,
+                    query,
+                    result_count,
+                    error,
+                });
+            }
+            let asked: Vec<String> = steps.iter().map(|s| s.query.clone()).collect();
+            queries =
+                research::follow_up_queries(&args.query, found.iter().map(Arc::as_ref), &asked);
+            responses.extend(found);
+        }
+        if responses.is_empty() {
+            let errors: Vec<&str> = steps.iter().filter_map(|s| s.error.as_deref()).collect();
+            return Err(DaedraError::SearchError(format!(
+                "every search failed: {}",
+                errors.join("; ")
+            )));
+        }
+
+        // Twice the page budget, so failures and duplicates can be replaced
+        let mut candidates = research::rank_candidates(responses.iter().map(Arc::as_ref));
+        candidates.truncate(max_pages * 2);
+        let page_budget = args.max_tokens_hint.map(|tokens| tokens / max_pages);
+        let region = args.options.as_ref().map(|o| o.region.clone()).unwrap_or_default();
+        let concurrency = self.max_concurrent_tools;
+        let mut sources: Vec<DigestSource> = Vec::new();
+        let mut skipped: Vec<SkippedSource> = Vec::new();
+        let mut fingerprints: HashMap<String, usize> = HashMap::new();
+        let mut next = 0;
+        while sources.len() < max_pages && next < candidates.len() {
+            let end = (next + max_pages - sources.len()).min(candidates.len());
+            let batch: Vec<SearchResult> =
+                candidates[next..end].iter().map(|c| c.result.clone()).collect();
+            next = end;
+            let pages = self
+                .fetch_result_pages(&batch, batch.len(), &region, concurrency, READ_TIMEOUT)
+                .await;
+            for (result, page) in batch.into_iter().zip(pages) {
+                let page = match page {
+                    Ok(page) => page,
+                    Err(e) => {
+                        skipped.push(SkippedSource {
+                            url: result.url,
+                            reason: e.to_string(),
+                        });
+                        continue;
+                    },
+                };
+                let fingerprint = research::content_fingerprint(&page.content);
+                let duplicate_of = fingerprints.get(&fingerprint);
+                if fingerprint.is_empty() || duplicate_of.is_some() {
+                    let reason = match duplicate_of {
+                        Some(index) => format!("duplicate of [{}]", index),
+                        None => "no readable content".to_string(),
+                    };
+                    skipped.push(SkippedSource {
+                        url: result.url,
+                        reason,
+                    });
+                    continue;
+                }
+
+                let mut page = Arc::unwrap_or_clone(page);
+                budget::fit_page(&mut page, page_budget, None)?;
+                let index = sources.len() + 1;
+                fingerprints.insert(fingerprint, index);
+                sources.push(DigestSource {
+                    index,
+                    title: result.title,
+                    url: result.url,
+                    snippet: result.description,
+                    published_date: result.metadata.published_date.or(page.published_date),
+                    content: Some(page.content),
+                    truncated: page.truncated,
+                    error: None,
+                });
+            }
+        }
+
+        Ok(DeepResearchReport {
+            query: args.query,
+            steps,
+            sources,
+            skipped,
+        })
+    }
+
+    /// Describe what [`execute_deep_research`](Self::execute_deep_research)
+    /// would do: the first round's searches, plus notes on what follows them.
+    pub fn plan_deep_research(&self, args: &DeepResearchArgs) -> ResearchPlan {
+        let depth = args.depth.clamp(1, MAX_RESEARCH_DEPTH);
+        let max_pages = args.max_pages.clamp(1, MAX_RESEARCH_PAGES);
+        let searches = research::decompose_query(&args.query)
+            .into_iter()
+            .map(|query| {
+                self.plan_search(&SearchArgs {
+                    query,
+                    options: args.options.clone(),
+                })
+            })
+            .collect();
+        let mut notes = Vec::new();
+        if depth > 1 {
+            notes.push(format!(
+                "up to {} more rounds of up to {} searches each, chosen from the previous \
+                 round's related questions and recurring title terms",
+                depth - 1,
+                research::MAX_QUERIES_PER_ROUND
+            ));
+        }
+        notes.push(format!(
+            "the {} results returned by the most searches are then read (GET, {} at a time, \
+             {}s timeout, pages not already cached); failed and duplicate pages are replaced \
+             from the next {} candidates",
+            max_pages,
+            self.max_concurrent_tools,
+            READ_TIMEOUT.as_secs(),
+            max_pages
+        ));
+        ResearchPlan {
+            query: args.query.clone(),
+            depth,
+            max_pages,
+            searches,
+            notes,
+        }
+    }
+
+    /// Execute the multi-search tool: every query concurrently, each with
+    /// [`execute_search`](Self::execute_search)'s caching and fallback. One
+    /// query failing does not fail the others; its item carries the error.
+    pub async fn execute_multi_search(
+        &self,
+        args: MultiSearchArgs,
+    ) -> DaedraResult<Vec<MultiSearchItem>> {
+        args.validate()?;
+        let searches = args.searches();
+        let outcomes =
+            futures::future::join_all(searches.iter().cloned().map(|s| self.execute_search(s)))
+                .await;
+        let items: Vec<MultiSearchItem> = searches
+            .into_iter()
+            .zip(outcomes)
+            .map(|(search, outcome)| MultiSearchItem::new(search.query, outcome))
+            .collect();
+        let failed = items.iter().filter(|item| item.error.is_some()).count();
+        info!(queries = items.len(), failed, "Multi-search complete");
+        Ok(items)
+    }
+
+    /// What [`execute_multi_search`](Self::execute_multi_search) would do:
+    /// each query's [`SearchPlan`], in order
+    pub fn plan_multi_search(&self, args: &MultiSearchArgs) -> Vec<SearchPlan> {
+        args.searches().iter().map(|search| self.plan_search(search)).collect()
+    }
+
+    /// Execute the Hacker News search tool
+    pub async fn execute_search_hackernews(
+        &self,
+        args: HackerNewsArgs,
+    ) -> DaedraResult<SearchResponse> {
+        let mut response = self.hackernews.search(&args).await?;
+        self.drop_denied_domains(&mut response);
+        Ok(response)
+    }
+
+    /// The request [`execute_search_hackernews`](Self::execute_search_hackernews)
+    /// would send, without sending it.
+    pub fn plan_search_hackernews(&self, args: &HackerNewsArgs) -> PlannedRequest {
+        self.hackernews.plan(args)
+    }
+
+    /// Execute the Stack Overflow search tool
+    pub async fn execute_search_stackoverflow(
+        &self,
+        args: StackOverflowArgs,
+    ) -> DaedraResult<SearchResponse> {
+        let mut response = self.stackoverflow.search_questions(&args).await?;
+        self.drop_denied_domains(&mut response);
+        Ok(response)
+    }
+
+    /// Drop results from hosts the domain policy rules out
+    fn drop_denied_domains(&self, response: &mut SearchResponse) {
+        let dropped = self.fetch_client.domain_policy().filter(&mut response.data);
+        if dropped > 0 {
+            debug!(dropped, "Dropped results outside the domain policy");
+            response.metadata.result_count = response.data.len();
+        }
+    }
+
+    /// The request [`execute_search_stackoverflow`](Self::execute_search_stackoverflow)
+    /// would send, without sending it.
+    pub fn plan_search_stackoverflow(&self, args: &StackOverflowArgs) -> PlannedRequest {
+        self.stackoverflow.plan_questions(args)
+    }
+
+    /// Execute the YouTube transcript tool
+    pub async fn execute_get_transcript(&self, args: TranscriptArgs) -> DaedraResult<Transcript> {
+        self.fetch_client.domain_policy().check(&self.youtube.plan(&args)?.url)?;
+        self.youtube.transcript(&args).await
+    }
+
+    /// The first request [`execute_get_transcript`](Self::execute_get_transcript)
+    /// would send, without sending it.
+    pub fn plan_get_transcript(&self, args: &TranscriptArgs) -> DaedraResult<PlannedRequest> {
+        self.youtube.plan(args)
+    }
+
+    /// Execute the URL check tool
+    pub async fn execute_check_url(&self, args: CheckUrlArgs) -> DaedraResult<UrlCheck> {
+        self.fetch_client.check_url(&args.url).await
+    }
+
+    /// The first request [`execute_check_url`](Self::execute_check_url) would
+    /// send, without sending it.
+    pub fn plan_check_url(&self, args: &CheckUrlArgs) -> DaedraResult<PlannedRequest> {
+        self.fetch_client.plan_check_url(&args.url)
+    }
+
+    /// Execute fetch/visit page tool
+    #[instrument(skip(self))]
+    pub async fn execute_fetch(&self, args: VisitPageArgs) -> DaedraResult<PageContent> {
+        self.fetch_shared(args).await.map(Arc::unwrap_or_clone)
+    }
+
+    /// [`execute_fetch`](Self::execute_fetch), sharing the cached page instead
+    /// of copying it when the whole page is wanted
+    async fn fetch_shared(&self, args: VisitPageArgs) -> DaedraResult<Arc<PageContent>> {
+        if args.start_index.is_some() && args.cursor.is_some() {
+            return Err(DaedraError::InvalidArguments(
+                "Pass either cursor or start_index, not both".to_string(),
+            ));
+        }
+        let content = self.fetch_uncut(&args).await?;
+        if args.max_tokens_hint.is_none()
+            && args.cursor.is_none()
+            && args.start_index.is_none()
+            && args.max_length.is_none()
+        {
+            return Ok(content);
+        }
+        let mut content = Arc::unwrap_or_clone(content);
+        if args.start_index.is_some() || args.max_length.is_some() {
+            budget::slice_page(
+                &mut content,
+                args.start_index.unwrap_or(0),
+                args.max_length,
+                args.max_tokens_hint,
+            )?;
+        } else {
+            budget::fit_page(&mut content, args.max_tokens_hint, args.cursor.as_deref())?;
+        }
+        Ok(Arc::new(content))
+    }
+
+    /// The full page for `args`, cached or fresh, before any token budget is applied
+    async fn fetch_uncut(&self, args: &VisitPageArgs) -> DaedraResult<Arc<PageContent>> {
+        // Check cache first
+        if let Some(cached) = self.cache.get_page(args).await {
+            info!(url = %args.url, "Returning cached page content");
+            return Ok(cached);
+        }
+        if let Some(failure) = self.cache.get_failure(args).await {
+            info!(url = %args.url, "Returning cached fetch failure");
+            return Err(failure);
+        }
+
+        let fetched = self.fetch_and_cache(args).await;
+        if let Err(e) = &fetched {
+            self.cache.set_failure(args, e).await;
+        }
+        fetched
+    }
+
+    /// Fetch the page and cache it, revalidating an expired copy when the
+    /// cache kept one
+    async fn fetch_and_cache(&self, args: &VisitPageArgs) -> DaedraResult<Arc<PageContent>> {
+        // Ask whether an expired page changed before fetching it whole
+        if let Some((stale, validators)) = self.cache.get_stale_page(args).await {
+            let Some((content, validators)) =
+                self.fetch_client.revalidate(args, &validators).await?
+            else {
+                info!(url = %args.url, "Page not modified, refreshing cached content");
+                self.cache.set_page_validated(args, stale.clone(), validators).await;
+                return Ok(stale);
+            };
+            let content = Arc::new(content);
+            self.cache.set_page_validated(args, content.clone(), validators).await;
+            return Ok(content);
+        }
+
+        // Fetch page
+        let (content, validators) = self.fetch_client.fetch_with_validators(args).await?;
+        let content = Arc::new(content);
+
+        // Cache the results
+        self.cache.set_page_validated(args, content.clone(), validators).await;
 
         Ok(content)
     }
 
+    /// Describe what [`execute_fetch`](Self::execute_fetch) would do, without fetching.
+    pub fn plan_fetch(&self, args: &VisitPageArgs) -> FetchPlan {
+        let cached = self.cache.contains_page(args);
+        FetchPlan {
+            url: args.url.clone(),
+            cached,
+            request: (!cached).then(|| {
+                let mut note = "follows up to 10 redirects; retried with backoff on failure".to_string();
+                if let Some(language) = args.region.as_deref().and_then(regions::accept_language) {
+                    note = format!("Accept-Language: {}; {}", language, note);
+                }
+                if !self.fetch_client.ignores_robots() {
+                    note.push_str("; skipped if the site's robots.txt disallows it");
+                }
+                if self.cache.contains_failure(args) {
+                    note.push_str("; not sent while an earlier failure is cached");
+                } else if self.cache.contains_stale_page(args) {
+                    note.push_str("; conditional, reusing the expired cached page if unchanged");
+                }
+                PlannedRequest::get::<&str, &str>(&args.url, &[]).with_note(note)
+            }),
+        }
+    }
+
     /// Handle a JSON-RPC request
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         debug!(method = %request.method, "Handling request");
@@ -354,7 +1729,7 @@ impl DaedraHandler {
         params: Option<Value>,
     ) -> JsonRpcResponse {
         match method {
-            "initialize" => JsonRpcResponse::success(id, self.get_server_info()),
+            "initialize" => JsonRpcResponse::success(id, self.initialize(params.as_ref()).await),
             "initialized" | "notifications/initialized" => JsonRpcResponse::success(id, json!({})),
             "tools/list" => JsonRpcResponse::success(id, json!({ "tools": self.list_tools() })),
             "tools/call" => match parse_tool_call_params(params, id.clone()) {
@@ -370,7 +1745,13 @@ impl DaedraHandler {
         }
     }
 
+    /// Every tool takes `dry_run` next to its own arguments; the server setting forces it on
+    fn is_dry_run(&self, arguments: &Value) -> bool {
+        self.dry_run || arguments.get("dry_run").and_then(Value::as_bool).unwrap_or(false)
+    }
+
     async fn handle_web_search(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
         let args: SearchArgs = match serde_json::from_value(arguments) {
             Ok(a) => a,
             Err(e) => {
@@ -381,783 +1762,3981 @@ impl DaedraHandler {
                 );
             },
         };
+        if let Some(Err(e)) = args.options.as_ref().map(SearchOptions::validate) {
+            return tool_error_response(id, &e.to_string());
+        }
+
+        if dry_run {
+            return plan_response(id, &self.plan_search(&args), self.json_format);
+        }
 
-        match self.execute_search(args).await {
+        match self.search_shared(args).await {
             Ok(response) => {
-                let text = serde_json::to_string_pretty(&response).unwrap_or_default();
+                let text = self.versioned_json(&*response).await;
                 tool_success_response(id, text)
             }
             Err(e) => {
-                error!(error = %e, "Search failed");
-                tool_error_response(id, &format!("Search failed: {}", e))
+                error!(error = %privacy::scrub(&e.to_string()), "Search failed");
+                tool_failure_response(id, "Search failed", &e)
             }
         }
     }
 
-    async fn handle_visit_page(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
-        let args: VisitPageArgs = match serde_json::from_value(arguments) {
+    async fn handle_multi_search(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: MultiSearchArgs = match serde_json::from_value(arguments) {
             Ok(a) => a,
             Err(e) => {
                 return JsonRpcResponse::error(
                     id,
                     -32602,
-                    format!("Invalid fetch arguments: {}", e),
+                    format!("Invalid multi_search arguments: {}", e),
                 );
             },
         };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
+        }
 
-        if !fetch::is_valid_url(&args.url) {
-            return tool_error_response(id, "Invalid URL: must be HTTP or HTTPS");
+        if dry_run {
+            return plan_response(id, &self.plan_multi_search(&args), self.json_format);
         }
 
-        match self.execute_fetch(args).await {
-            Ok(content) => tool_success_response(id, format_page_result(&content)),
+        match self.execute_multi_search(args).await {
+            Ok(items) => {
+                let text = self.versioned_json(&items).await;
+                tool_success_response(id, text)
+            }
             Err(e) => {
-                error!(error = %e, "Fetch failed");
-                tool_error_response(id, &format!("Failed to fetch page: {}", e))
+                error!(error = %privacy::scrub(&e.to_string()), "Multi-search failed");
+                tool_failure_response(id, "Search failed", &e)
             }
         }
     }
 
-    async fn handle_crawl_site(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
-        let args: CrawlArgs = match serde_json::from_value(arguments) {
+    async fn handle_search_and_read(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: SearchAndReadArgs = match serde_json::from_value(arguments) {
             Ok(a) => a,
             Err(e) => {
                 return JsonRpcResponse::error(
                     id,
                     -32602,
-                    format!("Invalid crawl arguments: {}", e),
+                    format!("Invalid search_and_read arguments: {}", e),
                 );
             },
         };
+        if let Some(Err(e)) = args.options.as_ref().map(SearchOptions::validate) {
+            return tool_error_response(id, &e.to_string());
+        }
 
-        match crawl_site(args).await {
-            Ok(result) => {
-                let text = serde_json::to_string_pretty(&result).unwrap_or_default();
+        if dry_run {
+            return plan_response(id, &self.plan_search_and_read(&args), self.json_format);
+        }
+
+        match self.execute_search_and_read(args).await {
+            Ok(digest) => {
+                let text = telemetry::timed_sync(Phase::Serialize, || digest.to_markdown());
                 tool_success_response(id, text)
-            }
+            },
             Err(e) => {
-                error!(error = %e, "Crawl failed");
-                tool_error_response(id, &format!("Crawl failed: {}", e))
+                error!(error = %privacy::scrub(&e.to_string()), "Search and read failed");
+                tool_failure_response(id, "Search failed", &e)
             }
         }
     }
 
-    /// Call a specific tool
-    async fn call_tool(&self, id: Option<Value>, name: &str, arguments: Value) -> JsonRpcResponse {
-        info!(tool = %name, "Executing tool");
+    async fn handle_deep_research(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: DeepResearchArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid deep_research arguments: {}", e),
+                );
+            },
+        };
+        if let Some(Err(e)) = args.options.as_ref().map(SearchOptions::validate) {
+            return tool_error_response(id, &e.to_string());
+        }
 
-        match name {
-            "web_search" | "search_duckduckgo" => self.handle_web_search(id, arguments).await,
-            "visit_page" => self.handle_visit_page(id, arguments).await,
-            "crawl_site" => self.handle_crawl_site(id, arguments).await,
-            _ => JsonRpcResponse::error(id, -32601, format!("Unknown tool: {}", name)),
+        if dry_run {
+            return plan_response(id, &self.plan_deep_research(&args), self.json_format);
         }
-    }
 
-    /// Get cache reference
-    pub fn cache(&self) -> &SearchCache {
-        &self.cache
+        match self.execute_deep_research(args).await {
+            Ok(report) => {
+                let text = telemetry::timed_sync(Phase::Serialize, || report.to_markdown());
+                tool_success_response(id, text)
+            },
+            Err(e) => {
+                error!(error = %privacy::scrub(&e.to_string()), "Deep research failed");
+                tool_failure_response(id, "Research failed", &e)
+            }
+        }
     }
-}
 
-fn parse_tool_call_params(
-    params: Option<Value>,
-    id: Option<Value>,
-) -> Result<(String, Value), JsonRpcResponse> {
-    let params = match params {
-        Some(p) => p,
-        None => {
-            return Err(JsonRpcResponse::error(
-                id,
-                -32602,
-                "Missing parameters".to_string(),
-            ));
+    async fn handle_search_hackernews(
+        &self,
+        id: Option<Value>,
+        arguments: Value,
+    ) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: HackerNewsArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid search_hackernews arguments: {}", e),
+                );
+            },
+        };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
         }
-    };
-    let tool_name = params
-        .get("name")
-        .and_then(|v| v.as_str())
-        .unwrap_or_default()
-        .to_string();
-    let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
-    Ok((tool_name, arguments))
-}
-
-fn format_page_result(content: &PageContent) -> String {
-    format!(
-        "# {}
-
-**URL:** {}
-**Fetched:** {}
-**Words:** {}
-
----
-
-{}",
-        content.title, content.url, content.timestamp, content.word_count, content.content
-    )
-}
-
-fn tool_error_response(id: Option<Value>, message: &str) -> JsonRpcResponse {
-    JsonRpcResponse::success(
-        id,
-        json!({
-            "content": [{ "type": "text", "text": message }],
-            "isError": true
-        }),
-    )
-}
 
-fn tool_success_response(id: Option<Value>, text: String) -> JsonRpcResponse {
-    JsonRpcResponse::success(
-        id,
-        json!({
-            "content": [{ "type": "text", "text": text }],
-            "isError": false
-        }),
-    )
-}
+        if dry_run {
+            return plan_response(id, &self.plan_search_hackernews(&args), self.json_format);
+        }
 
-/// Parse and handle one STDIO line; returns a response only for non-notification requests.
-async fn process_stdio_line(line: &str, handler: &DaedraHandler) -> Option<JsonRpcResponse> {
-    if line.trim().is_empty() {
-        return None;
+        match self.execute_search_hackernews(args).await {
+            Ok(response) => {
+                let text = self.versioned_json(&response).await;
+                tool_success_response(id, text)
+            }
+            Err(e) => {
+                error!(error = %privacy::scrub(&e.to_string()), "Hacker News search failed");
+                tool_failure_response(id, "Search failed", &e)
+            }
+        }
     }
 
-    debug!(request = %line, "Received request");
+    async fn handle_search_stackoverflow(
+        &self,
+        id: Option<Value>,
+        arguments: Value,
+    ) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: StackOverflowArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid search_stackoverflow arguments: {}", e),
+                );
+            },
+        };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
+        }
 
-    let request: JsonRpcRequest = match serde_json::from_str(line) {
-        Ok(r) => r,
-        Err(e) => {
-            return Some(JsonRpcResponse::error(
-                None,
-                -32700,
-                format!("Parse error: {}", e),
-            ));
+        if dry_run {
+            return plan_response(id, &self.plan_search_stackoverflow(&args), self.json_format);
         }
-    };
 
-    let response = handler.handle_request(request.clone()).await;
-    if is_notification(&request) {
-        None
-    } else {
-        Some(response)
-    }
-}
+        match self.execute_search_stackoverflow(args).await {
+            Ok(response) => {
+                let text = self.versioned_json(&response).await;
+                tool_success_response(id, text)
+            }
+            Err(e) => {
+                error!(error = %privacy::scrub(&e.to_string()), "Stack Overflow search failed");
+                tool_failure_response(id, "Search failed", &e)
+            }
+        }
+    }
 
-/// Serialize a JSON-RPC response and write it to STDIO (with trailing newline).
-async fn write_stdio_response(
-    response: JsonRpcResponse,
-    stdout: &mut tokio::io::BufWriter<tokio::io::Stdout>,
-) -> std::io::Result<()> {
-    let response_str = serde_json::to_string(&response).unwrap();
-    debug!(response = %response_str, "Sending response");
-    stdout.write_all(response_str.as_bytes()).await?;
-    stdout.write_all(b"
-").await?;
-    stdout.flush().await
+    async fn handle_get_transcript(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: TranscriptArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid get_transcript arguments: {}", e),
+                );
+            },
+        };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
+        }
+
+        if dry_run {
+            return match self.plan_get_transcript(&args) {
+                Ok(plan) => plan_response(id, &plan, self.json_format),
+                Err(e) => tool_error_response(id, &e.to_string()),
+            };
+        }
+
+        match self.execute_get_transcript(args).await {
+            Ok(transcript) => {
+                let text = telemetry::timed_sync(Phase::Serialize, || transcript.to_markdown());
+                tool_success_response(id, text)
+            },
+            Err(e) => {
+                error!(error = %privacy::scrub(&e.to_string()), "Transcript fetch failed");
+                tool_failure_response(id, "Transcript failed", &e)
+            }
+        }
+    }
+
+    async fn handle_visit_page(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: VisitPageArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid fetch arguments: {}", e),
+                );
+            },
+        };
+
+        if !fetch::is_valid_url(&args.url) {
+            return tool_error_response(id, "Invalid URL: must be HTTP or HTTPS");
+        }
+        if let Some(Err(e)) = args.region.as_deref().map(regions::validate_region) {
+            return tool_error_response(id, &e.to_string());
+        }
+        if dry_run {
+            return plan_response(id, &self.plan_fetch(&args), self.json_format);
+        }
+
+        let format = args.format;
+        match self.fetch_shared(args).await {
+            Ok(content) if format == PageFormat::Json => {
+                let text = self.versioned_json(&*content).await;
+                tool_success_response(id, text)
+            },
+            Ok(content) => {
+                let text = telemetry::timed_sync(Phase::Serialize, || format_page_result(&content));
+                tool_success_response(id, text)
+            },
+            Err(e) => {
+                error!(error = %e, "Fetch failed");
+                tool_failure_response(id, "Failed to fetch page", &e)
+            }
+        }
+    }
+
+    async fn handle_check_url(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: CheckUrlArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid check_url arguments: {}", e),
+                );
+            },
+        };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
+        }
+
+        if dry_run {
+            return match self.plan_check_url(&args) {
+                Ok(plan) => plan_response(id, &plan, self.json_format),
+                Err(e) => tool_error_response(id, &e.to_string()),
+            };
+        }
+
+        match self.execute_check_url(args).await {
+            Ok(check) => {
+                let text = self.json_format.to_string(&check).unwrap_or_default();
+                tool_success_response(id, text)
+            },
+            Err(e) => {
+                error!(error = %e, "URL check failed");
+                tool_failure_response(id, "URL check failed", &e)
+            },
+        }
+    }
+
+    async fn handle_cache_invalidate(
+        &self,
+        id: Option<Value>,
+        arguments: Value,
+    ) -> JsonRpcResponse {
+        let args: CacheInvalidateArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid cache_invalidate arguments: {}", e),
+                );
+            },
+        };
+        if let Err(e) = args.validate() {
+            return tool_error_response(id, &e.to_string());
+        }
+
+        let invalidated = self.invalidate_cache(&args.pattern).await;
+        let text = self.json_format.to_string(&invalidated).unwrap_or_default();
+        tool_success_response(id, text)
+    }
+
+    async fn handle_crawl_site(&self, id: Option<Value>, arguments: Value) -> JsonRpcResponse {
+        let dry_run = self.is_dry_run(&arguments);
+        let args: CrawlArgs = match serde_json::from_value(arguments) {
+            Ok(a) => a,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    id,
+                    -32602,
+                    format!("Invalid crawl arguments: {}", e),
+                );
+            },
+        };
+
+        if dry_run {
+            return match tools::crawl::plan_crawl(&args) {
+                Ok(plan) => plan_response(id, &plan, self.json_format),
+                Err(e) => tool_error_response(id, &format!("Crawl failed: {}", e)),
+            };
+        }
+
+        match crawl_site_with(args, (*self.fetch_client).clone()).await {
+            Ok(result) => {
+                let text = self.json_format.to_string(&result).unwrap_or_default();
+                tool_success_response(id, text)
+            }
+            Err(e) => {
+                error!(error = %e, "Crawl failed");
+                tool_failure_response(id, "Crawl failed", &e)
+            }
+        }
+    }
+
+    /// Call a specific tool, turning a panic inside it into a tool error.
+    ///
+    /// Tool results carry what the call cost in `_meta.telemetry`.
+    async fn call_tool(&self, id: Option<Value>, name: &str, arguments: Value) -> JsonRpcResponse {
+        info!(tool = %name, "Executing tool");
+
+        let (mut response, cost) = telemetry::measure(run_isolated(
+            id.clone(),
+            name,
+            self.dispatch_tool(id, name, arguments),
+        ))
+        .await;
+        debug!(tool = %name, ?cost, "Tool call finished");
+        self.log_if_slow(name, &cost);
+        self.publish_job_completed(&response, name, cost.duration_ms);
+        if let Some(meta) = response
+            .result
+            .as_mut()
+            .and_then(Value::as_object_mut)
+            .map(|result| result.entry("_meta").or_insert_with(|| json!({})))
+            .and_then(Value::as_object_mut)
+        {
+            meta.insert("telemetry".to_string(), json!(cost));
+        }
+        response
+    }
+
+    async fn dispatch_tool(
+        &self,
+        id: Option<Value>,
+        name: &str,
+        arguments: Value,
+    ) -> JsonRpcResponse {
+        match name {
+            "web_search" | "search_duckduckgo" => self.handle_web_search(id, arguments).await,
+            "multi_search" => self.handle_multi_search(id, arguments).await,
+            "search_and_read" => self.handle_search_and_read(id, arguments).await,
+            "deep_research" => self.handle_deep_research(id, arguments).await,
+            "search_hackernews" => self.handle_search_hackernews(id, arguments).await,
+            "search_stackoverflow" => self.handle_search_stackoverflow(id, arguments).await,
+            "get_transcript" => self.handle_get_transcript(id, arguments).await,
+            "visit_page" => self.handle_visit_page(id, arguments).await,
+            "check_url" => self.handle_check_url(id, arguments).await,
+            "crawl_site" => self.handle_crawl_site(id, arguments).await,
+            "cache_invalidate" if self.admin_tools => {
+                self.handle_cache_invalidate(id, arguments).await
+            },
+            _ => JsonRpcResponse::error(id, -32601, format!("Unknown tool: {}", name)),
+        }
+    }
+
+    /// Parse and handle one raw JSON-RPC message.
+    ///
+    /// Returns `None` for blank input and notifications; malformed JSON yields a
+    /// `-32700` parse-error response. Unless [`ServerConfig::strict_json`] is
+    /// set, a byte order mark, trailing commas and trailing data are tolerated.
+    /// `shutdown` and `exit` are acknowledged; acting on them is up to the caller.
+    pub async fn handle_message(&self, message: &str) -> Option<JsonRpcResponse> {
+        process_stdio_line(message, self, &mut StdioSession::default()).await
+    }
+
+    /// Warn about a call slower than its tool's threshold, with where the time went
+    fn log_if_slow(&self, tool: &str, cost: &telemetry::Telemetry) {
+        let Some(threshold) = self.slow_requests.exceeded(tool, cost) else {
+            return;
+        };
+        let timings = &cost.timings;
+        warn!(
+            tool = %tool,
+            duration_ms = cost.duration_ms,
+            threshold_ms = u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX),
+            queue_ms = timings.queue_ms,
+            cache_ms = timings.cache_ms,
+            network_ms = timings.network_ms,
+            parse_ms = timings.parse_ms,
+            serialize_ms = timings.serialize_ms,
+            upstream_requests = cost.upstream_requests,
+            bytes_received = cost.bytes_received,
+            cache_hits = cost.cache_hits,
+            retries = cost.retries,
+            "Slow tool call"
+        );
+    }
+
+    /// Tell the client that asked (or every client) a tool call finished,
+    /// with a warning log message when it failed.
+    fn publish_job_completed(&self, response: &JsonRpcResponse, tool: &str, duration_ms: u64) {
+        let failure = match (&response.error, &response.result) {
+            (Some(error), _) => Some(error.message.clone()),
+            (None, Some(result)) if result["isError"] == true => Some(
+                result["content"][0]["text"].as_str().unwrap_or("tool failed").to_string(),
+            ),
+            _ => None,
+        };
+        if let Some(message) = &failure {
+            self.events.publish(ServerNotification::Log {
+                level: LogLevel::Warning,
+                logger: SERVER_NAME.to_string(),
+                data: json!({ "tool": tool, "message": message }),
+            });
+        }
+        self.events.publish(ServerNotification::JobCompleted {
+            request_id: response.id.clone(),
+            tool: tool.to_string(),
+            success: failure.is_none(),
+            duration_ms,
+        });
+    }
+
+    /// Get cache reference
+    pub fn cache(&self) -> &SearchCache {
+        &self.cache
+    }
+
+    /// Bus the handler publishes client notifications on
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Drop every cached search and page, and tell connected clients
+    pub async fn clear_cache(&self) {
+        let stats = self.cache.stats();
+        self.cache.clear().await;
+        self.events.broadcast(ServerNotification::CacheInvalidated {
+            search_entries: stats.search_entries,
+            page_entries: stats.page_entries,
+        });
+    }
+
+    /// Drop the cached pages and searches `pattern` matches (see
+    /// [`SearchCache::invalidate_matching`]), and tell connected clients
+    pub async fn invalidate_cache(&self, pattern: &str) -> InvalidatedEntries {
+        let invalidated = self.cache.invalidate_matching(pattern).await;
+        info!(
+            search_entries = invalidated.search_entries,
+            page_entries = invalidated.page_entries,
+            "Cache entries invalidated by pattern"
+        );
+        self.events.broadcast(ServerNotification::CacheInvalidated {
+            search_entries: invalidated.search_entries,
+            page_entries: invalidated.page_entries,
+        });
+        invalidated
+    }
 }
 
-/// Main Daedra MCP server
-pub struct DaedraServer {
-    handler: DaedraHandler,
-    #[allow(dead_code)]
-    config: ServerConfig,
+fn parse_tool_call_params(
+    params: Option<Value>,
+    id: Option<Value>,
+) -> Result<(String, Value), JsonRpcResponse> {
+    let params = match params {
+        Some(p) => p,
+        None => {
+            return Err(JsonRpcResponse::error(
+                id,
+                -32602,
+                "Missing parameters".to_string(),
+            ));
+        }
+    };
+    let tool_name = params
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+    Ok((tool_name, arguments))
 }
 
-impl DaedraServer {
-    /// Create a new Daedra server with the given configuration
-    pub fn new(config: ServerConfig) -> DaedraResult<Self> {
-        let handler = DaedraHandler::new(config.clone())?;
-        Ok(Self { handler, config })
+fn format_page_result(content: &PageContent) -> String {
+    let published = content
+        .published_date
+        .as_ref()
+        .map(|date| format!("\n**Published:** {}", date))
+        .unwrap_or_default();
+    let content_type = content
+        .content_type
+        .map(|t| format!("\n**Type:** {}", t))
+        .unwrap_or_default();
+    let meta = content.meta.as_ref().map_or_else(String::new, |meta| {
+        [
+            ("Author", &meta.author),
+            ("Site", &meta.site_name),
+            ("Modified", &meta.modified_date),
+            ("Canonical", &meta.canonical_url),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("\n**{}:** {}", name, value.as_ref()?)))
+        .collect()
+    });
+    let host = idn_host_line(&content.url);
+    let pages = content
+        .pages
+        .as_ref()
+        .map(|pages| format!("\n**Pages:** {}", pages.join(", ")))
+        .unwrap_or_default();
+    let next_cursor = content
+        .next_cursor
+        .as_ref()
+        .map(|cursor| format!("\n**Next cursor:** {}", cursor))
+        .unwrap_or_default();
+    let next_index = content
+        .next_index
+        .map(|index| format!("\n**Next index:** {}", index))
+        .unwrap_or_default();
+    format!(
+        "# {}
+
+**URL:** {}{}{}{}{}{}
+**Fetched:** {}
+**Words:** {}{}{}
+
+---
+
+{}",
+        content.title,
+        content.url,
+        host,
+        published,
+        content_type,
+        meta,
+        pages,
+        content.timestamp,
+        content.word_count,
+        next_cursor,
+        next_index,
+        content.content
+    )
+}
+
+/// A `**Host:**` header line for a page on an internationalized (punycode)
+/// host: the name in Unicode, or a warning when it could pass for another
+fn idn_host_line(url: &str) -> String {
+    let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string))
+    else {
+        return String::new();
+    };
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return String::new();
+    }
+    if domains::is_homograph_risk(&host) {
+        format!("\n**Host:** {} (possible homograph: could pass for a different name)", host)
+    } else {
+        format!("\n**Host:** {}", domains::display_host(&host))
+    }
+}
+
+/// Drive a tool future, catching any panic (e.g. from an HTML parser choking on a
+/// hostile page) so it fails the one call instead of the whole session.
+async fn run_isolated<F>(id: Option<Value>, name: &str, tool: F) -> JsonRpcResponse
+where
+    F: std::future::Future<Output = JsonRpcResponse>,
+{
+    match AssertUnwindSafe(tool).catch_unwind().await {
+        Ok(response) => response,
+        Err(panic) => {
+            let message = panic_message(panic.as_ref());
+            error!(tool = %name, panic = %message, "Tool panicked");
+            tool_error_response(id, &format!("Tool '{}' failed unexpectedly: {}", name, message))
+        },
+    }
+}
+
+/// Best-effort text of a panic payload.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+fn tool_error_response(id: Option<Value>, message: &str) -> JsonRpcResponse {
+    JsonRpcResponse::success(
+        id,
+        json!({
+            "content": [{ "type": "text", "text": message }],
+            "isError": true
+        }),
+    )
+}
+
+/// A tool error for `error`, prefixed with what failed. When a rate limit
+/// said how long to wait, `_meta.retry_after_secs` carries it.
+fn tool_failure_response(id: Option<Value>, what: &str, error: &DaedraError) -> JsonRpcResponse {
+    let mut response = tool_error_response(id, &format!("{}: {}", what, error));
+    if let Some(wait) = error.retry_after()
+        && let Some(result) = response.result.as_mut().and_then(Value::as_object_mut)
+    {
+        result.insert("_meta".to_string(), json!({ "retry_after_secs": wait.as_secs() }));
+    }
+    response
+}
+
+/// A dry-run plan as a successful tool result
+fn plan_response<T: Serialize>(id: Option<Value>, plan: &T, format: JsonFormat) -> JsonRpcResponse {
+    tool_success_response(id, format.to_string(plan).unwrap_or_default())
+}
+
+fn tool_success_response(id: Option<Value>, text: String) -> JsonRpcResponse {
+    JsonRpcResponse::success(
+        id,
+        json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": false
+        }),
+    )
+}
+
+/// Schema version a connection settled in `initialize`, if it asked for one
+type NegotiatedSchema = Arc<Mutex<Option<SchemaVersion>>>;
+
+tokio::task_local! {
+    /// Negotiated schema of the connection the current request came in on
+    static CONNECTION_SCHEMA: NegotiatedSchema;
+}
+
+/// Where a STDIO client is in the shutdown handshake.
+#[derive(Debug, Default)]
+struct StdioSession {
+    /// Schema version negotiated in `initialize`
+    schema: NegotiatedSchema,
+    /// `shutdown` was received: only `exit` is handled from now on
+    shutting_down: bool,
+    /// `exit` was received: stop once its response (if any) is written
+    exiting: bool,
+}
+
+impl StdioSession {
+    /// Response to a lifecycle message, or `None` to dispatch it as usual
+    fn lifecycle_response(&mut self, request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+        let id = request.id.clone();
+        match request.method.as_str() {
+            "shutdown" => {
+                self.shutting_down = true;
+                Some(JsonRpcResponse::success(id, Value::Null))
+            },
+            "exit" => {
+                if !self.shutting_down {
+                    warn!("Client sent exit without shutdown");
+                }
+                self.exiting = true;
+                Some(JsonRpcResponse::success(id, json!({})))
+            },
+            method if self.shutting_down => Some(JsonRpcResponse::error(
+                id,
+                -32600,
+                format!("Server is shutting down; {method} rejected"),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Parse and handle one STDIO line; returns a response only for non-notification requests.
+async fn process_stdio_line(
+    line: &str,
+    handler: &DaedraHandler,
+    session: &mut StdioSession,
+) -> Option<JsonRpcResponse> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    if privacy::hashing_logged_queries() {
+        debug!(bytes = line.len(), "Received request");
+    } else {
+        debug!(request = %line, "Received request");
+    }
+
+    let request = match parse_json_rpc(line, handler.strict_json) {
+        Ok(r) => r,
+        Err(e) => return Some(parse_error_response(&e)),
+    };
+
+    let response = match session.lifecycle_response(&request) {
+        Some(response) => response,
+        None => {
+            let schema = Arc::clone(&session.schema);
+            CONNECTION_SCHEMA.scope(schema, handler.handle_request(request.clone())).await
+        },
+    };
+    if is_notification(&request) {
+        None
+    } else {
+        Some(response)
+    }
+}
+
+/// Parse a JSON-RPC request.
+///
+/// Unless `strict`, a message that fails to parse is retried without a UTF-8
+/// byte order mark and trailing commas, and with anything after the first JSON
+/// value ignored; the original error is reported if that fails too.
+fn parse_json_rpc(message: &str, strict: bool) -> serde_json::Result<JsonRpcRequest> {
+    let error = match serde_json::from_str(message) {
+        Ok(request) => return Ok(request),
+        Err(e) => e,
+    };
+    if !strict {
+        let repaired = strip_trailing_commas(message.trim_start_matches('\u{feff}'));
+        let mut values = serde_json::Deserializer::from_str(&repaired).into_iter();
+        if let Some(Ok(request)) = values.next() {
+            debug!(error = %error, "Accepted malformed JSON-RPC message after repair");
+            return Ok(request);
+        }
+    }
+    Err(error)
+}
+
+/// The `-32700` answer to a message that isn't a JSON-RPC request
+fn parse_error_response(error: &serde_json::Error) -> JsonRpcResponse {
+    JsonRpcResponse::error(None, -32700, format!("Parse error: {}", error))
+}
+
+/// `json` without commas that directly precede a closing `}` or `]`; string
+/// contents are left alone
+fn strip_trailing_commas(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in json.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {},
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && json[i + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Serialize a JSON-RPC response and write it to STDIO in `framing`: with a
+/// trailing newline, or after a `Content-Length` header.
+async fn write_stdio_response<W: AsyncWrite + Unpin>(
+    response: JsonRpcResponse,
+    framing: StdioFraming,
+    stdout: &mut W,
+) -> std::io::Result<()> {
+    let mut message = Vec::with_capacity(JSON_BUFFER_CAPACITY);
+    serde_json::to_writer(&mut message, &response)?;
+    if privacy::hashing_logged_queries() {
+        debug!(bytes = message.len(), "Sending response");
+    } else {
+        debug!(response = %String::from_utf8_lossy(&message), "Sending response");
+    }
+    match framing {
+        StdioFraming::Newline => {
+            message.push(b'\n');
+        },
+        StdioFraming::ContentLength => {
+            let header = format!("Content-Length: {}\r\n\r\n", message.len());
+            stdout.write_all(header.as_bytes()).await?;
+        },
+    }
+    stdout.write_all(&message).await?;
+    stdout.flush().await
+}
+
+/// Write a response to STDOUT; `Ok(false)` when the client has closed it.
+async fn send_stdio_response<W: AsyncWrite + Unpin>(
+    response: JsonRpcResponse,
+    framing: StdioFraming,
+    stdout: &mut W,
+) -> std::io::Result<bool> {
+    match write_stdio_response(response, framing, stdout).await {
+        Ok(()) => Ok(true),
+        Err(e) if is_disconnect(&e) => {
+            info!(error = %e, "STDOUT closed by client, shutting down");
+            Ok(false)
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether an I/O error means the peer on the other end of a pipe went away.
+fn is_disconnect(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        err.kind(),
+        ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::WriteZero
+    )
+}
+
+/// Wait for more input without consuming it; `true` when the reader hit EOF or disconnected.
+async fn input_closed<R: AsyncBufRead + Unpin>(reader: &mut R) -> bool {
+    match reader.fill_buf().await {
+        Ok(buf) => buf.is_empty(),
+        Err(e) => is_disconnect(&e),
+    }
+}
+
+/// Longest header line of a `Content-Length` framed STDIO message
+const STDIO_HEADER_MAX_BYTES: usize = 1024;
+
+/// Most header lines of a `Content-Length` framed STDIO message
+const STDIO_MAX_HEADERS: usize = 16;
+
+/// One message read from STDIO.
+#[derive(Debug, PartialEq)]
+enum StdioLine {
+    /// A complete line, without its line terminator
+    Line(String),
+    /// A message longer than the limit, discarded
+    TooLong {
+        /// Length in bytes
+        len: usize,
+        /// Top-level `id` found in the part that was read, to answer with
+        id: Option<Value>,
+    },
+    /// A `Content-Length` header that doesn't hold a length
+    BadHeader(String),
+    /// The input is closed
+    Eof,
+}
+
+/// How a STDIO message was framed; each response is framed like its request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StdioFraming {
+    /// One JSON message per line
+    Newline,
+    /// LSP-style `Content-Length: N` headers, a blank line, then N bytes
+    ContentLength,
+}
+
+/// Read one message in whichever framing the client uses: a line of JSON, or
+/// a `Content-Length` header block followed by that many bytes. Clients may
+/// mix the two from one message to the next.
+async fn read_stdio_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> std::io::Result<(StdioLine, StdioFraming)> {
+    let first = read_bounded_line(reader, max_bytes).await?;
+    let length = match &first {
+        StdioLine::Line(line) => content_length(line),
+        _ => None,
+    };
+    let Some(length) = length else {
+        return Ok((first, StdioFraming::Newline));
+    };
+
+    // Other headers (`Content-Type`) are ignored up to the blank line
+    for headers in 1.. {
+        if headers > STDIO_MAX_HEADERS {
+            let reason = format!("More than {STDIO_MAX_HEADERS} headers");
+            return Ok((StdioLine::BadHeader(reason), StdioFraming::ContentLength));
+        }
+        match read_bounded_line(reader, STDIO_HEADER_MAX_BYTES).await? {
+            StdioLine::Line(header) if header.is_empty() => break,
+            StdioLine::Eof => return Ok((StdioLine::Eof, StdioFraming::ContentLength)),
+            _ => {},
+        }
+    }
+    let message = match length {
+        Err(value) => StdioLine::BadHeader(format!("Invalid Content-Length: {value:?}")),
+        Ok(length) if length > max_bytes => {
+            // Keep up to the limit to find the id in, and drain the rest unread
+            let mut head = Vec::new();
+            (&mut *reader).take(max_bytes as u64).read_to_end(&mut head).await?;
+            let mut rest = (&mut *reader).take((length - head.len()) as u64);
+            tokio::io::copy(&mut rest, &mut tokio::io::sink()).await?;
+            StdioLine::TooLong {
+                len: length,
+                id: leading_request_id(&head),
+            }
+        },
+        Ok(length) => {
+            let mut body = Vec::with_capacity(length);
+            (&mut *reader).take(length as u64).read_to_end(&mut body).await?;
+            if body.len() < length {
+                StdioLine::Eof
+            } else {
+                StdioLine::Line(String::from_utf8_lossy(&body).into_owned())
+            }
+        },
+    };
+    Ok((message, StdioFraming::ContentLength))
+}
+
+/// The top-level `id` of a JSON-RPC message of which only `prefix` was read,
+/// so that a request too large to parse can still be answered under its id.
+/// `None` unless a string or number id was read in full.
+fn leading_request_id(prefix: &[u8]) -> Option<Value> {
+    let text = String::from_utf8_lossy(prefix);
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                let key = text.get(start..=i)?;
+                if depth == 1
+                    && key == "\"id\""
+                    && let Some(rest) = text[i + 1..].trim_start().strip_prefix(':')
+                {
+                    let mut values = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+                    let id = values.next()?.ok()?;
+                    // A value running to the end of the prefix may have been cut
+                    let complete = values.byte_offset() < rest.len();
+                    return (complete && (id.is_string() || id.is_number())).then_some(id);
+                }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    None
+}
+
+/// `Some` when `line` is a `Content-Length` header: the length, or the value
+/// that isn't one
+fn content_length(line: &str) -> Option<Result<usize, String>> {
+    let (name, value) = line.split_once(':')?;
+    let value = value.trim();
+    name.trim()
+        .eq_ignore_ascii_case("content-length")
+        .then(|| value.parse().map_err(|_| value.to_string()))
+}
+
+/// Read one line, buffering at most `max_bytes` of it. Longer lines are drained
+/// without being stored so a runaway client cannot exhaust memory.
+async fn read_bounded_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> std::io::Result<StdioLine> {
+    let mut line = Vec::new();
+    let mut len = 0usize;
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            if len == 0 {
+                return Ok(StdioLine::Eof);
+            }
+            break;
+        }
+        let (piece, done) = match chunk.iter().position(|&b| b == b'\n') {
+            Some(i) => (&chunk[..i], true),
+            None => (chunk, false),
+        };
+        len += piece.len();
+        // Past the limit only the start is kept, to find the request id in
+        let keep = piece.len().min(max_bytes.saturating_sub(line.len()));
+        line.extend_from_slice(&piece[..keep]);
+        let consumed = piece.len() + usize::from(done);
+        reader.consume(consumed);
+        if done {
+            break;
+        }
+    }
+
+    if len > max_bytes {
+        return Ok(StdioLine::TooLong {
+            len,
+            id: leading_request_id(&line),
+        });
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(StdioLine::Line(String::from_utf8_lossy(&line).into_owned()))
+}
+
+/// Body of the SSE `/health` endpoint, with the watchdog's judgement when it runs.
+fn health_report(backends: &[tools::BackendStatus], watchdog: Option<&Health>) -> Value {
+    let all_open = !backends.is_empty()
+        && backends.iter().all(|b| b.state == tools::CircuitState::Open);
+    let unhealthy = watchdog.is_some_and(|health| !health.is_healthy());
+    let mut report = json!({
+        "status": if all_open || unhealthy { "degraded" } else { "ok" },
+        "version": VERSION,
+        "backends": backends,
+    });
+    if let Some(health) = watchdog {
+        report["watchdog"] = json!(health);
+    }
+    report
+}
+
+/// `Host`/`Origin` validation for the SSE transport, guarding against DNS rebinding:
+/// a web page on `evil.example` resolving to 127.0.0.1 still sends `Host: evil.example`.
+#[derive(Debug)]
+struct HostPolicy {
+    /// Accepted hostnames (lowercase, IPv6 without brackets)
+    names: Vec<String>,
+    /// Skip the `Host` check (wildcard configured, or bound to every interface
+    /// with no explicit names — clients may then use any address of the machine)
+    any_host: bool,
+    /// Skip the `Origin` check (wildcard configured)
+    any_origin: bool,
+}
+
+impl HostPolicy {
+    /// Accept loopback, the bind host and its resolved addresses, plus `allowed_hosts`.
+    fn new(bind_host: &str, bind_ips: &[IpAddr], allowed_hosts: &[String]) -> Self {
+        let wildcard = allowed_hosts.iter().any(|h| h.trim() == "*");
+        let bind_any = bind_ips.iter().any(IpAddr::is_unspecified);
+        let mut names: Vec<String> = ["localhost", "127.0.0.1", "::1"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if !bind_any {
+            names.push(normalize_host(bind_host));
+            names.extend(bind_ips.iter().map(IpAddr::to_string));
+        }
+        names.extend(
+            allowed_hosts
+                .iter()
+                .map(|h| normalize_host(h.trim()))
+                .filter(|h| !h.is_empty() && h != "*"),
+        );
+        Self {
+            names,
+            any_host: wildcard || (bind_any && allowed_hosts.is_empty()),
+            any_origin: wildcard,
+        }
+    }
+
+    fn allows(&self, host: &str) -> bool {
+        self.names.iter().any(|n| *n == normalize_host(host))
+    }
+
+    /// Check the request headers; `Err` carries the reason for the 403.
+    fn check(&self, headers: &axum::http::HeaderMap) -> Result<(), &'static str> {
+        use axum::http::header::{HOST, ORIGIN};
+
+        if !self.any_host {
+            let host = headers.get(HOST).and_then(|v| v.to_str().ok());
+            if !host.is_some_and(|h| self.allows(h)) {
+                return Err("Host header not allowed");
+            }
+        }
+        if !self.any_origin
+            && let Some(origin) = headers.get(ORIGIN)
+        {
+            let origin_host = origin
+                .to_str()
+                .ok()
+                .and_then(|o| url::Url::parse(o).ok())
+                .and_then(|u| u.host_str().map(str::to_string));
+            if !origin_host.is_some_and(|h| self.allows(&h)) {
+                return Err("Origin not allowed");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the SSE bind host (IP literal, optionally bracketed, or a name such as
+/// `localhost`) into the socket addresses to listen on.
+pub async fn resolve_bind_addrs(host: &str, port: u16) -> DaedraResult<Vec<SocketAddr>> {
+    let host = host.trim();
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    if unbracketed.is_empty() {
+        return Err(DaedraError::InvalidArguments("Bind host is empty".to_string()));
+    }
+
+    let mut addrs: Vec<SocketAddr> = Vec::new();
+    let resolved = tokio::net::lookup_host((unbracketed, port)).await.map_err(|e| {
+        DaedraError::ServerError(format!("Failed to resolve bind host '{unbracketed}': {e}"))
+    })?;
+    for addr in resolved {
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+    if addrs.is_empty() {
+        return Err(DaedraError::ServerError(format!(
+            "Bind host '{unbracketed}' resolved to no addresses"
+        )));
+    }
+    Ok(addrs)
+}
+
+/// Lowercase a `Host` value and strip its port and IPv6 brackets.
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().to_ascii_lowercase();
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or_default().to_string();
+    }
+    match host.rsplit_once(':') {
+        // A bare IPv6 address has several colons and no port
+        Some((name, port)) if !name.contains(':') && port.chars().all(|c| c.is_ascii_digit()) => {
+            name.to_string()
+        },
+        _ => host,
+    }
+}
+
+/// An event sent on an SSE session
+#[derive(Debug, Clone)]
+struct SseEvent {
+    seq: u64,
+    name: &'static str,
+    data: String,
+}
+
+/// One SSE session: the events sent so far and the connection, if any,
+/// currently streaming them.
+#[derive(Debug, Default)]
+struct SseSession {
+    /// Latest events, oldest first, for replay after a reconnect
+    backlog: VecDeque<SseEvent>,
+    next_seq: u64,
+    /// Number and channel of the live connection
+    connection: Option<(u64, mpsc::UnboundedSender<SseEvent>)>,
+    /// When the last connection closed
+    detached_at: Option<Instant>,
+    /// Schema version negotiated in `initialize`; goes with the session
+    schema: NegotiatedSchema,
+}
+
+impl SseSession {
+    fn push(&mut self, name: &'static str, data: String) {
+        self.next_seq += 1;
+        let event = SseEvent {
+            seq: self.next_seq,
+            name,
+            data,
+        };
+        if let Some((_, sender)) = &self.connection {
+            // A closed receiver is a connection on its way out; the backlog keeps the event
+            let _ = sender.send(event.clone());
+        }
+        if self.backlog.len() == SSE_REPLAY_EVENTS {
+            self.backlog.pop_front();
+        }
+        self.backlog.push_back(event);
+    }
+}
+
+/// Sessions of the SSE transport.
+///
+/// Each `GET /sse` opens a session with its own channel and announces it in an
+/// `endpoint` event (`/rpc?session_id=…`); responses to requests posted there
+/// come back on the stream as `message` events. Event ids are
+/// `<session>-<seq>`, so a client reconnecting with `Last-Event-ID` resumes
+/// its session and gets the events it missed. Sessions without a connection
+/// are dropped after [`SSE_SESSION_TTL`].
+#[derive(Debug)]
+struct SseSessions {
+    sessions: Mutex<HashMap<String, SseSession>>,
+    next_connection: AtomicU64,
+    keep_alive: Duration,
+}
+
+/// A live `/sse` connection; dropping it detaches its session.
+struct SseConnection {
+    sessions: Arc<SseSessions>,
+    session: String,
+    number: u64,
+    replay: Vec<SseEvent>,
+    events: mpsc::UnboundedReceiver<SseEvent>,
+}
+
+impl SseSessions {
+    fn new(keep_alive: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            next_connection: AtomicU64::new(1),
+            keep_alive,
+        }
+    }
+
+    /// Resume the session `last_event_id` names, or open a new one.
+    fn connect(self: &Arc<Self>, last_event_id: Option<&str>) -> SseConnection {
+        let (sender, events) = mpsc::unbounded_channel();
+        let number = self.next_connection.fetch_add(1, Ordering::Relaxed);
+        let mut sessions = self.sessions.lock().expect("SSE sessions lock");
+        Self::purge_expired(&mut sessions);
+
+        let resumed = last_event_id.and_then(parse_event_id).and_then(|(id, seen)| {
+            let session = sessions.get_mut(id)?;
+            session.connection = Some((number, sender.clone()));
+            session.detached_at = None;
+            let replay: Vec<SseEvent> =
+                session.backlog.iter().filter(|e| e.seq > seen).cloned().collect();
+            Some((id.to_string(), replay))
+        });
+        let (session, replay) = match resumed {
+            Some((id, replay)) => {
+                info!(session = %id, replayed = replay.len(), "SSE connection resumed");
+                (id, replay)
+            },
+            None => {
+                if let Some(stale) = last_event_id {
+                    debug!(last_event_id = stale, "SSE session to resume not found");
+                }
+                let id = uuid::Uuid::new_v4().to_string();
+                let mut session = SseSession {
+                    connection: Some((number, sender)),
+                    ..Default::default()
+                };
+                session.push("endpoint", format!("/rpc?session_id={id}"));
+                sessions.insert(id.clone(), session);
+                info!(session = %id, "SSE connection opened");
+                (id, Vec::new())
+            },
+        };
+        SseConnection {
+            sessions: Arc::clone(self),
+            session,
+            number,
+            replay,
+            events,
+        }
+    }
+
+    /// Negotiated schema of `session`; `None` when there is no such session
+    fn schema(&self, session: &str) -> Option<NegotiatedSchema> {
+        let sessions = self.sessions.lock().expect("SSE sessions lock");
+        sessions.get(session).map(|session| Arc::clone(&session.schema))
+    }
+
+    /// Queue a `name` event for `session`; false when there is no such session.
+    fn send(&self, session: &str, name: &'static str, data: String) -> bool {
+        let mut sessions = self.sessions.lock().expect("SSE sessions lock");
+        match sessions.get_mut(session) {
+            Some(session) => {
+                session.push(name, data);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Send a bus notification to its session, or to every session
+    fn deliver(&self, event: &ServerEvent) {
+        let data = event.notification.to_json_rpc().to_string();
+        match &event.session {
+            Some(session) => {
+                self.send(session, "message", data);
+            },
+            None => {
+                let mut sessions = self.sessions.lock().expect("SSE sessions lock");
+                for session in sessions.values_mut() {
+                    session.push("message", data.clone());
+                }
+            },
+        }
+    }
+
+    /// End every open stream, for shutdown: graceful shutdown waits for them
+    fn close_all(&self) {
+        let mut sessions = self.sessions.lock().expect("SSE sessions lock");
+        for session in sessions.values_mut() {
+            session.connection = None;
+        }
+    }
+
+    /// Detach `session` from connection `number`, unless a newer one took over
+    fn disconnect(&self, session: &str, number: u64) {
+        let mut sessions = self.sessions.lock().expect("SSE sessions lock");
+        if let Some(session) = sessions.get_mut(session)
+            && session.connection.as_ref().is_some_and(|(n, _)| *n == number)
+        {
+            session.connection = None;
+            session.detached_at = Some(Instant::now());
+        }
+    }
+
+    fn purge_expired(sessions: &mut HashMap<String, SseSession>) {
+        sessions.retain(|id, session| {
+            let expired = session.detached_at.is_some_and(|at| at.elapsed() > SSE_SESSION_TTL);
+            if expired {
+                debug!(session = %id, "SSE session expired");
+            }
+            !expired
+        });
+    }
+}
+
+impl SseConnection {
+    /// Missed events, then new ones as they are sent, until the client goes
+    /// away or a newer connection resumes the session.
+    fn into_stream(
+        mut self,
+    ) -> impl futures::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>
+    {
+        use futures::StreamExt;
+
+        let session = self.session.clone();
+        let replay = futures::stream::iter(std::mem::take(&mut self.replay));
+        let live = futures::stream::unfold(self, |mut connection| async move {
+            let event = connection.events.recv().await?;
+            Some((event, connection))
+        });
+        replay.chain(live).map(move |event| {
+            Ok(axum::response::sse::Event::default()
+                .id(format!("{}-{}", session, event.seq))
+                .event(event.name)
+                .data(event.data))
+        })
+    }
+}
+
+impl Drop for SseConnection {
+    fn drop(&mut self) {
+        self.sessions.disconnect(&self.session, self.number);
+        info!(session = %self.session, "SSE connection closed");
+    }
+}
+
+/// Forward bus notifications to SSE sessions until the bus closes
+async fn forward_events(mut events: broadcast::Receiver<ServerEvent>, sessions: Arc<SseSessions>) {
+    loop {
+        match events.recv().await {
+            Ok(event) => sessions.deliver(&event),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!(missed, "SSE clients missed notifications");
+            },
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Session id and sequence number of a `<session>-<seq>` event id
+fn parse_event_id(id: &str) -> Option<(&str, u64)> {
+    let (session, seq) = id.trim().rsplit_once('-')?;
+    Some((session, seq.parse().ok()?))
+}
+
+/// HTTP routes for the SSE transport, with request size and time limits from `config`.
+fn sse_router(
+    handler: Arc<DaedraHandler>,
+    config: &ServerConfig,
+    host_policy: HostPolicy,
+    sessions: Arc<SseSessions>,
+) -> axum::Router {
+    use axum::{
+        Json, Router,
+        extract::{DefaultBodyLimit, Extension, Query, Request, State},
+        http::{HeaderMap, StatusCode},
+        middleware::{self, Next},
+        response::{
+            IntoResponse, Response,
+            sse::{Event, KeepAlive, Sse},
+        },
+        routing::{get, post},
+    };
+    use futures::stream::Stream;
+    use std::convert::Infallible;
+    use tower_http::cors::CorsLayer;
+
+    let request_timeout = config.request_timeout;
+    let host_policy = Arc::new(host_policy);
+
+    // Health check endpoint — always 200 while the process is serving;
+    // "degraded" when every backend circuit is open or the watchdog finds trouble
+    async fn health(State(handler): State<Arc<DaedraHandler>>) -> Json<Value> {
+        let watchdog = handler.watchdog.enabled.then(|| handler.health.borrow().clone());
+        Json(health_report(&handler.backend_status(), watchdog.as_ref()))
+    }
+
+    // SSE endpoint for server-to-client messages, resumable with Last-Event-ID
+    async fn sse_handler(
+        Extension(sessions): Extension<Arc<SseSessions>>,
+        headers: HeaderMap,
+    ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let last_event_id = headers.get("last-event-id").and_then(|v| v.to_str().ok());
+        let connection = sessions.connect(last_event_id);
+        Sse::new(connection.into_stream())
+            .keep_alive(KeepAlive::new().interval(sessions.keep_alive).text("keep-alive"))
+    }
+
+    #[derive(Deserialize)]
+    struct RpcQuery {
+        session_id: Option<String>,
+    }
+
+    // JSON-RPC endpoint: answers in the body, or on the SSE stream of `session_id`
+    async fn rpc_handler(
+        State(handler): State<Arc<DaedraHandler>>,
+        Extension(sessions): Extension<Arc<SseSessions>>,
+        Query(query): Query<RpcQuery>,
+        body: String,
+    ) -> Response {
+        let request = match parse_json_rpc(&body, handler.strict_json) {
+            Ok(request) => request,
+            Err(e) => {
+                return (StatusCode::BAD_REQUEST, Json(parse_error_response(&e))).into_response();
+            },
+        };
+        let Some(session) = query.session_id else {
+            return Json(handler.handle_request(request).await).into_response();
+        };
+        let Some(schema) = sessions.schema(&session) else {
+            return (StatusCode::NOT_FOUND, "Unknown SSE session").into_response();
+        };
+        let notification = is_notification(&request);
+        let work = events::scope(session.clone(), handler.handle_request(request));
+        let response = CONNECTION_SCHEMA.scope(schema, work).await;
+        if !notification {
+            match serde_json::to_string(&response) {
+                Ok(data) => {
+                    sessions.send(&session, "message", data);
+                },
+                Err(e) => error!(error = %e, "Failed to serialize SSE response"),
+            }
+        }
+        StatusCode::ACCEPTED.into_response()
+    }
+
+    Router::new()
+        .route("/health", get(health))
+        .route("/sse", get(sse_handler))
+        .route("/rpc", post(rpc_handler))
+        .layer(Extension(sessions))
+        .layer(DefaultBodyLimit::max(config.max_request_bytes))
+        .layer(middleware::from_fn(move |request: Request, next: Next| {
+            let host_policy = Arc::clone(&host_policy);
+            async move {
+                match host_policy.check(request.headers()) {
+                    Ok(()) => next.run(request).await,
+                    Err(reason) => {
+                        warn!(reason, "Rejected HTTP request (DNS rebinding protection)");
+                        (StatusCode::FORBIDDEN, reason).into_response()
+                    },
+                }
+            }
+        }))
+        .layer(middleware::from_fn(move |request: Request, next: Next| async move {
+            match tokio::time::timeout(request_timeout, next.run(request)).await {
+                Ok(response) => response,
+                Err(_) => {
+                    warn!(timeout = ?request_timeout, "HTTP request timed out");
+                    (StatusCode::REQUEST_TIMEOUT, "Request timed out").into_response()
+                },
+            }
+        }))
+        .layer(CorsLayer::permissive())
+        .with_state(handler)
+}
+
+/// Per-connection limits of the HTTP transport, from [`ServerConfig`]
+#[derive(Debug, Clone, Copy)]
+struct ConnectionLimits {
+    idle_timeout: Duration,
+    write_timeout: Duration,
+}
+
+/// Listener that accepts a connection only while fewer than the configured
+/// number are open, and puts each under [`ConnectionLimits`].
+struct LimitedListener {
+    listener: tokio::net::TcpListener,
+    permits: Arc<Semaphore>,
+    limits: ConnectionLimits,
+}
+
+impl axum::serve::Listener for LimitedListener {
+    type Io = LimitedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let permit = match Arc::clone(&self.permits).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                warn!("Connection limit reached; waiting for a connection to close");
+                Arc::clone(&self.permits)
+                    .acquire_owned()
+                    .await
+                    .expect("connection semaphore is never closed")
+            },
+        };
+        let (stream, addr) = axum::serve::Listener::accept(&mut self.listener).await;
+        debug!(%addr, "HTTP connection accepted");
+        (LimitedStream::new(stream, permit, self.limits), addr)
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        self.listener.local_addr()
+    }
+}
+
+/// Accepted connection holding one of the listener's permits until it closes.
+///
+/// Reads and writes fail with `TimedOut` once the connection has moved no
+/// bytes for `idle_timeout`, or a write has been stuck for `write_timeout`;
+/// the server then drops the connection.
+struct LimitedStream {
+    stream: tokio::net::TcpStream,
+    _permit: tokio::sync::OwnedSemaphorePermit,
+    limits: ConnectionLimits,
+    idle: std::pin::Pin<Box<tokio::time::Sleep>>,
+    write_stall: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl LimitedStream {
+    fn new(
+        stream: tokio::net::TcpStream,
+        permit: tokio::sync::OwnedSemaphorePermit,
+        limits: ConnectionLimits,
+    ) -> Self {
+        Self {
+            stream,
+            _permit: permit,
+            limits,
+            idle: Box::pin(tokio::time::sleep(limits.idle_timeout)),
+            write_stall: None,
+        }
+    }
+
+    fn touch(&mut self) {
+        let deadline = tokio::time::Instant::now() + self.limits.idle_timeout;
+        self.idle.as_mut().reset(deadline);
+    }
+
+    fn poll_idle<T>(&mut self, cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<T>> {
+        match self.idle.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                debug!(timeout = ?self.limits.idle_timeout, "Closing idle HTTP connection");
+                Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "connection idle",
+                )))
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn after_write<T>(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        result: Poll<std::io::Result<T>>,
+    ) -> Poll<std::io::Result<T>> {
+        if result.is_ready() {
+            self.write_stall = None;
+            self.touch();
+            return result;
+        }
+        let timeout = self.limits.write_timeout;
+        let stall = self
+            .write_stall
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        match stall.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                warn!(?timeout, "Closing HTTP connection to a client that stopped reading");
+                Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "client stopped reading",
+                )))
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncRead for LimitedStream {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        match std::pin::Pin::new(&mut this.stream).poll_read(cx, buf) {
+            Poll::Ready(result) => {
+                this.touch();
+                Poll::Ready(result)
+            },
+            Poll::Pending => this.poll_idle(cx),
+        }
+    }
+}
+
+impl AsyncWrite for LimitedStream {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let result = std::pin::Pin::new(&mut this.stream).poll_write(cx, buf);
+        this.after_write(cx, result)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let result = std::pin::Pin::new(&mut this.stream).poll_write_vectored(cx, bufs);
+        this.after_write(cx, result)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let result = std::pin::Pin::new(&mut this.stream).poll_flush(cx);
+        this.after_write(cx, result)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+/// SSE transport bound to its sockets, returned by [`DaedraServer::bind`].
+pub struct BoundServer {
+    app: axum::Router,
+    handler: Arc<DaedraHandler>,
+    sessions: Arc<SseSessions>,
+    events: broadcast::Receiver<ServerEvent>,
+    listeners: Vec<tokio::net::TcpListener>,
+    /// Open connections allowed, shared by every listener
+    permits: Arc<Semaphore>,
+    limits: ConnectionLimits,
+    local_addrs: Vec<SocketAddr>,
+}
+
+impl BoundServer {
+    /// Address of the first listener, with the actual port when bound to port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addrs[0]
+    }
+
+    /// Every bound address (one per resolved bind address)
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// Serve requests until the listeners fail
+    pub async fn serve(self) -> DaedraResult<()> {
+        self.serve_with_shutdown(std::future::pending()).await
+    }
+
+    /// Serve requests until `signal` completes, then stop accepting connections
+    /// and return once in-flight requests have finished.
+    ///
+    /// When [`WatchdogConfig::restart_after`] is set, the listeners are also
+    /// drained and bound again on the same addresses whenever the server stays
+    /// unhealthy that long.
+    pub async fn serve_with_shutdown<F>(self, signal: F) -> DaedraResult<()>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let app = self.app;
+        let sessions = self.sessions;
+        let forwarder = tokio::spawn(forward_events(self.events, Arc::clone(&sessions)));
+        let watchdog = self.handler.spawn_watchdog();
+        let memory_guard = self.handler.spawn_memory_guard();
+        let restart_after = self.handler.watchdog.restart_after.filter(|_| watchdog.is_some());
+        let signal = signal.shared();
+        let mut listeners = self.listeners;
+        let mut restarted = false;
+
+        let served = loop {
+            let stop = {
+                let signal = signal.clone();
+                let health = self.handler.health.subscribe();
+                let sessions = Arc::clone(&sessions);
+                async move {
+                    let restart = async {
+                        match restart_after {
+                            Some(after) => watchdog::unhealthy_for(health, after, restarted).await,
+                            None => std::future::pending().await,
+                        }
+                    };
+                    tokio::select! {
+                        _ = signal => {},
+                        _ = restart => warn!("Watchdog restarting the HTTP listener"),
+                    }
+                    sessions.close_all();
+                }
+                .shared()
+            };
+            let servers = listeners.into_iter().map(|listener| {
+                let listener = LimitedListener {
+                    listener,
+                    permits: Arc::clone(&self.permits),
+                    limits: self.limits,
+                };
+                let app = app.clone();
+                let stop = stop.clone();
+                async move { axum::serve(listener, app).with_graceful_shutdown(stop).await }
+            });
+            if let Err(e) = futures::future::try_join_all(servers).await {
+                break Err(e);
+            }
+            if signal.clone().now_or_never().is_some() {
+                break Ok(());
+            }
+            listeners = match rebind(&self.local_addrs).await {
+                Ok(listeners) => listeners,
+                Err(e) => break Err(e),
+            };
+            restarted = true;
+            info!(addrs = ?self.local_addrs, "SSE listener restarted");
+        };
+        forwarder.abort();
+        for task in [watchdog, memory_guard].into_iter().flatten() {
+            task.abort();
+        }
+        self.handler.cache.flush().await;
+        self.handler.fetch_client.cookie_jar().flush().await;
+        served.map_err(|e| DaedraError::ServerError(format!("Server error: {}", e)))?;
+        info!("SSE server stopped");
+        Ok(())
+    }
+
+    /// Serve on a background task, returning a handle that can stop it
+    pub fn spawn(self) -> ServerHandle {
+        let (shutdown, mut stop) = tokio::sync::watch::channel(false);
+        let local_addrs = self.local_addrs.clone();
+        let task = tokio::spawn(self.serve_with_shutdown(async move {
+            // A dropped sender (handle gone) also stops the server
+            let _ = stop.wait_for(|stop| *stop).await;
+        }));
+        ServerHandle {
+            local_addrs,
+            shutdown,
+            task,
+        }
+    }
+}
+
+/// Listeners on `addrs` again, after a restart released them
+async fn rebind(addrs: &[SocketAddr]) -> std::io::Result<Vec<tokio::net::TcpListener>> {
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        listeners.push(tokio::net::TcpListener::bind(addr).await?);
+    }
+    Ok(listeners)
+}
+
+/// Handle to an SSE server running in the background, returned by
+/// [`DaedraServer::spawn`] and [`BoundServer::spawn`].
+///
+/// Dropping the handle shuts the server down; use [`ServerHandle::join`] to
+/// wait for it instead.
+pub struct ServerHandle {
+    local_addrs: Vec<SocketAddr>,
+    shutdown: tokio::sync::watch::Sender<bool>,
+    task: tokio::task::JoinHandle<DaedraResult<()>>,
+}
+
+impl ServerHandle {
+    /// Address of the first listener, with the actual port when bound to port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addrs[0]
+    }
+
+    /// Every bound address (one per resolved bind address)
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// Ask the server to stop accepting connections. In-flight requests still
+    /// complete; await [`ServerHandle::join`] to know when it has stopped.
+    /// Calling it again is a no-op.
+    pub fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Wait for the server to stop, returning its result
+    pub async fn join(self) -> DaedraResult<()> {
+        // `self.shutdown` stays alive until the task ends, so waiting never stops the server
+        self.task
+            .await
+            .map_err(|e| DaedraError::ServerError(format!("Server task failed: {}", e)))?
+    }
+}
+
+/// Main Daedra MCP server
+pub struct DaedraServer {
+    handler: DaedraHandler,
+    config: ServerConfig,
+}
+
+impl DaedraServer {
+    /// Create a new Daedra server with the given configuration
+    pub fn new(config: ServerConfig) -> DaedraResult<Self> {
+        let handler = DaedraHandler::new(config.clone())?;
+        Ok(Self { handler, config })
+    }
+
+    /// Circuit breaker status of every search backend, in priority order
+    pub fn backend_status(&self) -> Vec<tools::BackendStatus> {
+        self.handler.backend_status()
+    }
+
+    /// Create a new server with default configuration
+    pub fn with_defaults() -> DaedraResult<Self> {
+        Self::new(ServerConfig::default())
+    }
+
+    /// Run the server with the specified transport
+    #[instrument(skip(self))]
+    pub async fn run(self, transport: TransportType) -> DaedraResult<()> {
+        info!(
+            server = SERVER_NAME,
+            version = VERSION,
+            "Starting Daedra MCP server"
+        );
+
+        match transport.http_config() {
+            None => {
+                let cache = self.handler.cache.clone();
+                let fetch_client = Arc::clone(&self.handler.fetch_client);
+                let served = self.run_stdio().await;
+                cache.flush().await;
+                fetch_client.cookie_jar().flush().await;
+                served
+            },
+            Some(http) => self.run_sse(http).await,
+        }
+    }
+
+    /// Run the server with STDIO transport.
+    ///
+    /// Returns `Ok(())` once the client goes away: stdin reaching EOF (which also
+    /// cancels a request still in flight) or stdout failing with a broken pipe.
+    /// A client may instead end the session with a `shutdown` request, answered
+    /// with `null` once earlier responses are written (later requests other than
+    /// `exit` are rejected), then an `exit` notification.
+    async fn run_stdio(self) -> DaedraResult<()> {
+        info!("Starting STDIO transport");
+
+        let stdin = tokio::io::stdin();
+        let mut stdout = tokio::io::BufWriter::new(tokio::io::stdout());
+        let mut reader = BufReader::new(stdin);
+        let max_line = self.config.max_request_bytes;
+        let mut session = StdioSession::default();
+        let watchdog = self.handler.spawn_watchdog();
+        let memory_guard = self.handler.spawn_memory_guard();
+
+        loop {
+            let (message, framing) = match read_stdio_message(&mut reader, max_line).await {
+                Ok(message) => message,
+                Err(e) if is_disconnect(&e) => {
+                    info!(error = %e, "STDIN disconnected, shutting down");
+                    break;
+                },
+                Err(e) => return Err(e.into()),
+            };
+            let line = match message {
+                StdioLine::Line(line) => line,
+                StdioLine::TooLong { len, id } => {
+                    warn!(bytes = len, limit = max_line, "Discarded oversized STDIO request");
+                    let response = JsonRpcResponse::error(
+                        id,
+                        -32600,
+                        format!("Request of {len} bytes exceeds the {max_line} byte limit"),
+                    );
+                    if send_stdio_response(response, framing, &mut stdout).await? {
+                        continue;
+                    }
+                    break;
+                },
+                StdioLine::BadHeader(reason) => {
+                    warn!(%reason, "Discarded STDIO message with a bad header");
+                    let response =
+                        JsonRpcResponse::error(None, -32700, format!("Parse error: {reason}"));
+                    if send_stdio_response(response, framing, &mut stdout).await? {
+                        continue;
+                    }
+                    break;
+                },
+                StdioLine::Eof => {
+                    info!("STDIN closed by client, shutting down");
+                    break;
+                },
+            };
+
+            let response = {
+                let work = process_stdio_line(&line, &self.handler, &mut session);
+                tokio::pin!(work);
+                tokio::select! {
+                    response = &mut work => response,
+                    closed = input_closed(&mut reader) => {
+                        if closed {
+                            info!("STDIN closed with a request in flight, cancelling it");
+                            break;
+                        }
+                        // The client pipelined another request; finish this one first.
+                        work.await
+                    },
+                }
+            };
+
+            if let Some(response) = response
+                && !send_stdio_response(response, framing, &mut stdout).await?
+            {
+                break;
+            }
+            if session.exiting {
+                info!("Client requested exit, shutting down");
+                break;
+            }
+        }
+
+        stdout.flush().await.ok();
+        for task in [watchdog, memory_guard].into_iter().flatten() {
+            task.abort();
+        }
+        let stats = self.handler.cache.stats();
+        info!(
+            search_entries = stats.search_entries,
+            page_entries = stats.page_entries,
+            cache_bytes = stats.bytes,
+            "STDIO server stopped"
+        );
+        Ok(())
+    }
+
+    /// Run the server with SSE transport
+    async fn run_sse(self, http: HttpTransportConfig) -> DaedraResult<()> {
+        info!(host = %http.host, port = http.port, "Starting SSE transport");
+        self.bind(&http.host, http.port).await?.serve().await
+    }
+
+    /// Bind the SSE transport's sockets without serving yet.
+    ///
+    /// Port `0` picks an ephemeral port; read it back with
+    /// [`BoundServer::local_addr`] before calling [`BoundServer::serve`]. A host
+    /// that resolves to several addresses shares one port across all of them.
+    pub async fn bind(self, host: &str, port: u16) -> DaedraResult<BoundServer> {
+        let addrs = resolve_bind_addrs(host, port).await?;
+        let mut listeners: Vec<tokio::net::TcpListener> = Vec::with_capacity(addrs.len());
+        let mut bind_errors = Vec::new();
+        for mut addr in addrs.iter().copied() {
+            // Keep every listener on the port the first ephemeral bind got
+            if let Some(bound) = listeners.first().and_then(|l| l.local_addr().ok()) {
+                addr.set_port(bound.port());
+            }
+            match tokio::net::TcpListener::bind(addr).await {
+                Ok(listener) => listeners.push(listener),
+                Err(e) => {
+                    warn!(%addr, error = %e, "Failed to bind");
+                    bind_errors.push(format!("{addr}: {e}"));
+                },
+            }
+        }
+        if listeners.is_empty() {
+            return Err(DaedraError::ServerError(format!(
+                "Failed to bind to {host}:{port}: {}",
+                bind_errors.join("; ")
+            )));
+        }
+
+        let bind_ips: Vec<IpAddr> = addrs.iter().map(|a| a.ip()).collect();
+        let host_policy = HostPolicy::new(host, &bind_ips, &self.config.allowed_hosts);
+        if host_policy.any_host && !host_policy.any_origin {
+            warn!(
+                "Listening on all interfaces without allowed hosts; Host header checks are off \
+                 (set ServerConfig::allowed_hosts / --allowed-host to enable them)"
+            );
+        }
+        let local_addrs = listeners
+            .iter()
+            .map(|l| l.local_addr())
+            .collect::<std::io::Result<Vec<_>>>()?;
+        for addr in &local_addrs {
+            info!("SSE server listening on http://{addr}");
+        }
+
+        let sessions = Arc::new(SseSessions::new(self.config.sse_keep_alive));
+        let events = self.handler.events.subscribe();
+        let limits = ConnectionLimits {
+            idle_timeout: self.config.idle_timeout,
+            write_timeout: self.config.write_timeout,
+        };
+        if limits.idle_timeout <= self.config.request_timeout
+            || limits.idle_timeout <= self.config.sse_keep_alive
+        {
+            warn!(
+                idle_timeout = ?limits.idle_timeout,
+                "Idle timeout is not above the request timeout and SSE keep-alive; \
+                 slow requests and quiet SSE streams may be cut off"
+            );
+        }
+        let handler = Arc::new(self.handler);
+        Ok(BoundServer {
+            app: sse_router(
+                Arc::clone(&handler),
+                &self.config,
+                host_policy,
+                Arc::clone(&sessions),
+            ),
+            handler,
+            sessions,
+            events,
+            listeners,
+            permits: Arc::new(Semaphore::new(self.config.max_connections.max(1))),
+            limits,
+            local_addrs,
+        })
+    }
+
+    /// Bind the SSE transport and serve it on a background task.
+    ///
+    /// The returned [`ServerHandle`] reports the bound address and stops the
+    /// server with [`ServerHandle::shutdown`], which makes in-process hosting
+    /// and tests straightforward. Must be called within a Tokio runtime.
+    pub async fn spawn(self, host: &str, port: u16) -> DaedraResult<ServerHandle> {
+        Ok(self.bind(host, port).await?.spawn())
+    }
+
+    /// Get the server's cache statistics
+    pub fn cache_stats(&self) -> crate::cache::CacheStats {
+        self.handler.cache.stats()
+    }
+
+    /// Clear the server's cache
+    pub async fn clear_cache(&self) {
+        self.handler.clear_cache().await;
+    }
+
+    /// Bus for notifications to connected clients, e.g. to announce
+    /// [`ServerNotification::ToolsListChanged`] while the server runs
+    pub fn events(&self) -> EventBus {
+        self.handler.events.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::LinkScope;
+
+    #[test]
+    fn test_server_config_default() {
+        let config = ServerConfig::default();
+        assert!(!config.verbose);
+        assert_eq!(config.max_concurrent_tools, 10);
+    }
+
+    #[test]
+    fn test_transport_type_default() {
+        assert_eq!(TransportType::default(), TransportType::Stdio);
+        assert_eq!(TransportType::Stdio.http_config(), None);
+        let http = HttpTransportConfig::new("::1", 8080);
+        assert_eq!(TransportType::from(http.clone()).http_config(), Some(http));
+        assert_eq!(HttpTransportConfig::default().port, 3000);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_legacy_sse_transport_maps_to_http_config() {
+        let legacy = TransportType::Sse {
+            port: 8080,
+            host: "0.0.0.0".to_string(),
+        };
+        assert_eq!(legacy.http_config(), Some(HttpTransportConfig::new("0.0.0.0", 8080)));
+    }
+
+    #[tokio::test]
+    async fn test_handler_creation() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config);
+        assert!(handler.is_ok());
+    }
+
+    #[test]
+    fn test_list_tools() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+        let tools = handler.list_tools();
+
+        assert_eq!(tools.len(), 11);
+        assert!(tools.iter().any(|t| t.name == "web_search"));
+        assert!(tools.iter().any(|t| t.name == "search_duckduckgo"));
+        assert!(tools.iter().any(|t| t.name == "search_and_read"));
+        assert!(tools.iter().any(|t| t.name == "deep_research"));
+        assert!(tools.iter().any(|t| t.name == "visit_page"));
+        assert!(tools.iter().any(|t| t.name == "crawl_site"));
+        assert!(tools.iter().any(|t| t.name == "search_hackernews"));
+        assert!(tools.iter().any(|t| t.name == "search_stackoverflow"));
+        assert!(tools.iter().any(|t| t.name == "get_transcript"));
+        assert!(tools.iter().any(|t| t.name == "check_url"));
+    }
+
+    #[test]
+    fn test_json_rpc_response_success() {
+        let response = JsonRpcResponse::success(Some(json!(1)), json!({"status": "ok"}));
+        assert_eq!(response.jsonrpc, "2.0");
+        assert!(response.result.is_some());
+        assert!(response.error.is_none());
+    }
+
+    #[test]
+    fn test_json_rpc_response_error() {
+        let response =
+            JsonRpcResponse::error(Some(json!(1)), -32600, "Invalid request".to_string());
+        assert_eq!(response.jsonrpc, "2.0");
+        assert!(response.result.is_none());
+        assert!(response.error.is_some());
+        assert_eq!(response.error.unwrap().code, -32600);
+    }
+
+    #[tokio::test]
+    async fn test_handle_ping() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "ping".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.result.is_some());
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_initialize() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "initialize".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.result.is_some());
+
+        let result = response.result.unwrap();
+        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
+        assert_eq!(result["serverInfo"]["name"], SERVER_NAME);
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_list() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/list".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.result.is_some());
+
+        let result = response.result.unwrap();
+        let tools = result["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_handle_unknown_method() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "unknown/method".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_some());
+        assert_eq!(response.error.unwrap().code, -32601);
+    }
+
+    #[test]
+    fn test_parse_json_rpc_leniency() {
+        let quirky = [
+            "\u{feff}{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}",
+            r#"{"jsonrpc":"2.0","id":1,"method":"ping","params":{"tags":["a,]",],},}"#,
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\r\n\0\0 garbage",
+        ];
+        for message in quirky {
+            let request = parse_json_rpc(message, false).unwrap();
+            assert_eq!((request.method.as_str(), request.id), ("ping", Some(json!(1))));
+            assert!(parse_json_rpc(message, true).is_err(), "{message}");
+        }
+        let request = parse_json_rpc(quirky[1], false).unwrap();
+        assert_eq!(request.params.unwrap()["tags"], json!(["a,]"]));
+
+        assert!(parse_json_rpc("{\"jsonrpc\":", false).is_err());
+        assert!(parse_json_rpc("garbage {}", false).is_err());
+        assert_eq!(
+            strip_trailing_commas(r#"[1, 2 ,] "\",}" {"a":1 , }"#),
+            r#"[1, 2 ] "\",}" {"a":1  }"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_parse_error_and_notification() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+
+        let response = handler.handle_message("{not json").await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32700);
+
+        assert!(handler.handle_message("   ").await.is_none());
+        assert!(
+            handler
+                .handle_message(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+                .await
+                .is_none()
+        );
+
+        let response = handler
+            .handle_message(r#"{"jsonrpc":"2.0","id":7,"method":"ping"}"#)
+            .await
+            .unwrap();
+        assert_eq!(response.id, Some(json!(7)));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_shutdown_handshake() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let mut session = StdioSession::default();
+        let mut send = async |line: &str| process_stdio_line(line, &handler, &mut session).await;
+
+        let ping = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+        assert!(send(ping).await.unwrap().error.is_none());
+        let response = send(r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#).await.unwrap();
+        assert_eq!(response.result, Some(Value::Null));
+        let response = send(ping).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32600);
+        assert!(send(r#"{"jsonrpc":"2.0","method":"exit"}"#).await.is_none());
+        assert!(session.shutting_down && session.exiting);
+    }
+
+    #[tokio::test]
+    async fn test_run_isolated_turns_panic_into_tool_error() {
+        let response = run_isolated(Some(json!(3)), "visit_page", async {
+            panic!("selector blew up on hostile page")
+        })
+        .await;
+
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], true);
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("visit_page"));
+        assert!(text.contains("selector blew up on hostile page"));
+        assert_eq!(response.id, Some(json!(3)));
+    }
+
+    #[tokio::test]
+    async fn test_run_isolated_passes_through_normal_responses() {
+        let response = run_isolated(Some(json!(1)), "ping", async {
+            tool_success_response(Some(json!(1)), "ok".to_string())
+        })
+        .await;
+        assert_eq!(response.result.unwrap()["isError"], false);
+    }
+
+    #[test]
+    fn test_panic_message_formats() {
+        let owned: Box<dyn std::any::Any + Send> = Box::new(format!("code {}", 42));
+        assert_eq!(panic_message(owned.as_ref()), "code 42");
+        let other: Box<dyn std::any::Any + Send> = Box::new(7_u8);
+        assert_eq!(panic_message(other.as_ref()), "unknown panic");
+    }
+
+    #[tokio::test]
+    async fn test_handle_notifications_initialized() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        // Test the "notifications/initialized" variant (with prefix)
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "notifications/initialized".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        // Should succeed, not return "Method not found"
+        assert!(
+            response.error.is_none(),
+            "notifications/initialized should not return error"
+        );
+        assert!(response.result.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_handle_initialized_without_prefix() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        // Test the "initialized" variant (without prefix)
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "initialized".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(
+            response.error.is_none(),
+            "initialized should not return error"
+        );
+        assert!(response.result.is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "network"]
+    async fn test_handle_call_tool_web_search() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({"name": "web_search", "arguments": {"query": "test"}})),
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], false);
+        assert!(result["content"].as_array().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_handle_call_tool_unknown() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({"name": "nonexistent", "arguments": {}})),
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_some());
+        assert_eq!(response.error.unwrap().code, -32601);
+    }
+
+    #[tokio::test]
+    async fn test_handle_call_tool_missing_params() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: None,
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_some());
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_is_notification_no_id() {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            method: "initialized".to_string(),
+            params: None,
+        };
+        assert!(is_notification(&request));
+    }
+
+    #[test]
+    fn test_is_notification_null_id() {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(Value::Null),
+            method: "initialized".to_string(),
+            params: None,
+        };
+        assert!(is_notification(&request));
+    }
+
+    #[test]
+    fn test_is_notification_with_id() {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "ping".to_string(),
+            params: None,
+        };
+        assert!(!is_notification(&request));
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc_parse_error() {
+        let config = ServerConfig::default();
+        let handler = DaedraHandler::new(config).unwrap();
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "tools/call".to_string(),
+            params: Some(json!({"name": "web_search", "arguments": {"not_query": true}})),
+        };
+
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_some());
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_execute_search_caches_results() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let args = SearchArgs {
+            query: "cache-test-unique-query-xyz".to_string(),
+            options: None,
+        };
+        let options = args.options.clone().unwrap_or_default();
+        let cached_response = SearchResponse::new(args.query.clone(), vec![], &options);
+        handler
+            .cache()
+            .set_search(
+                &args.query,
+                &options.region,
+                &options.safe_search.to_string(),
+                cached_response.clone(),
+            )
+            .await;
+
+        let result = handler.execute_search(args).await.unwrap();
+        assert_eq!(result.data.len(), cached_response.data.len());
+        assert_eq!(result.metadata.query, cached_response.metadata.query);
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_initialize() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method("initialize", Some(json!(1)), None)
+            .await;
+        assert!(response.result.is_some());
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
+        assert_eq!(result["serverInfo"]["name"], SERVER_NAME);
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_ping() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler.handle_method("ping", Some(json!(1)), None).await;
+        assert!(response.result.is_some());
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_tools_list() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler.handle_method("tools/list", Some(json!(1)), None).await;
+        assert!(response.result.is_some());
+        let result = response.result.unwrap();
+        let tools = result["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_unknown() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler.handle_method("foo", Some(json!(1)), None).await;
+        let err = response.error.unwrap();
+        assert_eq!(err.code, -32601);
+        assert!(err.message.contains("foo"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_initialized() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler.handle_method("initialized", Some(json!(1)), None).await;
+        assert!(response.error.is_none());
+        assert_eq!(response.result.unwrap(), json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_notifications_initialized() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method("notifications/initialized", Some(json!(1)), None)
+            .await;
+        assert!(response.error.is_none());
+        assert_eq!(response.result.unwrap(), json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_tools_call_missing_params() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method("tools/call", Some(json!(1)), None)
+            .await;
+        assert!(response.result.is_none());
+        let err = response.error.unwrap();
+        assert_eq!(err.code, -32602);
+        assert!(err.message.contains("Missing parameters"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_method_tools_call_unknown_tool() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method(
+                "tools/call",
+                Some(json!(1)),
+                Some(json!({"name": "unknown", "arguments": {}})),
+            )
+            .await;
+        assert!(response.result.is_none());
+        let err = response.error.unwrap();
+        assert_eq!(err.code, -32601);
+        assert!(err.message.contains("unknown"));
+    }
+
+    #[tokio::test]
+    #[ignore = "network"]
+    async fn test_handle_method_tools_call_web_search_no_args() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method(
+                "tools/call",
+                Some(json!(1)),
+                Some(json!({"name": "web_search", "arguments": {}})),
+            )
+            .await;
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert!(result.get("isError").is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "network"]
+    async fn test_execute_search_returns_results() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let args = SearchArgs {
+            query: "Rust programming language".to_string(),
+            options: Some(crate::types::SearchOptions {
+                num_results: 5,
+                ..Default::default()
+            }),
+        };
+        let response = handler.execute_search(args).await.unwrap();
+        assert!(!response.data.is_empty(), "search should return results");
+    }
+
+    #[tokio::test]
+    #[ignore = "network"]
+    async fn test_execute_search_caches_on_second_call() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let args = SearchArgs {
+            query: "cache-second-call-unique-query-abc".to_string(),
+            options: None,
+        };
+        let first = handler.execute_search(args.clone()).await;
+        let second = handler.execute_search(args).await;
+        assert!(first.is_ok(), "first search should succeed: {:?}", first.err());
+        assert!(second.is_ok(), "second search should succeed: {:?}", second.err());
+        assert!(!first.unwrap().data.is_empty());
+        assert!(!second.unwrap().data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_visit_page_malformed_args() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_visit_page(Some(json!(1)), json!({"url": 12345}))
+            .await;
+        assert!(response.result.is_none());
+        let err = response.error.unwrap();
+        assert_eq!(err.code, -32602);
+        assert!(err.message.contains("Invalid fetch arguments"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_visit_page_invalid_url() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = handler
+            .handle_method(
+                "tools/call",
+                Some(json!(1)),
+                Some(json!({
+                    "name": "visit_page",
+                    "arguments": {"url": "ftp://example.com"}
+                })),
+            )
+            .await;
+        assert!(response.error.is_none());
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], true);
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Invalid URL"));
     }
 
-    /// Create a new server with default configuration
-    pub fn with_defaults() -> DaedraResult<Self> {
-        Self::new(ServerConfig::default())
+    fn tool_text(response: JsonRpcResponse) -> Value {
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], false, "{result}");
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap()
     }
 
-    /// Run the server with the specified transport
-    #[instrument(skip(self))]
-    pub async fn run(self, transport: TransportType) -> DaedraResult<()> {
-        info!(
-            server = SERVER_NAME,
-            version = VERSION,
-            "Starting Daedra MCP server"
-        );
-
-        match transport {
-            TransportType::Stdio => self.run_stdio().await,
-            TransportType::Sse { port, host } => self.run_sse(host, port).await,
-        }
+    /// Call the tool `name` through `tools/call`
+    async fn call_tool(handler: &DaedraHandler, name: &str, arguments: Value) -> JsonRpcResponse {
+        let params = json!({"name": name, "arguments": arguments});
+        handler.handle_method("tools/call", Some(json!(1)), Some(params)).await
     }
 
-    /// Run the server with STDIO transport
-    async fn run_stdio(self) -> DaedraResult<()> {
-        info!("Starting STDIO transport");
-
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::BufWriter::new(tokio::io::stdout());
-        let reader = BufReader::new(stdin);
-        let mut lines = reader.lines();
-
-        while let Ok(Some(line)) = lines.next_line().await {
-            if let Some(response) = process_stdio_line(&line, &self.handler).await {
-                write_stdio_response(response, &mut stdout).await?;
-            }
-        }
-
-        info!("STDIO server stopped");
-        Ok(())
+    #[tokio::test]
+    async fn test_json_format_lays_out_tool_results() {
+        let value = json!({"data": [{"title": "a"}, 2]});
+        let pretty = JsonFormat::Pretty.to_string(&value).unwrap();
+        assert_eq!(pretty, serde_json::to_string_pretty(&value).unwrap());
+        let compact = JsonFormat::Compact.to_string(&value).unwrap();
+        assert_eq!(compact, r#"{"data":[{"title":"a"},2]}"#);
+
+        let handler = DaedraHandler::new(ServerConfig {
+            json_format: JsonFormat::Compact,
+            dry_run: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let response = call_tool(&handler, "web_search", json!({"query": "rust"})).await;
+        let text = response.result.unwrap()["content"][0]["text"].as_str().unwrap().to_string();
+        assert!(!text.contains('\n'), "{text}");
+        assert!(serde_json::from_str::<Value>(&text).unwrap()["backends"].is_array());
     }
 
-    /// Run the server with SSE transport
-    async fn run_sse(self, host: [u8; 4], port: u16) -> DaedraResult<()> {
-        use axum::{
-            Json, Router,
-            extract::State,
-            response::sse::{Event, Sse},
-            routing::{get, post},
-        };
-        use futures::stream::{self, Stream};
-        use std::convert::Infallible;
-        use tower_http::cors::CorsLayer;
-
-        info!(host = ?host, port = port, "Starting SSE transport");
-
-        let handler = Arc::new(self.handler);
-
-        // Health check endpoint
-        async fn health() -> &'static str {
-            "OK"
-        }
-
-        // SSE endpoint for server-to-client messages
-        async fn sse_handler() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-            let stream = stream::once(async { Ok(Event::default().data("connected")) });
-            Sse::new(stream)
-        }
-
-        // JSON-RPC endpoint
-        async fn rpc_handler(
-            State(handler): State<Arc<DaedraHandler>>,
-            Json(request): Json<JsonRpcRequest>,
-        ) -> Json<JsonRpcResponse> {
-            let response = handler.handle_request(request).await;
-            Json(response)
-        }
-
-        // Build the router
-        let app = Router::new()
-            .route("/health", get(health))
-            .route("/sse", get(sse_handler))
-            .route("/rpc", post(rpc_handler))
-            .layer(CorsLayer::permissive())
-            .with_state(handler);
-
-        let addr = std::net::SocketAddr::from((host, port));
-        let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
-            DaedraError::ServerError(format!(
-                "Failed to bind to {}:{}: {}",
-                host.iter()
-                    .map(|b| b.to_string())
-                    .collect::<Vec<_>>()
-                    .join("."),
-                port,
-                e
+    #[tokio::test]
+    async fn test_schema_version_negotiated_per_session() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Doc</title><meta name='author' content='Ann'></head>\
+                 <body><main><p>Some words about the topic.</p></main></body></html>",
+                "text/html",
             ))
-        })?;
+            .expect(1)
+            .mount(&site)
+            .await;
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let initialize = |requested: Value| {
+            let params = json!({"capabilities": {"experimental": {"daedra": requested}}});
+            handler.handle_method("initialize", Some(json!(1)), Some(params))
+        };
+        let visit = || {
+            let arguments = json!({"url": site.uri(), "format": "json"});
+            handler.handle_method(
+                "tools/call",
+                Some(json!(2)),
+                Some(json!({"name": "visit_page", "arguments": arguments})),
+            )
+        };
+        let negotiated = |response: JsonRpcResponse| {
+            response.result.unwrap()["capabilities"]["experimental"]["daedra"]["schemaVersion"]
+                .clone()
+        };
 
-        info!(
-            "SSE server listening on http://{}:{}",
-            host.iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<_>>()
-                .join("."),
-            port
+        assert_eq!(negotiated(initialize(json!({})).await), 2);
+        let page = tool_text(visit().await);
+        assert_eq!(page["schema_version"], 2);
+        assert_eq!(page["meta"]["author"], "Ann");
+
+        // Without a connection to remember it on, a requested version doesn't stick
+        assert_eq!(negotiated(initialize(json!({"schemaVersion": 1})).await), 2);
+        assert_eq!(tool_text(visit().await)["schema_version"], 2);
+
+        let old = NegotiatedSchema::default();
+        let with_old = |work| CONNECTION_SCHEMA.scope(Arc::clone(&old), work);
+        assert_eq!(negotiated(with_old(initialize(json!({"schemaVersion": 1}))).await), 1);
+        let page = tool_text(with_old(visit()).await);
+        assert!(page.get("schema_version").is_none(), "{page}");
+        assert!(page.get("meta").is_none(), "{page}");
+        assert_eq!(page["title"], "Doc");
+        // Other connections keep the configured version; unknown newer ones get the newest
+        assert_eq!(tool_text(visit().await)["schema_version"], 2);
+        let newer = CONNECTION_SCHEMA.scope(
+            NegotiatedSchema::default(),
+            initialize(json!({"schemaVersion": 9})),
         );
+        assert_eq!(negotiated(newer.await), 2);
 
-        axum::serve(listener, app)
-            .await
-            .map_err(|e| DaedraError::ServerError(format!("Server error: {}", e)))?;
-
-        Ok(())
-    }
-
-    /// Get the server's cache statistics
-    pub fn cache_stats(&self) -> crate::cache::CacheStats {
-        self.handler.cache.stats()
+        let handler = DaedraHandler::new(ServerConfig {
+            schema_version: SchemaVersion::V1,
+            ..Default::default()
+        })
+        .unwrap();
+        let info = handler.get_server_info();
+        assert_eq!(info["capabilities"]["experimental"]["daedra"]["schemaVersion"], 1);
+        assert_eq!(info["capabilities"]["experimental"]["daedra"]["schemaVersions"], json!([1, 2]));
     }
 
-    /// Clear the server's cache
-    pub async fn clear_cache(&self) {
-        self.handler.cache.clear().await;
+    #[tokio::test]
+    async fn test_web_search_dry_run_reports_plan() {
+        let handler = DaedraHandler::new(ServerConfig {
+            search_chain: vec!["bing".to_string(), "nosuch".to_string(), "duckduckgo".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let arguments = json!({"query": "rust async", "dry_run": true});
+        let plan = tool_text(call_tool(&handler, "web_search", arguments).await);
+
+        assert_eq!(plan["strategy"], "fallback");
+        assert_eq!(plan["cached"], false);
+        let backends = plan["backends"].as_array().unwrap();
+        let names: Vec<&str> = backends.iter().map(|b| b["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["bing", "nosuch", "duckduckgo"]);
+        assert_eq!(backends[0]["request"]["params"]["q"], "rust async");
+        assert_eq!(backends[0]["rate_limit"], "scraper: 1 request / 2s, burst 3");
+        assert_eq!(backends[1]["would_query"], false);
+        assert_eq!(backends[1]["skip_reason"], "not configured");
+        assert_eq!(backends[2]["request"]["method"], "POST");
+        assert_eq!(handler.cache().stats().search_entries, 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_server_config_default() {
-        let config = ServerConfig::default();
-        assert!(!config.verbose);
-        assert_eq!(config.max_concurrent_tools, 10);
-    }
+    #[tokio::test]
+    async fn test_search_hackernews_dry_run_and_validation() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "search_hackernews", arguments);
+        let plan = tool_text(
+            call(json!({"query": "rust", "sort": "date", "kind": "show_hn", "dry_run": true}))
+                .await,
+        );
+        assert_eq!(plan["url"], "https://hn.algolia.com/api/v1/search_by_date");
+        assert_eq!(plan["params"]["tags"], "show_hn");
+        assert_eq!(plan["params"]["hitsPerPage"], "10");
 
-    #[test]
-    fn test_transport_type_default() {
-        assert_eq!(TransportType::default(), TransportType::Stdio);
+        let result = call(json!({"query": " ", "dry_run": true})).await.result.unwrap();
+        assert_eq!(result["isError"], true);
+        let response = call(json!({"query": "rust", "sort": "votes"})).await;
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
     #[tokio::test]
-    async fn test_handler_creation() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config);
-        assert!(handler.is_ok());
+    async fn test_search_stackoverflow_dry_run_and_validation() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "search_stackoverflow", arguments);
+        let plan = tool_text(
+            call(json!({
+                "query": "borrow checker",
+                "tags": ["rust"],
+                "accepted_only": true,
+                "site": "codereview",
+                "dry_run": true
+            }))
+            .await,
+        );
+        assert_eq!(plan["url"], "https://api.stackexchange.com/2.3/search/advanced");
+        assert_eq!(plan["params"]["site"], "codereview");
+        assert_eq!(plan["params"]["tagged"], "rust");
+        assert_eq!(plan["params"]["accepted"], "True");
+
+        let result = call(json!({"query": "rust", "site": "", "dry_run": true})).await.result;
+        assert_eq!(result.unwrap()["isError"], true);
+        let response = call(json!({"query": "rust", "sort": "points"})).await;
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
-    #[test]
-    fn test_list_tools() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-        let tools = handler.list_tools();
+    #[tokio::test]
+    async fn test_check_url_reports_status_without_the_body() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/old"))
+            .respond_with(
+                ResponseTemplate::new(301).insert_header("location", format!("{}/new", site.uri())),
+            )
+            .mount(&site)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/new"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/pdf")
+                    .insert_header("server", "nginx"),
+            )
+            .expect(1)
+            .mount(&site)
+            .await;
 
-        assert_eq!(tools.len(), 4);
-        assert!(tools.iter().any(|t| t.name == "web_search"));
-        assert!(tools.iter().any(|t| t.name == "search_duckduckgo"));
-        assert!(tools.iter().any(|t| t.name == "visit_page"));
-        assert!(tools.iter().any(|t| t.name == "crawl_site"));
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "check_url", arguments);
+        let old = format!("{}/old", site.uri());
+        let check = tool_text(call(json!({"url": old})).await);
+        assert_eq!(check["status"], 200);
+        assert_eq!(check["final_url"], format!("{}/new", site.uri()));
+        assert_eq!(check["method"], "HEAD");
+        assert_eq!(check["content_type"], "application/pdf");
+        assert_eq!(check["headers"]["server"], "nginx");
+        assert_eq!(check["fetchable"], true);
+
+        let plan = tool_text(call(json!({"url": old, "dry_run": true})).await);
+        assert_eq!(plan["method"], "HEAD");
+        assert_eq!(plan["url"], old);
+
+        let result = call(json!({"url": "ftp://example.com"})).await.result;
+        assert_eq!(result.unwrap()["isError"], true);
+        let response = call(json!({})).await;
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
-    #[test]
-    fn test_json_rpc_response_success() {
-        let response = JsonRpcResponse::success(Some(json!(1)), json!({"status": "ok"}));
-        assert_eq!(response.jsonrpc, "2.0");
-        assert!(response.result.is_some());
-        assert!(response.error.is_none());
-    }
+    #[tokio::test]
+    async fn test_get_transcript_dry_run_and_validation() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "get_transcript", arguments);
+        let plan = tool_text(
+            call(json!({"url": "https://youtu.be/dQw4w9WgXcQ", "dry_run": true})).await,
+        );
+        assert_eq!(plan["url"], "https://www.youtube.com/watch");
+        assert_eq!(plan["params"]["v"], "dQw4w9WgXcQ");
 
-    #[test]
-    fn test_json_rpc_response_error() {
-        let response =
-            JsonRpcResponse::error(Some(json!(1)), -32600, "Invalid request".to_string());
-        assert_eq!(response.jsonrpc, "2.0");
-        assert!(response.result.is_none());
-        assert!(response.error.is_some());
-        assert_eq!(response.error.unwrap().code, -32600);
+        let result = call(json!({"url": "https://vimeo.com/1", "dry_run": true})).await.result;
+        assert_eq!(result.unwrap()["isError"], true);
+        let response = call(json!({"url": 42})).await;
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
     #[tokio::test]
-    async fn test_handle_ping() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "ping".to_string(),
-            params: None,
-        };
-
-        let response = handler.handle_request(request).await;
-        assert!(response.result.is_some());
-        assert!(response.error.is_none());
+    async fn test_multi_search_dry_run_and_validation() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "multi_search", arguments);
+        let plans = tool_text(call(json!({"queries": ["tokio", "axum"], "dry_run": true})).await);
+        let plans = plans.as_array().unwrap();
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[1]["query"], "axum");
+
+        let empty = call(json!({"queries": [], "dry_run": true})).await.result.unwrap();
+        assert_eq!(empty["isError"], true);
+        let region = json!({"queries": ["rust"], "options": {"region": "en-us"}, "dry_run": true});
+        assert_eq!(call(region).await.result.unwrap()["isError"], true);
+        let response = call(json!({"queries": "rust"})).await;
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_initialize() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "initialize".to_string(),
-            params: None,
-        };
-
-        let response = handler.handle_request(request).await;
-        assert!(response.result.is_some());
+    async fn test_multi_search_isolates_failing_queries() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let result = json!({
+            "url": "https://tokio.rs/",
+            "title": "Tokio",
+            "content": "An asynchronous runtime for the Rust programming language, with I/O and timers.",
+        });
+        Mock::given(path("/search"))
+            .and(query_param("q", "tokio"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [result]})))
+            .mount(&site)
+            .await;
+        Mock::given(path("/search"))
+            .and(query_param("q", "broken"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&site)
+            .await;
 
-        let result = response.result.unwrap();
-        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
-        assert_eq!(result["serverInfo"]["name"], SERVER_NAME);
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let items = handler
+            .execute_multi_search(MultiSearchArgs::new(["tokio", "broken"]))
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].query, "tokio");
+        assert_eq!(items[0].response.as_ref().unwrap().data[0].url, "https://tokio.rs/");
+        assert!(items[0].error.is_none());
+        assert_eq!(items[1].query, "broken");
+        assert!(items[1].response.is_none());
+        assert!(items[1].error.is_some());
     }
 
     #[tokio::test]
-    async fn test_handle_tools_list() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "tools/list".to_string(),
-            params: None,
-        };
-
-        let response = handler.handle_request(request).await;
-        assert!(response.result.is_some());
-
+    async fn test_web_search_rejects_unknown_region() {
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let arguments = json!({"query": "rust", "options": {"region": "en-us"}, "dry_run": true});
+        let response = call_tool(&handler, "web_search", arguments).await;
         let result = response.result.unwrap();
-        let tools = result["tools"].as_array().unwrap();
-        assert_eq!(tools.len(), 4);
+        assert_eq!(result["isError"], true);
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Unknown region 'en-us'"), "{text}");
+        assert!(text.contains("us-en"), "{text}");
     }
 
     #[tokio::test]
-    async fn test_handle_unknown_method() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "unknown/method".to_string(),
-            params: None,
-        };
+    async fn test_server_dry_run_does_not_fetch() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::any())
+            .respond_with(wiremock::ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&site)
+            .await;
 
-        let response = handler.handle_request(request).await;
-        assert!(response.error.is_some());
-        assert_eq!(response.error.unwrap().code, -32601);
+        let handler = DaedraHandler::new(ServerConfig {
+            dry_run: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let url = format!("{}/page?id=1", site.uri());
+        let plan = tool_text(call_tool(&handler, "visit_page", json!({"url": url})).await);
+        assert_eq!(plan["cached"], false);
+        assert_eq!(plan["request"]["url"], url);
+        let note = plan["request"]["note"].as_str().unwrap();
+        assert!(note.ends_with("skipped if the site's robots.txt disallows it"), "{note}");
+
+        let arguments = json!({"root_url": site.uri()});
+        let plan = tool_text(call_tool(&handler, "crawl_site", arguments).await);
+        assert_eq!(plan["max_pages"], 25);
+        assert_eq!(plan["discovery"][0]["url"], format!("{}/sitemap.xml", site.uri()));
     }
 
     #[tokio::test]
-    async fn test_handle_notifications_initialized() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
+    async fn test_tool_results_carry_telemetry() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Hi</title></head><body><p>Hello there.</p></body></html>",
+                "text/html",
+            ))
+            .expect(1)
+            .mount(&site)
+            .await;
 
-        // Test the "notifications/initialized" variant (with prefix)
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "notifications/initialized".to_string(),
-            params: None,
-        };
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = || call_tool(&handler, "visit_page", json!({"url": site.uri()}));
+
+        let first = call().await.result.unwrap();
+        let cost = &first["_meta"]["telemetry"];
+        // The site's robots.txt (a 404 here), then the page
+        assert_eq!(cost["upstream_requests"], 2, "{first}");
+        assert_eq!(cost["bytes_received"], 75);
+        assert_eq!(cost["cache_hits"], 0);
+        assert_eq!(cost["retries"], 0);
+        assert!(cost["duration_ms"].is_u64());
+        for phase in ["queue_ms", "cache_ms", "network_ms", "parse_ms", "serialize_ms"] {
+            assert!(cost["timings"][phase].is_u64(), "{phase}: {first}");
+        }
 
-        let response = handler.handle_request(request).await;
-        // Should succeed, not return "Method not found"
-        assert!(
-            response.error.is_none(),
-            "notifications/initialized should not return error"
-        );
-        assert!(response.result.is_some());
+        let second = call().await.result.unwrap();
+        let cost = &second["_meta"]["telemetry"];
+        assert_eq!(cost["upstream_requests"], 0);
+        assert_eq!(cost["cache_hits"], 1);
     }
 
     #[tokio::test]
-    async fn test_handle_initialized_without_prefix() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        // Test the "initialized" variant (without prefix)
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "initialized".to_string(),
-            params: None,
-        };
+    async fn test_rate_limited_tool_error_says_when_to_retry() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(429).insert_header("retry-after", "600"))
+            .mount(&site)
+            .await;
 
-        let response = handler.handle_request(request).await;
-        assert!(
-            response.error.is_none(),
-            "initialized should not return error"
-        );
-        assert!(response.result.is_some());
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let response = call_tool(&handler, "visit_page", json!({"url": site.uri()})).await;
+        let result = response.result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["_meta"]["retry_after_secs"], 600, "{result}");
+        assert!(result["_meta"]["telemetry"].is_object());
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("in 600s"));
     }
 
     #[tokio::test]
-    #[ignore = "network"]
-    async fn test_handle_call_tool_web_search() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
+    async fn test_cache_invalidate_is_an_admin_tool() {
+        let call = json!({
+            "name": "cache_invalidate",
+            "arguments": {"pattern": "https://a.example/"}
+        });
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        assert!(!handler.list_tools().iter().any(|t| t.name == "cache_invalidate"));
+        let response =
+            handler.handle_method("tools/call", Some(json!(1)), Some(call.clone())).await;
+        assert_eq!(response.error.unwrap().code, -32601);
 
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "tools/call".to_string(),
-            params: Some(json!({"name": "web_search", "arguments": {"query": "test"}})),
+        let config = ServerConfig {
+            admin_tools: true,
+            ..Default::default()
+        };
+        let handler = DaedraHandler::new(config).unwrap();
+        assert!(handler.list_tools().iter().any(|t| t.name == "cache_invalidate"));
+        let mut notifications = handler.events().subscribe();
+        let args = |url: &str| VisitPageArgs {
+            url: url.to_string(),
+            ..Default::default()
         };
+        for url in ["https://a.example/x", "https://b.example/y"] {
+            let page = PageContent {
+                url: url.to_string(),
+                title: "Page".to_string(),
+                content: "Body".to_string(),
+                timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
+                word_count: 1,
+                links: None,
+                images: None,
+                published_date: None,
+                content_type: None,
+                meta: None,
+                pages: None,
+                truncated: false,
+                next_cursor: None,
+                next_index: None,
+            };
+            handler.cache().set_page(&args(url), page).await;
+        }
 
-        let response = handler.handle_request(request).await;
-        assert!(response.error.is_none());
-        let result = response.result.unwrap();
+        let result = handler
+            .handle_method("tools/call", Some(json!(1)), Some(call))
+            .await
+            .result
+            .unwrap();
         assert_eq!(result["isError"], false);
-        assert!(result["content"].as_array().is_some());
+        let text = result["content"][0]["text"].as_str().unwrap();
+        let dropped: InvalidatedEntries = serde_json::from_str(text).unwrap();
+        assert_eq!(dropped.page_entries, 1);
+        assert!(!handler.cache().contains_page(&args("https://a.example/x")));
+        assert!(handler.cache().contains_page(&args("https://b.example/y")));
+        let notification = notifications.recv().await.unwrap();
+        assert!(matches!(
+            notification.notification,
+            ServerNotification::CacheInvalidated { page_entries: 1, .. }
+        ));
+
+        let empty = json!({"name": "cache_invalidate", "arguments": {"pattern": " "}});
+        let result = handler.handle_method("tools/call", None, Some(empty)).await.result.unwrap();
+        assert_eq!(result["isError"], true);
     }
 
     #[tokio::test]
-    async fn test_handle_call_tool_unknown() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "tools/call".to_string(),
-            params: Some(json!({"name": "nonexistent", "arguments": {}})),
-        };
+    async fn test_dead_link_is_not_fetched_again_while_its_failure_is_cached() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/gone"))
+            .respond_with(wiremock::ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&site)
+            .await;
 
-        let response = handler.handle_request(request).await;
-        assert!(response.error.is_some());
-        assert_eq!(response.error.unwrap().code, -32601);
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let visit = json!({
+            "name": "visit_page",
+            "arguments": {"url": format!("{}/gone", site.uri())}
+        });
+        let mut texts = Vec::new();
+        for _ in 0..2 {
+            let result = handler
+                .handle_method("tools/call", Some(json!(1)), Some(visit.clone()))
+                .await
+                .result
+                .unwrap();
+            assert_eq!(result["isError"], true);
+            texts.push(result["content"][0]["text"].as_str().unwrap().to_string());
+            if texts.len() == 2 {
+                assert!(result["_meta"]["retry_after_secs"].as_u64().unwrap() <= 60);
+            }
+        }
+        assert!(texts[0].contains("HTTP 404"), "{}", texts[0]);
+        assert!(texts[1].contains("HTTP 404"), "{}", texts[1]);
+        assert!(texts[1].contains("cached from an earlier attempt"), "{}", texts[1]);
     }
 
     #[tokio::test]
-    async fn test_handle_call_tool_missing_params() {
-        let config = ServerConfig::default();
+    async fn test_expired_page_is_revalidated_and_kept_when_unchanged() {
+        use wiremock::matchers::{header, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        Mock::given(path("/doc"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&site)
+            .await;
+        Mock::given(path("/doc"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_raw("<p>Original text</p>", "text/html"),
+            )
+            .expect(1)
+            .mount(&site)
+            .await;
+        let config = ServerConfig {
+            cache: CacheConfig {
+                ttl: Duration::from_millis(200),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
         let handler = DaedraHandler::new(config).unwrap();
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "tools/call".to_string(),
-            params: None,
+        let args = VisitPageArgs {
+            url: format!("{}/doc", site.uri()),
+            ..Default::default()
         };
 
-        let response = handler.handle_request(request).await;
-        assert!(response.error.is_some());
-        assert_eq!(response.error.unwrap().code, -32602);
+        let first = handler.fetch_uncut(&args).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(!handler.cache.contains_page(&args));
+        let note = handler.plan_fetch(&args).request.unwrap().note.unwrap();
+        assert!(note.contains("conditional"), "{note}");
+
+        let second = handler.fetch_uncut(&args).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(second.content.contains("Original text"));
+        // The 304 refreshed the page's time in the cache
+        assert!(handler.cache.contains_page(&args));
     }
 
-    #[test]
-    fn test_is_notification_no_id() {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: None,
-            method: "initialized".to_string(),
-            params: None,
-        };
-        assert!(is_notification(&request));
-    }
+    #[cfg(feature = "searxng")]
+    #[tokio::test]
+    async fn test_expand_snippets_replaces_snippets_with_page_leads() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [
+                    {"url": format!("{}/a", site.uri()), "title": "A", "content": "short"},
+                    {"url": format!("{}/b", site.uri()), "title": "B", "content": "kept"}
+                ]
+            })))
+            .mount(&site)
+            .await;
+        Mock::given(path("/a"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>A</title></head><body><article><h1>A</h1>\
+                 <p>Page A opens with a paragraph long enough to count as its lead.</p>\
+                 <p>Its second paragraph adds the detail that makes the result worth reading.</p>\
+                 <p>The third paragraph is not part of the lead.</p></article></body></html>",
+                "text/html",
+            ))
+            .expect(1)
+            .mount(&site)
+            .await;
+        Mock::given(path("/b"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&site)
+            .await;
 
-    #[test]
-    fn test_is_notification_null_id() {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(Value::Null),
-            method: "initialized".to_string(),
-            params: None,
-        };
-        assert!(is_notification(&request));
-    }
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let response = handler
+            .execute_search(SearchArgs {
+                query: "pages".to_string(),
+                options: Some(SearchOptions {
+                    expand_snippets: true,
+                    ..Default::default()
+                }),
+            })
+            .await
+            .unwrap();
 
-    #[test]
-    fn test_is_notification_with_id() {
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "ping".to_string(),
-            params: None,
+        assert_eq!(
+            response.data[0].description,
+            "Page A opens with a paragraph long enough to count as its lead.\n\n\
+             Its second paragraph adds the detail that makes the result worth reading."
+        );
+        assert_eq!(response.data[1].description, "kept");
+        // The expanded page is now cached for visit_page
+        let page = VisitPageArgs {
+            url: format!("{}/a", site.uri()),
+            ..Default::default()
         };
-        assert!(!is_notification(&request));
+        assert!(handler.cache().contains_page(&page));
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_json_rpc_parse_error() {
-        let config = ServerConfig::default();
-        let handler = DaedraHandler::new(config).unwrap();
+    async fn test_probe_fetchability_annotates_top_results() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
 
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: Some(json!(1)),
-            method: "tools/call".to_string(),
-            params: Some(json!({"name": "web_search", "arguments": {"not_query": true}})),
-        };
+        let site = MockServer::start().await;
+        let results: Vec<Value> = ["page", "missing", "clip"]
+            .iter()
+            .map(|p| json!({"url": format!("{}/{}", site.uri(), p), "title": p}))
+            .collect();
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+            .mount(&site)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/page"))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-type", "text/html"))
+            .mount(&site)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/clip"))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-type", "video/mp4"))
+            .mount(&site)
+            .await;
 
-        let response = handler.handle_request(request).await;
-        assert!(response.error.is_some());
-        assert_eq!(response.error.unwrap().code, -32602);
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let response = handler
+            .execute_search(SearchArgs {
+                query: "pages".to_string(),
+                options: Some(SearchOptions {
+                    probe_fetchability: true,
+                    ..Default::default()
+                }),
+            })
+            .await
+            .unwrap();
+
+        let probes: Vec<&FetchProbe> =
+            response.data.iter().map(|r| r.metadata.probe.as_ref().unwrap()).collect();
+        assert!(probes[0].fetchable);
+        assert_eq!(probes[0].content_type.as_deref(), Some("text/html"));
+        assert!(!probes[1].fetchable);
+        assert_eq!(probes[1].status, Some(404));
+        assert!(!probes[2].fetchable);
+        assert_eq!(probes[2].reason.as_deref(), Some("binary content (video/mp4)"));
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_execute_search_caches_results() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let args = SearchArgs {
-            query: "cache-test-unique-query-xyz".to_string(),
-            options: None,
-        };
-        let options = args.options.clone().unwrap_or_default();
-        let cached_response = SearchResponse::new(args.query.clone(), vec![], &options);
-        handler
-            .cache()
-            .set_search(
-                &args.query,
-                &options.region,
-                &options.safe_search.to_string(),
-                cached_response.clone(),
-            )
+    async fn test_enrich_top_fills_thin_snippets_and_dates() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let detailed = "A backend snippet that already says more than the page's first paragraph \
+                        does, so enrichment leaves it alone.";
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [
+                    {"url": format!("{}/a", site.uri()), "title": "A", "content": "thin"},
+                    {"url": format!("{}/b", site.uri()), "title": "B", "content": detailed},
+                    {"url": format!("{}/c", site.uri()), "title": "C", "content": "not fetched"}
+                ]
+            })))
+            .mount(&site)
             .await;
+        for (page, date) in [("a", "2024-03-12T09:00:00Z"), ("b", "2023-01-02")] {
+            Mock::given(path(format!("/{page}")))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    format!(
+                        "<html><head><title>{page}</title>\
+                         <meta property=\"article:published_time\" content=\"{date}\"></head>\
+                         <body><article><p>Page {page} opens with a paragraph long enough to \
+                         count as its lead.</p></article></body></html>"
+                    ),
+                    "text/html",
+                ))
+                .expect(1)
+                .mount(&site)
+                .await;
+        }
 
-        let result = handler.execute_search(args).await.unwrap();
-        assert_eq!(result.data.len(), cached_response.data.len());
-        assert_eq!(result.metadata.query, cached_response.metadata.query);
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let response = handler
+            .execute_search(SearchArgs {
+                query: "pages".to_string(),
+                options: Some(SearchOptions {
+                    enrich_top: 2,
+                    ..Default::default()
+                }),
+            })
+            .await
+            .unwrap();
+
+        let a = &response.data[0];
+        assert_eq!(
+            a.description,
+            "Page a opens with a paragraph long enough to count as its lead."
+        );
+        assert_eq!(a.metadata.published_date.as_deref(), Some("2024-03-12T09:00:00Z"));
+        assert_eq!(response.data[1].description, detailed);
+        assert_eq!(response.data[1].metadata.published_date.as_deref(), Some("2023-01-02"));
+        assert_eq!(response.data[2].description, "not fetched");
+        assert!(response.data[2].metadata.published_date.is_none());
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_initialize() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+    async fn test_search_and_read_builds_digest() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let results: Vec<Value> = ["a", "missing", "c", "d"]
+            .iter()
+            .map(|p| {
+                json!({
+                    "url": format!("{}/{}", site.uri(), p),
+                    "title": format!("Page {p}"),
+                    "content": format!("Snippet {p} is long enough that nothing enriches it \
+                                        by fetching the page first."),
+                })
+            })
+            .collect();
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+            .mount(&site)
+            .await;
+        for page in ["a", "c"] {
+            Mock::given(path(format!("/{page}")))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    format!(
+                        "<html><head><title>Page {page}</title></head><body><article>\
+                         <p>Body of page {page}.</p></article></body></html>"
+                    ),
+                    "text/html",
+                ))
+                .expect(1)
+                .mount(&site)
+                .await;
+        }
+        Mock::given(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&site)
+            .await;
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
         let response = handler
-            .handle_method("initialize", Some(json!(1)), None)
+            .call_tool(
+                Some(json!(1)),
+                "search_and_read",
+                json!({"query": "pages", "read_count": 3}),
+            )
             .await;
-        assert!(response.result.is_some());
-        assert!(response.error.is_none());
-        let result = response.result.unwrap();
-        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
-        assert_eq!(result["serverInfo"]["name"], SERVER_NAME);
+        let text = response.result.unwrap()["content"][0]["text"].as_str().unwrap().to_string();
+
+        assert!(text.starts_with("# Research digest: pages"), "{text}");
+        assert!(text.contains("Read 2 of the top 3 results"), "{text}");
+        assert!(text.contains(&format!("## [1] Page a\n\n**URL:** {}/a", site.uri())), "{text}");
+        assert!(text.contains("Body of page a."), "{text}");
+        assert!(text.contains("## [2] Page missing"), "{text}");
+        assert!(text.contains("> Snippet missing"), "{text}");
+        assert!(text.contains("Body of page c."), "{text}");
+        assert!(text.contains(&format!("- [4] [Page d]({}/d)", site.uri())), "{text}");
+
+        let plan = handler
+            .call_tool(
+                Some(json!(2)),
+                "search_and_read",
+                json!({"query": "pages", "dry_run": true}),
+            )
+            .await;
+        let plan = plan.result.unwrap()["content"][0]["text"].as_str().unwrap().to_string();
+        assert!(plan.contains("top 3 results is then read"), "{plan}");
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_ping() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler.handle_method("ping", Some(json!(1)), None).await;
-        assert!(response.result.is_some());
-        assert!(response.error.is_none());
+    async fn test_deep_research_searches_parts_and_skips_duplicates() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let result = |page: &str| {
+            json!({
+                "url": format!("{}/{}", site.uri(), page),
+                "title": format!("Page {page}"),
+                "content": format!("Snippet {page} runs past a hundred characters, so no \
+                                    search fetches the page to enrich it before it is read."),
+            })
+        };
+        for (query, pages) in [
+            ("rc vs arc", vec!["shared", "mirror"]),
+            ("rc", vec!["shared", "rc"]),
+            ("arc", vec!["broken", "arc"]),
+        ] {
+            let results: Vec<Value> = pages.into_iter().map(result).collect();
+            Mock::given(path("/search"))
+                .and(query_param("q", query))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+                .expect(1)
+                .mount(&site)
+                .await;
+        }
+        for (page, body) in [
+            ("shared", "Rc counts references on one thread."),
+            ("mirror", "Rc counts references on one thread."),
+            ("rc", "Rc is not Send."),
+            ("arc", "Arc uses atomic counts."),
+        ] {
+            Mock::given(path(format!("/{page}")))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    format!(
+                        "<html><head><title>Page {page}</title></head><body><article>\
+                         <p>{body}</p></article></body></html>"
+                    ),
+                    "text/html",
+                ))
+                .expect(1)
+                .mount(&site)
+                .await;
+        }
+        Mock::given(path("/broken"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&site)
+            .await;
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let report = handler
+            .execute_deep_research(DeepResearchArgs {
+                query: "rc vs arc".to_string(),
+                depth: 1,
+                max_pages: 3,
+                max_tokens_hint: None,
+                options: None,
+            })
+            .await
+            .unwrap();
+
+        let queries: Vec<&str> = report.steps.iter().map(|s| s.query.as_str()).collect();
+        assert_eq!(queries, ["rc vs arc", "rc", "arc"]);
+        let urls: Vec<String> = report.sources.iter().map(|s| s.url.clone()).collect();
+        let url = |page: &str| format!("{}/{}", site.uri(), page);
+        // Found by two searches, so read first
+        assert_eq!(urls, [url("shared"), url("rc"), url("arc")]);
+        let reasons: Vec<(String, &str)> =
+            report.skipped.iter().map(|s| (s.url.clone(), s.reason.as_str())).collect();
+        assert_eq!(reasons[0].0, url("broken"));
+        assert_eq!(reasons[1], (url("mirror"), "duplicate of [1]"));
+
+        let plan = handler.plan_deep_research(&DeepResearchArgs {
+            query: "rc vs arc".to_string(),
+            depth: 9,
+            max_pages: 0,
+            max_tokens_hint: None,
+            options: None,
+        });
+        assert_eq!((plan.depth, plan.max_pages), (MAX_RESEARCH_DEPTH, 1));
+        assert_eq!(plan.searches.len(), 3);
+        assert!(plan.notes[0].contains("up to 2 more rounds"), "{:?}", plan.notes);
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_tools_list() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler.handle_method("tools/list", Some(json!(1)), None).await;
-        assert!(response.result.is_some());
-        let result = response.result.unwrap();
-        let tools = result["tools"].as_array().unwrap();
-        assert_eq!(tools.len(), 4);
+    async fn test_min_safe_search_raises_laxer_requests() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let result = json!({
+            "url": "https://example.com/",
+            "title": "Example",
+            "content": "A snippet long enough that the result is not enriched by fetching its page.",
+        });
+        Mock::given(path("/search"))
+            .and(query_param("safesearch", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [result]})))
+            .expect(1)
+            .mount(&site)
+            .await;
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            min_safe_search: SafeSearchLevel::Strict,
+            ..Default::default()
+        })
+        .unwrap();
+        let args = SearchArgs {
+            query: "anything".to_string(),
+            options: Some(SearchOptions {
+                safe_search: SafeSearchLevel::Off,
+                ..Default::default()
+            }),
+        };
+        let plan = handler.plan_search(&args);
+        let request = plan.backends[0].request.as_ref().unwrap();
+        assert_eq!(request.params["safesearch"], "2");
+
+        let response = handler.execute_search(args).await.unwrap();
+        assert_eq!(response.data.len(), 1);
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_unknown() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler.handle_method("foo", Some(json!(1)), None).await;
-        let err = response.error.unwrap();
-        assert_eq!(err.code, -32601);
-        assert!(err.message.contains("foo"));
+    async fn test_query_rewriters_run_before_dispatch() {
+        use crate::rewrite::{QuestionToKeywords, StopwordPruner};
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let result = json!({
+            "url": "https://doc.rust-lang.org/std/fs/fn.read_to_string.html",
+            "title": "read_to_string",
+            "content": "Reads the entire contents of a file into a string, a convenience function.",
+        });
+        Mock::given(path("/search"))
+            .and(query_param("q", "read file Rust"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [result]})))
+            .expect(1)
+            .mount(&site)
+            .await;
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            query_rewriters: vec![Arc::new(QuestionToKeywords), Arc::new(StopwordPruner)],
+            ..Default::default()
+        })
+        .unwrap();
+        let args = SearchArgs {
+            query: "How do I read a file in Rust?".to_string(),
+            options: None,
+        };
+        let plan = handler.plan_search(&args);
+        assert_eq!(plan.rewritten_query.as_deref(), Some("read file Rust"));
+
+        let response = handler.execute_search(args).await.unwrap();
+        assert_eq!(response.metadata.query, "How do I read a file in Rust?");
+        assert_eq!(response.metadata.rewritten_query.as_deref(), Some("read file Rust"));
     }
 
-    #[tokio::test]
-    async fn test_handle_method_initialized() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler.handle_method("initialized", Some(json!(1)), None).await;
-        assert!(response.error.is_none());
-        assert_eq!(response.result.unwrap(), json!({}));
+    #[test]
+    fn test_enforce_safe_search_keeps_stricter_requests() {
+        let handler = DaedraHandler::new(ServerConfig {
+            min_safe_search: SafeSearchLevel::Moderate,
+            ..Default::default()
+        })
+        .unwrap();
+        let level = |safe_search| {
+            let args = SearchArgs {
+                query: "q".to_string(),
+                options: Some(SearchOptions {
+                    safe_search,
+                    ..Default::default()
+                }),
+            };
+            handler.enforce_safe_search(args).options.unwrap().safe_search
+        };
+        assert_eq!(level(SafeSearchLevel::Off), SafeSearchLevel::Moderate);
+        assert_eq!(level(SafeSearchLevel::Strict), SafeSearchLevel::Strict);
+        assert!(SafeSearchLevel::Off < SafeSearchLevel::Moderate);
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_notifications_initialized() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler
-            .handle_method("notifications/initialized", Some(json!(1)), None)
+    async fn test_blocklisted_domains_are_dropped() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let snippet = "A snippet long enough that the result is not enriched by fetching its page.";
+        let results: Vec<Value> = [
+            "https://docs.rs/tokio",
+            "https://newbedev.com/tokio-question",
+            "https://www.farm.example/tokio",
+        ]
+        .iter()
+        .map(|url| json!({"url": url, "title": "Tokio", "content": snippet.repeat(2)}))
+        .collect();
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+            .mount(&site)
             .await;
-        assert!(response.error.is_none());
-        assert_eq!(response.result.unwrap(), json!({}));
-    }
 
-    #[tokio::test]
-    async fn test_handle_method_tools_call_missing_params() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            blocklist: BlocklistConfig {
+                curated: true,
+                domains: vec!["farm.example".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
         let response = handler
-            .handle_method("tools/call", Some(json!(1)), None)
-            .await;
-        assert!(response.result.is_none());
-        let err = response.error.unwrap();
-        assert_eq!(err.code, -32602);
-        assert!(err.message.contains("Missing parameters"));
+            .execute_search(SearchArgs {
+                query: "tokio".to_string(),
+                options: None,
+            })
+            .await
+            .unwrap();
+
+        let urls: Vec<&str> = response.data.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://docs.rs/tokio"]);
+        assert_eq!(response.metadata.result_count, 1);
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    async fn test_handle_method_tools_call_unknown_tool() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler
-            .handle_method(
-                "tools/call",
-                Some(json!(1)),
-                Some(json!({"name": "unknown", "arguments": {}})),
-            )
+    async fn test_domain_policy_filters_results_and_refuses_pages() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let snippet = "A snippet long enough that the result is not enriched by fetching its page.";
+        let results: Vec<Value> = [
+            "https://docs.rs/tokio",
+            "https://api.internal.example/tokio",
+            "https://secret.internal.example/tokio",
+            "https://blog.example/tokio",
+        ]
+        .iter()
+        .map(|url| json!({"url": url, "title": "Tokio", "content": snippet.repeat(2)}))
+        .collect();
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+            .mount(&site)
             .await;
-        assert!(response.result.is_none());
-        let err = response.error.unwrap();
-        assert_eq!(err.code, -32601);
-        assert!(err.message.contains("unknown"));
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            allowed_domains: vec!["docs.rs".to_string(), "*.internal.example".to_string()],
+            blocked_domains: vec!["secret.*".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let response = handler
+            .execute_search(SearchArgs {
+                query: "tokio".to_string(),
+                options: None,
+            })
+            .await
+            .unwrap();
+        let urls: Vec<&str> = response.data.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://docs.rs/tokio", "https://api.internal.example/tokio"]);
+        assert_eq!(response.metadata.result_count, 2);
+
+        let err = handler
+            .execute_fetch(VisitPageArgs {
+                url: "https://blog.example/tokio".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DaedraError::DomainNotAllowed(_)), "{err}");
+
+        let bad_glob = ServerConfig {
+            allowed_domains: vec!["not a host".to_string()],
+            ..Default::default()
+        };
+        assert!(DaedraHandler::new(bad_glob).is_err());
     }
 
+    #[cfg(feature = "searxng")]
     #[tokio::test]
-    #[ignore = "network"]
-    async fn test_handle_method_tools_call_web_search_no_args() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler
-            .handle_method(
-                "tools/call",
-                Some(json!(1)),
-                Some(json!({"name": "web_search", "arguments": {}})),
-            )
+    async fn test_content_type_preferences_reorder_and_exclude() {
+        use crate::types::ContentType;
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let site = MockServer::start().await;
+        let snippet = "A snippet long enough that the result is not enriched by fetching its page.";
+        let results: Vec<Value> = [
+            "https://www.amazon.com/tokio-book",
+            "https://blog.example/tokio-tutorial",
+            "https://docs.rs/tokio",
+            "https://x.com/tokio_rs",
+        ]
+        .iter()
+        .map(|url| json!({"url": url, "title": "Tokio", "content": snippet.repeat(2)}))
+        .collect();
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": results})))
+            .mount(&site)
             .await;
-        assert!(response.error.is_none());
-        let result = response.result.unwrap();
-        assert!(result.get("isError").is_some());
+
+        let handler = DaedraHandler::new(ServerConfig {
+            searxng_url: Some(site.uri()),
+            search_chain: vec!["searxng".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let search = |prefer_types: Vec<ContentType>, exclude_types: Vec<ContentType>| {
+            handler.execute_search(SearchArgs {
+                query: "tokio tutorial".to_string(),
+                options: Some(SearchOptions {
+                    prefer_types,
+                    exclude_types,
+                    ..Default::default()
+                }),
+            })
+        };
+
+        let response = search(
+            vec![ContentType::Documentation],
+            vec![ContentType::Shopping, ContentType::Social],
+        )
+        .await
+        .unwrap();
+        let urls: Vec<&str> = response.data.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://docs.rs/tokio", "https://blog.example/tokio-tutorial"]);
+        assert_eq!(response.metadata.result_count, 2);
+
+        let unfiltered = search(Vec::new(), Vec::new()).await.unwrap();
+        assert_eq!(unfiltered.data.len(), 4);
     }
 
     #[tokio::test]
-    #[ignore = "network"]
-    async fn test_execute_search_returns_results() {
+    async fn test_visit_page_pages_through_token_budget() {
+        let paragraphs: Vec<String> = (0..40)
+            .map(|i| format!("<p>Paragraph {i} says something worth reading about the topic.</p>"))
+            .collect();
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "<html><head><title>Long</title></head><body><article>{}</article></body>",
+                    paragraphs.concat()
+                ) + "</html>",
+                "text/html",
+            ))
+            .expect(1)
+            .mount(&site)
+            .await;
+
         let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let args = SearchArgs {
-            query: "Rust programming language".to_string(),
-            options: Some(crate::types::SearchOptions {
-                num_results: 5,
-                ..Default::default()
-            }),
+        let call = |cursor: Option<String>| {
+            let mut arguments = json!({"url": site.uri(), "max_tokens_hint": 150});
+            if let Some(cursor) = cursor {
+                arguments["cursor"] = json!(cursor);
+            }
+            call_tool(&handler, "visit_page", arguments)
         };
-        let response = handler.execute_search(args).await.unwrap();
-        assert!(!response.data.is_empty(), "search should return results");
+
+        let first = PageContent::from_tool_content(&call(None).await.result.unwrap()).unwrap();
+        assert!(first.truncated);
+        assert!(first.content.contains("Paragraph 0 "));
+        assert!(!first.content.contains("Paragraph 39 "));
+
+        // Continuing is served from the cache
+        let second = call(first.next_cursor.clone()).await.result.unwrap();
+        let second = PageContent::from_tool_content(&second).unwrap();
+        assert!(!second.content.contains("Paragraph 0 "));
+        assert_ne!(second.next_cursor, first.next_cursor);
+
+        let bad = call(Some("bogus".to_string())).await.result.unwrap();
+        assert_eq!(bad["isError"], true);
     }
 
     #[tokio::test]
-    #[ignore = "network"]
-    async fn test_execute_search_caches_on_second_call() {
+    async fn test_visit_page_formats() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Doc</title></head><body><main><h1>Doc</h1>\
+                 <p>Some <em>emphasis</em> and <a href='/next'>a link</a>.</p>\
+                 </main></body></html>",
+                "text/html",
+            ))
+            .expect(3)
+            .mount(&site)
+            .await;
+
         let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let args = SearchArgs {
-            query: "cache-second-call-unique-query-abc".to_string(),
-            options: None,
+        let call = |format: &str| {
+            call_tool(&handler, "visit_page", json!({"url": site.uri(), "format": format}))
         };
-        let first = handler.execute_search(args.clone()).await;
-        let second = handler.execute_search(args).await;
-        assert!(first.is_ok(), "first search should succeed: {:?}", first.err());
-        assert!(second.is_ok(), "second search should succeed: {:?}", second.err());
-        assert!(!first.unwrap().data.is_empty());
-        assert!(!second.unwrap().data.is_empty());
+
+        let text = call("text").await.result.unwrap();
+        let text = PageContent::from_tool_content(&text).unwrap();
+        assert_eq!(text.content, "Doc\n\nSome emphasis and a link.");
+
+        let html = call("html").await.result.unwrap();
+        let html = PageContent::from_tool_content(&html).unwrap();
+        assert!(html.content.contains("<em>emphasis</em>"), "{}", html.content);
+
+        // Markdown and JSON share a cache entry: three fetches in all
+        call("markdown").await.result.unwrap();
+        let json = call("json").await.result.unwrap();
+        let page: PageContent =
+            serde_json::from_str(json["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(page.title, "Doc");
+        assert!(page.content.contains("emphasis"));
+
+        let bad = call("pdf").await;
+        assert_eq!(bad.error.unwrap().code, -32602);
     }
 
     #[tokio::test]
-    async fn test_handle_visit_page_malformed_args() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler
-            .handle_visit_page(Some(json!(1)), json!({"url": 12345}))
+    async fn test_visit_page_extracts_links() {
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Map</title></head><body><p>Short.</p>\
+                 <a href='/next'>Next</a><a href='#top'>Top</a>\
+                 <a href='https://other.example/'>Elsewhere</a></body></html>",
+                "text/html",
+            ))
+            .expect(2)
+            .mount(&site)
             .await;
-        assert!(response.result.is_none());
-        let err = response.error.unwrap();
-        assert_eq!(err.code, -32602);
-        assert!(err.message.contains("Invalid fetch arguments"));
+
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |extract: &str, format: &str| {
+            let arguments = json!({"url": site.uri(), "extract": extract, "format": format});
+            call_tool(&handler, "visit_page", arguments)
+        };
+
+        let listed = call("links", "markdown").await.result.unwrap();
+        let listed = PageContent::from_tool_content(&listed).unwrap();
+        assert!(
+            listed.content.starts_with(&format!(
+                "## Internal links (1)\n\n- [Next]({}/next)",
+                site.uri()
+            )),
+            "{}",
+            listed.content
+        );
+        assert!(listed.content.contains("## Anchors (1)"));
+
+        // JSON shares the cached links page, and content mode is fetched apart
+        let json = call("links", "json").await.result.unwrap();
+        let page: PageContent =
+            serde_json::from_str(json["content"][0]["text"].as_str().unwrap()).unwrap();
+        let scopes: Vec<_> = page.links.unwrap().iter().map(|link| link.scope).collect();
+        assert_eq!(
+            scopes,
+            [Some(LinkScope::Internal), Some(LinkScope::Anchor), Some(LinkScope::External)]
+        );
+        let content = call("content", "markdown").await.result.unwrap();
+        let content = PageContent::from_tool_content(&content).unwrap();
+        assert!(content.content.contains("Short."));
+
+        let bad = call("images", "markdown").await;
+        assert_eq!(bad.error.unwrap().code, -32602);
     }
 
     #[tokio::test]
-    async fn test_handle_visit_page_invalid_url() {
-        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
-        let response = handler
-            .handle_method(
-                "tools/call",
-                Some(json!(1)),
-                Some(json!({
-                    "name": "visit_page",
-                    "arguments": {"url": "ftp://example.com"}
-                })),
-            )
+    async fn test_visit_page_reads_slices_by_start_index() {
+        let paragraphs: Vec<String> =
+            (0..20).map(|i| format!("<p>Paragraph {i} of the long page.</p>")).collect();
+        let site = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "<html><head><title>Long</title></head><body><article>{}</article></body>",
+                    paragraphs.concat()
+                ) + "</html>",
+                "text/html",
+            ))
+            .expect(1)
+            .mount(&site)
             .await;
-        assert!(response.error.is_none());
-        let result = response.result.unwrap();
-        assert_eq!(result["isError"], true);
-        let text = result["content"][0]["text"].as_str().unwrap();
-        assert!(text.contains("Invalid URL"));
+
+        let handler = DaedraHandler::new(ServerConfig::default()).unwrap();
+        let call = |arguments| call_tool(&handler, "visit_page", arguments);
+
+        let mut read = String::new();
+        let mut start = 0;
+        loop {
+            let arguments = json!({"url": site.uri(), "max_length": 100, "start_index": start});
+            let slice = call(arguments).await.result.unwrap();
+            let slice = PageContent::from_tool_content(&slice).unwrap();
+            read += &slice.content;
+            match slice.next_index {
+                Some(next) => {
+                    assert!(slice.truncated);
+                    assert_eq!(slice.content.chars().count(), 100);
+                    start = next;
+                },
+                None => break,
+            }
+        }
+        assert!(start > 0);
+        assert!(read.contains("Paragraph 0 ") && read.contains("Paragraph 19 "));
+
+        let whole = call(json!({"url": site.uri()})).await.result.unwrap();
+        assert_eq!(PageContent::from_tool_content(&whole).unwrap().content, read);
+
+        let both = json!({"url": site.uri(), "start_index": 5, "cursor": "5"});
+        assert_eq!(call(both).await.result.unwrap()["isError"], true);
+        let past_end = json!({"url": site.uri(), "start_index": read.chars().count() + 1});
+        assert_eq!(call(past_end).await.result.unwrap()["isError"], true);
     }
 
     #[tokio::test]
@@ -1242,6 +5821,626 @@ mod tests {
         assert_eq!(args["url"], "https://example.com");
     }
 
+    #[tokio::test]
+    async fn test_read_bounded_line() {
+        let input =
+            b"{\"id\":1}\r\n0123456789abcdef\nshort\nno-newline\n{\"id\":9,\"pad\":\"xxxx\"}";
+        let mut reader = BufReader::with_capacity(4, &input[..]);
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            StdioLine::Line("{\"id\":1}".to_string())
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            StdioLine::TooLong { len: 16, id: None }
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            StdioLine::Line("short".to_string())
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            StdioLine::Line("no-newline".to_string())
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            StdioLine::TooLong {
+                len: 21,
+                id: Some(json!(9))
+            }
+        );
+        assert_eq!(read_bounded_line(&mut reader, 10).await.unwrap(), StdioLine::Eof);
+    }
+
+    #[test]
+    fn test_leading_request_id() {
+        let id = |prefix: &str| leading_request_id(prefix.as_bytes());
+        let cut = r#"{"jsonrpc":"2.0","id":42,"method":"tools/call","params":{"ar"#;
+        assert_eq!(id(cut), Some(json!(42)));
+        assert_eq!(id(r#"{"method":"id","params":{"id":1},"id":"req-7","#), Some(json!("req-7")));
+        assert_eq!(id(r#"{"jsonrpc":"2.0","method":"x","params":{"id":1,"pad":"aa"#), None);
+        assert_eq!(id(r#"{"params":{"q":"\"id\":3"},"id":null,"x"#), None);
+        // Cut inside the id itself
+        assert_eq!(id(r#"{"jsonrpc":"2.0","id":12"#), None);
+        assert_eq!(id("not json at all"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_stdio_message_bounds_header_blocks() {
+        let input = "Content-Length: 2\r\n".to_string() + &"X-Pad: 1\r\n".repeat(20) + "\r\n{}";
+        let mut reader = BufReader::new(input.as_bytes());
+        let (message, _) = read_stdio_message(&mut reader, 64).await.unwrap();
+        assert!(matches!(message, StdioLine::BadHeader(reason) if reason.contains("headers")));
+    }
+
+    #[tokio::test]
+    async fn test_read_stdio_message_mixes_framings() {
+        let input = b"Content-Length: 8\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n\
+            {\"id\":1}{\"id\":2}\ncontent-length:40\r\n\r\n\
+            {\"id\":\"big\",\"pad\":\"0123456789012345678\"}\
+            Content-Length: many\r\n\r\n{\"id\":3}\nContent-Length: 9\r\n\r\n{}";
+        let mut reader = BufReader::with_capacity(8, &input[..]);
+        let mut messages = Vec::new();
+        loop {
+            let message = read_stdio_message(&mut reader, 32).await.unwrap();
+            if message.0 == StdioLine::Eof {
+                break;
+            }
+            messages.push(message);
+        }
+        assert_eq!(
+            messages,
+            [
+                (StdioLine::Line("{\"id\":1}".to_string()), StdioFraming::ContentLength),
+                (StdioLine::Line("{\"id\":2}".to_string()), StdioFraming::Newline),
+                (
+                    StdioLine::TooLong {
+                        len: 40,
+                        id: Some(json!("big"))
+                    },
+                    StdioFraming::ContentLength
+                ),
+                (
+                    StdioLine::BadHeader("Invalid Content-Length: \"many\"".to_string()),
+                    StdioFraming::ContentLength
+                ),
+                (StdioLine::Line("{\"id\":3}".to_string()), StdioFraming::Newline),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_stdio_response_in_either_framing() {
+        let response = || JsonRpcResponse::success(Some(json!(1)), json!({}));
+        let mut out = Vec::new();
+        write_stdio_response(response(), StdioFraming::Newline, &mut out).await.unwrap();
+        write_stdio_response(response(), StdioFraming::ContentLength, &mut out).await.unwrap();
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+        let expected = format!("{body}\nContent-Length: {}\r\n\r\n{body}", body.len());
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_sse_router_enforces_host_and_body_limit() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let config = ServerConfig {
+            max_request_bytes: 256,
+            ..Default::default()
+        };
+        let router = sse_router(
+            Arc::new(DaedraHandler::new(config.clone()).unwrap()),
+            &config,
+            HostPolicy::new("127.0.0.1", &[IpAddr::from([127, 0, 0, 1])], &[]),
+            Arc::new(SseSessions::new(config.sse_keep_alive)),
+        );
+        let rpc = |body: String| {
+            Request::post("/rpc")
+                .header("host", "127.0.0.1:3000")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}).to_string();
+        let mut rebound = rpc(ping.clone());
+        rebound
+            .headers_mut()
+            .insert("host", "evil.example:3000".parse().unwrap());
+        let response = router.clone().oneshot(rebound).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = router.clone().oneshot(rpc(ping)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let quirky = "\u{feff}{\"jsonrpc\": \"2.0\", \"id\": 3, \"method\": \"ping\",}\n\0";
+        let response = router.clone().oneshot(rpc(quirky.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.clone().oneshot(rpc("{oops".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        let error: JsonRpcResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.error.unwrap().code, -32700);
+
+        let huge = json!({"jsonrpc": "2.0", "id": 2, "method": "ping", "params": {"pad": "x".repeat(1024)}});
+        let response = router.oneshot(rpc(huge.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_normalize_host() {
+        assert_eq!(normalize_host("LocalHost:3000"), "localhost");
+        assert_eq!(normalize_host("127.0.0.1"), "127.0.0.1");
+        assert_eq!(normalize_host("[::1]:3000"), "::1");
+        assert_eq!(normalize_host("::1"), "::1");
+        assert_eq!(normalize_host("mcp.example.com"), "mcp.example.com");
+    }
+
+    #[test]
+    fn test_host_policy_loopback_bind() {
+        use axum::http::{HeaderMap, HeaderValue, header};
+
+        let policy = HostPolicy::new("127.0.0.1", &[IpAddr::from([127, 0, 0, 1])], &[]);
+        let headers = |host: &str, origin: Option<&str>| {
+            let mut map = HeaderMap::new();
+            map.insert(header::HOST, HeaderValue::from_str(host).unwrap());
+            if let Some(origin) = origin {
+                map.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+            }
+            map
+        };
+
+        assert!(policy.check(&headers("127.0.0.1:3000", None)).is_ok());
+        assert!(policy.check(&headers("localhost:3000", Some("http://localhost:5173"))).is_ok());
+        assert!(policy.check(&headers("[::1]:3000", None)).is_ok());
+        assert!(policy.check(&headers("evil.example:3000", None)).is_err());
+        assert!(policy.check(&headers("localhost:3000", Some("http://evil.example"))).is_err());
+        assert!(policy.check(&headers("localhost:3000", Some("null"))).is_err());
+        assert!(policy.check(&HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_host_policy_overrides() {
+        use axum::http::{HeaderMap, HeaderValue, header};
+
+        let host = |value: &str| {
+            let mut map = HeaderMap::new();
+            map.insert(header::HOST, HeaderValue::from_str(value).unwrap());
+            map
+        };
+
+        let loopback = [IpAddr::from([127, 0, 0, 1])];
+        let any = [IpAddr::from([0, 0, 0, 0])];
+
+        let proxied = HostPolicy::new("127.0.0.1", &loopback, &["MCP.example.com".to_string()]);
+        assert!(proxied.check(&host("mcp.example.com")).is_ok());
+        assert!(proxied.check(&host("other.example.com")).is_err());
+
+        let wildcard = HostPolicy::new("127.0.0.1", &loopback, &["*".to_string()]);
+        assert!(wildcard.check(&host("anything.example")).is_ok());
+
+        let all_interfaces = HostPolicy::new("0.0.0.0", &any, &[]);
+        assert!(all_interfaces.check(&host("192.168.1.20:3000")).is_ok());
+
+        let pinned = HostPolicy::new("0.0.0.0", &any, &["daedra.lan".to_string()]);
+        assert!(pinned.check(&host("daedra.lan:3000")).is_ok());
+        assert!(pinned.check(&host("192.168.1.20:3000")).is_err());
+
+        let lan_ip: IpAddr = "fd00::20".parse().unwrap();
+        let named = HostPolicy::new("daedra-box", &[lan_ip], &[]);
+        assert!(named.check(&host("daedra-box:3000")).is_ok());
+        assert!(named.check(&host("[fd00::20]:3000")).is_ok());
+        assert!(named.check(&host("other-box:3000")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_bind_addrs() {
+        assert_eq!(
+            resolve_bind_addrs("127.0.0.1", 3000).await.unwrap(),
+            vec![SocketAddr::from(([127, 0, 0, 1], 3000))]
+        );
+        assert_eq!(
+            resolve_bind_addrs("[::1]", 3000).await.unwrap(),
+            vec!["[::1]:3000".parse::<SocketAddr>().unwrap()]
+        );
+        assert_eq!(
+            resolve_bind_addrs("::", 8080).await.unwrap(),
+            vec!["[::]:8080".parse::<SocketAddr>().unwrap()]
+        );
+
+        let localhost = resolve_bind_addrs("localhost", 3000).await.unwrap();
+        assert!(!localhost.is_empty());
+        assert!(localhost.iter().all(|a| a.ip().is_loopback() && a.port() == 3000));
+
+        assert!(resolve_bind_addrs("", 3000).await.is_err());
+        assert!(resolve_bind_addrs("no-such-host.invalid", 3000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind_ephemeral_port_then_serve() {
+        let bound = DaedraServer::with_defaults()
+            .unwrap()
+            .bind("127.0.0.1", 0)
+            .await
+            .unwrap();
+        let addr = bound.local_addr();
+        assert!(addr.ip().is_loopback());
+        assert_ne!(addr.port(), 0);
+        assert_eq!(bound.local_addrs(), &[addr]);
+
+        let serving = tokio::spawn(bound.serve());
+        let health: Value = reqwest::get(format!("http://{addr}/health"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["status"], "ok");
+        serving.abort();
+    }
+
+    #[tokio::test]
+    async fn test_spawn_then_shutdown() {
+        let handle = DaedraServer::with_defaults()
+            .unwrap()
+            .spawn("127.0.0.1", 0)
+            .await
+            .unwrap();
+        let addr = handle.local_addr();
+        assert_ne!(addr.port(), 0);
+
+        let health = reqwest::get(format!("http://{addr}/health")).await.unwrap();
+        assert!(health.status().is_success());
+
+        handle.shutdown();
+        handle.shutdown();
+        tokio::time::timeout(Duration::from_secs(5), handle.join())
+            .await
+            .expect("server did not stop")
+            .unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_restarts_listener_once_unhealthy() {
+        let config = ServerConfig {
+            watchdog: WatchdogConfig {
+                enabled: true,
+                interval: Duration::from_secs(60),
+                restart_after: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bound = DaedraServer::new(config).unwrap().bind("127.0.0.1", 0).await.unwrap();
+        let handler = Arc::clone(&bound.handler);
+        let handle = bound.spawn();
+        let addr = handle.local_addr();
+        let health = async || -> Value {
+            reqwest::get(format!("http://{addr}/health")).await.unwrap().json().await.unwrap()
+        };
+        assert_eq!(health().await["watchdog"]["state"], "healthy");
+
+        let mut stream = reqwest::get(format!("http://{addr}/sse")).await.unwrap();
+        read_sse_until(&mut stream, "endpoint").await;
+        handler.health.send_replace(Health {
+            state: watchdog::HealthState::Stalled,
+            reason: Some("test".to_string()),
+        });
+        let ended = tokio::time::timeout(Duration::from_secs(5), async {
+            while stream.chunk().await.ok().flatten().is_some() {}
+        });
+        ended.await.expect("SSE stream not closed by the restart");
+
+        // Served again on the same address, without restarting a second time
+        let report = health().await;
+        assert_eq!(report["status"], "degraded");
+        assert_eq!(report["watchdog"]["reason"], "test");
+        let mut stream = reqwest::get(format!("http://{addr}/sse")).await.unwrap();
+        read_sse_until(&mut stream, "endpoint").await;
+        let quiet = tokio::time::timeout(Duration::from_millis(300), stream.chunk()).await;
+        assert!(quiet.is_err(), "stream closed again: {quiet:?}");
+
+        handle.shutdown();
+        tokio::time::timeout(Duration::from_secs(5), handle.join())
+            .await
+            .expect("server did not stop")
+            .unwrap();
+    }
+
+    /// Read `stream` until its text contains `needle`, returning everything read
+    async fn read_sse_until(stream: &mut reqwest::Response, needle: &str) -> String {
+        let mut text = String::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !text.contains(needle) {
+                let chunk = stream.chunk().await.unwrap().expect("SSE stream ended");
+                text.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("no {needle:?} in SSE stream: {text}"));
+        text
+    }
+
+    #[tokio::test]
+    async fn test_sse_session_streams_responses_and_resumes() {
+        let config = ServerConfig {
+            sse_keep_alive: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let handle = DaedraServer::new(config).unwrap().spawn("127.0.0.1", 0).await.unwrap();
+        let base = format!("http://{}", handle.local_addr());
+        let client = reqwest::Client::new();
+
+        let mut stream = client.get(format!("{base}/sse")).send().await.unwrap();
+        let opened = read_sse_until(&mut stream, ": keep-alive").await;
+        assert!(opened.contains("event: endpoint"), "{opened}");
+        let endpoint = opened
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap()
+            .to_string();
+        let session = endpoint.strip_prefix("/rpc?session_id=").unwrap().to_string();
+        assert!(opened.contains(&format!("id: {session}-1")), "{opened}");
+
+        let post = |body: Value| client.post(format!("{base}{endpoint}")).json(&body).send();
+        let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        assert_eq!(post(list).await.unwrap().status(), 202);
+        let message = read_sse_until(&mut stream, "multi_search").await;
+        assert!(message.contains("event: message"), "{message}");
+        drop(stream);
+
+        // Sent while disconnected, then replayed on reconnect
+        let init = json!({"jsonrpc": "2.0", "id": 2, "method": "initialize"});
+        assert_eq!(post(init).await.unwrap().status(), 202);
+        let mut resumed = client
+            .get(format!("{base}/sse"))
+            .header("Last-Event-ID", format!("{session}-2"))
+            .send()
+            .await
+            .unwrap();
+        let replayed = read_sse_until(&mut resumed, "protocolVersion").await;
+        assert!(replayed.contains(&format!("id: {session}-3")), "{replayed}");
+        assert!(!replayed.contains("event: endpoint"), "{replayed}");
+
+        let unknown = client
+            .post(format!("{base}/rpc?session_id=nope"))
+            .json(&json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), 404);
+
+        // Shutdown ends open streams instead of waiting on them
+        handle.shutdown();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while resumed.chunk().await.is_ok_and(|chunk| chunk.is_some()) {}
+        })
+        .await
+        .expect("SSE stream still open after shutdown");
+        tokio::time::timeout(Duration::from_secs(5), handle.join())
+            .await
+            .expect("server did not stop")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sse_sessions_receive_broadcast_and_scoped_notifications() {
+        let server = DaedraServer::with_defaults().unwrap();
+        let events = server.events();
+        let handle = server.spawn("127.0.0.1", 0).await.unwrap();
+        let base = format!("http://{}", handle.local_addr());
+        let client = reqwest::Client::new();
+
+        let mut first = client.get(format!("{base}/sse")).send().await.unwrap();
+        let mut second = client.get(format!("{base}/sse")).send().await.unwrap();
+        let opened = read_sse_until(&mut first, "event: endpoint").await;
+        read_sse_until(&mut second, "event: endpoint").await;
+        let endpoint = opened.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+
+        events.broadcast(ServerNotification::ToolsListChanged);
+        read_sse_until(&mut first, "notifications/tools/list_changed").await;
+        read_sse_until(&mut second, "notifications/tools/list_changed").await;
+
+        let call = json!({
+            "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+            "params": {"name": "no_such_tool", "arguments": {}}
+        });
+        let posted = client.post(format!("{base}{endpoint}")).json(&call).send().await.unwrap();
+        assert_eq!(posted.status(), 202);
+        let scoped = read_sse_until(&mut first, "notifications/daedra/job_completed").await;
+        assert!(scoped.contains("notifications/message"), "{scoped}");
+
+        events.broadcast(ServerNotification::ToolsListChanged);
+        let other = read_sse_until(&mut second, "list_changed").await;
+        assert!(!other.contains("job_completed"), "{other}");
+        handle.shutdown();
+    }
+
+    #[test]
+    fn test_sse_session_backlog_and_event_ids() {
+        let mut session = SseSession::default();
+        for i in 0..SSE_REPLAY_EVENTS + 5 {
+            session.push("message", i.to_string());
+        }
+        assert_eq!(session.backlog.len(), SSE_REPLAY_EVENTS);
+        assert_eq!(session.backlog[0].seq, 6);
+
+        let id = "0b6e0f4e-5a1d-4c1e-9d2a-3f7f0e6c1a2b";
+        assert_eq!(parse_event_id(&format!("{id}-42")), Some((id, 42)));
+        assert_eq!(parse_event_id("no-number-x"), None);
+        assert_eq!(parse_event_id("7"), None);
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit_queues_extra_clients() {
+        use tokio::io::AsyncReadExt;
+
+        let config = ServerConfig {
+            max_connections: 1,
+            ..Default::default()
+        };
+        let handle = DaedraServer::new(config).unwrap().spawn("127.0.0.1", 0).await.unwrap();
+        let addr = handle.local_addr();
+
+        let first = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut second = tokio::net::TcpStream::connect(addr).await.unwrap();
+        second
+            .write_all(b"GET /health HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(300), second.read_to_end(&mut response));
+        assert!(waiting.await.is_err(), "second client served while the first was open");
+
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), second.read_to_end(&mut response))
+            .await
+            .expect("second client never served")
+            .unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200"), "{}", String::from_utf8_lossy(&response));
+    }
+
+    #[tokio::test]
+    async fn test_idle_connections_are_closed() {
+        use tokio::io::AsyncReadExt;
+
+        let config = ServerConfig {
+            idle_timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let handle = DaedraServer::new(config).unwrap().spawn("127.0.0.1", 0).await.unwrap();
+        let mut idle = tokio::net::TcpStream::connect(handle.local_addr()).await.unwrap();
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(5), idle.read(&mut buf))
+            .await
+            .expect("idle connection left open");
+        assert!(matches!(read, Ok(0) | Err(_)), "{read:?}");
+    }
+
+    #[tokio::test]
+    async fn test_writes_to_a_client_that_stopped_reading_time_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = tokio::net::TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        let permit = Arc::new(Semaphore::new(1)).try_acquire_owned().unwrap();
+        let limits = ConnectionLimits {
+            idle_timeout: Duration::from_secs(60),
+            write_timeout: Duration::from_millis(100),
+        };
+        let mut stream = LimitedStream::new(accepted, permit, limits);
+
+        let written = tokio::time::timeout(Duration::from_secs(10), async {
+            stream.write_all(&vec![0u8; 64 << 20]).await
+        })
+        .await
+        .expect("write never timed out");
+        assert_eq!(written.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_handle_stops_server() {
+        let bound = DaedraServer::with_defaults()
+            .unwrap()
+            .bind("127.0.0.1", 0)
+            .await
+            .unwrap();
+        let addr = bound.local_addr();
+        drop(bound.spawn());
+
+        let stopped = async {
+            while tokio::net::TcpStream::connect(addr).await.is_ok() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), stopped)
+            .await
+            .expect("server still accepting connections");
+    }
+
+    #[tokio::test]
+    async fn test_bind_reports_port_in_use() {
+        let first = DaedraServer::with_defaults()
+            .unwrap()
+            .bind("127.0.0.1", 0)
+            .await
+            .unwrap();
+        let port = first.local_addr().port();
+        let err = DaedraServer::with_defaults()
+            .unwrap()
+            .bind("127.0.0.1", port)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Failed to bind"), "{err}");
+    }
+
+    #[test]
+    fn test_is_disconnect() {
+        use std::io::{Error, ErrorKind};
+        assert!(is_disconnect(&Error::from(ErrorKind::BrokenPipe)));
+        assert!(is_disconnect(&Error::from(ErrorKind::ConnectionReset)));
+        assert!(is_disconnect(&Error::from(ErrorKind::UnexpectedEof)));
+        assert!(!is_disconnect(&Error::from(ErrorKind::PermissionDenied)));
+        assert!(!is_disconnect(&Error::from(ErrorKind::InvalidData)));
+    }
+
+    #[tokio::test]
+    async fn test_input_closed_does_not_consume_pending_lines() {
+        let mut reader = BufReader::new(&b"{\"jsonrpc\":\"2.0\"}\n"[..]);
+        assert!(!input_closed(&mut reader).await);
+        let mut lines = reader.lines();
+        assert_eq!(
+            lines.next_line().await.unwrap().as_deref(),
+            Some("{\"jsonrpc\":\"2.0\"}")
+        );
+        assert!(input_closed(lines.get_mut()).await);
+    }
+
+    #[test]
+    fn test_health_report_degraded_when_all_circuits_open() {
+        let status = |name: &str, state| tools::BackendStatus {
+            name: name.to_string(),
+            state,
+            consecutive_failures: 0,
+            retry_in_secs: None,
+            requires_api_key: false,
+        };
+
+        let backends = [
+            status("duckduckgo", tools::CircuitState::Open),
+            status("wikipedia", tools::CircuitState::Closed),
+        ];
+        let report = health_report(&backends, None);
+        assert_eq!(report["status"], "ok");
+        assert_eq!(report["backends"][0]["state"], "open");
+        assert_eq!(report["backends"][1]["name"], "wikipedia");
+        assert!(report.get("watchdog").is_none());
+
+        let report = health_report(&[status("duckduckgo", tools::CircuitState::Open)], None);
+        assert_eq!(report["status"], "degraded");
+
+        let stalled = Health {
+            state: watchdog::HealthState::Stalled,
+            reason: Some("runtime stalled for 1500 ms".to_string()),
+        };
+        let report = health_report(&backends, Some(&stalled));
+        assert_eq!(report["status"], "degraded");
+        assert_eq!(report["watchdog"]["state"], "stalled");
+        assert_eq!(health_report(&backends, Some(&Health::default()))["status"], "ok");
+    }
+
+    #[test]
+    fn test_server_backend_status_lists_backends() {
+        let server = DaedraServer::with_defaults().unwrap();
+        let status = server.backend_status();
+        assert!(status.iter().any(|b| b.name == "duckduckgo"));
+        assert!(status.iter().all(|b| b.state == tools::CircuitState::Closed));
+    }
+
     #[test]
     fn test_tool_error_response_has_is_error() {
         let response = tool_error_response(Some(json!(1)), "something went wrong");
@@ -1267,15 +6466,104 @@ mod tests {
             url: "https://example.com".to_string(),
             title: "Example".to_string(),
             content: "Hello world".to_string(),
-            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
             word_count: 2,
             links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
         };
         let formatted = format_page_result(&content);
         assert!(formatted.contains("Example"));
         assert!(formatted.contains("https://example.com"));
         assert!(formatted.contains("**Words:** 2"));
         assert!(formatted.contains("Hello world"));
+        assert!(!formatted.contains("**Host:**"));
+
+        let idn = PageContent {
+            url: "https://xn--bcher-kva.de/".to_string(),
+            ..content.clone()
+        };
+        assert!(format_page_result(&idn).contains("\n**Host:** bücher.de\n"));
+        let lookalike = PageContent {
+            url: "https://xn--pypal-4ve.com/login".to_string(),
+            ..content
+        };
+        let formatted = format_page_result(&lookalike);
+        let warning = "**Host:** xn--pypal-4ve.com (possible homograph";
+        assert!(formatted.contains(warning), "{}", formatted);
+        let parsed = PageContent::from_tool_content(
+            &tool_success_response(Some(json!(1)), formatted).result.unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parsed.url, lookalike.url);
+    }
+
+    #[test]
+    fn test_page_result_round_trips_through_tool_content() {
+        use crate::types::ContentType;
+        let content = PageContent {
+            url: "https://example.com/a".to_string(),
+            title: "Example: a page".to_string(),
+            content: "Intro\n\n---\n\nAfter a rule".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
+            word_count: 5,
+            links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        };
+        let response = tool_success_response(Some(json!(1)), format_page_result(&content));
+        let parsed = PageContent::from_tool_content(&response.result.unwrap()).unwrap();
+        assert_eq!(parsed.url, content.url);
+        assert_eq!(parsed.title, content.title);
+        assert_eq!(parsed.timestamp, content.timestamp);
+        assert_eq!(parsed.word_count, 5);
+        assert_eq!(parsed.content, content.content);
+        assert!(!parsed.truncated);
+        assert!(parsed.published_date.is_none());
+        assert!(parsed.content_type.is_none());
+        assert!(parsed.meta.is_none());
+
+        let meta = crate::types::PageMeta {
+            author: Some("Ana Lima, Bo Chen".to_string()),
+            site_name: Some("Field Notes".to_string()),
+            canonical_url: Some("https://example.com/post".to_string()),
+            ..Default::default()
+        };
+        let cut = PageContent {
+            published_date: Some("2024-03-12T09:00:00Z".to_string()),
+            content_type: Some(ContentType::Blog),
+            meta: Some(meta.clone()),
+            pages: Some(vec![
+                "https://example.com/post".to_string(),
+                "https://example.com/post?page=2".to_string(),
+            ]),
+            next_cursor: Some("120".to_string()),
+            next_index: Some(118),
+            truncated: true,
+            ..content
+        };
+        let response = tool_success_response(Some(json!(1)), format_page_result(&cut));
+        let parsed = PageContent::from_tool_content(&response.result.unwrap()).unwrap();
+        assert!(parsed.truncated);
+        assert_eq!(parsed.next_cursor.as_deref(), Some("120"));
+        assert_eq!(parsed.next_index, Some(118));
+        assert_eq!(parsed.published_date.as_deref(), Some("2024-03-12T09:00:00Z"));
+        assert_eq!(parsed.content_type, Some(ContentType::Blog));
+        assert_eq!(parsed.meta, Some(meta));
+        assert_eq!(parsed.pages, cut.pages);
+        assert_eq!(parsed.content, cut.content);
     }
 
     #[tokio::test]
@@ -0,0 +1,216 @@
+//! HTML tables as GitHub-flavored Markdown pipe tables.
+//!
+//! Pricing pages, comparison pages and spec sheets keep their substance in
+//! `<table>`s, which generic HTML-to-Markdown conversion runs together into
+//! loose lines. [`to_markdown`] lays a table out on a grid first: a cell
+//! spanning several columns (`colspan`) fills the first of them and leaves the
+//! rest empty, and one spanning several rows (`rowspan`) repeats its text in
+//! each, so every row has the same columns and lines up under the header.
+//! Columns left empty in every row (by an overlong `colspan`) are dropped.
+//!
+//! ```
+//! use daedra::tables;
+//! use scraper::{Html, Selector};
+//!
+//! let html = Html::parse_fragment(
+//!     "<table><tr><th>Plan</th><th>Price</th></tr><tr><td>Pro</td><td>$10</td></tr></table>",
+//! );
+//! let table = html.select(&Selector::parse("table").unwrap()).next().unwrap();
+//! assert_eq!(tables::to_markdown(table), "| Plan | Price |\n| --- | --- |\n| Pro | $10 |");
+//! ```
+
+use crate::text;
+use scraper::ElementRef;
+
+/// Most columns or rows one cell may span; larger spans are treated as this
+const MAX_SPAN: usize = 64;
+
+/// The table as Markdown: a pipe table whose header is the first row, after
+/// the caption when there is one. A table of a single column, as used for
+/// layout, becomes one paragraph per row instead; an empty one, nothing.
+/// Cells keep their text only, with whitespace collapsed.
+pub fn to_markdown(table: ElementRef<'_>) -> String {
+    let grid = grid(table);
+    let columns = grid
+        .iter()
+        .filter_map(|row| row.iter().rposition(|cell| !cell.is_empty()))
+        .max()
+        .map_or(0, |last| last + 1);
+    let caption = table
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == "caption")
+        .map(cell_text)
+        .filter(|caption| !caption.is_empty());
+
+    let mut blocks: Vec<String> = caption.into_iter().map(|c| format!("**{}**", c)).collect();
+    if columns <= 1 {
+        blocks.extend(grid.into_iter().flatten().filter(|cell| !cell.is_empty()));
+        return blocks.join("\n\n");
+    }
+
+    let mut lines = Vec::with_capacity(grid.len() + 1);
+    for (i, mut row) in grid.into_iter().enumerate() {
+        row.resize(columns, String::new());
+        let line = row.iter().fold(String::from("|"), |mut line, cell| {
+            if !cell.is_empty() {
+                line.push(' ');
+                line.push_str(&cell.replace('|', "\\|"));
+            }
+            line.push_str(" |");
+            line
+        });
+        lines.push(line);
+        if i == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+    blocks.push(lines.join("\n"));
+    blocks.join("\n\n")
+}
+
+/// Text of every row of `table` (not of tables nested in it), with spanned
+/// cells laid out on a grid
+fn grid(table: ElementRef<'_>) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    // Per column: text and remaining rows of a cell spanning down into it
+    let mut pending: Vec<Option<(String, usize)>> = Vec::new();
+
+    for row in table_rows(table) {
+        let mut cells = Vec::new();
+        for cell in row
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+        {
+            fill_spanned_rows(&mut cells, &mut pending);
+            let text = cell_text(cell);
+            let rowspan = span(cell, "rowspan");
+            for offset in 0..span(cell, "colspan") {
+                let text = if offset == 0 { text.clone() } else { String::new() };
+                if rowspan > 1 {
+                    if pending.len() <= cells.len() {
+                        pending.resize(cells.len() + 1, None);
+                    }
+                    pending[cells.len()] = Some((text.clone(), rowspan - 1));
+                }
+                cells.push(text);
+            }
+        }
+        fill_spanned_rows(&mut cells, &mut pending);
+        if cells.iter().any(|cell| !cell.is_empty()) {
+            rows.push(cells);
+        }
+    }
+    rows
+}
+
+/// Add the cells that rows above span down into, from the next column on
+fn fill_spanned_rows(cells: &mut Vec<String>, pending: &mut [Option<(String, usize)>]) {
+    while let Some(slot) = pending.get_mut(cells.len())
+        && let Some((text, left)) = slot
+    {
+        cells.push(text.clone());
+        *left -= 1;
+        if *left == 0 {
+            *slot = None;
+        }
+    }
+}
+
+/// The `<tr>`s of `table` itself, in `<thead>`, `<tbody>` and `<tfoot>` or
+/// directly under it
+fn table_rows(table: ElementRef<'_>) -> impl Iterator<Item = ElementRef<'_>> {
+    table.children().filter_map(ElementRef::wrap).flat_map(|child| {
+        let rows: Vec<ElementRef<'_>> = match child.value().name() {
+            "tr" => vec![child],
+            "thead" | "tbody" | "tfoot" => child
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|row| row.value().name() == "tr")
+                .collect(),
+            _ => Vec::new(),
+        };
+        rows
+    })
+}
+
+fn span(cell: ElementRef<'_>, attribute: &str) -> usize {
+    cell.value()
+        .attr(attribute)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_SPAN)
+}
+
+fn cell_text(element: ElementRef<'_>) -> String {
+    text::clean(&element.text().collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    fn convert(html: &str) -> String {
+        let fragment = Html::parse_fragment(html);
+        let table = fragment.select(&Selector::parse("table").unwrap()).next().unwrap();
+        to_markdown(table)
+    }
+
+    #[test]
+    fn test_pipe_table_with_sections_and_escaping() {
+        let markdown = convert(
+            "<table><caption>Plans</caption>
+             <thead><tr><th>Plan</th><th>Price</th><th>Notes</th></tr></thead>
+             <tbody><tr><td>Free</td><td>$0</td><td>a | b</td></tr>
+             <tr><td><a href='/pro'>Pro</a>\n plan</td><td><b>$10</b>/mo</td></tr></tbody>
+             </table>",
+        );
+        assert_eq!(
+            markdown,
+            "**Plans**\n\n\
+             | Plan | Price | Notes |\n\
+             | --- | --- | --- |\n\
+             | Free | $0 | a \\| b |\n\
+             | Pro plan | $10/mo | |"
+        );
+    }
+
+    #[test]
+    fn test_spans_are_flattened_onto_the_grid() {
+        let markdown = convert(
+            "<table>
+             <tr><th rowspan=2>Feature</th><th colspan=2>Plans</th></tr>
+             <tr><th>Free</th><th>Pro</th></tr>
+             <tr><td>Storage</td><td>1 GB</td><td rowspan=2>Unlimited</td></tr>
+             <tr><td>Seats</td><td>1</td></tr>
+             <tr><td>Support</td><td colspan=99>Email</td></tr>
+             </table>",
+        );
+        assert_eq!(
+            markdown.lines().collect::<Vec<_>>(),
+            [
+                "| Feature | Plans | |",
+                "| --- | --- | --- |",
+                "| Feature | Free | Pro |",
+                "| Storage | 1 GB | Unlimited |",
+                "| Seats | 1 | Unlimited |",
+                "| Support | Email | |",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_and_nested_tables() {
+        let layout =
+            "<table><tr><td>Intro</td></tr><tr><td></td></tr><tr><td>Body</td></tr></table>";
+        assert_eq!(convert(layout), "Intro\n\nBody");
+        assert_eq!(convert("<table></table>"), "");
+
+        let nested = convert(
+            "<table><tr><td>Outer</td><td><table><tr><td>Inner</td></tr></table></td></tr></table>",
+        );
+        assert_eq!(nested, "| Outer | Inner |\n| --- | --- |");
+    }
+}
@@ -0,0 +1,328 @@
+//! Per-call telemetry for agent frameworks budgeting their tool use.
+//!
+//! [`measure`] runs a future in a fresh scope and returns, next to its output,
+//! a [`Telemetry`] summary: upstream HTTP requests, response bytes, cache hits,
+//! retries, wall-clock duration and where that time went ([`Timings`]).
+//! Backends, the fetcher and the cache record into whichever scope is current;
+//! outside of one, recording is a no-op.
+//!
+//! The MCP server wraps every tool call this way and returns the summary in the
+//! tool result's `_meta.telemetry`. Calls slower than their
+//! [`SlowRequestConfig`] threshold are also logged as warnings with the full
+//! breakdown.
+
+use crate::types::{DaedraError, DaedraResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// What one tool call cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Telemetry {
+    /// HTTP requests sent upstream, retries and follow-up pages included
+    pub upstream_requests: u64,
+    /// Response body bytes read (after decompression)
+    pub bytes_received: u64,
+    /// Searches and pages answered from the cache
+    pub cache_hits: u64,
+    /// Requests repeated after a transient failure
+    pub retries: u64,
+    /// Wall-clock time of the whole call
+    pub duration_ms: u64,
+    /// Where that time went
+    pub timings: Timings,
+}
+
+/// Time a tool call spent in each [`Phase`], in milliseconds.
+///
+/// Work done concurrently counts in full, so with parallel fetches the phases
+/// can add up to more than the call's `duration_ms`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timings {
+    /// Waiting for a concurrency permit or a rate limiter
+    pub queue_ms: u64,
+    /// Reading and writing the response cache
+    pub cache_ms: u64,
+    /// Sending requests and reading response bodies
+    pub network_ms: u64,
+    /// Decoding responses and extracting pages
+    pub parse_ms: u64,
+    /// Rendering the tool result
+    pub serialize_ms: u64,
+}
+
+/// Part of a tool call timed separately in [`Timings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Waiting for a concurrency permit or a rate limiter
+    Queue,
+    /// Reading and writing the response cache
+    Cache,
+    /// Sending requests and reading response bodies
+    Network,
+    /// Decoding responses and extracting pages
+    Parse,
+    /// Rendering the tool result
+    Serialize,
+}
+
+#[derive(Default)]
+struct Counters {
+    upstream_requests: AtomicU64,
+    bytes_received: AtomicU64,
+    cache_hits: AtomicU64,
+    retries: AtomicU64,
+    /// Microseconds spent in each [`Phase`], in declaration order
+    phases: [AtomicU64; 5],
+}
+
+impl Counters {
+    fn snapshot(&self, elapsed: Duration) -> Telemetry {
+        let ms = |phase: Phase| self.phases[phase as usize].load(Ordering::Relaxed) / 1000;
+        Telemetry {
+            upstream_requests: self.upstream_requests.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            duration_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            timings: Timings {
+                queue_ms: ms(Phase::Queue),
+                cache_ms: ms(Phase::Cache),
+                network_ms: ms(Phase::Network),
+                parse_ms: ms(Phase::Parse),
+                serialize_ms: ms(Phase::Serialize),
+            },
+        }
+    }
+}
+
+/// Durations past which tool calls are logged as slow.
+///
+/// A slow call produces one warning carrying the tool name, its duration, the
+/// threshold it crossed and the full [`Telemetry`], [`Timings`] included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlowRequestConfig {
+    /// Threshold for tools without one of their own; `None` logs none of them
+    pub default_threshold: Option<Duration>,
+    /// Thresholds by tool name (`visit_page`, `deep_research`), overriding the default
+    pub tools: HashMap<String, Duration>,
+}
+
+impl SlowRequestConfig {
+    /// Log every tool taking longer than `threshold`
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            default_threshold: Some(threshold),
+            tools: HashMap::new(),
+        }
+    }
+
+    /// Give `tool` a threshold of its own
+    pub fn with_tool(mut self, tool: impl Into<String>, threshold: Duration) -> Self {
+        self.tools.insert(tool.into(), threshold);
+        self
+    }
+
+    /// Threshold that applies to `tool`, if any
+    pub fn threshold(&self, tool: &str) -> Option<Duration> {
+        self.tools.get(tool).copied().or(self.default_threshold)
+    }
+
+    /// The threshold `telemetry` crossed for `tool`, when the call was slow
+    pub fn exceeded(&self, tool: &str, telemetry: &Telemetry) -> Option<Duration> {
+        self.threshold(tool)
+            .filter(|threshold| u128::from(telemetry.duration_ms) > threshold.as_millis())
+    }
+}
+
+tokio::task_local! {
+    static COUNTERS: Arc<Counters>;
+}
+
+/// Run `future` and report what it cost.
+///
+/// Scopes nest: an inner `measure` counts only its own work, and that work is
+/// not added to the outer scope.
+pub async fn measure<F: Future>(future: F) -> (F::Output, Telemetry) {
+    let counters = Arc::new(Counters::default());
+    let start = Instant::now();
+    let output = COUNTERS.scope(Arc::clone(&counters), future).await;
+    (output, counters.snapshot(start.elapsed()))
+}
+
+/// Carry the current scope into a future that will be `tokio::spawn`ed.
+pub(crate) fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let current = COUNTERS.try_with(Arc::clone).ok();
+    async move {
+        match current {
+            Some(counters) => COUNTERS.scope(counters, future).await,
+            None => future.await,
+        }
+    }
+}
+
+fn add(counter: fn(&Counters) -> &AtomicU64, n: u64) {
+    let _ = COUNTERS.try_with(|c| counter(c).fetch_add(n, Ordering::Relaxed));
+}
+
+/// Add `elapsed` to the time spent in `phase`
+pub(crate) fn record_phase(phase: Phase, elapsed: Duration) {
+    let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    let _ = COUNTERS.try_with(|c| c.phases[phase as usize].fetch_add(micros, Ordering::Relaxed));
+}
+
+/// Run `future`, counting the time it takes as `phase`
+pub(crate) async fn timed<F: Future>(phase: Phase, future: F) -> F::Output {
+    let start = Instant::now();
+    let output = future.await;
+    record_phase(phase, start.elapsed());
+    output
+}
+
+/// Run `f`, counting the time it takes as `phase`
+pub(crate) fn timed_sync<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let output = f();
+    record_phase(phase, start.elapsed());
+    output
+}
+
+/// Count a cache hit
+pub(crate) fn record_cache_hit() {
+    add(|c| &c.cache_hits, 1);
+}
+
+/// Count response body bytes read without [`CountedResponse`]
+pub(crate) fn record_bytes(n: usize) {
+    add(|c| &c.bytes_received, n as u64);
+}
+
+/// Count a retried request
+pub(crate) fn record_retry() {
+    add(|c| &c.retries, 1);
+}
+
+/// Counting stand-in for [`reqwest::RequestBuilder::send`].
+pub(crate) trait CountedRequest {
+    /// Send the request, counting it as upstream traffic
+    fn send_counted(self) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send;
+}
+
+impl CountedRequest for reqwest::RequestBuilder {
+    fn send_counted(self) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send {
+        add(|c| &c.upstream_requests, 1);
+        timed(Phase::Network, self.send())
+    }
+}
+
+/// Counting stand-ins for the [`reqwest::Response`] body readers.
+pub(crate) trait CountedResponse {
+    /// Read the body as text, counting its size
+    fn text_counted(self) -> impl Future<Output = reqwest::Result<String>> + Send;
+
+    /// Read and decode a JSON body, counting its size
+    fn json_counted<T: DeserializeOwned>(self) -> impl Future<Output = DaedraResult<T>> + Send;
+}
+
+impl CountedResponse for reqwest::Response {
+    async fn text_counted(self) -> reqwest::Result<String> {
+        let text = timed(Phase::Network, self.text()).await?;
+        record_bytes(text.len());
+        Ok(text)
+    }
+
+    async fn json_counted<T: DeserializeOwned>(self) -> DaedraResult<T> {
+        let bytes = timed(Phase::Network, self.bytes()).await?;
+        record_bytes(bytes.len());
+        timed_sync(Phase::Parse, || serde_json::from_slice(&bytes)).map_err(DaedraError::JsonError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_measure_counts_only_inside_scope() {
+        record_retry();
+        let ((), telemetry) = measure(async {
+            record_cache_hit();
+            record_retry();
+            record_retry();
+        })
+        .await;
+        assert_eq!(telemetry.cache_hits, 1);
+        assert_eq!(telemetry.retries, 2);
+        assert_eq!(telemetry.upstream_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_propagate_into_spawned_task() {
+        let ((), telemetry) = measure(async {
+            tokio::spawn(propagate(async { record_cache_hit() }))
+                .await
+                .unwrap();
+            // Without propagation the spawned task records nowhere
+            tokio::spawn(async { record_cache_hit() }).await.unwrap();
+        })
+        .await;
+        assert_eq!(telemetry.cache_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_phases_are_timed_separately() {
+        let ((), telemetry) = measure(async {
+            timed(Phase::Queue, tokio::time::sleep(Duration::from_millis(20))).await;
+            timed_sync(Phase::Serialize, || std::thread::sleep(Duration::from_millis(5)));
+            record_phase(Phase::Serialize, Duration::from_millis(5));
+        })
+        .await;
+        assert!(telemetry.timings.queue_ms >= 20);
+        assert!(telemetry.timings.serialize_ms >= 10);
+        assert_eq!(telemetry.timings.network_ms, 0);
+        assert!(telemetry.duration_ms >= telemetry.timings.queue_ms);
+    }
+
+    #[test]
+    fn test_slow_request_thresholds() {
+        let call = |duration_ms| Telemetry {
+            duration_ms,
+            ..Default::default()
+        };
+        assert_eq!(SlowRequestConfig::default().exceeded("web_search", &call(u64::MAX)), None);
+
+        let config = SlowRequestConfig::new(Duration::from_secs(2))
+            .with_tool("deep_research", Duration::from_secs(30));
+        assert_eq!(config.exceeded("web_search", &call(2000)), None);
+        assert_eq!(config.exceeded("web_search", &call(2001)), Some(Duration::from_secs(2)));
+        assert_eq!(config.exceeded("deep_research", &call(2001)), None);
+        assert_eq!(config.threshold("deep_research"), Some(Duration::from_secs(30)));
+    }
+
+    #[tokio::test]
+    async fn test_counted_request_and_body() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::any())
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_string(r#"{"ok":true}"#))
+            .mount(&server)
+            .await;
+
+        let (value, telemetry) = measure(async {
+            let response = reqwest::Client::new()
+                .get(server.uri())
+                .send_counted()
+                .await
+                .unwrap();
+            response.json_counted::<serde_json::Value>().await.unwrap()
+        })
+        .await;
+        assert_eq!(value["ok"], true);
+        assert!(telemetry.timings.network_ms <= telemetry.duration_ms);
+        assert_eq!(telemetry.upstream_requests, 1);
+        assert_eq!(telemetry.bytes_received, 11);
+    }
+}
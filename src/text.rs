@@ -0,0 +1,207 @@
+//! Normalizing text before it is compared.
+//!
+//! Cache keys, re-ranking and keyword extraction all compare words, and the
+//! same word reaches them in different forms: `café` composed or as `e` plus a
+//! combining accent, `Straße` or `STRASSE`, `crates` or `crate`. The helpers
+//! here put text in one form first:
+//!
+//! - Unicode NFC, so composed and decomposed characters compare equal
+//! - case folding, with Turkish and Azerbaijani dotted and dotless `i`
+//! - optionally, stemming of English plurals
+//!
+//! Text scraped from HTML also goes through [`decode_entities`] (or [`clean`],
+//! which collapses whitespace too), so `&eacute;` and `&#8217;` never reach
+//! results or cache keys.
+//!
+//! ```
+//! use daedra::text::{self, TermOptions};
+//!
+//! assert_eq!(text::key("  Cafe\u{301}  Straße "), "café strasse");
+//! let options = TermOptions { stem: true, ..Default::default() };
+//! assert_eq!(text::terms("Rust crates, libraries", options), ["rust", "crate", "library"]);
+//! ```
+
+use std::borrow::Cow;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
+
+/// Languages whose `I` folds to dotless `ı` and `İ` to `i`
+const TURKIC_LANGUAGES: &[&str] = &["tr", "az"];
+
+/// Endings that look like plurals but are not (`status`, `glass`, `analysis`)
+const NON_PLURAL_ENDINGS: &[&str] = &["us", "ss", "is"];
+
+/// Shortest word [`stem`] shortens
+const MIN_STEMMED_LEN: usize = 4;
+
+/// How [`terms`] splits and normalizes text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TermOptions<'a> {
+    /// Language tag (`en`, `tr-TR`) of the text, for case folding and
+    /// stemming; `None` folds as most languages do and stems as English
+    pub language: Option<&'a str>,
+
+    /// Reduce words to their stems
+    pub stem: bool,
+
+    /// Keep hyphenated words (`async-std`) whole
+    pub keep_hyphens: bool,
+}
+
+/// `text` in Unicode normalization form C.
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        _ => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// `text` with HTML character references decoded: every named entity
+/// (`&eacute;`, `&rsquo;`, `&nbsp;`) and decimal and hex references
+/// (`&#8217;`, `&#x27;`). Unknown entities are left as written.
+pub fn decode_entities(text: &str) -> Cow<'_, str> {
+    if text.contains('&') {
+        html_escape::decode_html_entities(text)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Scraped `text` ready for display: entities decoded and whitespace
+/// collapsed, non-breaking spaces included.
+pub fn clean(text: &str) -> String {
+    collapse_whitespace(&decode_entities(text))
+}
+
+/// `text` with runs of whitespace collapsed to single spaces and trimmed.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` in NFC and case folded for `language`, so that case variants of a
+/// word compare equal: `Straße` and `STRASSE` both fold to `strasse`.
+pub fn fold_case(text: &str, language: Option<&str>) -> String {
+    let turkic = language
+        .is_some_and(|tag| TURKIC_LANGUAGES.contains(&primary_language(tag).as_str()));
+    let mut folded = String::with_capacity(text.len());
+    for c in nfc(text).chars() {
+        match c {
+            'I' if turkic => folded.push('ı'),
+            'İ' if turkic => folded.push('i'),
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            c => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
+/// Key under which two spellings of `text` count as the same: case folded,
+/// in NFC, with whitespace collapsed.
+pub fn key(text: &str) -> String {
+    collapse_whitespace(&fold_case(text, None))
+}
+
+/// `word` with an English plural ending removed (`libraries` → `library`,
+/// `crates` → `crate`). Words in other languages, short words and words with
+/// digits are left alone. Expects a case-folded word.
+pub fn stem<'a>(word: &'a str, language: Option<&str>) -> Cow<'a, str> {
+    let english = language.is_none_or(|tag| primary_language(tag) == "en");
+    if !english
+        || word.chars().count() < MIN_STEMMED_LEN
+        || !word.chars().all(char::is_alphabetic)
+    {
+        return Cow::Borrowed(word);
+    }
+    if let Some(stem) = word.strip_suffix("ies")
+        && !stem.ends_with(['a', 'e'])
+    {
+        return Cow::Owned(format!("{}y", stem));
+    }
+    if let Some(stem) = word.strip_suffix("es")
+        && !stem.ends_with(['a', 'e', 'o'])
+    {
+        return Cow::Owned(format!("{}e", stem));
+    }
+    match word.strip_suffix('s') {
+        Some(stem) if !NON_PLURAL_ENDINGS.iter().any(|e| word.ends_with(e)) => {
+            Cow::Borrowed(stem)
+        },
+        _ => Cow::Borrowed(word),
+    }
+}
+
+/// The words of `text`, normalized as `options` say.
+pub fn terms(text: &str, options: TermOptions<'_>) -> Vec<String> {
+    let folded = fold_case(text, options.language);
+    folded
+        .split(|c: char| !(c.is_alphanumeric() || options.keep_hyphens && c == '-'))
+        .map(|t| t.trim_matches('-'))
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            if options.stem {
+                stem(t, options.language).into_owned()
+            } else {
+                t.to_string()
+            }
+        })
+        .collect()
+}
+
+/// `tr` from `tr-TR`, `tr_TR` or `TR`
+fn primary_language(tag: &str) -> String {
+    tag.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfc_composes_only_when_needed() {
+        assert!(matches!(nfc("plain ascii"), Cow::Borrowed(_)));
+        assert_eq!(nfc("cafe\u{301}"), "café");
+        assert_eq!(key("CAFE\u{301}"), key("café"));
+    }
+
+    #[test]
+    fn test_fold_case_is_locale_aware() {
+        assert_eq!(fold_case("Straße ΟΔΟΣ", None), "strasse οδοσ");
+        assert_eq!(fold_case("STRASSE", None), fold_case("straße", None));
+        assert_eq!(fold_case("DİYARBAKIR", Some("tr-TR")), "diyarbakır");
+        assert_eq!(fold_case("Istanbul", Some("az")), "ıstanbul");
+        assert_eq!(fold_case("Istanbul", Some("en")), "istanbul");
+    }
+
+    #[test]
+    fn test_stem_english_plurals_only() {
+        let stems: Vec<Cow<'_, str>> = ["libraries", "crates", "tokens", "status", "glass", "rust"]
+            .iter()
+            .map(|w| stem(w, None))
+            .collect();
+        assert_eq!(stems, ["library", "crate", "token", "status", "glass", "rust"]);
+        assert_eq!(stem("bus", None), "bus");
+        assert_eq!(stem("h2s", None), "h2s");
+        assert_eq!(stem("kurses", Some("de")), "kurses");
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("caf&eacute; &amp; cr&egrave;me"), "café & crème");
+        assert_eq!(decode_entities("it&#8217;s &#x27;ok&#39;"), "it\u{2019}s 'ok'");
+        assert_eq!(decode_entities("&notanentity; & more"), "&notanentity; & more");
+        assert!(matches!(decode_entities("no entities"), Cow::Borrowed(_)));
+        assert_eq!(clean(" a&nbsp;&nbsp;b \n c "), "a b c");
+    }
+
+    #[test]
+    fn test_terms() {
+        let plain = TermOptions::default();
+        assert_eq!(terms("Tokio vs async-std!", plain), ["tokio", "vs", "async", "std"]);
+        let hyphens = TermOptions {
+            keep_hyphens: true,
+            ..Default::default()
+        };
+        assert_eq!(terms("Tokio vs -async-std-", hyphens), ["tokio", "vs", "async-std"]);
+        assert!(terms(" ,; ", plain).is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+//! Points in time as Daedra reports them.
+//!
+//! [`Timestamp`] is an instant in UTC. It is written everywhere in one form —
+//! RFC 3339 to the second with a `Z` suffix, `2024-05-01T09:30:00Z` — and read
+//! from any RFC 3339 offset or from Unix seconds, so pages, search metadata and
+//! cache files written by older versions still load. [`Timestamp::to_local_string`]
+//! renders it in the machine's time zone for people reading CLI output.
+//!
+//! ```
+//! use daedra::Timestamp;
+//!
+//! let at: Timestamp = "2024-05-01T11:30:00+02:00".parse().unwrap();
+//! assert_eq!(at.to_string(), "2024-05-01T09:30:00Z");
+//! assert_eq!(Timestamp::from_unix(at.unix()), Some(at));
+//! ```
+
+use crate::types::{DaedraError, DaedraResult};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// An instant in UTC, to the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(DateTime<Utc>);
+
+impl Timestamp {
+    /// The current time
+    pub fn now() -> Self {
+        Self::from(Utc::now())
+    }
+
+    /// The time `secs` seconds after the Unix epoch, when it is in range
+    pub fn from_unix(secs: i64) -> Option<Self> {
+        DateTime::from_timestamp(secs, 0).map(Self)
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn unix(&self) -> i64 {
+        self.0.timestamp()
+    }
+
+    /// The instant as a [`chrono`] date-time
+    pub fn as_datetime(&self) -> DateTime<Utc> {
+        self.0
+    }
+
+    /// Time since this instant; zero when it is in the future
+    pub fn elapsed(&self) -> Duration {
+        (Utc::now() - self.0).to_std().unwrap_or_default()
+    }
+
+    /// The instant in the local time zone, `2024-05-01 11:30:00 +02:00`
+    pub fn to_local_string(&self) -> String {
+        self.0.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string()
+    }
+}
+
+impl Default for Timestamp {
+    /// The Unix epoch
+    fn default() -> Self {
+        Self(DateTime::UNIX_EPOCH)
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(at: DateTime<Utc>) -> Self {
+        // Sub-second precision is dropped, so what is written reads back equal
+        Self::from_unix(at.timestamp()).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = DaedraError;
+
+    /// Parse RFC 3339 with any offset, or Unix seconds
+    fn from_str(s: &str) -> DaedraResult<Self> {
+        let s = s.trim();
+        if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self::from(at.with_timezone(&Utc)));
+        }
+        s.parse()
+            .ok()
+            .and_then(Self::from_unix)
+            .ok_or_else(|| DaedraError::InvalidArguments(format!("Not a timestamp: {}", s)))
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Unix(i64),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+            Raw::Unix(secs) => Self::from_unix(secs)
+                .ok_or_else(|| serde::de::Error::custom(format!("Unix time out of range: {secs}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_written_in_one_form() {
+        for text in [
+            "2024-05-01T09:30:00Z",
+            "2024-05-01T09:30:00+00:00",
+            "2024-05-01T09:30:00.123456789+00:00",
+            "2024-05-01T05:30:00-04:00",
+            "1714555800",
+        ] {
+            let at: Timestamp = text.parse().unwrap();
+            assert_eq!(at.to_string(), "2024-05-01T09:30:00Z", "{text}");
+        }
+        assert!("yesterday".parse::<Timestamp>().is_err());
+        assert_eq!(Timestamp::default().unix(), 0);
+    }
+
+    #[test]
+    fn test_serde_reads_strings_and_unix_seconds() {
+        let at = Timestamp::from_unix(1_714_555_800).unwrap();
+        assert_eq!(serde_json::to_string(&at).unwrap(), "\"2024-05-01T09:30:00Z\"");
+        let read: Timestamp = serde_json::from_str("\"2024-05-01T09:30:00+00:00\"").unwrap();
+        assert_eq!(read, at);
+        assert_eq!(serde_json::from_str::<Timestamp>("1714555800").unwrap(), at);
+        assert!(serde_json::from_str::<Timestamp>("\"soon\"").is_err());
+    }
+
+    #[test]
+    fn test_now_round_trips() {
+        let now = Timestamp::now();
+        assert_eq!(now.to_string().parse::<Timestamp>().unwrap(), now);
+        assert!(now.elapsed() < Duration::from_secs(5));
+        let local = DateTime::parse_from_str(&now.to_local_string(), "%Y-%m-%d %H:%M:%S %:z");
+        assert_eq!(local.unwrap().with_timezone(&Utc), now.as_datetime());
+    }
+}
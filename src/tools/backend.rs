@@ -6,17 +6,125 @@
 //! - Tavily (AI-optimized search, needs TAVILY_API_KEY)
 //! - DuckDuckGo HTML scraping (blocked from datacenter IPs, fallback only)
 
-use crate::types::{DaedraError, DaedraResult, SearchArgs, SearchResponse};
+use crate::fingerprint::Rotation;
+use crate::privacy::{self, ClientProfile};
+use crate::query::{self, QueryDialect};
+use crate::regions;
+use crate::telemetry::{self, Phase};
+use crate::types::{
+    DaedraError, DaedraResult, KnowledgeGraph, PlannedRequest, RelatedQuestion, SearchArgs,
+    SearchResponse, SearchStrategy, Verticals,
+};
+use super::rerank;
 use async_trait::async_trait;
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoff;
 use governor::{DefaultDirectRateLimiter, DefaultKeyedRateLimiter, Quota, RateLimiter};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+/// Record which backend produced each result.
+fn tag_backend(results: &mut [crate::types::SearchResult], backend: &str) {
+    for r in results {
+        r.metadata.backend = Some(backend.into());
+    }
+}
+
+/// Query parameters that only track clicks and never change the page.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "msclkid", "ref_src", "mc_cid", "mc_eid"];
+
+/// Key under which two result URLs count as the same page: scheme, `www.`,
+/// fragment, trailing slash and tracking parameters are ignored.
+pub(crate) fn dedup_key(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url.trim()) else {
+        return url.trim().to_lowercase();
+    };
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let path = parsed.path().trim_end_matches('/');
+    let query: Vec<String> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect();
+    let port = parsed.port().map(|p| format!(":{p}")).unwrap_or_default();
+    if query.is_empty() {
+        format!("{host}{port}{path}")
+    } else {
+        format!("{host}{port}{path}?{}", query.join("&"))
+    }
+}
+
+/// Circuit breaker tuning applied to every backend of a [`SearchProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures before the circuit opens.
+    pub failure_threshold: u32,
+    /// How long an open circuit skips the backend before a probe is let through.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Circuit state of a backend, as reported by [`BackendHealth::state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Healthy — requests flow normally.
+    Closed,
+    /// Tripped — the backend is skipped until the cooldown elapses.
+    Open,
+    /// Cooldown elapsed — the next request is a probe that closes or re-opens the circuit.
+    HalfOpen,
+}
+
+/// Point-in-time health of one search backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendStatus {
+    /// Backend name
+    pub name: String,
+    /// Circuit state
+    pub state: CircuitState,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+    /// Seconds until an open circuit allows a probe
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+    /// Whether the backend needs an API key
+    pub requires_api_key: bool,
+}
+
+/// What a search would do with one backend, as reported by a dry run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendPlan {
+    /// Backend name
+    pub name: String,
+    /// Whether the backend would be queried (for fallback, once every earlier one fails)
+    pub would_query: bool,
+    /// Why it would be skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+    /// Circuit state
+    pub circuit: CircuitState,
+    /// Rate-limit quota the request counts against
+    pub rate_limit: String,
+    /// The request it would send
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<PlannedRequest>,
+}
 
 /// Circuit breaker state for a single backend — opens after consecutive failures, cools down, then probes.
 #[derive(Debug)]
@@ -49,6 +157,31 @@ impl BackendHealth {
         last.elapsed() >= self.cooldown
     }
 
+    /// Current circuit state.
+    pub fn state(&self) -> CircuitState {
+        if !self.is_open.load(Ordering::Relaxed) {
+            CircuitState::Closed
+        } else if self.cooldown_remaining().is_some() {
+            CircuitState::Open
+        } else {
+            CircuitState::HalfOpen
+        }
+    }
+
+    /// Failures recorded since the last success.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Time left before an open circuit admits a probe; `None` unless open and cooling down.
+    pub fn cooldown_remaining(&self) -> Option<Duration> {
+        if !self.is_open.load(Ordering::Relaxed) {
+            return None;
+        }
+        let last = self.last_failure.lock().expect("last_failure lock");
+        self.cooldown.checked_sub(last.elapsed()).filter(|d| !d.is_zero())
+    }
+
     /// Record a successful request — resets consecutive failure count and closes the circuit.
     pub fn record_success(&self) {
         self.consecutive_failures.store(0, Ordering::Relaxed);
@@ -105,11 +238,38 @@ impl BackendRateLimiters {
         scraper_default: &DefaultKeyedRateLimiter<String>,
     ) {
         let key = name.to_string();
-        match name {
+        match RateTier::of(name) {
             // Scraper backends use the moderate default keyed limiter on SearchProvider.
-            "bing" | "duckduckgo" => scraper_default.until_key_ready(&key).await,
-            "serper" | "tavily" => self.api.until_key_ready(&key).await,
-            _ => self.knowledge.until_key_ready(&key).await,
+            RateTier::Scraper => scraper_default.until_key_ready(&key).await,
+            RateTier::Api => self.api.until_key_ready(&key).await,
+            RateTier::Knowledge => self.knowledge.until_key_ready(&key).await,
+        }
+    }
+}
+
+/// Which [`BackendRateLimiters`] quota a backend draws from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RateTier {
+    Scraper,
+    Api,
+    Knowledge,
+}
+
+impl RateTier {
+    fn of(name: &str) -> Self {
+        match name {
+            "bing" | "duckduckgo" => Self::Scraper,
+            "serper" | "tavily" | "google_cse" | "mojeek" => Self::Api,
+            _ => Self::Knowledge,
+        }
+    }
+
+    /// The quota in words, for dry-run reports
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Scraper => "scraper: 1 request / 2s, burst 3",
+            Self::Api => "api: 2 requests / s",
+            Self::Knowledge => "knowledge: 2 requests / s",
         }
     }
 }
@@ -133,6 +293,28 @@ pub trait SearchBackend: Send + Sync {
     fn is_available(&self) -> bool {
         true
     }
+
+    /// The request [`search`](Self::search) would send for `args`, for dry runs.
+    fn plan_request(&self, _args: &SearchArgs) -> Option<PlannedRequest> {
+        None
+    }
+
+    /// Query operators the engine understands; queries using others are
+    /// rewritten before [`search`](Self::search) sees them.
+    fn query_dialect(&self) -> QueryDialect {
+        QueryDialect::Full
+    }
+}
+
+/// `args` with the query rewritten for `backend`'s dialect, when that changes it
+fn args_for(backend: &dyn SearchBackend, args: &SearchArgs) -> Option<SearchArgs> {
+    match query::for_dialect(&args.query, backend.query_dialect()) {
+        Cow::Borrowed(_) => None,
+        Cow::Owned(query) => Some(SearchArgs {
+            query,
+            options: args.options.clone(),
+        }),
+    }
 }
 
 /// Multi-backend search provider with automatic fallback.
@@ -146,6 +328,9 @@ pub struct SearchProvider {
     backend_limiters: DefaultKeyedRateLimiter<String>,
     backend_rate_limits: Arc<BackendRateLimiters>,
     circuit_breakers: HashMap<String, Arc<BackendHealth>>,
+    circuit_breaker_config: CircuitBreakerConfig,
+    /// Profile of the backends this provider builds itself
+    client_profile: ClientProfile,
 }
 
 impl SearchProvider {
@@ -162,29 +347,61 @@ impl SearchProvider {
         BackendRateLimiters::default_limiter()
     }
 
-    fn init_circuit_breakers(backends: &[Box<dyn SearchBackend>]) -> HashMap<String, Arc<BackendHealth>> {
+    fn init_circuit_breakers(
+        backends: &[Box<dyn SearchBackend>],
+        config: CircuitBreakerConfig,
+    ) -> HashMap<String, Arc<BackendHealth>> {
         backends
             .iter()
             .map(|b| {
                 (
                     b.name().to_string(),
-                    Arc::new(BackendHealth::new(3, Duration::from_secs(30))),
+                    Arc::new(BackendHealth::new(config.failure_threshold, config.cooldown)),
                 )
             })
             .collect()
     }
 
     fn from_backends(backends: Vec<Box<dyn SearchBackend>>) -> Self {
-        let circuit_breakers = Self::init_circuit_breakers(&backends);
+        let circuit_breaker_config = CircuitBreakerConfig::default();
+        let circuit_breakers = Self::init_circuit_breakers(&backends, circuit_breaker_config);
         Self {
             backends,
             rate_limiter: Self::new_rate_limiter(),
             backend_limiters: Self::new_backend_limiters(),
             backend_rate_limits: BackendRateLimiters::new(),
             circuit_breakers,
+            circuit_breaker_config,
+            client_profile: ClientProfile::Standard,
         }
     }
 
+    /// Use `config` for every backend's circuit breaker (resets current breaker state).
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breakers = Self::init_circuit_breakers(&self.backends, config);
+        self.circuit_breaker_config = config;
+        self
+    }
+
+    /// Health of every backend, in priority order.
+    pub fn backend_status(&self) -> Vec<BackendStatus> {
+        self.backends
+            .iter()
+            .map(|b| {
+                let health = self.circuit_breakers.get(b.name());
+                BackendStatus {
+                    name: b.name().to_string(),
+                    state: health.map_or(CircuitState::Closed, |h| h.state()),
+                    consecutive_failures: health.map_or(0, |h| h.consecutive_failures()),
+                    retry_in_secs: health
+                        .and_then(|h| h.cooldown_remaining())
+                        .map(|d| d.as_secs_f64().ceil() as u64),
+                    requires_api_key: b.requires_api_key(),
+                }
+            })
+            .collect()
+    }
+
     /// Create a new provider with the given backends (in priority order).
     pub fn new(backends: Vec<Box<dyn SearchBackend>>) -> Self {
         Self::from_backends(backends)
@@ -192,13 +409,20 @@ impl SearchProvider {
 
     /// Create a provider with all available backends auto-detected from env.
     pub fn auto() -> Self {
+        Self::auto_with(ClientProfile::Standard, Arc::default())
+    }
+
+    /// [`auto`](Self::auto), with every backend sending the headers of
+    /// `profile`, and the scraping ones taking browser profiles from `rotation`.
+    pub fn auto_with(profile: ClientProfile, rotation: Arc<Rotation>) -> Self {
         let mut backends: Vec<Box<dyn SearchBackend>> = Vec::new();
 
         // Serper (Google results) — if API key is set
         if let Ok(key) = std::env::var("SERPER_API_KEY") {
             if !key.is_empty() {
                 info!("Serper backend enabled (SERPER_API_KEY set)");
-                backends.push(Box::new(super::serper::SerperBackend::new(key)));
+                let serper = super::serper::SerperBackend::new(key).with_client_profile(profile);
+                backends.push(Box::new(serper));
             }
         }
 
@@ -206,39 +430,107 @@ impl SearchProvider {
         if let Ok(key) = std::env::var("TAVILY_API_KEY") {
             if !key.is_empty() {
                 info!("Tavily backend enabled (TAVILY_API_KEY set)");
-                backends.push(Box::new(super::tavily::TavilyBackend::new(key)));
+                let tavily = super::tavily::TavilyBackend::new(key).with_client_profile(profile);
+                backends.push(Box::new(tavily));
             }
         }
 
+        // Google Programmable Search — needs both the API key and the engine ID
+        match (std::env::var("GOOGLE_API_KEY"), std::env::var("GOOGLE_CSE_ID")) {
+            (Ok(key), Ok(cx)) if !key.is_empty() && !cx.is_empty() => {
+                info!("Google CSE backend enabled (GOOGLE_API_KEY + GOOGLE_CSE_ID set)");
+                let google_cse = super::google_cse::GoogleCseBackend::new(key, cx)
+                    .with_client_profile(profile);
+                backends.push(Box::new(google_cse));
+            },
+            (Ok(_), Err(_)) | (Err(_), Ok(_)) => {
+                warn!("Google CSE backend disabled: set both GOOGLE_API_KEY and GOOGLE_CSE_ID");
+            },
+            _ => {},
+        }
+
+        // Mojeek — independent crawler index, if API key is set
+        if let Ok(key) = std::env::var("MOJEEK_API_KEY")
+            && !key.is_empty()
+        {
+            info!("Mojeek backend enabled (MOJEEK_API_KEY set)");
+            let mojeek = super::mojeek::MojeekBackend::new(key).with_client_profile(profile);
+            backends.push(Box::new(mojeek));
+        }
+
         // Bing HTML scraping — no API key, but often CAPTCHA-blocked from datacenter IPs
         info!("Bing backend enabled (no API key, may be blocked from datacenter IPs)");
-        backends.push(Box::new(super::bing::BingBackend::new()));
+        let bing = super::bing::BingBackend::new()
+            .with_client_profile(profile)
+            .with_browser_profiles(Arc::clone(&rotation));
+        backends.push(Box::new(bing));
 
         // Wikipedia — always works from any IP, knowledge-focused
         info!("Wikipedia backend enabled (always works, knowledge-focused)");
-        backends.push(Box::new(super::wikipedia::WikipediaBackend::new()));
+        let wikipedia = super::wikipedia::WikipediaBackend::new().with_client_profile(profile);
+        backends.push(Box::new(wikipedia));
 
         // StackExchange — always works from any IP, technical Q&A
         info!("StackExchange backend enabled (always works, technical)");
-        backends.push(Box::new(super::stackexchange::StackExchangeBackend::new()));
+        let stackexchange =
+            super::stackexchange::StackExchangeBackend::new().with_client_profile(profile);
+        backends.push(Box::new(stackexchange));
 
         // GitHub — always works, code/repo search
         info!("GitHub backend enabled (always works, code/repos)");
-        backends.push(Box::new(super::github::GitHubBackend::new()));
+        backends.push(Box::new(super::github::GitHubBackend::new().with_client_profile(profile)));
 
         // Wiby — indie web search, always works
         info!("Wiby backend enabled (always works, indie web)");
-        backends.push(Box::new(super::wiby::WibyBackend::new()));
+        backends.push(Box::new(super::wiby::WibyBackend::new().with_client_profile(profile)));
 
         // DDG Instant Answers — knowledge graph, always works (different from HTML scraping)
         info!("DDG Instant Answers backend enabled (always works, knowledge)");
-        backends.push(Box::new(super::ddg_instant::DdgInstantBackend::new()));
+        let ddg_instant = super::ddg_instant::DdgInstantBackend::new().with_client_profile(profile);
+        backends.push(Box::new(ddg_instant));
 
         // DDG HTML scraping — blocked from most datacenter IPs, last resort
         info!("DuckDuckGo HTML backend enabled (last resort)");
-        backends.push(Box::new(super::search::SearchClient::new().unwrap()));
+        backends.push(Box::new(
+            super::search::SearchClient::builder()
+                .client_profile(profile)
+                .browser_profiles(rotation)
+                .build()
+                .unwrap(),
+        ));
 
-        Self::from_backends(backends)
+        let mut provider = Self::from_backends(backends);
+        provider.client_profile = profile;
+        provider
+    }
+
+    /// Use `api_key` for Serper, replacing any key picked up from the environment.
+    pub fn with_serper(self, api_key: &str) -> Self {
+        info!("Serper backend enabled (explicit API key)");
+        let profile = self.client_profile;
+        let serper = super::serper::SerperBackend::new(api_key.to_string())
+            .with_client_profile(profile);
+        let mut backends: Vec<Box<dyn SearchBackend>> = vec![Box::new(serper)];
+        let config = self.circuit_breaker_config;
+        backends.extend(self.backends.into_iter().filter(|b| b.name() != "serper"));
+        let mut provider = Self::from_backends(backends).with_circuit_breaker(config);
+        provider.client_profile = profile;
+        provider
+    }
+
+    /// Put a SearXNG instance at the front of the backend list.
+    #[cfg(feature = "searxng")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "searxng")))]
+    pub fn with_searxng(self, base_url: &str) -> DaedraResult<Self> {
+        let profile = self.client_profile;
+        let backend = super::searxng::SearxngBackend::with_client_profile(base_url, profile)?;
+        info!(url = %base_url, "SearXNG backend enabled");
+        let mut backends: Vec<Box<dyn SearchBackend>> = vec![Box::new(backend)];
+        let config = self.circuit_breaker_config;
+        backends.extend(self.backends);
+        let mut provider = Self::from_backends(backends).with_circuit_breaker(config);
+        provider.client_profile = profile;
+        Ok(provider)
     }
 
     const NON_RETRYABLE_SUBSTRINGS: &[&str] = &[
@@ -250,7 +542,9 @@ impl SearchProvider {
 
     fn is_non_retryable(err: &DaedraError) -> bool {
         match err {
-            DaedraError::BotProtectionDetected | DaedraError::RateLimitExceeded => true,
+            DaedraError::BotProtectionDetected
+            | DaedraError::CaptchaChallenged(_)
+            | DaedraError::RateLimitExceeded { .. } => true,
             DaedraError::SearchError(msg) => {
                 let m = msg.to_lowercase();
                 Self::NON_RETRYABLE_SUBSTRINGS.iter().any(|s| m.contains(s))
@@ -263,7 +557,8 @@ impl SearchProvider {
 
     fn is_transient(err: &DaedraError) -> bool {
         match err {
-            DaedraError::HttpError(_) | DaedraError::Timeout => true,
+            // A JSON error is usually an HTML error page in place of the API response
+            DaedraError::HttpError(_) | DaedraError::JsonError(_) | DaedraError::Timeout => true,
             DaedraError::SearchError(msg) => {
                 let m = msg.to_lowercase();
                 Self::TRANSIENT_SUBSTRINGS.iter().any(|s| m.contains(s))
@@ -315,6 +610,7 @@ impl SearchProvider {
         if let Some(delay) = backoff.next_backoff() {
             tokio::time::sleep(delay).await;
         }
+        telemetry::record_retry();
         b.search(args).await
     }
 
@@ -329,7 +625,11 @@ impl SearchProvider {
     ) -> (String, DaedraResult<SearchResponse>) {
         if let Err(e) = &result {
             Self::record_health_outcome(&health, false);
-            warn!(backend = %name, error = %e, "Backend transient error, retrying once");
+            warn!(
+                backend = %name,
+                error = %privacy::scrub(&e.to_string()),
+                "Backend transient error, retrying once"
+            );
         }
         let retry_result = Self::retry_once(b, args).await;
         match &retry_result {
@@ -350,7 +650,11 @@ impl SearchProvider {
     ) -> (String, DaedraResult<SearchResponse>) {
         if let Err(e) = &result {
             Self::record_health_outcome(&health, false);
-            warn!(backend = %name, error = %e, "Backend error (no retry)");
+            warn!(
+                backend = %name,
+                error = %privacy::scrub(&e.to_string()),
+                "Backend error (no retry)"
+            );
         }
         (name, result)
     }
@@ -364,7 +668,7 @@ impl SearchProvider {
     ) -> (String, DaedraResult<SearchResponse>) {
         let name = b.name().to_string();
 
-        limiters.until_ready(&name, scraper_default).await;
+        telemetry::timed(Phase::Queue, limiters.until_ready(&name, scraper_default)).await;
 
         if let Some(h) = &health {
             if !h.is_available() {
@@ -379,16 +683,18 @@ impl SearchProvider {
             }
         }
 
-        info!(backend = %name, query = %args.query, "Querying backend");
-        let result = b.search(args).await;
+        let rewritten = args_for(b, args);
+        let backend_args = rewritten.as_ref().unwrap_or(args);
+        info!(backend = %name, query = %privacy::loggable(&backend_args.query), "Querying backend");
+        let result = b.search(backend_args).await;
 
-        match &result {
+        let (name, mut result) = match &result {
             Ok(_) => Self::handle_successful_result(name, result, health),
             Err(e) if Self::is_non_retryable(e) => Self::handle_non_retryable(name, result, health),
             Err(e) if Self::is_transient(e) => {
                 Self::handle_transient_error(
                     b,
-                    args,
+                    backend_args,
                     name,
                     result,
                     health,
@@ -398,7 +704,14 @@ impl SearchProvider {
                 .await
             }
             Err(_) => Self::handle_unrecoverable_error(name, result, health),
+        };
+        // Responses report the query as the caller wrote it
+        if rewritten.is_some()
+            && let Ok(response) = &mut result
+        {
+            response.metadata.query = args.query.clone();
         }
+        (name, result)
     }
     /// Aggregate search across ALL available backends.
     ///
@@ -406,9 +719,10 @@ impl SearchProvider {
     /// and interleaves sources for diversity (Wikipedia, StackOverflow, GitHub
     /// results mixed rather than grouped).
 
-    fn collect_queryable_backends(&self) -> Vec<&Box<dyn SearchBackend>> {
+    fn collect_queryable_backends(&self, only: Option<&[String]>) -> Vec<&Box<dyn SearchBackend>> {
         self.backends
             .iter()
+            .filter(|b| only.is_none_or(|names| names.iter().any(|n| n == b.name())))
             .filter(|b| b.is_available())
             .filter(|b| {
                 self.circuit_breakers
@@ -466,13 +780,15 @@ impl SearchProvider {
                 "Backend result"
             );
             match result {
-                Ok(response) if !response.data.is_empty() => {
+                Ok(mut response) if !response.data.is_empty() => {
                     any_success = true;
+                    tag_backend(&mut response.data, &name);
                     by_source.push((name, response.data));
                 }
                 Ok(_) => {}
                 Err(e) => {
-                    warn!(backend = %name, error = %e, "Backend failed");
+                    let error = privacy::scrub(&e.to_string()).into_owned();
+                    warn!(backend = %name, %error, "Backend failed");
                 }
             }
         }
@@ -480,6 +796,29 @@ impl SearchProvider {
         (by_source, any_success, tried)
     }
 
+    /// Pick the first knowledge graph, and the deduplicated "people also ask"
+    /// questions and vertical items, across all backend responses in backend
+    /// priority order.
+    fn collect_answer_boxes(
+        results: &[(String, DaedraResult<SearchResponse>)],
+    ) -> (Option<KnowledgeGraph>, Vec<RelatedQuestion>, Verticals) {
+        let mut knowledge_graph = None;
+        let mut people_also_ask: Vec<RelatedQuestion> = Vec::new();
+        let mut verticals = Verticals::default();
+        for response in results.iter().filter_map(|(_, r)| r.as_ref().ok()) {
+            if knowledge_graph.is_none() {
+                knowledge_graph = response.knowledge_graph.clone();
+            }
+            for q in &response.people_also_ask {
+                if !people_also_ask.iter().any(|seen| seen.question == q.question) {
+                    people_also_ask.push(q.clone());
+                }
+            }
+            verticals.merge(&response.verticals);
+        }
+        (knowledge_graph, people_also_ask, verticals)
+    }
+
     fn take_next_unseen<'a, I>(
         queue: &mut std::iter::Peekable<I>,
         seen: &mut std::collections::HashSet<String>,
@@ -488,7 +827,7 @@ impl SearchProvider {
         I: Iterator<Item = &'a crate::types::SearchResult>,
     {
         while let Some(r) = queue.next() {
-            if seen.insert(r.url.clone()) {
+            if seen.insert(dedup_key(&r.url)) {
                 return Some(r.clone());
             }
         }
@@ -524,12 +863,48 @@ impl SearchProvider {
 
     /// Execute a search across all backends with fallback, rate limiting, and circuit breaker protection.
     pub async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+        self.merge_search(args, None).await
+    }
+
+    /// Like [`search`](Self::search), but only queries the named backends.
+    pub async fn search_merged(
+        &self,
+        args: &SearchArgs,
+        backends: &[String],
+    ) -> DaedraResult<SearchResponse> {
+        self.merge_search(args, Some(backends)).await
+    }
+
+    /// Search with an explicit [`SearchStrategy`] over `backends` (all when empty).
+    pub async fn search_with_strategy(
+        &self,
+        args: &SearchArgs,
+        strategy: SearchStrategy,
+        backends: &[String],
+    ) -> DaedraResult<SearchResponse> {
+        match (strategy, backends.is_empty()) {
+            (SearchStrategy::Merge, true) => self.search(args).await,
+            (SearchStrategy::Merge, false) => self.search_merged(args, backends).await,
+            (SearchStrategy::Fallback, true) => {
+                let all: Vec<String> = self.backends.iter().map(|b| b.name().to_string()).collect();
+                self.search_chain(args, &all).await
+            },
+            (SearchStrategy::Fallback, false) => self.search_chain(args, backends).await,
+        }
+    }
+
+    async fn merge_search(
+        &self,
+        args: &SearchArgs,
+        only: Option<&[String]>,
+    ) -> DaedraResult<SearchResponse> {
         let opts = args.options.clone().unwrap_or_default();
+        opts.validate()?;
         let target_count = opts.num_results;
 
-        self.rate_limiter.until_ready().await;
+        telemetry::timed(Phase::Queue, self.rate_limiter.until_ready()).await;
 
-        let queryable = self.collect_queryable_backends();
+        let queryable = self.collect_queryable_backends(only);
         if queryable.is_empty() {
             let open: Vec<String> = self
                 .circuit_breakers
@@ -544,6 +919,7 @@ impl SearchProvider {
         }
 
         let results = self.execute_concurrent_queries(&queryable, args).await;
+        let (knowledge_graph, people_also_ask, verticals) = Self::collect_answer_boxes(&results);
         let (by_source, any_success, tried) = Self::categorize_results(results);
 
         if !any_success {
@@ -566,7 +942,11 @@ impl SearchProvider {
             )));
         }
 
-        let merged = Self::merge_interleave_results(&by_source, target_count);
+        let mut merged = Self::merge_interleave_results(&by_source, target_count);
+        if opts.rerank != Some(false) {
+            let language = regions::locale(&opts.region).map(|l| l.language);
+            rerank::rerank(&args.query, language.as_deref(), &mut merged);
+        }
         let sources: Vec<String> = by_source.iter().map(|(n, _)| n.clone()).collect();
         info!(
             total = merged.len(),
@@ -575,8 +955,130 @@ impl SearchProvider {
             sources.len()
         );
 
-        Ok(SearchResponse::new(args.query.clone(), merged, &opts))
+        let mut response = SearchResponse::new(args.query.clone(), merged, &opts);
+        response.knowledge_graph = knowledge_graph;
+        response.people_also_ask = people_also_ask;
+        response.verticals = verticals;
+        Ok(response)
+    }
+    /// Search backends one at a time in `chain` order, returning the first
+    /// non-empty response.
+    ///
+    /// A backend that is rate limited, bot-blocked, erroring, circuit-open or
+    /// empty hands over to the next one; invalid arguments stop the chain since
+    /// no backend would accept them. Names not matching a configured backend are
+    /// skipped. The serving backend is recorded in `metadata.served_by`.
+    pub async fn search_chain(
+        &self,
+        args: &SearchArgs,
+        chain: &[String],
+    ) -> DaedraResult<SearchResponse> {
+        if let Some(options) = &args.options {
+            options.validate()?;
+        }
+        telemetry::timed(Phase::Queue, self.rate_limiter.until_ready()).await;
+
+        let mut failures: Vec<String> = Vec::new();
+        for name in chain {
+            let Some(backend) = self
+                .backends
+                .iter()
+                .find(|b| b.name() == name && b.is_available())
+            else {
+                warn!(backend = %name, "Fallback chain names an unknown or unavailable backend");
+                continue;
+            };
+
+            let health = self.circuit_breakers.get(name).cloned();
+            if let Some(h) = health.as_ref().filter(|h| !h.is_available()) {
+                let retry_in = h.cooldown_remaining().unwrap_or_default().as_secs();
+                debug!(backend = %name, retry_in, "Skipping backend with open circuit");
+                failures.push(format!("{name}: circuit open (retry in {retry_in}s)"));
+                continue;
+            }
+            let (name, result) = Self::query_backend(
+                backend.as_ref(),
+                args,
+                health,
+                &self.backend_rate_limits,
+                &self.backend_limiters,
+            )
+            .await;
+
+            match result {
+                Ok(mut response) if !response.data.is_empty() => {
+                    info!(backend = %name, skipped = ?failures, "Fallback chain served");
+                    tag_backend(&mut response.data, &name);
+                    if let Some(opts) = &args.options
+                        && opts.rerank == Some(true)
+                    {
+                        let language = regions::locale(&opts.region).map(|l| l.language);
+                        rerank::rerank(&args.query, language.as_deref(), &mut response.data);
+                    }
+                    response.metadata.served_by = Some(name);
+                    return Ok(response);
+                }
+                Ok(_) => failures.push(format!("{name}: no results")),
+                Err(e @ DaedraError::InvalidArguments(_)) => return Err(e),
+                Err(e) => {
+                    info!(
+                        backend = %name,
+                        error = %privacy::scrub(&e.to_string()),
+                        "Falling through to next backend"
+                    );
+                    failures.push(format!("{name}: {e}"));
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            return Err(DaedraError::SearchError(format!(
+                "Fallback chain [{}] matched no configured backend",
+                chain.join(", ")
+            )));
+        }
+        Err(DaedraError::SearchError(format!(
+            "All backends in the fallback chain failed ({})",
+            failures.join("; ")
+        )))
+    }
+
+    /// Describe, without sending anything, what searching `backends` (all when
+    /// empty) would do: which would be queried, in order, and with what request.
+    pub fn plan(&self, args: &SearchArgs, backends: &[String]) -> Vec<BackendPlan> {
+        let names: Vec<String> = if backends.is_empty() {
+            self.backends.iter().map(|b| b.name().to_string()).collect()
+        } else {
+            backends.to_vec()
+        };
+        names
+            .into_iter()
+            .map(|name| {
+                let backend = self.backends.iter().find(|b| b.name() == name);
+                let health = self.circuit_breakers.get(&name);
+                let skip_reason = match (backend, health) {
+                    (None, _) => Some("not configured".to_string()),
+                    (Some(b), _) if !b.is_available() => Some("unavailable".to_string()),
+                    (_, Some(h)) if !h.is_available() => Some(format!(
+                        "circuit open (retry in {}s)",
+                        h.cooldown_remaining().unwrap_or_default().as_secs()
+                    )),
+                    _ => None,
+                };
+                BackendPlan {
+                    would_query: skip_reason.is_none(),
+                    skip_reason,
+                    circuit: health.map_or(CircuitState::Closed, |h| h.state()),
+                    rate_limit: RateTier::of(&name).describe().to_string(),
+                    request: backend.and_then(|b| {
+                        b.plan_request(args_for(b.as_ref(), args).as_ref().unwrap_or(args))
+                    }),
+                    name,
+                }
+            })
+            .collect()
     }
+
     /// List available backend names.
     pub fn available_backends(&self) -> Vec<&str> {
         self.backends
@@ -666,7 +1168,7 @@ mod tests {
             &DaedraError::BotProtectionDetected
         ));
         assert!(SearchProvider::is_non_retryable(
-            &DaedraError::RateLimitExceeded
+            &DaedraError::RateLimitExceeded { retry_after: None }
         ));
         assert!(SearchProvider::is_non_retryable(&DaedraError::SearchError(
             "HTTP 403 forbidden".to_string()
@@ -674,6 +1176,9 @@ mod tests {
         assert!(SearchProvider::is_non_retryable(&DaedraError::SearchError(
             "CAPTCHA required".to_string()
         )));
+        assert!(SearchProvider::is_non_retryable(&DaedraError::CaptchaChallenged(
+            "duckduckgo".to_string()
+        )));
         assert!(!SearchProvider::is_non_retryable(&DaedraError::Timeout));
     }
 
@@ -689,6 +1194,8 @@ mod tests {
         );
         assert!(SearchProvider::is_transient(&http_err));
         assert!(SearchProvider::is_transient(&DaedraError::Timeout));
+        let json_err = serde_json::from_str::<serde_json::Value>("<html>").unwrap_err();
+        assert!(SearchProvider::is_transient(&DaedraError::JsonError(json_err)));
         assert!(!SearchProvider::is_transient(&DaedraError::SearchError(
             "not transient".to_string()
         )));
@@ -711,9 +1218,10 @@ mod tests {
             description: "desc".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Other,
-                source: "test".to_string(),
+                source: "test".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }
     }
@@ -750,6 +1258,89 @@ mod tests {
         assert_eq!(merged[1].url, "https://other");
     }
 
+    #[test]
+    fn test_collect_answer_boxes_first_graph_and_dedup_questions() {
+        use crate::types::{KnowledgeGraph, RelatedQuestion, SearchOptions, VerticalItem};
+        let question = |q: &str| RelatedQuestion {
+            question: q.to_string(),
+            snippet: None,
+            title: None,
+            url: None,
+        };
+        let mut serper = SearchResponse::new("q".into(), vec![], &SearchOptions::default());
+        serper.knowledge_graph = Some(KnowledgeGraph {
+            title: "Rust".to_string(),
+            ..Default::default()
+        });
+        serper.people_also_ask = vec![question("Is Rust fast?"), question("Is Rust safe?")];
+        let mut other = SearchResponse::new("q".into(), vec![], &SearchOptions::default());
+        other.knowledge_graph = Some(KnowledgeGraph {
+            title: "Rust (game)".to_string(),
+            ..Default::default()
+        });
+        other.people_also_ask = vec![question("Is Rust safe?"), question("Who made Rust?")];
+        let story = |url: &str| VerticalItem {
+            title: "Rust news".to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        };
+        serper.verticals.news = vec![story("https://news/1")];
+        other.verticals.news = vec![story("https://news/1"), story("https://news/2")];
+
+        let results = vec![
+            ("bing".to_string(), Err(DaedraError::Timeout)),
+            ("serper".to_string(), Ok(serper)),
+            ("tavily".to_string(), Ok(other)),
+        ];
+        let (kg, paa, verticals) = SearchProvider::collect_answer_boxes(&results);
+        assert_eq!(kg.unwrap().title, "Rust");
+        let questions: Vec<_> = paa.iter().map(|q| q.question.as_str()).collect();
+        assert_eq!(questions, ["Is Rust fast?", "Is Rust safe?", "Who made Rust?"]);
+        let news: Vec<_> = verticals.news.iter().map(|n| n.url.as_str()).collect();
+        assert_eq!(news, ["https://news/1", "https://news/2"]);
+    }
+
+    #[test]
+    fn test_with_serper_replaces_env_backend() {
+        let provider = SearchProvider::new(vec![
+            Box::new(super::super::serper::SerperBackend::new("env".to_string())),
+            Box::new(super::super::wiby::WibyBackend::new()),
+        ])
+        .with_serper("configured");
+        assert_eq!(provider.available_backends(), vec!["serper", "wiby"]);
+    }
+
+    #[test]
+    fn test_plan_reports_circuit_and_redacts_keys() {
+        let provider = SearchProvider::new(vec![
+            Box::new(super::super::mojeek::MojeekBackend::new("secret".to_string())),
+            Box::new(super::super::wiby::WibyBackend::new()),
+        ]);
+        for _ in 0..3 {
+            provider.circuit_breakers["wiby"].record_failure();
+        }
+        let args = SearchArgs {
+            query: "rust".to_string(),
+            options: None,
+        };
+
+        let plan = provider.plan(&args, &[]);
+        assert_eq!(plan.len(), 2);
+        assert!(plan[0].would_query);
+        assert_eq!(plan[0].rate_limit, RateTier::Api.describe());
+        let request = plan[0].request.as_ref().unwrap();
+        assert_eq!(request.params["api_key"], crate::types::REDACTED);
+        assert_eq!(request.params["q"], "rust");
+
+        assert!(!plan[1].would_query);
+        assert_eq!(plan[1].circuit, CircuitState::Open);
+        assert!(plan[1].skip_reason.as_deref().unwrap().starts_with("circuit open"));
+
+        let plan = provider.plan(&args, &["wiby".to_string(), "bing".to_string()]);
+        assert_eq!(plan[1].skip_reason.as_deref(), Some("not configured"));
+        assert!(plan[1].request.is_none());
+    }
+
     #[test]
     fn test_merge_interleave_results_respects_target() {
         let results: Vec<_> = (0..5)
@@ -914,6 +1505,336 @@ mod tests {
         }
     }
 
+    /// Backend that always returns the same scripted outcome and counts calls.
+    struct ScriptedBackend {
+        name: &'static str,
+        outcome: fn() -> DaedraResult<Vec<crate::types::SearchResult>>,
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl ScriptedBackend {
+        fn boxed(
+            name: &'static str,
+            outcome: fn() -> DaedraResult<Vec<crate::types::SearchResult>>,
+        ) -> (Box<dyn SearchBackend>, Arc<std::sync::atomic::AtomicUsize>) {
+            let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let backend = Self {
+                name,
+                outcome,
+                calls: Arc::clone(&calls),
+            };
+            (Box::new(backend), calls)
+        }
+    }
+
+    #[async_trait]
+    impl SearchBackend for ScriptedBackend {
+        async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let opts = args.options.clone().unwrap_or_default();
+            Ok(SearchResponse::new(args.query.clone(), (self.outcome)()?, &opts))
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn chain(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn query() -> SearchArgs {
+        SearchArgs {
+            query: "rust".to_string(),
+            options: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_chain_falls_through_on_rate_limit_and_bot_protection() {
+        let (limited, _) = ScriptedBackend::boxed("limited", || Err(DaedraError::RateLimitExceeded { retry_after: None }));
+        let (blocked, _) =
+            ScriptedBackend::boxed("blocked", || Err(DaedraError::BotProtectionDetected));
+        let (good, _) =
+            ScriptedBackend::boxed("good", || Ok(vec![test_search_result("https://ok", "ok")]));
+        let (unused, unused_calls) =
+            ScriptedBackend::boxed("unused", || Ok(vec![test_search_result("https://x", "x")]));
+        let provider = SearchProvider::new(vec![unused, good, blocked, limited]);
+
+        let response = provider
+            .search_chain(&query(), &chain(&["limited", "blocked", "good", "unused"]))
+            .await
+            .unwrap();
+        assert_eq!(response.metadata.served_by.as_deref(), Some("good"));
+        assert_eq!(response.data[0].url, "https://ok");
+        assert_eq!(unused_calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    /// Backend matching keywords only, recording the queries it is sent.
+    struct KeywordsBackend {
+        seen: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl SearchBackend for KeywordsBackend {
+        async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+            self.seen.lock().unwrap().push(args.query.clone());
+            let results = vec![test_search_result("https://ok", "ok")];
+            let opts = crate::types::SearchOptions::default();
+            Ok(SearchResponse::new(args.query.clone(), results, &opts))
+        }
+
+        fn name(&self) -> &str {
+            "keywords"
+        }
+
+        fn query_dialect(&self) -> QueryDialect {
+            QueryDialect::Keywords
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backends_get_queries_in_their_dialect() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let backend = KeywordsBackend {
+            seen: Arc::clone(&seen),
+        };
+        let provider = SearchProvider::new(vec![Box::new(backend)]);
+        let args = SearchArgs {
+            query: r#""borrow checker" site:doc.rust-lang.org -nightly"#.to_string(),
+            options: None,
+        };
+
+        let response = provider.search_chain(&args, &chain(&["keywords"])).await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["borrow checker"]);
+        assert_eq!(response.metadata.query, args.query);
+
+        let wikipedia = Box::new(crate::tools::wikipedia::WikipediaBackend::new());
+        let wikipedia = SearchProvider::new(vec![wikipedia]);
+        let plan = wikipedia.plan(&args, &[]).remove(0).request.unwrap();
+        assert_eq!(plan.params["search"], "borrow checker");
+    }
+
+    #[tokio::test]
+    async fn test_search_chain_skips_empty_and_unknown_backends() {
+        let (empty, _) = ScriptedBackend::boxed("empty", || Ok(vec![]));
+        let (good, _) =
+            ScriptedBackend::boxed("good", || Ok(vec![test_search_result("https://ok", "ok")]));
+        let provider = SearchProvider::new(vec![empty, good]);
+
+        let response = provider
+            .search_chain(&query(), &chain(&["missing", "empty", "good"]))
+            .await
+            .unwrap();
+        assert_eq!(response.metadata.served_by.as_deref(), Some("good"));
+    }
+
+    #[tokio::test]
+    async fn test_search_chain_stops_on_invalid_arguments() {
+        let (bad, _) = ScriptedBackend::boxed("bad", || {
+            Err(DaedraError::InvalidArguments("empty query".to_string()))
+        });
+        let (good, good_calls) =
+            ScriptedBackend::boxed("good", || Ok(vec![test_search_result("https://ok", "ok")]));
+        let provider = SearchProvider::new(vec![bad, good]);
+
+        let err = provider
+            .search_chain(&query(), &chain(&["bad", "good"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DaedraError::InvalidArguments(_)));
+        assert_eq!(good_calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_chain_reports_every_failure() {
+        let (limited, _) = ScriptedBackend::boxed("limited", || Err(DaedraError::RateLimitExceeded { retry_after: None }));
+        let (empty, _) = ScriptedBackend::boxed("empty", || Ok(vec![]));
+        let provider = SearchProvider::new(vec![limited, empty]);
+
+        let err = provider
+            .search_chain(&query(), &chain(&["limited", "empty"]))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("limited"), "{err}");
+        assert!(err.contains("empty: no results"), "{err}");
+
+        let err = provider
+            .search_chain(&query(), &chain(&["nope"]))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("matched no configured backend"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_search_chain_skips_open_circuit() {
+        let (blocked, blocked_calls) =
+            ScriptedBackend::boxed("blocked", || Err(DaedraError::BotProtectionDetected));
+        let (good, _) =
+            ScriptedBackend::boxed("good", || Ok(vec![test_search_result("https://ok", "ok")]));
+        let provider = SearchProvider::new(vec![blocked, good]).with_circuit_breaker(
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                cooldown: Duration::from_secs(60),
+            },
+        );
+        let order = chain(&["blocked", "good"]);
+
+        for _ in 0..3 {
+            let response = provider.search_chain(&query(), &order).await.unwrap();
+            assert_eq!(response.metadata.served_by.as_deref(), Some("good"));
+        }
+        assert_eq!(blocked_calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let status = provider.backend_status();
+        assert_eq!(status[0].name, "blocked");
+        assert_eq!(status[0].state, CircuitState::Open);
+        assert_eq!(status[0].consecutive_failures, 2);
+        assert!(status[0].retry_in_secs.is_some_and(|s| s > 0 && s <= 60));
+        assert_eq!(status[1].state, CircuitState::Closed);
+        assert_eq!(status[1].retry_in_secs, None);
+    }
+
+    #[test]
+    fn test_backend_health_state_transitions() {
+        let health = BackendHealth::new(2, Duration::from_millis(50));
+        assert_eq!(health.state(), CircuitState::Closed);
+        health.record_failure();
+        assert_eq!(health.state(), CircuitState::Closed);
+        assert_eq!(health.consecutive_failures(), 1);
+        health.record_failure();
+        assert_eq!(health.state(), CircuitState::Open);
+        assert!(health.cooldown_remaining().is_some());
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(health.state(), CircuitState::HalfOpen);
+        assert_eq!(health.cooldown_remaining(), None);
+        health.record_success();
+        assert_eq!(health.state(), CircuitState::Closed);
+        assert_eq!(health.consecutive_failures(), 0);
+    }
+
+    #[test]
+    fn test_with_circuit_breaker_survives_backend_changes() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 7,
+            cooldown: Duration::from_secs(5),
+        };
+        let provider = SearchProvider::new(vec![]).with_circuit_breaker(config).with_serper("key");
+        assert_eq!(provider.circuit_breaker_config, config);
+        assert_eq!(provider.backend_status()[0].name, "serper");
+        assert!(provider.backend_status()[0].requires_api_key);
+    }
+
+    #[test]
+    fn test_dedup_key_normalizes_urls() {
+        let key = dedup_key("https://www.Example.com/docs/?utm_source=x&page=2#intro");
+        assert_eq!(key, "example.com/docs?page=2");
+        assert_eq!(dedup_key("http://example.com/docs?page=2&fbclid=abc"), key);
+        assert_ne!(dedup_key("https://example.com/docs?page=3"), key);
+        assert_ne!(dedup_key("https://example.com:8443/docs?page=2"), key);
+        assert_eq!(dedup_key("not a url"), "not a url");
+    }
+
+    #[tokio::test]
+    async fn test_merge_dedupes_normalized_urls_and_attributes_backend() {
+        let (first, _) = ScriptedBackend::boxed("first", || {
+            Ok(vec![
+                test_search_result("https://www.example.com/a/", "a"),
+                test_search_result("https://example.com/b", "b"),
+            ])
+        });
+        let (second, _) = ScriptedBackend::boxed("second", || {
+            Ok(vec![
+                test_search_result("http://example.com/a?utm_medium=feed", "a again"),
+                test_search_result("https://example.com/c", "c"),
+            ])
+        });
+        let provider = SearchProvider::new(vec![first, second]);
+
+        let response = provider.search(&query()).await.unwrap();
+        let urls: Vec<&str> = response.data.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://www.example.com/a/", "https://example.com/c", "https://example.com/b"]
+        );
+        let backends: Vec<Option<&str>> =
+            response.data.iter().map(|r| r.metadata.backend.as_deref()).collect();
+        assert_eq!(backends, vec![Some("first"), Some("second"), Some("first")]);
+    }
+
+    #[tokio::test]
+    async fn test_search_with_strategy() {
+        let (first, first_calls) =
+            ScriptedBackend::boxed("first", || Ok(vec![test_search_result("https://a", "a")]));
+        let (second, second_calls) =
+            ScriptedBackend::boxed("second", || Ok(vec![test_search_result("https://b", "b")]));
+        let (third, third_calls) =
+            ScriptedBackend::boxed("third", || Ok(vec![test_search_result("https://c", "c")]));
+        let provider = SearchProvider::new(vec![first, second, third]);
+        let calls = |c: &Arc<std::sync::atomic::AtomicUsize>| {
+            c.load(std::sync::atomic::Ordering::SeqCst)
+        };
+
+        let response = provider
+            .search_with_strategy(&query(), SearchStrategy::Fallback, &[])
+            .await
+            .unwrap();
+        assert_eq!(response.metadata.served_by.as_deref(), Some("first"));
+        assert_eq!(response.data[0].metadata.backend.as_deref(), Some("first"));
+        assert_eq!((calls(&first_calls), calls(&second_calls)), (1, 0));
+
+        let response = provider
+            .search_with_strategy(&query(), SearchStrategy::Merge, &chain(&["second", "third"]))
+            .await
+            .unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(calls(&first_calls), 1, "merge must stay within the named backends");
+        assert_eq!((calls(&second_calls), calls(&third_calls)), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_rerank_on_merge_and_on_request() {
+        let scripted = || {
+            let (first, _) = ScriptedBackend::boxed("first", || {
+                Ok(vec![
+                    test_search_result("https://a", "Gardening tips"),
+                    test_search_result("https://b", "Rust ownership explained"),
+                ])
+            });
+            let (second, _) = ScriptedBackend::boxed("second", || {
+                Ok(vec![test_search_result("https://c", "Cooking")])
+            });
+            SearchProvider::new(vec![first, second])
+        };
+        let args = |rerank: Option<bool>| SearchArgs {
+            query: "rust".to_string(),
+            options: Some(crate::types::SearchOptions {
+                rerank,
+                ..Default::default()
+            }),
+        };
+        let urls = |r: &SearchResponse| r.data.iter().map(|d| d.url.clone()).collect::<Vec<_>>();
+
+        let merged = scripted().search(&args(None)).await.unwrap();
+        assert_eq!(urls(&merged), ["https://b", "https://a", "https://c"]);
+        assert!(merged.data[0].metadata.relevance.unwrap() > 0.0);
+        let positions: Vec<usize> = merged.data.iter().map(|d| d.metadata.position).collect();
+        assert_eq!(positions, [2, 1, 1], "each result keeps its rank on its backend's page");
+
+        let unranked = scripted().search(&args(Some(false))).await.unwrap();
+        assert_eq!(urls(&unranked), ["https://a", "https://c", "https://b"]);
+        assert_eq!(unranked.data[0].metadata.relevance, None);
+
+        let chain = chain(&["first"]);
+        let fallback = scripted().search_chain(&args(None), &chain).await.unwrap();
+        assert_eq!(urls(&fallback), ["https://a", "https://b"]);
+        let fallback = scripted().search_chain(&args(Some(true)), &chain).await.unwrap();
+        assert_eq!(urls(&fallback), ["https://b", "https://a"]);
+    }
+
     #[test]
     fn test_is_transient_rate_limit() {
         assert!(SearchProvider::is_transient(&DaedraError::SearchError(
@@ -4,20 +4,37 @@
 //! Google/DDG for datacenter IPs. Default backend for self-hosted use.
 
 use super::backend::SearchBackend;
+use crate::fingerprint::Rotation;
+use crate::privacy::{self, ClientProfile};
+use crate::regions;
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::text;
 use crate::types::{
-    ContentType, DaedraError, DaedraResult, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use reqwest::Client;
 use scraper::{ElementRef, Html, Selector};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{info, warn};
 
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
 const BING_URL: &str = "https://www.bing.com/search";
 
+/// Search parameters; the region becomes Bing's `cc` country and `setlang` language.
+fn query_params(args: &SearchArgs, opts: &SearchOptions) -> Vec<(&'static str, String)> {
+    let mut params = vec![("q", args.query.clone()), ("count", opts.num_results.to_string())];
+    if let Some(locale) = regions::locale(&opts.region) {
+        if let Some(country) = locale.country_code() {
+            params.push(("cc", country));
+        }
+        params.push(("setlang", locale.language));
+    }
+    params
+}
+
 lazy_static! {
     static ref RESULT_SELECTOR: Selector = Selector::parse("li.b_algo").unwrap();
     static ref TITLE_SELECTOR: Selector = Selector::parse("h2 a").unwrap();
@@ -44,14 +61,15 @@ fn extract_bing_result(element: &ElementRef) -> Option<SearchResult> {
         .unwrap_or_default();
 
     Some(SearchResult {
-        title: title.trim().to_string(),
+        title: text::clean(&title),
         url: url.to_string(),
-        description: description.trim().to_string(),
+        description: text::clean(&description),
         metadata: ResultMetadata {
             content_type: ContentType::Other,
-            source: "bing".to_string(),
+            source: "bing".into(),
             favicon: None,
             published_date: None,
+            ..Default::default()
         },
     })
 }
@@ -59,20 +77,48 @@ fn extract_bing_result(element: &ElementRef) -> Option<SearchResult> {
 /// Bing HTML scraping backend — parses search results from bing.com SERP pages.
 pub struct BingBackend {
     client: Client,
+    profile: ClientProfile,
+    rotation: Arc<Rotation>,
+}
+
+/// The backend's HTTP client, sending the headers of `profile` and by default
+/// the `User-Agent` of `rotation`
+fn http_client(profile: ClientProfile, rotation: &Rotation) -> Client {
+    privacy::client_builder(profile, Some(rotation.user_agent()))
+        .timeout(Duration::from_secs(30))
+        .gzip(true)
+        .brotli(true)
+        .zstd(true)
+        .deflate(true)
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .build()
+        .expect("Failed to build HTTP client")
 }
 
 impl BingBackend {
     /// Create a new Bing backend instance.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent(USER_AGENT)
-            .timeout(Duration::from_secs(30))
-            .gzip(true)
-            .brotli(true)
-            .redirect(reqwest::redirect::Policy::limited(5))
-            .build()
-            .expect("Failed to build HTTP client");
-        Self { client }
+        let profile = ClientProfile::Standard;
+        let rotation = Arc::default();
+        Self {
+            client: http_client(profile, &rotation),
+            profile,
+            rotation,
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = http_client(profile, &self.rotation);
+        self.profile = profile;
+        self
+    }
+
+    /// Take browser profiles from `rotation` (the built-in pool by default)
+    pub fn with_browser_profiles(mut self, rotation: Arc<Rotation>) -> Self {
+        self.client = http_client(self.profile, &rotation);
+        self.rotation = rotation;
+        self
     }
 
     fn parse_results(&self, html: &str, max_results: usize) -> Vec<SearchResult> {
@@ -90,13 +136,13 @@ impl SearchBackend for BingBackend {
     async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
         let opts = args.options.clone().unwrap_or_default();
 
-        let resp = self.client
+        let language = regions::accept_language(&opts.region);
+        let resp = self
+            .client
             .get(BING_URL)
-            .query(&[
-                ("q", args.query.as_str()),
-                ("count", &opts.num_results.to_string()),
-            ])
-            .send()
+            .query(&query_params(args, &opts))
+            .headers(self.rotation.next_headers(self.profile, language.as_deref()))
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
@@ -105,7 +151,7 @@ impl SearchBackend for BingBackend {
             return Err(DaedraError::SearchError(format!("Bing status {}", resp.status())));
         }
 
-        let html = resp.text().await.map_err(DaedraError::HttpError)?;
+        let html = resp.text_counted().await.map_err(DaedraError::HttpError)?;
         let results = self.parse_results(&html, opts.num_results);
 
         if results.is_empty() {
@@ -117,6 +163,11 @@ impl SearchBackend for BingBackend {
     }
 
     fn name(&self) -> &str { "bing" }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        Some(PlannedRequest::get(BING_URL, &query_params(args, &opts)))
+    }
 }
 
 #[cfg(test)]
@@ -172,6 +223,23 @@ mod tests {
         assert!(b.parse_results("<html></html>", 10).is_empty());
     }
 
+    #[test]
+    fn test_query_params_carry_region() {
+        let args = SearchArgs {
+            query: "rust".to_string(),
+            options: None,
+        };
+        let opts = SearchOptions {
+            region: "ch-fr".to_string(),
+            ..Default::default()
+        };
+        let params = query_params(&args, &opts);
+        assert!(params.contains(&("cc", "ch".to_string())));
+        assert!(params.contains(&("setlang", "fr".to_string())));
+        let worldwide = query_params(&args, &SearchOptions::default());
+        assert!(!worldwide.iter().any(|(k, _)| *k == "cc" || *k == "setlang"));
+    }
+
     #[test]
     fn test_bing_backend_name() {
         assert_eq!(BingBackend::new().name(), "bing");
@@ -0,0 +1,319 @@
+//! The cookie jar page fetches share.
+//!
+//! A [`CookieJar`] keeps the cookies sites set, so a consent redirect, a
+//! session-gated page or a crawl's later pages see the cookies the first
+//! response set, as a browser would. Cookies follow the usual rules for
+//! domain, path, `Secure` and expiry.
+//!
+//! A jar opened with [`CookieJar::open`] reads its file back and writes its
+//! persistent cookies there after a response changes them, so sessions
+//! survive restarts. Session cookies, those without `Expires` or `Max-Age`,
+//! are never written, and setting only those leaves the file alone. Fetches
+//! never wait on the disk: a writer thread saves the jar, once for all the
+//! changes made within [`SAVE_DELAY`]. On Unix the file is readable by its
+//! owner only.
+
+use crate::types::{DaedraError, DaedraResult};
+use cookie_store::{CookieStore, RawCookie};
+use reqwest::header::HeaderValue;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Longest a change waits to be saved, so a burst of responses setting
+/// cookies is written once
+pub const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Cookies set by the sites fetched from, optionally kept in a file
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    store: Arc<RwLock<CookieStore>>,
+    file: Option<Arc<JarFile>>,
+    /// Queues saves for the writer thread, for a jar kept in a file
+    saves: Option<Sender<Command>>,
+}
+
+/// The file a jar is kept in
+#[derive(Debug)]
+struct JarFile {
+    path: PathBuf,
+    store: Arc<RwLock<CookieStore>>,
+    /// Held while the file is written, so saves don't interleave
+    saving: Mutex<()>,
+}
+
+/// A request to the writer thread
+#[derive(Debug)]
+enum Command {
+    Save,
+    Flush(oneshot::Sender<()>),
+}
+
+impl CookieJar {
+    /// An empty jar kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A jar kept in `path`, starting with the unexpired cookies saved there;
+    /// empty when the file doesn't exist yet. An error when it can't be read.
+    pub fn open(path: impl Into<PathBuf>) -> DaedraResult<Self> {
+        let path = path.into();
+        let store = match File::open(&path) {
+            Ok(file) => cookie_store::serde::json::load(BufReader::new(file))
+                .map_err(|e| cookie_file_error(&path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CookieStore::default(),
+            Err(e) => return Err(cookie_file_error(&path, e)),
+        };
+        debug!(path = %path.display(), cookies = store.iter_unexpired().count(), "Cookie jar loaded");
+        let store = Arc::new(RwLock::new(store));
+        let file = Arc::new(JarFile {
+            path,
+            store: Arc::clone(&store),
+            saving: Mutex::new(()),
+        });
+        let (saves, commands) = mpsc::channel();
+        let writer = Arc::clone(&file);
+        std::thread::Builder::new()
+            .name("daedra-cookie-writer".to_string())
+            .spawn(move || writer.run(commands))
+            .map_err(|e| cookie_file_error(&file.path, e))?;
+        Ok(Self {
+            store,
+            file: Some(file),
+            saves: Some(saves),
+        })
+    }
+
+    /// The file the jar is kept in, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// Number of unexpired cookies in the jar
+    pub fn len(&self) -> usize {
+        self.read().iter_unexpired().count()
+    }
+
+    /// Whether the jar holds no unexpired cookies
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cookie, from the file too
+    pub fn clear(&self) -> DaedraResult<()> {
+        self.write().clear();
+        self.save()
+    }
+
+    /// Write the jar's persistent cookies to its file now, replacing it
+    /// whole; nothing to do for a jar kept in memory
+    pub fn save(&self) -> DaedraResult<()> {
+        self.file.as_ref().map_or(Ok(()), |file| file.save())
+    }
+
+    /// Wait until the changes made so far are saved, for a jar kept in a file
+    pub async fn flush(&self) {
+        let Some(saves) = &self.saves else {
+            return;
+        };
+        let (done, saved) = oneshot::channel();
+        if saves.send(Command::Flush(done)).is_ok() {
+            let _ = saved.await;
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, CookieStore> {
+        self.store.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, CookieStore> {
+        self.store.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl JarFile {
+    /// Save the jar on each change, batching those within [`SAVE_DELAY`],
+    /// until the jar is dropped
+    fn run(&self, commands: Receiver<Command>) {
+        while let Ok(first) = commands.recv() {
+            let deadline = Instant::now() + SAVE_DELAY;
+            let mut waiting = Vec::new();
+            let mut changed = false;
+            let mut next = Some(first);
+            while let Some(command) = next.take() {
+                match command {
+                    Command::Save => changed = true,
+                    Command::Flush(done) => {
+                        waiting.push(done);
+                        break;
+                    },
+                }
+                next = match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) => commands.recv_timeout(left).ok(),
+                    None => commands.try_recv().ok(),
+                };
+            }
+            if changed && let Err(e) = self.save() {
+                warn!(error = %e, "Could not save cookies");
+            }
+            for done in waiting {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    /// Write the persistent cookies to the file, replacing it whole
+    fn save(&self) -> DaedraResult<()> {
+        let _saving = self.saving.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp = self.path.with_extension("tmp");
+        let write = || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let mut options = OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let mut writer = BufWriter::new(options.open(&tmp)?);
+            let store = self.store.read().unwrap_or_else(|e| e.into_inner());
+            cookie_store::serde::json::save(&store, &mut writer)?;
+            drop(store);
+            writer.flush()?;
+            fs::rename(&tmp, &self.path)?;
+            Ok(())
+        };
+        write().map_err(|e| cookie_file_error(&self.path, e))
+    }
+}
+
+/// Whether `cookie` is one the file keeps
+fn is_persistent(cookie: &RawCookie<'_>) -> bool {
+    cookie.max_age().is_some() || cookie.expires().is_some()
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies: Vec<RawCookie<'static>> = cookie_headers
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| RawCookie::parse(value).ok())
+            .map(RawCookie::into_owned)
+            .collect();
+        if cookies.is_empty() {
+            return;
+        }
+        let mut store = self.write();
+        // A session cookie may replace a persistent one, which the file then drops
+        let changes_file = cookies.iter().any(|cookie| {
+            is_persistent(cookie)
+                || store.iter_any().any(|kept| kept.name() == cookie.name() && kept.is_persistent())
+        });
+        store.store_response_cookies(cookies.into_iter(), url);
+        drop(store);
+        if let Some(saves) = &self.saves
+            && changes_file
+            && saves.send(Command::Save).is_err()
+        {
+            warn!("Cookie writer has stopped; cookies not saved");
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .read()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+/// An error reading or writing the cookie file at `path`
+fn cookie_file_error(path: &Path, e: impl std::fmt::Display) -> DaedraError {
+    DaedraError::InvalidArguments(format!("Cannot use cookie file {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::cookie::CookieStore as _;
+
+    fn set(jar: &CookieJar, url: &str, cookies: &[&str]) {
+        let values: Vec<HeaderValue> =
+            cookies.iter().map(|c| HeaderValue::from_str(c).unwrap()).collect();
+        jar.set_cookies(&mut values.iter(), &Url::parse(url).unwrap());
+    }
+
+    fn sent(jar: &CookieJar, url: &str) -> Option<String> {
+        jar.cookies(&Url::parse(url).unwrap()).map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_cookies_follow_domain_and_path() {
+        let jar = CookieJar::new();
+        let cookies = ["session=abc; Path=/app", "consent=yes; Domain=example.com; Path=/"];
+        set(&jar, "https://www.example.com/app/login", &cookies);
+        let sent_to_app = sent(&jar, "https://www.example.com/app/home").unwrap();
+        let mut sent_to_app: Vec<&str> = sent_to_app.split("; ").collect();
+        sent_to_app.sort();
+        assert_eq!(sent_to_app, ["consent=yes", "session=abc"]);
+        assert_eq!(sent(&jar, "https://docs.example.com/").as_deref(), Some("consent=yes"));
+        assert_eq!(sent(&jar, "https://example.org/"), None);
+        assert_eq!(jar.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_cookies_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        let jar = CookieJar::open(&path).unwrap();
+        assert!(jar.is_empty());
+        set(&jar, "https://wiki.example.com/", &["remember=me; Max-Age=3600", "session=abc"]);
+        jar.flush().await;
+
+        let reopened = CookieJar::open(&path).unwrap();
+        assert_eq!(reopened.path(), Some(path.as_path()));
+        assert_eq!(sent(&reopened, "https://wiki.example.com/").as_deref(), Some("remember=me"));
+
+        reopened.clear().unwrap();
+        assert!(CookieJar::open(&path).unwrap().is_empty());
+
+        fs::write(&path, "not json").unwrap();
+        let err = CookieJar::open(&path).unwrap_err();
+        assert!(err.to_string().contains("Cannot use cookie file"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_saves_skip_session_cookies_and_keep_the_file_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        let jar = CookieJar::open(&path).unwrap();
+        set(&jar, "https://wiki.example.com/", &["session=abc"]);
+        jar.flush().await;
+        assert!(!path.exists());
+
+        for visit in 0..20 {
+            set(&jar, "https://wiki.example.com/", &[&format!("visits={visit}; Max-Age=3600")]);
+        }
+        jar.flush().await;
+        let reopened = CookieJar::open(&path).unwrap();
+        assert_eq!(sent(&reopened, "https://wiki.example.com/").as_deref(), Some("visits=19"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        // A session cookie taking a persistent one's place drops it from the file
+        set(&jar, "https://wiki.example.com/", &["visits=20"]);
+        jar.flush().await;
+        assert!(CookieJar::open(&path).unwrap().is_empty());
+    }
+}
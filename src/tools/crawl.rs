@@ -17,10 +17,12 @@
 //! This is the "deep" half of the `broad search + deep crawl` MIT stack —
 //! see `reference_smartcrawler_vs_daedra.md` for the design rationale.
 
-use crate::tools::fetch::fetch_page;
+use crate::privacy;
+use crate::telemetry::{self, CountedRequest, CountedResponse};
+use crate::tools::fetch::FetchClient;
 use crate::types::{
-    CrawlArgs, CrawlError, CrawlResult, CrawlSummary, CrawledPage, DaedraError, DaedraResult,
-    PageContent, VisitPageArgs,
+    CrawlArgs, CrawlError, CrawlPlan, CrawlResult, CrawlSummary, CrawledPage, DaedraError,
+    DaedraResult, PageContent, PlannedRequest, VisitPageArgs,
 };
 use lazy_static::lazy_static;
 use reqwest::Client;
@@ -57,7 +59,7 @@ fn is_sitemap_size_ok(body: &str) -> bool {
 }
 
 async fn read_sitemap_body(resp: reqwest::Response, url: &Url) -> Option<String> {
-    match resp.text().await {
+    match resp.text_counted().await {
         Ok(b) if is_sitemap_size_ok(&b) => Some(b),
         Ok(_) => {
             warn!(
@@ -76,8 +78,7 @@ async fn read_sitemap_body(resp: reqwest::Response, url: &Url) -> Option<String>
 async fn fetch_sitemap_body(client: &Client, url: &Url) -> Option<String> {
     let resp = match client
         .get(url.clone())
-        .header("User-Agent", USER_AGENT)
-        .send()
+        .send_counted()
         .await
     {
         Ok(r) => r,
@@ -229,11 +230,10 @@ pub(crate) fn extract_same_origin_links(doc: &Html, root: &Url, cap: usize) -> V
 async fn discover_via_anchors(client: &Client, root: &Url, cap: usize) -> DaedraResult<Vec<Url>> {
     let body = client
         .get(root.clone())
-        .header("User-Agent", USER_AGENT)
-        .send()
+        .send_counted()
         .await
         .map_err(|e| DaedraError::FetchError(format!("anchor discovery GET {} failed: {}", root, e)))?
-        .text()
+        .text_counted()
         .await
         .map_err(|e| DaedraError::FetchError(format!("anchor discovery body {} failed: {}", root, e)))?;
 
@@ -264,8 +264,10 @@ async fn discover_urls(
     }
 }
 
-/// Spawn semaphore-guarded fetch tasks for each candidate URL.
+/// Spawn semaphore-guarded fetch tasks for each candidate URL. The tasks
+/// share `fetcher`, so the site's robots.txt is read once.
 async fn fetch_candidates_concurrently(
+    fetcher: FetchClient,
     candidates: Vec<Url>,
     concurrency: usize,
 ) -> Vec<tokio::task::JoinHandle<Option<(String, DaedraResult<PageContent>)>>> {
@@ -273,16 +275,18 @@ async fn fetch_candidates_concurrently(
     let mut handles = Vec::with_capacity(candidates.len());
     for url in candidates {
         let sem = Arc::clone(&sem);
+        let fetcher = fetcher.clone();
         let args = VisitPageArgs {
             url: url.to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
-        handles.push(tokio::spawn(async move {
+        handles.push(tokio::spawn(telemetry::propagate(async move {
             let _permit = sem.acquire_owned().await.ok()?;
-            let result = fetch_page(&args).await;
+            let result = fetcher.fetch(&args).await;
             Some((args.url, result))
-        }));
+        })));
     }
     handles
 }
@@ -322,24 +326,57 @@ async fn collect_crawl_results(
     (pages, errors)
 }
 
+fn parse_root(root_url: &str) -> DaedraResult<Url> {
+    Url::parse(root_url)
+        .map_err(|e| DaedraError::InvalidArguments(format!("invalid root_url: {}", e)))
+}
+
+/// Describe what [`crawl_site`] would do with `args`, without sending anything.
+pub fn plan_crawl(args: &CrawlArgs) -> DaedraResult<CrawlPlan> {
+    let root = parse_root(&args.root_url)?;
+    let (max_pages, concurrency) = clamp_crawl_args(args.max_pages, args.concurrency);
+    let mut discovery: Vec<PlannedRequest> = SITEMAP_CANDIDATES
+        .iter()
+        .filter_map(|path| root.join(path).ok())
+        .map(|url| PlannedRequest::get::<&str, &str>(url.as_str(), &[]))
+        .collect();
+    discovery.push(
+        PlannedRequest::get::<&str, &str>(root.as_str(), &[])
+            .with_note("no sitemap found: follow same-origin links on the root page"),
+    );
+    Ok(CrawlPlan {
+        root_url: root.to_string(),
+        max_pages,
+        concurrency,
+        discovery,
+    })
+}
+
 /// Walk a site deeply, returning extracted page content for each URL.
 ///
 /// The caller supplies a URL and a page budget. daedra finds the URLs
 /// (sitemap first, HTML anchors second), fetches them under a concurrency
 /// semaphore, converts each to markdown via the existing `visit_page`
 /// pipeline, and returns a structured result with per-URL success/error
-/// buckets.
+/// buckets. Pages the site's robots.txt disallows land in the error bucket.
 pub async fn crawl_site(args: CrawlArgs) -> DaedraResult<CrawlResult> {
-    let root = Url::parse(&args.root_url)
-        .map_err(|e| DaedraError::InvalidArguments(format!("invalid root_url: {}", e)))?;
+    crawl_site_with(args, FetchClient::new()?).await
+}
+
+/// [`crawl_site`], fetching pages with `fetcher` so its robots.txt and domain
+/// policy apply. The root must be a host the policy permits.
+pub async fn crawl_site_with(args: CrawlArgs, fetcher: FetchClient) -> DaedraResult<CrawlResult> {
+    let root = parse_root(&args.root_url)?;
+    fetcher.domain_policy().check(root.as_str())?;
 
     let (max_pages, concurrency) = clamp_crawl_args(args.max_pages, args.concurrency);
 
-    let client = Client::builder()
-        .user_agent(USER_AGENT)
+    let client = privacy::client_builder(fetcher.client_profile(), Some(USER_AGENT))
         .timeout(SITEMAP_TIMEOUT)
         .gzip(true)
         .brotli(true)
+        .zstd(true)
+        .deflate(true)
         .build()
         .map_err(|e| DaedraError::FetchError(format!("http client build: {}", e)))?;
 
@@ -355,7 +392,7 @@ pub async fn crawl_site(args: CrawlArgs) -> DaedraResult<CrawlResult> {
         "crawl_site starting"
     );
 
-    let handles = fetch_candidates_concurrently(candidates, concurrency).await;
+    let handles = fetch_candidates_concurrently(fetcher, candidates, concurrency).await;
     let (pages, errors) = collect_crawl_results(handles, max_pages).await;
 
     Ok(CrawlResult {
@@ -725,4 +762,27 @@ mod tests {
         assert_eq!(urls[0].path(), "/dup");
     }
 
+    #[test]
+    fn test_plan_crawl_lists_discovery_requests() {
+        let plan = plan_crawl(&CrawlArgs {
+            root_url: "https://example.com/docs/".to_string(),
+            max_pages: 10_000,
+            concurrency: 0,
+        })
+        .unwrap();
+        assert_eq!((plan.max_pages, plan.concurrency), (500, 1));
+        let urls: Vec<&str> = plan.discovery.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls[0], "https://example.com/sitemap.xml");
+        assert_eq!(urls.last(), Some(&"https://example.com/docs/"));
+        assert_eq!(urls.len(), SITEMAP_CANDIDATES.len() + 1);
+
+        let err = plan_crawl(&CrawlArgs {
+            root_url: "not a url".to_string(),
+            max_pages: 1,
+            concurrency: 1,
+        })
+        .unwrap_err();
+        assert!(matches!(err, DaedraError::InvalidArguments(_)));
+    }
+
 }
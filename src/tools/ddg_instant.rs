@@ -5,9 +5,12 @@
 //! and Wikipedia summaries. Not a full web search but great for factual queries.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    ContentType, DaedraResult, DaedraError, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -17,6 +20,10 @@ use tracing::info;
 
 const DDG_API: &str = "https://api.duckduckgo.com/";
 
+fn query_params(query: &str) -> [(&'static str, &str); 4] {
+    [("q", query), ("format", "json"), ("no_html", "1"), ("skip_disambig", "1")]
+}
+
 /// DuckDuckGo Instant Answer API backend — returns abstract/topic results from the DDG API.
 pub struct DdgInstantBackend {
     client: Client,
@@ -37,13 +44,19 @@ struct DdgResponse {
 impl DdgInstantBackend {
     /// Create a new DuckDuckGo Instant Answer backend instance.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent("daedra/1.0")
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("HTTP client");
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0"),
+            Duration::from_secs(10),
+        );
         Self { client }
     }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, Some("daedra/1.0"), Duration::from_secs(10));
+        self
+    }
 }
 
 fn abstract_to_result(data: &DdgResponse) -> Option<SearchResult> {
@@ -56,9 +69,10 @@ fn abstract_to_result(data: &DdgResponse) -> Option<SearchResult> {
         description: data.abstract_text.clone(),
         metadata: ResultMetadata {
             content_type: ContentType::Documentation,
-            source: "ddg-instant".to_string(),
+            source: "ddg-instant".into(),
             favicon: None,
             published_date: None,
+            ..Default::default()
         },
     })
 }
@@ -88,9 +102,10 @@ fn topic_to_result(topic: &serde_json::Value) -> Option<SearchResult> {
         description: text.to_string(),
         metadata: ResultMetadata {
             content_type: ContentType::Documentation,
-            source: "ddg-instant".to_string(),
+            source: "ddg-instant".into(),
             favicon: None,
             published_date: None,
+            ..Default::default()
         },
     })
 }
@@ -102,17 +117,12 @@ impl SearchBackend for DdgInstantBackend {
 
         let resp = self.client
             .get(DDG_API)
-            .query(&[
-                ("q", args.query.as_str()),
-                ("format", "json"),
-                ("no_html", "1"),
-                ("skip_disambig", "1"),
-            ])
-            .send()
+            .query(&query_params(&args.query))
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
-        let data: DdgResponse = resp.json().await.map_err(DaedraError::HttpError)?;
+        let data: DdgResponse = resp.json_counted().await?;
 
         let mut results = Vec::new();
 
@@ -134,6 +144,11 @@ impl SearchBackend for DdgInstantBackend {
     }
 
     fn name(&self) -> &str { "ddg-instant" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Keywords }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        Some(PlannedRequest::get(DDG_API, &query_params(&args.query)))
+    }
 }
 
 #[cfg(test)]
@@ -3,25 +3,63 @@
 //! This module provides functionality to fetch web pages and extract
 //! their content as Markdown.
 
-use crate::types::{DaedraError, DaedraResult, PageContent, PageLink, VisitPageArgs};
-use backoff::{ExponentialBackoff, future::retry};
+use crate::domains;
+use crate::fingerprint::Rotation;
+use crate::memory::MemoryPressure;
+use crate::metadata;
+use crate::privacy::ClientProfile;
+use crate::regions;
+use crate::sandbox::DomainPolicy;
+use crate::tables;
+use crate::telemetry::{self, CountedRequest, Phase};
+use crate::tools::cookies::CookieJar;
+use crate::tools::http::HttpSettings;
+use crate::tools::retry_after;
+use crate::tools::robots::RobotsCache;
+use crate::text;
+use crate::timestamp::Timestamp;
+use crate::types::{
+    ContentType, DaedraError, DaedraResult, ExtractMode, FetchProbe, LinkScope, PageContent,
+    PageFormat, PageImage, PageLink, PlannedRequest, UrlCheck, VisitPageArgs,
+};
+use crate::url_classification;
+use backoff::{ExponentialBackoff, future::retry_notify};
 use dom_smoothie::Readability;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use futures::StreamExt;
 use lazy_static::lazy_static;
-use reqwest::Client;
-use scraper::{ElementRef, Html, Selector};
-use std::collections::HashSet;
-use std::time::Duration;
-use tracing::{error, info, instrument, warn};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, LOCATION};
+use reqwest::{Client, RequestBuilder, Response};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, instrument, warn};
 use url::Url;
 
-/// Default user agent for requests
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
-
 /// Request timeout
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
-/// Maximum content size (10MB)
-const MAX_CONTENT_SIZE: usize = 10 * 1024 * 1024;
+/// Longest a page fetch keeps retrying
+const RETRY_BUDGET: Duration = Duration::from_secs(60);
+
+/// Most redirects followed from one URL
+const MAX_REDIRECTS: usize = 10;
+
+/// Default cap on a page body (10 MiB); see [`FetchClient::with_max_page_bytes`]
+pub const DEFAULT_MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Most pages of a paginated article `follow_pagination` joins
+pub const MAX_PAGINATION_PAGES: usize = 10;
+
+/// Wayback Machine availability API, asked for a dead link's latest snapshot
+pub const WAYBACK_AVAILABLE_URL: &str = "https://archive.org/wayback/available";
 
 lazy_static! {
     // Content selectors in order of preference
@@ -68,11 +106,63 @@ lazy_static! {
         Selector::parse("[class*='comment']").unwrap(),
     ];
 
+    // Charset a page declares in a `<meta>` tag, either form
+    static ref META_CHARSET: Regex =
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_.:-]+)"#).unwrap();
+
+    // Encoding named by an XML declaration
+    static ref XML_ENCODING: Regex =
+        Regex::new(r#"(?i)^\s*<\?xml[^>]+encoding\s*=\s*["']([a-z0-9_.:-]+)"#).unwrap();
+
+    // An `<img>` tag, attributes included
+    static ref IMG_TAG: Regex = Regex::new(r"(?i)<img\b[^>]*>").unwrap();
+
+    // Markdown link or image, keeping the link text
+    static ref MARKDOWN_LINK: Regex = Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap();
+
     // Title selector
     static ref TITLE_SELECTOR: Selector = Selector::parse("title").unwrap();
+    static ref BODY_SELECTOR: Selector = Selector::parse("body").unwrap();
+
+    // Meta tags declaring the publication date, most specific first
+    static ref PUBLISHED_META_SELECTORS: Vec<Selector> = vec![
+        Selector::parse("meta[property='article:published_time']").unwrap(),
+        Selector::parse("meta[itemprop='datePublished']").unwrap(),
+        Selector::parse("meta[name='publish-date']").unwrap(),
+        Selector::parse("meta[name='pubdate']").unwrap(),
+        Selector::parse("meta[name='DC.date.issued']").unwrap(),
+        Selector::parse("meta[name='date']").unwrap(),
+    ];
+
+    // `datePublished` in JSON-LD structured data
+    static ref JSON_LD_DATE_PUBLISHED: Regex =
+        Regex::new(r#""datePublished"\s*:\s*"([^"]+)""#).unwrap();
+
+    // `@type` of JSON-LD structured data (the first, when it is a list)
+    static ref JSON_LD_TYPE: Regex = Regex::new(r#""@type"\s*:\s*\[?\s*"([^"]+)""#).unwrap();
+
+    // schema.org microdata
+    static ref ITEMTYPE_SELECTOR: Selector = Selector::parse("[itemtype]").unwrap();
+
+    // Google Scholar tags that scholarly publishers put on papers
+    static ref CITATION_META_SELECTOR: Selector = Selector::parse(
+        "meta[name='citation_title'], meta[name='citation_doi'], \
+         meta[name='citation_journal_title']"
+    )
+    .unwrap();
+
+    static ref OG_TYPE_SELECTOR: Selector = Selector::parse("meta[property='og:type']").unwrap();
+
+    // `<time>` elements marking the publication date
+    static ref PUBLISHED_TIME_SELECTOR: Selector = Selector::parse(
+        "time[itemprop='datePublished'][datetime], time[pubdate][datetime], article time[datetime]"
+    )
+    .unwrap();
 
     // Link selector
     static ref LINK_SELECTOR: Selector = Selector::parse("a[href]").unwrap();
+    static ref REL_NEXT_SELECTOR: Selector =
+        Selector::parse("link[rel~='next'][href], a[rel~='next'][href]").unwrap();
 
     // Heading selectors
     static ref H1_SELECTOR: Selector = Selector::parse("h1").unwrap();
@@ -94,6 +184,13 @@ lazy_static! {
     static ref PRE_SELECTOR: Selector = Selector::parse("pre").unwrap();
     static ref CODE_SELECTOR: Selector = Selector::parse("code").unwrap();
 
+    // Elements that are never part of the text
+    static ref HIDDEN_SELECTOR: Selector =
+        Selector::parse("script, style, noscript, template").unwrap();
+
+    // Blocks converted apart from the rest of the page
+    static ref BLOCK_SELECTOR: Selector = Selector::parse("table, pre").unwrap();
+
     // Image selector
     static ref IMG_SELECTOR: Selector = Selector::parse("img").unwrap();
 
@@ -128,6 +225,10 @@ const SUSPICIOUS_TITLES: &[&str] = &[
 enum FetchedContent {
     Html(String),
     Pdf(String),
+    /// Text that is not markup (plain text, Markdown, CSV, XML), with its MIME type
+    Text { mime: String, text: String },
+    /// A JSON document, pretty-printed
+    Json(String),
     Binary { mime: String, size: usize },
 }
 
@@ -149,12 +250,7 @@ fn resolve_href(base: &Url, href: &str) -> Option<Url> {
 
 /// Clean up link text: collapse whitespace, skip empty/short text.
 fn normalize_link_text(element: &ElementRef<'_>) -> Option<String> {
-    let text: String = element
-        .text()
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ");
+    let text = text::collapse_whitespace(&element.text().collect::<String>());
     if text.is_empty() || text.len() <= 2 {
         None
     } else {
@@ -162,61 +258,838 @@ fn normalize_link_text(element: &ElementRef<'_>) -> Option<String> {
     }
 }
 
+/// Where `url` points relative to the page at `page`: a fragment of the page
+/// itself, another page on its origin, or elsewhere
+fn link_scope(url: &Url, page: &Url) -> LinkScope {
+    let without_fragment = |url: &Url| {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        url
+    };
+    if url.fragment().is_some() && without_fragment(url) == without_fragment(page) {
+        LinkScope::Anchor
+    } else if url.origin() == page.origin() {
+        LinkScope::Internal
+    } else {
+        LinkScope::External
+    }
+}
+
+/// Every link under `root`, once each, in page order. Unlike the links
+/// listed with a page's content these include anchors and links without
+/// text, labelled by their `aria-label`, `title` or image `alt` instead.
+fn all_links(root: ElementRef<'_>, base_url: &Url) -> Vec<PageLink> {
+    let mut seen_urls = HashSet::new();
+    root.select(&LINK_SELECTOR)
+        .filter_map(|element| {
+            let href = element.value().attr("href")?.trim();
+            if href == "#" || (is_skippable_href(href) && !href.starts_with('#')) {
+                return None;
+            }
+            let resolved = base_url.join(href).ok()?;
+            if !seen_urls.insert(resolved.to_string()) {
+                return None;
+            }
+            Some(PageLink {
+                text: link_label(&element),
+                homograph_risk: resolved.host_str().is_some_and(domains::is_homograph_risk),
+                kind: url_classification::classify_link(resolved.as_str()),
+                scope: Some(link_scope(&resolved, base_url)),
+                url: resolved.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A link's text, or for a link without any (an icon or image), its
+/// `aria-label`, `title` or first image's `alt`; empty when it has none
+fn link_label(element: &ElementRef<'_>) -> String {
+    let text = text::collapse_whitespace(&element.text().collect::<String>());
+    if !text.is_empty() {
+        return text;
+    }
+    let attributes = [element.value().attr("aria-label"), element.value().attr("title")];
+    let image_alt = element
+        .select(&IMG_SELECTOR)
+        .find_map(|img| img.value().attr("alt"));
+    attributes
+        .into_iter()
+        .chain([image_alt])
+        .flatten()
+        .map(text::collapse_whitespace)
+        .find(|label| !label.is_empty())
+        .unwrap_or_default()
+}
+
+/// List `links` under a heading per [`LinkScope`] with its count: Markdown
+/// lists for [`PageFormat::Markdown`] and [`PageFormat::Json`], tab-separated
+/// text and URL lines for [`PageFormat::Text`], `<ul>`s for [`PageFormat::Html`]
+fn render_links(links: &[PageLink], format: PageFormat) -> String {
+    let groups = [
+        (LinkScope::Internal, "Internal links"),
+        (LinkScope::External, "External links"),
+        (LinkScope::Anchor, "Anchors"),
+    ];
+    let sections: Vec<String> = groups
+        .into_iter()
+        .filter_map(|(scope, heading)| {
+            let group: Vec<&PageLink> =
+                links.iter().filter(|link| link.scope == Some(scope)).collect();
+            if group.is_empty() {
+                return None;
+            }
+            let heading = format!("{} ({})", heading, group.len());
+            let items = group.iter().map(|link| match format {
+                PageFormat::Text if link.text.is_empty() => link.url.clone(),
+                PageFormat::Text => format!("{}\t{}", link.text, link.url),
+                PageFormat::Html => format!(
+                    "<li><a href=\"{}\">{}</a></li>",
+                    escape_attribute(&link.url),
+                    escape_attribute(&link.text)
+                ),
+                PageFormat::Markdown | PageFormat::Json if link.text.is_empty() => {
+                    format!("- <{}>", link.url)
+                },
+                PageFormat::Markdown | PageFormat::Json => format!(
+                    "- [{}]({})",
+                    link.text.replace('[', "\\[").replace(']', "\\]"),
+                    link.url
+                ),
+            });
+            let items = items.collect::<Vec<_>>().join("\n");
+            Some(match format {
+                PageFormat::Text => format!("{}\n\n{}", heading, items),
+                PageFormat::Html => format!("<h2>{}</h2>\n<ul>\n{}\n</ul>", heading, items),
+                PageFormat::Markdown | PageFormat::Json => format!("## {}\n\n{}", heading, items),
+            })
+        })
+        .collect();
+    if sections.is_empty() {
+        "No links found.".to_string()
+    } else {
+        sections.join("\n\n")
+    }
+}
+
+/// Rewrite the `<img>` tags of a content fragment. Without a base URL they are
+/// removed; with one, each becomes a bare `<img src alt>` with its source
+/// resolved (falling back to a lazy-loading `data-src`), so it converts to
+/// `![alt](url)`, and is listed once in the returned images. Images without a
+/// usable source, including inline `data:` URIs, are removed either way.
+fn rewrite_images(fragment: &str, base_url: Option<&Url>) -> (String, Vec<PageImage>) {
+    let mut images: Vec<PageImage> = Vec::new();
+    let rewritten = IMG_TAG.replace_all(fragment, |caps: &regex::Captures<'_>| {
+        let Some(image) = base_url.and_then(|base| parse_image(&caps[0], base)) else {
+            return String::new();
+        };
+        let tag = format!(
+            "<img src=\"{}\" alt=\"{}\">",
+            escape_attribute(&image.url),
+            escape_attribute(&image.alt)
+        );
+        if !images.iter().any(|seen| seen.url == image.url) {
+            images.push(image);
+        }
+        tag
+    });
+    (rewritten.into_owned(), images)
+}
+
+/// The image an `<img>` tag shows, with its source resolved against `base_url`
+fn parse_image(tag: &str, base_url: &Url) -> Option<PageImage> {
+    let fragment = Html::parse_fragment(tag);
+    let img = fragment.select(&IMG_SELECTOR).next()?.value();
+    let source = [img.attr("src"), img.attr("data-src")]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|src| !src.is_empty() && !src.starts_with("data:"))?;
+    let url = resolve_href(base_url, source)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let dimension = |name: &str| {
+        let value = img.attr(name)?.trim();
+        value.strip_suffix("px").unwrap_or(value).parse().ok()
+    };
+    Some(PageImage {
+        url: url.to_string(),
+        alt: text::collapse_whitespace(img.attr("alt").unwrap_or_default()),
+        width: dimension("width"),
+        height: dimension("height"),
+    })
+}
+
+/// Escape text for a double-quoted HTML attribute value
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Pages extracted at once when not configured: one per CPU core
+pub fn default_extraction_threads() -> usize {
+    std::thread::available_parallelism().map_or(4, NonZeroUsize::get)
+}
+
 /// HTTP client for fetching pages
 #[derive(Clone)]
 pub struct FetchClient {
     client: Client,
+    /// `client` following no redirects, for requests that carry site headers
+    site_client: Option<Client>,
+    /// What `client` is built from, kept for rebuilding it
+    http: HttpSettings,
+    /// Permits for pages being parsed and converted on blocking threads
+    extraction: Arc<Semaphore>,
+    /// Where `allow_archive` looks up snapshots of dead links
+    archive_endpoint: String,
+    /// Whether the process is over its memory cap
+    memory: MemoryPressure,
+    /// Largest page body fetched
+    max_page_bytes: usize,
+    /// Largest page body fetched under memory pressure
+    pressure_fetch_limit: usize,
+    /// robots.txt rules of the sites fetched from
+    robots: RobotsCache,
+    /// Fetch pages robots.txt disallows anyway
+    ignore_robots: bool,
+    /// Hosts pages may be read from, redirects included
+    domains: Arc<DomainPolicy>,
+    /// Extra headers for the hosts matching each glob
+    site_headers: Arc<[(String, HeaderMap)]>,
+    /// Cookies the sites fetched from have set
+    cookies: Arc<CookieJar>,
+}
+
+/// Extra headers sent with every request to hosts matching a glob, such as
+/// credentials for a private wiki or staging site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainHeaders {
+    /// Host glob, as in [`DomainPolicy`]: `wiki.example.com`, `*.staging.example.com`
+    pub glob: String,
+
+    /// Header names and values, sent in place of any the request would send
+    pub headers: Vec<(String, String)>,
+}
+
+impl FromStr for DomainHeaders {
+    type Err = DaedraError;
+
+    /// Parse `GLOB=Name: value`, as in `wiki.example.com=Authorization: Bearer abc`
+    fn from_str(s: &str) -> DaedraResult<Self> {
+        let invalid = || {
+            DaedraError::InvalidArguments(format!(
+                "Invalid domain header {s:?}; expected GLOB=Name: value"
+            ))
+        };
+        let (glob, header) = s.split_once('=').ok_or_else(invalid)?;
+        let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+        if glob.trim().is_empty() || name.trim().is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            glob: glob.trim().to_string(),
+            headers: vec![(name.trim().to_string(), value.trim().to_string())],
+        })
+    }
+}
+
+/// What a page's response said to tell later whether it changed: its `ETag`
+/// and `Last-Modified` headers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// `ETag`, sent back as `If-None-Match`
+    pub etag: Option<String>,
+
+    /// `Last-Modified`, sent back as `If-Modified-Since`
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    /// The validators among `headers`
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Self {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        }
+    }
+
+    /// Whether there is nothing to revalidate with
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// The conditional request headers asking for the page only if it changed
+    fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let values = [
+            (reqwest::header::IF_NONE_MATCH, &self.etag),
+            (reqwest::header::IF_MODIFIED_SINCE, &self.last_modified),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
+}
+
+/// Builds a [`FetchClient`] with HTTP settings of its own, or around a
+/// [`Client`] the embedder set up (see [`super::http`])
+#[derive(Debug)]
+pub struct FetchClientBuilder {
+    http: HttpSettings,
+    extraction_threads: usize,
+}
+
+impl FetchClientBuilder {
+    /// Send requests with `client` as is, TLS, proxy and middleware included;
+    /// the timeout, user agent and redirect limit set here no longer apply,
+    /// nor does the client's [`CookieJar`]. The [`DomainPolicy`] still
+    /// refuses pages a redirect ends up on.
+    pub fn client(mut self, client: Client) -> Self {
+        self.http.client = Some(client);
+        self
+    }
+
+    /// Give up on a request after `timeout` (30 seconds by default)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = timeout;
+        self
+    }
+
+    /// Send `user_agent` on every request instead of rotating browser profiles'
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Follow at most `max` redirects (10 by default); 0 follows none
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.http.max_redirects = max;
+        self
+    }
+
+    /// Send the headers of `profile` ([`ClientProfile::Standard`] by default)
+    pub fn client_profile(mut self, profile: ClientProfile) -> Self {
+        self.http.profile = profile;
+        self
+    }
+
+    /// Take browser profiles from `rotation` (the built-in pool by default);
+    /// clients given the same one take turns in it
+    pub fn browser_profiles(mut self, rotation: Arc<Rotation>) -> Self {
+        self.http.rotation = rotation;
+        self
+    }
+
+    /// Parse and convert at most `threads` pages at once (see
+    /// [`FetchClient::with_extraction_threads`])
+    pub fn extraction_threads(mut self, threads: usize) -> Self {
+        self.extraction_threads = threads;
+        self
+    }
+
+    /// The fetch client
+    pub fn build(self) -> DaedraResult<FetchClient> {
+        let domains = Arc::new(DomainPolicy::default());
+        let cookies = Arc::new(CookieJar::new());
+        Ok(FetchClient {
+            client: build_client(&self.http, &domains, &cookies, self.http.max_redirects)?,
+            site_client: None,
+            http: self.http,
+            extraction: Arc::new(Semaphore::new(self.extraction_threads.max(1))),
+            archive_endpoint: WAYBACK_AVAILABLE_URL.to_string(),
+            memory: MemoryPressure::default(),
+            max_page_bytes: DEFAULT_MAX_PAGE_BYTES,
+            pressure_fetch_limit: DEFAULT_MAX_PAGE_BYTES,
+            robots: RobotsCache::default(),
+            ignore_robots: false,
+            domains,
+            site_headers: Arc::new([]),
+            cookies,
+        })
+    }
 }
 
 impl FetchClient {
     /// Create a new fetch client
     pub fn new() -> DaedraResult<Self> {
-        let client = Client::builder()
-            .user_agent(USER_AGENT)
-            .timeout(REQUEST_TIMEOUT)
-            .gzip(true)
-            .brotli(true)
-            .redirect(reqwest::redirect::Policy::limited(10))
-            .build()
-            .map_err(DaedraError::HttpError)?;
+        Self::builder().build()
+    }
+
+    /// A builder for a fetch client with its own HTTP settings
+    pub fn builder() -> FetchClientBuilder {
+        FetchClientBuilder {
+            http: HttpSettings::new(REQUEST_TIMEOUT, MAX_REDIRECTS),
+            extraction_threads: default_extraction_threads(),
+        }
+    }
+
+    /// Create a fetch client that parses and converts at most `threads` pages
+    /// at once. Extraction runs on Tokio's blocking threads, so a large page
+    /// doesn't hold up the async workers serving other requests.
+    pub fn with_extraction_threads(threads: usize) -> DaedraResult<Self> {
+        Self::builder().extraction_threads(threads).build()
+    }
+
+    /// Keep cookies in `jar`, such as one [`CookieJar::open`] keeps in a file,
+    /// instead of this client's own
+    pub fn with_cookie_jar(mut self, jar: CookieJar) -> DaedraResult<Self> {
+        self.cookies = Arc::new(jar);
+        self.rebuild_clients()?;
+        Ok(self)
+    }
+
+    /// The cookies the sites fetched from have set
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookies
+    }
+
+    /// Send `rules`' headers with every request to the hosts they match;
+    /// where several rules set one header, the last wins. Values are kept out
+    /// of debug output. An error when a glob, name or value is invalid.
+    ///
+    /// Redirects are then followed one hop at a time, each hop with the
+    /// headers of its own host, so a redirect to another host doesn't carry
+    /// them there. A client given to [`FetchClientBuilder::client`] follows
+    /// redirects by its own policy, though: give it one that follows none.
+    pub fn with_domain_headers(mut self, rules: &[DomainHeaders]) -> DaedraResult<Self> {
+        let invalid = |what: &str, rule: &DomainHeaders| {
+            DaedraError::InvalidArguments(format!("Invalid header {what} for {}", rule.glob))
+        };
+        self.site_headers = rules
+            .iter()
+            .map(|rule| {
+                let mut headers = HeaderMap::new();
+                for (name, value) in &rule.headers {
+                    let name = HeaderName::from_str(name).map_err(|_| invalid("name", rule))?;
+                    let mut value =
+                        HeaderValue::from_str(value).map_err(|_| invalid("value", rule))?;
+                    value.set_sensitive(true);
+                    headers.insert(name, value);
+                }
+                Ok((crate::sandbox::normalize_glob(&rule.glob)?, headers))
+            })
+            .collect::<DaedraResult<Vec<_>>>()?
+            .into();
+        self.rebuild_clients()?;
+        Ok(self)
+    }
+
+    /// Rebuild the clients after the settings they're built from changed
+    fn rebuild_clients(&mut self) -> DaedraResult<()> {
+        let (domains, cookies) = (&self.domains, &self.cookies);
+        self.client = build_client(&self.http, domains, cookies, self.http.max_redirects)?;
+        self.site_client = if self.site_headers.is_empty() {
+            None
+        } else {
+            Some(build_client(&self.http, domains, cookies, 0)?)
+        };
+        Ok(())
+    }
+
+    /// The extra headers for `url`'s host
+    fn site_headers(&self, url: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let Some(host) = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+        else {
+            return headers;
+        };
+        for (glob, extra) in self.site_headers.iter() {
+            if crate::sandbox::glob_matches(glob, host.trim_end_matches('.')) {
+                headers.extend(extra.clone());
+            }
+        }
+        headers
+    }
+
+    /// Send what `request` builds for `url`. With site headers set, redirects
+    /// are followed here rather than by the client, so each hop carries the
+    /// site headers of its own host and no other's.
+    async fn send_with_site_headers(
+        &self,
+        url: &str,
+        request: impl Fn(&Client, &str) -> RequestBuilder,
+    ) -> DaedraResult<Response> {
+        let Some(client) = &self.site_client else {
+            return request(&self.client, url).send_counted().await.map_err(refused_or_http);
+        };
+        let mut url = Url::parse(url)?;
+        for _ in 0..=self.http.max_redirects {
+            let response = request(client, url.as_str())
+                .headers(self.site_headers(url.as_str()))
+                .send_counted()
+                .await
+                .map_err(refused_or_http)?;
+            let redirected = matches!(response.status().as_u16(), 301 | 302 | 303 | 307 | 308);
+            let location = response.headers().get(LOCATION).and_then(|l| l.to_str().ok());
+            let next = match location {
+                Some(location) if redirected && self.http.max_redirects > 0 => url.join(location)?,
+                _ => return Ok(response),
+            };
+            self.domains.check(next.as_str())?;
+            url = next;
+        }
+        Err(DaedraError::FetchError(format!("too many redirects from {url}")))
+    }
+
+    /// The hosts pages may be read from
+    pub fn domain_policy(&self) -> &DomainPolicy {
+        &self.domains
+    }
+
+    /// What the client's requests reveal about the deployment
+    pub fn client_profile(&self) -> ClientProfile {
+        self.http.profile
+    }
+
+    /// Read pages only from hosts `policy` permits: others, and redirects to
+    /// them, fail with [`DaedraError::DomainNotAllowed`]
+    pub fn with_domain_policy(mut self, policy: DomainPolicy) -> DaedraResult<Self> {
+        self.domains = Arc::new(policy);
+        self.rebuild_clients()?;
+        Ok(self)
+    }
+
+    /// Fetch pages even when the site's robots.txt disallows them. By default
+    /// such pages fail with [`DaedraError::DisallowedByRobots`].
+    pub fn with_ignore_robots(mut self, ignore: bool) -> Self {
+        self.ignore_robots = ignore;
+        self
+    }
+
+    /// Whether pages robots.txt disallows are fetched anyway
+    pub fn ignores_robots(&self) -> bool {
+        self.ignore_robots
+    }
+
+    /// Whether robots.txt lets this client fetch `url`
+    async fn robots_allow(&self, url: &Url) -> bool {
+        self.ignore_robots || self.robots.is_allowed(&self.client, url).await
+    }
+
+    /// Refuse pages larger than `limit` bytes while `memory` is under pressure
+    /// (see [`crate::memory`])
+    pub fn with_memory_pressure(mut self, memory: MemoryPressure, limit: usize) -> Self {
+        self.memory = memory;
+        self.pressure_fetch_limit = limit;
+        self
+    }
+
+    /// Refuse page bodies over `bytes` ([`DEFAULT_MAX_PAGE_BYTES`] by
+    /// default). Bodies are read as they arrive, so a page that doesn't
+    /// declare its length is cut off as soon as it passes the cap.
+    pub fn with_max_page_bytes(mut self, bytes: usize) -> Self {
+        self.max_page_bytes = bytes;
+        self
+    }
+
+    /// The cap on a page body right now, at most `requested` bytes when given
+    fn body_limit(&self, requested: Option<usize>) -> BodyLimit {
+        let bytes = requested.map_or(self.max_page_bytes, |r| r.min(self.max_page_bytes));
+        if self.memory.is_high() && self.pressure_fetch_limit < bytes {
+            BodyLimit {
+                bytes: self.pressure_fetch_limit,
+                memory_pressure: true,
+            }
+        } else {
+            BodyLimit {
+                bytes,
+                memory_pressure: false,
+            }
+        }
+    }
 
-        Ok(Self { client })
+    /// Look up archived snapshots at `endpoint`, a Wayback Machine
+    /// availability API, instead of [`WAYBACK_AVAILABLE_URL`]
+    pub fn with_archive_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.archive_endpoint = endpoint.into();
+        self
     }
 
     /// Fetch and extract content from a URL
+    ///
+    /// With [`VisitPageArgs::allow_archive`], a page that is gone (HTTP 404 or
+    /// 410, or a host that no longer resolves) is read from its latest Wayback
+    /// Machine snapshot instead: the page's `url` is the snapshot's and its
+    /// `timestamp` the time the snapshot was taken.
+    ///
+    /// Pages the site's robots.txt disallows fail with
+    /// [`DaedraError::DisallowedByRobots`] unless the client
+    /// [ignores robots.txt](Self::with_ignore_robots).
     #[instrument(skip(self), fields(url = %args.url))]
     pub async fn fetch(&self, args: &VisitPageArgs) -> DaedraResult<PageContent> {
-        info!(url = %args.url, "Fetching page");
+        self.fetch_with_validators(args).await.map(|(page, _)| page)
+    }
 
-        let parsed_url = validate_url(&args.url)?;
-        let fetched = self.fetch_with_retry(&args.url).await?;
+    /// [`fetch`](Self::fetch), with the [`Validators`] to
+    /// [`revalidate`](Self::revalidate) the page with later. Archived and
+    /// joined pages have none.
+    pub async fn fetch_with_validators(
+        &self,
+        args: &VisitPageArgs,
+    ) -> DaedraResult<(PageContent, Validators)> {
+        self.fetch_page(args, None).await?.ok_or_else(unasked_not_modified)
+    }
 
-        match fetched {
+    /// Fetch the page again only if it changed since `validators` were
+    /// taken: `None` when the server answers `304 Not Modified`, without
+    /// sending the page or it being parsed again
+    pub async fn revalidate(
+        &self,
+        args: &VisitPageArgs,
+        validators: &Validators,
+    ) -> DaedraResult<Option<(PageContent, Validators)>> {
+        self.fetch_page(args, Some(validators)).await
+    }
+
+    /// Fetch and extract the page, asking for it only if it changed since
+    /// `conditional` when given; `None` when it didn't
+    async fn fetch_page(
+        &self,
+        args: &VisitPageArgs,
+        conditional: Option<&Validators>,
+    ) -> DaedraResult<Option<(PageContent, Validators)>> {
+        info!(url = %args.url, revalidating = conditional.is_some(), "Fetching page");
+
+        let url = validate_url(&args.url)?;
+        self.domains.check(&args.url)?;
+        if !self.robots_allow(&url).await {
+            return Err(DaedraError::DisallowedByRobots(args.url.clone()));
+        }
+        let accept_language = args.region.as_deref().and_then(regions::accept_language);
+        let limit = self.body_limit(args.max_bytes);
+        let mut archived = None;
+        let language = accept_language.as_deref();
+        let fetched = self.fetch_validated(&args.url, language, limit, conditional).await;
+        let (fetched, mut validators) = match fetched {
+            Ok(Some(fetched)) => fetched,
+            Ok(None) => {
+                info!(url = %args.url, "Page not modified");
+                return Ok(None);
+            },
+            Err(e) if args.allow_archive && is_dead_link(&e) => {
+                if !self.domains.permits(&self.archive_endpoint) {
+                    return Err(e);
+                }
+                let Some(snapshot) = self.wayback_snapshot(&args.url).await else {
+                    return Err(e);
+                };
+                self.domains.check(&snapshot.raw_url())?;
+                info!(snapshot = %snapshot.url, error = %e, "Page gone, reading the archive");
+                let fetched = self.fetch_with_retry(&snapshot.raw_url(), None, limit).await?;
+                archived = Some(snapshot);
+                (fetched, Validators::default())
+            },
+            Err(e) => return Err(e),
+        };
+        if args.extract == ExtractMode::Links && !matches!(fetched, FetchedContent::Html(_)) {
+            return Err(DaedraError::ExtractionError(format!(
+                "{} is not an HTML page; links can only be extracted from HTML",
+                args.url
+            )));
+        }
+        // Documents without markup come back as they are in text and HTML
+        let fenced = matches!(args.format, PageFormat::Markdown | PageFormat::Json);
+
+        let mut page = match fetched {
             FetchedContent::Html(html) => {
-                self.build_page_from_html(&html, &args.url, &parsed_url, args.selector.as_deref())
-            }
-            FetchedContent::Pdf(text) => Ok(FetchClient::build_page_from_pdf(&text, &args.url)),
+                let pages = args.follow_pagination.unwrap_or(1).min(MAX_PAGINATION_PAGES);
+                let paginated =
+                    pages > 1 && args.extract == ExtractMode::Content && archived.is_none();
+                let (page, next) = self.extract_blocking(html, args.clone(), paginated).await?;
+                if paginated {
+                    // Only the first page would be revalidated
+                    validators = Validators::default();
+                    Ok(self.join_pages(page, next, args, pages, accept_language.as_deref()).await)
+                } else {
+                    Ok(page)
+                }
+            },
+            FetchedContent::Pdf(text) => {
+                Ok(FetchClient::build_page_from_text(&text, &args.url, "application/pdf"))
+            },
+            FetchedContent::Text { mime, text } => {
+                let content = if fenced { fence_text(&mime, &text) } else { text };
+                Ok(FetchClient::build_page_from_text(&content, &args.url, &mime))
+            },
+            FetchedContent::Json(json) => {
+                let content = if fenced { format!("```json\n{}\n```", json) } else { json };
+                Ok(FetchClient::build_page_from_text(&content, &args.url, "application/json"))
+            },
             FetchedContent::Binary { mime, size } => Err(DaedraError::ExtractionError(format!(
-                "Unsupported content type: {mime} ({size} bytes)"
+                "unsupported content type {mime} ({size} bytes)"
             ))),
+        }?;
+        if let Some(snapshot) = archived {
+            page.timestamp = snapshot.captured_at().unwrap_or(page.timestamp);
+            page.url = snapshot.archive_url();
+        }
+        Ok(Some((page, validators)))
+    }
+
+    /// The latest Wayback Machine snapshot of `url`, if there is one
+    async fn wayback_snapshot(&self, url: &str) -> Option<WaybackSnapshot> {
+        let response = self
+            .client
+            .get(&self.archive_endpoint)
+            .query(&[("url", url)])
+            .send_counted()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        let availability = match response {
+            Ok(response) => response.json::<WaybackAvailability>().await,
+            Err(e) => Err(e),
+        };
+        match availability {
+            Ok(availability) => availability
+                .archived_snapshots
+                .closest
+                .filter(|snapshot| snapshot.available && snapshot.status.starts_with('2')),
+            Err(e) => {
+                warn!(url = %url, error = %e, "Wayback Machine lookup failed");
+                None
+            },
+        }
+    }
+
+    /// Extract a page from HTML already downloaded from `args.url`, the way
+    /// [`fetch`](Self::fetch) does with what it downloads
+    pub fn extract_html(&self, html: &str, args: &VisitPageArgs) -> DaedraResult<PageContent> {
+        let base_url = validate_url(&args.url)?;
+        let selector = args.selector.as_deref();
+        match args.extract {
+            ExtractMode::Content => self.build_page_from_html(
+                html,
+                &args.url,
+                &base_url,
+                selector,
+                args.include_images,
+                args.format,
+            ),
+            ExtractMode::Links => {
+                self.build_links_page(html, &args.url, &base_url, selector, args.format)
+            },
         }
     }
 
+    /// [`extract_html`](Self::extract_html) on a blocking thread, waiting
+    /// while as many pages as the client allows are being extracted. With
+    /// `find_next`, also the article's next page (see [`next_page_url`]).
+    async fn extract_blocking(
+        &self,
+        html: String,
+        args: VisitPageArgs,
+        find_next: bool,
+    ) -> DaedraResult<(PageContent, Option<Url>)> {
+        let permit = telemetry::timed(Phase::Queue, Arc::clone(&self.extraction).acquire_owned())
+            .await
+            .map_err(|_| DaedraError::ServerError("page extraction is shut down".to_string()))?;
+        let client = self.clone();
+        let span = tracing::Span::current();
+        let extraction = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            span.in_scope(|| {
+                let page = client.extract_html(&html, &args)?;
+                let next = match Url::parse(&args.url) {
+                    Ok(url) if find_next => next_page_url(&Html::parse_document(&html), &url),
+                    _ => None,
+                };
+                Ok((page, next))
+            })
+        });
+        telemetry::timed(Phase::Parse, extraction)
+            .await
+            .map_err(|e| DaedraError::ExtractionError(format!("extraction task failed: {e}")))?
+    }
+
+    /// Follow `first`'s pagination from its `next` page and append the
+    /// article's next pages to it, up to `max_pages` in all. A next page that
+    /// fails to fetch or extract ends the article there.
+    async fn join_pages(
+        &self,
+        mut first: PageContent,
+        mut next: Option<Url>,
+        args: &VisitPageArgs,
+        max_pages: usize,
+        accept_language: Option<&str>,
+    ) -> PageContent {
+        let mut pages = vec![args.url.clone()];
+
+        while pages.len() < max_pages
+            && let Some(url) = next.take()
+            && !pages.contains(&url.to_string())
+        {
+            if !self.robots_allow(&url).await {
+                info!(url = %url, "Stopped following pagination at a page robots.txt disallows");
+                break;
+            }
+            let limit = self.body_limit(args.max_bytes);
+            let html = match self.fetch_with_retry(url.as_str(), accept_language, limit).await {
+                Ok(FetchedContent::Html(html)) => html,
+                Ok(_) => break,
+                Err(e) => {
+                    warn!(url = %url, error = %e, "Stopped following pagination");
+                    break;
+                },
+            };
+            let page_args = VisitPageArgs {
+                url: url.to_string(),
+                follow_pagination: None,
+                ..args.clone()
+            };
+            let page = match self.extract_blocking(html, page_args, true).await {
+                Ok((page, following)) => {
+                    next = following;
+                    page
+                },
+                Err(e) => {
+                    warn!(url = %url, error = %e, "Stopped following pagination");
+                    break;
+                },
+            };
+            append_page(&mut first, page);
+            pages.push(url.to_string());
+        }
+
+        if pages.len() > 1 {
+            info!(url = %args.url, pages = pages.len(), "Joined paginated article");
+            first.pages = Some(pages);
+        }
+        first
+    }
+
     fn build_page_from_html(
         &self,
         html: &str,
         url: &str,
         base_url: &Url,
         selector: Option<&str>,
+        include_images: bool,
+        format: PageFormat,
     ) -> DaedraResult<PageContent> {
         let document = Html::parse_document(html);
 
         self.check_bot_protection(&document)?;
 
         let title = self.extract_title(&document);
-        let content = self.extract_content(html, &document, url, selector)?;
-
-        let word_count = word_count(&content);
+        let published_date = extract_published_date(&document, html);
+        let content_type = extract_page_type(&document, html);
+        let meta = Some(metadata::extract(&document, base_url)).filter(|meta| !meta.is_empty());
+        let image_base = include_images.then_some(base_url);
+        let (content, images) =
+            self.extract_content(html, &document, url, selector, image_base, format)?;
+
+        let word_count = match format {
+            PageFormat::Html => word_count(&html_to_text(&content)),
+            _ => word_count(&content),
+        };
 
         let links = if word_count >= 50 {
             Some(self.extract_links(&document, base_url))
@@ -235,13 +1108,70 @@ impl FetchClient {
             url: url.to_string(),
             title,
             content,
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: Timestamp::now(),
             word_count,
             links,
+            images: include_images.then_some(images),
+            published_date,
+            content_type,
+            meta,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
         })
     }
 
-    fn build_page_from_pdf(text: &str, url: &str) -> PageContent {
+    /// Page listing every link in `html`, or in the element `selector` picks,
+    /// by [`LinkScope`]: the content lists them in `format` and
+    /// [`PageContent::links`] holds them all
+    fn build_links_page(
+        &self,
+        html: &str,
+        url: &str,
+        base_url: &Url,
+        selector: Option<&str>,
+        format: PageFormat,
+    ) -> DaedraResult<PageContent> {
+        let document = Html::parse_document(html);
+
+        self.check_bot_protection(&document)?;
+
+        let selected = match selector {
+            Some(sel) => {
+                let selector = Selector::parse(sel).map_err(|_| {
+                    DaedraError::InvalidArguments(format!("Invalid CSS selector: {}", sel))
+                })?;
+                document.select(&selector).next()
+            },
+            None => None,
+        };
+        let links = all_links(selected.unwrap_or_else(|| document.root_element()), base_url);
+        let content = render_links(&links, format);
+
+        info!(url = %url, links = links.len(), "Page links extracted");
+
+        Ok(PageContent {
+            url: url.to_string(),
+            title: self.extract_title(&document),
+            word_count: word_count(&content),
+            content,
+            timestamp: Timestamp::now(),
+            links: Some(links),
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        })
+    }
+
+    /// Page for a document with no markup to extract from (PDF, plain text,
+    /// JSON), titled after the last segment of its URL
+    fn build_page_from_text(text: &str, url: &str, mime: &str) -> PageContent {
         let content = text.trim().to_string();
         let word_count = word_count(&content);
         let title = title_from_url(url);
@@ -250,43 +1180,178 @@ impl FetchClient {
             url = %url,
             title = %title,
             word_count = word_count,
-            "PDF fetched successfully"
+            mime = %mime,
+            "Document fetched successfully"
         );
 
         PageContent {
             url: url.to_string(),
             title,
             content,
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: Timestamp::now(),
             word_count,
             links: None,
+            images: None,
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
+        }
+    }
+
+    /// Check with a HEAD request (a ranged GET whose body is never read, for
+    /// servers that refuse HEAD) whether `url` is worth a [`fetch`](Self::fetch).
+    pub async fn probe(&self, url: &str) -> FetchProbe {
+        if let Err(e) = validate_url(url) {
+            return FetchProbe {
+                reason: Some(e.to_string()),
+                ..Default::default()
+            };
+        }
+        match self.head_or_ranged_get(url).await {
+            Ok((response, _)) => {
+                probe_from_head(response.status(), response.headers(), self.max_page_bytes)
+            },
+            Err(e) => FetchProbe {
+                reason: Some(format!("request failed: {}", e)),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Status, final URL, content type, size and headers of `url`, from a HEAD
+    /// request (a GET of its first byte, for servers that refuse HEAD) whose
+    /// body is never read.
+    pub async fn check_url(&self, url: &str) -> DaedraResult<UrlCheck> {
+        validate_url(url)?;
+        self.domains.check(url)?;
+        let (response, method) = self.head_or_ranged_get(url).await?;
+        let status = response.status();
+        let probe = probe_from_head(status, response.headers(), self.max_page_bytes);
+        info!(url = %url, status = status.as_u16(), %method, "URL checked");
+
+        let mut headers: BTreeMap<String, String> = BTreeMap::new();
+        for (name, value) in response.headers() {
+            if matches!(name.as_str(), "set-cookie" | "set-cookie2") {
+                continue;
+            }
+            let value = String::from_utf8_lossy(value.as_bytes());
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|joined| {
+                    joined.push_str(", ");
+                    joined.push_str(&value);
+                })
+                .or_insert_with(|| value.into_owned());
         }
+        Ok(UrlCheck {
+            url: url.to_string(),
+            final_url: response.url().to_string(),
+            method: method.to_string(),
+            status: status.as_u16(),
+            status_text: status.canonical_reason().map(str::to_string),
+            content_type: probe.content_type,
+            content_length: probe.content_length,
+            fetchable: probe.fetchable,
+            reason: probe.reason,
+            headers,
+        })
+    }
+
+    /// The request [`check_url`](Self::check_url) sends first
+    pub fn plan_check_url(&self, url: &str) -> DaedraResult<PlannedRequest> {
+        validate_url(url)?;
+        let request = PlannedRequest {
+            method: "HEAD".to_string(),
+            ..PlannedRequest::get::<&str, &str>(url, &[])
+        };
+        Ok(request.with_note(
+            "follows up to 10 redirects; a GET with Range: bytes=0-0 when HEAD is refused",
+        ))
+    }
+
+    /// Send a HEAD request for `url`, or when the server refuses HEAD (405 or
+    /// 501) a GET of its first byte; the body is left unread either way.
+    async fn head_or_ranged_get(
+        &self,
+        url: &str,
+    ) -> DaedraResult<(reqwest::Response, reqwest::Method)> {
+        let response = self.send_with_site_headers(url, |client, url| client.head(url)).await?;
+        if !matches!(response.status().as_u16(), 405 | 501) {
+            return Ok((response, reqwest::Method::HEAD));
+        }
+        let ranged = |client: &Client, url: &str| {
+            client.get(url).header(reqwest::header::RANGE, "bytes=0-0")
+        };
+        let response = self.send_with_site_headers(url, ranged).await?;
+        Ok((response, reqwest::Method::GET))
+    }
+
+    /// Fetch page content with retry logic, refusing a body past `limit`
+    async fn fetch_with_retry(
+        &self,
+        url: &str,
+        accept_language: Option<&str>,
+        limit: BodyLimit,
+    ) -> DaedraResult<FetchedContent> {
+        let fetched = self.fetch_validated(url, accept_language, limit, None).await?;
+        fetched.map(|(content, _)| content).ok_or_else(unasked_not_modified)
     }
 
-    /// Fetch page content with retry logic
-    async fn fetch_with_retry(&self, url: &str) -> DaedraResult<FetchedContent> {
+    /// [`fetch_with_retry`](Self::fetch_with_retry), with the response's
+    /// validators; asking for the page only if it changed since `conditional`
+    /// when given, and `None` when it didn't
+    async fn fetch_validated(
+        &self,
+        url: &str,
+        accept_language: Option<&str>,
+        limit: BodyLimit,
+        conditional: Option<&Validators>,
+    ) -> DaedraResult<Option<(FetchedContent, Validators)>> {
         let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(60)),
+            max_elapsed_time: Some(RETRY_BUDGET),
             ..Default::default()
         };
 
-        let client = self.client.clone();
+        let http = &self.http;
+        let domains = &self.domains;
+        let conditional_headers = conditional.map(Validators::conditional_headers);
+        let started = Instant::now();
         let url = url.to_string();
 
-        retry(backoff, || async {
-            let response = client.get(&url).send().await.map_err(|e| {
-                warn!(error = %e, url = %url, "Fetch request failed, retrying...");
-                backoff::Error::transient(DaedraError::HttpError(e))
+        let op = || async {
+            let headers = http.request_headers(accept_language);
+            let request = |client: &Client, url: &str| {
+                client
+                    .get(url)
+                    .headers(headers.clone())
+                    .headers(conditional_headers.clone().unwrap_or_default())
+            };
+            let response = self.send_with_site_headers(&url, request).await.map_err(|e| match e {
+                DaedraError::HttpError(e) => {
+                    warn!(error = %e, url = %url, "Fetch request failed, retrying...");
+                    backoff::Error::transient(DaedraError::HttpError(e))
+                },
+                refused => backoff::Error::permanent(refused),
             })?;
+            // A client the embedder gave follows redirects without asking the policy
+            if let Err(refused) = domains.check(response.url().as_str()) {
+                return Err(backoff::Error::permanent(refused));
+            }
+            if conditional.is_some() && response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
 
-            classify_response_status(response.status(), &url)?;
+            classify_response_status(&response, &url, started)?;
+            let validators = Validators::from_headers(response.headers());
 
             if let Some(content_length) = response.content_length()
-                && content_length as usize > MAX_CONTENT_SIZE
+                && content_length > limit.bytes as u64
             {
-                return Err(backoff::Error::permanent(DaedraError::FetchError(
-                    "Content too large".to_string(),
-                )));
+                return Err(backoff::Error::permanent(limit.exceeded()));
             }
 
             let content_type = response
@@ -296,38 +1361,19 @@ impl FetchClient {
                 .unwrap_or("")
                 .to_string();
 
-            let ct = normalize_content_type(&content_type);
-
-            if ct.contains("application/pdf") {
-                let bytes = response.bytes().await.map_err(|e| {
+            let body = telemetry::timed(Phase::Network, read_body(response, limit)).await;
+            let bytes = body.map_err(|e| {
+                if let DaedraError::HttpError(e) = &e {
                     error!(error = %e, url = %url, "Failed to read response body");
-                    backoff::Error::permanent(DaedraError::HttpError(e))
-                })?;
-                check_body_size(bytes.len())?;
-                return Ok(extract_pdf_content(&bytes)?);
-            }
-
-            if is_known_binary_content_type(&ct) {
-                let bytes = response.bytes().await.map_err(|e| {
-                    error!(error = %e, url = %url, "Failed to read response body");
-                    backoff::Error::permanent(DaedraError::HttpError(e))
-                })?;
-                check_body_size(bytes.len())?;
-                return Ok(FetchedContent::Binary {
-                    mime: ct,
-                    size: bytes.len(),
-                });
-            }
-
-            let bytes = response.bytes().await.map_err(|e| {
-                error!(error = %e, url = %url, "Failed to read response body");
-                backoff::Error::permanent(DaedraError::HttpError(e))
+                }
+                backoff::Error::permanent(e)
             })?;
-            check_body_size(bytes.len())?;
 
-            classify_fetched_content(&content_type, &bytes).map_err(|e| backoff::Error::permanent(e))
-        })
-        .await
+            telemetry::timed_sync(Phase::Parse, || classify_fetched_content(&content_type, &bytes))
+                .map(|content| Some((content, validators)))
+                .map_err(backoff::Error::permanent)
+        };
+        retry_notify(backoff, op, |_, _| telemetry::record_retry()).await
     }
 
     /// Check for bot protection indicators
@@ -371,23 +1417,40 @@ impl FetchClient {
         }
     }
 
-    /// Extract and convert content to Markdown
+    /// Extract the content and convert it to `format` (Markdown, plain text
+    /// or HTML), with its images resolved against `image_base` or, without
+    /// one, dropped
     fn extract_content(
         &self,
         html: &str,
         document: &Html,
         url: &str,
         selector: Option<&str>,
-    ) -> DaedraResult<String> {
+        image_base: Option<&Url>,
+        format: PageFormat,
+    ) -> DaedraResult<(String, Vec<PageImage>)> {
+        if selector.is_none()
+            && let Some(body) = plain_text_body(document)
+        {
+            debug!("Plain text page, skipping the content cascade");
+            return Ok((plain_text_content(body, format), Vec::new()));
+        }
+
         let content_html = self.select_content_html(html, document, url, selector)?;
-        let markdown = html_to_markdown(&content_html);
-        let cleaned = clean_markdown(&markdown);
+        let (content_html, images) = rewrite_images(&content_html, image_base);
+        let cleaned = match format {
+            PageFormat::Markdown | PageFormat::Json => {
+                clean_markdown(&html_to_markdown(&content_html))
+            },
+            PageFormat::Text => html_to_text(&content_html),
+            PageFormat::Html => strip_scripts(&content_html).trim().to_string(),
+        };
 
         if word_count(&cleaned) < 10 {
             warn!("Extracted content is very short");
         }
 
-        Ok(cleaned)
+        Ok((cleaned, images))
     }
 
     fn select_html_fragment(&self, document: &Html, sel: &str) -> DaedraResult<Option<String>> {
@@ -412,7 +1475,7 @@ impl FetchClient {
 
     fn select_body_html(&self, document: &Html) -> String {
         document
-            .select(&Selector::parse("body").unwrap())
+            .select(&BODY_SELECTOR)
             .next()
             .map(|el| el.html())
             .unwrap_or_default()
@@ -438,6 +1501,9 @@ impl FetchClient {
             };
             links.push(PageLink {
                 text,
+                homograph_risk: resolved.host_str().is_some_and(domains::is_homograph_risk),
+                kind: url_classification::classify_link(resolved.as_str()),
+                scope: Some(link_scope(&resolved, base_url)),
                 url: resolved.to_string(),
             });
         }
@@ -447,6 +1513,55 @@ impl FetchClient {
     }
 }
 
+/// The page client, following at most `max_redirects` redirects and only to
+/// hosts `domains` permits, and keeping cookies in `cookies`, except under
+/// the low-footprint profile
+fn build_client(
+    http: &HttpSettings,
+    domains: &Arc<DomainPolicy>,
+    cookies: &Arc<CookieJar>,
+    max_redirects: usize,
+) -> DaedraResult<Client> {
+    let domains = Arc::clone(domains);
+    let cookies = Arc::clone(cookies);
+    let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+        if !domains.permits_url(attempt.url()) {
+            let host = attempt.url().host_str().unwrap_or_default().to_string();
+            attempt.error(DaedraError::DomainNotAllowed(host))
+        } else if max_redirects == 0 {
+            attempt.stop()
+        } else if attempt.previous().len() >= max_redirects {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    });
+    http.build(|builder| {
+        let builder = builder.redirect(redirects);
+        match http.profile {
+            ClientProfile::Standard => builder.cookie_provider(cookies),
+            ClientProfile::LowFootprint => builder,
+        }
+    })
+}
+
+/// The domain policy refusal behind `error`, when a redirect was refused
+fn domain_refusal(error: &reqwest::Error) -> Option<DaedraError> {
+    let mut source = std::error::Error::source(error);
+    while let Some(e) = source {
+        if let Some(DaedraError::DomainNotAllowed(host)) = e.downcast_ref::<DaedraError>() {
+            return Some(DaedraError::DomainNotAllowed(host.clone()));
+        }
+        source = e.source();
+    }
+    None
+}
+
+/// `error` as a domain policy refusal, or else an HTTP error
+fn refused_or_http(error: reqwest::Error) -> DaedraError {
+    domain_refusal(&error).unwrap_or(DaedraError::HttpError(error))
+}
+
 impl Default for FetchClient {
     fn default() -> Self {
         Self::new().expect("Failed to create default fetch client")
@@ -470,14 +1585,90 @@ fn validate_url(url: &str) -> DaedraResult<Url> {
     Ok(parsed_url)
 }
 
+/// Whether `error` means the page is gone rather than briefly unreachable:
+/// HTTP 404 or 410, or a host name that doesn't resolve
+fn is_dead_link(error: &DaedraError) -> bool {
+    match error {
+        DaedraError::FetchError(message) => {
+            message.starts_with("HTTP 404") || message.starts_with("HTTP 410")
+        },
+        DaedraError::HttpError(e) => {
+            e.is_connect()
+                && std::iter::successors(Some(e as &dyn std::error::Error), |e| e.source())
+                    .any(|e| e.to_string().contains("dns error"))
+        },
+        _ => false,
+    }
+}
+
+/// Whether retrying soon after `error` is bound to fail the same way: a
+/// [dead link](is_dead_link) or bot protection
+pub fn is_lasting_failure(error: &DaedraError) -> bool {
+    matches!(error, DaedraError::BotProtectionDetected) || is_dead_link(error)
+}
+
+/// Answer of the Wayback Machine availability API
+#[derive(Debug, Deserialize)]
+struct WaybackAvailability {
+    #[serde(default)]
+    archived_snapshots: WaybackSnapshots,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WaybackSnapshots {
+    closest: Option<WaybackSnapshot>,
+}
+
+/// A Wayback Machine capture of a page
+#[derive(Debug, Deserialize)]
+struct WaybackSnapshot {
+    #[serde(default)]
+    available: bool,
+    /// The capture's `https://web.archive.org/web/<timestamp>/<url>` address
+    url: String,
+    /// When it was captured, as `YYYYMMDDhhmmss` in UTC
+    timestamp: String,
+    /// HTTP status the page was captured with
+    #[serde(default)]
+    status: String,
+}
+
+impl WaybackSnapshot {
+    /// The capture as the site served it, without the archive's toolbar and
+    /// rewritten links
+    fn raw_url(&self) -> String {
+        let stamp = format!("/{}/", self.timestamp);
+        self.archive_url().replacen(&stamp, &format!("/{}id_/", self.timestamp), 1)
+    }
+
+    /// The capture's address, over HTTPS
+    fn archive_url(&self) -> String {
+        match self.url.strip_prefix("http://web.archive.org/") {
+            Some(rest) => format!("https://web.archive.org/{rest}"),
+            None => self.url.clone(),
+        }
+    }
+
+    /// When it was captured
+    fn captured_at(&self) -> Option<Timestamp> {
+        chrono::NaiveDateTime::parse_from_str(&self.timestamp, "%Y%m%d%H%M%S")
+            .ok()
+            .map(|at| Timestamp::from(at.and_utc()))
+    }
+}
+
 fn is_retryable_status(status: u16) -> bool {
     status == 429
 }
 
+/// The retry error for `response` unless it succeeded; a 429 waits as long as
+/// its `Retry-After` asks, counted against the retries begun at `started`
 fn classify_response_status(
-    status: reqwest::StatusCode,
+    response: &reqwest::Response,
     url: &str,
+    started: Instant,
 ) -> Result<(), backoff::Error<DaedraError>> {
+    let status = response.status();
     if status.is_success() {
         return Ok(());
     }
@@ -485,7 +1676,7 @@ fn classify_response_status(
     warn!(status = %status, url = %url, "Fetch returned non-success status");
 
     if is_retryable_status(status.as_u16()) {
-        return Err(backoff::Error::transient(DaedraError::RateLimitExceeded));
+        return Err(retry_after::rate_limited(response.headers(), started, RETRY_BUDGET));
     }
 
     if status.as_u16() == 403 {
@@ -530,21 +1721,186 @@ fn is_known_binary_content_type(content_type: &str) -> bool {
         || BINARY_CONTENT_EXACT.iter().any(|exact| ct == *exact)
 }
 
+/// Judge fetchability from a response's status and headers alone.
+fn probe_from_head(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    max_bytes: usize,
+) -> FetchProbe {
+    let content_type = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(normalize_content_type)
+        .filter(|ct| !ct.is_empty());
+    let content_length = declared_length(status, headers);
+
+    let reason = if status.as_u16() == 403 {
+        Some(format!("HTTP {} (likely bot protection)", status))
+    } else if !status.is_success() {
+        Some(format!("HTTP {}", status))
+    } else if content_length.is_some_and(|len: u64| len > max_bytes as u64) {
+        Some(format!("larger than the {} limit", size_label(max_bytes)))
+    } else {
+        content_type
+            .as_deref()
+            .filter(|ct| is_known_binary_content_type(ct))
+            .map(|ct| format!("binary content ({})", ct))
+    };
+
+    FetchProbe {
+        fetchable: reason.is_none(),
+        status: Some(status.as_u16()),
+        content_type,
+        content_length,
+        reason,
+    }
+}
+
+/// Size of the whole body a response declares: the total of a 206's
+/// `Content-Range`, otherwise its `Content-Length` (read from the header,
+/// since reqwest reports no body size for HEAD responses)
+fn declared_length(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+) -> Option<u64> {
+    let header = |name| headers.get(name)?.to_str().ok();
+    if status == reqwest::StatusCode::PARTIAL_CONTENT {
+        // `bytes 0-0/12345`; the total is `*` when the server doesn't know it
+        let (_, total) = header(reqwest::header::CONTENT_RANGE)?.rsplit_once('/')?;
+        return total.trim().parse().ok();
+    }
+    header(reqwest::header::CONTENT_LENGTH)?.trim().parse().ok()
+}
+
 fn is_binary_mime(mime: &str) -> bool {
     is_known_binary_content_type(mime)
         || mime == "application/pdf"
         || mime.starts_with("application/vnd.")
 }
 
+/// How much of a body is searched for a `<meta>` charset, as browsers do
+const CHARSET_PRESCAN_BYTES: usize = 1024;
+
+/// `bytes` re-encoded as UTF-8 from the charset named by a byte order mark,
+/// the `Content-Type` header, or a `<meta>` tag or XML declaration, in that
+/// order. An undeclared body that isn't UTF-8 but is declared or looks like
+/// text is read as windows-1252, as browsers do. Binary bodies and UTF-8 are
+/// returned as they are.
+fn transcode_to_utf8<'a>(content_type: &str, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+    let ct = normalize_content_type(content_type);
+    let binary =
+        infer::get(bytes).is_some_and(|kind| kind.matcher_type() != infer::MatcherType::Text);
+    if binary || is_known_binary_content_type(&ct) {
+        return Cow::Borrowed(bytes);
+    }
+
+    let declared = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| charset_param(content_type).and_then(|l| Encoding::for_label(l.as_bytes())))
+        .or_else(|| sniff_declared_charset(bytes));
+    let encoding = match declared {
+        Some(encoding) => encoding,
+        None if std::str::from_utf8(bytes).is_ok() => return Cow::Borrowed(bytes),
+        None if is_html_mime(&ct) || is_text_mime(&ct) || looks_like_markup(bytes) => WINDOWS_1252,
+        None => return Cow::Borrowed(bytes),
+    };
+    if encoding == UTF_8 {
+        return Cow::Borrowed(bytes);
+    }
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        debug!(charset = encoding.name(), "Body had bytes invalid in its charset");
+    }
+    Cow::Owned(text.into_owned().into_bytes())
+}
+
+/// `charset` parameter of a `Content-Type` value
+fn charset_param(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+/// Encoding a page declares in its first bytes (`<meta charset>`, `<meta
+/// http-equiv>` or `<?xml encoding?>`). UTF-16 declared this way means UTF-8,
+/// since an ASCII-readable declaration can't be UTF-16.
+fn sniff_declared_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(CHARSET_PRESCAN_BYTES)]);
+    let label = XML_ENCODING
+        .captures(&head)
+        .or_else(|| META_CHARSET.captures(&head))?
+        .get(1)?
+        .as_str()
+        .to_string();
+    Encoding::for_label(label.as_bytes()).map(Encoding::output_encoding)
+}
+
+fn looks_like_markup(bytes: &[u8]) -> bool {
+    bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'<')
+}
+
 fn bytes_to_utf8_string(bytes: &[u8]) -> String {
     String::from_utf8_lossy(bytes).into_owned()
 }
 
-fn check_body_size(size: usize) -> DaedraResult<()> {
-    if size > MAX_CONTENT_SIZE {
-        return Err(DaedraError::FetchError("Content too large".to_string()));
+/// The error for a `304 Not Modified` to a request that didn't ask whether
+/// the page changed
+fn unasked_not_modified() -> DaedraError {
+    DaedraError::FetchError(format!("HTTP {}", reqwest::StatusCode::NOT_MODIFIED))
+}
+
+/// Cap on the body of one fetch
+#[derive(Debug, Clone, Copy)]
+struct BodyLimit {
+    bytes: usize,
+    /// Whether memory pressure lowered the cap
+    memory_pressure: bool,
+}
+
+impl BodyLimit {
+    /// An error unless `size` bytes fit
+    fn check(self, size: usize) -> DaedraResult<()> {
+        if size > self.bytes {
+            return Err(self.exceeded());
+        }
+        Ok(())
+    }
+
+    /// The error for a body past the cap
+    fn exceeded(self) -> DaedraError {
+        let why = if self.memory_pressure { " while memory is over its cap" } else { "" };
+        DaedraError::FetchError(format!(
+            "Content too large: over the {} limit{why}",
+            size_label(self.bytes)
+        ))
     }
-    Ok(())
+}
+
+/// `bytes` in MiB when a whole number of them, else in bytes
+fn size_label(bytes: usize) -> String {
+    const MIB: usize = 1024 * 1024;
+    if bytes >= MIB && bytes.is_multiple_of(MIB) {
+        format!("{} MiB", bytes / MIB)
+    } else {
+        format!("{bytes} byte")
+    }
+}
+
+/// Read `response`'s body as it arrives, giving up as soon as it passes `limit`
+async fn read_body(response: reqwest::Response, limit: BodyLimit) -> DaedraResult<Vec<u8>> {
+    let declared = response.content_length().unwrap_or(0) as usize;
+    let mut body = Vec::with_capacity(declared.min(limit.bytes));
+    let mut chunks = response.bytes_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(DaedraError::HttpError)?;
+        telemetry::record_bytes(chunk.len());
+        limit.check(body.len() + chunk.len())?;
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
 }
 
 fn extract_pdf_content(bytes: &[u8]) -> DaedraResult<FetchedContent> {
@@ -578,31 +1934,193 @@ fn text_from_selector(document: &Html, selector: &Selector) -> Option<String> {
         .map(|t| clean_title(&t))
 }
 
+/// The publication date a page declares, as written: from its meta tags, then
+/// JSON-LD `datePublished`, then a `<time>` element marking it.
+fn extract_published_date(document: &Html, html: &str) -> Option<String> {
+    let non_empty = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    PUBLISHED_META_SELECTORS
+        .iter()
+        .find_map(|selector| {
+            document.select(selector).find_map(|el| el.value().attr("content").and_then(non_empty))
+        })
+        .or_else(|| {
+            JSON_LD_DATE_PUBLISHED.captures(html).and_then(|c| non_empty(&c[1]))
+        })
+        .or_else(|| {
+            document
+                .select(&PUBLISHED_TIME_SELECTOR)
+                .find_map(|el| el.value().attr("datetime").and_then(non_empty))
+        })
+}
+
+/// The kind of page `document` declares: a schema.org type (JSON-LD or
+/// microdata), then Google Scholar `citation_*` tags, then `og:type`. Generic
+/// types such as `WebPage` or `website` say nothing and are skipped.
+fn extract_page_type(document: &Html, html: &str) -> Option<ContentType> {
+    let json_ld = JSON_LD_TYPE.captures_iter(html).map(|c| c[1].to_string());
+    let microdata = document
+        .select(&ITEMTYPE_SELECTOR)
+        .filter_map(|el| el.value().attr("itemtype"))
+        .flat_map(|types| types.split_whitespace().map(str::to_string).collect::<Vec<_>>());
+    json_ld
+        .chain(microdata)
+        .find_map(|t| schema_org_type(&t))
+        .or_else(|| {
+            document.select(&CITATION_META_SELECTOR).next().map(|_| ContentType::Academic)
+        })
+        .or_else(|| {
+            document
+                .select(&OG_TYPE_SELECTOR)
+                .find_map(|el| el.value().attr("content"))
+                .and_then(og_type)
+        })
+}
+
+/// [`ContentType`] of a schema.org type, by name or URL
+/// (`https://schema.org/BlogPosting`)
+fn schema_org_type(name: &str) -> Option<ContentType> {
+    match name.trim().rsplit(['/', ':']).next()? {
+        "Blog" | "BlogPosting" | "LiveBlogPosting" => Some(ContentType::Blog),
+        "NewsArticle" | "ReportageNewsArticle" | "AnalysisNewsArticle" | "OpinionNewsArticle" => {
+            Some(ContentType::Article)
+        },
+        "ScholarlyArticle" | "MedicalScholarlyArticle" | "Thesis" => Some(ContentType::Academic),
+        "TechArticle" | "APIReference" | "SoftwareSourceCode" => Some(ContentType::Documentation),
+        "QAPage" | "DiscussionForumPosting" => Some(ContentType::Forum),
+        "VideoObject" | "VideoGallery" => Some(ContentType::Video),
+        "Product" | "ProductGroup" | "Offer" | "AggregateOffer" => Some(ContentType::Shopping),
+        "GovernmentOrganization" | "GovernmentOffice" | "GovernmentService" | "Legislation" => {
+            Some(ContentType::Government)
+        },
+        "DefinedTerm" | "DefinedTermSet" | "Dataset" => Some(ContentType::Reference),
+        "ProfilePage" | "SocialMediaPosting" => Some(ContentType::Social),
+        _ => None,
+    }
+}
+
+/// [`ContentType`] of an Open Graph `og:type`
+fn og_type(value: &str) -> Option<ContentType> {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "article" => Some(ContentType::Article),
+        "blog" => Some(ContentType::Blog),
+        "book" => Some(ContentType::Reference),
+        "profile" => Some(ContentType::Social),
+        "product" | "og:product" | "product.item" => Some(ContentType::Shopping),
+        v if v.starts_with("video") => Some(ContentType::Video),
+        _ => None,
+    }
+}
+
+/// [`ContentType`] a response's MIME type implies, for media that has no
+/// page to read metadata from
+pub fn content_type_for_mime(mime: &str) -> Option<ContentType> {
+    let mime = normalize_content_type(mime);
+    (mime.starts_with("video/") || mime.starts_with("audio/")).then_some(ContentType::Video)
+}
+
 fn classify_inferred_mime(mime: &str, bytes: &[u8]) -> Option<FetchedContent> {
     match mime {
         "application/pdf" => extract_pdf_content(bytes).ok(),
-        "text/html" | "application/xhtml+xml" => Some(FetchedContent::Html(bytes_to_utf8_string(bytes))),
+        m if is_html_mime(m) => Some(FetchedContent::Html(bytes_to_utf8_string(bytes))),
         m if is_binary_mime(m) => Some(FetchedContent::Binary {
             mime: m.to_string(),
             size: bytes.len(),
         }),
-        m if m.starts_with("text/") => Some(FetchedContent::Html(bytes_to_utf8_string(bytes))),
+        m if m.starts_with("text/") => Some(FetchedContent::Text {
+            mime: m.to_string(),
+            text: bytes_to_utf8_string(bytes),
+        }),
         _ => None,
     }
 }
 
+fn is_html_mime(mime: &str) -> bool {
+    mime == "text/html" || mime == "application/xhtml+xml"
+}
+
+fn is_json_mime(mime: &str) -> bool {
+    mime == "application/json" || mime.ends_with("+json")
+}
+
+/// Types shown as text rather than converted from markup
+fn is_text_mime(mime: &str) -> bool {
+    !is_html_mime(mime)
+        && (mime.starts_with("text/")
+            || mime.ends_with("+xml")
+            || TEXT_APPLICATION_TYPES.contains(&mime))
+}
+
+const TEXT_APPLICATION_TYPES: &[&str] = &[
+    "application/xml",
+    "application/javascript",
+    "application/x-yaml",
+    "application/yaml",
+    "application/toml",
+];
+
+/// `text` pretty-printed when it parses as JSON
+fn pretty_json(text: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Route a UTF-8 body by its declared type, sniffing the body when the type
+/// is missing or unfamiliar: markup is HTML, an object or array that parses
+/// is JSON, anything else plain text.
+fn classify_text(ct: &str, text: &str) -> FetchedContent {
+    let body = text.trim_start();
+    let json_like = is_json_mime(ct) || (!is_text_mime(ct) && body.starts_with(['{', '[']));
+    if json_like && let Some(json) = pretty_json(text) {
+        return FetchedContent::Json(json);
+    }
+    if is_text_mime(ct) || is_json_mime(ct) {
+        return FetchedContent::Text {
+            mime: ct.to_string(),
+            text: text.to_string(),
+        };
+    }
+    if body.starts_with('<') {
+        FetchedContent::Html(text.to_string())
+    } else {
+        FetchedContent::Text {
+            mime: "text/plain".to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Text of `mime` as Markdown: prose as written, data formats in a code block
+fn fence_text(mime: &str, text: &str) -> String {
+    let language = match mime {
+        "text/csv" => "csv",
+        "text/xml" | "application/xml" => "xml",
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" => "javascript",
+        "application/x-yaml" | "application/yaml" => "yaml",
+        "application/toml" => "toml",
+        m if m.ends_with("+xml") => "xml",
+        _ => return text.trim().to_string(),
+    };
+    format!("```{}\n{}\n```", language, text.trim())
+}
+
 fn classify_by_inference(kind: &infer::Type, bytes: &[u8]) -> Option<FetchedContent> {
     classify_inferred_mime(kind.mime_type(), bytes)
 }
 
 fn classify_by_fallback(content_type: &str, bytes: &[u8]) -> DaedraResult<FetchedContent> {
     let ct = normalize_content_type(content_type);
-    if ct.contains("text/html") {
+    if is_html_mime(&ct) {
         return Ok(FetchedContent::Html(bytes_to_utf8_string(bytes)));
     }
-
-    if std::str::from_utf8(bytes).is_ok() {
-        return Ok(FetchedContent::Html(bytes_to_utf8_string(bytes)));
+    if ct == "application/pdf" {
+        return extract_pdf_content(bytes);
+    }
+    if !is_known_binary_content_type(&ct)
+        && let Ok(text) = std::str::from_utf8(bytes)
+    {
+        return Ok(classify_text(&ct, text));
     }
 
     Ok(FetchedContent::Binary {
@@ -615,8 +2133,17 @@ fn classify_by_fallback(content_type: &str, bytes: &[u8]) -> DaedraResult<Fetche
     })
 }
 
+/// Route a response body by its magic bytes, then by its `Content-Type`.
+///
+/// A declared HTML type wins over a body that merely looks like XML, so
+/// XHTML pages with an `<?xml` prolog are still read as pages.
 fn classify_fetched_content(content_type: &str, bytes: &[u8]) -> DaedraResult<FetchedContent> {
-    if let Some(kind) = infer::get(bytes) {
+    let transcoded = transcode_to_utf8(content_type, bytes);
+    let bytes = transcoded.as_ref();
+    let declared_html = is_html_mime(&normalize_content_type(content_type));
+    if let Some(kind) = infer::get(bytes)
+        && !(declared_html && kind.matcher_type() == infer::MatcherType::Text)
+    {
         if let Some(content) = classify_by_inference(&kind, bytes) {
             return Ok(content);
         }
@@ -673,6 +2200,7 @@ fn title_from_url(url: &str) -> String {
 ///         url: "https://example.com".to_string(),
 ///         selector: None,
 ///         include_images: false,
+///         ..Default::default()
 ///     };
 ///     let content = fetch_page(&args).await?;
 ///     println!("Title: {}", content.title);
@@ -692,34 +2220,474 @@ pub fn is_valid_url(url: &str) -> bool {
     }
 }
 
-/// Convert HTML to Markdown
-fn html_to_markdown(html: &str) -> String {
+/// Convert HTML to Markdown, with tables as pipe tables (see [`crate::tables`])
+/// and preformatted text as fenced code blocks tagged with their language
+pub fn html_to_markdown(html: &str) -> String {
+    let (html, blocks) = take_blocks(html);
     // Use htmd crate for conversion
-    htmd::convert(html).unwrap_or_else(|_| html.to_string())
+    let markdown = htmd::convert(&html).unwrap_or_else(|_| html.to_string());
+    blocks.iter().enumerate().fold(markdown, |markdown, (i, block)| {
+        markdown.replacen(&block_placeholder(i), &format!("\n\n{}\n\n", block), 1)
+    })
 }
 
-/// Clean up Markdown content
-fn clean_markdown(markdown: &str) -> String {
-    markdown
-        .lines()
-        .filter(|line| {
-            let trimmed = line.trim();
-            trimmed != "-" && trimmed != "*" && trimmed != "+"
-        })
-        .fold(String::new(), |mut acc, line| {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                if !acc.ends_with("\n\n") {
-                    acc.push('\n');
+/// Convert HTML to plain text: one paragraph per block element, list items and
+/// table rows on lines of their own with the cells separated by tabs, and
+/// `<pre>` text exactly as written. Scripts and styles are left out.
+pub fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut writer = TextWriter::default();
+    writer.walk(fragment.root_element());
+    writer.out
+}
+
+/// The next page of a paginated article on `page`: where its `rel="next"`
+/// link points, or failing that a link to the same URL with the page number
+/// one past this one's (see [`page_number`]). Only pages on the same origin
+/// count.
+fn next_page_url(document: &Html, page: &Url) -> Option<Url> {
+    let links = |selector: &'static Selector| {
+        document
+            .select(selector)
+            .filter_map(|element| element.value().attr("href"))
+            .filter_map(|href| page.join(href.trim()).ok())
+            .filter(|url| url.origin() == page.origin())
+    };
+    let this = page_number(page);
+    let following = (this.0.clone(), this.1 + 1);
+    links(&REL_NEXT_SELECTOR)
+        .find(|url| page_number(url) != this)
+        .or_else(|| links(&LINK_SELECTOR).find(|url| page_number(url) == following))
+}
+
+/// A URL's page number, from a `page` query parameter or a `/page/N` path
+/// segment (1 when it has neither), and the URL without it, its fragment or
+/// a trailing slash, which every page of the article shares
+fn page_number(url: &Url) -> (String, usize) {
+    let mut base = url.clone();
+    base.set_fragment(None);
+
+    let (numbers, pairs): (Vec<_>, Vec<_>) =
+        url.query_pairs().into_owned().partition(|(key, _)| key == "page");
+    let mut number = numbers.first().and_then(|(_, value)| value.parse().ok());
+    if pairs.is_empty() {
+        base.set_query(None);
+    } else {
+        base.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let segments: Vec<&str> = url.path().split('/').collect();
+    let position = segments
+        .windows(2)
+        .position(|pair| pair[0] == "page" && pair[1].parse::<usize>().is_ok());
+    if number.is_none()
+        && let Some(i) = position
+    {
+        number = segments[i + 1].parse().ok();
+        base.set_path(&[&segments[..i], &segments[i + 2..]].concat().join("/"));
+    }
+    let path = base.path().trim_end_matches('/').to_string();
+    base.set_path(&path);
+
+    (base.to_string(), number.unwrap_or(1))
+}
+
+/// Append the next page of an article to `page`: its content after a blank
+/// line, and its links and images that `page` doesn't have yet
+fn append_page(page: &mut PageContent, next: PageContent) {
+    page.content.push_str("\n\n");
+    page.content.push_str(&next.content);
+    page.word_count += next.word_count;
+
+    if let Some(more) = next.links {
+        let links = page.links.get_or_insert_with(Vec::new);
+        for link in more {
+            if !links.iter().any(|known| known.url == link.url) {
+                links.push(link);
+            }
+        }
+        links.truncate(50);
+    }
+    if let Some(more) = next.images {
+        let images = page.images.get_or_insert_with(Vec::new);
+        for image in more {
+            if !images.iter().any(|known| known.url == image.url) {
+                images.push(image);
+            }
+        }
+    }
+}
+
+/// The `<body>` of a document holding nothing but preformatted and bare text
+/// (a log, a changelog, the `<pre>` a browser wraps a text file in), which
+/// needs neither readability nor the Markdown converter; `None` for any
+/// other document
+fn plain_text_body(document: &Html) -> Option<ElementRef<'_>> {
+    let body = document.select(&BODY_SELECTOR).next()?;
+    let mut has_text = false;
+    for child in body.children() {
+        match child.value() {
+            Node::Text(text) => has_text |= !text.trim().is_empty(),
+            Node::Element(element) => match element.name() {
+                "pre" => has_text = true,
+                "br" | "hr" | "script" | "style" | "noscript" | "template" => {},
+                _ => return None,
+            },
+            _ => {},
+        }
+    }
+    has_text.then_some(body)
+}
+
+/// Content of a [`plain_text_body`] in `format`: `<pre>` blocks as fenced
+/// code and bare text as paragraphs in Markdown, both as written in text
+fn plain_text_content(body: ElementRef<'_>, format: PageFormat) -> String {
+    match format {
+        PageFormat::Markdown | PageFormat::Json => {
+            let mut blocks = Vec::new();
+            let mut text = String::new();
+            for child in body.children() {
+                if let Node::Text(run) = child.value() {
+                    text.push_str(run);
+                    continue;
                 }
-            } else {
-                acc.push_str(trimmed);
-                acc.push('\n');
+                let paragraph = text::collapse_whitespace(&std::mem::take(&mut text));
+                if !paragraph.is_empty() {
+                    blocks.push(paragraph);
+                }
+                let pre = ElementRef::wrap(child).filter(|el| el.value().name() == "pre");
+                blocks.extend(pre.map(code_block));
             }
-            acc
+            let paragraph = text::collapse_whitespace(&text);
+            if !paragraph.is_empty() {
+                blocks.push(paragraph);
+            }
+            blocks.join("\n\n")
+        },
+        PageFormat::Text => {
+            let mut writer = TextWriter::default();
+            writer.walk(body);
+            writer.out
+        },
+        PageFormat::Html => strip_scripts(&body.html()).trim().to_string(),
+    }
+}
+
+/// Plain text being written by [`html_to_text`], with the whitespace and line
+/// breaks owed before the next text
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    /// Line breaks before the next text: 1 for a new line, 2 for a paragraph
+    breaks: usize,
+    space: bool,
+}
+
+impl TextWriter {
+    fn walk(&mut self, element: ElementRef<'_>) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.inline(text),
+                Node::Element(_) => {
+                    let Some(child) = ElementRef::wrap(child) else { continue };
+                    let name = child.value().name();
+                    match name {
+                        "script" | "style" | "noscript" | "template" | "head" => {},
+                        "br" => self.line_break(1),
+                        "pre" => {
+                            self.line_break(2);
+                            let code = code_text(child);
+                            self.raw(code.strip_suffix('\n').unwrap_or(&code));
+                            self.line_break(2);
+                        },
+                        "td" | "th" => {
+                            if child.prev_siblings().any(|s| ElementRef::wrap(s).is_some()) {
+                                self.space = false;
+                                self.raw("\t");
+                            }
+                            self.walk(child);
+                        },
+                        "li" | "tr" | "dt" | "dd" => {
+                            self.line_break(1);
+                            self.walk(child);
+                            self.line_break(1);
+                        },
+                        _ if is_block_element(name) => {
+                            self.line_break(2);
+                            self.walk(child);
+                            self.line_break(2);
+                        },
+                        _ => self.walk(child),
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Text with its whitespace collapsed
+    fn inline(&mut self, text: &str) {
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        for (i, word) in text.split_whitespace().enumerate() {
+            if i > 0 {
+                self.space = true;
+            }
+            self.raw(word);
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.space = true;
+        }
+    }
+
+    /// Text as it is, after the breaks or space owed
+    fn raw(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.out.is_empty() {
+            if self.breaks > 0 {
+                self.out.truncate(self.out.trim_end_matches(' ').len());
+                self.out.push_str(&"\n".repeat(self.breaks));
+            } else if self.space {
+                self.out.push(' ');
+            }
+        }
+        self.out.push_str(text);
+        self.breaks = 0;
+        self.space = false;
+    }
+
+    fn line_break(&mut self, breaks: usize) {
+        self.breaks = self.breaks.max(breaks);
+        self.space = false;
+    }
+}
+
+/// `html` without its scripts, styles and templates
+fn strip_scripts(html: &str) -> String {
+    let mut fragment = Html::parse_fragment(html);
+    let hidden: Vec<_> = fragment.select(&HIDDEN_SELECTOR).map(|element| element.id()).collect();
+    for id in hidden {
+        if let Some(mut node) = fragment.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    fragment.root_element().inner_html()
+}
+
+fn is_block_element(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "section"
+            | "article"
+            | "main"
+            | "header"
+            | "footer"
+            | "nav"
+            | "aside"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "ul"
+            | "ol"
+            | "dl"
+            | "blockquote"
+            | "table"
+            | "caption"
+            | "figure"
+            | "figcaption"
+            | "hr"
+            | "form"
+            | "fieldset"
+            | "address"
+            | "details"
+            | "summary"
+    )
+}
+
+/// `html` with each outermost table and `<pre>` replaced by a placeholder word
+/// for the converter to pass through, and those blocks as Markdown
+fn take_blocks(html: &str) -> (Cow<'_, str>, Vec<String>) {
+    let lowered = html.to_ascii_lowercase();
+    if !lowered.contains("<table") && !lowered.contains("<pre") {
+        return (Cow::Borrowed(html), Vec::new());
+    }
+    let lowered = html.get(..1024).unwrap_or(html).to_ascii_lowercase();
+    let is_document = lowered.contains("<html") || lowered.contains("<body");
+    let mut parsed = if is_document {
+        Html::parse_document(html)
+    } else {
+        Html::parse_fragment(html)
+    };
+
+    let blocks: Vec<_> = parsed
+        .select(&BLOCK_SELECTOR)
+        .filter(|block| {
+            !block
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|ancestor| matches!(ancestor.value().name(), "table" | "pre"))
         })
-        .trim()
-        .to_string()
+        .map(|block| match block.value().name() {
+            "table" => (block.id(), tables::to_markdown(block)),
+            _ => (block.id(), code_block(block)),
+        })
+        .collect();
+    for (i, (id, _)) in blocks.iter().enumerate() {
+        if let Some(mut node) = parsed.tree.get_mut(*id) {
+            let text = block_placeholder(i).as_str().into();
+            node.insert_before(Node::Text(scraper::node::Text { text }));
+            node.detach();
+        }
+    }
+
+    let html = if is_document {
+        parsed.html()
+    } else {
+        parsed.root_element().inner_html()
+    };
+    (Cow::Owned(html), blocks.into_iter().map(|(_, markdown)| markdown).collect())
+}
+
+/// Word standing in for the `i`th block while the rest of the page is converted
+fn block_placeholder(i: usize) -> String {
+    format!("xdaedrablock{}x", i)
+}
+
+/// A `<pre>` as a fenced code block: its text exactly as written (syntax
+/// highlighting markup dropped, `<br>` as line breaks), tagged with the
+/// language its classes name
+fn code_block(pre: ElementRef<'_>) -> String {
+    let code = code_text(pre);
+    let code = code.strip_suffix('\n').unwrap_or(&code);
+
+    // Longer than any run of backticks in the code
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let language = code_language(pre).unwrap_or_default();
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+/// Text of a `<pre>` exactly as written, with `<br>` as line breaks
+fn code_text(pre: ElementRef<'_>) -> String {
+    let mut code = String::new();
+    for node in pre.descendants() {
+        match node.value() {
+            Node::Text(text) => code.push_str(text),
+            Node::Element(element) if element.name() == "br" => code.push('\n'),
+            _ => {},
+        }
+    }
+    code
+}
+
+/// Language of a code block, from a `language-*`, `lang-*` or `highlight-*`
+/// class or a `data-lang` attribute on the `<pre>`, its `<code>` or its parent
+fn code_language(pre: ElementRef<'_>) -> Option<String> {
+    let code = pre
+        .children()
+        .filter_map(ElementRef::wrap)
+        .find(|child| child.value().name() == "code");
+    let parent = pre.parent().and_then(ElementRef::wrap);
+    [code, Some(pre), parent].into_iter().flatten().find_map(|element| {
+        let element = element.value();
+        let from_class = element.classes().find_map(|class| {
+            ["language-", "lang-", "highlight-source-", "highlight-"]
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix))
+        });
+        from_class
+            .or_else(|| element.attr("data-lang"))
+            .or_else(|| element.attr("data-language"))
+            .map(|language| language.trim().to_ascii_lowercase())
+            .filter(|language| {
+                !language.is_empty()
+                    && language
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '#' | '.'))
+            })
+    })
+}
+
+/// Clean up Markdown content: trim lines, drop bare list markers, collapse blank runs,
+/// and decode HTML entities left in the text (fenced code blocks are kept as written)
+pub fn clean_markdown(markdown: &str) -> String {
+    let mut out = String::new();
+    // Backticks that opened the code block being copied
+    let mut fence: Option<&str> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(opening) = fence {
+            let closing = trimmed.starts_with(opening) && trimmed.chars().all(|c| c == '`');
+            if closing {
+                fence = None;
+            }
+            out.push_str(if closing { trimmed } else { line });
+            out.push('\n');
+        } else if trimmed == "-" || trimmed == "*" || trimmed == "+" {
+            continue;
+        } else if trimmed.is_empty() {
+            if !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+        } else if trimmed.starts_with("```") {
+            fence = Some(&trimmed[..trimmed.len() - trimmed.trim_start_matches('`').len()]);
+            out.push_str(trimmed);
+            out.push('\n');
+        } else {
+            out.push_str(&text::decode_entities(trimmed));
+            out.push('\n');
+        }
+    }
+    out.trim().to_string()
+}
+
+/// The opening prose of a Markdown page: its first `paragraphs` paragraphs of
+/// running text, as plain text, cut to about `max_chars` at a word boundary.
+///
+/// Headings, lists, tables, quotes, code and short lines such as bylines are
+/// skipped. `None` when the page has no such paragraph.
+pub fn lead_paragraphs(markdown: &str, paragraphs: usize, max_chars: usize) -> Option<String> {
+    const MIN_PARAGRAPH_CHARS: usize = 60;
+    let mut in_code = false;
+    let lead: Vec<String> = markdown
+        .split("\n\n")
+        .filter(|block| {
+            let fenced = block.matches("```").count() % 2 == 1;
+            let inside = in_code || block.trim_start().starts_with("```");
+            in_code ^= fenced;
+            !inside
+        })
+        .map(|block| block.trim())
+        .filter(|block| !block.starts_with(['#', '-', '*', '+', '>', '|', '<']))
+        .filter(|block| {
+            let numbered = block.split_once(". ").map(|(n, _)| n);
+            !numbered.is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|block| {
+            let unlinked = MARKDOWN_LINK.replace_all(block, "$1");
+            text::collapse_whitespace(&unlinked.replace("**", ""))
+        })
+        .filter(|text| text.chars().count() >= MIN_PARAGRAPH_CHARS)
+        .take(paragraphs)
+        .collect();
+    if lead.is_empty() {
+        return None;
+    }
+
+    let mut lead = lead.join("\n\n");
+    if let Some((end, _)) = lead.char_indices().nth(max_chars) {
+        let end = lead[..end].rfind(char::is_whitespace).unwrap_or(end);
+        lead.truncate(end);
+        lead.push('…');
+    }
+    Some(lead)
 }
 
 /// Clean up a page title
@@ -751,7 +2719,7 @@ impl FetchClient {
         selector: Option<&str>,
     ) -> DaedraResult<String> {
         let document = Html::parse_document(html);
-        self.extract_content(html, &document, "", selector)
+        Ok(self.extract_content(html, &document, "", selector, None, PageFormat::Markdown)?.0)
     }
 
     /// Same path as [`FetchClient::build_page_from_html`] without HTTP.
@@ -762,7 +2730,7 @@ impl FetchClient {
         selector: Option<&str>,
     ) -> DaedraResult<PageContent> {
         let parsed_url = validate_url(url)?;
-        self.build_page_from_html(html, url, &parsed_url, selector)
+        self.build_page_from_html(html, url, &parsed_url, selector, false, PageFormat::Markdown)
     }
 
     /// Exposes bot-protection checks for unit tests.
@@ -786,6 +2754,7 @@ impl FetchClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::LinkKind;
 
     const CELIACHIA_FIXTURE: &str = include_str!("../../tests/fixtures/celiachia.html");
     const CELIACHIA_ARTICLE_MARKER: &str = "indagine 2023 su";
@@ -849,25 +2818,113 @@ mod tests {
     }
 
     #[test]
-    fn test_clean_markdown_strips_list_markers() {
-        let input = "Content\n-\n*\n+\nMore";
-        assert_eq!(clean_markdown(input), "Content\nMore");
+    fn test_clean_markdown_strips_list_markers() {
+        let input = "Content\n-\n*\n+\nMore";
+        assert_eq!(clean_markdown(input), "Content\nMore");
+    }
+
+    #[test]
+    fn test_clean_markdown_preserves_content() {
+        let input = "# Heading\n\nParagraph with **bold** text.";
+        assert_eq!(clean_markdown(input), "# Heading\n\nParagraph with **bold** text.");
+    }
+
+    #[test]
+    fn test_clean_markdown_decodes_entities_outside_code() {
+        let input = "Caf&eacute; &#8217;s\n\n```html\n&lt;p&gt;\n```\nR&amp;D";
+        assert_eq!(clean_markdown(input), "Café \u{2019}s\n\n```html\n&lt;p&gt;\n```\nR&D");
+    }
+
+    #[test]
+    fn test_clean_markdown_keeps_code_blocks_as_written() {
+        let input = "  Intro  \n````text\n  indented\n-\n\n\n\n```\n  still code\n````\n  Outro";
+        assert_eq!(
+            clean_markdown(input),
+            "Intro\n````text\n  indented\n-\n\n\n\n```\n  still code\n````\nOutro"
+        );
+    }
+
+    #[test]
+    fn test_clean_markdown_empty_input() {
+        assert_eq!(clean_markdown(""), "");
+    }
+
+    #[test]
+    fn test_clean_markdown_only_blanks() {
+        assert_eq!(clean_markdown("\n\n\n\n"), "");
+    }
+
+    #[test]
+    fn test_lead_paragraphs_skips_non_prose() {
+        let markdown = "# Tokio\n\nBy Jane Doe\n\n- [Home](/)\n- Docs\n\n\
+            ```rust\nfn main() {}\n\n\
+            // a comment long enough to pass as a paragraph of text\n```\n\n\
+            Tokio is an **asynchronous runtime** for the [Rust](https://rust-lang.org) \
+            programming language.\n\n\
+            It provides the building blocks needed for writing network applications.\n\n\
+            A third paragraph that is not part of the lead at all, however long it is.";
+        let lead = lead_paragraphs(markdown, 2, 500).unwrap();
+        assert_eq!(
+            lead,
+            "Tokio is an asynchronous runtime for the Rust programming language.\n\n\
+             It provides the building blocks needed for writing network applications."
+        );
     }
 
     #[test]
-    fn test_clean_markdown_preserves_content() {
-        let input = "# Heading\n\nParagraph with **bold** text.";
-        assert_eq!(clean_markdown(input), "# Heading\n\nParagraph with **bold** text.");
+    fn test_lead_paragraphs_caps_length() {
+        let markdown = "word ".repeat(100);
+        let lead = lead_paragraphs(&markdown, 2, 42).unwrap();
+        assert!(lead.ends_with("word…"), "{lead}");
+        assert!(lead.chars().count() <= 43);
+        assert_eq!(lead_paragraphs("# Only a heading\n\n- a list", 2, 100), None);
     }
 
     #[test]
-    fn test_clean_markdown_empty_input() {
-        assert_eq!(clean_markdown(""), "");
+    fn test_extract_published_date_sources() {
+        let date = |html: &str| extract_published_date(&Html::parse_document(html), html);
+        assert_eq!(
+            date(r#"<head><meta name="date" content="2020-01-01">
+                <meta property="article:published_time" content=" 2024-03-12T09:00:00Z "></head>"#)
+            .as_deref(),
+            Some("2024-03-12T09:00:00Z")
+        );
+        assert_eq!(
+            date(r#"<script type="application/ld+json">{"@type":"Article",
+                "datePublished": "2023-05-18T14:33:42+00:00"}</script>"#)
+            .as_deref(),
+            Some("2023-05-18T14:33:42+00:00")
+        );
+        assert_eq!(
+            date(r#"<footer><time datetime="2019-01-01">x</time></footer>
+                <article><time datetime="2022-07-04">July 4</time></article>"#)
+            .as_deref(),
+            Some("2022-07-04")
+        );
+        assert_eq!(date(r#"<meta property="article:published_time" content="">"#), None);
     }
 
     #[test]
-    fn test_clean_markdown_only_blanks() {
-        assert_eq!(clean_markdown("\n\n\n\n"), "");
+    fn test_extract_page_type_sources() {
+        let page_type = |html: &str| extract_page_type(&Html::parse_document(html), html);
+        let json_ld = r#"<script type="application/ld+json">
+            {"@context":"https://schema.org","@type":["BlogPosting","Article"]}</script>"#;
+        assert_eq!(page_type(json_ld), Some(ContentType::Blog));
+        let unknown_first = r#"<script type="application/ld+json">[{"@type":"WebSite"},
+            {"@type":"ScholarlyArticle"}]</script><meta property="og:type" content="article">"#;
+        assert_eq!(page_type(unknown_first), Some(ContentType::Academic));
+        let microdata = r#"<div itemscope itemtype="https://schema.org/QAPage"></div>"#;
+        assert_eq!(page_type(microdata), Some(ContentType::Forum));
+        let citation = r#"<meta name="citation_doi" content="10.1145/1">
+            <meta property="og:type" content="article">"#;
+        assert_eq!(page_type(citation), Some(ContentType::Academic));
+        assert_eq!(
+            page_type(r#"<meta property="og:type" content="video.other">"#),
+            Some(ContentType::Video)
+        );
+        assert_eq!(page_type(r#"<meta property="og:type" content="website">"#), None);
+        assert_eq!(content_type_for_mime("video/mp4; codecs=avc1"), Some(ContentType::Video));
+        assert_eq!(content_type_for_mime("application/pdf"), None);
     }
 
     #[test]
@@ -879,6 +2936,44 @@ mod tests {
         assert!(markdown.contains("bold"));
     }
 
+    #[test]
+    fn test_html_to_markdown_renders_tables() {
+        let html = "<h2>Pricing</h2><div><table><tr><th>Plan</th><th>Price</th></tr>\
+            <tr><td>Pro</td><td>$10 <table><tr><td>billed yearly</td></tr></table></td></tr>\
+            </table><p>Prices exclude VAT.</p><TABLE><TR><TD>a</TD><TD>b</TD></TR></TABLE></div>";
+        let markdown = clean_markdown(&html_to_markdown(html));
+        assert!(
+            markdown.contains("| Plan | Price |\n| --- | --- |\n| Pro | $10 billed yearly |"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains("| a | b |\n| --- | --- |"), "{}", markdown);
+        assert!(markdown.contains("Prices exclude VAT."));
+        assert!(!markdown.contains("xdaedrablock"));
+        let table_at = markdown.find("| Plan").unwrap();
+        assert!(markdown.find("Pricing").unwrap() < table_at);
+        assert!(table_at < markdown.find("Prices exclude").unwrap());
+
+        let (unchanged, blocks) = take_blocks("<p>No tables here</p>");
+        assert!(matches!(unchanged, Cow::Borrowed(_)) && blocks.is_empty());
+    }
+
+    #[test]
+    fn test_html_to_markdown_keeps_code_blocks_exact() {
+        let html = "<p>Run it:</p><div class=\"highlight-python\"><pre><span class=\"k\">def\
+            </span> main():\n    <span>if</span> x &lt; 1:\n\n        return `x`\n</pre></div>\
+            <pre><code class=\"hljs language-Rust\">fn main() {\n    println!(\"```\");\n}</code>\
+            </pre><pre data-lang=\"shell\">cargo run<br>cargo test</pre><pre>plain</pre>";
+        let markdown = clean_markdown(&html_to_markdown(html));
+        let python = "```python\ndef main():\n    if x < 1:\n\n        return `x`\n```";
+        assert!(markdown.contains(python), "{}", markdown);
+        let rust = "````rust\nfn main() {\n    println!(\"```\");\n}\n````";
+        assert!(markdown.contains(rust), "{}", markdown);
+        assert!(markdown.contains("```shell\ncargo run\ncargo test\n```"), "{}", markdown);
+        assert!(markdown.contains("```\nplain\n```"), "{}", markdown);
+        assert!(markdown.find("Run it:").unwrap() < markdown.find("```python").unwrap());
+    }
+
     #[test]
     fn test_classify_fetched_content_html() {
         let bytes = b"<html><body><p>Hello</p></body></html>";
@@ -904,9 +2999,344 @@ mod tests {
     fn test_classify_fetched_content_fallback_utf8() {
         let bytes = b"plain text without magic bytes";
         let result = classify_fetched_content("", bytes).unwrap();
+        assert!(matches!(result, FetchedContent::Text { .. }));
+    }
+
+    #[test]
+    fn test_classify_fetched_content_sniffs_json_and_text() {
+        let json = classify_fetched_content("application/json", br#"{"a":[1,2]}"#).unwrap();
+        assert!(matches!(json, FetchedContent::Json(ref j) if j.contains("\n  \"a\": [")));
+        let undeclared = classify_fetched_content("", b"  [1, 2, 3]").unwrap();
+        assert!(matches!(undeclared, FetchedContent::Json(_)));
+        let broken = classify_fetched_content("application/json", b"{not json").unwrap();
+        assert!(matches!(broken, FetchedContent::Text { .. }));
+        let markdown = classify_fetched_content("text/markdown", b"# Notes\n\n[x]").unwrap();
+        assert!(matches!(markdown, FetchedContent::Text { mime, .. } if mime == "text/markdown"));
+        let page = classify_fetched_content("", b"<p>untyped page</p>").unwrap();
+        assert!(matches!(page, FetchedContent::Html(_)));
+    }
+
+    #[test]
+    fn test_classify_fetched_content_trusts_magic_over_header() {
+        let png: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00];
+        let result = classify_fetched_content("text/html", png).unwrap();
+        assert!(matches!(result, FetchedContent::Binary { mime, .. } if mime == "image/png"));
+        let declared = classify_fetched_content("image/gif", b"GIF-ish text").unwrap();
+        assert!(matches!(declared, FetchedContent::Binary { mime, .. } if mime == "image/gif"));
+        let xhtml = b"<?xml version=\"1.0\"?><html><body><p>Hi</p></body></html>";
+        let result = classify_fetched_content("text/html; charset=utf-8", xhtml).unwrap();
         assert!(matches!(result, FetchedContent::Html(_)));
     }
 
+    #[test]
+    fn test_transcode_to_utf8_follows_declared_charsets() {
+        // "中文" in GBK, declared in the header
+        let gbk: &[u8] = b"<html><body><p>\xd6\xd0\xce\xc4</p></body></html>";
+        let decoded = transcode_to_utf8("text/html; charset=GBK", gbk);
+        assert!(String::from_utf8_lossy(&decoded).contains("中文"));
+
+        // "日本" in Shift_JIS, declared in a meta tag
+        let sjis: &[u8] = b"<meta http-equiv=\"Content-Type\" content=\"text/html; \
+            charset=Shift_JIS\"><p>\x93\xfa\x96\x7b</p>";
+        assert!(String::from_utf8_lossy(&transcode_to_utf8("text/html", sjis)).contains("日本"));
+
+        // Undeclared Latin-1 reads as windows-1252
+        let latin1: &[u8] = b"<p>caf\xe9 cr\xe8me</p>";
+        assert_eq!(transcode_to_utf8("", latin1).as_ref(), "<p>café crème</p>".as_bytes());
+
+        let utf8 = "<meta charset=utf-8><p>naïve</p>".as_bytes();
+        assert!(matches!(transcode_to_utf8("text/html", utf8), Cow::Borrowed(_)));
+        let binary: &[u8] = &[0x80, 0x81, 0x82, 0x83];
+        assert!(matches!(transcode_to_utf8("", binary), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_charset_sniffing_helpers() {
+        assert_eq!(charset_param("text/html; Charset=\"iso-8859-1\""), Some("iso-8859-1"));
+        assert_eq!(charset_param("text/html"), None);
+        assert_eq!(sniff_declared_charset(b"<meta charset='euc-kr'>"), Some(encoding_rs::EUC_KR));
+        assert_eq!(sniff_declared_charset(b"<meta charset=utf-16le>"), Some(UTF_8));
+        assert_eq!(
+            sniff_declared_charset(b"<?xml version=\"1.0\" encoding=\"windows-1251\"?><rss/>"),
+            Some(encoding_rs::WINDOWS_1251)
+        );
+        assert_eq!(sniff_declared_charset(b"<p>no declaration</p>"), None);
+    }
+
+    #[test]
+    fn test_classify_fetched_content_decodes_legacy_text() {
+        let latin1 = "text/plain; charset=iso-8859-1";
+        let result = classify_fetched_content(latin1, b"na\xefve").unwrap();
+        assert!(matches!(result, FetchedContent::Text { text, .. } if text == "naïve"));
+    }
+
+    #[test]
+    fn test_fence_text() {
+        assert_eq!(fence_text("text/plain", "  hello \n"), "hello");
+        assert_eq!(fence_text("text/csv", "a,b\n1,2\n"), "```csv\na,b\n1,2\n```");
+        assert_eq!(fence_text("application/rss+xml", "<rss/>"), "```xml\n<rss/>\n```");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_routes_by_content_type() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let png: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00];
+        Mock::given(path("/logo.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(png, "image/png"))
+            .mount(&server)
+            .await;
+        let json = ResponseTemplate::new(200).set_body_raw(r#"{"ok":true}"#, "application/json");
+        Mock::given(path("/data.json")).respond_with(json).mount(&server).await;
+        let text = ResponseTemplate::new(200).set_body_raw("line one\nline two", "text/plain");
+        Mock::given(path("/notes.txt")).respond_with(text).mount(&server).await;
+
+        let client = FetchClient::new().unwrap();
+        let fetch = |name: &str| VisitPageArgs {
+            url: format!("{}/{}", server.uri(), name),
+            ..Default::default()
+        };
+        let err = client.fetch(&fetch("logo.png")).await.unwrap_err().to_string();
+        assert!(err.contains("unsupported content type image/png"), "{err}");
+
+        let json = client.fetch(&fetch("data.json")).await.unwrap();
+        assert_eq!(json.title, "data.json");
+        assert_eq!(json.content, "```json\n{\n  \"ok\": true\n}\n```");
+
+        let text = client.fetch(&fetch("notes.txt")).await.unwrap();
+        assert_eq!(text.content, "line one\nline two");
+        assert_eq!(text.word_count, 4);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_respects_robots_txt() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let robots = "User-agent: *\nDisallow: /private\n";
+        Mock::given(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(robots))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let page = ResponseTemplate::new(200).set_body_raw("secret notes", "text/plain");
+        Mock::given(path("/private/notes.txt")).respond_with(page).expect(1).mount(&server).await;
+        let page = ResponseTemplate::new(200).set_body_raw("public notes", "text/plain");
+        Mock::given(path("/notes.txt")).respond_with(page).mount(&server).await;
+
+        let client = FetchClient::new().unwrap();
+        let fetch = |name: &str| VisitPageArgs {
+            url: format!("{}/{}", server.uri(), name),
+            ..Default::default()
+        };
+        let err = client.fetch(&fetch("private/notes.txt")).await.unwrap_err();
+        assert!(matches!(err, DaedraError::DisallowedByRobots(_)), "{err}");
+        assert_eq!(client.fetch(&fetch("notes.txt")).await.unwrap().content, "public notes");
+
+        // Ignoring robots.txt, the disallowed page is fetched
+        let client = client.with_ignore_robots(true);
+        let page = client.fetch(&fetch("private/notes.txt")).await.unwrap();
+        assert_eq!(page.content, "secret notes");
+    }
+
+    #[tokio::test]
+    async fn test_domain_policy_refuses_pages_and_redirects() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let redirect = ResponseTemplate::new(302).insert_header("location", "http://outside.example/");
+        Mock::given(path("/away")).respond_with(redirect).expect(1).mount(&server).await;
+        let page = ResponseTemplate::new(200).set_body_raw("inside", "text/plain");
+        Mock::given(path("/here")).respond_with(page).mount(&server).await;
+
+        let allowed = ["127.0.0.1".to_string()];
+        let client = FetchClient::new()
+            .unwrap()
+            .with_domain_policy(DomainPolicy::new(&allowed, &[]).unwrap())
+            .unwrap();
+        let fetch = |url: String| VisitPageArgs {
+            url,
+            ..Default::default()
+        };
+        let page = client.fetch(&fetch(format!("{}/here", server.uri()))).await.unwrap();
+        assert_eq!(page.content, "inside");
+        let err = client.fetch(&fetch(format!("{}/away", server.uri()))).await.unwrap_err();
+        assert!(matches!(&err, DaedraError::DomainNotAllowed(host) if host == "outside.example"), "{err}");
+        let err = client.fetch(&fetch("https://outside.example/".to_string())).await.unwrap_err();
+        assert!(matches!(err, DaedraError::DomainNotAllowed(_)), "{err}");
+        let err = client.check_url("https://outside.example/").await.unwrap_err();
+        assert!(matches!(err, DaedraError::DomainNotAllowed(_)), "{err}");
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(
+            page_number(&url("https://e.com/a?id=5&page=3#top")),
+            ("https://e.com/a?id=5".to_string(), 3)
+        );
+        let blog = "https://e.com/blog".to_string();
+        assert_eq!(page_number(&url("https://e.com/blog/page/2/")), (blog.clone(), 2));
+        assert_eq!(page_number(&url("https://e.com/blog/")), (blog, 1));
+
+        let next = |html: &str, page: &str| {
+            next_page_url(&Html::parse_document(html), &url(page)).map(String::from)
+        };
+        let numbered = r#"<a href="?page=1">1</a> <a href="?page=3">3</a> <a href="?page=2">2</a>"#;
+        assert_eq!(
+            next(numbered, "https://e.com/list").as_deref(),
+            Some("https://e.com/list?page=2")
+        );
+        assert_eq!(
+            next(numbered, "https://e.com/list?page=2").as_deref(),
+            Some("https://e.com/list?page=3")
+        );
+        let rel = r#"<link rel="next" href="https://other.example/2"><a rel="next" href="/p2">"#;
+        assert_eq!(next(rel, "https://e.com/p1").as_deref(), Some("https://e.com/p2"));
+        assert_eq!(next(r#"<a href="/about">About</a>"#, "https://e.com/p1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_extracts_pages_on_blocking_threads() {
+        use wiremock::matchers::path_regex;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let html = "<html><head><title>Busy</title></head><body><main>\
+            <p>One of many pages extracted at once.</p></main></body></html>";
+        Mock::given(path_regex("^/[0-9]$"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        // One extraction at a time: the others wait for the permit
+        let client = FetchClient::with_extraction_threads(1).unwrap();
+        let fetches = (0..4).map(|i| {
+            let args = VisitPageArgs {
+                url: format!("{}/{i}", server.uri()),
+                selector: Some("main".to_string()),
+                ..Default::default()
+            };
+            let client = client.clone();
+            async move { client.fetch(&args).await }
+        });
+        for page in futures::future::join_all(fetches).await {
+            assert!(page.unwrap().content.contains("many pages"));
+        }
+        assert_eq!(client.extraction.available_permits(), 1);
+        let at_least_one = FetchClient::with_extraction_threads(0).unwrap();
+        assert_eq!(at_least_one.extraction.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_follows_pagination() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let pages = [
+            ("/story", r#"<link rel="next" href="/story/page/2/"></head><body><main>
+                <p>Part one of the story.</p></main>"#),
+            ("/story/page/2/", r#"</head><body><main><p>Part two of the story.</p>
+                <a href="/story">1</a> <a href="/story/page/3">3</a></main>"#),
+            ("/story/page/3", r#"</head><body><main><p>Part three of the story.</p>
+                <a href="/story/page/2/">2</a></main>"#),
+        ];
+        for ((route, body), fetches) in pages.into_iter().zip([3, 2, 1]) {
+            let html = format!("<html><head><title>Story</title>{body}</body></html>");
+            Mock::given(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+                .expect(fetches)
+                .mount(&server)
+                .await;
+        }
+
+        let client = FetchClient::new().unwrap();
+        let fetch = |follow_pagination| VisitPageArgs {
+            url: format!("{}/story", server.uri()),
+            selector: Some("main".to_string()),
+            follow_pagination,
+            ..Default::default()
+        };
+        let joined = client.fetch(&fetch(Some(5))).await.unwrap();
+        assert_eq!(joined.title, "Story");
+        let expected = ["/story", "/story/page/2/", "/story/page/3"].map(|p| server.uri() + p);
+        assert_eq!(joined.pages.as_deref(), Some(&expected[..]));
+        let part = |n| joined.content.find(n).unwrap();
+        assert!(part("Part one") < part("Part two") && part("Part two") < part("Part three"));
+
+        let two = client.fetch(&fetch(Some(2))).await.unwrap();
+        assert_eq!(two.pages.unwrap().len(), 2);
+        assert!(!two.content.contains("Part three"));
+        let one = client.fetch(&fetch(None)).await.unwrap();
+        assert_eq!(one.pages, None);
+        assert!(!one.content.contains("Part two"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_reads_dead_links_from_the_archive() {
+        use wiremock::matchers::{path, path_regex, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let gone = format!("{}/gone", server.uri());
+        let snapshot = format!("{}/web/20230405060708/{gone}", server.uri());
+        for route in ["/gone", "/missing"] {
+            Mock::given(path(route))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(path("/wayback/available"))
+            .and(query_param("url", gone.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "archived_snapshots": {"closest": {
+                    "available": true, "status": "200",
+                    "url": snapshot, "timestamp": "20230405060708"
+                }}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/wayback/available"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "archived_snapshots": {}
+            })))
+            .mount(&server)
+            .await;
+        let html = "<html><head><title>Old page</title></head><body><main>\
+            <p>What the page said before it was taken down.</p></main></body></html>";
+        Mock::given(path_regex("^/web/20230405060708id_/"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = FetchClient::new()
+            .unwrap()
+            .with_archive_endpoint(format!("{}/wayback/available", server.uri()));
+        let visit = |url: &str, allow_archive| VisitPageArgs {
+            url: url.to_string(),
+            allow_archive,
+            ..Default::default()
+        };
+        let page = client.fetch(&visit(&gone, true)).await.unwrap();
+        assert_eq!(page.url, snapshot);
+        assert_eq!(page.timestamp.to_string(), "2023-04-05T06:07:08Z");
+        assert!(page.content.contains("before it was taken down"), "{}", page.content);
+
+        let error = client.fetch(&visit(&gone, false)).await.unwrap_err();
+        assert!(error.to_string().contains("HTTP 404"), "{error}");
+        let missing = format!("{}/missing", server.uri());
+        let error = client.fetch(&visit(&missing, true)).await.unwrap_err();
+        assert!(is_dead_link(&error), "{error}");
+        assert!(!is_dead_link(&DaedraError::FetchError("HTTP 500 Internal Server Error".into())));
+    }
+
     #[test]
     fn test_classify_fetched_content_fallback_binary() {
         let bytes: &[u8] = &[0x80, 0x81, 0x82, 0x83];
@@ -922,6 +3352,496 @@ mod tests {
         );
     }
 
+    fn probe_headers(content_type: &str, length: &str) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_TYPE, content_type.parse().unwrap());
+        headers.insert(reqwest::header::CONTENT_LENGTH, length.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_probe_from_head() {
+        use reqwest::StatusCode;
+
+        let headers = probe_headers("text/html; charset=utf-8", "512");
+        let page = probe_from_head(StatusCode::OK, &headers, DEFAULT_MAX_PAGE_BYTES);
+        assert!(page.fetchable);
+        assert_eq!(page.content_type.as_deref(), Some("text/html"));
+        assert_eq!(page.content_length, Some(512));
+        assert_eq!(page.reason, None);
+
+        let probe = |status, headers| probe_from_head(status, &headers, DEFAULT_MAX_PAGE_BYTES);
+        let pdf = probe(StatusCode::OK, probe_headers("application/pdf", "9000"));
+        assert!(pdf.fetchable);
+
+        let video = probe(StatusCode::OK, probe_headers("video/mp4", "9000"));
+        assert!(!video.fetchable);
+        assert_eq!(video.reason.as_deref(), Some("binary content (video/mp4)"));
+
+        let huge = probe(StatusCode::OK, probe_headers("text/html", "99999999"));
+        assert_eq!(huge.reason.as_deref(), Some("larger than the 10 MiB limit"));
+
+        let gone = probe(StatusCode::NOT_FOUND, reqwest::header::HeaderMap::new());
+        assert!(!gone.fetchable);
+        assert_eq!(gone.status, Some(404));
+        assert_eq!(gone.reason.as_deref(), Some("HTTP 404 Not Found"));
+        let blocked = probe(StatusCode::FORBIDDEN, reqwest::header::HeaderMap::new());
+        assert!(blocked.reason.unwrap().contains("bot protection"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_falls_back_to_get_when_head_is_refused() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(405))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("<p>hi</p>", "text/html"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let probe = FetchClient::new().unwrap().probe(&server.uri()).await;
+        assert!(probe.fetchable, "{probe:?}");
+        assert_eq!(probe.status, Some(200));
+
+        let invalid = FetchClient::new().unwrap().probe("ftp://example.com").await;
+        assert!(!invalid.fetchable);
+        assert!(invalid.reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_check_url_reads_the_size_from_a_ranged_get() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(501))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(header("range", "bytes=0-0"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("content-range", "bytes 0-0/52428800")
+                    .insert_header("set-cookie", "session=secret")
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_raw("<", "text/html"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let check = FetchClient::new().unwrap().check_url(&server.uri()).await.unwrap();
+        assert_eq!(check.method, "GET");
+        assert_eq!(check.status, 206);
+        assert_eq!(check.status_text.as_deref(), Some("Partial Content"));
+        assert_eq!(check.content_type.as_deref(), Some("text/html"));
+        assert_eq!(check.content_length, Some(52_428_800));
+        assert!(!check.fetchable, "{check:?}");
+        assert_eq!(check.headers["etag"], "\"v1\"");
+        assert!(!check.headers.contains_key("set-cookie"));
+
+        let unknown = probe_headers("text/html", "1");
+        assert_eq!(declared_length(reqwest::StatusCode::OK, &unknown), Some(1));
+        let mut ranged = reqwest::header::HeaderMap::new();
+        ranged.insert(reqwest::header::CONTENT_RANGE, "bytes 0-0/*".parse().unwrap());
+        assert_eq!(declared_length(reqwest::StatusCode::PARTIAL_CONTENT, &ranged), None);
+        assert!(FetchClient::new().unwrap().check_url("ftp://example.com").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sends_region_accept_language() {
+        use wiremock::matchers::headers;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(headers("accept-language", vec!["de-DE", "de;q=0.9"]))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw("<p>Hallo Welt</p>", "text/html"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let args = VisitPageArgs {
+            url: server.uri(),
+            region: Some("de-de".to_string()),
+            ..Default::default()
+        };
+        FetchClient::new().unwrap().fetch(&args).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_domain_headers_reach_matching_hosts() {
+        use wiremock::matchers::{header, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = ResponseTemplate::new(200).set_body_raw("<p>Private wiki</p>", "text/html");
+        Mock::given(path("/"))
+            .and(header("authorization", "Bearer wiki-token"))
+            .and(header("x-team", "docs"))
+            .respond_with(page)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let rules: Vec<DomainHeaders> = [
+            "127.0.0.1=Authorization: Bearer old-token",
+            "127.0.0.*=Authorization: Bearer wiki-token",
+            "127.0.0.1=X-Team: docs",
+            "*.example.com=X-Team: other",
+        ]
+        .iter()
+        .map(|rule| rule.parse().unwrap())
+        .collect();
+        let client = FetchClient::new()
+            .unwrap()
+            .with_ignore_robots(true)
+            .with_domain_headers(&rules)
+            .unwrap();
+        let args = VisitPageArgs {
+            url: server.uri(),
+            ..Default::default()
+        };
+        assert!(client.fetch(&args).await.unwrap().content.contains("Private wiki"));
+        assert_eq!(client.check_url(&server.uri()).await.unwrap().status, 200);
+        assert!(client.site_headers("https://docs.rs/").is_empty());
+
+        assert!("wiki.example.com".parse::<DomainHeaders>().is_err());
+        let bad = DomainHeaders {
+            glob: "wiki.example.com".to_string(),
+            headers: vec![("Bad Name".to_string(), "x".to_string())],
+        };
+        assert!(FetchClient::new().unwrap().with_domain_headers(&[bad]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_domain_headers_stay_off_redirects_to_other_hosts() {
+        use wiremock::matchers::{header, header_exists, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let wiki = MockServer::start().await;
+        let elsewhere = MockServer::start().await;
+        let away = format!("{}/landing", elsewhere.uri()).replace("127.0.0.1", "localhost");
+        Mock::given(path("/moved"))
+            .and(header("x-api-key", "wiki-key"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", away.as_str()))
+            .expect(4)
+            .mount(&wiki)
+            .await;
+        Mock::given(header_exists("x-api-key"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(0)
+            .mount(&elsewhere)
+            .await;
+        let landing = ResponseTemplate::new(200).set_body_raw("<p>Landed</p>", "text/html");
+        Mock::given(path("/landing")).respond_with(landing).expect(2).mount(&elsewhere).await;
+
+        let rules = ["127.0.0.1=X-Api-Key: wiki-key".parse::<DomainHeaders>().unwrap()];
+        let client = FetchClient::new()
+            .unwrap()
+            .with_ignore_robots(true)
+            .with_domain_headers(&rules)
+            .unwrap();
+        let moved = format!("{}/moved", wiki.uri());
+        let args = VisitPageArgs {
+            url: moved.clone(),
+            ..Default::default()
+        };
+        assert!(client.fetch(&args).await.unwrap().content.contains("Landed"));
+        let check = client.check_url(&moved).await.unwrap();
+        assert_eq!(check.status, 200);
+        assert_eq!(check.final_url, away);
+
+        // The redirect limit and domain policy still hold for hops followed by hand
+        let client = FetchClient::builder()
+            .max_redirects(0)
+            .build()
+            .unwrap()
+            .with_domain_headers(&rules)
+            .unwrap();
+        assert_eq!(client.check_url(&moved).await.unwrap().status, 302);
+        let policy = DomainPolicy::new(&[], &["localhost".to_string()]).unwrap();
+        let client = FetchClient::new()
+            .unwrap()
+            .with_domain_policy(policy)
+            .unwrap()
+            .with_domain_headers(&rules)
+            .unwrap();
+        let err = client.check_url(&moved).await.unwrap_err();
+        assert!(matches!(err, DaedraError::DomainNotAllowed(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_cookies_carry_across_redirects_and_runs() {
+        use wiremock::matchers::{header, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let consent = ResponseTemplate::new(302)
+            .insert_header("set-cookie", "consent=yes; Max-Age=3600; Path=/")
+            .insert_header("location", "/article");
+        Mock::given(path("/consent")).respond_with(consent).mount(&server).await;
+        let article = ResponseTemplate::new(200).set_body_raw("<p>The article</p>", "text/html");
+        Mock::given(path("/article"))
+            .and(header("cookie", "consent=yes"))
+            .respond_with(article)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("cookies.json");
+        let fetch = |url: String| VisitPageArgs {
+            url,
+            ..Default::default()
+        };
+        let client = FetchClient::new()
+            .unwrap()
+            .with_ignore_robots(true)
+            .with_cookie_jar(CookieJar::open(&file).unwrap())
+            .unwrap();
+        let page = client.fetch(&fetch(format!("{}/consent", server.uri()))).await.unwrap();
+        assert!(page.content.contains("The article"));
+        assert_eq!(client.cookie_jar().len(), 1);
+        client.cookie_jar().flush().await;
+
+        // A later run starts with the saved cookie
+        let client = FetchClient::new()
+            .unwrap()
+            .with_ignore_robots(true)
+            .with_cookie_jar(CookieJar::open(&file).unwrap())
+            .unwrap();
+        let page = client.fetch(&fetch(format!("{}/article", server.uri()))).await.unwrap();
+        assert!(page.content.contains("The article"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sends_a_browser_profile() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = ResponseTemplate::new(200).set_body_raw("<p>Hello</p>", "text/html");
+        Mock::given(path("/")).respond_with(page).expect(2).mount(&server).await;
+        let client = FetchClient::new().unwrap().with_ignore_robots(true);
+        let args = VisitPageArgs {
+            url: server.uri(),
+            ..Default::default()
+        };
+        client.fetch(&args).await.unwrap();
+        client.fetch(&args).await.unwrap();
+
+        let agents: Vec<String> =
+            crate::fingerprint::builtin_profiles().into_iter().map(|p| p.user_agent).collect();
+        for request in server.received_requests().await.unwrap() {
+            let agent = request.headers["user-agent"].to_str().unwrap();
+            assert!(agents.iter().any(|a| a == agent), "{agent}");
+            assert!(request.headers.get("dnt").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_browser_profiles_are_per_client() {
+        use crate::fingerprint::FingerprintConfig;
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = ResponseTemplate::new(200).set_body_raw("<p>Hello</p>", "text/html");
+        Mock::given(path("/")).respond_with(page).mount(&server).await;
+        let pinned = FingerprintConfig {
+            pin: Some("firefox-linux".to_string()),
+            ..Default::default()
+        };
+        let firefox = FetchClient::builder()
+            .browser_profiles(Arc::new(Rotation::new(&pinned).unwrap()))
+            .build()
+            .unwrap()
+            .with_ignore_robots(true);
+        let other = FetchClient::new().unwrap().with_ignore_robots(true);
+        let args = VisitPageArgs {
+            url: server.uri(),
+            ..Default::default()
+        };
+        firefox.fetch(&args).await.unwrap();
+        other.fetch(&args).await.unwrap();
+        firefox.fetch(&args).await.unwrap();
+
+        let agents: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.headers["user-agent"].to_str().unwrap().to_string())
+            .collect();
+        let firefox_linux = "Mozilla/5.0 (X11; Linux x86_64; rv:133.0) Gecko/20100101 Firefox/133.0";
+        assert_eq!(agents[0], firefox_linux);
+        assert_eq!(agents[1], crate::fingerprint::builtin_profiles()[0].user_agent);
+        assert_eq!(agents[2], firefox_linux);
+    }
+
+    #[tokio::test]
+    async fn test_low_footprint_client_sends_no_profile_or_cookies() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = ResponseTemplate::new(200)
+            .insert_header("set-cookie", "seen=yes; Max-Age=3600; Path=/")
+            .set_body_raw("<p>Hello</p>", "text/html");
+        Mock::given(path("/")).respond_with(page).expect(2).mount(&server).await;
+        let client = FetchClient::builder()
+            .client_profile(ClientProfile::LowFootprint)
+            .build()
+            .unwrap()
+            .with_ignore_robots(true);
+        assert_eq!(client.client_profile(), ClientProfile::LowFootprint);
+        let args = VisitPageArgs {
+            url: server.uri(),
+            ..Default::default()
+        };
+        client.fetch(&args).await.unwrap();
+        client.fetch(&args).await.unwrap();
+
+        for request in server.received_requests().await.unwrap() {
+            assert_eq!(request.headers["user-agent"], crate::privacy::LOW_FOOTPRINT_USER_AGENT);
+            assert_eq!(request.headers["dnt"], "1");
+            assert!(request.headers.get("cookie").is_none());
+            assert!(request.headers.get("sec-ch-ua").is_none());
+        }
+        // Other clients in the process keep the standard profile
+        assert_eq!(FetchClient::new().unwrap().client_profile(), ClientProfile::Standard);
+    }
+
+    #[tokio::test]
+    async fn test_builder_settings_apply() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = ResponseTemplate::new(200).set_body_raw("<p>Hello</p>", "text/html");
+        Mock::given(path("/page")).respond_with(page).mount(&server).await;
+        let moved = ResponseTemplate::new(302).insert_header("location", "/page");
+        Mock::given(path("/moved")).respond_with(moved).mount(&server).await;
+        let away =
+            ResponseTemplate::new(302).insert_header("location", format!("{}/page", server.uri()));
+        Mock::given(path("/away")).respond_with(away).mount(&server).await;
+        let fetch = |url: String| VisitPageArgs {
+            url,
+            ..Default::default()
+        };
+
+        let client = FetchClient::builder()
+            .user_agent("acme-bot/1.0")
+            .max_redirects(0)
+            .build()
+            .unwrap()
+            .with_ignore_robots(true);
+        client.fetch(&fetch(format!("{}/page", server.uri()))).await.unwrap();
+        assert!(client.fetch(&fetch(format!("{}/moved", server.uri()))).await.is_err());
+        let agent = server.received_requests().await.unwrap()[0].headers["user-agent"].clone();
+        assert_eq!(agent, "acme-bot/1.0");
+
+        // A given client follows the redirect itself, but the policy still holds
+        let policy = DomainPolicy::new(&[], &["127.0.0.1".to_string()]).unwrap();
+        let client = FetchClient::builder()
+            .client(Client::new())
+            .build()
+            .unwrap()
+            .with_ignore_robots(true)
+            .with_domain_policy(policy)
+            .unwrap();
+        let away = format!("{}/away", server.uri()).replace("127.0.0.1", "localhost");
+        let err = client.fetch(&fetch(away)).await.unwrap_err();
+        assert!(matches!(err, DaedraError::DomainNotAllowed(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_waits_out_retry_after() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/busy"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/busy"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("<p>Hello</p>", "text/html"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/closed"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = FetchClient::new().unwrap().with_ignore_robots(true);
+        let fetch = |url: String| VisitPageArgs {
+            url,
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        let page = client.fetch(&fetch(format!("{}/busy", server.uri()))).await.unwrap();
+        assert!(page.content.contains("Hello"));
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        // Asked to wait past the cap, the fetch gives up at once
+        let err = client.fetch(&fetch(format!("{}/closed", server.uri()))).await.unwrap_err();
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(600)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_page_revalidates_without_a_body() {
+        use wiremock::matchers::{header, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/doc"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/doc"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v2\"")
+                    .insert_header("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")
+                    .set_body_raw("<p>Hello</p>", "text/html"),
+            )
+            .mount(&server)
+            .await;
+        let client = FetchClient::new().unwrap().with_ignore_robots(true);
+        let args = VisitPageArgs {
+            url: format!("{}/doc", server.uri()),
+            ..Default::default()
+        };
+
+        let (page, validators) = client.fetch_with_validators(&args).await.unwrap();
+        assert!(page.content.contains("Hello"));
+        assert_eq!(validators.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(validators.last_modified.as_deref(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+
+        let unchanged = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        assert!(client.revalidate(&args, &unchanged).await.unwrap().is_none());
+        let (page, _) = client.revalidate(&args, &validators).await.unwrap().unwrap();
+        assert!(page.content.contains("Hello"));
+    }
+
     #[test]
     fn test_is_known_binary_content_type() {
         assert!(is_known_binary_content_type("image/png"));
@@ -929,14 +3849,126 @@ mod tests {
         assert!(is_known_binary_content_type("application/zip"));
     }
 
+    fn default_limit() -> BodyLimit {
+        BodyLimit {
+            bytes: DEFAULT_MAX_PAGE_BYTES,
+            memory_pressure: false,
+        }
+    }
+
     #[test]
     fn test_check_body_size_ok() {
-        assert!(check_body_size(100).is_ok());
+        assert!(default_limit().check(100).is_ok());
     }
 
     #[test]
     fn test_check_body_size_too_large() {
-        assert!(check_body_size(MAX_CONTENT_SIZE + 1).is_err());
+        let err = default_limit().check(DEFAULT_MAX_PAGE_BYTES + 1).unwrap_err();
+        assert!(err.to_string().contains("over the 10 MiB limit"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_memory_pressure_refuses_large_pages() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let html = format!("<html><body><main><p>{}</p></main></body></html>", "word ".repeat(500));
+        Mock::given(path("/big"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+            .mount(&server)
+            .await;
+
+        let memory = MemoryPressure::default();
+        let client = FetchClient::new().unwrap().with_memory_pressure(memory.clone(), 1024);
+        let args = VisitPageArgs {
+            url: format!("{}/big", server.uri()),
+            ..Default::default()
+        };
+        assert!(client.fetch(&args).await.is_ok());
+        memory.set(true);
+        let error = client.fetch(&args).await.unwrap_err();
+        assert!(error.to_string().contains("1024 byte limit"), "{error}");
+        memory.set(false);
+        assert!(client.fetch(&args).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_and_deflate_pages_are_decoded() {
+        use std::io::Write;
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let html = "<html><body><main><p>Compressed on the way here.</p></main></body></html>";
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(html.as_bytes()).unwrap();
+        let encoded = [
+            ("zstd", zstd::encode_all(html.as_bytes(), 3).unwrap()),
+            ("deflate", zlib.finish().unwrap()),
+        ];
+        let server = MockServer::start().await;
+        for (encoding, body) in &encoded {
+            let page = ResponseTemplate::new(200)
+                .insert_header("content-encoding", *encoding)
+                .set_body_raw(body.clone(), "text/html");
+            Mock::given(path(format!("/{encoding}"))).respond_with(page).mount(&server).await;
+        }
+
+        let client = FetchClient::new().unwrap().with_ignore_robots(true);
+        for (encoding, _) in &encoded {
+            let args = VisitPageArgs {
+                url: format!("{}/{encoding}", server.uri()),
+                ..Default::default()
+            };
+            let page = client.fetch(&args).await.unwrap();
+            assert!(page.content.contains("Compressed on the way here."), "{encoding}");
+        }
+        let request = &server.received_requests().await.unwrap()[0];
+        let accepted = request.headers["accept-encoding"].to_str().unwrap();
+        for encoding in ["gzip", "br", "zstd", "deflate"] {
+            assert!(accepted.contains(encoding), "{accepted}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_is_cut_off_at_the_cap() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A chunked page that never ends
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 4096];
+                    let _ = socket.read(&mut request).await;
+                    let head = "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\
+                                transfer-encoding: chunked\r\n\r\n";
+                    if socket.write_all(head.as_bytes()).await.is_err() {
+                        return;
+                    }
+                    let chunk = format!("400\r\n{}\r\n", "x".repeat(0x400));
+                    while socket.write_all(chunk.as_bytes()).await.is_ok() {}
+                });
+            }
+        });
+
+        let client =
+            FetchClient::new().unwrap().with_ignore_robots(true).with_max_page_bytes(64 * 1024);
+        let mut args = VisitPageArgs {
+            url: format!("http://{addr}/endless"),
+            ..Default::default()
+        };
+        let err = client.fetch(&args).await.unwrap_err();
+        assert!(err.to_string().contains("over the 65536 byte limit"), "{err}");
+
+        // A call can ask for less than the client's cap, but not more
+        args.max_bytes = Some(4096);
+        let err = client.fetch(&args).await.unwrap_err();
+        assert!(err.to_string().contains("over the 4096 byte limit"), "{err}");
+        args.max_bytes = Some(usize::MAX);
+        let err = client.fetch(&args).await.unwrap_err();
+        assert!(err.to_string().contains("over the 65536 byte limit"), "{err}");
     }
 
     #[test]
@@ -957,6 +3989,40 @@ mod tests {
         assert_eq!(links[1].url, "https://example.com/two");
         assert_eq!(links[2].text, "Third Link");
         assert_eq!(links[2].url, "https://example.com/three");
+        assert!(links.iter().all(|l| !l.homograph_risk));
+    }
+
+    #[test]
+    fn test_extract_links_flags_homograph_hosts() {
+        let html = r#"<html><body>
+            <a href="https://bücher.de/suche">Books</a>
+            <a href="https://pаypal.com/login">Log in</a>
+        </body></html>"#;
+        let document = Html::parse_document(html);
+        let base = Url::parse("https://example.com").unwrap();
+        let links = FetchClient::default().extract_links(&document, &base);
+        assert_eq!(links[0].url, "https://xn--bcher-kva.de/suche");
+        assert!(!links[0].homograph_risk);
+        assert_eq!(links[1].url, "https://xn--pypal-4ve.com/login");
+        assert!(links[1].homograph_risk);
+    }
+
+    #[test]
+    fn test_extract_links_classifies_risky_targets() {
+        let html = r#"<html><body>
+            <a href="/guide">The guide</a>
+            <a href="/releases/tool-setup.exe">Installer</a>
+            <a href="https://bit.ly/3xyz">Short link</a>
+            <a href="data:text/html,hello">Inline page</a>
+        </body></html>"#;
+        let document = Html::parse_document(html);
+        let base = Url::parse("https://example.com").unwrap();
+        let links = FetchClient::default().extract_links(&document, &base);
+        let kinds: Vec<LinkKind> = links.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [LinkKind::Page, LinkKind::Executable, LinkKind::Shortener, LinkKind::DataUri]
+        );
     }
 
     #[test]
@@ -1099,7 +4165,7 @@ mod tests {
     fn test_classify_by_fallback_utf8_text() {
         let bytes = b"plain text without magic bytes";
         let result = classify_by_fallback("", bytes).unwrap();
-        assert!(matches!(result, FetchedContent::Html(_)));
+        assert!(matches!(result, FetchedContent::Text { mime, .. } if mime == "text/plain"));
     }
 
     #[test]
@@ -1111,7 +4177,11 @@ mod tests {
 
     #[test]
     fn test_build_page_from_pdf() {
-        let page = FetchClient::build_page_from_pdf("  hello world  ", "https://example.com/doc.pdf");
+        let page = FetchClient::build_page_from_text(
+            "  hello world  ",
+            "https://example.com/doc.pdf",
+            "application/pdf",
+        );
         assert_eq!(page.url, "https://example.com/doc.pdf");
         assert_eq!(page.title, "doc.pdf");
         assert_eq!(page.content, "hello world");
@@ -1161,6 +4231,98 @@ mod tests {
         assert!(!page.content.is_empty());
     }
 
+    #[test]
+    fn test_rewrite_images() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let fragment = r#"<p>Intro</p><IMG SRC="/img/a.png" alt=" A  chart " width="640px"
+            height="480"><img data-src="lazy.jpg" src="data:image/gif;base64,R0lGOD">
+            <img src="/img/a.png" alt="again"><img alt="no source"><img src="javascript:x()">"#;
+
+        let (html, images) = rewrite_images(fragment, Some(&base));
+        assert_eq!(
+            images,
+            [
+                PageImage {
+                    url: "https://example.com/img/a.png".to_string(),
+                    alt: "A chart".to_string(),
+                    width: Some(640),
+                    height: Some(480),
+                },
+                PageImage {
+                    url: "https://example.com/blog/lazy.jpg".to_string(),
+                    alt: String::new(),
+                    width: None,
+                    height: None,
+                },
+            ]
+        );
+        assert!(html.contains(r#"<img src="https://example.com/img/a.png" alt="A chart">"#));
+        assert!(html.contains(r#"<img src="https://example.com/img/a.png" alt="again">"#));
+        assert!(!html.contains("data:") && !html.contains("javascript:"));
+
+        let (html, images) = rewrite_images(fragment, None);
+        assert!(images.is_empty());
+        assert!(!html.to_lowercase().contains("<img"), "{}", html);
+        assert!(html.starts_with("<p>Intro</p>"));
+    }
+
+    #[test]
+    fn test_build_page_from_html_lists_images_when_asked() {
+        let html = r#"<html><head><title>Gallery</title></head><body>
+            <div id="main"><p>Photos from the trip.</p><img src="photos/1.jpg" alt="Lake"></div>
+        </body></html>"#;
+        let client = FetchClient::default();
+        let url = Url::parse("https://example.com/trip/").unwrap();
+        let markdown = PageFormat::Markdown;
+        let page = client
+            .build_page_from_html(html, url.as_str(), &url, Some("#main"), true, markdown)
+            .unwrap();
+        let images = page.images.unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/trip/photos/1.jpg");
+        assert_eq!(images[0].alt, "Lake");
+
+        let page = client
+            .build_page_from_html(html, url.as_str(), &url, Some("#main"), false, markdown)
+            .unwrap();
+        assert!(page.images.is_none());
+        assert!(!page.content.contains("photos/1.jpg"));
+    }
+
+    #[test]
+    fn test_build_page_from_html_in_each_format() {
+        let html = r#"<html><head><title>Specs</title></head><body><div id="main">
+            <h2>Sizes</h2><p>Pick  a <b>size</b>:</p>
+            <ul><li>Small</li><li>Large</li></ul>
+            <table><tr><th>Size</th><th>Price</th></tr><tr><td>S</td><td>$5</td></tr></table>
+            <pre>fn main() {
+    run();
+}</pre><script>track()</script></div></body></html>"#;
+        let client = FetchClient::default();
+        let url = Url::parse("https://example.com/specs").unwrap();
+        let page = |format| {
+            client.build_page_from_html(html, url.as_str(), &url, Some("#main"), false, format)
+        };
+
+        let text = page(PageFormat::Text).unwrap();
+        assert_eq!(
+            text.content,
+            "Sizes\n\nPick a size:\n\nSmall\nLarge\n\nSize\tPrice\nS\t$5\n\n\
+             fn main() {\n    run();\n}"
+        );
+        assert_eq!(text.word_count, 15);
+
+        let html = page(PageFormat::Html).unwrap();
+        assert!(html.content.starts_with(r#"<div id="main">"#), "{}", html.content);
+        assert!(html.content.contains("<li>Small</li>"));
+        assert!(!html.content.contains("track()"));
+        assert_eq!(html.word_count, text.word_count);
+
+        let markdown = page(PageFormat::Markdown).unwrap();
+        assert!(markdown.content.contains("| Size | Price |"));
+        assert_eq!(page(PageFormat::Json).unwrap().content, markdown.content);
+    }
+
     #[test]
     fn test_build_page_from_html_with_selector() {
         let html = r#"<html><head><title>Site</title></head><body>
@@ -1198,6 +4360,90 @@ mod tests {
         assert!(links.iter().any(|l| l.url.contains("example.com/one")));
     }
 
+    #[test]
+    fn test_plain_text_pages_skip_the_content_cascade() {
+        let log = "<html><head><title>build.log</title></head><body>Build log\n\
+            <pre>[ok]  compile &lt;core&gt;\n[ok]  link\n</pre><hr>\
+            <script>track()</script></body></html>";
+        let document = Html::parse_document(log);
+        assert!(plain_text_body(&document).is_some());
+        for other in [
+            "<body><div><pre>nested</pre></div></body>",
+            "<body><p>Prose</p><pre>code</pre></body>",
+            "<body> <br> </body>",
+        ] {
+            assert!(plain_text_body(&Html::parse_document(other)).is_none(), "{other}");
+        }
+
+        let client = FetchClient::default();
+        let url = Url::parse("https://example.com/build.log").unwrap();
+        let page = |format| {
+            client.build_page_from_html(log, url.as_str(), &url, None, true, format)
+        };
+        let markdown = page(PageFormat::Markdown).unwrap();
+        assert_eq!(markdown.title, "build.log");
+        assert_eq!(markdown.content, "Build log\n\n```\n[ok]  compile <core>\n[ok]  link\n```");
+        assert_eq!(markdown.images, Some(Vec::new()));
+        assert_eq!(
+            page(PageFormat::Text).unwrap().content,
+            "Build log\n\n[ok]  compile <core>\n[ok]  link"
+        );
+        let html = page(PageFormat::Html).unwrap().content;
+        assert!(html.starts_with("Build log\n<pre>") && !html.contains("track()"), "{html}");
+    }
+
+    #[test]
+    fn test_build_links_page_lists_every_link_by_scope() {
+        let mut html = String::from(
+            r##"<html><head><title>Map</title></head><body>
+            <nav><a href="/docs">Docs</a> <a href="#top">Top</a> <a href="#">Menu</a>
+            <a href="/docs">Docs again</a> <a href="mailto:a@example.com">Mail</a>
+            <a href="https://other.example/x" title="Other site"><img src="x.png"></a>
+            <a href="/guide/#install">Install [beta]</a></nav><main>"##,
+        );
+        for i in 0..60 {
+            html.push_str(&format!(r#"<a href="/p/{i}">Page {i}</a>"#));
+        }
+        html.push_str("</main></body></html>");
+        let client = FetchClient::default();
+        let url = Url::parse("https://example.com/guide/").unwrap();
+        let page = |selector, format| {
+            client.build_links_page(&html, url.as_str(), &url, selector, format).unwrap()
+        };
+
+        let markdown = page(None, PageFormat::Markdown);
+        assert_eq!(markdown.title, "Map");
+        let links = markdown.links.unwrap();
+        assert_eq!(links.len(), 64, "no 50-link cap, duplicates dropped");
+        let scope = |url: &str| links.iter().find(|l| l.url == url).and_then(|l| l.scope);
+        assert_eq!(scope("https://example.com/docs"), Some(LinkScope::Internal));
+        assert_eq!(scope("https://example.com/guide/#top"), Some(LinkScope::Anchor));
+        assert_eq!(scope("https://example.com/guide/#install"), Some(LinkScope::Anchor));
+        assert_eq!(scope("https://other.example/x"), Some(LinkScope::External));
+        assert_eq!(links[2].text, "Other site");
+        assert!(markdown.content.starts_with(
+            "## Internal links (61)\n\n- [Docs](https://example.com/docs)\n\
+             - [Page 0](https://example.com/p/0)"
+        ));
+        assert!(markdown.content.contains(
+            "## External links (1)\n\n- [Other site](https://other.example/x)\n\n\
+             ## Anchors (2)\n\n- [Top](https://example.com/guide/#top)\n\
+             - [Install \\[beta\\]](https://example.com/guide/#install)"
+        ));
+
+        let nav = page(Some("nav"), PageFormat::Text);
+        assert_eq!(nav.links.unwrap().len(), 4);
+        assert!(nav.content.starts_with("Internal links (1)\n\nDocs\thttps://example.com/docs"));
+        let html_list = page(Some("nav"), PageFormat::Html).content;
+        assert!(html_list.contains(r#"<li><a href="https://example.com/guide/#top">Top</a></li>"#));
+        assert_eq!(
+            client.build_links_page("<p>None</p>", url.as_str(), &url, None, PageFormat::Text)
+                .unwrap()
+                .content,
+            "No links found."
+        );
+    }
+
     #[test]
     fn test_build_page_from_html_short_no_links() {
         let html = r#"<html><head><title>Short</title></head><body>
@@ -1411,21 +4657,21 @@ mod tests {
     fn test_classify_inferred_mime_text_plain() {
         let bytes = b"plain text content";
         let result = classify_inferred_mime("text/plain", bytes);
-        assert!(matches!(result, Some(FetchedContent::Html(_))));
+        assert!(matches!(result, Some(FetchedContent::Text { mime, .. }) if mime == "text/plain"));
     }
 
     #[test]
     fn test_classify_inferred_mime_text_csv() {
         let bytes = b"name,value\na,1";
         let result = classify_inferred_mime("text/csv", bytes);
-        assert!(matches!(result, Some(FetchedContent::Html(_))));
+        assert!(matches!(result, Some(FetchedContent::Text { mime, .. }) if mime == "text/csv"));
     }
 
     #[test]
     fn test_classify_inferred_mime_text_xml() {
         let bytes = b"<?xml version=\"1.0\"?><root/>";
         let result = classify_inferred_mime("text/xml", bytes);
-        assert!(matches!(result, Some(FetchedContent::Html(_))));
+        assert!(matches!(result, Some(FetchedContent::Text { mime, .. }) if mime == "text/xml"));
     }
 
     #[test]
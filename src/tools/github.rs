@@ -4,9 +4,12 @@
 //! Rate limit: 10 requests/minute unauthenticated, 30 with GITHUB_TOKEN.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    ContentType, DaedraError, DaedraResult, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -16,6 +19,15 @@ use tracing::info;
 
 const GITHUB_API: &str = "https://api.github.com/search/repositories";
 
+fn query_params(args: &SearchArgs, num_results: usize) -> [(&'static str, String); 4] {
+    [
+        ("q", args.query.clone()),
+        ("per_page", num_results.min(30).to_string()),
+        ("sort", "stars".to_string()),
+        ("order", "desc".to_string()),
+    ]
+}
+
 /// GitHub search backend — searches repositories, issues, and code via GitHub's search endpoint.
 pub struct GitHubBackend {
     client: Client,
@@ -40,13 +52,19 @@ impl GitHubBackend {
     /// Create a new GitHub search backend instance.
     pub fn new() -> Self {
         let token = std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty());
-        let client = Client::builder()
-            .user_agent("daedra/1.0")
-            .timeout(Duration::from_secs(15))
-            .build()
-            .expect("HTTP client");
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0"),
+            Duration::from_secs(15),
+        );
         Self { client, token }
     }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, Some("daedra/1.0"), Duration::from_secs(15));
+        self
+    }
 }
 
 #[async_trait]
@@ -56,18 +74,13 @@ impl SearchBackend for GitHubBackend {
 
         let mut req = self.client
             .get(GITHUB_API)
-            .query(&[
-                ("q", args.query.as_str()),
-                ("per_page", &opts.num_results.min(30).to_string()),
-                ("sort", "stars"),
-                ("order", "desc"),
-            ]);
+            .query(&query_params(args, opts.num_results));
 
         if let Some(ref token) = self.token {
             req = req.header("Authorization", format!("Bearer {}", token));
         }
 
-        let resp = req.send().await.map_err(DaedraError::HttpError)?;
+        let resp = req.send_counted().await.map_err(DaedraError::HttpError)?;
 
         if !resp.status().is_success() {
             return Err(DaedraError::SearchError(
@@ -75,7 +88,7 @@ impl SearchBackend for GitHubBackend {
             ));
         }
 
-        let data: GhResponse = resp.json().await.map_err(DaedraError::HttpError)?;
+        let data: GhResponse = resp.json_counted().await?;
 
         let results: Vec<SearchResult> = data.items.unwrap_or_default()
             .into_iter()
@@ -92,9 +105,10 @@ impl SearchBackend for GitHubBackend {
                     description: desc,
                     metadata: ResultMetadata {
                         content_type: ContentType::Documentation,
-                        source: "github".to_string(),
+                        source: "github".into(),
                         favicon: None,
                         published_date: None,
+                        ..Default::default()
                     },
                 }
             })
@@ -106,6 +120,16 @@ impl SearchBackend for GitHubBackend {
     }
 
     fn name(&self) -> &str { "github" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Keywords }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let request = PlannedRequest::get(GITHUB_API, &query_params(args, opts.num_results));
+        Some(match self.token {
+            Some(_) => request.with_note("authenticated with GITHUB_TOKEN"),
+            None => request,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,372 @@
+//! Google Programmable Search (Custom Search JSON API) backend.
+//!
+//! Requires `GOOGLE_API_KEY` and the search engine ID in `GOOGLE_CSE_ID`.
+//! 100 free queries per day, billed per 1000 after; at most 10 results per request.
+
+use super::backend::SearchBackend;
+use super::retry_after;
+use crate::privacy::{self, ClientProfile};
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::types::{
+    DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SafeSearchLevel, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult, TimeRange,
+};
+use crate::url_classification::classify_search_url;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+const GOOGLE_CSE_URL: &str = "https://www.googleapis.com/customsearch/v1";
+
+/// The API rejects `num` outside 1..=10.
+const MAX_RESULTS_PER_REQUEST: usize = 10;
+
+/// Google Custom Search JSON API backend — requires an API key and engine ID.
+pub struct GoogleCseBackend {
+    client: Client,
+    api_key: String,
+    cse_id: String,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct CseResponse {
+    #[serde(default)]
+    items: Vec<CseItem>,
+}
+
+#[derive(Deserialize)]
+struct CseItem {
+    title: String,
+    link: String,
+    #[serde(default)]
+    snippet: Option<String>,
+    #[serde(default)]
+    pagemap: Option<CsePagemap>,
+}
+
+#[derive(Deserialize)]
+struct CsePagemap {
+    #[serde(default)]
+    metatags: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Deserialize)]
+struct CseErrorBody {
+    error: CseError,
+}
+
+#[derive(Deserialize)]
+struct CseError {
+    message: String,
+}
+
+impl GoogleCseBackend {
+    /// Create a new Google CSE backend instance.
+    pub fn new(api_key: String, cse_id: String) -> Self {
+        Self::with_endpoint(api_key, cse_id, GOOGLE_CSE_URL)
+    }
+
+    /// Create a backend that queries a custom endpoint (proxies, tests).
+    pub fn with_endpoint(api_key: String, cse_id: String, endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            None,
+            Duration::from_secs(30),
+        );
+        Self {
+            client,
+            api_key,
+            cse_id,
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, None, Duration::from_secs(30));
+        self
+    }
+
+    fn build_search_params(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Vec<(&'static str, String)> {
+        let num = options.num_results.clamp(1, MAX_RESULTS_PER_REQUEST);
+        let mut params = vec![
+            ("key", self.api_key.clone()),
+            ("cx", self.cse_id.clone()),
+            ("q", query.to_string()),
+            ("num", num.to_string()),
+            ("safe", safe_value(options.safe_search).to_string()),
+        ];
+        if let Some((country, lang)) = options.region.split_once('-')
+            && country != "wt"
+            && !lang.is_empty()
+        {
+            params.push(("gl", country.to_lowercase()));
+            params.push(("hl", lang.to_lowercase()));
+        }
+        if let Some(range) = options.time_range.and_then(date_restrict_value) {
+            params.push(("dateRestrict", range.to_string()));
+        }
+        if let Some(TimeRange::Custom { from, to }) = options.time_range {
+            let (from, to) = (from.format("%Y%m%d"), to.format("%Y%m%d"));
+            params.push(("sort", format!("date:r:{}:{}", from, to)));
+        }
+        params
+    }
+}
+
+/// CSE only distinguishes `active` and `off`; moderate filtering maps to `active`.
+fn safe_value(level: SafeSearchLevel) -> &'static str {
+    match level {
+        SafeSearchLevel::Off => "off",
+        SafeSearchLevel::Moderate | SafeSearchLevel::Strict => "active",
+    }
+}
+
+/// CSE's `dateRestrict` value for a relative time range; custom ranges use a `sort` date range.
+fn date_restrict_value(range: TimeRange) -> Option<&'static str> {
+    match range {
+        TimeRange::Day => Some("d1"),
+        TimeRange::Week => Some("w1"),
+        TimeRange::Month => Some("m1"),
+        TimeRange::Year => Some("y1"),
+        TimeRange::Custom { .. } => None,
+    }
+}
+
+/// Publication date from the page's Open Graph / article metatags, when Google indexed one.
+fn published_date(pagemap: Option<&CsePagemap>) -> Option<String> {
+    const DATE_TAGS: &[&str] = &["article:published_time", "og:updated_time", "date"];
+    let tags = pagemap?.metatags.first()?;
+    DATE_TAGS
+        .iter()
+        .find_map(|tag| tags.get(*tag)?.as_str())
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+}
+
+fn to_search_result(item: CseItem) -> SearchResult {
+    SearchResult {
+        title: item.title,
+        metadata: ResultMetadata {
+            content_type: classify_search_url(&item.link),
+            source: "google_cse".into(),
+            published_date: published_date(item.pagemap.as_ref()),
+            ..Default::default()
+        },
+        url: item.link,
+        description: item
+            .snippet
+            .map(|s| s.replace('\n', " ").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl SearchBackend for GoogleCseBackend {
+    async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = self.build_search_params(&args.query, &opts);
+
+        let resp = self.client
+            .get(&self.endpoint)
+            .query(&params)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(DaedraError::RateLimitExceeded {
+                retry_after: retry_after::parse(resp.headers()),
+            });
+        }
+        if !status.is_success() {
+            let detail = resp
+                .json_counted::<CseErrorBody>()
+                .await
+                .map(|b| b.error.message)
+                .unwrap_or_default();
+            return Err(DaedraError::SearchError(format!(
+                "Google CSE returned status {status}: {detail}"
+            )));
+        }
+
+        let data: CseResponse = resp.json_counted().await?;
+        let results: Vec<SearchResult> = data
+            .items
+            .into_iter()
+            .map(to_search_result)
+            .take(opts.num_results)
+            .collect();
+
+        info!(backend = "google_cse", results = results.len(), "Google CSE search complete");
+        Ok(SearchResponse::new(args.query.clone(), results, &opts))
+    }
+
+    fn name(&self) -> &str { "google_cse" }
+    fn requires_api_key(&self) -> bool { true }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = self.build_search_params(&args.query, &opts);
+        Some(PlannedRequest::get(&self.endpoint, &params).redact_param("key"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "kind": "customsearch#search",
+            "searchInformation": {"totalResults": "2"},
+            "items": [
+                {
+                    "title": "Tokio - An asynchronous Rust runtime",
+                    "link": "https://tokio.rs/",
+                    "displayLink": "tokio.rs",
+                    "snippet": "Tokio is an asynchronous runtime\nfor Rust."
+                },
+                {
+                    "title": "tokio - Rust",
+                    "link": "https://docs.rs/tokio/latest/tokio/",
+                    "snippet": "A runtime for writing reliable network applications.",
+                    "pagemap": {
+                        "metatags": [{"article:published_time": "2024-05-01T00:00:00Z"}]
+                    }
+                }
+            ]
+        })
+    }
+
+    fn args(options: Option<SearchOptions>) -> SearchArgs {
+        SearchArgs {
+            query: "tokio runtime".to_string(),
+            options,
+        }
+    }
+
+    fn backend(server: &MockServer) -> GoogleCseBackend {
+        GoogleCseBackend::with_endpoint(
+            "key".to_string(),
+            "engine".to_string(),
+            format!("{}/customsearch/v1", server.uri()),
+        )
+    }
+
+    #[test]
+    fn test_google_cse_name() {
+        let backend = GoogleCseBackend::new("key".to_string(), "cx".to_string());
+        assert_eq!(backend.name(), "google_cse");
+        assert!(backend.requires_api_key());
+    }
+
+    #[test]
+    fn test_build_search_params() {
+        let backend = GoogleCseBackend::new("key".to_string(), "cx".to_string());
+        let options = SearchOptions {
+            region: "de-de".to_string(),
+            safe_search: SafeSearchLevel::Off,
+            num_results: 25,
+            time_range: Some(TimeRange::Week),
+            ..Default::default()
+        };
+        let params = backend.build_search_params("rust", &options);
+        let get = |k: &str| params.iter().find(|(n, _)| *n == k).map(|(_, v)| v.as_str());
+        assert_eq!(get("cx"), Some("cx"));
+        assert_eq!(get("num"), Some("10"));
+        assert_eq!(get("safe"), Some("off"));
+        assert_eq!(get("gl"), Some("de"));
+        assert_eq!(get("hl"), Some("de"));
+        assert_eq!(get("dateRestrict"), Some("w1"));
+
+        let params = backend.build_search_params("rust", &SearchOptions::default());
+        assert!(!params.iter().any(|(n, _)| *n == "gl" || *n == "dateRestrict"));
+        assert!(params.contains(&("safe", "active".to_string())));
+
+        let options = SearchOptions {
+            time_range: Some("2024-01-01..2024-02-15".parse().unwrap()),
+            ..Default::default()
+        };
+        let params = backend.build_search_params("rust", &options);
+        assert!(params.contains(&("sort", "date:r:20240101:20240215".to_string())));
+        assert!(!params.iter().any(|(n, _)| *n == "dateRestrict"));
+    }
+
+    #[tokio::test]
+    async fn test_google_cse_search_maps_items() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/customsearch/v1"))
+            .and(query_param("key", "key"))
+            .and(query_param("cx", "engine"))
+            .and(query_param("q", "tokio runtime"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .mount(&server)
+            .await;
+
+        let response = backend(&server).search(&args(None)).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[0].url, "https://tokio.rs/");
+        assert_eq!(response.data[0].description, "Tokio is an asynchronous runtime for Rust.");
+        assert_eq!(response.data[0].metadata.source, "google_cse");
+        assert_eq!(
+            response.data[1].metadata.published_date.as_deref(),
+            Some("2024-05-01T00:00:00Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_google_cse_no_items() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"searchInformation": {"totalResults": "0"}})),
+            )
+            .mount(&server)
+            .await;
+
+        let response = backend(&server).search(&args(None)).await.unwrap();
+        assert!(response.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_google_cse_quota_exhausted_is_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let err = backend(&server).search(&args(None)).await.unwrap_err();
+        assert!(matches!(err, DaedraError::RateLimitExceeded { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_google_cse_reports_api_error_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {"code": 400, "message": "API key not valid. Please pass a valid API key."}
+            })))
+            .mount(&server)
+            .await;
+
+        let err = backend(&server).search(&args(None)).await.unwrap_err();
+        assert!(
+            matches!(&err, DaedraError::SearchError(msg) if msg.contains("400") && msg.contains("API key not valid")),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Hacker News search through the Algolia HN Search API, for the
+//! `search_hackernews` tool.
+//!
+//! Free, no API key, works from any IP. Relevance order comes from the
+//! `search` endpoint and date order from `search_by_date`. Algolia has no
+//! points order, so for [`HackerNewsSort::Points`] a larger page of the best
+//! matches is fetched and sorted by points here.
+
+use crate::privacy::{self, ClientProfile};
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::text;
+use crate::types::{
+    ContentType, DaedraError, DaedraResult, HackerNewsArgs, HackerNewsKind, HackerNewsSort,
+    PlannedRequest, ResultMetadata, SearchOptions, SearchResponse, SearchResult,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+const HN_API: &str = "https://hn.algolia.com/api/v1";
+
+const HN_ITEM_URL: &str = "https://news.ycombinator.com/item?id=";
+
+/// Most results one search returns
+const MAX_RESULTS: usize = 50;
+
+/// Best matches fetched to pick the most upvoted from
+const POINTS_SORT_POOL: usize = 100;
+
+/// Longest story text kept in a description, in characters
+const MAX_TEXT_CHARS: usize = 300;
+
+lazy_static! {
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// Client for the Algolia HN Search API.
+pub struct HackerNewsClient {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct HnResponse {
+    #[serde(default)]
+    hits: Vec<HnHit>,
+}
+
+#[derive(Deserialize)]
+struct HnHit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+    title: Option<String>,
+    url: Option<String>,
+    author: Option<String>,
+    points: Option<u64>,
+    num_comments: Option<u64>,
+    created_at: Option<String>,
+    story_text: Option<String>,
+}
+
+impl HackerNewsClient {
+    /// Create a client for the public API.
+    pub fn new() -> Self {
+        Self::with_endpoint(HN_API)
+    }
+
+    /// Create a client for a custom API root (proxies, tests).
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0"),
+            Duration::from_secs(15),
+        );
+        Self {
+            client,
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, Some("daedra/1.0"), Duration::from_secs(15));
+        self
+    }
+
+    /// Search Hacker News.
+    pub async fn search(&self, args: &HackerNewsArgs) -> DaedraResult<SearchResponse> {
+        args.validate()?;
+        let (url, params) = self.request(args);
+        let resp = self
+            .client
+            .get(&url)
+            .query(&params)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+        if !resp.status().is_success() {
+            return Err(DaedraError::SearchError(format!(
+                "Hacker News search returned {}",
+                resp.status()
+            )));
+        }
+        let data: HnResponse = resp.json_counted().await?;
+
+        let mut results: Vec<SearchResult> = data.hits.into_iter().filter_map(to_result).collect();
+        for (i, result) in results.iter_mut().enumerate() {
+            result.metadata.position = i + 1;
+        }
+        if args.sort == HackerNewsSort::Points {
+            results.sort_by_key(|r| std::cmp::Reverse(r.metadata.points.unwrap_or(0)));
+        }
+        results.truncate(num_results(args));
+
+        info!(results = results.len(), "Hacker News search complete");
+        let opts = SearchOptions {
+            num_results: num_results(args),
+            ..Default::default()
+        };
+        let mut response = SearchResponse::new(args.query.clone(), results, &opts);
+        response.metadata.served_by = Some("hackernews".to_string());
+        Ok(response)
+    }
+
+    /// The request [`search`](Self::search) would send, without sending it
+    pub fn plan(&self, args: &HackerNewsArgs) -> PlannedRequest {
+        let (url, params) = self.request(args);
+        let request = PlannedRequest::get(&url, &params);
+        match args.sort {
+            HackerNewsSort::Points => request.with_note(format!(
+                "the {} best matches are sorted by points and the top {} kept",
+                POINTS_SORT_POOL,
+                num_results(args)
+            )),
+            _ => request,
+        }
+    }
+
+    fn request(&self, args: &HackerNewsArgs) -> (String, Vec<(&'static str, String)>) {
+        let endpoint = match args.sort {
+            HackerNewsSort::Date => "search_by_date",
+            HackerNewsSort::Relevance | HackerNewsSort::Points => "search",
+        };
+        let tags = match args.kind {
+            HackerNewsKind::Story => "story",
+            HackerNewsKind::AskHn => "ask_hn",
+            HackerNewsKind::ShowHn => "show_hn",
+        };
+        let hits = match args.sort {
+            HackerNewsSort::Points => POINTS_SORT_POOL,
+            _ => num_results(args),
+        };
+        let mut params = vec![
+            ("query", args.query.trim().to_string()),
+            ("tags", tags.to_string()),
+            ("hitsPerPage", hits.to_string()),
+        ];
+        if let Some(min_points) = args.min_points {
+            params.push(("numericFilters", format!("points>={}", min_points)));
+        }
+        (format!("{}/{}", self.endpoint, endpoint), params)
+    }
+}
+
+impl Default for HackerNewsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn num_results(args: &HackerNewsArgs) -> usize {
+    args.num_results.clamp(1, MAX_RESULTS)
+}
+
+fn to_result(hit: HnHit) -> Option<SearchResult> {
+    let title = text::clean(hit.title.as_deref()?);
+    if title.is_empty() {
+        return None;
+    }
+    let discussion = format!("{}{}", HN_ITEM_URL, hit.object_id);
+    let link = hit.url.filter(|u| u.starts_with("http"));
+
+    let mut description = format!(
+        "{} points by {} | {} comments | {}",
+        hit.points.unwrap_or(0),
+        hit.author.as_deref().unwrap_or("unknown"),
+        hit.num_comments.unwrap_or(0),
+        discussion
+    );
+    let story = hit.story_text.as_deref().map(|t| text::clean(&HTML_TAG.replace_all(t, " ")));
+    if let Some(story) = story.filter(|s| !s.is_empty()) {
+        let kept: String = story.chars().take(MAX_TEXT_CHARS).collect();
+        let ellipsis = if kept.len() < story.len() { "…" } else { "" };
+        description = format!("{} — {}{}", description, kept, ellipsis);
+    }
+
+    Some(SearchResult {
+        title,
+        url: link.clone().unwrap_or_else(|| discussion.clone()),
+        description,
+        metadata: ResultMetadata {
+            content_type: if link.is_some() { ContentType::Article } else { ContentType::Forum },
+            source: "news.ycombinator.com".into(),
+            published_date: hit.created_at,
+            backend: Some("hackernews".into()),
+            points: hit.points,
+            comments: hit.num_comments,
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "hits": [
+                {
+                    "objectID": "101",
+                    "title": "Tokio 1.0 released",
+                    "url": "https://tokio.rs/blog/2020-12-tokio-1-0",
+                    "author": "carllerche",
+                    "points": 120,
+                    "num_comments": 40,
+                    "created_at": "2020-12-23T18:00:00.000Z"
+                },
+                {
+                    "objectID": "102",
+                    "title": "Ask HN: Tokio or async-std?",
+                    "url": null,
+                    "author": "someone",
+                    "points": 300,
+                    "num_comments": 95,
+                    "created_at": "2021-03-01T10:00:00.000Z",
+                    "story_text": "<p>Which runtime &amp; why?</p>"
+                },
+                { "objectID": "103", "title": null, "points": 5 }
+            ]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_search_surfaces_points_and_comments() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("query", "tokio"))
+            .and(query_param("tags", "story"))
+            .and(query_param("hitsPerPage", "10"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HackerNewsClient::with_endpoint(server.uri());
+        let response = client.search(&HackerNewsArgs::new("tokio")).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.metadata.served_by.as_deref(), Some("hackernews"));
+
+        let story = &response.data[0];
+        assert_eq!(story.url, "https://tokio.rs/blog/2020-12-tokio-1-0");
+        assert_eq!(story.metadata.points, Some(120));
+        assert_eq!(story.metadata.comments, Some(40));
+        assert!(story.description.contains("news.ycombinator.com/item?id=101"));
+
+        let ask = &response.data[1];
+        assert_eq!(ask.url, "https://news.ycombinator.com/item?id=102");
+        assert_eq!(ask.metadata.content_type, ContentType::Forum);
+        assert!(ask.description.ends_with("Which runtime & why?"), "{}", ask.description);
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_date_and_points() {
+        let server = MockServer::start().await;
+        Mock::given(path("/search_by_date"))
+            .and(query_param("tags", "show_hn"))
+            .and(query_param("numericFilters", "points>=50"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/search"))
+            .and(query_param("hitsPerPage", POINTS_SORT_POOL.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HackerNewsClient::with_endpoint(server.uri());
+        let by_date = HackerNewsArgs {
+            sort: HackerNewsSort::Date,
+            kind: HackerNewsKind::ShowHn,
+            min_points: Some(50),
+            ..HackerNewsArgs::new("rust")
+        };
+        client.search(&by_date).await.unwrap();
+
+        let by_points = HackerNewsArgs {
+            sort: HackerNewsSort::Points,
+            num_results: 1,
+            ..HackerNewsArgs::new("rust")
+        };
+        let response = client.search(&by_points).await.unwrap();
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].metadata.points, Some(300));
+        assert_eq!(response.data[0].metadata.position, 2);
+        assert!(client.plan(&by_points).note.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_empty_query_and_reports_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let client = HackerNewsClient::with_endpoint(server.uri());
+        assert!(matches!(
+            client.search(&HackerNewsArgs::new("  ")).await,
+            Err(DaedraError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            client.search(&HackerNewsArgs::new("rust")).await,
+            Err(DaedraError::SearchError(_))
+        ));
+    }
+}
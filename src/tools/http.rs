@@ -0,0 +1,99 @@
+//! HTTP settings of the scraping clients.
+//!
+//! [`SearchClient::builder`](super::SearchClient::builder) and
+//! [`FetchClient::builder`](super::FetchClient::builder) build their
+//! [`reqwest::Client`] with a timeout, `User-Agent` and redirect limit, or use
+//! one the embedder made as is: for TLS roots, proxies or middleware daedra
+//! doesn't set up itself.
+//!
+//! Requests normally rotate browser profiles (see [`crate::fingerprint`]),
+//! the built-in pool unless the builder is given a [`Rotation`] of its own. A
+//! client with a `User-Agent` of its own, set here or on a given client, keeps
+//! it: requests send the other profile headers only. Under
+//! [`ClientProfile::LowFootprint`] a client sends the same few headers as
+//! every other low-footprint client instead.
+
+use crate::fingerprint::Rotation;
+use crate::privacy::{self, ClientProfile};
+use crate::types::{DaedraError, DaedraResult};
+use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::{Client, ClientBuilder};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How a scraping client gets its [`Client`]
+#[derive(Debug, Clone)]
+pub(crate) struct HttpSettings {
+    /// Client to use as is, in place of one built from the other settings
+    pub(crate) client: Option<Client>,
+    /// Whole-request timeout
+    pub(crate) timeout: Duration,
+    /// `User-Agent` to send in place of the browser profiles'
+    pub(crate) user_agent: Option<String>,
+    /// Redirects followed before giving up; 0 follows none
+    pub(crate) max_redirects: usize,
+    /// What requests reveal about the deployment
+    pub(crate) profile: ClientProfile,
+    /// Browser profiles requests take in turn
+    pub(crate) rotation: Arc<Rotation>,
+}
+
+impl HttpSettings {
+    /// Settings building a client with `timeout` and `max_redirects`
+    pub(crate) fn new(timeout: Duration, max_redirects: usize) -> Self {
+        Self {
+            client: None,
+            timeout,
+            user_agent: None,
+            max_redirects,
+            profile: ClientProfile::Standard,
+            rotation: Arc::default(),
+        }
+    }
+
+    /// The given client, or else one built with these settings and whatever
+    /// else `configure` sets up
+    pub(crate) fn build(
+        &self,
+        configure: impl FnOnce(ClientBuilder) -> ClientBuilder,
+    ) -> DaedraResult<Client> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let user_agent = self.user_agent.as_deref().unwrap_or(self.rotation.user_agent());
+        let builder = privacy::client_builder(self.profile, Some(user_agent))
+            .timeout(self.timeout)
+            .gzip(true)
+            .brotli(true)
+            .zstd(true)
+            .deflate(true);
+        configure(builder).build().map_err(DaedraError::HttpError)
+    }
+
+    /// Headers for the next browser-like request, as
+    /// [`Rotation::next_headers`] gives them, without the `User-Agent` when
+    /// the client has one of its own
+    pub(crate) fn request_headers(&self, accept_language: Option<&str>) -> HeaderMap {
+        let mut headers = self.rotation.next_headers(self.profile, accept_language);
+        if self.client.is_some() || self.user_agent.is_some() {
+            headers.remove(USER_AGENT);
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_user_agent_is_kept() {
+        let mut settings = HttpSettings::new(Duration::from_secs(5), 3);
+        settings.user_agent = Some("acme-bot/1.0".to_string());
+        assert!(!settings.request_headers(Some("de")).contains_key(USER_AGENT));
+
+        settings.user_agent = None;
+        settings.client = Some(Client::new());
+        assert!(!settings.request_headers(None).contains_key(USER_AGENT));
+    }
+}
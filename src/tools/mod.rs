@@ -3,25 +3,50 @@
 //! Search backends (in fallback order):
 //! 1. Serper.dev — Google results via API (needs SERPER_API_KEY)
 //! 2. Tavily — AI-optimized search (needs TAVILY_API_KEY)
-//! 3. Bing HTML scraping — no key, but blocked from most datacenter IPs
-//! 4. Wikipedia — always works, knowledge-focused
-//! 5. StackExchange — always works, technical Q&A
-//! 6. DuckDuckGo — blocked from datacenter IPs, last resort
+//! 3. Google Programmable Search — Custom Search JSON API (needs GOOGLE_API_KEY + GOOGLE_CSE_ID)
+//! 4. Mojeek — independent index via API (needs MOJEEK_API_KEY)
+//! 5. Bing HTML scraping — no key, but blocked from most datacenter IPs
+//! 6. Wikipedia — always works, knowledge-focused
+//! 7. StackExchange — always works, technical Q&A
+//! 8. DuckDuckGo — blocked from datacenter IPs, last resort
+//!
+//! With the `searxng` feature, a self-hosted SearXNG instance can be put in
+//! front of the chain via `ServerConfig::searxng_url`.
+//!
+//! [`hackernews`] is not in the chain; it backs the `search_hackernews` tool.
+//! [`stackexchange`] also backs the `search_stackoverflow` tool, and
+//! [`youtube`] the `get_transcript` tool. [`robots`] holds the robots.txt rules
+//! page fetches follow, and [`cookies`] the cookie jar they share.
+//! [`retry_after`] waits out rate limits as long as the server asks, and
+//! [`http`] covers the HTTP settings the scraping clients' builders take.
 
 pub mod backend;
 pub mod bing;
+pub mod cookies;
 pub mod crawl;
 pub mod ddg_instant;
 pub mod fetch;
 pub mod github;
+pub mod google_cse;
+pub mod hackernews;
+pub mod http;
+pub mod mojeek;
+pub mod rerank;
+pub mod research;
+pub mod retry_after;
+pub mod robots;
 pub mod search;
+#[cfg(feature = "searxng")]
+#[cfg_attr(docsrs, doc(cfg(feature = "searxng")))]
+pub mod searxng;
 pub mod serper;
 pub mod stackexchange;
 pub mod tavily;
 pub mod wiby;
 pub mod wikipedia;
+pub mod youtube;
 
 pub use backend::*;
-pub use crawl::{crawl_site, parse_sitemap};
+pub use crawl::{crawl_site, crawl_site_with, parse_sitemap};
 pub use fetch::*;
 pub use search::*;
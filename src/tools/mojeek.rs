@@ -0,0 +1,311 @@
+//! Mojeek search backend — independent crawler index via the Mojeek Search API.
+//!
+//! Requires an API key in `MOJEEK_API_KEY`. Mojeek runs its own index (no
+//! Bing/Google syndication), so results stay useful when Bing-derived engines
+//! such as DuckDuckGo are blocked or unwanted.
+
+use super::backend::SearchBackend;
+use super::retry_after;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::types::{
+    DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SafeSearchLevel, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult,
+};
+use crate::url_classification::classify_search_url;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+const MOJEEK_URL: &str = "https://api.mojeek.com/search";
+
+/// Results per request accepted by the API.
+const MAX_RESULTS_PER_REQUEST: usize = 20;
+
+/// Mojeek Search API backend — requires an API key.
+pub struct MojeekBackend {
+    client: Client,
+    api_key: String,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct MojeekEnvelope {
+    response: MojeekResponse,
+}
+
+#[derive(Deserialize)]
+struct MojeekResponse {
+    status: String,
+    #[serde(default)]
+    results: Vec<MojeekResult>,
+}
+
+#[derive(Deserialize)]
+struct MojeekResult {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    desc: Option<String>,
+    #[serde(default)]
+    date: Option<serde_json::Value>,
+}
+
+impl MojeekBackend {
+    /// Create a new Mojeek backend instance.
+    pub fn new(api_key: String) -> Self {
+        Self::with_endpoint(api_key, MOJEEK_URL)
+    }
+
+    /// Create a backend that queries a custom endpoint (proxies, tests).
+    pub fn with_endpoint(api_key: String, endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            None,
+            Duration::from_secs(30),
+        );
+        Self {
+            client,
+            api_key,
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, None, Duration::from_secs(30));
+        self
+    }
+
+    fn build_search_params(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Vec<(&'static str, String)> {
+        let count = options.num_results.clamp(1, MAX_RESULTS_PER_REQUEST);
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("api_key", self.api_key.clone()),
+            ("fmt", "json".to_string()),
+            ("t", count.to_string()),
+            ("safe", safe_value(options.safe_search).to_string()),
+        ];
+        // Region and language are biases (boosts), not hard filters.
+        if let Some((country, lang)) = options.region.split_once('-')
+            && country != "wt"
+            && !lang.is_empty()
+        {
+            params.push(("rb", country.to_uppercase()));
+            params.push(("lb", lang.to_uppercase()));
+        }
+        params
+    }
+}
+
+/// Mojeek has a single on/off safe search switch.
+fn safe_value(level: SafeSearchLevel) -> u8 {
+    match level {
+        SafeSearchLevel::Off => 0,
+        SafeSearchLevel::Moderate | SafeSearchLevel::Strict => 1,
+    }
+}
+
+/// The API reports dates as text or as a Unix timestamp depending on the result.
+fn date_string(date: Option<serde_json::Value>) -> Option<String> {
+    match date? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn to_search_result(r: MojeekResult) -> SearchResult {
+    SearchResult {
+        title: r.title,
+        metadata: ResultMetadata {
+            content_type: classify_search_url(&r.url),
+            source: "mojeek".into(),
+            published_date: date_string(r.date),
+            ..Default::default()
+        },
+        url: r.url,
+        description: r.desc.unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MojeekBackend {
+    async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = self.build_search_params(&args.query, &opts);
+
+        let resp = self.client
+            .get(&self.endpoint)
+            .query(&params)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(DaedraError::RateLimitExceeded {
+                retry_after: retry_after::parse(resp.headers()),
+            });
+        }
+        if !status.is_success() {
+            return Err(DaedraError::SearchError(format!(
+                "Mojeek returned status {status}"
+            )));
+        }
+
+        let data: MojeekEnvelope = resp.json_counted().await?;
+        if data.response.status != "OK" {
+            return Err(DaedraError::SearchError(format!(
+                "Mojeek API error: {}",
+                data.response.status
+            )));
+        }
+
+        let results: Vec<SearchResult> = data
+            .response
+            .results
+            .into_iter()
+            .map(to_search_result)
+            .take(opts.num_results)
+            .collect();
+
+        info!(backend = "mojeek", results = results.len(), "Mojeek search complete");
+        Ok(SearchResponse::new(args.query.clone(), results, &opts))
+    }
+
+    fn name(&self) -> &str { "mojeek" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Basic }
+    fn requires_api_key(&self) -> bool { true }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = self.build_search_params(&args.query, &opts);
+        Some(PlannedRequest::get(&self.endpoint, &params).redact_param("api_key"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "response": {
+                "status": "OK",
+                "head": {"query": "tokio runtime", "results": 2},
+                "results": [
+                    {
+                        "url": "https://tokio.rs/",
+                        "title": "Tokio - An asynchronous Rust runtime",
+                        "desc": "Tokio is an event-driven, non-blocking I/O platform."
+                    },
+                    {
+                        "url": "https://github.com/tokio-rs/tokio",
+                        "title": "tokio-rs/tokio",
+                        "desc": "A runtime for writing reliable asynchronous applications.",
+                        "date": 1714521600
+                    }
+                ]
+            }
+        })
+    }
+
+    fn args() -> SearchArgs {
+        SearchArgs {
+            query: "tokio runtime".to_string(),
+            options: None,
+        }
+    }
+
+    fn backend(server: &MockServer) -> MojeekBackend {
+        MojeekBackend::with_endpoint("key".to_string(), format!("{}/search", server.uri()))
+    }
+
+    #[test]
+    fn test_mojeek_name() {
+        let backend = MojeekBackend::new("key".to_string());
+        assert_eq!(backend.name(), "mojeek");
+        assert!(backend.requires_api_key());
+    }
+
+    #[test]
+    fn test_build_search_params() {
+        let backend = MojeekBackend::new("key".to_string());
+        let options = SearchOptions {
+            region: "uk-en".to_string(),
+            safe_search: SafeSearchLevel::Off,
+            num_results: 50,
+            time_range: None,
+            ..Default::default()
+        };
+        let params = backend.build_search_params("rust", &options);
+        let get = |k: &str| params.iter().find(|(n, _)| *n == k).map(|(_, v)| v.as_str());
+        assert_eq!(get("fmt"), Some("json"));
+        assert_eq!(get("t"), Some("20"));
+        assert_eq!(get("safe"), Some("0"));
+        assert_eq!(get("rb"), Some("UK"));
+        assert_eq!(get("lb"), Some("EN"));
+
+        let params = backend.build_search_params("rust", &SearchOptions::default());
+        assert!(!params.iter().any(|(n, _)| *n == "rb" || *n == "lb"));
+    }
+
+    #[tokio::test]
+    async fn test_mojeek_search_maps_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("api_key", "key"))
+            .and(query_param("q", "tokio runtime"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .mount(&server)
+            .await;
+
+        let response = backend(&server).search(&args()).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[0].url, "https://tokio.rs/");
+        assert_eq!(response.data[0].metadata.source, "mojeek");
+        assert_eq!(response.data[0].metadata.published_date, None);
+        assert_eq!(response.data[1].metadata.published_date.as_deref(), Some("1714521600"));
+    }
+
+    #[tokio::test]
+    async fn test_mojeek_api_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "response": {"status": "ERROR: Invalid API key"}
+            })))
+            .mount(&server)
+            .await;
+
+        let err = backend(&server).search(&args()).await.unwrap_err();
+        assert!(
+            matches!(&err, DaedraError::SearchError(msg) if msg.contains("Invalid API key")),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mojeek_rate_limited() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let err = backend(&server).search(&args()).await.unwrap_err();
+        assert!(matches!(err, DaedraError::RateLimitExceeded { .. }));
+    }
+}
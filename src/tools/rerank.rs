@@ -0,0 +1,212 @@
+//! Query-relevance re-ranking of search results.
+//!
+//! Backends rank by their own signals, and merging interleaves those rankings
+//! without comparing them. This pass scores each result's title and snippet
+//! against the query with BM25 — titles count twice — and reorders by score.
+//! Ties keep their incoming order, so results matching no query term stay
+//! where the backends put them. Terms are compared folded and stemmed (see
+//! [`crate::text`]), so `Crates` in a query matches `crate` in a title.
+//!
+//! [`apply_type_preferences`] then applies a search's `prefer_types` and
+//! `exclude_types`.
+
+use crate::text::{self, TermOptions};
+use crate::types::{ContentType, SearchResult};
+use crate::url_classification::classify_search_url;
+use std::collections::{HashMap, HashSet};
+
+/// Term-frequency saturation
+const K1: f32 = 1.2;
+
+/// Document-length normalization
+const B: f32 = 0.75;
+
+/// Folded, stemmed terms of `text` in `language`
+fn terms(text: &str, language: Option<&str>) -> Vec<String> {
+    let options = TermOptions {
+        language,
+        stem: true,
+        ..Default::default()
+    };
+    text::terms(text, options)
+}
+
+/// A result's terms, with the title's counted twice
+fn document(result: &SearchResult, language: Option<&str>) -> Vec<String> {
+    terms(&result.title, language)
+        .into_iter()
+        .flat_map(|t| [t.clone(), t])
+        .chain(terms(&result.description, language))
+        .collect()
+}
+
+/// BM25 score of each result against `query`, in input order. `language` is
+/// the language tag the results are in, if known.
+pub fn bm25_scores(query: &str, language: Option<&str>, results: &[SearchResult]) -> Vec<f32> {
+    let query_terms: HashSet<String> = terms(query, language).into_iter().collect();
+    let docs: Vec<Vec<String>> = results.iter().map(|r| document(r, language)).collect();
+    if docs.is_empty() || query_terms.is_empty() {
+        return vec![0.0; docs.len()];
+    }
+
+    let n = docs.len() as f32;
+    let avg_len = docs.iter().map(Vec::len).sum::<usize>() as f32 / n;
+    let idf: HashMap<&str, f32> = query_terms
+        .iter()
+        .map(|term| {
+            let containing = docs.iter().filter(|d| d.contains(term)).count() as f32;
+            let idf = (1.0 + (n - containing + 0.5) / (containing + 0.5)).ln();
+            (term.as_str(), idf)
+        })
+        .collect();
+
+    docs.iter()
+        .map(|doc| {
+            let len_norm = 1.0 - B + B * doc.len() as f32 / avg_len.max(1.0);
+            idf.iter()
+                .map(|(term, idf)| {
+                    let tf = doc.iter().filter(|t| t == term).count() as f32;
+                    idf * tf * (K1 + 1.0) / (tf + K1 * len_norm)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Reorder `results` by BM25 relevance to `query`, recording each score in
+/// [`ResultMetadata::relevance`](crate::types::ResultMetadata::relevance).
+pub fn rerank(query: &str, language: Option<&str>, results: &mut Vec<SearchResult>) {
+    let scores = bm25_scores(query, language, results);
+    let mut scored: Vec<(f32, SearchResult)> = scores.into_iter().zip(results.drain(..)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    results.extend(scored.into_iter().map(|(score, mut result)| {
+        result.metadata.relevance = Some(score);
+        result
+    }));
+}
+
+/// The result's content type; backends that don't classify results leave
+/// [`ContentType::Other`], so those are classified by URL.
+fn content_type(result: &SearchResult) -> ContentType {
+    match result.metadata.content_type {
+        ContentType::Other => classify_search_url(&result.url),
+        known => known,
+    }
+}
+
+/// Drop results of an `exclude`d content type and move those of a `prefer`red
+/// type ahead of the rest. Earlier types in `prefer` weigh more, so
+/// `[Documentation, Article]` puts documentation first, then articles, then
+/// everything else; order is kept within each group. Returns how many results
+/// were dropped.
+pub fn apply_type_preferences(
+    results: &mut Vec<SearchResult>,
+    prefer: &[ContentType],
+    exclude: &[ContentType],
+) -> usize {
+    let before = results.len();
+    if !exclude.is_empty() {
+        results.retain(|r| !exclude.contains(&content_type(r)));
+    }
+    if !prefer.is_empty() {
+        results.sort_by_cached_key(|r| {
+            let kind = content_type(r);
+            prefer.iter().position(|t| *t == kind).unwrap_or(prefer.len())
+        });
+    }
+    before - results.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ResultMetadata;
+
+    fn result(title: &str, description: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            description: description.to_string(),
+            metadata: ResultMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_rerank_promotes_matching_results() {
+        let mut results = vec![
+            result("Cooking pasta", "How to boil water"),
+            result("Tokio tutorial", "Learn the tokio async runtime for Rust"),
+            result("Rust book", "The Rust programming language"),
+        ];
+        rerank("tokio async runtime", None, &mut results);
+        assert_eq!(results[0].title, "Tokio tutorial");
+        assert!(results[0].metadata.relevance.unwrap() > 0.0);
+        assert_eq!(results[2].metadata.relevance, Some(0.0));
+    }
+
+    #[test]
+    fn test_rerank_weights_titles_and_keeps_ties_in_order() {
+        let mut results = vec![
+            result("Unrelated", "mentions rust once"),
+            result("Rust", "nothing else"),
+            result("First miss", "none"),
+            result("Second miss", "none"),
+        ];
+        rerank("Rust", None, &mut results);
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["Rust", "Unrelated", "First miss", "Second miss"]);
+    }
+
+    #[test]
+    fn test_type_preferences_reorder_and_exclude() {
+        let typed = |url: &str, content_type: ContentType| SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            metadata: ResultMetadata {
+                content_type,
+                ..Default::default()
+            },
+        };
+        let mut results = vec![
+            typed("https://blog.example/tokio", ContentType::Article),
+            typed("https://www.amazon.com/tokio-mug", ContentType::Other),
+            typed("https://tokio.rs/tokio/tutorial", ContentType::Other),
+            typed("https://docs.rs/tokio", ContentType::Documentation),
+            typed("https://x.com/tokio_rs", ContentType::Social),
+            typed("https://www.reddit.com/r/rust", ContentType::Forum),
+        ];
+        let dropped = apply_type_preferences(
+            &mut results,
+            &[ContentType::Documentation, ContentType::Article],
+            &[ContentType::Shopping, ContentType::Social],
+        );
+        assert_eq!(dropped, 2);
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://docs.rs/tokio",
+                "https://blog.example/tokio",
+                "https://tokio.rs/tokio/tutorial",
+                "https://www.reddit.com/r/rust",
+            ]
+        );
+        assert_eq!(apply_type_preferences(&mut results, &[], &[]), 0);
+    }
+
+    #[test]
+    fn test_bm25_scores_empty_inputs() {
+        assert!(bm25_scores("rust", None, &[]).is_empty());
+        assert_eq!(bm25_scores("", None, &[result("Rust", "")]), vec![0.0]);
+    }
+
+    #[test]
+    fn test_bm25_matches_folded_and_stemmed_terms() {
+        let results = [result("Popular crates", "Libraries for Rust"), result("Cooking", "pasta")];
+        let scores = bm25_scores("CRATE library", None, &results);
+        assert!(scores[0] > 0.0 && scores[1] == 0.0, "{scores:?}");
+        let turkish = [result("Işık", ""), result("Other", "")];
+        assert!(bm25_scores("IŞIK", Some("tr"), &turkish)[0] > 0.0);
+    }
+}
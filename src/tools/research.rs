@@ -0,0 +1,244 @@
+//! Query decomposition and source selection for `deep_research`.
+//!
+//! There is no language model to plan the research, so queries are split and
+//! extended heuristically. The first round searches the question itself and
+//! each side of a compound one ("tokio vs async-std", "A and B"). Every later
+//! round follows up on what the previous one found: the "people also ask"
+//! questions backends returned, then the query narrowed by terms that recur
+//! across result titles. Sources are ranked by how many searches returned
+//! them, then by their best rank in any one search.
+
+use super::backend::dedup_key;
+use crate::text::{self, TermOptions};
+use crate::types::{SearchResponse, SearchResult};
+use std::collections::{HashMap, HashSet};
+
+/// Most searches run in one round
+pub const MAX_QUERIES_PER_ROUND: usize = 4;
+
+/// Separators of a compound query, strongest first; only the first one found is used
+const SEPARATORS: &[&str] =
+    &[" vs. ", " vs ", " versus ", " compared to ", ";", " and ", " or ", ","];
+
+/// Leading phrases dropped from the first part of a split query
+const LEAD_INS: &[&str] =
+    &["difference between ", "differences between ", "comparing ", "compare "];
+
+/// Title terms too common to narrow a query
+const STOPWORDS: &[&str] = &[
+    "about", "after", "also", "best", "from", "guide", "have", "into", "more", "most", "over",
+    "that", "their", "there", "these", "this", "what", "when", "where", "which", "while", "with",
+    "your",
+];
+
+/// A search result merged across every search that returned it.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The result as first seen
+    pub result: SearchResult,
+    /// How many searches returned it
+    pub hits: usize,
+    /// Its best 0-based rank in any one search
+    pub best_rank: usize,
+}
+
+/// First-round searches for `query`: the query, then each side of a compound one.
+pub fn decompose_query(query: &str) -> Vec<String> {
+    let query = query.trim();
+    let mut queries = vec![query.to_string()];
+    // ASCII lowercasing keeps byte offsets valid in `query`
+    let lower = query.to_ascii_lowercase();
+    if let Some(separator) = SEPARATORS.iter().find(|s| lower.contains(*s)) {
+        let mut start = 0;
+        for (end, _) in lower.match_indices(separator).chain([(lower.len(), "")]) {
+            let mut part = &query[start..end];
+            let part_lower = &lower[start..end];
+            if let Some(i) = LEAD_INS.iter().find_map(|l| part_lower.find(l).map(|i| i + l.len())) {
+                part = &part[i..];
+            }
+            push_query(&mut queries, part);
+            start = end + separator.len();
+        }
+    }
+    queries.truncate(MAX_QUERIES_PER_ROUND);
+    queries
+}
+
+/// Next-round searches from one round's `responses`: "people also ask"
+/// questions, then `query` narrowed by terms recurring across result titles.
+/// Anything in `asked` is skipped.
+pub fn follow_up_queries<'a>(
+    query: &str,
+    responses: impl IntoIterator<Item = &'a SearchResponse> + Clone,
+    asked: &[String],
+) -> Vec<String> {
+    let mut queries: Vec<String> = Vec::new();
+    let mut push = |candidate: &str| {
+        let is_new = !asked.iter().any(|a| text::key(a) == text::key(candidate));
+        if is_new && queries.len() < MAX_QUERIES_PER_ROUND {
+            push_query(&mut queries, candidate);
+        }
+    };
+
+    for question in responses.clone().into_iter().flat_map(|r| &r.people_also_ask) {
+        push(&question.question);
+    }
+
+    let query_terms: HashSet<String> = terms(query).into_iter().collect();
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for result in responses.into_iter().flat_map(|r| &r.data) {
+        let title_terms: HashSet<String> = terms(&result.title)
+            .into_iter()
+            .filter(|t| t.chars().count() >= 4 && !t.chars().all(|c| c.is_ascii_digit()))
+            .filter(|t| !STOPWORDS.contains(&t.as_str()) && !query_terms.contains(t))
+            .collect();
+        for term in title_terms {
+            match counts.iter_mut().find(|(t, _)| *t == term) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((term, 1)),
+            }
+        }
+    }
+    // Stable, so equally common terms keep the order they were first seen in
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    for (term, _) in counts.iter().filter(|(_, count)| *count >= 2) {
+        push(&format!("{} {}", query.trim(), term));
+    }
+    queries
+}
+
+/// Merge every search's results by URL, ordered by how many searches returned
+/// each, then by best rank.
+pub fn rank_candidates<'a>(
+    responses: impl IntoIterator<Item = &'a SearchResponse>,
+) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for response in responses {
+        let mut seen_here = HashSet::new();
+        for (rank, result) in response.data.iter().enumerate() {
+            let key = dedup_key(&result.url);
+            if !seen_here.insert(key.clone()) {
+                continue;
+            }
+            match by_key.get(&key) {
+                Some(&i) => {
+                    candidates[i].hits += 1;
+                    candidates[i].best_rank = candidates[i].best_rank.min(rank);
+                },
+                None => {
+                    by_key.insert(key, candidates.len());
+                    candidates.push(Candidate {
+                        result: result.clone(),
+                        hits: 1,
+                        best_rank: rank,
+                    });
+                },
+            }
+        }
+    }
+    candidates.sort_by_key(|c| (std::cmp::Reverse(c.hits), c.best_rank));
+    candidates
+}
+
+/// Key under which two pages count as the same content (mirrors and syndicated copies)
+pub fn content_fingerprint(content: &str) -> String {
+    text::fold_case(content, None)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .take(500)
+        .collect()
+}
+
+fn push_query(queries: &mut Vec<String>, candidate: &str) {
+    let candidate = candidate.trim().trim_end_matches('?').trim();
+    let useful = candidate.chars().filter(|c| c.is_alphanumeric()).count() >= 2;
+    if useful && !queries.iter().any(|q| text::key(q) == text::key(candidate)) {
+        queries.push(candidate.to_string());
+    }
+}
+
+fn terms(title: &str) -> Vec<String> {
+    let options = TermOptions {
+        keep_hyphens: true,
+        ..Default::default()
+    };
+    text::terms(title, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{RelatedQuestion, ResultMetadata, SearchOptions};
+
+    fn response(query: &str, urls_and_titles: &[(&str, &str)]) -> SearchResponse {
+        let results = urls_and_titles
+            .iter()
+            .map(|(url, title)| SearchResult {
+                title: title.to_string(),
+                url: url.to_string(),
+                description: String::new(),
+                metadata: ResultMetadata::default(),
+            })
+            .collect();
+        SearchResponse::new(query.to_string(), results, &SearchOptions::default())
+    }
+
+    #[test]
+    fn test_decompose_compound_queries() {
+        assert_eq!(
+            decompose_query("Tokio vs async-std"),
+            vec!["Tokio vs async-std", "Tokio", "async-std"]
+        );
+        assert_eq!(
+            decompose_query("difference between Rc and Arc"),
+            vec!["difference between Rc and Arc", "Rc", "Arc"]
+        );
+        assert_eq!(decompose_query("  rust borrow checker "), vec!["rust borrow checker"]);
+        assert_eq!(decompose_query("alpha, beta, gamma, delta").len(), MAX_QUERIES_PER_ROUND);
+    }
+
+    #[test]
+    fn test_follow_ups_use_related_questions_then_recurring_terms() {
+        let mut first = response(
+            "tokio",
+            &[
+                ("https://a.example", "Tokio scheduler internals"),
+                ("https://b.example", "How the Tokio scheduler works"),
+                ("https://c.example", "Tokio tutorial"),
+            ],
+        );
+        first.people_also_ask = vec![RelatedQuestion {
+            question: "Is Tokio multithreaded?".to_string(),
+            snippet: None,
+            title: None,
+            url: None,
+        }];
+        let asked = vec!["tokio".to_string()];
+        assert_eq!(
+            follow_up_queries("tokio", &[first], &asked),
+            vec!["Is Tokio multithreaded", "tokio scheduler"]
+        );
+    }
+
+    #[test]
+    fn test_rank_candidates_prefers_results_found_by_more_searches() {
+        let responses = [
+            response("a", &[("https://one.example/", "One"), ("https://two.example", "Two")]),
+            response("b", &[("https://www.two.example/", "Two"), ("https://three.example", "3")]),
+        ];
+        let ranked = rank_candidates(&responses);
+        let urls: Vec<&str> = ranked.iter().map(|c| c.result.url.as_str()).collect();
+        assert_eq!(urls, ["https://two.example", "https://one.example/", "https://three.example"]);
+        assert_eq!((ranked[0].hits, ranked[0].best_rank), (2, 0));
+    }
+
+    #[test]
+    fn test_content_fingerprint_ignores_formatting() {
+        assert_eq!(
+            content_fingerprint("# Title\n\nSame   *words* here."),
+            content_fingerprint("Title same words here")
+        );
+        assert_ne!(content_fingerprint("one text"), content_fingerprint("another text"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Waiting out `429 Too Many Requests` as the server asks.
+//!
+//! A 429 may say when to come back in its `Retry-After` header, as seconds or
+//! as an HTTP date. [`rate_limited`] turns such a response into a retry after
+//! that long instead of the usual exponential backoff. A wait longer than
+//! [`MAX_RETRY_WAIT`], or one that wouldn't end within the retry budget, gives
+//! up at once rather than hold the caller. Either way the
+//! [`DaedraError::RateLimitExceeded`] the caller ends up with says how long the
+//! server asked for.
+
+use crate::types::DaedraError;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Longest wait before retrying a rate-limited request; longer ones give up
+pub const MAX_RETRY_WAIT: Duration = Duration::from_secs(30);
+
+/// How long `headers` ask to wait before the next request, if they say
+pub fn parse(headers: &HeaderMap) -> Option<Duration> {
+    parse_at(headers, Utc::now())
+}
+
+/// [`parse`], counting an HTTP date from `now`; a date in the past means now
+fn parse_at(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - now).to_std().unwrap_or_default())
+}
+
+/// The retry error for a 429 whose headers are `headers`, in a retry loop
+/// that began at `started` and gives up after `budget`
+pub fn rate_limited(
+    headers: &HeaderMap,
+    started: Instant,
+    budget: Duration,
+) -> backoff::Error<DaedraError> {
+    let retry_after = parse(headers);
+    let error = DaedraError::RateLimitExceeded { retry_after };
+    match retry_after {
+        None => backoff::Error::transient(error),
+        Some(wait) => {
+            if wait > MAX_RETRY_WAIT || started.elapsed() + wait > budget {
+                return backoff::Error::permanent(error);
+            }
+            debug!(wait_ms = wait.as_millis() as u64, "Rate limited, waiting as asked");
+            backoff::Error::retry_after(error, wait)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_seconds_and_dates() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().to_utc();
+        assert_eq!(parse_at(&headers("120"), now), Some(Duration::from_secs(120)));
+        let date = headers("Wed, 21 Oct 2015 07:28:42 GMT");
+        assert_eq!(parse_at(&date, now), Some(Duration::from_secs(42)));
+        let past = headers("Wed, 21 Oct 2015 07:00:00 GMT");
+        assert_eq!(parse_at(&past, now), Some(Duration::ZERO));
+        assert_eq!(parse_at(&headers("soon"), now), None);
+        assert_eq!(parse_at(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn test_rate_limited_waits_within_the_budget() {
+        let budget = Duration::from_secs(60);
+        let started = Instant::now();
+        match rate_limited(&headers("5"), started, budget) {
+            backoff::Error::Transient { err, retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(5)));
+                assert_eq!(err.retry_after(), Some(Duration::from_secs(5)));
+            },
+            other => panic!("expected a retry, got {other:?}"),
+        }
+        // Waits past the cap or the budget give up, saying how long was asked for
+        match rate_limited(&headers("600"), started, budget) {
+            backoff::Error::Permanent(err) => {
+                assert_eq!(err.retry_after(), Some(Duration::from_secs(600)));
+            },
+            other => panic!("expected to give up, got {other:?}"),
+        }
+        let past_budget = rate_limited(&headers("20"), started, Duration::from_secs(10));
+        assert!(matches!(past_budget, backoff::Error::Permanent(_)));
+        let unknown = rate_limited(&HeaderMap::new(), started, budget);
+        assert!(matches!(unknown, backoff::Error::Transient { retry_after: None, .. }));
+    }
+}
@@ -0,0 +1,305 @@
+//! robots.txt rules, fetched once per site and cached.
+//!
+//! [`RobotsRules::parse`] reads a robots.txt the way RFC 9309 describes it:
+//! the groups naming the `daedra` user agent apply when there are any, the `*`
+//! groups otherwise, and within them the longest matching `Allow` or
+//! `Disallow` path wins (`Allow` on a tie). Paths may use the `*` and `$`
+//! wildcards.
+//!
+//! [`RobotsCache`] keeps each origin's rules for [`ROBOTS_TTL`]. A missing
+//! robots.txt (any 4xx) allows everything. One that can't be read — a network
+//! error or a 5xx — also allows everything, but isn't cached, so the next fetch
+//! from that site asks again.
+
+use crate::telemetry::{CountedRequest, CountedResponse};
+use moka::future::Cache;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Product token matched against `User-agent` lines
+pub const ROBOTS_USER_AGENT: &str = "daedra";
+
+/// How long a site's robots.txt is trusted before it is fetched again
+pub const ROBOTS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most of a robots.txt read; rules past it are ignored, as RFC 9309 allows
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+
+/// Most sites whose rules are kept at once
+const MAX_CACHED_SITES: u64 = 1_000;
+
+/// One `Allow` or `Disallow` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// The rules of one robots.txt that apply to Daedra
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+impl RobotsRules {
+    /// Rules allowing every path, as for a site without a robots.txt
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Parse a robots.txt, keeping the rules for `user_agent` or, when no
+    /// group names it, for `*`
+    pub fn parse(body: &str, user_agent: &str) -> Self {
+        // Each group is its user agents and the rules under them
+        let mut groups: Vec<(Vec<String>, Vec<Rule>)> = Vec::new();
+        let mut in_rules = false;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    let agent = value.to_ascii_lowercase();
+                    match groups.last_mut() {
+                        Some((agents, _)) if !in_rules => agents.push(agent),
+                        _ => groups.push((vec![agent], Vec::new())),
+                    }
+                    in_rules = false;
+                },
+                key @ ("allow" | "disallow") => {
+                    if let Some((_, rules)) = groups.last_mut() {
+                        rules.push(Rule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                    in_rules = true;
+                },
+                _ => {},
+            }
+        }
+
+        let user_agent = user_agent.to_ascii_lowercase();
+        let rules_for = |agent: &str| -> Option<Vec<Rule>> {
+            let mut matched = groups.iter().filter(|(agents, _)| agents.iter().any(|a| a == agent));
+            let first = matched.next()?;
+            Some(first.1.iter().chain(matched.flat_map(|(_, rules)| rules)).cloned().collect())
+        };
+        let rules = rules_for(&user_agent).or_else(|| rules_for("*")).unwrap_or_default();
+        Self { rules }
+    }
+
+    /// Whether `path` (with its query, as in `/search?q=rust`) may be fetched
+    pub fn is_allowed(&self, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        self.rules
+            .iter()
+            .filter(|rule| !rule.pattern.is_empty() && pattern_matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Whether a robots.txt path pattern matches `path`: a prefix match, where `*`
+/// stands for any run of characters and a trailing `$` anchors the end
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// robots.txt rules by origin, fetched on first use
+#[derive(Clone)]
+pub struct RobotsCache {
+    sites: Cache<String, Arc<RobotsRules>>,
+}
+
+impl RobotsCache {
+    /// An empty cache keeping each site's rules for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sites: Cache::builder()
+                .max_capacity(MAX_CACHED_SITES)
+                .time_to_live(ttl)
+                .build(),
+        }
+    }
+
+    /// Whether the robots.txt of `url`'s site allows fetching it, reading the
+    /// file with `client` if it isn't cached
+    pub async fn is_allowed(&self, client: &Client, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        self.rules(client, url).await.is_allowed(&path)
+    }
+
+    /// The rules for `url`'s site
+    async fn rules(&self, client: &Client, url: &Url) -> Arc<RobotsRules> {
+        let origin = url.origin().ascii_serialization();
+        let Ok(robots_url) = url.join("/robots.txt") else {
+            return Arc::new(RobotsRules::allow_all());
+        };
+        self.sites
+            .try_get_with(origin, fetch_rules(client, robots_url))
+            .await
+            .unwrap_or_else(|e| {
+                warn!(site = %url.origin().ascii_serialization(), error = %e, "robots.txt unreadable, allowing");
+                Arc::new(RobotsRules::allow_all())
+            })
+    }
+}
+
+impl Default for RobotsCache {
+    fn default() -> Self {
+        Self::new(ROBOTS_TTL)
+    }
+}
+
+/// Fetch and parse the robots.txt at `url`; an error when it can't be read
+async fn fetch_rules(client: &Client, url: Url) -> Result<Arc<RobotsRules>, String> {
+    let response = client.get(url.clone()).send_counted().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_client_error() {
+        debug!(url = %url, status = status.as_u16(), "No robots.txt");
+        return Ok(Arc::new(RobotsRules::allow_all()));
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status.as_u16()));
+    }
+    let mut body = response.text_counted().await.map_err(|e| e.to_string())?;
+    if body.len() > MAX_ROBOTS_BYTES {
+        let end = (0..=MAX_ROBOTS_BYTES).rev().find(|&i| body.is_char_boundary(i)).unwrap_or(0);
+        body.truncate(end);
+    }
+    Ok(Arc::new(RobotsRules::parse(&body, ROBOTS_USER_AGENT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+# Crawlers keep out of the admin pages
+User-agent: *
+Disallow: /admin/
+Allow: /admin/help
+Disallow: /*.pdf$
+Disallow: /search
+
+User-agent: Googlebot
+User-agent: Daedra
+Disallow: /private
+Allow: /private/open # this part is fine
+Sitemap: https://example.com/sitemap.xml
+Disallow: /tmp*/cache
+";
+
+    #[test]
+    fn test_named_group_takes_precedence() {
+        let rules = RobotsRules::parse(ROBOTS, ROBOTS_USER_AGENT);
+        assert!(!rules.is_allowed("/private/notes"));
+        assert!(rules.is_allowed("/private/open/door"));
+        assert!(!rules.is_allowed("/tmp-1/cache/x"));
+        assert!(rules.is_allowed("/tmp-1/data"));
+        // The `*` group doesn't apply once a group names Daedra
+        assert!(rules.is_allowed("/admin/"));
+
+        let rules = RobotsRules::parse(ROBOTS, "otherbot");
+        assert!(!rules.is_allowed("/admin/users"));
+        assert!(rules.is_allowed("/admin/help"));
+        assert!(!rules.is_allowed("/search?q=rust"));
+        assert!(!rules.is_allowed("/files/report.pdf"));
+        assert!(rules.is_allowed("/files/report.pdf?download=1"));
+        assert!(rules.is_allowed("/private/notes"));
+        assert!(rules.is_allowed("/robots.txt"));
+    }
+
+    #[test]
+    fn test_empty_or_missing_rules_allow_everything() {
+        assert!(RobotsRules::parse("", ROBOTS_USER_AGENT).is_allowed("/anything"));
+        let rules = RobotsRules::parse("User-agent: *\nDisallow:\n", ROBOTS_USER_AGENT);
+        assert!(rules.is_allowed("/anything"));
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /\n", ROBOTS_USER_AGENT);
+        assert!(!rules.is_allowed("/"));
+        assert!(!rules.is_allowed("/page"));
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/fish", "/fish.html"));
+        assert!(!pattern_matches("/fish", "/Fish"));
+        assert!(pattern_matches("/fish*.php", "/fish/salmon.php?id=1"));
+        assert!(pattern_matches("/*.php$", "/a/b.php"));
+        assert!(!pattern_matches("/*.php$", "/a/b.php5"));
+        assert!(pattern_matches("/fish$", "/fish"));
+        assert!(!pattern_matches("/fish$", "/fishes"));
+        assert!(pattern_matches("*", "/x"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_fetches_each_site_once() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nDisallow: /no\n"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cache = RobotsCache::default();
+        let client = Client::new();
+        let base = Url::parse(&server.uri()).unwrap();
+        assert!(cache.is_allowed(&client, &base.join("/yes").unwrap()).await);
+        assert!(!cache.is_allowed(&client, &base.join("/no/way").unwrap()).await);
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_failing_robots_allow_everything() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let missing = MockServer::start().await;
+        let failing = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&failing)
+            .await;
+        let cache = RobotsCache::default();
+        let client = Client::new();
+        let page = Url::parse(&missing.uri()).unwrap().join("/page").unwrap();
+        assert!(cache.is_allowed(&client, &page).await);
+        // A server error isn't cached, so it is asked again
+        let page = Url::parse(&failing.uri()).unwrap().join("/page").unwrap();
+        assert!(cache.is_allowed(&client, &page).await);
+        assert!(cache.is_allowed(&client, &page).await);
+    }
+}
@@ -3,36 +3,69 @@
 //! This module provides web search functionality using DuckDuckGo's
 //! HTML interface. Note: DDG blocks datacenter/VPS IPs since mid-2025.
 //! Use as fallback only — prefer Bing/Serper/Tavily backends.
+//!
+//! When `html.duckduckgo.com` is blocked or its markup stops parsing, the
+//! table-based `lite.duckduckgo.com` page is tried with its own parser. An
+//! anomaly (CAPTCHA) page on both is reported as [`DaedraError::CaptchaChallenged`].
+//!
+//! A results page holds roughly 10-25 hits; larger `num_results` values follow
+//! the page's "Next" form, pausing between requests, for up to five pages.
 
 use super::backend::SearchBackend;
+use super::http::HttpSettings;
+use super::retry_after;
+use crate::domains;
+use crate::fingerprint::Rotation;
+use crate::privacy::{self, ClientProfile};
+use crate::regions;
+use crate::telemetry::{self, CountedRequest, CountedResponse};
+use crate::text;
 use crate::types::{
-    ContentType, DaedraError, DaedraResult, ResultMetadata, SearchArgs, SearchOptions,
-    SearchResponse, SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
-use backoff::{ExponentialBackoff, future::retry};
+use backoff::{ExponentialBackoff, future::retry_notify};
 use futures::future::join_all;
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::Client;
 use scraper::{ElementRef, Html, Selector};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{error, info, instrument, warn};
 use url::Url;
 
-/// Default user agent for requests
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
-
 /// DuckDuckGo HTML search URL
 const DDG_HTML_URL: &str = "https://html.duckduckgo.com/html/";
 
+/// DuckDuckGo Lite search URL (secondary scrape path)
+const DDG_LITE_URL: &str = "https://lite.duckduckgo.com/lite/";
+
 /// Request timeout
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Redirects followed before giving up, as reqwest does by default
+const MAX_REDIRECTS: usize = 10;
+
+/// Longest a search keeps retrying
+const RETRY_BUDGET: Duration = Duration::from_secs(60);
+
 /// Maximum concurrent requests for parallel processing
 const MAX_CONCURRENT_REQUESTS: usize = 5;
 
+/// Most result pages fetched for one search (one page holds ~10-25 results)
+const MAX_PAGES: usize = 5;
+
+/// Pause between result pages, to stay under DDG's bot heuristics
+const PAGE_DELAY: Duration = Duration::from_millis(1500);
+
+/// Markup unique to DuckDuckGo's anomaly (CAPTCHA) interstitial
+const CAPTCHA_MARKERS: &[&str] = &["anomaly-modal", "/anomaly.js", "challenge-form"];
+
+/// Classes DuckDuckGo puts on ad blocks and ad badges
+const AD_CLASSES: &[&str] = &["result--ad", "result-sponsored", "badge--ad"];
+
 lazy_static! {
     /// Selector for search results
     static ref RESULT_SELECTOR: Selector = Selector::parse("div.result").unwrap();
@@ -43,8 +76,21 @@ lazy_static! {
     /// Selector for result snippet
     static ref SNIPPET_SELECTOR: Selector = Selector::parse("a.result__snippet").unwrap();
 
-    /// Regex for cleaning HTML entities
-    static ref HTML_ENTITY_REGEX: Regex = Regex::new(r"&#x([0-9a-fA-F]+);").unwrap();
+    /// Selector for the "Ad" badge inside a result
+    static ref AD_BADGE_SELECTOR: Selector = Selector::parse(".badge--ad").unwrap();
+
+    /// Selector for the pagination forms at the bottom of a results page
+    static ref NAV_FORM_SELECTOR: Selector = Selector::parse("div.nav-link form").unwrap();
+
+    /// Selector for a pagination form's submit button
+    static ref NAV_SUBMIT_SELECTOR: Selector = Selector::parse("input[type=submit]").unwrap();
+
+    /// Selector for a pagination form's carried-over parameters
+    static ref NAV_HIDDEN_SELECTOR: Selector = Selector::parse("input[type=hidden]").unwrap();
+
+    /// Selector for DDG Lite result links and snippets, in document order
+    static ref LITE_ITEM_SELECTOR: Selector =
+        Selector::parse("a.result-link, td.result-snippet").unwrap();
 
     /// Regex for domain extraction
     static ref DOMAIN_REGEX: Regex = Regex::new(r"^(?:https?://)?([^/]+)").unwrap();
@@ -54,40 +100,177 @@ lazy_static! {
 #[derive(Clone)]
 pub struct SearchClient {
     client: Client,
+    http: HttpSettings,
+    html_url: String,
+    lite_url: String,
+    page_delay: Duration,
+}
+
+/// Builds a [`SearchClient`] with HTTP settings of its own, or around a
+/// [`Client`] the embedder set up (see [`super::http`])
+#[derive(Debug)]
+pub struct SearchClientBuilder {
+    http: HttpSettings,
+    html_url: String,
+    lite_url: String,
+    page_delay: Duration,
+}
+
+impl SearchClientBuilder {
+    /// Send requests with `client` as is, TLS, proxy and middleware included;
+    /// the timeout, user agent and redirect limit set here no longer apply
+    pub fn client(mut self, client: Client) -> Self {
+        self.http.client = Some(client);
+        self
+    }
+
+    /// Give up on a request after `timeout` (30 seconds by default)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = timeout;
+        self
+    }
+
+    /// Send `user_agent` on every request instead of rotating browser profiles'
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Follow at most `max` redirects (10 by default); 0 follows none
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.http.max_redirects = max;
+        self
+    }
+
+    /// Send the headers of `profile` ([`ClientProfile::Standard`] by default)
+    pub fn client_profile(mut self, profile: ClientProfile) -> Self {
+        self.http.profile = profile;
+        self
+    }
+
+    /// Take browser profiles from `rotation` (the built-in pool by default);
+    /// clients given the same one take turns in it
+    pub fn browser_profiles(mut self, rotation: Arc<Rotation>) -> Self {
+        self.http.rotation = rotation;
+        self
+    }
+
+    /// Query custom HTML and Lite endpoints (proxies, tests)
+    pub fn endpoints(mut self, html_url: impl Into<String>, lite_url: impl Into<String>) -> Self {
+        self.html_url = html_url.into();
+        self.lite_url = lite_url.into();
+        self
+    }
+
+    /// Pause `delay` between result pages
+    pub fn page_delay(mut self, delay: Duration) -> Self {
+        self.page_delay = delay;
+        self
+    }
+
+    /// The search client
+    pub fn build(self) -> DaedraResult<SearchClient> {
+        let max_redirects = self.http.max_redirects;
+        let client = self.http.build(|builder| {
+            builder.redirect(match max_redirects {
+                0 => reqwest::redirect::Policy::none(),
+                max => reqwest::redirect::Policy::limited(max),
+            })
+        })?;
+        Ok(SearchClient {
+            client,
+            http: self.http,
+            html_url: self.html_url,
+            lite_url: self.lite_url,
+            page_delay: self.page_delay,
+        })
+    }
 }
 
 impl SearchClient {
     /// Create a new search client
     pub fn new() -> DaedraResult<Self> {
-        let client = Client::builder()
-            .user_agent(USER_AGENT)
-            .timeout(REQUEST_TIMEOUT)
-            .gzip(true)
-            .brotli(true)
-            .build()
-            .map_err(DaedraError::HttpError)?;
+        Self::builder().build()
+    }
+
+    /// A builder for a search client with its own HTTP settings
+    pub fn builder() -> SearchClientBuilder {
+        SearchClientBuilder {
+            http: HttpSettings::new(REQUEST_TIMEOUT, MAX_REDIRECTS),
+            html_url: DDG_HTML_URL.to_string(),
+            lite_url: DDG_LITE_URL.to_string(),
+            page_delay: PAGE_DELAY,
+        }
+    }
+
+    /// Set the pause between result pages when more results are requested than
+    /// one page holds
+    pub fn with_page_delay(mut self, delay: Duration) -> Self {
+        self.page_delay = delay;
+        self
+    }
 
-        Ok(Self { client })
+    /// Create a client that queries custom HTML and Lite endpoints (proxies, tests)
+    pub fn with_endpoints(
+        html_url: impl Into<String>,
+        lite_url: impl Into<String>,
+    ) -> DaedraResult<Self> {
+        Self::builder().endpoints(html_url, lite_url).build()
     }
 
     /// Perform a DuckDuckGo search
-    #[instrument(skip(self), fields(query = %args.query))]
+    #[instrument(skip(self, args), fields(query = %privacy::loggable(&args.query)))]
     pub async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
         let options = args.options.clone().unwrap_or_default();
 
-        info!(query = %args.query, region = %options.region, "Performing search");
+        info!(
+            query = %privacy::loggable(&args.query),
+            region = %options.region,
+            "Performing search"
+        );
 
         // Build search parameters
         let params = self.build_search_params(&args.query, &options);
 
-        // Execute search with retry
-        let html = self.execute_search_with_retry(&params).await?;
+        // Execute search with retry; a hard block on the HTML endpoint falls through to Lite
+        let html = match self.execute_search_with_retry(&self.html_url, &params).await {
+            Ok(html) => html,
+            Err(DaedraError::SearchError(reason)) => {
+                warn!(%reason, "DuckDuckGo HTML endpoint failed, trying Lite");
+                let results = self.search_lite(&params, &options).await?;
+                info!(
+                    query = %privacy::loggable(&args.query),
+                    result_count = results.len(),
+                    "Lite search completed"
+                );
+                return Ok(SearchResponse::new(args.query.clone(), results, &options));
+            },
+            Err(e) => return Err(e),
+        };
 
-        // Parse results
-        let results = self.parse_search_results(&html, options.num_results)?;
+        // Parse results. Zero results from a non-empty page usually means changed
+        // markup or a CAPTCHA, not an empty SERP; the Lite page parses independently.
+        let results = match self.parse_search_results(&html, &options) {
+            Ok(results) if !results.is_empty() => {
+                self.fetch_more_pages(html, results, &options).await
+            },
+            Ok(results) if html.trim().is_empty() => results,
+            primary => {
+                info!("Primary DuckDuckGo parser found nothing, trying Lite");
+                match (primary, self.search_lite(&params, &options).await) {
+                    (_, Ok(results)) if !results.is_empty() => results,
+                    (Err(e), _) | (_, Err(e @ DaedraError::CaptchaChallenged(_))) => return Err(e),
+                    (Ok(results), Ok(_)) => results,
+                    (Ok(results), Err(e)) => {
+                        warn!(error = %e, "DuckDuckGo Lite fallback failed");
+                        results
+                    },
+                }
+            },
+        };
 
         info!(
-            query = %args.query,
+            query = %privacy::loggable(&args.query),
             result_count = results.len(),
             "Search completed"
         );
@@ -104,31 +287,41 @@ impl SearchClient {
         ];
 
         // Add time range if specified
-        if let Some(ref time_range) = options.time_range {
-            params.push(("df", time_range.clone()));
+        if let Some(time_range) = options.time_range {
+            params.push(("df", time_range.to_string()));
         }
 
         params
     }
 
-    /// Execute search with exponential backoff retry
-    async fn execute_search_with_retry(&self, params: &[(&str, String)]) -> DaedraResult<String> {
+    /// Execute search with exponential backoff retry. The request's
+    /// `Accept-Language` matches its `kl` region, so headers and query agree.
+    async fn execute_search_with_retry(
+        &self,
+        endpoint: &str,
+        params: &[(&str, String)],
+    ) -> DaedraResult<String> {
         let backoff = ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(60)),
+            max_elapsed_time: Some(RETRY_BUDGET),
             ..Default::default()
         };
 
         let client = self.client.clone();
+        let http = &self.http;
         let params_owned: Vec<(String, String)> = params
             .iter()
             .map(|(k, v)| (k.to_string(), v.clone()))
             .collect();
+        let accept_language =
+            params.iter().find(|(k, _)| *k == "kl").and_then(|(_, v)| regions::accept_language(v));
+        let started = Instant::now();
 
-        retry(backoff, || async {
+        let op = || async {
             let response = client
-                .post(DDG_HTML_URL)
+                .post(endpoint)
                 .form(&params_owned)
-                .send()
+                .headers(http.request_headers(accept_language.as_deref()))
+                .send_counted()
                 .await
                 .map_err(|e| {
                     warn!(error = %e, "Search request failed, retrying...");
@@ -140,7 +333,7 @@ impl SearchClient {
                 warn!(status = %status, "Search returned non-success status");
 
                 if status.as_u16() == 429 {
-                    return Err(backoff::Error::transient(DaedraError::RateLimitExceeded));
+                    return Err(retry_after::rate_limited(response.headers(), started, RETRY_BUDGET));
                 }
 
                 return Err(backoff::Error::permanent(DaedraError::SearchError(
@@ -148,33 +341,91 @@ impl SearchClient {
                 )));
             }
 
-            response.text().await.map_err(|e| {
+            response.text_counted().await.map_err(|e| {
                 error!(error = %e, "Failed to read response body");
                 backoff::Error::permanent(DaedraError::HttpError(e))
             })
-        })
-        .await
+        };
+        retry_notify(backoff, op, |_, _| telemetry::record_retry()).await
     }
 
-    /// Parse search results from HTML response
-    fn parse_search_results(
+    /// Follow the "Next" form until `wanted` results are collected, the results
+    /// run out, or [`MAX_PAGES`] pages have been read.
+    ///
+    /// A failing later page ends pagination; the results so far are kept.
+    async fn fetch_more_pages(
         &self,
-        html: &str,
-        max_results: usize,
-    ) -> DaedraResult<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
-        let mut results = Vec::new();
-
-        for element in document.select(&RESULT_SELECTOR) {
-            if results.len() >= max_results {
+        mut html: String,
+        mut results: Vec<SearchResult>,
+        options: &SearchOptions,
+    ) -> Vec<SearchResult> {
+        let wanted = options.num_results;
+        let mut seen: std::collections::HashSet<String> =
+            results.iter().map(|r| r.url.clone()).collect();
+
+        for page in 2..=MAX_PAGES {
+            if results.len() >= wanted {
                 break;
             }
-            if let Some(result) = extract_result_from_element(&element) {
-                results.push(result);
+            let Some(next) = next_page_params(&html) else {
+                break;
+            };
+            tokio::time::sleep(self.page_delay).await;
+
+            let params: Vec<(&str, String)> =
+                next.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+            html = match self.execute_search_with_retry(&self.html_url, &params).await {
+                Ok(html) => html,
+                Err(e) => {
+                    warn!(page, error = %e, "Failed to fetch next results page");
+                    break;
+                },
+            };
+            let before = results.len();
+            for result in parse_serp(&html, wanted, options.include_sponsored) {
+                if results.len() >= wanted {
+                    break;
+                }
+                if seen.insert(result.url.clone()) {
+                    results.push(result);
+                }
             }
+            info!(page, added = results.len() - before, "Fetched next results page");
+            if results.len() == before {
+                break;
+            }
+        }
+
+        results
+    }
+
+    /// Query the Lite endpoint and parse its results
+    async fn search_lite(
+        &self,
+        params: &[(&str, String)],
+        options: &SearchOptions,
+    ) -> DaedraResult<Vec<SearchResult>> {
+        let html = self.execute_search_with_retry(&self.lite_url, params).await?;
+        if is_captcha_page(&html) {
+            warn!("DuckDuckGo Lite served a CAPTCHA challenge");
+            return Err(DaedraError::CaptchaChallenged("duckduckgo".to_string()));
         }
+        Ok(parse_lite_serp(&html, options.num_results, options.include_sponsored))
+    }
+
+    /// Parse search results from HTML response
+    fn parse_search_results(
+        &self,
+        html: &str,
+        options: &SearchOptions,
+    ) -> DaedraResult<Vec<SearchResult>> {
+        let results = parse_serp(html, options.num_results, options.include_sponsored);
 
         if results.is_empty() {
+            if is_captcha_page(html) {
+                warn!("DuckDuckGo served a CAPTCHA challenge");
+                return Err(DaedraError::CaptchaChallenged("duckduckgo".to_string()));
+            }
             warn!("No search results found in response");
         }
 
@@ -182,13 +433,50 @@ impl SearchClient {
     }
 }
 
+/// Parse up to `max_results` results out of a DuckDuckGo HTML SERP.
+///
+/// Ads are left out. Malformed or unrelated markup yields an empty list rather
+/// than an error.
+pub fn parse_search_results(html: &str, max_results: usize) -> Vec<SearchResult> {
+    parse_serp(html, max_results, false)
+}
+
+/// [`parse_search_results`], keeping ads marked as sponsored when `include_sponsored`
+fn parse_serp(html: &str, max_results: usize, include_sponsored: bool) -> Vec<SearchResult> {
+    let document = Html::parse_document(html);
+    let mut results = Vec::new();
+
+    for element in document.select(&RESULT_SELECTOR) {
+        if results.len() >= max_results {
+            break;
+        }
+        if let Some(result) = extract_result_from_element(&element)
+            && (include_sponsored || !result.metadata.sponsored)
+        {
+            results.push(result);
+        }
+    }
+
+    results
+}
+
 /// Extract a single search result from a DDG result div element.
+///
+/// An ad comes back marked sponsored, pointing at the advertiser's site rather
+/// than the ad click-through; an ad that does not name the site is dropped.
 pub(crate) fn extract_result_from_element(element: &ElementRef) -> Option<SearchResult> {
     let title_element = element.select(&TITLE_SELECTOR).next()?;
 
     let title = clean_text(&title_element.text().collect::<String>());
     let href = title_element.value().attr("href")?;
-    let url = extract_actual_url(href);
+    let sponsored = has_ad_class(element)
+        || element.select(&AD_BADGE_SELECTOR).next().is_some()
+        || is_ad_redirect(href);
+    let url = if is_ad_redirect(href) {
+        ad_landing_url(href)?
+    } else {
+        extract_actual_url(href)
+    };
 
     if url.is_empty() || !url.starts_with("http") {
         return None;
@@ -201,7 +489,7 @@ pub(crate) fn extract_result_from_element(element: &ElementRef) -> Option<Search
         .unwrap_or_default();
 
     let content_type = detect_content_type(&url);
-    let source = extract_domain(&url);
+    let source = extract_domain(&url).into();
 
     Some(SearchResult {
         title,
@@ -212,10 +500,141 @@ pub(crate) fn extract_result_from_element(element: &ElementRef) -> Option<Search
             source,
             favicon: None,
             published_date: None,
+            sponsored,
+            ..Default::default()
         },
     })
 }
 
+/// Form fields that request the page after this one, from the "Next" button.
+///
+/// Later pages also carry a "Previous" form, so the form is picked by its
+/// button. `None` on the last page.
+fn next_page_params(html: &str) -> Option<Vec<(String, String)>> {
+    let document = Html::parse_document(html);
+    let form = document.select(&NAV_FORM_SELECTOR).find(|form| {
+        form.select(&NAV_SUBMIT_SELECTOR)
+            .any(|button| button.value().attr("value").is_some_and(|v| v.starts_with("Next")))
+    })?;
+    let params: Vec<(String, String)> = form
+        .select(&NAV_HIDDEN_SELECTOR)
+        .filter_map(|input| {
+            let name = input.value().attr("name")?;
+            Some((name.to_string(), input.value().attr("value").unwrap_or_default().to_string()))
+        })
+        .collect();
+    params.iter().any(|(name, _)| name == "q").then_some(params)
+}
+
+/// Whether `html` is DuckDuckGo's anomaly (CAPTCHA) page rather than a SERP.
+pub fn is_captcha_page(html: &str) -> bool {
+    CAPTCHA_MARKERS.iter().any(|marker| html.contains(marker))
+}
+
+/// Parse up to `max_results` results out of a DuckDuckGo Lite SERP.
+///
+/// Lite lays results out as table rows: a `a.result-link` row followed by a
+/// `td.result-snippet` row. Sponsored rows and links back into DuckDuckGo are
+/// skipped. Malformed or unrelated markup yields an empty list.
+pub fn parse_lite_results(html: &str, max_results: usize) -> Vec<SearchResult> {
+    parse_lite_serp(html, max_results, false)
+}
+
+/// [`parse_lite_results`], keeping ads marked as sponsored when `include_sponsored`
+fn parse_lite_serp(html: &str, max_results: usize, include_sponsored: bool) -> Vec<SearchResult> {
+    let document = Html::parse_document(html);
+    let mut results: Vec<SearchResult> = Vec::new();
+    // Whether the latest link was kept, so an ad's snippet is not attached to it
+    let mut last_kept = false;
+
+    for element in document.select(&LITE_ITEM_SELECTOR) {
+        if element.value().name() == "td" {
+            if last_kept
+                && let Some(result) = results.last_mut()
+                && result.description.is_empty()
+            {
+                result.description = clean_text(&element.text().collect::<String>());
+            }
+            continue;
+        }
+
+        if results.len() >= max_results {
+            break;
+        }
+        last_kept = false;
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let sponsored = is_sponsored(&element) || is_ad_redirect(href);
+        if sponsored && !include_sponsored {
+            continue;
+        }
+        let url = if is_ad_redirect(href) {
+            match ad_landing_url(href) {
+                Some(url) => url,
+                None => continue,
+            }
+        } else {
+            extract_actual_url(href)
+        };
+        if !url.starts_with("http") || (!sponsored && is_ddg_internal(&url)) {
+            continue;
+        }
+        let title = clean_text(&element.text().collect::<String>());
+        if title.is_empty() {
+            continue;
+        }
+
+        results.push(SearchResult {
+            title,
+            description: String::new(),
+            metadata: ResultMetadata {
+                content_type: detect_content_type(&url),
+                source: extract_domain(&url).into(),
+                sponsored,
+                ..Default::default()
+            },
+            url,
+        });
+        last_kept = true;
+    }
+
+    results
+}
+
+/// Lite marks ad rows with `result-sponsored` on the enclosing `<tr>`.
+fn is_sponsored(element: &ElementRef) -> bool {
+    element.ancestors().filter_map(ElementRef::wrap).any(|el| has_ad_class(&el))
+}
+
+fn has_ad_class(element: &ElementRef) -> bool {
+    element.value().classes().any(|c| AD_CLASSES.contains(&c))
+}
+
+/// Ads link through DuckDuckGo's `y.js` click tracker instead of `l/?uddg=`.
+fn is_ad_redirect(href: &str) -> bool {
+    href.contains("duckduckgo.com/y.js") || href.starts_with("/y.js")
+}
+
+/// The advertiser's site, from the `ad_domain` parameter of an ad link.
+fn ad_landing_url(href: &str) -> Option<String> {
+    let absolute = Url::parse("https://duckduckgo.com/").ok()?.join(href).ok()?;
+    let domain = absolute
+        .query_pairs()
+        .find(|(k, _)| k == "ad_domain")
+        .map(|(_, v)| v.trim().to_lowercase())
+        .filter(|d| !d.is_empty() && !d.contains(['/', ' ']))?;
+    Some(format!("https://{}/", domain))
+}
+
+/// Ad click-throughs and "more results" links point back at duckduckgo.com.
+fn is_ddg_internal(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h == "duckduckgo.com" || h.ends_with(".duckduckgo.com")))
+        .unwrap_or(false)
+}
+
 impl Default for SearchClient {
     fn default() -> Self {
         Self::new().expect("Failed to create default search client")
@@ -318,51 +737,22 @@ fn detect_content_type(url: &str) -> ContentType {
     crate::url_classification::classify_search_url(url)
 }
 
-/// Extract domain from URL
+/// Extract domain from URL, internationalized names in Unicode (see
+/// [`domains::display_host`])
 fn extract_domain(url: &str) -> String {
-    Url::parse(url)
-        .map(|u| u.host_str().unwrap_or("unknown").to_string())
-        .unwrap_or_else(|_| {
-            DOMAIN_REGEX
-                .captures(url)
-                .and_then(|caps| caps.get(1))
-                .map(|m| m.as_str().to_string())
-                .unwrap_or_else(|| "unknown".to_string())
-        })
+    let host = match Url::parse(url) {
+        Ok(u) => u.host_str().map(str::to_string),
+        Err(_) => DOMAIN_REGEX
+            .captures(url)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().to_string()),
+    };
+    host.map(|h| domains::display_host(&h)).unwrap_or_else(|| "unknown".to_string())
 }
 
-/// Clean text by removing HTML entities and extra whitespace
+/// Clean text by decoding HTML entities and collapsing whitespace
 fn clean_text(text: &str) -> String {
-    let mut cleaned = text.to_string();
-
-    // Decode HTML entities
-    cleaned = cleaned
-        .replace("&#x27;", "'")
-        .replace("&quot;", "\"")
-        .replace("&amp;", "&")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&nbsp;", " ");
-
-    // Handle hex entities
-    cleaned = HTML_ENTITY_REGEX
-        .replace_all(&cleaned, |caps: &regex::Captures| {
-            let hex = &caps[1];
-            u32::from_str_radix(hex, 16)
-                .ok()
-                .and_then(char::from_u32)
-                .map(|c| c.to_string())
-                .unwrap_or_else(|| caps[0].to_string())
-        })
-        .to_string();
-
-    // Normalize whitespace
-    cleaned
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ")
-        .trim()
-        .to_string()
+    text::clean(text)
 }
 
 // Implement SearchBackend trait for DDG
@@ -373,6 +763,18 @@ impl SearchBackend for SearchClient {
     }
 
     fn name(&self) -> &str { "duckduckgo" }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let options = args.options.clone().unwrap_or_default();
+        let params = self.build_search_params(&args.query, &options);
+        let mut request = PlannedRequest::post_form(&self.html_url, &params);
+        let mut note = format!("falls back to {} if blocked or unparseable", self.lite_url);
+        if options.num_results > 10 {
+            note.push_str(&format!("; up to {} more pages for num_results", MAX_PAGES - 1));
+        }
+        request.note = Some(note);
+        Some(request)
+    }
 }
 
 #[cfg(test)]
@@ -436,8 +838,13 @@ mod tests {
             extract_domain("https://docs.rust-lang.org"),
             "docs.rust-lang.org"
         );
+        // Internationalized names read in Unicode, unless they imitate another
+        assert_eq!(extract_domain("https://bücher.de/suche"), "bücher.de");
+        assert_eq!(extract_domain("https://xn--bcher-kva.de/"), "bücher.de");
+        assert_eq!(extract_domain("https://pаypal.com/"), "xn--pypal-4ve.com");
         // For non-URL strings, the regex still extracts the text as a potential domain
         assert_eq!(extract_domain("invalid"), "invalid");
+        assert_eq!(extract_domain("xn--bcher-kva.de/suche"), "bücher.de");
         // Empty string should return unknown
         assert_eq!(extract_domain(""), "unknown");
     }
@@ -448,6 +855,7 @@ mod tests {
         assert_eq!(clean_text("Hello &amp; World"), "Hello & World");
         assert_eq!(clean_text("  Multiple   spaces  "), "Multiple spaces");
         assert_eq!(clean_text("&lt;html&gt;"), "<html>");
+        assert_eq!(clean_text("Caf&eacute; &#8217;s&nbsp;menu"), "Café \u{2019}s menu");
     }
 
     #[test]
@@ -457,7 +865,8 @@ mod tests {
             region: "us-en".to_string(),
             safe_search: crate::types::SafeSearchLevel::Strict,
             num_results: 10,
-            time_range: Some("w".to_string()),
+            time_range: Some(crate::types::TimeRange::Week),
+            ..Default::default()
         };
 
         let params = client.build_search_params("test query", &options);
@@ -473,6 +882,13 @@ mod tests {
         assert_eq!(extract_actual_url(direct), direct);
     }
 
+    fn first(num_results: usize) -> SearchOptions {
+        SearchOptions {
+            num_results,
+            ..Default::default()
+        }
+    }
+
     fn extract_from_result_html(html: &str) -> Option<SearchResult> {
         let fragment = Html::parse_fragment(html);
         let element = fragment.select(&RESULT_SELECTOR).next()?;
@@ -509,7 +925,8 @@ mod tests {
     #[test]
     fn test_parse_search_results_empty_html() {
         let client = SearchClient::new().unwrap();
-        let results = client.parse_search_results("<html><body></body></html>", 10).unwrap();
+        let html = "<html><body></body></html>";
+        let results = client.parse_search_results(html, &first(10)).unwrap();
         assert!(results.is_empty());
     }
 
@@ -517,13 +934,342 @@ mod tests {
     fn test_parse_search_results_with_results() {
         let html = r#"<div class="result"><a href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fexample.com&rut=abc" class="result__a">Example Title</a><a class="result__snippet">Example snippet</a></div>"#;
         let client = SearchClient::new().unwrap();
-        let results = client.parse_search_results(html, 10).unwrap();
+        let results = client.parse_search_results(html, &first(10)).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].title, "Example Title");
         assert_eq!(results[0].url, "https://example.com");
         assert_eq!(results[0].description, "Example snippet");
     }
 
+    const LITE_HTML: &str = r#"<table>
+        <tr class="result-sponsored"><td>1.</td><td><a rel="nofollow" href="https://duckduckgo.com/y.js?ad_domain=ads.example&u3=x" class="result-link">Sponsored thing</a></td></tr>
+        <tr class="result-sponsored"><td></td><td class="result-snippet">Buy now</td></tr>
+        <tr><td>1.&nbsp;</td><td><a rel="nofollow" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Ftokio.rs%2F&amp;rut=abc" class="result-link">Tokio</a></td></tr>
+        <tr><td></td><td class="result-snippet">An <b>asynchronous</b> runtime for Rust.</td></tr>
+        <tr><td></td><td><span class="link-text">tokio.rs</span></td></tr>
+        <tr><td>2.&nbsp;</td><td><a rel="nofollow" href="https://docs.rs/tokio" class="result-link">tokio - Rust</a></td></tr>
+        </table>"#;
+
+    const CAPTCHA_HTML: &str = r#"<html><body><form id="challenge-form" action="/anomaly.js?sv=html" method="POST">
+        <div class="anomaly-modal__title">Unfortunately, bots use DuckDuckGo too.</div>
+        </form></body></html>"#;
+
+    fn nav_form(label: &str, offset: usize) -> String {
+        format!(
+            r#"<div class="nav-link"><form action="/html/" method="post">
+            <input type="submit" class="btn btn--alt" value="{label}">
+            <input type="hidden" name="q" value="tokio">
+            <input type="hidden" name="s" value="{offset}">
+            <input type="hidden" name="dc" value="{}">
+            </form></div>"#,
+            offset + 1
+        )
+    }
+
+    fn serp_page(urls: &[&str], nav: &str) -> String {
+        let results: String = urls
+            .iter()
+            .map(|u| {
+                format!(r#"<div class="result"><a href="{u}" class="result__a">{u}</a></div>"#)
+            })
+            .collect();
+        format!("<html><body>{results}{nav}</body></html>")
+    }
+
+    #[test]
+    fn test_next_page_params() {
+        let nav = nav_form("Previous", 0) + &nav_form("Next", 20);
+        let html = serp_page(&["https://a.example"], &nav);
+        let params = next_page_params(&html).unwrap();
+        assert!(params.contains(&("q".to_string(), "tokio".to_string())));
+        assert!(params.contains(&("s".to_string(), "20".to_string())));
+        assert!(params.contains(&("dc".to_string(), "21".to_string())));
+
+        let last_page = serp_page(&["https://a.example"], &nav_form("Previous", 0));
+        assert!(next_page_params(&last_page).is_none());
+        assert!(next_page_params(&serp_page(&["https://a.example"], "")).is_none());
+    }
+
+    #[test]
+    fn test_is_captcha_page() {
+        assert!(is_captcha_page(CAPTCHA_HTML));
+        assert!(!is_captcha_page(LITE_HTML));
+        assert!(!is_captcha_page("<html><body></body></html>"));
+    }
+
+    #[test]
+    fn test_parse_search_results_captcha_is_error() {
+        let client = SearchClient::new().unwrap();
+        let err = client.parse_search_results(CAPTCHA_HTML, &first(10)).unwrap_err();
+        assert!(matches!(err, DaedraError::CaptchaChallenged(_)), "{err}");
+    }
+
+    #[test]
+    fn test_parse_lite_results() {
+        let results = parse_lite_results(LITE_HTML, 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Tokio");
+        assert_eq!(results[0].url, "https://tokio.rs/");
+        assert_eq!(results[0].description, "An asynchronous runtime for Rust.");
+        assert_eq!(results[0].metadata.source, "tokio.rs");
+        assert_eq!(results[1].url, "https://docs.rs/tokio");
+        assert_eq!(results[1].description, "");
+
+        assert_eq!(parse_lite_results(LITE_HTML, 1).len(), 1);
+        assert!(parse_lite_results("<html><body>Unexpected</body></html>", 10).is_empty());
+    }
+
+    #[test]
+    fn test_lite_ads_kept_as_sponsored_on_request() {
+        let results = parse_lite_serp(LITE_HTML, 10, true);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].title, "Sponsored thing");
+        assert_eq!(results[0].url, "https://ads.example/");
+        assert_eq!(results[0].description, "Buy now");
+        assert!(results[0].metadata.sponsored);
+        assert!(!results[1].metadata.sponsored);
+    }
+
+    const AD_HTML: &str = r#"
+        <div class="result results_links result--ad"><a class="result__a" href="https://duckduckgo.com/y.js?ad_domain=shop.example&amp;ad_provider=bingv7aa&amp;u3=x">Shop Rust mugs</a><a class="result__snippet">Ad - free shipping</a></div>
+        <div class="result results_links"><a class="result__a" href="//duckduckgo.com/y.js?ad_domain=&amp;u3=x">Unnamed ad</a></div>
+        <div class="result results_links"><span class="badge--ad">Ad</span><a class="result__a" href="https://badged.example/landing">Badged ad</a></div>
+        <div class="result results_links"><a class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Ftokio.rs%2F">Tokio</a></div>"#;
+
+    #[test]
+    fn test_ads_excluded_by_default() {
+        let client = SearchClient::new().unwrap();
+        let results = client.parse_search_results(AD_HTML, &first(10)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].url, "https://tokio.rs/");
+        assert!(!results[0].metadata.sponsored);
+        assert_eq!(parse_search_results(AD_HTML, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_ads_marked_sponsored_when_included() {
+        let client = SearchClient::new().unwrap();
+        let options = SearchOptions {
+            include_sponsored: true,
+            ..first(10)
+        };
+        let results = client.parse_search_results(AD_HTML, &options).unwrap();
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://shop.example/", "https://badged.example/landing", "https://tokio.rs/"]
+        );
+        let sponsored: Vec<bool> = results.iter().map(|r| r.metadata.sponsored).collect();
+        assert_eq!(sponsored, [true, true, false]);
+    }
+
+    mod fallback {
+        use super::*;
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn client(server: &MockServer) -> SearchClient {
+            SearchClient::with_endpoints(
+                format!("{}/html/", server.uri()),
+                format!("{}/lite/", server.uri()),
+            )
+            .unwrap()
+        }
+
+        fn args() -> SearchArgs {
+            SearchArgs {
+                query: "tokio".to_string(),
+                options: None,
+            }
+        }
+
+        fn captcha() -> ResponseTemplate {
+            ResponseTemplate::new(200).set_body_string(CAPTCHA_HTML)
+        }
+
+        async fn mount(server: &MockServer, route: &str, response: ResponseTemplate) {
+            Mock::given(method("POST"))
+                .and(path(route))
+                .respond_with(response)
+                .mount(server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn test_lite_used_when_primary_markup_unparseable() {
+            let server = MockServer::start().await;
+            let redesigned = r#"<html><body><div class="new-layout">x</div></body></html>"#;
+            mount(&server, "/html/", ResponseTemplate::new(200).set_body_string(redesigned)).await;
+            mount(&server, "/lite/", ResponseTemplate::new(200).set_body_string(LITE_HTML)).await;
+
+            let response = client(&server).search(&args()).await.unwrap();
+            assert_eq!(response.data.len(), 2);
+            assert_eq!(response.data[0].url, "https://tokio.rs/");
+        }
+
+        #[tokio::test]
+        async fn test_accept_language_follows_region() {
+            use wiremock::matchers::headers;
+
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/html/"))
+                .and(headers("accept-language", vec!["de-DE", "de;q=0.9"]))
+                .and(body_string_contains("kl=de-de"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_string(serp_page(&["https://a.de"], "")),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let args = SearchArgs {
+                query: "tokio".to_string(),
+                options: Some(SearchOptions {
+                    region: "de-de".to_string(),
+                    ..Default::default()
+                }),
+            };
+            let response = client(&server).search(&args).await.unwrap();
+            assert_eq!(response.data.len(), 1);
+            let context = response.metadata.search_context;
+            assert_eq!(context.country.as_deref(), Some("DE"));
+            assert_eq!(context.language.as_deref(), Some("de"));
+        }
+
+        #[tokio::test]
+        async fn test_builder_sends_with_the_given_client() {
+            use reqwest::header::{HeaderMap, HeaderValue};
+
+            let server = MockServer::start().await;
+            let page = ResponseTemplate::new(200).set_body_string(serp_page(&["https://a.de"], ""));
+            mount(&server, "/html/", page).await;
+            let mut headers = HeaderMap::new();
+            headers.insert("x-tenant", HeaderValue::from_static("acme"));
+            let embedded = Client::builder()
+                .user_agent("acme-bot/1.0")
+                .default_headers(headers)
+                .build()
+                .unwrap();
+            let client = SearchClient::builder()
+                .client(embedded)
+                .endpoints(format!("{}/html/", server.uri()), format!("{}/lite/", server.uri()))
+                .build()
+                .unwrap();
+
+            client.search(&args()).await.unwrap();
+            let requests = server.received_requests().await.unwrap();
+            assert_eq!(requests[0].headers["x-tenant"], "acme");
+            assert_eq!(requests[0].headers["user-agent"], "acme-bot/1.0");
+        }
+
+        #[tokio::test]
+        async fn test_lite_used_when_primary_blocked() {
+            let server = MockServer::start().await;
+            mount(&server, "/html/", ResponseTemplate::new(403)).await;
+            mount(&server, "/lite/", ResponseTemplate::new(200).set_body_string(LITE_HTML)).await;
+
+            let response = client(&server).search(&args()).await.unwrap();
+            assert_eq!(response.data.len(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_captcha_on_both_endpoints_is_captcha_error() {
+            let server = MockServer::start().await;
+            mount(&server, "/html/", captcha()).await;
+            mount(&server, "/lite/", captcha()).await;
+
+            let err = client(&server).search(&args()).await.unwrap_err();
+            assert!(matches!(err, DaedraError::CaptchaChallenged(_)), "{err}");
+        }
+
+        #[tokio::test]
+        async fn test_captcha_on_primary_recovers_via_lite() {
+            let server = MockServer::start().await;
+            mount(&server, "/html/", captcha()).await;
+            mount(&server, "/lite/", ResponseTemplate::new(200).set_body_string(LITE_HTML)).await;
+
+            let response = client(&server).search(&args()).await.unwrap();
+            assert_eq!(response.data.len(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_fetches_more_pages_until_count_reached() {
+            let server = MockServer::start().await;
+            let page2 = serp_page(
+                &["https://b.example", "https://c.example", "https://d.example"],
+                &nav_form("Next", 6),
+            );
+            Mock::given(method("POST"))
+                .and(path("/html/"))
+                .and(body_string_contains("s=3"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(page2))
+                .with_priority(1)
+                .expect(1)
+                .mount(&server)
+                .await;
+            let page1 =
+                serp_page(&["https://a.example", "https://b.example"], &nav_form("Next", 3));
+            mount(&server, "/html/", ResponseTemplate::new(200).set_body_string(page1)).await;
+
+            let args = SearchArgs {
+                query: "tokio".to_string(),
+                options: Some(SearchOptions {
+                    num_results: 4,
+                    ..Default::default()
+                }),
+            };
+            let client = client(&server).with_page_delay(Duration::ZERO);
+            let response = client.search(&args).await.unwrap();
+            let urls: Vec<&str> = response.data.iter().map(|r| r.url.as_str()).collect();
+            assert_eq!(
+                urls,
+                ["https://a.example", "https://b.example", "https://c.example", "https://d.example"]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_pagination_stops_when_pages_run_out() {
+            let server = MockServer::start().await;
+            // Every page offers a "Next" that repeats the same results
+            let page = serp_page(&["https://a.example"], &nav_form("Next", 1));
+            Mock::given(method("POST"))
+                .and(path("/html/"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(page))
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let args = SearchArgs {
+                query: "tokio".to_string(),
+                options: Some(SearchOptions {
+                    num_results: 50,
+                    ..Default::default()
+                }),
+            };
+            let client = client(&server).with_page_delay(Duration::ZERO);
+            let response = client.search(&args).await.unwrap();
+            assert_eq!(response.data.len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_lite_not_requested_when_primary_parses() {
+            let server = MockServer::start().await;
+            let html = r#"<div class="result"><a href="https://example.com" class="result__a">Example</a></div>"#;
+            mount(&server, "/html/", ResponseTemplate::new(200).set_body_string(html)).await;
+            Mock::given(method("POST"))
+                .and(path("/lite/"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(LITE_HTML))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            let response = client(&server).search(&args()).await.unwrap();
+            assert_eq!(response.data.len(), 1);
+            assert_eq!(response.data[0].url, "https://example.com");
+        }
+    }
+
     #[test]
     fn test_parse_search_results_respects_max() {
         let mut html = String::new();
@@ -533,7 +1279,7 @@ mod tests {
             ));
         }
         let client = SearchClient::new().unwrap();
-        let results = client.parse_search_results(&html, 2).unwrap();
+        let results = client.parse_search_results(&html, &first(2)).unwrap();
         assert_eq!(results.len(), 2);
     }
 }
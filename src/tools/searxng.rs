@@ -0,0 +1,299 @@
+//! SearXNG search backend — JSON API of a self-hosted metasearch instance.
+//!
+//! Needs the base URL of an instance with the `json` output format enabled
+//! (`search.formats` in `settings.yml`). No API key; the operator controls
+//! which upstream engines are queried, and each result carries their names.
+
+use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::regions;
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::types::{
+    DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SafeSearchLevel, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult, TimeRange,
+};
+use crate::url_classification::classify_search_url;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+/// SearXNG metasearch backend — queries a self-hosted instance's JSON API.
+pub struct SearxngBackend {
+    client: Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Deserialize)]
+struct SearxngResult {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    engine: Option<String>,
+    #[serde(default)]
+    engines: Vec<String>,
+    #[serde(default)]
+    score: Option<f64>,
+    #[serde(rename = "publishedDate", default)]
+    published_date: Option<String>,
+}
+
+impl SearxngBackend {
+    /// Create a backend for the instance at `base_url` (e.g. `http://localhost:8888`).
+    pub fn new(base_url: impl Into<String>) -> DaedraResult<Self> {
+        Self::with_client_profile(base_url, ClientProfile::Standard)
+    }
+
+    /// Create a backend for the instance at `base_url` whose requests send
+    /// the headers of `profile`.
+    pub fn with_client_profile(
+        base_url: impl Into<String>,
+        profile: ClientProfile,
+    ) -> DaedraResult<Self> {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        url::Url::parse(&base_url)?;
+        let client = privacy::client_builder(profile, Some("daedra/1.0"))
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(DaedraError::HttpError)?;
+        Ok(Self { client, base_url })
+    }
+
+    fn build_search_params(query: &str, options: &SearchOptions) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("format", "json".to_string()),
+            ("pageno", "1".to_string()),
+            ("safesearch", safesearch_value(options.safe_search).to_string()),
+            ("language", region_to_language(&options.region)),
+        ];
+        if let Some(range) = options.time_range.and_then(time_range_value) {
+            params.push(("time_range", range.to_string()));
+        }
+        params
+    }
+}
+
+/// SearXNG safesearch levels: 0 = off, 1 = moderate, 2 = strict.
+fn safesearch_value(level: SafeSearchLevel) -> u8 {
+    match level {
+        SafeSearchLevel::Off => 0,
+        SafeSearchLevel::Moderate => 1,
+        SafeSearchLevel::Strict => 2,
+    }
+}
+
+/// SearXNG's name for a time range; it has no absolute date ranges, so custom ones are dropped.
+fn time_range_value(range: TimeRange) -> Option<&'static str> {
+    match range {
+        TimeRange::Day => Some("day"),
+        TimeRange::Week => Some("week"),
+        TimeRange::Month => Some("month"),
+        TimeRange::Year => Some("year"),
+        TimeRange::Custom { .. } => None,
+    }
+}
+
+/// Convert a DDG region code (`us-en`, `wt-wt`) into a SearXNG language tag (`en-US`, `all`).
+fn region_to_language(region: &str) -> String {
+    regions::language_tag(region).unwrap_or_else(|| "all".to_string())
+}
+
+fn to_search_result(r: SearxngResult) -> SearchResult {
+    let mut engines = r.engines;
+    if engines.is_empty() {
+        engines.extend(r.engine);
+    }
+    SearchResult {
+        title: r.title,
+        metadata: ResultMetadata {
+            content_type: classify_search_url(&r.url),
+            source: "searxng".into(),
+            favicon: None,
+            published_date: r.published_date.filter(|d| !d.is_empty()),
+            engines,
+            score: r.score,
+            ..Default::default()
+        },
+        url: r.url,
+        description: r.content.unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = Self::build_search_params(&args.query, &opts);
+
+        let resp = self.client
+            .get(format!("{}/search", self.base_url))
+            .query(&params)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+
+        if !resp.status().is_success() {
+            return Err(DaedraError::SearchError(format!(
+                "SearXNG returned status {}",
+                resp.status()
+            )));
+        }
+
+        let data: SearxngResponse = resp.json_counted().await?;
+
+        let results: Vec<SearchResult> = data.results
+            .into_iter()
+            .filter(|r| !r.url.is_empty())
+            .take(opts.num_results)
+            .map(to_search_result)
+            .collect();
+
+        info!(backend = "searxng", results = results.len(), "SearXNG search complete");
+        Ok(SearchResponse::new(args.query.clone(), results, &opts))
+    }
+
+    fn name(&self) -> &str { "searxng" }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let params = Self::build_search_params(&args.query, &opts);
+        Some(PlannedRequest::get(&format!("{}/search", self.base_url), &params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentType;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "query": "tokio",
+            "results": [
+                {
+                    "url": "https://docs.rs/tokio",
+                    "title": "tokio - Rust",
+                    "content": "An event-driven, non-blocking I/O platform.",
+                    "engine": "duckduckgo",
+                    "engines": ["duckduckgo", "brave"],
+                    "score": 4.5,
+                    "category": "general",
+                    "publishedDate": null
+                },
+                {
+                    "url": "https://github.com/tokio-rs/tokio",
+                    "title": "tokio-rs/tokio",
+                    "engine": "github",
+                    "score": 1.0,
+                    "publishedDate": "2024-05-01T00:00:00"
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_searxng_name() {
+        let backend = SearxngBackend::new("http://localhost:8888").unwrap();
+        assert_eq!(backend.name(), "searxng");
+        assert!(!backend.requires_api_key());
+    }
+
+    #[test]
+    fn test_searxng_rejects_invalid_base_url() {
+        assert!(SearxngBackend::new("not a url").is_err());
+    }
+
+    #[test]
+    fn test_build_search_params() {
+        let options = SearchOptions {
+            region: "us-en".to_string(),
+            safe_search: SafeSearchLevel::Strict,
+            time_range: Some(TimeRange::Week),
+            ..Default::default()
+        };
+        let params = SearxngBackend::build_search_params("rust", &options);
+        assert!(params.contains(&("format", "json".to_string())));
+        assert!(params.contains(&("safesearch", "2".to_string())));
+        assert!(params.contains(&("language", "en-US".to_string())));
+        assert!(params.contains(&("time_range", "week".to_string())));
+    }
+
+    #[test]
+    fn test_region_to_language_worldwide() {
+        assert_eq!(region_to_language("wt-wt"), "all");
+        assert_eq!(region_to_language("bogus"), "all");
+        assert_eq!(region_to_language("de-de"), "de-DE");
+    }
+
+    #[test]
+    fn test_to_search_result_maps_engines_and_score() {
+        let data: SearxngResponse = serde_json::from_value(sample_response()).unwrap();
+        let results: Vec<SearchResult> = data.results.into_iter().map(to_search_result).collect();
+
+        assert_eq!(results[0].metadata.engines, vec!["duckduckgo", "brave"]);
+        assert_eq!(results[0].metadata.score, Some(4.5));
+        assert_eq!(results[0].metadata.source, "searxng");
+        assert_eq!(results[0].metadata.content_type, ContentType::Documentation);
+        assert!(results[0].metadata.published_date.is_none());
+
+        // Falls back to the single `engine` field when `engines` is absent.
+        assert_eq!(results[1].metadata.engines, vec!["github"]);
+        assert_eq!(results[1].description, "");
+        assert_eq!(
+            results[1].metadata.published_date.as_deref(),
+            Some("2024-05-01T00:00:00")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_searxng_search_against_mock_instance() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "tokio"))
+            .and(query_param("format", "json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .mount(&server)
+            .await;
+
+        let backend = SearxngBackend::new(format!("{}/", server.uri())).unwrap();
+        let args = SearchArgs {
+            query: "tokio".to_string(),
+            options: None,
+        };
+        let response = backend.search(&args).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[0].url, "https://docs.rs/tokio");
+    }
+
+    #[tokio::test]
+    async fn test_searxng_search_reports_http_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let backend = SearxngBackend::new(server.uri()).unwrap();
+        let args = SearchArgs {
+            query: "tokio".to_string(),
+            options: None,
+        };
+        let err = backend.search(&args).await.unwrap_err();
+        assert!(matches!(err, DaedraError::SearchError(msg) if msg.contains("403")));
+    }
+}
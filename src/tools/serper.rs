@@ -1,16 +1,24 @@
 //! Serper.dev search backend — Google results via API.
 //!
-//! Requires SERPER_API_KEY environment variable.
+//! Requires an API key, from SERPER_API_KEY or `ServerConfig::serper_api_key`.
 //! Free tier: 2500 queries. Pay-as-you-go after.
+//! Besides organic results, the knowledge graph, "people also ask" boxes and
+//! the top stories, videos and images modules are surfaced on
+//! [`SearchResponse`].
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::regions;
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    ContentType, DaedraError, DaedraResult, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, KnowledgeGraph, PlannedRequest, RelatedQuestion,
+    ResultMetadata, SearchArgs, SearchOptions, SearchResponse, SearchResult, VerticalItem,
+    Verticals,
 };
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tracing::info;
 
@@ -20,11 +28,22 @@ const SERPER_URL: &str = "https://google.serper.dev/search";
 pub struct SerperBackend {
     client: Client,
     api_key: String,
+    endpoint: String,
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct SerperResponse {
     organic: Option<Vec<SerperResult>>,
+    knowledge_graph: Option<SerperKnowledgeGraph>,
+    #[serde(default)]
+    people_also_ask: Vec<SerperQuestion>,
+    #[serde(default)]
+    top_stories: Vec<SerperVerticalItem>,
+    #[serde(default)]
+    videos: Vec<SerperVerticalItem>,
+    #[serde(default)]
+    images: Vec<SerperVerticalItem>,
 }
 
 #[derive(Deserialize)]
@@ -32,59 +51,401 @@ struct SerperResult {
     title: String,
     link: String,
     snippet: Option<String>,
+    date: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerperKnowledgeGraph {
+    title: String,
+    #[serde(rename = "type")]
+    entity_type: Option<String>,
+    description: Option<String>,
+    description_link: Option<String>,
+    website: Option<String>,
+    image_url: Option<String>,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct SerperQuestion {
+    question: String,
+    snippet: Option<String>,
+    title: Option<String>,
+    link: Option<String>,
+}
+
+/// An entry of the `topStories`, `videos` or `images` modules
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerperVerticalItem {
+    title: Option<String>,
+    link: Option<String>,
+    source: Option<String>,
+    channel: Option<String>,
+    date: Option<String>,
+    image_url: Option<String>,
+    thumbnail_url: Option<String>,
+    duration: Option<String>,
 }
 
 impl SerperBackend {
     /// Create a new Serper backend instance.
     pub fn new(api_key: String) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("HTTP client");
-        Self { client, api_key }
+        Self::with_endpoint(api_key, SERPER_URL)
+    }
+
+    /// Create a backend that posts to a custom endpoint (proxies, tests).
+    pub fn with_endpoint(api_key: String, endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            None,
+            Duration::from_secs(30),
+        );
+        Self {
+            client,
+            api_key,
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, None, Duration::from_secs(30));
+        self
+    }
+}
+
+impl From<SerperKnowledgeGraph> for KnowledgeGraph {
+    fn from(kg: SerperKnowledgeGraph) -> Self {
+        Self {
+            title: kg.title,
+            entity_type: kg.entity_type,
+            description: kg.description,
+            url: kg.description_link.or(kg.website),
+            image_url: kg.image_url,
+            attributes: kg.attributes,
+        }
     }
 }
 
+impl SerperVerticalItem {
+    /// Entries without a link are dropped
+    fn into_item(self) -> Option<VerticalItem> {
+        let url = self.link.filter(|l| l.starts_with("http"))?;
+        Some(VerticalItem {
+            title: self.title.unwrap_or_default(),
+            url,
+            source: self.source.or(self.channel),
+            date: self.date,
+            image_url: self.image_url.or(self.thumbnail_url),
+            duration: self.duration,
+        })
+    }
+}
+
+fn vertical(items: Vec<SerperVerticalItem>) -> Vec<VerticalItem> {
+    items.into_iter().filter_map(SerperVerticalItem::into_item).collect()
+}
+
+impl From<SerperQuestion> for RelatedQuestion {
+    fn from(q: SerperQuestion) -> Self {
+        Self {
+            question: q.question,
+            snippet: q.snippet,
+            title: q.title,
+            url: q.link,
+        }
+    }
+}
+
+fn to_search_response(data: SerperResponse, args: &SearchArgs) -> SearchResponse {
+    let opts = args.options.clone().unwrap_or_default();
+
+    let results: Vec<SearchResult> = data.organic.unwrap_or_default()
+        .into_iter()
+        .map(|r| SearchResult {
+            title: r.title,
+            url: r.link.clone(),
+            description: r.snippet.unwrap_or_default(),
+            metadata: ResultMetadata {
+                content_type: ContentType::Other,
+                source: "serper".into(),
+                favicon: None,
+                published_date: r.date,
+                ..Default::default()
+            },
+        })
+        .take(opts.num_results)
+        .collect();
+
+    let mut response = SearchResponse::new(args.query.clone(), results, &opts);
+    response.knowledge_graph = data.knowledge_graph.map(KnowledgeGraph::from);
+    response.people_also_ask = data.people_also_ask.into_iter().map(RelatedQuestion::from).collect();
+    response.verticals = Verticals {
+        news: vertical(data.top_stories),
+        videos: vertical(data.videos),
+        images: vertical(data.images),
+    };
+    response
+}
+
+/// Search request; the region becomes Google's `gl` country and `hl` language.
+fn request_body(args: &SearchArgs, opts: &SearchOptions) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "q": args.query,
+        "num": opts.num_results
+    });
+    if let Some(locale) = regions::locale(&opts.region) {
+        if let Some(country) = locale.country_code() {
+            body["gl"] = country.into();
+        }
+        body["hl"] = locale.language.into();
+    }
+    body
+}
+
 #[async_trait]
 impl SearchBackend for SerperBackend {
     async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
         let opts = args.options.clone().unwrap_or_default();
-        let body = serde_json::json!({
-            "q": args.query,
-            "num": opts.num_results
-        });
+        let body = request_body(args, &opts);
 
         let resp = self.client
-            .post(SERPER_URL)
+            .post(&self.endpoint)
             .header("X-API-KEY", &self.api_key)
             .header("Content-Type", "application/json")
             .json(&body)
-            .send()
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
-        let data: SerperResponse = resp.json().await.map_err(DaedraError::HttpError)?;
-
-        let results: Vec<SearchResult> = data.organic.unwrap_or_default()
-            .into_iter()
-            .map(|r| SearchResult {
-                title: r.title,
-                url: r.link.clone(),
-                description: r.snippet.unwrap_or_default(),
-                metadata: ResultMetadata {
-                    content_type: ContentType::Other,
-                    source: "serper".to_string(),
-                    favicon: None,
-                    published_date: None,
-                },
-            })
-            .take(opts.num_results)
-            .collect();
+        if !resp.status().is_success() {
+            return Err(DaedraError::SearchError(format!(
+                "Serper returned status {}",
+                resp.status()
+            )));
+        }
+
+        let data: SerperResponse = resp.json_counted().await?;
+        let response = to_search_response(data, args);
 
-        info!(backend = "serper", results = results.len(), "Serper search complete");
-        Ok(SearchResponse::new(args.query.clone(), results, &opts))
+        info!(
+            backend = "serper",
+            results = response.data.len(),
+            knowledge_graph = response.knowledge_graph.is_some(),
+            people_also_ask = response.people_also_ask.len(),
+            verticals = !response.verticals.is_empty(),
+            "Serper search complete"
+        );
+        Ok(response)
     }
 
     fn name(&self) -> &str { "serper" }
     fn requires_api_key(&self) -> bool { true }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let body = request_body(args, &opts);
+        let request = PlannedRequest::post_json(&self.endpoint, body);
+        Some(request.with_note("API key in X-API-KEY header"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "searchParameters": {"q": "rust language", "type": "search"},
+            "knowledgeGraph": {
+                "title": "Rust",
+                "type": "Programming language",
+                "website": "https://www.rust-lang.org/",
+                "description": "Rust is a general-purpose programming language.",
+                "descriptionSource": "Wikipedia",
+                "descriptionLink": "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "attributes": {"Designed by": "Graydon Hoare", "First appeared": "2015"}
+            },
+            "organic": [
+                {
+                    "title": "Rust Programming Language",
+                    "link": "https://www.rust-lang.org/",
+                    "snippet": "A language empowering everyone.",
+                    "position": 1
+                },
+                {
+                    "title": "Announcing Rust 1.80",
+                    "link": "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html",
+                    "date": "Jul 25, 2024",
+                    "position": 2
+                }
+            ],
+            "peopleAlsoAsk": [
+                {
+                    "question": "Is Rust hard to learn?",
+                    "snippet": "Rust has a steeper learning curve than most languages.",
+                    "title": "Learn Rust",
+                    "link": "https://www.rust-lang.org/learn"
+                }
+            ],
+            "topStories": [
+                {
+                    "title": "Rust 1.80 lands LazyLock",
+                    "link": "https://news.example/rust-1-80",
+                    "source": "Example News",
+                    "date": "2 days ago",
+                    "imageUrl": "https://news.example/thumb.jpg"
+                },
+                {"title": "No link"}
+            ],
+            "videos": [
+                {
+                    "title": "Rust in 100 seconds",
+                    "link": "https://www.youtube.com/watch?v=5C_HPTJg5ek",
+                    "channel": "Fireship",
+                    "duration": "2:29",
+                    "imageUrl": "https://i.ytimg.com/vi/5C_HPTJg5ek/default.jpg"
+                }
+            ],
+            "images": [
+                {
+                    "title": "Rust logo",
+                    "imageUrl": "https://www.rust-lang.org/logos/rust-logo.png",
+                    "link": "https://www.rust-lang.org/"
+                }
+            ]
+        })
+    }
+
+    fn args() -> SearchArgs {
+        SearchArgs {
+            query: "rust language".to_string(),
+            options: None,
+        }
+    }
+
+    #[test]
+    fn test_serper_name() {
+        let backend = SerperBackend::new("key".to_string());
+        assert_eq!(backend.name(), "serper");
+        assert!(backend.requires_api_key());
+    }
+
+    #[test]
+    fn test_to_search_response_maps_answer_boxes() {
+        let data: SerperResponse = serde_json::from_value(sample_response()).unwrap();
+        let response = to_search_response(data, &args());
+
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[1].metadata.published_date.as_deref(), Some("Jul 25, 2024"));
+
+        let kg = response.knowledge_graph.unwrap();
+        assert_eq!(kg.title, "Rust");
+        assert_eq!(kg.entity_type.as_deref(), Some("Programming language"));
+        assert_eq!(
+            kg.url.as_deref(),
+            Some("https://en.wikipedia.org/wiki/Rust_(programming_language)")
+        );
+        assert_eq!(kg.attributes.get("Designed by").map(String::as_str), Some("Graydon Hoare"));
+
+        assert_eq!(response.people_also_ask.len(), 1);
+        assert_eq!(response.people_also_ask[0].question, "Is Rust hard to learn?");
+        assert_eq!(
+            response.people_also_ask[0].url.as_deref(),
+            Some("https://www.rust-lang.org/learn")
+        );
+    }
+
+    #[test]
+    fn test_to_search_response_maps_vertical_modules() {
+        let data: SerperResponse = serde_json::from_value(sample_response()).unwrap();
+        let verticals = to_search_response(data, &args()).verticals;
+
+        assert_eq!(verticals.news.len(), 1);
+        assert_eq!(verticals.news[0].source.as_deref(), Some("Example News"));
+        assert_eq!(verticals.news[0].date.as_deref(), Some("2 days ago"));
+        assert_eq!(verticals.videos[0].source.as_deref(), Some("Fireship"));
+        assert_eq!(verticals.videos[0].duration.as_deref(), Some("2:29"));
+        assert_eq!(verticals.images[0].url, "https://www.rust-lang.org/");
+        assert_eq!(
+            verticals.images[0].image_url.as_deref(),
+            Some("https://www.rust-lang.org/logos/rust-logo.png")
+        );
+    }
+
+    #[test]
+    fn test_to_search_response_without_answer_boxes() {
+        let data: SerperResponse =
+            serde_json::from_value(serde_json::json!({"organic": []})).unwrap();
+        let response = to_search_response(data, &args());
+        assert!(response.knowledge_graph.is_none());
+        assert!(response.people_also_ask.is_empty());
+        assert!(response.verticals.is_empty());
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("knowledge_graph").is_none());
+        assert!(json.get("people_also_ask").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serper_search_sends_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/search"))
+            .and(header("X-API-KEY", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .mount(&server)
+            .await;
+
+        let backend =
+            SerperBackend::with_endpoint("secret".to_string(), format!("{}/search", server.uri()));
+        let response = backend.search(&args()).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert!(response.knowledge_graph.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_serper_search_sends_region_as_country_and_language() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({"gl": "gb", "hl": "en"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let backend = SerperBackend::with_endpoint("key".to_string(), server.uri());
+        let uk = SearchArgs {
+            options: Some(SearchOptions {
+                region: "uk-en".to_string(),
+                ..Default::default()
+            }),
+            ..args()
+        };
+        let context = backend.search(&uk).await.unwrap().metadata.search_context;
+        assert_eq!(context.country.as_deref(), Some("GB"));
+        assert_eq!(context.language.as_deref(), Some("en"));
+
+        let body = request_body(&args(), &SearchOptions::default());
+        assert!(body.get("gl").is_none() && body.get("hl").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serper_search_reports_auth_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let backend =
+            SerperBackend::with_endpoint("bad".to_string(), format!("{}/search", server.uri()));
+        let err = backend.search(&args()).await.unwrap_err();
+        assert!(matches!(err, DaedraError::SearchError(msg) if msg.contains("403")));
+    }
 }
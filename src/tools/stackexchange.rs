@@ -2,11 +2,20 @@
 //!
 //! Searches StackOverflow and other StackExchange sites via their public API.
 //! Great for technical/programming queries.
+//!
+//! Besides serving as a fallback backend, it backs the `search_stackoverflow`
+//! tool, which adds tag, site and order filters. Results carry the question's
+//! votes, answer count, whether an answer was accepted, and its tags in
+//! [`ResultMetadata`], so agents can prefer answered questions.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::text;
 use crate::types::{
-    ContentType, DaedraResult, DaedraError, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchOptions, SearchResponse, SearchResult, StackOverflowArgs, StackOverflowSort,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -16,9 +25,49 @@ use tracing::info;
 
 const STACKEXCHANGE_API: &str = "https://api.stackexchange.com/2.3/search/advanced";
 
+/// Most results one `search_stackoverflow` call returns
+const MAX_RESULTS: usize = 50;
+
+fn query_params(args: &SearchArgs, num_results: usize) -> [(&'static str, String); 6] {
+    [
+        ("q", args.query.clone()),
+        ("order", "desc".to_string()),
+        ("sort", "relevance".to_string()),
+        ("site", "stackoverflow".to_string()),
+        ("pagesize", num_results.min(25).to_string()),
+        ("filter", "default".to_string()),
+    ]
+}
+
+fn question_params(args: &StackOverflowArgs) -> Vec<(&'static str, String)> {
+    let sort = match args.sort {
+        StackOverflowSort::Relevance => "relevance",
+        StackOverflowSort::Votes => "votes",
+        StackOverflowSort::Activity => "activity",
+        StackOverflowSort::Creation => "creation",
+    };
+    let mut params = vec![
+        ("q", args.query.trim().to_string()),
+        ("order", "desc".to_string()),
+        ("sort", sort.to_string()),
+        ("site", args.site.trim().to_lowercase()),
+        ("pagesize", args.num_results.clamp(1, MAX_RESULTS).to_string()),
+        ("filter", "default".to_string()),
+    ];
+    let tags: Vec<&str> = args.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+    if !tags.is_empty() {
+        params.push(("tagged", tags.join(";")));
+    }
+    if args.accepted_only {
+        params.push(("accepted", "True".to_string()));
+    }
+    params
+}
+
 /// StackExchange API backend — searches StackOverflow and related sites.
 pub struct StackExchangeBackend {
     client: Client,
+    endpoint: String,
 }
 
 #[derive(Deserialize)]
@@ -34,19 +83,110 @@ struct SeItem {
     score: i64,
     #[serde(default)]
     answer_count: u64,
+    #[serde(default)]
+    accepted_answer_id: Option<u64>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    creation_date: Option<i64>,
 }
 
 impl StackExchangeBackend {
     /// Create a new StackExchange backend instance.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent("daedra/1.0")
-            .timeout(Duration::from_secs(15))
-            .gzip(true)
-            .brotli(true)
-            .build()
-            .expect("HTTP client");
-        Self { client }
+        Self::with_endpoint(STACKEXCHANGE_API)
+    }
+
+    /// Create a backend for a custom `search/advanced` URL (proxies, tests).
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0"),
+            Duration::from_secs(15),
+        );
+        Self {
+            client,
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, Some("daedra/1.0"), Duration::from_secs(15));
+        self
+    }
+
+    /// Search questions on a Stack Exchange site (the `search_stackoverflow` tool).
+    pub async fn search_questions(&self, args: &StackOverflowArgs) -> DaedraResult<SearchResponse> {
+        args.validate()?;
+        let num_results = args.num_results.clamp(1, MAX_RESULTS);
+        let items = self.fetch(&question_params(args)).await?;
+        let results: Vec<SearchResult> =
+            items.into_iter().map(to_result).take(num_results).collect();
+
+        info!(site = %args.site, results = results.len(), "Stack Exchange search complete");
+        let opts = SearchOptions {
+            num_results,
+            ..Default::default()
+        };
+        let mut response = SearchResponse::new(args.query.clone(), results, &opts);
+        response.metadata.served_by = Some("stackoverflow".to_string());
+        Ok(response)
+    }
+
+    /// The request [`search_questions`](Self::search_questions) would send,
+    /// without sending it
+    pub fn plan_questions(&self, args: &StackOverflowArgs) -> PlannedRequest {
+        PlannedRequest::get(&self.endpoint, &question_params(args))
+    }
+
+    async fn fetch(&self, params: &[(&str, String)]) -> DaedraResult<Vec<SeItem>> {
+        let resp = self
+            .client
+            .get(&self.endpoint)
+            .query(params)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+        if !resp.status().is_success() {
+            return Err(DaedraError::SearchError(format!(
+                "Stack Exchange search returned {}",
+                resp.status()
+            )));
+        }
+        let data: SeResponse = resp.json_counted().await?;
+        Ok(data.items.unwrap_or_default())
+    }
+}
+
+fn to_result(item: SeItem) -> SearchResult {
+    let accepted = item.accepted_answer_id.is_some();
+    let mut description = format!("Score: {} | Answers: {}", item.score, item.answer_count);
+    if accepted {
+        description.push_str(" (accepted)");
+    }
+    if !item.tags.is_empty() {
+        description = format!("{} | Tags: {}", description, item.tags.join(", "));
+    }
+    let published_date = item
+        .creation_date
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|date| date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    SearchResult {
+        title: text::clean(&item.title),
+        url: item.link,
+        description,
+        metadata: ResultMetadata {
+            content_type: ContentType::Forum,
+            source: "stackoverflow".into(),
+            favicon: None,
+            published_date,
+            votes: Some(item.score),
+            answers: Some(item.answer_count),
+            accepted_answer: Some(accepted),
+            tags: item.tags,
+            ..Default::default()
+        },
     }
 }
 
@@ -55,51 +195,119 @@ impl SearchBackend for StackExchangeBackend {
     async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
         let opts = args.options.clone().unwrap_or_default();
 
-        let resp = self.client
-            .get(STACKEXCHANGE_API)
-            .query(&[
-                ("q", args.query.as_str()),
-                ("order", "desc"),
-                ("sort", "relevance"),
-                ("site", "stackoverflow"),
-                ("pagesize", &opts.num_results.min(25).to_string()),
-                ("filter", "default"),
-            ])
-            .send()
-            .await
-            .map_err(DaedraError::HttpError)?;
-
-        let data: SeResponse = resp.json().await.map_err(DaedraError::HttpError)?;
-
-        let results: Vec<SearchResult> = data.items.unwrap_or_default()
-            .into_iter()
-            .map(|item| {
-                let desc = format!("Score: {} | Answers: {}", item.score, item.answer_count);
-                SearchResult {
-                    title: html_escape::decode_html_entities(&item.title).to_string(),
-                    url: item.link,
-                    description: desc,
-                    metadata: ResultMetadata {
-                        content_type: ContentType::Forum,
-                        source: "stackoverflow".to_string(),
-                        favicon: None,
-                        published_date: None,
-                    },
-                }
-            })
-            .take(opts.num_results)
-            .collect();
+        let items = self.fetch(&query_params(args, opts.num_results)).await?;
+        let results: Vec<SearchResult> =
+            items.into_iter().map(to_result).take(opts.num_results).collect();
 
         info!(backend = "stackoverflow", results = results.len(), "StackExchange search complete");
         Ok(SearchResponse::new(args.query.clone(), results, &opts))
     }
 
     fn name(&self) -> &str { "stackoverflow" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Keywords }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        Some(PlannedRequest::get(&self.endpoint, &query_params(args, opts.num_results)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{method, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sample_response() -> serde_json::Value {
+        serde_json::json!({
+            "items": [
+                {
+                    "title": "How do I spawn a task with &#39;static lifetime?",
+                    "link": "https://stackoverflow.com/questions/1/spawn",
+                    "score": 42,
+                    "answer_count": 3,
+                    "accepted_answer_id": 7,
+                    "is_answered": true,
+                    "tags": ["rust", "tokio"],
+                    "creation_date": 1609459200
+                },
+                {
+                    "title": "Tokio runtime panics",
+                    "link": "https://stackoverflow.com/questions/2/panics",
+                    "score": -1,
+                    "answer_count": 0,
+                    "is_answered": false,
+                    "tags": ["rust"]
+                }
+            ],
+            "has_more": false
+        })
+    }
+
+    #[tokio::test]
+    async fn test_search_questions_reports_votes_answers_and_tags() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("q", "tokio spawn"))
+            .and(query_param("site", "stackoverflow"))
+            .and(query_param("sort", "votes"))
+            .and(query_param("tagged", "rust;tokio"))
+            .and(query_param("accepted", "True"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sample_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let backend = StackExchangeBackend::with_endpoint(server.uri());
+        let args = StackOverflowArgs {
+            tags: vec!["rust".into(), " tokio ".into()],
+            sort: StackOverflowSort::Votes,
+            accepted_only: true,
+            ..StackOverflowArgs::new("tokio spawn")
+        };
+        let response = backend.search_questions(&args).await.unwrap();
+        assert_eq!(response.metadata.served_by.as_deref(), Some("stackoverflow"));
+        assert_eq!(response.data.len(), 2);
+
+        let answered = &response.data[0];
+        assert_eq!(answered.title, "How do I spawn a task with 'static lifetime?");
+        assert_eq!(answered.metadata.votes, Some(42));
+        assert_eq!(answered.metadata.answers, Some(3));
+        assert_eq!(answered.metadata.accepted_answer, Some(true));
+        assert_eq!(answered.metadata.tags, ["rust", "tokio"]);
+        assert_eq!(answered.metadata.published_date.as_deref(), Some("2021-01-01T00:00:00Z"));
+        assert_eq!(answered.description, "Score: 42 | Answers: 3 (accepted) | Tags: rust, tokio");
+
+        let open = &response.data[1];
+        assert_eq!(open.metadata.votes, Some(-1));
+        assert_eq!(open.metadata.accepted_answer, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_search_questions_validates_and_reports_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        let backend = StackExchangeBackend::with_endpoint(server.uri());
+        assert!(matches!(
+            backend.search_questions(&StackOverflowArgs::new(" ")).await,
+            Err(DaedraError::InvalidArguments(_))
+        ));
+        let bad_site = StackOverflowArgs {
+            site: "stackoverflow&key=x".into(),
+            ..StackOverflowArgs::new("rust")
+        };
+        assert!(matches!(
+            backend.search_questions(&bad_site).await,
+            Err(DaedraError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            backend.search_questions(&StackOverflowArgs::new("rust")).await,
+            Err(DaedraError::SearchError(_))
+        ));
+    }
 
     #[tokio::test]
     #[ignore = "network: live StackExchange API call"]
@@ -4,9 +4,11 @@
 //! Free tier: 1000 queries/month.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    DaedraError, DaedraResult, SearchArgs, SearchResponse, SearchResult, ResultMetadata,
-    ContentType,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, REDACTED, ResultMetadata, SearchArgs,
+    SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -16,6 +18,15 @@ use tracing::info;
 
 const TAVILY_URL: &str = "https://api.tavily.com/search";
 
+fn request_body(api_key: &str, args: &SearchArgs, num_results: usize) -> serde_json::Value {
+    serde_json::json!({
+        "api_key": api_key,
+        "query": args.query,
+        "max_results": num_results,
+        "search_depth": "basic",
+    })
+}
+
 /// Tavily Search API backend — AI-optimized search results, requires an API key.
 pub struct TavilyBackend {
     client: Client,
@@ -37,12 +48,19 @@ struct TavilyResult {
 impl TavilyBackend {
     /// Create a new Tavily backend instance.
     pub fn new(api_key: String) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("HTTP client");
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            None,
+            Duration::from_secs(30),
+        );
         Self { client, api_key }
     }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, None, Duration::from_secs(30));
+        self
+    }
 }
 
 #[async_trait]
@@ -50,22 +68,17 @@ impl SearchBackend for TavilyBackend {
     async fn search(&self, args: &SearchArgs) -> DaedraResult<SearchResponse> {
         let opts = args.options.clone().unwrap_or_default();
 
-        let body = serde_json::json!({
-            "api_key": self.api_key,
-            "query": args.query,
-            "max_results": opts.num_results,
-            "search_depth": "basic",
-        });
+        let body = request_body(&self.api_key, args, opts.num_results);
 
         let resp = self.client
             .post(TAVILY_URL)
             .header("Content-Type", "application/json")
             .json(&body)
-            .send()
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
-        let data: TavilyResponse = resp.json().await.map_err(DaedraError::HttpError)?;
+        let data: TavilyResponse = resp.json_counted().await?;
 
         let results: Vec<SearchResult> = data.results.unwrap_or_default()
             .into_iter()
@@ -75,9 +88,10 @@ impl SearchBackend for TavilyBackend {
                 description: r.content.unwrap_or_default(),
                 metadata: ResultMetadata {
                     content_type: ContentType::Other,
-                    source: "tavily".to_string(),
+                    source: "tavily".into(),
                     favicon: None,
                     published_date: None,
+                    ..Default::default()
                 },
             })
             .take(opts.num_results)
@@ -89,4 +103,10 @@ impl SearchBackend for TavilyBackend {
 
     fn name(&self) -> &str { "tavily" }
     fn requires_api_key(&self) -> bool { true }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let body = request_body(REDACTED, args, opts.num_results);
+        Some(PlannedRequest::post_json(TAVILY_URL, body))
+    }
 }
@@ -4,9 +4,12 @@
 //! hobbyist pages. Complements mainstream engines with human-curated indie web.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    ContentType, DaedraResult, DaedraError, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -16,6 +19,10 @@ use tracing::info;
 
 const WIBY_API: &str = "https://wiby.me/json/";
 
+fn query_params(query: &str) -> [(&'static str, &str); 1] {
+    [("q", query)]
+}
+
 /// Wiby.me search backend — lightweight search engine for the small web.
 pub struct WibyBackend {
     client: Client,
@@ -34,13 +41,19 @@ struct WibyResult {
 impl WibyBackend {
     /// Create a new Wiby backend instance.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent("daedra/1.0")
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("HTTP client");
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0"),
+            Duration::from_secs(10),
+        );
         Self { client }
     }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, Some("daedra/1.0"), Duration::from_secs(10));
+        self
+    }
 }
 
 #[async_trait]
@@ -50,12 +63,12 @@ impl SearchBackend for WibyBackend {
 
         let resp = self.client
             .get(WIBY_API)
-            .query(&[("q", args.query.as_str())])
-            .send()
+            .query(&query_params(&args.query))
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
-        let data: Vec<WibyResult> = resp.json().await.map_err(DaedraError::HttpError)?;
+        let data: Vec<WibyResult> = resp.json_counted().await?;
 
         let results: Vec<SearchResult> = data.into_iter()
             .take(opts.num_results)
@@ -65,9 +78,10 @@ impl SearchBackend for WibyBackend {
                 description: r.snippet,
                 metadata: ResultMetadata {
                     content_type: ContentType::Article,
-                    source: "wiby".to_string(),
+                    source: "wiby".into(),
                     favicon: None,
                     published_date: None,
+                    ..Default::default()
                 },
             })
             .collect();
@@ -77,4 +91,9 @@ impl SearchBackend for WibyBackend {
     }
 
     fn name(&self) -> &str { "wiby" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Keywords }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        Some(PlannedRequest::get(WIBY_API, &query_params(&args.query)))
+    }
 }
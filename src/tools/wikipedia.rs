@@ -1,19 +1,37 @@
 //! Wikipedia/Wikidata search backend — free, no API key, works from any IP.
 //!
-//! Uses the MediaWiki opensearch API for instant results.
+//! Uses the MediaWiki opensearch API for instant results, on the Wikipedia in
+//! the search region's language.
 //! Limited to Wikipedia content — not a general web search.
 
 use super::backend::SearchBackend;
+use crate::privacy::{self, ClientProfile};
+use crate::query::QueryDialect;
+use crate::regions;
+use crate::telemetry::{CountedRequest, CountedResponse};
 use crate::types::{
-    ContentType, DaedraResult, DaedraError, ResultMetadata, SearchArgs, SearchResponse,
-    SearchResult,
+    ContentType, DaedraError, DaedraResult, PlannedRequest, ResultMetadata, SearchArgs,
+    SearchResponse, SearchResult,
 };
 use async_trait::async_trait;
 use reqwest::Client;
 use std::time::Duration;
 use tracing::info;
 
-const WIKIPEDIA_API: &str = "https://en.wikipedia.org/w/api.php";
+/// API of the Wikipedia in the region's language; English for `wt-wt`.
+fn api_url(region: &str) -> String {
+    let language = regions::locale(region).map_or_else(|| "en".to_string(), |l| l.language);
+    format!("https://{}.wikipedia.org/w/api.php", language)
+}
+
+fn query_params(args: &SearchArgs, num_results: usize) -> [(&'static str, String); 4] {
+    [
+        ("action", "opensearch".to_string()),
+        ("search", args.query.clone()),
+        ("limit", num_results.min(20).to_string()),
+        ("format", "json".to_string()),
+    ]
+}
 
 /// Wikipedia API backend — searches Wikipedia articles via the MediaWiki API.
 pub struct WikipediaBackend {
@@ -23,13 +41,23 @@ pub struct WikipediaBackend {
 impl WikipediaBackend {
     /// Create a new Wikipedia backend instance.
     pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent("daedra/1.0 (search MCP server)")
-            .timeout(Duration::from_secs(15))
-            .build()
-            .expect("HTTP client");
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            Some("daedra/1.0 (search MCP server)"),
+            Duration::from_secs(15),
+        );
         Self { client }
     }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(
+            profile,
+            Some("daedra/1.0 (search MCP server)"),
+            Duration::from_secs(15),
+        );
+        self
+    }
 }
 
 #[async_trait]
@@ -38,19 +66,14 @@ impl SearchBackend for WikipediaBackend {
         let opts = args.options.clone().unwrap_or_default();
 
         let resp = self.client
-            .get(WIKIPEDIA_API)
-            .query(&[
-                ("action", "opensearch"),
-                ("search", &args.query),
-                ("limit", &opts.num_results.min(20).to_string()),
-                ("format", "json"),
-            ])
-            .send()
+            .get(api_url(&opts.region))
+            .query(&query_params(args, opts.num_results))
+            .send_counted()
             .await
             .map_err(DaedraError::HttpError)?;
 
         // OpenSearch returns: [query, [titles], [descriptions], [urls]]
-        let data: serde_json::Value = resp.json().await.map_err(DaedraError::HttpError)?;
+        let data: serde_json::Value = resp.json_counted().await?;
 
         let titles = data.get(1).and_then(|v| v.as_array());
         let descriptions = data.get(2).and_then(|v| v.as_array());
@@ -71,9 +94,10 @@ impl SearchBackend for WikipediaBackend {
                         description: desc.to_string(),
                         metadata: ResultMetadata {
                             content_type: ContentType::Documentation,
-                            source: "wikipedia".to_string(),
+                            source: "wikipedia".into(),
                             favicon: None,
                             published_date: None,
+                            ..Default::default()
                         },
                     });
                 }
@@ -85,12 +109,26 @@ impl SearchBackend for WikipediaBackend {
     }
 
     fn name(&self) -> &str { "wikipedia" }
+    fn query_dialect(&self) -> QueryDialect { QueryDialect::Keywords }
+
+    fn plan_request(&self, args: &SearchArgs) -> Option<PlannedRequest> {
+        let opts = args.options.clone().unwrap_or_default();
+        let url = api_url(&opts.region);
+        Some(PlannedRequest::get(&url, &query_params(args, opts.num_results)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_api_url_follows_region_language() {
+        assert_eq!(api_url("wt-wt"), "https://en.wikipedia.org/w/api.php");
+        assert_eq!(api_url("ch-fr"), "https://fr.wikipedia.org/w/api.php");
+        assert_eq!(api_url("jp-jp"), "https://ja.wikipedia.org/w/api.php");
+    }
+
     #[tokio::test]
     async fn test_wikipedia_search_live() {
         let backend = WikipediaBackend::new();
@@ -0,0 +1,477 @@
+//! YouTube captions for the `get_transcript` tool.
+//!
+//! No API key: the watch page embeds the player response, which lists the
+//! video's caption tracks. The chosen track is downloaded in YouTube's
+//! `json3` timed-text format, whose events carry each caption's start and
+//! duration in milliseconds. Manual captions are preferred over automatic
+//! (speech recognition) ones, and the requested language over any other.
+
+use crate::privacy::{self, ClientProfile};
+use crate::telemetry::{CountedRequest, CountedResponse};
+use crate::text;
+use crate::types::{
+    DaedraError, DaedraResult, PlannedRequest, Transcript, TranscriptArgs, TranscriptCue,
+};
+use reqwest::Client;
+use reqwest::header::COOKIE;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+/// Root of YouTube's web site
+pub const YOUTUBE_URL: &str = "https://www.youtube.com";
+
+/// Caption language used when the arguments don't name one
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// Declines the cookie consent page served in place of the watch page in
+/// some regions
+const CONSENT_COOKIE: &str = "SOCS=CAI";
+
+/// Client for YouTube watch pages and caption tracks.
+pub struct YouTubeClient {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerResponse {
+    playability_status: Option<PlayabilityStatus>,
+    video_details: Option<VideoDetails>,
+    captions: Option<Captions>,
+}
+
+#[derive(Deserialize)]
+struct PlayabilityStatus {
+    status: Option<String>,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VideoDetails {
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Captions {
+    player_captions_tracklist_renderer: Option<TrackList>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackList {
+    #[serde(default)]
+    caption_tracks: Vec<CaptionTrack>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptionTrack {
+    base_url: String,
+    language_code: String,
+    /// `asr` for automatic captions
+    kind: Option<String>,
+}
+
+impl CaptionTrack {
+    fn is_auto(&self) -> bool {
+        self.kind.as_deref() == Some("asr")
+    }
+}
+
+#[derive(Deserialize)]
+struct TimedText {
+    #[serde(default)]
+    events: Vec<TimedTextEvent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimedTextEvent {
+    #[serde(default)]
+    t_start_ms: u64,
+    #[serde(default)]
+    d_duration_ms: u64,
+    #[serde(default)]
+    segs: Vec<TimedTextSegment>,
+}
+
+#[derive(Deserialize)]
+struct TimedTextSegment {
+    #[serde(default)]
+    utf8: String,
+}
+
+impl YouTubeClient {
+    /// Create a client for youtube.com.
+    pub fn new() -> Self {
+        Self::with_endpoint(YOUTUBE_URL)
+    }
+
+    /// Create a client for a custom site root (proxies, tests).
+    pub fn with_endpoint(endpoint: impl Into<String>) -> Self {
+        let client = privacy::backend_client(
+            ClientProfile::Standard,
+            None,
+            Duration::from_secs(20),
+        );
+        Self {
+            client,
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send requests with the headers of `profile`
+    pub fn with_client_profile(mut self, profile: ClientProfile) -> Self {
+        self.client = privacy::backend_client(profile, None, Duration::from_secs(20));
+        self
+    }
+
+    /// Fetch the captions of the video `args` names.
+    pub async fn transcript(&self, args: &TranscriptArgs) -> DaedraResult<Transcript> {
+        let video_id = video_id(&args.url)?;
+        let (url, params) = self.request(&video_id);
+        let resp = self
+            .client
+            .get(&url)
+            .query(&params)
+            .header(COOKIE, CONSENT_COOKIE)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+        if !resp.status().is_success() {
+            return Err(DaedraError::FetchError(format!(
+                "YouTube watch page returned {}",
+                resp.status()
+            )));
+        }
+        let html = resp.text_counted().await.map_err(DaedraError::HttpError)?;
+        let player = player_response(&html).ok_or_else(|| {
+            DaedraError::FetchError(format!("No player response on the page of video {}", video_id))
+        })?;
+
+        let tracks = player
+            .captions
+            .and_then(|c| c.player_captions_tracklist_renderer)
+            .map(|list| list.caption_tracks)
+            .unwrap_or_default();
+        let language = args.language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+        let Some(track) = choose_track(&tracks, language) else {
+            let status = player.playability_status.unwrap_or(PlayabilityStatus {
+                status: None,
+                reason: None,
+            });
+            return Err(DaedraError::FetchError(match (status.status, status.reason) {
+                (Some(s), Some(reason)) if s != "OK" => {
+                    format!("Video {} is not playable: {}", video_id, reason)
+                },
+                _ => format!("Video {} has no captions", video_id),
+            }));
+        };
+
+        let track_url = if track.base_url.starts_with('/') {
+            format!("{}{}", self.endpoint, track.base_url)
+        } else {
+            track.base_url.clone()
+        };
+        let resp = self
+            .client
+            .get(&track_url)
+            .query(&[("fmt", "json3")])
+            .header(COOKIE, CONSENT_COOKIE)
+            .send_counted()
+            .await
+            .map_err(DaedraError::HttpError)?;
+        if !resp.status().is_success() {
+            return Err(DaedraError::FetchError(format!(
+                "YouTube caption track returned {}",
+                resp.status()
+            )));
+        }
+        let body = resp.text_counted().await.map_err(DaedraError::HttpError)?;
+        if body.trim().is_empty() {
+            return Err(DaedraError::FetchError(format!(
+                "YouTube returned an empty caption track for video {}",
+                video_id
+            )));
+        }
+        let timed: TimedText = serde_json::from_str(&body)?;
+        let cues = to_cues(timed);
+
+        info!(video_id, language = track.language_code, cues = cues.len(), "Transcript fetched");
+        Ok(Transcript {
+            url: watch_url(&video_id),
+            video_id,
+            title: player.video_details.and_then(|d| d.title).filter(|t| !t.trim().is_empty()),
+            language: track.language_code.clone(),
+            auto_generated: track.is_auto(),
+            cues,
+        })
+    }
+
+    /// The request [`transcript`](Self::transcript) would send first, without
+    /// sending it
+    pub fn plan(&self, args: &TranscriptArgs) -> DaedraResult<PlannedRequest> {
+        let (url, params) = self.request(&video_id(&args.url)?);
+        Ok(PlannedRequest::get(&url, &params)
+            .with_note("the caption track the page lists is downloaded next"))
+    }
+
+    fn request(&self, video_id: &str) -> (String, Vec<(&'static str, String)>) {
+        let params = vec![("v", video_id.to_string()), ("hl", "en".to_string())];
+        (format!("{}/watch", self.endpoint), params)
+    }
+}
+
+impl Default for YouTubeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The ID of the YouTube video `url` names: a watch, `youtu.be`, shorts,
+/// embed or live URL, or the bare 11-character ID.
+pub fn video_id(url: &str) -> DaedraResult<String> {
+    let url = url.trim();
+    let invalid = || DaedraError::InvalidArguments(format!("Not a YouTube video URL: {}", url));
+    if is_video_id(url) {
+        return Ok(url.to_string());
+    }
+
+    let with_scheme = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+    let parsed = url::Url::parse(&with_scheme).map_err(|_| invalid())?;
+    let host = parsed.host_str().ok_or_else(invalid)?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let mut segments = parsed.path_segments().into_iter().flatten().filter(|s| !s.is_empty());
+
+    let id = match host {
+        "youtu.be" => segments.next().map(str::to_string),
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" | "youtube-nocookie.com" => {
+            match segments.next() {
+                Some("watch") => {
+                    parsed.query_pairs().find(|(k, _)| k == "v").map(|(_, v)| v.into_owned())
+                },
+                Some("shorts" | "embed" | "live" | "v") => segments.next().map(str::to_string),
+                _ => None,
+            }
+        },
+        _ => None,
+    };
+    id.filter(|id| is_video_id(id)).ok_or_else(invalid)
+}
+
+fn is_video_id(s: &str) -> bool {
+    s.len() == 11 && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn watch_url(video_id: &str) -> String {
+    format!("{}/watch?v={}", YOUTUBE_URL, video_id)
+}
+
+/// The player response the watch page assigns to `ytInitialPlayerResponse`
+fn player_response(html: &str) -> Option<PlayerResponse> {
+    let at = html.find("ytInitialPlayerResponse")?;
+    let start = at + html[at..].find('{')?;
+    serde_json::Deserializer::from_str(&html[start..])
+        .into_iter::<PlayerResponse>()
+        .next()?
+        .ok()
+}
+
+/// The best track for `language`: manual captions in it, then automatic ones
+/// in it, then manual and automatic captions in any language. A track in
+/// another variant of the language (`en-GB` for `en`) counts as in it, after
+/// an exact match.
+fn choose_track<'a>(tracks: &'a [CaptionTrack], language: &str) -> Option<&'a CaptionTrack> {
+    let language = language.to_lowercase();
+    let primary = |code: &str| code.split('-').next().unwrap_or_default().to_string();
+    let rank = |track: &CaptionTrack| {
+        let code = track.language_code.to_lowercase();
+        let language_rank = if code == language {
+            0
+        } else if primary(&code) == primary(&language) {
+            1
+        } else {
+            2
+        };
+        (language_rank == 2, track.is_auto(), language_rank)
+    };
+    tracks.iter().min_by_key(|track| rank(track))
+}
+
+/// The captions in `timed`, without the empty events that only position text
+fn to_cues(timed: TimedText) -> Vec<TranscriptCue> {
+    timed
+        .events
+        .into_iter()
+        .filter_map(|event| {
+            let joined: String = event.segs.iter().map(|s| s.utf8.as_str()).collect();
+            let text = text::clean(&joined);
+            (!text.is_empty()).then_some(TranscriptCue {
+                start_ms: event.t_start_ms,
+                duration_ms: event.d_duration_ms,
+                text,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn watch_page(server: &MockServer) -> String {
+        let english = format!("{}/api/timedtext?v=dQw4w9WgXcQ&lang=en", server.uri());
+        let player = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "videoId": "dQw4w9WgXcQ", "title": "Rust in 100 seconds" },
+            "captions": {
+                "playerCaptionsTracklistRenderer": {
+                    "captionTracks": [
+                        {
+                            "baseUrl": english,
+                            "name": { "simpleText": "English (auto-generated)" },
+                            "languageCode": "en",
+                            "kind": "asr"
+                        },
+                        {
+                            "baseUrl": "/api/timedtext?v=dQw4w9WgXcQ&lang=de",
+                            "name": { "simpleText": "German" },
+                            "languageCode": "de"
+                        }
+                    ]
+                }
+            }
+        });
+        format!(
+            "<html><script>var ytInitialPlayerResponse = {};var meta = {{}};</script></html>",
+            player
+        )
+    }
+
+    fn timed_text() -> serde_json::Value {
+        serde_json::json!({
+            "events": [
+                { "tStartMs": 0, "dDurationMs": 5000, "id": 1 },
+                {
+                    "tStartMs": 400,
+                    "dDurationMs": 2600,
+                    "segs": [{ "utf8": "Rust is" }, { "utf8": " fast" }]
+                },
+                { "tStartMs": 3000, "dDurationMs": 20, "segs": [{ "utf8": "\n" }] },
+                { "tStartMs": 31000, "dDurationMs": 4000, "segs": [{ "utf8": "and &amp; safe" }] }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_video_id_from_urls() {
+        for url in [
+            "dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+            "youtube.com/watch?feature=share&v=dQw4w9WgXcQ",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?si=abc",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(video_id(url).unwrap(), "dQw4w9WgXcQ", "{}", url);
+        }
+        for url in ["", "https://vimeo.com/123", "https://www.youtube.com/@channel", "short"] {
+            assert!(matches!(video_id(url), Err(DaedraError::InvalidArguments(_))), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_manual_captions_in_the_language_come_first() {
+        let track = |code: &str, auto: bool| CaptionTrack {
+            base_url: String::new(),
+            language_code: code.to_string(),
+            kind: auto.then(|| "asr".to_string()),
+        };
+        let tracks = [track("en", true), track("de", false), track("en-GB", false)];
+        assert_eq!(choose_track(&tracks, "en").unwrap().language_code, "en-GB");
+        assert_eq!(choose_track(&tracks, "de").unwrap().language_code, "de");
+        assert_eq!(choose_track(&tracks, "fr").unwrap().language_code, "de");
+        assert!(choose_track(&tracks[..1], "fr").unwrap().is_auto());
+        assert!(choose_track(&[], "en").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transcript_reads_the_chosen_track() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/watch"))
+            .and(query_param("v", "dQw4w9WgXcQ"))
+            .and(header("cookie", CONSENT_COOKIE))
+            .respond_with(ResponseTemplate::new(200).set_body_string(watch_page(&server)))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(path("/api/timedtext"))
+            .and(query_param("lang", "en"))
+            .and(query_param("fmt", "json3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(timed_text()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/api/timedtext"))
+            .and(query_param("lang", "de"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(""))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = YouTubeClient::with_endpoint(server.uri());
+        let args = TranscriptArgs::new("https://youtu.be/dQw4w9WgXcQ");
+        let transcript = client.transcript(&args).await.unwrap();
+        assert_eq!(transcript.title.as_deref(), Some("Rust in 100 seconds"));
+        assert_eq!(transcript.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(transcript.language, "en");
+        assert!(transcript.auto_generated);
+        assert_eq!(transcript.cues.len(), 2);
+        assert_eq!(transcript.cues[0].text, "Rust is fast");
+        assert_eq!(transcript.cues[1].start_ms, 31000);
+        assert_eq!(transcript.cues[1].text, "and & safe");
+
+        let german = TranscriptArgs {
+            language: Some("de".to_string()),
+            ..args
+        };
+        assert!(matches!(
+            client.transcript(&german).await,
+            Err(DaedraError::FetchError(msg)) if msg.contains("empty caption track")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_transcript_reports_videos_without_captions() {
+        let server = MockServer::start().await;
+        let page = r#"<script>var ytInitialPlayerResponse = {"playabilityStatus":
+            {"status": "ERROR", "reason": "Video unavailable"}};</script>"#;
+        Mock::given(path("/watch"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page))
+            .mount(&server)
+            .await;
+        let client = YouTubeClient::with_endpoint(server.uri());
+        let result = client.transcript(&TranscriptArgs::new("dQw4w9WgXcQ")).await;
+        assert!(matches!(
+            result,
+            Err(DaedraError::FetchError(msg)) if msg.contains("Video unavailable")
+        ));
+
+        let plan = client.plan(&TranscriptArgs::new("dQw4w9WgXcQ")).unwrap();
+        assert_eq!(plan.url, format!("{}/watch", server.uri()));
+        assert_eq!(plan.params["v"], "dQw4w9WgXcQ");
+        assert!(client.plan(&TranscriptArgs::new("not a video")).is_err());
+    }
+}
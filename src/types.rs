@@ -5,12 +5,23 @@
 //! - Error types
 //! - Configuration structures
 
+use crate::intern::Interned;
+use crate::timestamp::Timestamp;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 /// Result type alias for Daedra operations
 pub type DaedraResult<T> = Result<T, DaedraError>;
 
+/// When to try again after a rate limit, for its error message
+fn retry_hint(retry_after: &Option<std::time::Duration>) -> String {
+    match retry_after {
+        Some(wait) => format!("in {}s", wait.as_secs().max(1)),
+        None => "later".to_string(),
+    }
+}
+
 /// Errors that can occur during Daedra operations
 #[derive(Error, Debug)]
 pub enum DaedraError {
@@ -55,20 +66,59 @@ pub enum DaedraError {
     UnsupportedContentType(String),
 
     /// Rate limit exceeded
-    #[error("Rate limit exceeded, please try again later")]
-    RateLimitExceeded,
+    #[error("Rate limit exceeded, please try again {}", retry_hint(.retry_after))]
+    RateLimitExceeded {
+        /// How long the server asked to wait, when it said
+        retry_after: Option<std::time::Duration>,
+    },
 
     /// Bot protection detected
     #[error("Bot protection detected on target page")]
     BotProtectionDetected,
 
+    /// The site's robots.txt disallows fetching the page
+    #[error("{0} is disallowed by the site's robots.txt")]
+    DisallowedByRobots(String),
+
+    /// The server's domain policy doesn't let tools read this host
+    #[error("{0} is not an allowed domain on this server")]
+    DomainNotAllowed(String),
+
+    /// A search engine answered with a CAPTCHA/anomaly challenge instead of results
+    #[error("{0} served a CAPTCHA challenge instead of results")]
+    CaptchaChallenged(String),
+
     /// Timeout occurred
     #[error("Operation timed out")]
     Timeout,
+
+    /// The same fetch failed moments ago, and the failure is still cached
+    #[error(
+        "{reason} (cached from an earlier attempt, try again {})",
+        retry_hint(&Some(*.retry_in))
+    )]
+    RecentlyFailed {
+        /// The earlier attempt's error
+        reason: String,
+        /// How long until the failure is forgotten
+        retry_in: std::time::Duration,
+    },
+}
+
+impl DaedraError {
+    /// How long a rate-limiting server asked to wait before trying again, or
+    /// until a cached failure is forgotten
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            DaedraError::RateLimitExceeded { retry_after } => *retry_after,
+            DaedraError::RecentlyFailed { retry_in, .. } => Some(*retry_in),
+            _ => None,
+        }
+    }
 }
 
-/// Safe search filtering levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+/// Safe search filtering levels, ordered from least to most strict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SafeSearchLevel {
     /// No filtering
@@ -117,10 +167,127 @@ impl std::str::FromStr for SafeSearchLevel {
     }
 }
 
+/// How a search combines several backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchStrategy {
+    /// Query backends one at a time; the first non-empty response wins
+    Fallback,
+    /// Query backends concurrently, then deduplicate and interleave by rank
+    Merge,
+}
+
+impl std::fmt::Display for SearchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchStrategy::Fallback => write!(f, "fallback"),
+            SearchStrategy::Merge => write!(f, "merge"),
+        }
+    }
+}
+
+impl std::str::FromStr for SearchStrategy {
+    type Err = DaedraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fallback" => Ok(SearchStrategy::Fallback),
+            "merge" => Ok(SearchStrategy::Merge),
+            _ => Err(DaedraError::InvalidArguments(format!(
+                "Invalid search strategy: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Time range filter for search results
+///
+/// Written as DuckDuckGo's `df` shorthand (`d`, `w`, `m`, `y`; `day`, `week`,
+/// `month` and `year` are accepted too) or as an inclusive date range,
+/// `YYYY-MM-DD..YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeRange {
+    /// Past day
+    Day,
+    /// Past week
+    Week,
+    /// Past month
+    Month,
+    /// Past year
+    Year,
+    /// Between two dates, both inclusive
+    Custom {
+        /// First day of the range
+        from: chrono::NaiveDate,
+        /// Last day of the range
+        to: chrono::NaiveDate,
+    },
+}
+
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeRange::Day => write!(f, "d"),
+            TimeRange::Week => write!(f, "w"),
+            TimeRange::Month => write!(f, "m"),
+            TimeRange::Year => write!(f, "y"),
+            TimeRange::Custom { from, to } => write!(f, "{}..{}", from, to),
+        }
+    }
+}
+
+impl std::str::FromStr for TimeRange {
+    type Err = DaedraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            DaedraError::InvalidArguments(format!(
+                "Invalid time range: {} (expected d, w, m, y or YYYY-MM-DD..YYYY-MM-DD)",
+                s
+            ))
+        };
+        match s.trim().to_lowercase().as_str() {
+            "d" | "day" => Ok(TimeRange::Day),
+            "w" | "week" => Ok(TimeRange::Week),
+            "m" | "month" => Ok(TimeRange::Month),
+            "y" | "year" => Ok(TimeRange::Year),
+            range => {
+                let (from, to) = range.split_once("..").ok_or_else(invalid)?;
+                let date = |d: &str| chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d");
+                let from = date(from).map_err(|_| invalid())?;
+                let to = date(to).map_err(|_| invalid())?;
+                if from > to {
+                    return Err(DaedraError::InvalidArguments(format!(
+                        "Invalid time range: {} starts after it ends",
+                        s
+                    )));
+                }
+                Ok(TimeRange::Custom { from, to })
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for TimeRange {
+    type Error = DaedraError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeRange> for String {
+    fn from(range: TimeRange) -> Self {
+        range.to_string()
+    }
+}
+
 /// Options for search operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchOptions {
-    /// Region for search results (e.g., "us-en", "zh-cn")
+    /// Region for search results (e.g., "us-en", "cn-zh"); see [`crate::regions`]
     #[serde(default = "default_region")]
     pub region: String,
 
@@ -132,9 +299,61 @@ pub struct SearchOptions {
     #[serde(default = "default_num_results")]
     pub num_results: usize,
 
-    /// Time range filter (e.g., "d" for day, "w" for week, "m" for month)
+    /// Time range filter
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub time_range: Option<String>,
+    pub time_range: Option<TimeRange>,
+
+    /// How to combine backends; `None` uses the server default (merge, or
+    /// fallback when a search chain is configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<SearchStrategy>,
+
+    /// Replace the snippets of the top results with the lead paragraphs of
+    /// their pages, fetched as part of the search
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expand_snippets: bool,
+
+    /// HEAD-check the top results and record the outcome in their
+    /// [`ResultMetadata::probe`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub probe_fetchability: bool,
+
+    /// Fetch this many top results and fill in thin snippets and missing
+    /// published dates from their pages (0 = off)
+    #[serde(default)]
+    pub enrich_top: usize,
+
+    /// Keep sponsored results (search ads) in the response, marked with
+    /// [`ResultMetadata::sponsored`], instead of leaving them out
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_sponsored: bool,
+
+    /// Content types to move ahead of the rest, most wanted first (see
+    /// [`crate::tools::rerank::apply_type_preferences`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefer_types: Vec<ContentType>,
+
+    /// Content types to leave out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_types: Vec<ContentType>,
+
+    /// Keep at most this many results from one site, counting subdomains of
+    /// a registrable domain together (see [`crate::domains`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_domain: Option<usize>,
+
+    /// Re-rank results by query relevance; `None` re-ranks merged results
+    /// but leaves a single backend's order alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank: Option<bool>,
+
+    /// Approximate token budget for the response; see [`crate::budget`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens_hint: Option<usize>,
+
+    /// `next_cursor` of a truncated response, to continue after its last result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 impl Default for SearchOptions {
@@ -144,7 +363,38 @@ impl Default for SearchOptions {
             safe_search: SafeSearchLevel::Moderate,
             num_results: 10,
             time_range: None,
+            strategy: None,
+            expand_snippets: false,
+            probe_fetchability: false,
+            enrich_top: 0,
+            include_sponsored: false,
+            prefer_types: Vec::new(),
+            exclude_types: Vec::new(),
+            max_per_domain: None,
+            rerank: None,
+            max_tokens_hint: None,
+            cursor: None,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Reject options the backends would silently ignore, such as an unknown
+    /// region, or that contradict each other
+    pub fn validate(&self) -> DaedraResult<()> {
+        crate::regions::validate_region(&self.region)?;
+        if let Some(both) = self.prefer_types.iter().find(|t| self.exclude_types.contains(t)) {
+            return Err(DaedraError::InvalidArguments(format!(
+                "Content type '{}' is both preferred and excluded",
+                both
+            )));
         }
+        if self.max_per_domain == Some(0) {
+            return Err(DaedraError::InvalidArguments(
+                "max_per_domain must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -159,7 +409,9 @@ fn default_num_results() -> usize {
 /// Arguments for the search tool
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchArgs {
-    /// The search query string
+    /// The search query string; deserializes from a
+    /// [`QueryBuilder`](crate::query::QueryBuilder) object too
+    #[serde(deserialize_with = "crate::query::deserialize_query")]
     pub query: String,
 
     /// Optional search configuration
@@ -167,8 +419,93 @@ pub struct SearchArgs {
     pub options: Option<SearchOptions>,
 }
 
-/// Arguments for the visit_page tool
+/// Most queries one `multi_search` call runs
+pub const MAX_MULTI_SEARCH_QUERIES: usize = 10;
+
+/// Arguments for the `multi_search` tool: several queries sharing one set
+/// of options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiSearchArgs {
+    /// The queries, answered in this order
+    pub queries: Vec<String>,
+
+    /// Search configuration for every query, as for `web_search`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchOptions>,
+}
+
+impl MultiSearchArgs {
+    /// Arguments for `queries` with default options
+    pub fn new<S: Into<String>>(queries: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            queries: queries.into_iter().map(Into::into).collect(),
+            options: None,
+        }
+    }
+
+    /// Reject an empty list, too many queries, a blank query or invalid options
+    pub fn validate(&self) -> DaedraResult<()> {
+        if self.queries.is_empty() {
+            return Err(DaedraError::InvalidArguments("queries must not be empty".to_string()));
+        }
+        if self.queries.len() > MAX_MULTI_SEARCH_QUERIES {
+            return Err(DaedraError::InvalidArguments(format!(
+                "At most {} queries per call, got {}",
+                MAX_MULTI_SEARCH_QUERIES,
+                self.queries.len()
+            )));
+        }
+        if let Some(i) = self.queries.iter().position(|q| q.trim().is_empty()) {
+            return Err(DaedraError::InvalidArguments(format!("Query {} is empty", i + 1)));
+        }
+        self.options.as_ref().map_or(Ok(()), SearchOptions::validate)
+    }
+
+    /// One [`SearchArgs`] per query
+    pub fn searches(&self) -> Vec<SearchArgs> {
+        self.queries
+            .iter()
+            .map(|query| SearchArgs {
+                query: query.clone(),
+                options: self.options.clone(),
+            })
+            .collect()
+    }
+}
+
+/// One query's outcome in a `multi_search` call: its response, or the error
+/// that query alone ran into
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiSearchItem {
+    /// The query, as given
+    pub query: String,
+
+    /// The search response, when the search succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<SearchResponse>,
+
+    /// Why the search failed, when it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl MultiSearchItem {
+    /// The outcome of searching for `query`
+    pub fn new(query: impl Into<String>, outcome: DaedraResult<SearchResponse>) -> Self {
+        let (response, error) = match outcome {
+            Ok(response) => (Some(response), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            query: query.into(),
+            response,
+            error,
+        }
+    }
+}
+
+/// Arguments for the visit_page tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VisitPageArgs {
     /// URL of the page to visit
     pub url: String,
@@ -177,9 +514,98 @@ pub struct VisitPageArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub selector: Option<String>,
 
-    /// Whether to include images in the response
+    /// Keep the content's images as `![alt](url)` Markdown and list them in
+    /// [`PageContent::images`]; by default they are left out
     #[serde(default)]
     pub include_images: bool,
+
+    /// What to return the content as; Markdown by default
+    #[serde(default)]
+    pub format: PageFormat,
+
+    /// What to extract: the page's content (the default) or all its links
+    #[serde(default)]
+    pub extract: ExtractMode,
+
+    /// Follow a paginated article's next-page links and join up to this many
+    /// of its pages, this one included, into one (at most
+    /// [`MAX_PAGINATION_PAGES`](crate::tools::fetch::MAX_PAGINATION_PAGES));
+    /// content extraction only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_pagination: Option<usize>,
+
+    /// When the page is gone (HTTP 404 or 410, or its host doesn't resolve),
+    /// read its latest Wayback Machine snapshot instead
+    #[serde(default)]
+    pub allow_archive: bool,
+
+    /// Approximate token budget for the page; see [`crate::budget`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens_hint: Option<usize>,
+
+    /// `next_cursor` of a truncated page, to continue where it stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+
+    /// Return at most this many characters of the content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+
+    /// Character offset to start the content at, e.g. the `next_index` of a
+    /// page cut by `max_length`; can't be combined with `cursor`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<usize>,
+
+    /// Refuse a page whose body is larger than this many bytes; at most the
+    /// server's own cap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+
+    /// Search region whose language to ask the site for (`Accept-Language`).
+    /// Pages are cached by URL, so a cached page comes back in whatever
+    /// language it was first fetched in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// What `visit_page` returns a page's content as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageFormat {
+    /// Markdown converted from the extracted HTML
+    #[default]
+    Markdown,
+    /// Plain text: one paragraph per block, table cells separated by tabs,
+    /// preformatted text as written, no markup
+    Text,
+    /// The extracted HTML itself, for structure Markdown can't express
+    Html,
+    /// The whole [`PageContent`] as JSON (links, images and metadata
+    /// included), with its content in Markdown
+    Json,
+}
+
+impl std::fmt::Display for PageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PageFormat::Markdown => "markdown",
+            PageFormat::Text => "text",
+            PageFormat::Html => "html",
+            PageFormat::Json => "json",
+        })
+    }
+}
+
+/// What `visit_page` extracts from a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractMode {
+    /// The main content, with up to 50 of the page's links
+    #[default]
+    Content,
+    /// Every link on the page (or in the selected element), by
+    /// [`LinkScope`], for mapping a site; the content lists them
+    Links,
 }
 
 /// Content type classification for search results
@@ -199,20 +625,75 @@ pub enum ContentType {
     Video,
     /// E-commerce/shopping
     Shopping,
+    /// Papers, preprints and other scholarly publications
+    Academic,
+    /// Government and public-sector sites
+    Government,
+    /// Blog posts
+    Blog,
+    /// Encyclopedias, dictionaries and other reference works
+    Reference,
     /// Other/unknown content
     #[default]
     Other,
 }
 
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ContentType::Documentation => "documentation",
+            ContentType::Social => "social",
+            ContentType::Article => "article",
+            ContentType::Forum => "forum",
+            ContentType::Video => "video",
+            ContentType::Shopping => "shopping",
+            ContentType::Academic => "academic",
+            ContentType::Government => "government",
+            ContentType::Blog => "blog",
+            ContentType::Reference => "reference",
+            ContentType::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for ContentType {
+    type Err = DaedraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "documentation" => Ok(ContentType::Documentation),
+            "social" => Ok(ContentType::Social),
+            "article" => Ok(ContentType::Article),
+            "forum" => Ok(ContentType::Forum),
+            "video" => Ok(ContentType::Video),
+            "shopping" => Ok(ContentType::Shopping),
+            "academic" => Ok(ContentType::Academic),
+            "government" => Ok(ContentType::Government),
+            "blog" => Ok(ContentType::Blog),
+            "reference" => Ok(ContentType::Reference),
+            "other" => Ok(ContentType::Other),
+            _ => Err(DaedraError::InvalidArguments(format!("Invalid content type: {}", s))),
+        }
+    }
+}
+
 /// Metadata for a search result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResultMetadata {
     /// Content type classification
     #[serde(rename = "type")]
     pub content_type: ContentType,
 
-    /// Source domain
-    pub source: String,
+    /// Source domain, internationalized names in Unicode unless they are a
+    /// homograph risk; [interned](crate::intern), as it repeats across results
+    pub source: Interned,
+
+    /// Registrable domain of the URL (`example.co.uk` for
+    /// `blog.example.co.uk`), for grouping results by site; see
+    /// [`crate::domains`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registered_domain: Option<Interned>,
 
     /// Favicon URL if available
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -221,6 +702,96 @@ pub struct ResultMetadata {
     /// Published date if available
     #[serde(skip_serializing_if = "Option::is_none")]
     pub published_date: Option<String>,
+
+    /// Upstream engines that returned this result (meta-search backends only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engines: Vec<String>,
+
+    /// Relevance score reported by the backend, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+
+    /// BM25 relevance of title and snippet to the query, set when results
+    /// are re-ranked (see [`crate::tools::rerank`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<f32>,
+
+    /// Search backend that returned this result (set by `SearchProvider`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<Interned>,
+
+    /// Outcome of a HEAD check on the URL, when `probe_fetchability` was set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe: Option<FetchProbe>,
+
+    /// Upvotes, for results from discussion sites such as Hacker News
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<u64>,
+
+    /// Number of comments, for results from discussion sites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<u64>,
+
+    /// Net votes, for questions from Q&A sites such as Stack Overflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub votes: Option<i64>,
+
+    /// Number of answers, for questions from Q&A sites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answers: Option<u64>,
+
+    /// Whether the asker accepted an answer, for questions from Q&A sites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accepted_answer: Option<bool>,
+
+    /// Tags the site filed the result under
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Rank, from 1, on the result page of the backend that returned it; kept
+    /// through merging, filtering and re-ranking. 0 until the result is put in
+    /// a [`SearchResponse`].
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub position: usize,
+
+    /// A search ad rather than an organic result; only kept when
+    /// [`SearchOptions::include_sponsored`] is set
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sponsored: bool,
+
+    /// The host could pass for a different name: it mixes scripts or spells
+    /// a Latin-looking name in Cyrillic or Greek (see
+    /// [`crate::domains::is_homograph_risk`])
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub homograph_risk: bool,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// What a HEAD request says about reading a result with `visit_page`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchProbe {
+    /// Whether `visit_page` can be expected to return content: a 2xx answer
+    /// with a page, text or PDF body within the size limit
+    pub fetchable: bool,
+
+    /// HTTP status, absent when no response arrived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Declared content type, without parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// Declared body size in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+
+    /// Why the page is not fetchable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
 /// A single search result
@@ -255,6 +826,15 @@ pub struct SearchContext {
     /// Region used for search
     pub region: String,
 
+    /// Country the backends were asked to search from (ISO 3166-1, e.g. `GB`),
+    /// derived from `region`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+
+    /// Language the backends were asked for (ISO 639-1), derived from `region`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
     /// Safe search level applied
     pub safe_search: String,
 
@@ -269,8 +849,8 @@ pub struct SearchMetadata {
     /// Original search query
     pub query: String,
 
-    /// ISO timestamp of when search was conducted
-    pub timestamp: String,
+    /// When the search was conducted
+    pub timestamp: Timestamp,
 
     /// Number of results returned
     pub result_count: usize,
@@ -280,6 +860,126 @@ pub struct SearchMetadata {
 
     /// Query analysis results
     pub query_analysis: QueryAnalysis,
+
+    /// Backend that served the results when searching a fallback chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+
+    /// What the backends were sent when the server's query rewriters changed
+    /// `query`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewritten_query: Option<String>,
+}
+
+/// Knowledge-graph panel for the query's main entity (Google via Serper)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeGraph {
+    /// Entity name
+    pub title: String,
+
+    /// Entity type (e.g. "Programming language")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<String>,
+
+    /// Short description of the entity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Link to the description's source or the entity's website
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Image URL if available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+
+    /// Key/value facts shown in the panel (e.g. "Designed by")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// A "people also ask" question with its answer snippet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedQuestion {
+    /// The related question
+    pub question: String,
+
+    /// Answer snippet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+
+    /// Title of the page the answer comes from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// URL of the page the answer comes from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// An item in one of a SERP's vertical modules: a top story, video or image
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerticalItem {
+    /// Headline, video title or image caption
+    pub title: String,
+
+    /// Article, video or image page URL
+    pub url: String,
+
+    /// Publisher, channel or hosting site
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// When it was published, as the backend reports it ("2 hours ago")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+
+    /// Thumbnail, or the image itself for image results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+
+    /// Running time of a video ("12:34")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+}
+
+/// Vertical modules embedded in a SERP — the news box and the video and image
+/// carousels — kept apart from the organic results (Google via Serper)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Verticals {
+    /// Top stories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub news: Vec<VerticalItem>,
+
+    /// Videos carousel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub videos: Vec<VerticalItem>,
+
+    /// Images carousel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<VerticalItem>,
+}
+
+impl Verticals {
+    /// Whether no module had any items
+    pub fn is_empty(&self) -> bool {
+        self.news.is_empty() && self.videos.is_empty() && self.images.is_empty()
+    }
+
+    /// Add `other`'s items, skipping URLs already present
+    pub fn merge(&mut self, other: &Verticals) {
+        for (ours, theirs) in [
+            (&mut self.news, &other.news),
+            (&mut self.videos, &other.videos),
+            (&mut self.images, &other.images),
+        ] {
+            for item in theirs {
+                if !ours.iter().any(|seen| seen.url == item.url) {
+                    ours.push(item.clone());
+                }
+            }
+        }
+    }
 }
 
 /// Complete search response
@@ -294,17 +994,47 @@ pub struct SearchResponse {
 
     /// Search metadata
     pub metadata: SearchMetadata,
+
+    /// Knowledge-graph panel, when a backend provides one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knowledge_graph: Option<KnowledgeGraph>,
+
+    /// "People also ask" questions, when a backend provides them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub people_also_ask: Vec<RelatedQuestion>,
+
+    /// News, video and image modules, when a backend provides them
+    #[serde(default, skip_serializing_if = "Verticals::is_empty")]
+    pub verticals: Verticals,
+
+    /// Whether snippets were shortened or results left out to fit `max_tokens_hint`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+
+    /// Cursor for the results left out, when any were
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl SearchResponse {
     /// Create a new search response
-    pub fn new(query: String, results: Vec<SearchResult>, options: &SearchOptions) -> Self {
-        let timestamp = chrono::Utc::now().to_rfc3339();
+    ///
+    /// `results` are in the order the backend ranked them; each one without a
+    /// [`position`](ResultMetadata::position) yet gets its rank in that order.
+    pub fn new(query: String, mut results: Vec<SearchResult>, options: &SearchOptions) -> Self {
+        for (i, result) in results.iter_mut().enumerate() {
+            if result.metadata.position == 0 {
+                result.metadata.position = i + 1;
+            }
+        }
+        crate::domains::annotate(&mut results);
+        let timestamp = Timestamp::now();
         let result_count = results.len();
 
         // Analyze query for language detection
         let language = detect_language(&query);
         let topics = detect_topics(&results);
+        let locale = crate::regions::locale(&options.region);
 
         Self {
             response_type: "search_results".to_string(),
@@ -315,13 +1045,61 @@ impl SearchResponse {
                 result_count,
                 search_context: SearchContext {
                     region: options.region.clone(),
+                    country: locale.as_ref().and_then(|l| l.country.clone()),
+                    language: locale.map(|l| l.language),
                     safe_search: options.safe_search.to_string(),
                     num_results: Some(options.num_results),
                 },
                 query_analysis: QueryAnalysis { language, topics },
+                served_by: None,
+                rewritten_query: None,
             },
+            knowledge_graph: None,
+            people_also_ask: Vec::new(),
+            verticals: Verticals::default(),
+            truncated: false,
+            next_cursor: None,
         }
     }
+
+    /// Parse the `web_search` tool's MCP result back into a typed response.
+    ///
+    /// Takes the `tools/call` result, its `content` array or the text block itself;
+    /// a tool error comes back as [`DaedraError::SearchError`].
+    pub fn from_tool_content(value: &serde_json::Value) -> DaedraResult<Self> {
+        let text = tool_text(value, DaedraError::SearchError)?;
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+/// Text of the first `text` block of an MCP tool result.
+///
+/// Accepts a whole `tools/call` result (`{"content": [...], "isError": ...}`),
+/// its `content` array, or a single content block. A result flagged `isError`
+/// becomes `on_error(text)`.
+fn tool_text(
+    value: &serde_json::Value,
+    on_error: fn(String) -> DaedraError,
+) -> DaedraResult<&str> {
+    let blocks = match value {
+        serde_json::Value::Array(blocks) => blocks.as_slice(),
+        serde_json::Value::Object(obj) => match obj.get("content") {
+            Some(serde_json::Value::Array(blocks)) => blocks.as_slice(),
+            _ => std::slice::from_ref(value),
+        },
+        _ => &[],
+    };
+    let text = blocks
+        .iter()
+        .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .find_map(|block| block.get("text")?.as_str())
+        .ok_or_else(|| {
+            DaedraError::InvalidArguments("tool result has no text content".to_string())
+        })?;
+    if value.get("isError").and_then(|e| e.as_bool()) == Some(true) {
+        return Err(on_error(text.to_string()));
+    }
+    Ok(text)
 }
 
 /// Result of visiting a page
@@ -336,8 +1114,8 @@ pub struct PageContent {
     /// Extracted content in Markdown format
     pub content: String,
 
-    /// ISO timestamp of when page was fetched
-    pub timestamp: String,
+    /// When the page was fetched (or archived, for a Wayback Machine snapshot)
+    pub timestamp: Timestamp,
 
     /// Word count of extracted content
     pub word_count: usize,
@@ -345,6 +1123,108 @@ pub struct PageContent {
     /// Links found on the page
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Vec<PageLink>>,
+
+    /// Images in the extracted content, when `include_images` was set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<PageImage>>,
+
+    /// Publication date the page declares (meta tags, JSON-LD or `<time>`), as written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_date: Option<String>,
+
+    /// Kind of page, as its metadata declares it (schema.org type, Google
+    /// Scholar `citation_*` tags or `og:type`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<ContentType>,
+
+    /// Authorship, dates and canonical URL the page declares, for citing it
+    /// (see [`crate::metadata`]); `None` when it declares none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<PageMeta>,
+
+    /// Every page the content was joined from, in order, when
+    /// `follow_pagination` followed the article past this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<Vec<String>>,
+
+    /// Whether the content was cut to fit `max_tokens_hint` or `max_length`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+
+    /// Cursor for the rest of the content, when it was cut
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+
+    /// `start_index` for the rest of the content, when it was cut
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_index: Option<usize>,
+}
+
+impl PageContent {
+    /// Parse the `visit_page` tool's MCP result back into a typed page.
+    ///
+    /// The tool answers with a Markdown header (title, URL, the host in
+    /// Unicode for an internationalized name, published date, page type,
+    /// author, site name, modification date and canonical URL when known, the
+    /// pages of a joined article, fetch time, word count, and the next cursor
+    /// and index of a truncated page) above the
+    /// page body, so `meta` holds only those of its fields;
+    /// `links` and `images` are not part of it and come back as `None` (the
+    /// images are still in the body as Markdown). A tool error comes
+    /// back as [`DaedraError::FetchError`].
+    pub fn from_tool_content(value: &serde_json::Value) -> DaedraResult<Self> {
+        let text = tool_text(value, DaedraError::FetchError)?;
+        let malformed = || DaedraError::InvalidArguments("not a visit_page result".to_string());
+
+        let (header, content) = text.split_once("\n\n---\n\n").ok_or_else(malformed)?;
+        let title = header
+            .lines()
+            .next()
+            .and_then(|l| l.strip_prefix("# "))
+            .ok_or_else(malformed)?;
+        let field = |name: &str| {
+            header
+                .lines()
+                .skip(1)
+                .find_map(|l| l.strip_prefix(name))
+                .map(|v| v.trim().to_string())
+                .ok_or_else(malformed)
+        };
+        let url = field("**URL:**")?;
+        let timestamp = field("**Fetched:**")?.parse().map_err(|_| malformed())?;
+        let word_count = field("**Words:**")?.parse().map_err(|_| malformed())?;
+        let next_cursor = field("**Next cursor:**").ok();
+        let next_index = field("**Next index:**").ok().and_then(|i| i.parse().ok());
+        let pages = field("**Pages:**")
+            .ok()
+            .map(|pages| pages.split(", ").map(str::to_string).collect());
+        let published_date = field("**Published:**").ok();
+        let content_type = field("**Type:**").ok().and_then(|t| t.parse().ok());
+        let meta = PageMeta {
+            author: field("**Author:**").ok(),
+            site_name: field("**Site:**").ok(),
+            modified_date: field("**Modified:**").ok(),
+            canonical_url: field("**Canonical:**").ok(),
+            ..PageMeta::default()
+        };
+
+        Ok(Self {
+            url,
+            title: title.to_string(),
+            content: content.to_string(),
+            timestamp,
+            word_count,
+            links: None,
+            images: None,
+            published_date,
+            content_type,
+            meta: Some(meta).filter(|meta| !meta.is_empty()),
+            pages,
+            truncated: next_cursor.is_some() || next_index.is_some(),
+            next_cursor,
+            next_index,
+        })
+    }
 }
 
 /// A link found on a page
@@ -353,35 +1233,501 @@ pub struct PageLink {
     /// Link text
     pub text: String,
 
-    /// Link URL
+    /// Link URL, with an internationalized host in punycode
     pub url: String,
-}
 
-/// Arguments for the `crawl_site` tool.
-///
-/// `max_pages` is clamped to `[1, 500]` and `concurrency` to `[1, 16]`
-/// inside `crawl::crawl_site` — the declared maxima here are advisory.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CrawlArgs {
-    /// The root URL of the site to crawl
-    pub root_url: String,
+    /// The link's host could pass for a different name (see
+    /// [`crate::domains::is_homograph_risk`])
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub homograph_risk: bool,
 
-    /// Upper bound on the number of pages to fetch
-    #[serde(default = "default_crawl_max_pages")]
-    pub max_pages: usize,
+    /// What following the link leads to, from its URL alone (see
+    /// [`crate::url_classification::classify_link`])
+    #[serde(default)]
+    pub kind: LinkKind,
 
-    /// Maximum number of concurrent fetches
-    #[serde(default = "default_crawl_concurrency")]
-    pub concurrency: usize,
+    /// Where the link points relative to the page it is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<LinkScope>,
 }
 
-fn default_crawl_max_pages() -> usize { 25 }
-fn default_crawl_concurrency() -> usize { 4 }
+/// Where a link points relative to the page it is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkScope {
+    /// Another page on the same origin (scheme, host and port)
+    Internal,
+    /// A page on another origin
+    External,
+    /// A fragment (`#section`) of the page itself
+    Anchor,
+}
 
-/// A single page fetched by `crawl_site`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CrawledPage {
-    /// Absolute URL of the fetched page
+/// An image in a page's content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageImage {
+    /// Image URL, resolved against the page
+    pub url: String,
+
+    /// Alternative text; empty when the page gives none
+    #[serde(default)]
+    pub alt: String,
+
+    /// Width in pixels, from the `width` attribute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+
+    /// Height in pixels, from the `height` attribute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+/// Metadata a page declares about itself, from Open Graph and other meta
+/// tags, its canonical link and schema.org JSON-LD
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageMeta {
+    /// Title for sharing (`og:title`, JSON-LD `headline`), which may differ from `<title>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Summary (`og:description` or `<meta name="description">`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Canonical URL (`<link rel="canonical">` or `og:url`), resolved against the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+
+    /// Author names, comma-separated (JSON-LD `author` or `<meta name="author">`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    /// Publication date, as written (JSON-LD `datePublished` or `article:published_time`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_date: Option<String>,
+
+    /// Last modification date, as written (JSON-LD `dateModified` or `article:modified_time`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_date: Option<String>,
+
+    /// Site or publisher name (`og:site_name` or JSON-LD `publisher`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+
+    /// Preview image URL (`og:image`), resolved against the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl PageMeta {
+    /// Whether the page declared nothing
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What a link leads to, so agents can tell pages from risky targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// An ordinary web page
+    #[default]
+    Page,
+    /// A file download: an archive, disk image or similar
+    Download,
+    /// A program or installer (`.exe`, `.msi`, `.apk`, shell scripts, ...)
+    Executable,
+    /// A URL shortener (`bit.ly`, `t.co`, ...) hiding the real destination
+    Shortener,
+    /// A `data:` URI carrying its content inline
+    DataUri,
+}
+
+impl LinkKind {
+    /// Whether following the link is riskier than loading a page
+    pub fn is_risky(self) -> bool {
+        self != LinkKind::Page
+    }
+}
+
+/// Arguments for the `crawl_site` tool.
+///
+/// `max_pages` is clamped to `[1, 500]` and `concurrency` to `[1, 16]`
+/// inside `crawl::crawl_site` — the declared maxima here are advisory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlArgs {
+    /// The root URL of the site to crawl
+    pub root_url: String,
+
+    /// Upper bound on the number of pages to fetch
+    #[serde(default = "default_crawl_max_pages")]
+    pub max_pages: usize,
+
+    /// Maximum number of concurrent fetches
+    #[serde(default = "default_crawl_concurrency")]
+    pub concurrency: usize,
+}
+
+fn default_crawl_max_pages() -> usize { 25 }
+fn default_crawl_concurrency() -> usize { 4 }
+
+/// Order of `search_hackernews` results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HackerNewsSort {
+    /// Best match first, as Algolia ranks it
+    #[default]
+    Relevance,
+    /// Newest first
+    Date,
+    /// Most upvoted first, among the best matches
+    Points,
+}
+
+/// Kind of Hacker News item `search_hackernews` returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HackerNewsKind {
+    /// All stories
+    #[default]
+    Story,
+    /// "Ask HN" posts
+    AskHn,
+    /// "Show HN" posts
+    ShowHn,
+}
+
+/// Arguments for the `search_hackernews` tool.
+///
+/// `num_results` is clamped to `[1, 50]` by the search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HackerNewsArgs {
+    /// The search query
+    pub query: String,
+
+    /// Result order
+    #[serde(default)]
+    pub sort: HackerNewsSort,
+
+    /// Kind of item to search
+    #[serde(default)]
+    pub kind: HackerNewsKind,
+
+    /// Number of results to return
+    #[serde(default = "default_num_results")]
+    pub num_results: usize,
+
+    /// Leave out items with fewer points
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_points: Option<u64>,
+}
+
+impl HackerNewsArgs {
+    /// Arguments for `query` with every option at its default
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            sort: HackerNewsSort::default(),
+            kind: HackerNewsKind::default(),
+            num_results: default_num_results(),
+            min_points: None,
+        }
+    }
+
+    /// Reject an empty query
+    pub fn validate(&self) -> DaedraResult<()> {
+        if self.query.trim().is_empty() {
+            return Err(DaedraError::InvalidArguments("query must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Order of `search_stackoverflow` results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StackOverflowSort {
+    /// Best match first
+    #[default]
+    Relevance,
+    /// Highest voted first
+    Votes,
+    /// Most recently active first
+    Activity,
+    /// Newest first
+    Creation,
+}
+
+/// Arguments for the `search_stackoverflow` tool.
+///
+/// `num_results` is clamped to `[1, 50]` by the search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackOverflowArgs {
+    /// The search query
+    pub query: String,
+
+    /// Only questions with all of these tags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Result order
+    #[serde(default)]
+    pub sort: StackOverflowSort,
+
+    /// Only questions with an accepted answer
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accepted_only: bool,
+
+    /// Stack Exchange site to search, by API name (`stackoverflow`,
+    /// `superuser`, `unix`)
+    #[serde(default = "default_stackexchange_site")]
+    pub site: String,
+
+    /// Number of results to return
+    #[serde(default = "default_num_results")]
+    pub num_results: usize,
+}
+
+fn default_stackexchange_site() -> String {
+    "stackoverflow".to_string()
+}
+
+impl StackOverflowArgs {
+    /// Arguments for `query` with every option at its default
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            tags: Vec::new(),
+            sort: StackOverflowSort::default(),
+            accepted_only: false,
+            site: default_stackexchange_site(),
+            num_results: default_num_results(),
+        }
+    }
+
+    /// Reject an empty query or site name
+    pub fn validate(&self) -> DaedraResult<()> {
+        if self.query.trim().is_empty() {
+            return Err(DaedraError::InvalidArguments("query must not be empty".to_string()));
+        }
+        let site = self.site.trim();
+        if site.is_empty() || !site.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+            return Err(DaedraError::InvalidArguments(format!(
+                "site must be a Stack Exchange site name such as stackoverflow, got {:?}",
+                self.site
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments for the `get_transcript` tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptArgs {
+    /// YouTube video: a watch, `youtu.be`, shorts, embed or live URL, or the
+    /// bare video ID
+    pub url: String,
+
+    /// Caption language to prefer (`en`, `pt-BR`); English when not given.
+    /// Another language is returned when the video has none in this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl TranscriptArgs {
+    /// Arguments for the video at `url`, in the default language
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            language: None,
+        }
+    }
+
+    /// Reject anything that doesn't name a YouTube video
+    pub fn validate(&self) -> DaedraResult<()> {
+        crate::tools::youtube::video_id(&self.url).map(|_| ())
+    }
+}
+
+/// One caption of a [`Transcript`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptCue {
+    /// Offset into the video the caption appears at, in milliseconds
+    pub start_ms: u64,
+
+    /// How long it stays up, in milliseconds
+    pub duration_ms: u64,
+
+    /// The caption text
+    pub text: String,
+}
+
+/// A video's captions, returned by the `get_transcript` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    /// YouTube video ID
+    pub video_id: String,
+
+    /// The video's watch URL
+    pub url: String,
+
+    /// The video's title, when the page gave one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Language code of the captions (`en`, `pt-BR`)
+    pub language: String,
+
+    /// Whether YouTube generated the captions by speech recognition
+    pub auto_generated: bool,
+
+    /// The captions, in order
+    pub cues: Vec<TranscriptCue>,
+}
+
+impl Transcript {
+    /// Seconds of captions gathered into one paragraph of [`to_markdown`](Self::to_markdown)
+    pub const PARAGRAPH_SECS: u64 = 30;
+
+    /// The transcript as Markdown: a heading with the video's title, its URL
+    /// and caption language, then paragraphs of about
+    /// [`PARAGRAPH_SECS`](Self::PARAGRAPH_SECS) each, led by a timestamp
+    /// linking to that point in the video
+    pub fn to_markdown(&self) -> String {
+        let title =
+            self.title.clone().unwrap_or_else(|| format!("YouTube video {}", self.video_id));
+        let kind = if self.auto_generated { ", auto-generated" } else { "" };
+        let mut out = format!(
+            "# {}\n\n**Video:** {}\n**Captions:** {}{}\n",
+            title, self.url, self.language, kind
+        );
+
+        let mut paragraph_start: Option<u64> = None;
+        for cue in &self.cues {
+            let starts_paragraph = paragraph_start
+                .is_none_or(|start| cue.start_ms >= start + Self::PARAGRAPH_SECS * 1000);
+            if starts_paragraph {
+                if paragraph_start.is_some() {
+                    out.push('\n');
+                }
+                let secs = cue.start_ms / 1000;
+                out.push_str(&format!(
+                    "\n**[{}]({}&t={}s)**",
+                    format_timestamp(secs),
+                    self.url,
+                    secs
+                ));
+                paragraph_start = Some(cue.start_ms);
+            }
+            out.push(' ');
+            out.push_str(&cue.text);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Arguments for the `check_url` tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckUrlArgs {
+    /// HTTP or HTTPS URL to check
+    pub url: String,
+}
+
+impl CheckUrlArgs {
+    /// Arguments for checking `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Reject anything but an HTTP or HTTPS URL
+    pub fn validate(&self) -> DaedraResult<()> {
+        if crate::tools::fetch::is_valid_url(&self.url) {
+            Ok(())
+        } else {
+            Err(DaedraError::InvalidArguments(format!(
+                "Invalid URL: must be HTTP or HTTPS: {}",
+                self.url
+            )))
+        }
+    }
+}
+
+/// Arguments for the `cache_invalidate` admin tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheInvalidateArgs {
+    /// Start of the page URLs or search queries to drop, `*` standing for any
+    /// run of characters (see [`SearchCache::invalidate_matching`](crate::SearchCache::invalidate_matching))
+    pub pattern: String,
+}
+
+impl CacheInvalidateArgs {
+    /// Reject an empty pattern, which would drop everything
+    pub fn validate(&self) -> DaedraResult<()> {
+        if self.pattern.trim().is_empty() {
+            return Err(DaedraError::InvalidArguments(
+                "pattern must not be empty; clear the whole cache instead".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What a URL answers with, returned by the `check_url` tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UrlCheck {
+    /// The URL as requested
+    pub url: String,
+
+    /// Where redirects ended
+    pub final_url: String,
+
+    /// Request that got the answer: `HEAD`, or `GET` with a one-byte range
+    /// for servers that refuse HEAD
+    pub method: String,
+
+    /// HTTP status of the final response
+    pub status: u16,
+
+    /// Reason phrase for `status` (`Not Found`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_text: Option<String>,
+
+    /// Declared content type, without parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// Declared size of the whole body in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+
+    /// Whether `visit_page` can be expected to return content (see [`FetchProbe`])
+    pub fetchable: bool,
+
+    /// Why the page is not fetchable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Response headers by lower-case name, cookies left out; repeated
+    /// headers are joined with `, `
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// `secs` as `m:ss`, or `h:mm:ss` from an hour on
+fn format_timestamp(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// A single page fetched by `crawl_site`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawledPage {
+    /// Absolute URL of the fetched page
     pub url: String,
 
     /// Extracted page title (may be empty)
@@ -432,8 +1778,348 @@ pub struct CrawlResult {
     /// Successfully fetched pages
     pub pages: Vec<CrawledPage>,
 
-    /// Per-URL errors that were silently dropped during the batch
-    pub errors: Vec<CrawlError>,
+    /// Per-URL errors that were silently dropped during the batch
+    pub errors: Vec<CrawlError>,
+}
+
+/// What `crawl_site` would do, as reported by a dry run.
+///
+/// Only discovery is known up front: after it, up to `max_pages` of the URLs
+/// found are fetched, `concurrency` at a time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlPlan {
+    /// The normalized root URL
+    pub root_url: String,
+
+    /// Page budget (post-clamp)
+    pub max_pages: usize,
+
+    /// Concurrent fetches (post-clamp)
+    pub concurrency: usize,
+
+    /// Discovery requests, in order; each runs only if the ones before it found nothing
+    pub discovery: Vec<PlannedRequest>,
+}
+
+/// Arguments for the `search_and_read` tool.
+///
+/// `read_count` is clamped to `[1, 10]` by the handler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchAndReadArgs {
+    /// The search query string
+    pub query: String,
+
+    /// How many of the top results to read
+    #[serde(default = "default_read_count")]
+    pub read_count: usize,
+
+    /// Approximate token budget for the digest, shared evenly by the pages read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens_hint: Option<usize>,
+
+    /// Search configuration, as for `web_search`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchOptions>,
+}
+
+fn default_read_count() -> usize { 3 }
+
+/// One search result in a [`ResearchDigest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSource {
+    /// Citation number; in `search_and_read`, the result's 1-based rank
+    pub index: usize,
+
+    /// Result title
+    pub title: String,
+
+    /// Result URL
+    pub url: String,
+
+    /// Search snippet
+    pub snippet: String,
+
+    /// Publication date, from the backend or the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_date: Option<String>,
+
+    /// Page content as Markdown, when the page was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Whether the content was cut to fit `max_tokens_hint`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+
+    /// Why the page could not be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of `search_and_read`: the top results' pages, and the rest as links.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchDigest {
+    /// The search query
+    pub query: String,
+
+    /// The results that were read (or failed to be), in rank order
+    pub sources: Vec<DigestSource>,
+
+    /// The remaining results, numbered after `sources`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub further_reading: Vec<DigestSource>,
+}
+
+impl ResearchDigest {
+    /// Render the digest as Markdown: a section per source headed by its
+    /// citation number, then the unread results as a link list.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Research digest: {}\n\n", self.query);
+        if self.sources.is_empty() {
+            out.push_str("No results found.\n");
+            return out;
+        }
+        let read = self.sources.iter().filter(|s| s.content.is_some()).count();
+        out.push_str(&format!(
+            "Read {} of the top {} results. Cite sources by number, e.g. [1].\n",
+            read,
+            self.sources.len()
+        ));
+
+        for source in &self.sources {
+            out.push_str(&format!(
+                "\n## [{}] {}\n\n**URL:** {}\n",
+                source.index, source.title, source.url
+            ));
+            if let Some(date) = &source.published_date {
+                out.push_str(&format!("**Published:** {}\n", date));
+            }
+            match (&source.content, &source.error) {
+                (Some(content), _) => out.push_str(&format!("\n{}\n", content.trim())),
+                (None, error) => out.push_str(&format!(
+                    "\n_Page not read ({}); search snippet:_\n\n> {}\n",
+                    error.as_deref().unwrap_or("unknown error"),
+                    source.snippet
+                )),
+            }
+            if source.truncated {
+                out.push_str("\n_Cut to fit the token budget; `visit_page` has the rest._\n");
+            }
+        }
+
+        if !self.further_reading.is_empty() {
+            out.push_str("\n## Further reading\n\n");
+            for source in &self.further_reading {
+                out.push_str(&format!("- [{}] [{}]({})", source.index, source.title, source.url));
+                if !source.snippet.is_empty() {
+                    out.push_str(&format!(" — {}", source.snippet));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Arguments for the `deep_research` tool.
+///
+/// `depth` is clamped to `[1, 3]` and `max_pages` to `[1, 20]` by the handler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepResearchArgs {
+    /// The research question
+    pub query: String,
+
+    /// Search rounds: the first searches the question and its parts, each
+    /// later one follows up on what the previous round found
+    #[serde(default = "default_research_depth")]
+    pub depth: usize,
+
+    /// Most pages to read into the report
+    #[serde(default = "default_research_max_pages")]
+    pub max_pages: usize,
+
+    /// Approximate token budget for the report, shared evenly by the pages read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens_hint: Option<usize>,
+
+    /// Search configuration for every search, as for `web_search`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SearchOptions>,
+}
+
+fn default_research_depth() -> usize { 2 }
+fn default_research_max_pages() -> usize { 5 }
+
+/// One search run by `deep_research`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchStep {
+    /// Round the search ran in, from 1
+    pub round: usize,
+
+    /// The query searched
+    pub query: String,
+
+    /// Results it returned
+    pub result_count: usize,
+
+    /// Why the search failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A source `deep_research` found but left out of the report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedSource {
+    /// Source URL
+    pub url: String,
+
+    /// Why it was left out: a fetch error, or the source it duplicates
+    pub reason: String,
+}
+
+/// Result of `deep_research`: the searches run and the sources read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepResearchReport {
+    /// The research question
+    pub query: String,
+
+    /// Every search, in the order run
+    pub steps: Vec<ResearchStep>,
+
+    /// Pages read, most-cited first, numbered from 1
+    pub sources: Vec<DigestSource>,
+
+    /// Sources tried but not read
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedSource>,
+}
+
+impl DeepResearchReport {
+    /// Render the report as Markdown: the searches run, a section per source
+    /// headed by its citation number, and a closing sources list.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Deep research: {}\n\n## Searches\n\n", self.query);
+        for step in &self.steps {
+            match &step.error {
+                Some(error) => out.push_str(&format!(
+                    "- Round {}: `{}` — failed: {}\n",
+                    step.round, step.query, error
+                )),
+                None => out.push_str(&format!(
+                    "- Round {}: `{}` — {} results\n",
+                    step.round, step.query, step.result_count
+                )),
+            }
+        }
+
+        out.push_str("\n## Findings\n");
+        if self.sources.is_empty() {
+            out.push_str("\nNo source could be read.\n");
+        }
+        for source in &self.sources {
+            out.push_str(&format!(
+                "\n### [{}] {}\n\n**URL:** {}\n",
+                source.index, source.title, source.url
+            ));
+            if let Some(date) = &source.published_date {
+                out.push_str(&format!("**Published:** {}\n", date));
+            }
+            out.push_str(&format!("\n{}\n", source.content.as_deref().unwrap_or_default().trim()));
+            if source.truncated {
+                out.push_str("\n_Cut to fit the token budget; `visit_page` has the rest._\n");
+            }
+        }
+
+        if !self.sources.is_empty() {
+            out.push_str("\n## Sources\n\n");
+            for source in &self.sources {
+                out.push_str(&format!("{}. [{}]({})\n", source.index, source.title, source.url));
+            }
+        }
+        if !self.skipped.is_empty() {
+            out.push_str("\n## Skipped\n\n");
+            for skipped in &self.skipped {
+                out.push_str(&format!("- {} — {}\n", skipped.url, skipped.reason));
+            }
+        }
+        out
+    }
+}
+
+/// Stand-in for API keys and tokens in a [`PlannedRequest`]
+pub const REDACTED: &str = "[redacted]";
+
+/// An HTTP request a tool would send, as reported by a dry run.
+///
+/// Credentials are replaced with [`REDACTED`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedRequest {
+    /// HTTP method
+    pub method: String,
+
+    /// URL without query string
+    pub url: String,
+
+    /// Query parameters, or form fields for a form POST
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+
+    /// JSON body, for POST requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+
+    /// Follow-up requests that depend on the response, and similar caveats
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl PlannedRequest {
+    /// A GET of `url` with `params` as the query string
+    pub fn get<K: ToString, V: ToString>(url: &str, params: &[(K, V)]) -> Self {
+        Self {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            params: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: None,
+            note: None,
+        }
+    }
+
+    /// A POST of `fields` as a URL-encoded form to `url`
+    pub fn post_form<K: ToString, V: ToString>(url: &str, fields: &[(K, V)]) -> Self {
+        Self {
+            method: "POST".to_string(),
+            ..Self::get(url, fields)
+        }
+    }
+
+    /// A POST of `body` as JSON to `url`
+    pub fn post_json(url: &str, body: serde_json::Value) -> Self {
+        Self {
+            method: "POST".to_string(),
+            url: url.to_string(),
+            params: BTreeMap::new(),
+            body: Some(body),
+            note: None,
+        }
+    }
+
+    /// Replace the value of query parameter `key` with [`REDACTED`]
+    pub fn redact_param(mut self, key: &str) -> Self {
+        if let Some(value) = self.params.get_mut(key) {
+            *value = REDACTED.to_string();
+        }
+        self
+    }
+
+    /// Attach a note
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
 }
 
 struct LangRange {
@@ -535,14 +2221,69 @@ fn detect_topics(results: &[SearchResult]) -> Vec<String> {
     topics.into_iter().collect()
 }
 
+/// JSON Schema for the `multi_search` tool's arguments
+pub fn multi_search_args_schema() -> serde_json::Value {
+    let search = search_args_schema();
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "queries": {
+                "type": "array",
+                "items": { "type": "string", "minLength": 1 },
+                "description": "Queries to search concurrently; the response has one entry per query, in this order",
+                "minItems": 1,
+                "maxItems": MAX_MULTI_SEARCH_QUERIES
+            },
+            "options": search["properties"]["options"],
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report each query's search plan without running any of them",
+                "default": false
+            }
+        },
+        "required": ["queries"]
+    })
+}
+
 /// JSON Schema for search arguments (used for MCP tool definition)
 pub fn search_args_schema() -> serde_json::Value {
     serde_json::json!({
         "type": "object",
         "properties": {
             "query": {
-                "type": "string",
-                "description": "The search query string"
+                "type": ["string", "object"],
+                "description": "The search query: text, which may use operators (\"exact phrase\", -exclude, site:, filetype:, a OR b), or an object of its parts, rewritten for each backend's operator syntax",
+                "properties": {
+                    "terms": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Words that should appear"
+                    },
+                    "phrases": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Exact phrases that must appear"
+                    },
+                    "sites": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Domains to search within; results may come from any of them"
+                    },
+                    "exclude": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Words or phrases that must not appear"
+                    },
+                    "any_of": {
+                        "type": "array",
+                        "items": {"type": "array", "items": {"type": "string"}},
+                        "description": "Groups of alternatives; at least one of each group must appear"
+                    },
+                    "filetype": {
+                        "type": "string",
+                        "description": "File extension results must have, e.g. 'pdf'"
+                    }
+                }
             },
             "options": {
                 "type": "object",
@@ -550,7 +2291,7 @@ pub fn search_args_schema() -> serde_json::Value {
                 "properties": {
                     "region": {
                         "type": "string",
-                        "description": "Region for search results (e.g., 'us-en', 'wt-wt' for worldwide)",
+                        "description": "DuckDuckGo region code, country-language (e.g., 'us-en', 'wt-wt' for worldwide); unknown codes are rejected with suggestions",
                         "default": "wt-wt"
                     },
                     "safe_search": {
@@ -568,9 +2309,82 @@ pub fn search_args_schema() -> serde_json::Value {
                     },
                     "time_range": {
                         "type": "string",
-                        "description": "Time range filter (d=day, w=week, m=month, y=year)"
+                        "description": "Time range filter: d=day, w=week, m=month, y=year, or an inclusive date range YYYY-MM-DD..YYYY-MM-DD",
+                        "pattern": "^([dwmy]|day|week|month|year|\\d{4}-\\d{2}-\\d{2}\\.\\.\\d{4}-\\d{2}-\\d{2})$"
+                    },
+                    "strategy": {
+                        "type": "string",
+                        "enum": ["fallback", "merge"],
+                        "description": "How to combine search backends: 'merge' queries them concurrently and deduplicates, 'fallback' stops at the first one with results. Defaults to the server setting"
+                    },
+                    "expand_snippets": {
+                        "type": "boolean",
+                        "description": "Fetch the top results and replace their snippets with the pages' opening paragraphs: slower, but a much better basis for choosing what to read",
+                        "default": false
+                    },
+                    "prefer_types": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": [
+                                "documentation", "article", "blog", "forum", "video", "academic",
+                                "government", "reference", "social", "shopping", "other"
+                            ]
+                        },
+                        "description": "Content types to rank ahead of the rest, most wanted first, e.g. [\"documentation\", \"article\"]"
+                    },
+                    "exclude_types": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": [
+                                "documentation", "article", "blog", "forum", "video", "academic",
+                                "government", "reference", "social", "shopping", "other"
+                            ]
+                        },
+                        "description": "Content types to leave out, e.g. [\"shopping\", \"social\"]"
+                    },
+                    "max_per_domain": {
+                        "type": "integer",
+                        "description": "Keep at most this many results from one site. Subdomains count as the same site (blog.example.co.uk and www.example.co.uk are both example.co.uk, per metadata.registered_domain)",
+                        "minimum": 1
+                    },
+                    "include_sponsored": {
+                        "type": "boolean",
+                        "description": "Keep search ads, marked with metadata.sponsored, instead of leaving them out",
+                        "default": false
+                    },
+                    "probe_fetchability": {
+                        "type": "boolean",
+                        "description": "HEAD-check the top results and add metadata.probe to each: fetchable, HTTP status, content type and size, and why not when it is not fetchable (403/404, video, too large)",
+                        "default": false
+                    },
+                    "enrich_top": {
+                        "type": "integer",
+                        "description": "Fetch this many top results (at most 10) and replace thin snippets with each page's first paragraph, adding the page's published date when the backend gave none",
+                        "minimum": 0,
+                        "maximum": 10,
+                        "default": 0
+                    },
+                    "rerank": {
+                        "type": "boolean",
+                        "description": "Re-rank results by BM25 relevance of title and snippet to the query, exposed as metadata.relevance. Merged results are re-ranked unless this is false; a fallback chain's only when it is true"
+                    },
+                    "max_tokens_hint": {
+                        "type": "integer",
+                        "description": "Approximate token budget for the response: snippets are shortened and trailing results left out to fit, with 'truncated' and 'next_cursor' set",
+                        "minimum": 1
+                    },
+                    "cursor": {
+                        "type": "string",
+                        "description": "'next_cursor' from a truncated response, to get the results it left out"
                     }
                 }
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the backends, cache state, rate limits and requests this search would use, without sending any",
+                "default": false
             }
         },
         "required": ["query"]
@@ -593,7 +2407,267 @@ pub fn visit_page_args_schema() -> serde_json::Value {
             },
             "include_images": {
                 "type": "boolean",
-                "description": "Whether to include image references in the response",
+                "description": "Keep images in the Markdown as ![alt](url), with URLs resolved against the page",
+                "default": false
+            },
+            "extract": {
+                "type": "string",
+                "enum": ["content", "links"],
+                "description": "'links' returns every link on the page (or in the selector's element) instead of its content, grouped as internal, external and anchor links, with no limit on their number",
+                "default": "content"
+            },
+            "format": {
+                "type": "string",
+                "enum": ["markdown", "text", "html", "json"],
+                "description": "Return the content as Markdown, plain text, the extracted HTML, or the whole page (links, images, metadata) as JSON with Markdown content",
+                "default": "markdown"
+            },
+            "max_tokens_hint": {
+                "type": "integer",
+                "description": "Approximate token budget for the page: longer content is cut at a paragraph boundary and a 'Next cursor' line added",
+                "minimum": 1
+            },
+            "cursor": {
+                "type": "string",
+                "description": "'Next cursor' from a truncated page, to continue where it stopped"
+            },
+            "max_length": {
+                "type": "integer",
+                "description": "Return at most this many characters of the content; a longer page is cut there and a 'Next index' line added",
+                "minimum": 1
+            },
+            "follow_pagination": {
+                "type": "integer",
+                "description": "For an article split over several pages: follow its next-page links (rel=\"next\", ?page=N) and join up to this many pages, this one included, into one",
+                "minimum": 1,
+                "maximum": 10
+            },
+            "allow_archive": {
+                "type": "boolean",
+                "description": "If the page is gone (HTTP 404 or 410, or its host no longer resolves), return its latest Wayback Machine snapshot instead; the URL is then the snapshot's and the timestamp when it was taken",
+                "default": false
+            },
+            "start_index": {
+                "type": "integer",
+                "description": "Character offset to start the content at, e.g. the 'Next index' of a page cut by max_length; not with 'cursor'",
+                "minimum": 0,
+                "default": 0
+            },
+            "region": {
+                "type": "string",
+                "description": "Search region code (e.g. 'de-de') whose language to ask the site for via Accept-Language; pages already cached come back as first fetched"
+            },
+            "max_bytes": {
+                "type": "integer",
+                "description": "Refuse the page if its body is larger than this many bytes (at most the server's own cap, 10 MiB by default); the download stops as soon as it passes",
+                "minimum": 1
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report whether the page is cached and what request would fetch it, without sending it",
+                "default": false
+            }
+        },
+        "required": ["url"]
+    })
+}
+
+/// Returns the JSON Schema for the search_and_read tool arguments.
+pub fn search_and_read_args_schema() -> serde_json::Value {
+    let search = search_args_schema();
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "query": search["properties"]["query"],
+            "read_count": {
+                "type": "integer",
+                "description": "How many of the top results to fetch and include in full (default: 3)",
+                "minimum": 1,
+                "maximum": 10,
+                "default": 3
+            },
+            "max_tokens_hint": {
+                "type": "integer",
+                "description": "Approximate token budget for the whole digest, split evenly between the pages read",
+                "minimum": 1
+            },
+            "options": search["properties"]["options"],
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the search plan and the pages that would be read, without sending any request",
+                "default": false
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+/// Returns the JSON Schema for the deep_research tool arguments.
+pub fn deep_research_args_schema() -> serde_json::Value {
+    let search = search_args_schema();
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "The research question; compound questions ('X vs Y', 'A and B') are searched part by part too"
+            },
+            "depth": {
+                "type": "integer",
+                "description": "Search rounds: 1 searches the question and its parts; each further round follows up on related questions and recurring topics in the previous round's results (default: 2)",
+                "minimum": 1,
+                "maximum": 3,
+                "default": 2
+            },
+            "max_pages": {
+                "type": "integer",
+                "description": "Most pages to read into the report, chosen by how many searches found them (default: 5)",
+                "minimum": 1,
+                "maximum": 20,
+                "default": 5
+            },
+            "max_tokens_hint": {
+                "type": "integer",
+                "description": "Approximate token budget for the whole report, split evenly between the pages read",
+                "minimum": 1
+            },
+            "options": search["properties"]["options"],
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the first round of searches and the reads that would follow, without sending any request",
+                "default": false
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+/// Returns the JSON Schema for the search_hackernews tool arguments.
+pub fn hackernews_args_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "Search query, matched against story titles, URLs and text"
+            },
+            "sort": {
+                "type": "string",
+                "enum": ["relevance", "date", "points"],
+                "description": "Result order: best match, newest first, or most upvoted first among the best matches (default: relevance)",
+                "default": "relevance"
+            },
+            "kind": {
+                "type": "string",
+                "enum": ["story", "ask_hn", "show_hn"],
+                "description": "Items to search: all stories, Ask HN or Show HN posts (default: story)",
+                "default": "story"
+            },
+            "num_results": {
+                "type": "integer",
+                "description": "Number of results to return (default: 10)",
+                "minimum": 1,
+                "maximum": 50,
+                "default": 10
+            },
+            "min_points": {
+                "type": "integer",
+                "description": "Leave out items with fewer points",
+                "minimum": 0
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the Algolia request this search would send, without sending it",
+                "default": false
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+/// Returns the JSON Schema for the search_stackoverflow tool arguments.
+pub fn stackoverflow_args_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "Search query, matched against question titles and bodies"
+            },
+            "tags": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Only questions with all of these tags, such as [\"rust\", \"tokio\"]"
+            },
+            "sort": {
+                "type": "string",
+                "enum": ["relevance", "votes", "activity", "creation"],
+                "description": "Result order: best match, highest voted, recently active or newest (default: relevance)",
+                "default": "relevance"
+            },
+            "accepted_only": {
+                "type": "boolean",
+                "description": "Only questions with an accepted answer",
+                "default": false
+            },
+            "site": {
+                "type": "string",
+                "description": "Stack Exchange site to search, such as stackoverflow, superuser or unix (default: stackoverflow)",
+                "default": "stackoverflow"
+            },
+            "num_results": {
+                "type": "integer",
+                "description": "Number of results to return (default: 10)",
+                "minimum": 1,
+                "maximum": 50,
+                "default": 10
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the Stack Exchange API request this search would send, without sending it",
+                "default": false
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+/// Returns the JSON Schema for the get_transcript tool arguments.
+pub fn transcript_args_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "url": {
+                "type": "string",
+                "description": "YouTube video: a watch, youtu.be, shorts, embed or live URL, or the bare 11-character video ID"
+            },
+            "language": {
+                "type": "string",
+                "description": "Caption language to prefer, e.g. 'en' or 'pt-BR' (default: en); manual captions are preferred over automatic ones, and another language is returned when the video has none in this one"
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the watch page request this call would send, without sending it",
+                "default": false
+            }
+        },
+        "required": ["url"]
+    })
+}
+
+/// Returns the JSON Schema for the check_url tool arguments.
+pub fn check_url_args_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "url": {
+                "type": "string",
+                "description": "HTTP or HTTPS URL to check",
+                "format": "uri"
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the request this call would send, without sending it",
                 "default": false
             }
         },
@@ -601,6 +2675,20 @@ pub fn visit_page_args_schema() -> serde_json::Value {
     })
 }
 
+/// Returns the JSON Schema for the cache_invalidate tool arguments.
+pub fn cache_invalidate_args_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "pattern": {
+                "type": "string",
+                "description": "Start of the page URLs or search queries to drop; '*' stands for any run of characters, e.g. 'https://*.example.com/'"
+            }
+        },
+        "required": ["pattern"]
+    })
+}
+
 /// Returns the JSON Schema for the crawl_site tool arguments.
 pub fn crawl_args_schema() -> serde_json::Value {
     serde_json::json!({
@@ -620,6 +2708,11 @@ pub fn crawl_args_schema() -> serde_json::Value {
                 "type": "integer",
                 "description": "Maximum concurrent fetches (default: 4)",
                 "default": 4
+            },
+            "dry_run": {
+                "type": "boolean",
+                "description": "Report the discovery requests and page budget this crawl would use, without sending any",
+                "default": false
             }
         },
         "required": ["root_url"]
@@ -681,9 +2774,10 @@ mod tests {
             description: "Test description".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: "example.com".to_string(),
+                source: "example.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }];
 
@@ -693,6 +2787,74 @@ mod tests {
         assert_eq!(response.response_type, "search_results");
         assert_eq!(response.data.len(), 1);
         assert_eq!(response.metadata.query, "test query");
+        assert_eq!(response.data[0].metadata.position, 1);
+    }
+
+    #[test]
+    fn test_search_response_keeps_existing_positions() {
+        let result = |url: &str, position: usize| SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            description: String::new(),
+            metadata: ResultMetadata {
+                position,
+                ..Default::default()
+            },
+        };
+        let results = vec![result("https://b", 4), result("https://new", 0)];
+        let response = SearchResponse::new("q".to_string(), results, &SearchOptions::default());
+        let positions: Vec<usize> = response.data.iter().map(|r| r.metadata.position).collect();
+        assert_eq!(positions, [4, 2]);
+
+        let json = serde_json::to_value(&response.data[0]).unwrap();
+        assert_eq!(json["metadata"]["position"], 4);
+        let unranked = serde_json::to_value(result("https://c", 0)).unwrap();
+        assert!(unranked["metadata"].get("position").is_none());
+    }
+
+    #[test]
+    fn test_search_response_from_tool_content() {
+        let response = SearchResponse::new("q".to_string(), vec![], &SearchOptions::default());
+        let text = serde_json::to_string_pretty(&response).unwrap();
+        let result = serde_json::json!({
+            "content": [{"type": "text", "text": text}],
+            "isError": false
+        });
+
+        let parsed = SearchResponse::from_tool_content(&result).unwrap();
+        assert_eq!(parsed.metadata.query, "q");
+        let parsed = SearchResponse::from_tool_content(&result["content"]).unwrap();
+        assert_eq!(parsed.metadata.query, "q");
+        let parsed = SearchResponse::from_tool_content(&result["content"][0]).unwrap();
+        assert_eq!(parsed.metadata.query, "q");
+    }
+
+    #[test]
+    fn test_from_tool_content_errors() {
+        let failed = serde_json::json!({
+            "content": [{"type": "text", "text": "Search failed: boom"}],
+            "isError": true
+        });
+        assert!(matches!(
+            SearchResponse::from_tool_content(&failed),
+            Err(DaedraError::SearchError(msg)) if msg == "Search failed: boom"
+        ));
+        assert!(matches!(
+            PageContent::from_tool_content(&failed),
+            Err(DaedraError::FetchError(_))
+        ));
+
+        let no_text = serde_json::json!({"content": [{"type": "image", "data": ""}]});
+        assert!(matches!(
+            SearchResponse::from_tool_content(&no_text),
+            Err(DaedraError::InvalidArguments(_))
+        ));
+        let not_json = serde_json::json!({"type": "text", "text": "plain words"});
+        assert!(matches!(
+            SearchResponse::from_tool_content(&not_json),
+            Err(DaedraError::JsonError(_))
+        ));
+        assert!(PageContent::from_tool_content(&not_json).is_err());
     }
 
     #[test]
@@ -703,9 +2865,10 @@ mod tests {
             description: "Source".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Documentation,
-                source: "github.com".to_string(),
+                source: "github.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }];
         let response = SearchResponse::new("rust".to_string(), results, &SearchOptions::default());
@@ -727,6 +2890,59 @@ mod tests {
         assert!("invalid".parse::<SafeSearchLevel>().is_err());
     }
 
+    #[test]
+    fn test_time_range_parsing() {
+        assert_eq!("w".parse::<TimeRange>().unwrap(), TimeRange::Week);
+        assert_eq!("Month".parse::<TimeRange>().unwrap(), TimeRange::Month);
+        let custom: TimeRange = "2024-01-01..2024-03-31".parse().unwrap();
+        assert_eq!(custom.to_string(), "2024-01-01..2024-03-31");
+
+        assert!("fortnight".parse::<TimeRange>().is_err());
+        assert!("2024-13-01..2024-03-31".parse::<TimeRange>().is_err());
+        let backwards = "2024-03-31..2024-01-01".parse::<TimeRange>().unwrap_err();
+        assert!(backwards.to_string().contains("starts after it ends"));
+    }
+
+    #[test]
+    fn test_time_range_serde_round_trip() {
+        let options: SearchOptions = serde_json::from_str(r#"{"time_range":"y"}"#).unwrap();
+        assert_eq!(options.time_range, Some(TimeRange::Year));
+        assert_eq!(serde_json::to_value(&options).unwrap()["time_range"], "y");
+        assert!(serde_json::from_str::<SearchOptions>(r#"{"time_range":"x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_search_options_validate_region() {
+        assert!(SearchOptions::default().validate().is_ok());
+        let options = SearchOptions {
+            region: "en-us".to_string(),
+            ..Default::default()
+        };
+        match options.validate() {
+            Err(DaedraError::InvalidArguments(msg)) => assert!(msg.contains("us-en"), "{msg}"),
+            other => panic!("expected InvalidArguments, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_search_options_content_type_preferences() {
+        let options: SearchOptions = serde_json::from_str(
+            r#"{"prefer_types":["documentation","article"],"exclude_types":["shopping"]}"#,
+        )
+        .unwrap();
+        assert_eq!(options.prefer_types, [ContentType::Documentation, ContentType::Article]);
+        assert!(options.validate().is_ok());
+
+        let contradictory = SearchOptions {
+            exclude_types: vec![ContentType::Article],
+            ..options
+        };
+        match contradictory.validate() {
+            Err(DaedraError::InvalidArguments(msg)) => assert!(msg.contains("'article'"), "{msg}"),
+            other => panic!("expected InvalidArguments, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_crawl_args_defaults() {
         let args: CrawlArgs =
@@ -736,6 +2952,139 @@ mod tests {
         assert_eq!(args.concurrency, 4);
     }
 
+    #[test]
+    fn test_search_and_read_args_defaults() {
+        let args: SearchAndReadArgs = serde_json::from_str(r#"{"query":"tokio"}"#).unwrap();
+        assert_eq!(args.read_count, 3);
+        assert!(args.max_tokens_hint.is_none() && args.options.is_none());
+    }
+
+    #[test]
+    fn test_multi_search_args_validate() {
+        let args: MultiSearchArgs =
+            serde_json::from_str(r#"{"queries":["tokio","async-std"],"options":{"num_results":3}}"#)
+                .unwrap();
+        assert!(args.validate().is_ok());
+        let searches = args.searches();
+        assert_eq!(searches[1].query, "async-std");
+        assert_eq!(searches[1].options.as_ref().unwrap().num_results, 3);
+
+        assert!(MultiSearchArgs::new(Vec::<String>::new()).validate().is_err());
+        assert!(MultiSearchArgs::new(["rust", " "]).validate().is_err());
+        let too_many = MultiSearchArgs::new(vec!["q"; MAX_MULTI_SEARCH_QUERIES + 1]);
+        assert!(too_many.validate().is_err());
+        let bad_region = MultiSearchArgs {
+            options: Some(SearchOptions {
+                region: "en-us".to_string(),
+                ..Default::default()
+            }),
+            ..MultiSearchArgs::new(["rust"])
+        };
+        assert!(bad_region.validate().is_err());
+    }
+
+    #[test]
+    fn test_multi_search_item_keeps_errors_per_query() {
+        let failed = MultiSearchItem::new("q", Err(DaedraError::SearchError("boom".to_string())));
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(json["query"], "q");
+        assert!(json["error"].as_str().unwrap().contains("boom"));
+        assert!(json.get("response").is_none());
+    }
+
+    #[test]
+    fn test_deep_research_args_defaults() {
+        let args: DeepResearchArgs = serde_json::from_str(r#"{"query":"tokio"}"#).unwrap();
+        assert_eq!((args.depth, args.max_pages), (2, 5));
+    }
+
+    #[test]
+    fn test_deep_research_report_markdown() {
+        let report = DeepResearchReport {
+            query: "rc vs arc".to_string(),
+            steps: vec![
+                ResearchStep {
+                    round: 1,
+                    query: "rc vs arc".to_string(),
+                    result_count: 10,
+                    error: None,
+                },
+                ResearchStep {
+                    round: 2,
+                    query: "rc vs arc threads".to_string(),
+                    result_count: 0,
+                    error: Some("rate limited".to_string()),
+                },
+            ],
+            sources: vec![DigestSource {
+                index: 1,
+                title: "Rc and Arc".to_string(),
+                url: "https://example.com/rc".to_string(),
+                snippet: String::new(),
+                published_date: None,
+                content: Some("Arc is the thread-safe one.".to_string()),
+                truncated: false,
+                error: None,
+            }],
+            skipped: vec![SkippedSource {
+                url: "https://mirror.example/rc".to_string(),
+                reason: "duplicate of [1]".to_string(),
+            }],
+        };
+        let md = report.to_markdown();
+        assert!(md.contains("- Round 1: `rc vs arc` — 10 results\n"), "{md}");
+        assert!(md.contains("- Round 2: `rc vs arc threads` — failed: rate limited\n"), "{md}");
+        assert!(md.contains("### [1] Rc and Arc\n\n**URL:** https://example.com/rc\n"), "{md}");
+        assert!(md.contains("Arc is the thread-safe one."), "{md}");
+        assert!(md.contains("## Sources\n\n1. [Rc and Arc](https://example.com/rc)\n"), "{md}");
+        assert!(md.contains("- https://mirror.example/rc — duplicate of [1]"), "{md}");
+    }
+
+    #[test]
+    fn test_research_digest_markdown() {
+        let source = |index: usize, content: Option<&str>| DigestSource {
+            index,
+            title: format!("Source {index}"),
+            url: format!("https://example.com/{index}"),
+            snippet: format!("Snippet {index}"),
+            published_date: None,
+            content: content.map(str::to_string),
+            truncated: false,
+            error: content.is_none().then(|| "HTTP 404".to_string()),
+        };
+        let digest = ResearchDigest {
+            query: "tokio".to_string(),
+            sources: vec![
+                DigestSource {
+                    published_date: Some("2024-03-12".to_string()),
+                    truncated: true,
+                    ..source(1, Some("Body of the first page."))
+                },
+                source(2, None),
+            ],
+            further_reading: vec![DigestSource { error: None, ..source(3, None) }],
+        };
+        let md = digest.to_markdown();
+        assert!(md.starts_with("# Research digest: tokio\n"), "{md}");
+        assert!(md.contains("Read 1 of the top 2 results"), "{md}");
+        assert!(md.contains("## [1] Source 1\n\n**URL:** https://example.com/1\n"), "{md}");
+        assert!(md.contains("**Published:** 2024-03-12"), "{md}");
+        assert!(md.contains("Body of the first page."), "{md}");
+        assert!(md.contains("Cut to fit the token budget"), "{md}");
+        assert!(md.contains("_Page not read (HTTP 404); search snippet:_\n\n> Snippet 2"), "{md}");
+        assert!(
+            md.contains("## Further reading\n\n- [3] [Source 3](https://example.com/3) — Snippet 3"),
+            "{md}"
+        );
+
+        let empty = ResearchDigest {
+            query: "nothing".to_string(),
+            sources: Vec::new(),
+            further_reading: Vec::new(),
+        };
+        assert!(empty.to_markdown().contains("No results found."));
+    }
+
     #[test]
     fn test_content_type_default() {
         assert_eq!(ContentType::default(), ContentType::Other);
@@ -747,12 +3096,28 @@ mod tests {
             url: "https://example.com".to_string(),
             title: "Example".to_string(),
             content: "# Hello".to_string(),
-            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".parse().unwrap(),
             word_count: 1,
             links: Some(vec![PageLink {
                 text: "Link".to_string(),
                 url: "https://example.com/other".to_string(),
+                homograph_risk: false,
+                kind: LinkKind::Page,
+                scope: None,
             }]),
+            images: Some(vec![PageImage {
+                url: "https://example.com/logo.png".to_string(),
+                alt: "Logo".to_string(),
+                width: Some(120),
+                height: None,
+            }]),
+            published_date: None,
+            content_type: None,
+            meta: None,
+            pages: None,
+            truncated: false,
+            next_cursor: None,
+            next_index: None,
         };
         let json = serde_json::to_string(&page).unwrap();
         let round_trip: PageContent = serde_json::from_str(&json).unwrap();
@@ -765,5 +3130,35 @@ mod tests {
             round_trip.links.as_ref().and_then(|v| v.first()).map(|l| l.url.as_str()),
             page.links.as_ref().and_then(|v| v.first()).map(|l| l.url.as_str())
         );
+        assert_eq!(round_trip.images, page.images);
+        assert!(!json.contains("\"height\""));
+    }
+
+    #[test]
+    fn test_transcript_markdown_paragraphs_and_timestamps() {
+        let cue = |secs: u64, text: &str| TranscriptCue {
+            start_ms: secs * 1000 + 250,
+            duration_ms: 2000,
+            text: text.to_string(),
+        };
+        let transcript = Transcript {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            title: Some("Talk".to_string()),
+            language: "en".to_string(),
+            auto_generated: true,
+            cues: vec![cue(0, "Hello"), cue(12, "world."), cue(45, "Later"), cue(3725, "End")],
+        };
+        let url = &transcript.url;
+        let markdown = transcript.to_markdown();
+        assert_eq!(
+            markdown,
+            format!(
+                "# Talk\n\n**Video:** {url}\n**Captions:** en, auto-generated\n\n\
+                 **[0:00]({url}&t=0s)** Hello world.\n\n\
+                 **[0:45]({url}&t=45s)** Later\n\n\
+                 **[1:02:05]({url}&t=3725s)** End\n"
+            )
+        );
     }
 }
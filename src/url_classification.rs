@@ -1,4 +1,4 @@
-use crate::types::ContentType;
+use crate::types::{ContentType, LinkKind};
 
 struct SubstringRule {
     content_type: ContentType,
@@ -6,6 +6,36 @@ struct SubstringRule {
 }
 
 const SUBSTRING_RULES: &[SubstringRule] = &[
+    SubstringRule {
+        content_type: ContentType::Academic,
+        patterns: &[
+            "arxiv.org",
+            "doi.org/",
+            "scholar.google",
+            "semanticscholar.org",
+            "pubmed.",
+            "ncbi.nlm.nih.gov/pmc",
+            "researchgate.net",
+            "ieeexplore.ieee.org",
+            "dl.acm.org",
+            "link.springer.com",
+            ".edu/",
+        ],
+    },
+    SubstringRule {
+        content_type: ContentType::Government,
+        patterns: &[".gov/", ".gov.", ".gouv.", ".gob.", ".mil/", "europa.eu"],
+    },
+    SubstringRule {
+        content_type: ContentType::Reference,
+        patterns: &[
+            "wikipedia.org",
+            "wiktionary.org",
+            "britannica.com",
+            "merriam-webster.com",
+            "dictionary.",
+        ],
+    },
     SubstringRule {
         content_type: ContentType::Documentation,
         patterns: &[
@@ -50,6 +80,18 @@ const SUBSTRING_RULES: &[SubstringRule] = &[
         content_type: ContentType::Shopping,
         patterns: &["amazon.", "ebay.", "shop.", "/shop/", "store."],
     },
+    SubstringRule {
+        content_type: ContentType::Blog,
+        patterns: &[
+            "blog.",
+            "/blog/",
+            "medium.com",
+            "substack.com",
+            "dev.to/",
+            "wordpress.com",
+            "blogspot.",
+        ],
+    },
     SubstringRule {
         content_type: ContentType::Article,
         patterns: &["news.", "/news/", "bbc.", "cnn.", "nytimes.", "reuters."],
@@ -57,6 +99,10 @@ const SUBSTRING_RULES: &[SubstringRule] = &[
 ];
 
 /// Classify a search result URL into a [`ContentType`] using substring rules.
+///
+/// This is a guess from the URL alone, and anything unrecognized counts as an
+/// article; fetched pages refine it from their metadata (see
+/// [`PageContent::content_type`](crate::types::PageContent::content_type)).
 pub fn classify_search_url(url: &str) -> ContentType {
     let lower = url.to_lowercase();
     for rule in SUBSTRING_RULES {
@@ -67,6 +113,52 @@ pub fn classify_search_url(url: &str) -> ContentType {
     ContentType::Article
 }
 
+/// File extensions of programs, installers and scripts
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    ".exe", ".msi", ".bat", ".cmd", ".scr", ".ps1", ".vbs", ".sh", ".apk", ".deb",
+    ".rpm", ".jar", ".pkg", ".appimage", ".dll",
+];
+
+/// File extensions of archives, disk images and other downloads
+const DOWNLOAD_EXTENSIONS: &[&str] = &[
+    ".zip", ".tar", ".gz", ".tgz", ".bz2", ".xz", ".zst", ".7z", ".rar", ".iso", ".dmg",
+    ".img", ".bin", ".whl", ".crate",
+];
+
+/// URL shortener hosts, matched with their subdomains
+const SHORTENER_HOSTS: &[&str] = &[
+    "bit.ly", "t.co", "tinyurl.com", "goo.gl", "ow.ly", "is.gd", "buff.ly", "rebrand.ly",
+    "cutt.ly", "shorturl.at", "tiny.cc", "rb.gy", "s.id", "lnkd.in", "t.ly", "bl.ink",
+];
+
+/// Classify a link found on a page into a [`LinkKind`] from its URL alone:
+/// `data:` URIs, URL shorteners, then executables and downloads by the file
+/// extension of the path. Anything else, unparsable URLs included, is a page.
+pub fn classify_link(url: &str) -> LinkKind {
+    let Ok(parsed) = url::Url::parse(url.trim()) else {
+        return LinkKind::Page;
+    };
+    if parsed.scheme() == "data" {
+        return LinkKind::DataUri;
+    }
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let shortened = SHORTENER_HOSTS
+        .iter()
+        .any(|s| host == *s || host.strip_suffix(s).is_some_and(|h| h.ends_with('.')));
+    if shortened {
+        return LinkKind::Shortener;
+    }
+    let file = parsed.path_segments().and_then(|mut s| s.next_back()).unwrap_or_default();
+    let file = file.to_lowercase();
+    if EXECUTABLE_EXTENSIONS.iter().any(|ext| file.ends_with(ext)) {
+        LinkKind::Executable
+    } else if DOWNLOAD_EXTENSIONS.iter().any(|ext| file.ends_with(ext)) {
+        LinkKind::Download
+    } else {
+        LinkKind::Page
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +219,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classify_academic_government_reference_blog() {
+        assert_eq!(classify_search_url("https://arxiv.org/abs/2401.00001"), ContentType::Academic);
+        assert_eq!(classify_search_url("https://cs.stanford.edu/papers"), ContentType::Academic);
+        assert_eq!(classify_search_url("https://www.nist.gov/topics"), ContentType::Government);
+        assert_eq!(classify_search_url("https://www.gov.uk/browse"), ContentType::Government);
+        assert_eq!(
+            classify_search_url("https://en.wikipedia.org/wiki/Rust"),
+            ContentType::Reference
+        );
+        assert_eq!(
+            classify_search_url("https://blog.rust-lang.org/2024/07/25"),
+            ContentType::Blog
+        );
+        assert_eq!(classify_search_url("https://example.com/blog/post"), ContentType::Blog);
+    }
+
+    #[test]
+    fn test_classify_link() {
+        assert_eq!(classify_link("https://example.com/docs/intro"), LinkKind::Page);
+        assert_eq!(classify_link("https://example.com/setup.EXE?v=2"), LinkKind::Executable);
+        assert_eq!(classify_link("https://example.com/install.sh"), LinkKind::Executable);
+        assert_eq!(classify_link("https://example.com/src-1.0.tar.gz"), LinkKind::Download);
+        assert_eq!(classify_link("https://bit.ly/3abcd"), LinkKind::Shortener);
+        assert_eq!(classify_link("https://go.t.co/x"), LinkKind::Shortener);
+        assert_eq!(classify_link("https://reddit.co/x"), LinkKind::Page);
+        assert_eq!(classify_link("data:text/html;base64,PGgxPg=="), LinkKind::DataUri);
+        assert_eq!(classify_link("not a url"), LinkKind::Page);
+        assert!(LinkKind::Executable.is_risky() && !LinkKind::Page.is_risky());
+    }
+
     #[test]
     fn test_classify_unknown() {
         assert_eq!(
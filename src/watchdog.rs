@@ -0,0 +1,315 @@
+//! Health watchdog for long-running, unattended servers.
+//!
+//! With [`WatchdogConfig::enabled`], a background task wakes every
+//! [`interval`](WatchdogConfig::interval) and judges the server's
+//! [`Health`]:
+//!
+//! - **Stalled** when its own timer fires more than
+//!   [`stall_threshold`](WatchdogConfig::stall_threshold) late, meaning
+//!   something is blocking the async runtime's threads;
+//! - **Degraded** during a failure storm: at least
+//!   [`failure_threshold`](WatchdogConfig::failure_threshold) failed tool calls
+//!   within [`failure_window`](WatchdogConfig::failure_window), or every search
+//!   backend's circuit open at once;
+//! - **Healthy** otherwise.
+//!
+//! Each change is logged and broadcast to clients as
+//! [`ServerNotification::HealthChanged`], and the SSE `/health` endpoint
+//! reports the current health. With
+//! [`restart_after`](WatchdogConfig::restart_after) set, the SSE transport
+//! also closes its streams and rebinds its listeners on the same addresses
+//! once the server has been unhealthy that long, at most once per episode.
+
+use crate::events::{EventBus, ServerEvent, ServerNotification};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
+use tracing::{info, warn};
+
+/// When the watchdog runs and what it counts as unhealthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// Run the watchdog at all
+    pub enabled: bool,
+    /// Time between checks
+    pub interval: Duration,
+    /// How late a check may run before the runtime counts as stalled
+    pub stall_threshold: Duration,
+    /// Failed tool calls within `failure_window` that make a failure storm
+    pub failure_threshold: usize,
+    /// Period failed tool calls are counted over
+    pub failure_window: Duration,
+    /// Rebind the SSE listeners after being unhealthy this long; `None` never does
+    pub restart_after: Option<Duration>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(1),
+            stall_threshold: Duration::from_secs(1),
+            failure_threshold: 20,
+            failure_window: Duration::from_secs(60),
+            restart_after: None,
+        }
+    }
+}
+
+/// How the server is doing, from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// Serving normally
+    #[default]
+    Healthy,
+    /// Serving, but upstream requests are failing en masse
+    Degraded,
+    /// The async runtime is blocked and requests are held up
+    Stalled,
+}
+
+impl std::fmt::Display for HealthState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Stalled => "stalled",
+        })
+    }
+}
+
+/// A [`HealthState`] and why the server is in it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Health {
+    /// Current state
+    pub state: HealthState,
+    /// What made it unhealthy; `None` when healthy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Health {
+    /// Whether the server is [`HealthState::Healthy`]
+    pub fn is_healthy(&self) -> bool {
+        self.state == HealthState::Healthy
+    }
+}
+
+/// The watchdog's judgement, apart from its timer and event plumbing.
+#[derive(Debug)]
+pub struct Monitor {
+    config: WatchdogConfig,
+    failures: VecDeque<Instant>,
+    health: Health,
+}
+
+impl Monitor {
+    /// A monitor that starts out healthy
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            failures: VecDeque::new(),
+            health: Health::default(),
+        }
+    }
+
+    /// Current health
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+
+    /// Count a failed tool call
+    pub fn record_failure(&mut self, at: Instant) {
+        self.failures.push_back(at);
+        if self.failures.len() > self.config.failure_threshold.max(1) {
+            self.failures.pop_front();
+        }
+    }
+
+    /// Judge health at `now`, given how late this check ran and whether every
+    /// backend's circuit is open; returns the new health when it changed.
+    pub fn check(
+        &mut self,
+        now: Instant,
+        lag: Duration,
+        all_circuits_open: bool,
+    ) -> Option<Health> {
+        let window = self.config.failure_window;
+        while self.failures.front().is_some_and(|at| now.duration_since(*at) > window) {
+            self.failures.pop_front();
+        }
+
+        let health = if lag > self.config.stall_threshold {
+            Health {
+                state: HealthState::Stalled,
+                reason: Some(format!("runtime stalled for {} ms", lag.as_millis())),
+            }
+        } else if self.config.failure_threshold > 0
+            && self.failures.len() >= self.config.failure_threshold
+        {
+            Health {
+                state: HealthState::Degraded,
+                reason: Some(format!(
+                    "{} failed tool calls in {}s",
+                    self.failures.len(),
+                    window.as_secs()
+                )),
+            }
+        } else if all_circuits_open {
+            Health {
+                state: HealthState::Degraded,
+                reason: Some("every search backend's circuit is open".to_string()),
+            }
+        } else {
+            Health::default()
+        };
+
+        (health != self.health).then(|| {
+            self.health = health.clone();
+            health
+        })
+    }
+}
+
+/// Run the watchdog until the event bus closes, publishing health changes on
+/// `events` and into `health`. `all_circuits_open` reports the backends' state.
+pub(crate) async fn run(
+    config: WatchdogConfig,
+    events: EventBus,
+    health: std::sync::Arc<watch::Sender<Health>>,
+    all_circuits_open: impl Fn() -> bool,
+) {
+    info!(interval = ?config.interval, "Watchdog started");
+    let mut monitor = Monitor::new(config);
+    let mut jobs = events.subscribe();
+    let mut ticks = tokio::time::interval(config.interval.max(Duration::from_millis(10)));
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            scheduled = ticks.tick() => {
+                let now = Instant::now();
+                let lag = now.saturating_duration_since(scheduled.into_std());
+                if let Some(changed) = monitor.check(now, lag, all_circuits_open()) {
+                    match &changed.reason {
+                        Some(reason) => warn!(state = %changed.state, %reason, "Health changed"),
+                        None => info!(state = %changed.state, "Health changed"),
+                    }
+                    events.broadcast(ServerNotification::HealthChanged {
+                        state: changed.state,
+                        reason: changed.reason.clone(),
+                    });
+                    health.send_replace(changed);
+                }
+            },
+            event = jobs.recv() => match event {
+                Ok(ServerEvent {
+                    notification: ServerNotification::JobCompleted { success: false, .. },
+                    ..
+                }) => monitor.record_failure(Instant::now()),
+                Err(broadcast::error::RecvError::Closed) => break,
+                _ => {},
+            },
+        }
+    }
+}
+
+/// Complete once `health` has stayed unhealthy for `duration`, after first
+/// waiting for it to recover when `recover_first`. Never completes once the
+/// watchdog is gone.
+pub(crate) async fn unhealthy_for(
+    mut health: watch::Receiver<Health>,
+    duration: Duration,
+    recover_first: bool,
+) {
+    if !stayed_unhealthy(&mut health, duration, recover_first).await {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// [`unhealthy_for`], returning `false` instead when the watchdog goes away
+async fn stayed_unhealthy(
+    health: &mut watch::Receiver<Health>,
+    duration: Duration,
+    recover_first: bool,
+) -> bool {
+    if recover_first && health.wait_for(Health::is_healthy).await.is_err() {
+        return false;
+    }
+    loop {
+        if health.wait_for(|h| !h.is_healthy()).await.is_err() {
+            return false;
+        }
+        match tokio::time::timeout(duration, health.wait_for(Health::is_healthy)).await {
+            Err(_) => return true,
+            Ok(Err(_)) => return false,
+            Ok(Ok(_)) => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WatchdogConfig {
+        WatchdogConfig {
+            enabled: true,
+            failure_threshold: 3,
+            failure_window: Duration::from_secs(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stalls_and_failure_storms_change_health() {
+        let mut monitor = Monitor::new(config());
+        let start = Instant::now();
+        let on_time = Duration::from_millis(5);
+        assert_eq!(monitor.check(start, on_time, false), None);
+
+        let stalled = monitor.check(start, Duration::from_secs(3), false).unwrap();
+        assert_eq!(stalled.state, HealthState::Stalled);
+        assert_eq!(stalled.reason.as_deref(), Some("runtime stalled for 3000 ms"));
+        assert!(monitor.check(start, on_time, false).unwrap().is_healthy());
+
+        for _ in 0..3 {
+            monitor.record_failure(start);
+        }
+        let storm = monitor.check(start + Duration::from_secs(1), on_time, false).unwrap();
+        assert_eq!(storm.state, HealthState::Degraded);
+        assert_eq!(storm.reason.as_deref(), Some("3 failed tool calls in 10s"));
+        assert_eq!(monitor.check(start + Duration::from_secs(2), on_time, false), None);
+
+        // The failures age out of the window, but every circuit is open
+        let later = start + Duration::from_secs(11);
+        let circuits = monitor.check(later, on_time, true).unwrap();
+        assert_eq!(circuits.state, HealthState::Degraded);
+        assert!(circuits.reason.unwrap().contains("circuit"));
+        assert!(monitor.check(later, on_time, false).unwrap().is_healthy());
+        assert!(monitor.health().is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_for_waits_out_recoveries() {
+        let (health, receiver) = watch::channel(Health::default());
+        let stalled = Health {
+            state: HealthState::Stalled,
+            reason: None,
+        };
+        let ms = Duration::from_millis;
+        let wait = tokio::spawn(unhealthy_for(receiver, ms(300), false));
+
+        health.send_replace(stalled.clone());
+        tokio::time::sleep(ms(200)).await;
+        health.send_replace(Health::default());
+        tokio::time::sleep(ms(20)).await;
+        health.send_replace(stalled);
+        tokio::time::sleep(ms(150)).await;
+        assert!(!wait.is_finished(), "recovered 150 ms ago");
+        tokio::time::sleep(ms(300)).await;
+        assert!(wait.is_finished());
+    }
+}
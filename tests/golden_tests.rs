@@ -0,0 +1,249 @@
+//! Golden-file regression tests for SERP parsing and page extraction.
+//!
+//! Everything here runs offline: SERP fixtures are parsed directly, and page/API
+//! fixtures are served from a local `wiremock` server.
+//!
+//! ## Layout
+//!
+//! `tests/fixtures/golden/` holds pairs of `<name>.<html|json>` inputs and
+//! `<name>.expected.json` outputs:
+//!
+//! - **SERP goldens** (`ddg_serp_*`, `ddg_lite_*`, `searxng_*`) store the exact serialized
+//!   `Vec<SearchResult>`. Any change to titles, URLs, snippets or metadata fails
+//!   the test.
+//! - **Page goldens** (`article_*`, `docs_*`) store an extraction contract
+//!   (title, phrases that must / must not appear, minimum word count, links)
+//!   because exact Markdown output shifts with `htmd`/readability upgrades.
+//!
+//! ## Updating
+//!
+//! After an intentional parser change, regenerate the SERP goldens and review the diff:
+//!
+//! ```bash
+//! DAEDRA_UPDATE_GOLDEN=1 cargo test --test golden_tests
+//! git diff tests/fixtures/golden
+//! ```
+
+use std::path::PathBuf;
+
+use daedra::{
+    tools::{
+        fetch::FetchClient,
+        search::{is_captcha_page, parse_lite_results, parse_search_results},
+    },
+    types::{PageContent, SearchResult, VisitPageArgs},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+mod helpers {
+    use super::*;
+
+    pub fn golden_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/golden")
+    }
+
+    pub fn read_fixture(name: &str) -> String {
+        let path = golden_dir().join(name);
+        std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("missing fixture {}: {e}", path.display()))
+    }
+
+    fn update_requested() -> bool {
+        std::env::var("DAEDRA_UPDATE_GOLDEN").is_ok_and(|v| v == "1")
+    }
+
+    /// Compare `results` against `<name>.expected.json`, or rewrite it when
+    /// `DAEDRA_UPDATE_GOLDEN=1` is set.
+    pub fn assert_serp_golden(name: &str, results: &[SearchResult]) {
+        let actual = serde_json::to_value(results).unwrap();
+        let path = golden_dir().join(format!("{name}.expected.json"));
+
+        if update_requested() {
+            let pretty = serde_json::to_string_pretty(&actual).unwrap();
+            std::fs::write(&path, pretty + "\n").unwrap();
+            return;
+        }
+
+        let expected: Value = serde_json::from_str(&read_fixture(&format!("{name}.expected.json")))
+            .unwrap_or_else(|e| panic!("invalid golden {}: {e}", path.display()));
+        assert_eq!(
+            actual,
+            expected,
+            "{name}: parsed results differ from golden file \
+             (re-run with DAEDRA_UPDATE_GOLDEN=1 if the change is intended)"
+        );
+    }
+
+    /// Extraction contract for a fetched page fixture.
+    #[derive(Debug, Deserialize)]
+    pub struct PageExpectation {
+        pub title: String,
+        pub min_word_count: usize,
+        #[serde(default)]
+        pub contains: Vec<String>,
+        #[serde(default)]
+        pub excludes: Vec<String>,
+        #[serde(default)]
+        pub link_paths: Vec<String>,
+        #[serde(default)]
+        pub published_date: Option<String>,
+    }
+
+    pub fn assert_page_golden(name: &str, page: &PageContent) {
+        let expected: PageExpectation =
+            serde_json::from_str(&read_fixture(&format!("{name}.expected.json"))).unwrap();
+
+        assert_eq!(page.title, expected.title, "{name}: title");
+        assert_eq!(page.published_date, expected.published_date, "{name}: published_date");
+        assert!(
+            page.word_count >= expected.min_word_count,
+            "{name}: word_count {} < {}",
+            page.word_count,
+            expected.min_word_count
+        );
+        for phrase in &expected.contains {
+            assert!(
+                page.content.contains(phrase.as_str()),
+                "{name}: expected content to contain {phrase:?}\n---\n{}",
+                page.content
+            );
+        }
+        for phrase in &expected.excludes {
+            assert!(
+                !page.content.contains(phrase.as_str()),
+                "{name}: boilerplate {phrase:?} leaked into content\n---\n{}",
+                page.content
+            );
+        }
+        let links = page.links.as_deref().unwrap_or_default();
+        for link_path in &expected.link_paths {
+            assert!(
+                links.iter().any(|l| l.url.ends_with(link_path.as_str())),
+                "{name}: expected a link ending in {link_path:?}, got {links:?}"
+            );
+        }
+    }
+
+    /// Serve `body` from a mock server and return the page URL.
+    pub async fn serve(server: &MockServer, route: &str, body: String, content_type: &str) -> String {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", content_type)
+                    .set_body_string(body),
+            )
+            .mount(server)
+            .await;
+        format!("{}{}", server.uri(), route)
+    }
+
+    pub async fn fetch_fixture(name: &str) -> PageContent {
+        let server = MockServer::start().await;
+        let route = format!("/{name}");
+        let url = serve(
+            &server,
+            &route,
+            read_fixture(&format!("{name}.html")),
+            "text/html; charset=utf-8",
+        )
+        .await;
+        FetchClient::new()
+            .unwrap()
+            .fetch(&VisitPageArgs {
+                url,
+                selector: None,
+                include_images: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap_or_else(|e| panic!("{name}: fetch failed: {e}"))
+    }
+}
+
+use helpers::*;
+
+mod serp {
+    use super::*;
+
+    #[test]
+    fn golden_ddg_serp_rust_async() {
+        let results = parse_search_results(&read_fixture("ddg_serp_rust_async.html"), 10);
+        assert_serp_golden("ddg_serp_rust_async", &results);
+    }
+
+    #[test]
+    fn golden_ddg_serp_respects_max_results() {
+        let results = parse_search_results(&read_fixture("ddg_serp_rust_async.html"), 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].url, "https://tokio.rs/");
+    }
+
+    #[test]
+    fn golden_ddg_serp_no_results() {
+        let results = parse_search_results(&read_fixture("ddg_serp_no_results.html"), 10);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn golden_ddg_serp_captcha_detected() {
+        let html = read_fixture("ddg_serp_captcha.html");
+        assert!(parse_search_results(&html, 10).is_empty());
+        assert!(is_captcha_page(&html));
+        assert!(!is_captcha_page(&read_fixture("ddg_serp_no_results.html")));
+        assert!(!is_captcha_page(&read_fixture("ddg_serp_rust_async.html")));
+    }
+
+    #[test]
+    fn golden_ddg_lite_rust_async() {
+        let results = parse_lite_results(&read_fixture("ddg_lite_rust_async.html"), 10);
+        assert_serp_golden("ddg_lite_rust_async", &results);
+    }
+
+    #[cfg(feature = "searxng")]
+    #[tokio::test]
+    async fn golden_searxng_tokio() {
+        use daedra::tools::{SearchBackend, searxng::SearxngBackend};
+        use daedra::types::SearchArgs;
+
+        let server = MockServer::start().await;
+        serve(
+            &server,
+            "/search",
+            read_fixture("searxng_tokio.json"),
+            "application/json",
+        )
+        .await;
+
+        let backend = SearxngBackend::new(server.uri()).unwrap();
+        let response = backend
+            .search(&SearchArgs {
+                query: "tokio runtime".to_string(),
+                options: None,
+            })
+            .await
+            .unwrap();
+        assert_serp_golden("searxng_tokio", &response.data);
+    }
+}
+
+mod pages {
+    use super::*;
+
+    #[tokio::test]
+    async fn golden_article_blog() {
+        let page = fetch_fixture("article_blog").await;
+        assert_page_golden("article_blog", &page);
+    }
+
+    #[tokio::test]
+    async fn golden_docs_page() {
+        let page = fetch_fixture("docs_page").await;
+        assert_page_golden("docs_page", &page);
+    }
+}
@@ -18,6 +18,7 @@ mod search_tests {
                 region: "wt-wt".to_string(),
                 safe_search: SafeSearchLevel::Moderate,
                 time_range: None,
+                ..Default::default()
             }),
         };
 
@@ -53,6 +54,7 @@ mod search_tests {
                 region: "us-en".to_string(),
                 safe_search: SafeSearchLevel::Strict,
                 time_range: None,
+                ..Default::default()
             }),
         };
 
@@ -102,6 +104,7 @@ mod fetch_tests {
             url: "https://example.com".to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
 
         let result = fetch::fetch_page(&args).await;
@@ -125,6 +128,7 @@ mod fetch_tests {
             url: "https://example.com".to_string(),
             selector: Some("p".to_string()),
             include_images: false,
+            ..Default::default()
         };
 
         let result = fetch::fetch_page(&args).await;
@@ -164,9 +168,10 @@ mod cache_tests {
             description: "A test result".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: "example.com".to_string(),
+                source: "example.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }];
 
@@ -78,9 +78,10 @@ mod helpers {
             description: "fixture".to_string(),
             metadata: ResultMetadata {
                 content_type: ContentType::Article,
-                source: "example.com".to_string(),
+                source: "example.com".into(),
                 favicon: None,
                 published_date: None,
+                ..Default::default()
             },
         }
     }
@@ -148,6 +149,7 @@ mod issue_6 {
             url: server.uri(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         client.fetch(&args).await.expect("fetch fixture")
     }
@@ -201,6 +203,7 @@ mod issue_6 {
             url: CELIACHIA_LIVE_URL.to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         let page = client.fetch(&args).await.expect("live fetch");
         assert!(page.word_count < 50, "live issue #6: got {} words", page.word_count);
@@ -214,6 +217,7 @@ mod issue_6 {
             url: CELIACHIA_LIVE_URL.to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         let page = client.fetch(&args).await.expect("live fetch");
         assert!(page.word_count >= 50);
@@ -386,6 +390,7 @@ mod issue_8 {
             url: format!("{}/doc.pdf", server.uri()),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         client.fetch(&args).await.expect("fetch pdf fixture")
     }
@@ -432,6 +437,7 @@ mod issue_8 {
             url: SAMPLE_PDF_URL.to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         let page = client
             .fetch(&args)
@@ -453,6 +459,7 @@ mod issue_8 {
             url: SAMPLE_PDF_URL.to_string(),
             selector: None,
             include_images: false,
+            ..Default::default()
         };
         let page = client.fetch(&args).await.expect("live pdf");
         assert!(looks_like_markdown_article(&page.content));
@@ -0,0 +1,49 @@
+//! Integration tests for the `mcp-client` feature against the real binary
+//!
+//! These tests verify that `McpClient::spawn`:
+//! - completes the initialize handshake over STDIO
+//! - lists tools and surfaces tool errors as typed errors
+
+#![cfg(feature = "mcp-client")]
+
+use daedra::DaedraError;
+use daedra::mcp_client::McpClient;
+use daedra::types::VisitPageArgs;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+async fn spawn_client() -> McpClient {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_daedra"));
+    command.args(["--quiet", "serve", "--transport", "stdio"]);
+    timeout(Duration::from_secs(10), McpClient::spawn(command))
+        .await
+        .expect("Timeout initializing daedra")
+        .expect("Failed to initialize daedra")
+}
+
+#[tokio::test]
+async fn test_stdio_list_tools() {
+    let client = spawn_client().await;
+    assert_eq!(client.server_info()["serverInfo"]["name"], "daedra");
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert!(names.contains(&"web_search"), "{names:?}");
+    assert!(names.contains(&"visit_page"), "{names:?}");
+}
+
+#[tokio::test]
+async fn test_stdio_tool_error_is_typed() {
+    let client = spawn_client().await;
+    let err = client
+        .visit_page(&VisitPageArgs {
+            url: "not-a-url".to_string(),
+            selector: None,
+            include_images: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DaedraError::FetchError(_)), "{err}");
+}
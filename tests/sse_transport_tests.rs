@@ -0,0 +1,43 @@
+//! Integration tests for the SSE transport binary
+//!
+//! These tests verify that `daedra serve --transport sse`:
+//! - binds an ephemeral port with `--port 0`
+//! - prints the bound address on stdout
+//! - answers health checks on that address
+
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_port_zero_prints_bound_address() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_daedra"))
+        .args(["--quiet", "serve", "--transport", "sse", "--port", "0"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("Failed to spawn daedra process");
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let url = timeout(Duration::from_secs(10), async {
+        while let Some(line) = stdout.next_line().await.unwrap() {
+            if let Some(url) = line.strip_prefix("Listening on ") {
+                return url.to_string();
+            }
+        }
+        panic!("daedra exited without printing its address");
+    })
+    .await
+    .expect("Timeout waiting for bound address");
+
+    assert!(url.starts_with("http://127.0.0.1:"), "{url}");
+    assert!(!url.ends_with(":0"), "{url}");
+
+    let health = reqwest::get(format!("{url}/health")).await.unwrap();
+    assert!(health.status().is_success());
+
+    child.kill().await.unwrap();
+}
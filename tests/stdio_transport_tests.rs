@@ -130,6 +130,18 @@ impl DaedraProcess {
         response
     }
 
+    /// Close stdin (as an MCP client does on shutdown) and wait for the process to exit
+    async fn close_stdin_and_wait(self) -> std::process::ExitStatus {
+        let Self {
+            mut child, stdin, ..
+        } = self;
+        drop(stdin);
+        timeout(Duration::from_secs(10), child.wait())
+            .await
+            .expect("daedra did not exit after stdin closed")
+            .expect("Failed to wait for daedra")
+    }
+
     /// Cleanup the process
     async fn cleanup(mut self) {
         drop(self.stdin);
@@ -834,3 +846,155 @@ mod concurrent_request_tests {
         process.cleanup().await;
     }
 }
+
+mod disconnect_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_exits_cleanly_when_stdin_closes() {
+        let mut process = DaedraProcess::spawn().await;
+        process.initialize().await;
+
+        let status = process.close_stdin_and_wait().await;
+        assert!(status.success(), "expected clean exit, got {status}");
+    }
+
+    /// `shutdown` is answered with `null` and later requests are refused until
+    /// the `exit` notification, after which the server exits with code 0 while
+    /// stdin is still open.
+    #[tokio::test]
+    async fn test_shutdown_then_exit_handshake() {
+        let mut process = DaedraProcess::spawn().await;
+        process.initialize().await;
+
+        let shutdown = json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"});
+        let response = process.send_request_expect_success(shutdown).await;
+        assert_eq!(response["id"], 3);
+        assert!(response["result"].is_null());
+
+        let ping = json!({"jsonrpc": "2.0", "id": 4, "method": "ping"});
+        let response = process.send_request(ping).await.unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+
+        let exit = json!({"jsonrpc": "2.0", "method": "exit"});
+        process
+            .stdin
+            .write_all(format!("{exit}\n").as_bytes())
+            .await
+            .unwrap();
+        process.stdin.flush().await.unwrap();
+
+        let status = timeout(Duration::from_secs(10), process.child.wait())
+            .await
+            .expect("daedra did not exit after the exit notification")
+            .unwrap();
+        assert!(status.success(), "expected clean exit, got {status}");
+        let trailing = process.stdout_reader.next_line().await.unwrap();
+        assert_eq!(trailing, None, "no response to the exit notification");
+    }
+
+    #[tokio::test]
+    async fn test_exits_cleanly_when_stdout_closes() {
+        let DaedraProcess {
+            mut child,
+            mut stdin,
+            stdout_reader,
+            mut stderr_reader,
+        } = DaedraProcess::spawn().await;
+        drop(stdout_reader);
+
+        // Keep stdin open: the server must notice the dead stdout on its own.
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "ping", "params": {}});
+        stdin
+            .write_all(format!("{request}\n").as_bytes())
+            .await
+            .unwrap();
+        stdin.flush().await.unwrap();
+
+        let status = timeout(Duration::from_secs(10), child.wait())
+            .await
+            .expect("daedra did not exit after stdout closed")
+            .unwrap();
+        assert!(status.success(), "expected clean exit, got {status}");
+
+        let mut stderr = String::new();
+        while let Ok(Some(line)) = stderr_reader.next_line().await {
+            stderr.push_str(&line);
+            stderr.push('\n');
+        }
+        assert!(!stderr.contains("panicked"), "stderr: {stderr}");
+    }
+}
+
+mod limit_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_oversized_line_is_rejected_and_server_keeps_running() {
+        let mut process = DaedraProcess::spawn_with_args(&["--max-request-bytes", "128"]).await;
+
+        let oversized = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "ping",
+            "params": {"pad": "x".repeat(4096)}
+        });
+        let response = process.send_request(oversized).await.unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], 1, "the id is recovered from the start of the request");
+
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping", "params": {}});
+        let response = process.send_request(ping).await.unwrap();
+        assert_eq!(response["id"], 2);
+        assert!(response.get("error").is_none());
+
+        process.cleanup().await;
+    }
+
+    /// A client that frames some messages with `Content-Length` headers (as
+    /// LSP-style libraries do) and others as lines gets each reply framed like
+    /// its request.
+    #[tokio::test]
+    async fn test_content_length_and_newline_framing_mix() {
+        let mut process = DaedraProcess::spawn().await;
+
+        let framed = json!({"jsonrpc": "2.0", "id": 1, "method": "ping", "params": {}});
+        let body = serde_json::to_string(&framed).unwrap();
+        let message = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        process.stdin.write_all(message.as_bytes()).await.unwrap();
+        process.stdin.flush().await.unwrap();
+
+        let mut next_line = async || {
+            timeout(Duration::from_secs(5), process.stdout_reader.next_line())
+                .await
+                .expect("Timeout waiting for response")
+                .unwrap()
+                .expect("No response")
+        };
+        let header = next_line().await;
+        let length: usize = header
+            .strip_prefix("Content-Length: ")
+            .unwrap_or_else(|| panic!("not a Content-Length header: {header:?}"))
+            .parse()
+            .unwrap();
+        assert_eq!(next_line().await, "");
+
+        // The framed body has no line terminator, so it arrives joined to the
+        // newline-framed reply to the next request
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping", "params": {}});
+        let line = serde_json::to_string(&ping).unwrap() + "\n";
+        process.stdin.write_all(line.as_bytes()).await.unwrap();
+        process.stdin.flush().await.unwrap();
+        let joined = next_line().await;
+        let (first, second) = joined.split_at(length);
+
+        let first: Value = serde_json::from_str(first).unwrap();
+        assert_eq!(first["id"], 1);
+        assert!(first.get("error").is_none());
+        let second: Value = serde_json::from_str(second).unwrap();
+        assert_eq!(second["id"], 2);
+        assert!(second.get("error").is_none());
+
+        process.cleanup().await;
+    }
+}